@@ -4,9 +4,23 @@
 
 use std::collections::HashMap;
 
+/// The value that's conventionally passed to mean standard input or output
+/// instead of a file path.
+pub const STDIO_VALUE: &str = "-";
+
 pub struct ArgumentParser {
   name: String,
+  description: String,
+  authors: Vec<String>,
   args: HashMap<String, (Argument, bool, usize)>,
+  groups: Vec<ArgumentGroup>,
+}
+
+/// A titled section of arguments within the usage of a parser.
+struct ArgumentGroup {
+  name: String,
+  arguments: Vec<String>,
+  required_one_of: bool,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
@@ -18,6 +32,35 @@ pub enum ArgumentType {
   String,
 }
 
+/// Describes what an argument's value refers to, so that shell completions &
+/// man pages can describe or complete it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ValueHint {
+  /// Any value.
+  Unknown,
+  FilePath,
+  DirPath,
+  ExecutablePath,
+  Hostname,
+  Url,
+  Username,
+}
+
+impl ValueHint {
+  /// The placeholder that stands in for the value in help text & man pages.
+  pub fn placeholder(&self) -> &'static str {
+    return match self {
+      ValueHint::Unknown => "VALUE",
+      ValueHint::FilePath => "FILE",
+      ValueHint::DirPath => "DIR",
+      ValueHint::ExecutablePath => "EXECUTABLE",
+      ValueHint::Hostname => "HOST",
+      ValueHint::Url => "URL",
+      ValueHint::Username => "USER",
+    };
+  }
+}
+
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum ArgumentValue {
   None,
@@ -75,6 +118,7 @@ pub struct Argument {
   required: bool,
   arg_type: ArgumentType,
   default_value: ArgumentValue,
+  value_hint: ValueHint,
 }
 
 impl Argument {
@@ -86,6 +130,7 @@ impl Argument {
       required: false,
       arg_type: ArgumentType::String,
       default_value: ArgumentValue::None,
+      value_hint: ValueHint::Unknown,
     };
   }
 
@@ -125,6 +170,12 @@ impl Argument {
     return self;
   }
 
+  /// Sets what the argument's value refers to, such as a file path.
+  pub fn with_value_hint(mut self, value_hint: ValueHint) -> Self {
+    self.value_hint = value_hint;
+    return self;
+  }
+
   pub fn arg_type(&self) -> ArgumentType {
     return self.arg_type.clone();
   }
@@ -140,6 +191,10 @@ impl Argument {
   pub fn description(&self) -> &str {
     return self.description.as_ref();
   }
+
+  pub fn value_hint(&self) -> ValueHint {
+    return self.value_hint;
+  }
 }
 
 #[derive(Debug, Clone)]
@@ -170,7 +225,10 @@ impl ArgumentParser {
   pub fn new(name: &str) -> Self {
     return ArgumentParser {
       name: name.to_string(),
+      description: String::new(),
+      authors: vec![],
       args: HashMap::new(),
+      groups: vec![],
     };
   }
 
@@ -184,13 +242,186 @@ impl ArgumentParser {
     return self.args.len();
   }
 
-  pub fn with_author(mut self, author: &str) {
-    todo!("Implement adding authors to a command line parser")
+  /// Adds an author of the command line application.
+  pub fn with_author(mut self, author: &str) -> Self {
+    self.authors.push(author.to_string());
+    return self;
+  }
+
+  /// Sets the one line description of the command line application.
+  pub fn with_description(mut self, description: &str) -> Self {
+    self.description = description.to_string();
+    return self;
+  }
+
+  /// The description of the parser.
+  pub fn description(&self) -> &str {
+    return self.description.as_ref();
+  }
+
+  /// The authors of the parser.
+  pub fn authors(&self) -> &[String] {
+    return self.authors.as_ref();
   }
 
-  // TODO(vmarcella): Add description to the name
-  pub fn with_description(mut self, description: &str) {
-    todo!("Implement adding a description to the command line parser.")
+  /// The registered arguments in the order they were registered in.
+  pub fn arguments(&self) -> Vec<&Argument> {
+    let mut arguments = self.args.values().collect::<Vec<_>>();
+    arguments.sort_by_key(|(_, _, index)| *index);
+    return arguments
+      .into_iter()
+      .map(|(argument, _, _)| argument)
+      .collect();
+  }
+
+  /// Generates a man page in roff from the registered arguments, their
+  /// descriptions, and the authors, which can be viewed with `man -l`.
+  pub fn generate_man_page(&self) -> String {
+    let mut page =
+      format!(".TH {} 1\n", roff_escape(&self.name.to_uppercase()));
+
+    page.push_str(".SH NAME\n");
+    page.push_str(&roff_escape(&self.name));
+    if !self.description.is_empty() {
+      page.push_str(&format!(" \\- {}", roff_escape(&self.description)));
+    }
+    page.push('\n');
+
+    page.push_str(".SH SYNOPSIS\n");
+    page.push_str(&format!(".B {}\n", roff_escape(&self.name)));
+    for argument in self.arguments() {
+      let usage = argument_usage(argument);
+      match argument.required {
+        true => page.push_str(&format!("{}\n", usage)),
+        false => page.push_str(&format!("[{}]\n", usage)),
+      }
+    }
+
+    let arguments = self.arguments();
+    if !arguments.is_empty() {
+      page.push_str(".SH OPTIONS\n");
+    }
+    for argument in arguments {
+      page.push_str(&format!(".TP\n{}\n", argument_usage(argument)));
+      page.push_str(&format!("{}\n", roff_escape(&argument_details(argument))));
+    }
+
+    if !self.authors.is_empty() {
+      page.push_str(".SH AUTHORS\n");
+      let authors = self
+        .authors
+        .iter()
+        .map(|author| roff_escape(author))
+        .collect::<Vec<_>>();
+      page.push_str(&format!("{}\n", authors.join(", ")));
+    }
+    return page;
+  }
+
+  /// Lists the arguments under a titled section of the usage instead of under
+  /// the other options. The arguments must already be registered.
+  pub fn with_group(mut self, name: &str, arguments: &[&str]) -> Self {
+    self.add_group(name, arguments, false);
+    return self;
+  }
+
+  /// Lists the arguments under a titled section of the usage, of which at
+  /// least one must be passed. The arguments must already be registered.
+  pub fn with_required_group(mut self, name: &str, arguments: &[&str]) -> Self {
+    self.add_group(name, arguments, true);
+    return self;
+  }
+
+  fn add_group(
+    &mut self,
+    name: &str,
+    arguments: &[&str],
+    required_one_of: bool,
+  ) {
+    for argument in arguments {
+      if !self.args.contains_key(*argument) {
+        panic!("{} can't be grouped since it isn't registered.", argument);
+      }
+      if self.group_of(argument).is_some() {
+        panic!("{} is already within a group.", argument);
+      }
+    }
+    self.groups.push(ArgumentGroup {
+      name: name.to_string(),
+      arguments: arguments
+        .iter()
+        .map(|argument| argument.to_string())
+        .collect(),
+      required_one_of,
+    });
+  }
+
+  fn group_of(&self, argument: &str) -> Option<&ArgumentGroup> {
+    return self.groups.iter().find(|group| {
+      return group.arguments.iter().any(|name| name == argument);
+    });
+  }
+
+  /// The usage of the parser, which lists its arguments under the sections
+  /// of their groups, followed by the arguments that aren't in a group
+  /// under "Options".
+  pub fn usage(&self) -> String {
+    let arguments = self.arguments();
+    let mut usage = format!("Usage: {}", self.name);
+    for argument in arguments.iter() {
+      match argument.required {
+        true => usage.push_str(&format!(" {}", plain_argument_usage(argument))),
+        false => {
+          usage.push_str(&format!(" [{}]", plain_argument_usage(argument)))
+        }
+      }
+    }
+    usage.push('\n');
+    if !self.description.is_empty() {
+      usage.push_str(&format!("\n{}\n", self.description));
+    }
+
+    let width = arguments
+      .iter()
+      .map(|argument| plain_argument_usage(argument).len())
+      .max()
+      .unwrap_or(0);
+    let section = |title: String, arguments: Vec<&Argument>| {
+      let mut section = format!("\n{}:\n", title);
+      for argument in arguments {
+        let line = format!(
+          "  {:width$}  {}",
+          plain_argument_usage(argument),
+          argument_details(argument),
+          width = width
+        );
+        section.push_str(line.trim_end());
+        section.push('\n');
+      }
+      return section;
+    };
+
+    let ungrouped = arguments
+      .iter()
+      .copied()
+      .filter(|argument| self.group_of(&argument.name).is_none())
+      .collect::<Vec<_>>();
+    if !ungrouped.is_empty() {
+      usage.push_str(&section("Options".to_string(), ungrouped));
+    }
+    for group in self.groups.iter() {
+      let title = match group.required_one_of {
+        true => format!("{} (one of which is required)", group.name),
+        false => group.name.clone(),
+      };
+      let grouped = arguments
+        .iter()
+        .copied()
+        .filter(|argument| group.arguments.contains(&argument.name))
+        .collect::<Vec<_>>();
+      usage.push_str(&section(title, grouped));
+    }
+    return usage;
   }
 
   pub fn with_argument(mut self, argument: Argument) -> Self {
@@ -207,8 +438,24 @@ impl ArgumentParser {
   /// is the name of the executable being run. (Which is the standard for
   /// arguments passed in from std::env::args()). The ordering of the arguments
   /// returned is always the same as order they're registered in with the
-  /// parser.
-  pub fn compile(mut self, args: &[String]) -> Vec<ParsedArgument> {
+  /// parser. Panics if the arguments are invalid, see `try_compile` for
+  /// arguments that should be reported instead.
+  pub fn compile(self, args: &[String]) -> Vec<ParsedArgument> {
+    return self
+      .try_compile(args)
+      .unwrap_or_else(|error| panic!("{}", error));
+  }
+
+  /// Compiles the arguments like `compile`, but returns an error describing
+  /// the first invalid argument instead of panicking.
+  ///
+  /// The token after an argument is always its value, so values may start
+  /// with a hyphen, such as `--offset -5`, `--pattern -v`, or `-` (see
+  /// `STDIO_VALUE`).
+  pub fn try_compile(
+    mut self,
+    args: &[String],
+  ) -> Result<Vec<ParsedArgument>, String> {
     let mut collecting_values = false;
     let mut last_argument: Option<&mut (Argument, bool, usize)> = None;
 
@@ -218,44 +465,29 @@ impl ArgumentParser {
       ParsedArgument::new("", ArgumentValue::None),
     );
 
-    for arg in args.into_iter().skip(1) {
+    for arg in args.iter().skip(1) {
       if collecting_values {
         let (arg_ref, found, index) = last_argument.as_mut().unwrap();
-
+        let invalid = |type_name: &str, error: &dyn std::fmt::Display| {
+          return format!(
+            "Could not convert {:?} to {} for {} because of: {}",
+            arg, type_name, arg_ref.name, error
+          );
+        };
         let parsed_value = match arg_ref.arg_type() {
           ArgumentType::String => ArgumentValue::String(arg.clone()),
-          ArgumentType::Float => {
-            ArgumentValue::Float(arg.parse().unwrap_or_else(|err| {
-              panic!(
-                "Could not convert {:?} to a float because of: {}",
-                arg, err
-              )
-            }))
-          }
-          ArgumentType::Double => {
-            ArgumentValue::Double(arg.parse().unwrap_or_else(|err| {
-              panic!(
-                "Could not convert {:?} to a double because of: {}",
-                arg, err
-              )
-            }))
-          }
-          ArgumentType::Integer => {
-            ArgumentValue::Integer(arg.parse().unwrap_or_else(|err| {
-              panic!(
-                "Could not convert {:?} to an integer because of: {}",
-                arg, err
-              )
-            }))
-          }
-          ArgumentType::Boolean => {
-            ArgumentValue::Boolean(arg.parse().unwrap_or_else(|err| {
-              panic!(
-                "Could not convert {:?} to a boolean because of: {}",
-                arg, err
-              )
-            }))
-          }
+          ArgumentType::Float => ArgumentValue::Float(
+            arg.parse().map_err(|error| invalid("a float", &error))?,
+          ),
+          ArgumentType::Double => ArgumentValue::Double(
+            arg.parse().map_err(|error| invalid("a double", &error))?,
+          ),
+          ArgumentType::Integer => ArgumentValue::Integer(
+            arg.parse().map_err(|error| invalid("an integer", &error))?,
+          ),
+          ArgumentType::Boolean => ArgumentValue::Boolean(
+            arg.parse().map_err(|error| invalid("a boolean", &error))?,
+          ),
         };
 
         parsed_arguments[*index] =
@@ -266,30 +498,37 @@ impl ArgumentParser {
         continue;
       }
 
-      // Panic if the argument cannot be found inside of the registered
-      // arguments.
-      let found_argument = self.args.get_mut(arg).unwrap_or_else(|| {
-        panic!("Argument: {} is not a valid argument", &arg)
-      });
+      let found_argument = self
+        .args
+        .get_mut(arg)
+        .ok_or_else(|| format!("Argument: {} is not a valid argument", &arg))?;
 
-      // If the argument has already been found, throw an error.
-      if found_argument.1 == true {
-        panic!("{} was set more than once.", found_argument.0.name.clone());
+      if found_argument.1 {
+        return Err(format!(
+          "{} was set more than once.",
+          found_argument.0.name
+        ));
       }
 
       collecting_values = true;
       last_argument = Some(found_argument);
     }
 
+    if let (true, Some((arg, _, _))) = (collecting_values, last_argument) {
+      return Err(format!("{} expects a value, but none was given.", arg.name));
+    }
+
     // Go through all of the registered arguments and check for forgotten flags/
     // apply default values.
     for (arg, found, index) in self.args.values() {
       match (arg.required, found, arg.default_value.clone()) {
         // Argument was required as user input, but not found.
-        (true, false, _) => panic!(
-          "--{} is a required argument, but was not found.",
-          arg.name.clone()
-        ),
+        (true, false, _) => {
+          return Err(format!(
+            "--{} is a required argument, but was not found.",
+            arg.name
+          ));
+        }
         // Argument wasn't required & wasn't found, but has a default value
         (false, false, value) => {
           parsed_arguments[*index] =
@@ -299,6 +538,271 @@ impl ArgumentParser {
         (_, _, _) => {}
       }
     }
-    return parsed_arguments;
+
+    for group in self.groups.iter().filter(|group| group.required_one_of) {
+      let found = group.arguments.iter().any(|name| {
+        return self.args.get(name).is_some_and(|(_, found, _)| *found);
+      });
+      if !found {
+        return Err(format!(
+          "One of {} is required for {}, but none were found.",
+          group.arguments.join(", "),
+          group.name
+        ));
+      }
+    }
+    return Ok(parsed_arguments);
+  }
+}
+
+/// The placeholder for the argument's value, from its value hint or type.
+fn value_placeholder(argument: &Argument) -> &'static str {
+  return match argument.value_hint {
+    ValueHint::Unknown => match argument.arg_type {
+      ArgumentType::Boolean => "BOOL",
+      ArgumentType::Integer => "INTEGER",
+      ArgumentType::Float | ArgumentType::Double => "NUMBER",
+      ArgumentType::String => ValueHint::Unknown.placeholder(),
+    },
+    value_hint => value_hint.placeholder(),
+  };
+}
+
+/// The argument's flag followed by a placeholder for its value.
+fn plain_argument_usage(argument: &Argument) -> String {
+  return format!("{} {}", argument.name, value_placeholder(argument));
+}
+
+/// The argument's flag followed by a placeholder for its value, in roff.
+fn argument_usage(argument: &Argument) -> String {
+  return format!(
+    "\\fB{}\\fR \\fI{}\\fR",
+    roff_escape(&argument.name),
+    value_placeholder(argument)
+  );
+}
+
+/// The description of the argument, followed by whether it's required and
+/// its default value.
+fn argument_details(argument: &Argument) -> String {
+  let mut details = vec![];
+  if !argument.description.is_empty() {
+    details.push(argument.description.clone());
+  }
+  if argument.required {
+    details.push("Required.".to_string());
+  }
+  if let Some(default) = default_value_text(&argument.default_value) {
+    details.push(format!("Defaults to {}.", default));
+  }
+  return details.join(" ");
+}
+
+fn default_value_text(value: &ArgumentValue) -> Option<String> {
+  return match value {
+    ArgumentValue::None => None,
+    ArgumentValue::String(value) if value.is_empty() => None,
+    ArgumentValue::Boolean(value) => Some(value.to_string()),
+    ArgumentValue::Integer(value) => Some(value.to_string()),
+    ArgumentValue::Float(value) => Some(value.to_string()),
+    ArgumentValue::Double(value) => Some(value.to_string()),
+    ArgumentValue::String(value) => Some(format!("\"{}\"", value)),
+  };
+}
+
+/// Escapes text so that roff renders it as is, rather than treating
+/// backslashes, hyphens, or leading periods as formatting.
+fn roff_escape(text: &str) -> String {
+  let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+  return match escaped.starts_with('.') || escaped.starts_with('\'') {
+    true => format!("\\&{}", escaped),
+    false => escaped,
+  };
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    roff_escape,
+    Argument,
+    ArgumentParser,
+    ArgumentType,
+    ArgumentValue,
+    ValueHint,
+    STDIO_VALUE,
+  };
+
+  #[test]
+  fn man_pages_list_arguments_in_registration_order() {
+    let parser = ArgumentParser::new("lambda-pack")
+      .with_description("Packs assets.")
+      .with_author("lambda")
+      .with_argument(
+        Argument::new("--input")
+          .is_required(true)
+          .with_value_hint(ValueHint::DirPath)
+          .with_description("The directory of assets to pack."),
+      )
+      .with_argument(
+        Argument::new("--level")
+          .with_type(ArgumentType::Integer)
+          .with_default_value(ArgumentValue::Integer(6)),
+      );
+
+    assert_eq!(
+      parser.generate_man_page(),
+      ".TH LAMBDA\\-PACK 1\n\
+       .SH NAME\n\
+       lambda\\-pack \\- Packs assets.\n\
+       .SH SYNOPSIS\n\
+       .B lambda\\-pack\n\
+       \\fB\\-\\-input\\fR \\fIDIR\\fR\n\
+       [\\fB\\-\\-level\\fR \\fIINTEGER\\fR]\n\
+       .SH OPTIONS\n\
+       .TP\n\
+       \\fB\\-\\-input\\fR \\fIDIR\\fR\n\
+       The directory of assets to pack. Required.\n\
+       .TP\n\
+       \\fB\\-\\-level\\fR \\fIINTEGER\\fR\n\
+       Defaults to 6.\n\
+       .SH AUTHORS\n\
+       lambda\n"
+    );
+  }
+
+  #[test]
+  fn usage_lists_arguments_under_their_groups() {
+    let parser = ArgumentParser::new("demo")
+      .with_argument(
+        Argument::new("--verbose").with_type(ArgumentType::Boolean),
+      )
+      .with_argument(
+        Argument::new("--width")
+          .with_type(ArgumentType::Integer)
+          .with_description("The width of the window."),
+      )
+      .with_argument(
+        Argument::new("--host").with_value_hint(ValueHint::Hostname),
+      )
+      .with_group("Rendering", &["--width"])
+      .with_required_group("Network", &["--host"]);
+
+    assert_eq!(
+      parser.usage(),
+      [
+        "Usage: demo [--verbose BOOL] [--width INTEGER] [--host HOST]",
+        "",
+        "Options:",
+        "  --verbose BOOL",
+        "",
+        "Rendering:",
+        "  --width INTEGER  The width of the window.",
+        "",
+        "Network (one of which is required):",
+        "  --host HOST",
+        "",
+      ]
+      .join("\n")
+    );
+
+    let args = ["demo", "--host", "localhost"].map(String::from);
+    assert_eq!(
+      parser.compile(&args)[2].value(),
+      ArgumentValue::String("localhost".to_string())
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "One of --host is required for Network")]
+  fn required_groups_need_one_argument() {
+    ArgumentParser::new("demo")
+      .with_argument(Argument::new("--host"))
+      .with_required_group("Network", &["--host"])
+      .compile(&["demo".to_string()]);
+  }
+
+  #[test]
+  fn values_may_start_with_a_hyphen() {
+    let parser = ArgumentParser::new("demo")
+      .with_argument(Argument::new("--offset").with_type(ArgumentType::Integer))
+      .with_argument(Argument::new("--scale").with_type(ArgumentType::Double))
+      .with_argument(Argument::new("--input"));
+    let args = ["demo", "--offset", "-5", "--scale", "-0.5", "--input", "-"]
+      .map(String::from);
+
+    let values = parser
+      .compile(&args)
+      .into_iter()
+      .map(|argument| argument.value())
+      .collect::<Vec<_>>();
+    assert_eq!(
+      values,
+      vec![
+        ArgumentValue::Integer(-5),
+        ArgumentValue::Double(-0.5),
+        ArgumentValue::String(STDIO_VALUE.to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn string_values_may_look_like_arguments() {
+    let parser = || {
+      return ArgumentParser::new("demo")
+        .with_argument(
+          Argument::new("--offset").with_type(ArgumentType::Integer),
+        )
+        .with_argument(Argument::new("--pattern"));
+    };
+
+    let pattern = parser()
+      .try_compile(&["demo", "--pattern", "-v"].map(String::from))
+      .unwrap();
+    assert_eq!(pattern[1].value(), ArgumentValue::String("-v".to_string()));
+
+    let pattern = parser()
+      .try_compile(&["demo", "--pattern", "--offset"].map(String::from))
+      .unwrap();
+    assert_eq!(
+      pattern[1].value(),
+      ArgumentValue::String("--offset".to_string())
+    );
+    assert!(parser()
+      .try_compile(&["demo", "--offset", "--pattern"].map(String::from))
+      .is_err());
+  }
+
+  #[test]
+  fn invalid_arguments_are_errors() {
+    let parser = || {
+      return ArgumentParser::new("demo").with_argument(
+        Argument::new("--width").with_type(ArgumentType::Integer),
+      );
+    };
+
+    assert_eq!(
+      parser()
+        .try_compile(&["demo", "--wdith", "5"].map(String::from))
+        .err(),
+      Some("Argument: --wdith is not a valid argument".to_string())
+    );
+    assert!(parser()
+      .try_compile(&["demo", "--width", "wide"].map(String::from))
+      .is_err());
+    assert!(parser()
+      .try_compile(&["demo", "--width", "1", "--width", "2"].map(String::from))
+      .is_err());
+    assert!(parser()
+      .try_compile(&["demo", "--width"].map(String::from))
+      .is_err());
+  }
+
+  #[test]
+  fn roff_formatting_is_escaped() {
+    assert_eq!(roff_escape(".hidden"), "\\&.hidden");
+    assert_eq!(roff_escape("C:\\path"), "C:\\epath");
+    assert_eq!(roff_escape("a-b"), "a\\-b");
   }
 }
@@ -0,0 +1,276 @@
+//! Records every span closed while recording as an event in the Chrome trace
+//! event format, which can be opened in `chrome://tracing` or Perfetto to
+//! see how the time of every frame was spent on each thread.
+
+use std::{
+  cell::Cell,
+  collections::BTreeMap,
+  fmt::Write,
+  path::{
+    Path,
+    PathBuf,
+  },
+  sync::{
+    atomic::{
+      AtomicBool,
+      AtomicU64,
+      Ordering,
+    },
+    Mutex,
+  },
+  time::{
+    Duration,
+    Instant,
+  },
+};
+
+use crate::handler::escape_json;
+
+/// The environment variable that recording is started from, which is the
+/// path of the file to write the trace to.
+pub const TRACE_FILE_ENV: &str = "LAMBDA_TRACE_FILE";
+
+/// The most events that are kept, so that recordings left running don't use
+/// all of the memory. Events past the limit are dropped.
+const MAX_EVENTS: usize = 1_000_000;
+
+static RECORDING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+  /// The id that events recorded on the current thread are given.
+  static THREAD_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+struct Recording {
+  started_at: Instant,
+  events: Vec<TraceEvent>,
+  thread_names: BTreeMap<u64, String>,
+  dropped: u64,
+}
+
+/// A span that was closed while recording.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEvent {
+  pub name: String,
+  /// The target of the span, such as the module it was entered in.
+  pub category: String,
+  pub thread: u64,
+  /// When the span was entered, relative to when recording started.
+  pub start: Duration,
+  pub duration: Duration,
+}
+
+/// The events of a recording and the names of the threads they were
+/// recorded on.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trace {
+  pub events: Vec<TraceEvent>,
+  pub thread_names: BTreeMap<u64, String>,
+  /// The number of events dropped after the recording was full.
+  pub dropped: u64,
+}
+
+impl Trace {
+  /// The trace as a JSON object in the Chrome trace event format, with a
+  /// complete event for every span and a metadata event naming every
+  /// thread. Timestamps are in microseconds.
+  pub fn to_json(&self) -> String {
+    let mut events = vec![];
+    for (thread, name) in self.thread_names.iter() {
+      events.push(format!(
+        "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":{},\"tid\":{},\
+         \"args\":{{\"name\":\"{}\"}}}}",
+        std::process::id(),
+        thread,
+        escape_json(name)
+      ));
+    }
+    for event in self.events.iter() {
+      let mut json = String::new();
+      write!(
+        json,
+        "{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\
+         \"dur\":{:.3},\"pid\":{},\"tid\":{}}}",
+        escape_json(&event.name),
+        escape_json(&event.category),
+        event.start.as_nanos() as f64 / 1000.0,
+        event.duration.as_nanos() as f64 / 1000.0,
+        std::process::id(),
+        event.thread
+      )
+      .expect("Writing to a string can't fail.");
+      events.push(json);
+    }
+    return format!(
+      "{{\"traceEvents\":[\n{}\n],\"displayTimeUnit\":\"ms\"}}\n",
+      events.join(",\n")
+    );
+  }
+
+  /// Writes the trace as JSON to the file at the path.
+  pub fn write_to_file(&self, path: &Path) -> Result<(), String> {
+    return std::fs::write(path, self.to_json()).map_err(|error| {
+      format!("Failed to write the trace to {}: {}", path.display(), error)
+    });
+  }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<Recording>> {
+  return RECORDING
+    .lock()
+    .expect("The trace recording lock was poisoned.");
+}
+
+/// Starts recording, discarding any events that were already recorded.
+pub fn start() {
+  *lock() = Some(Recording {
+    started_at: Instant::now(),
+    events: vec![],
+    thread_names: BTreeMap::new(),
+    dropped: 0,
+  });
+  RECORDING_ENABLED.store(true, Ordering::Release);
+}
+
+/// Starts recording if the environment variable is set, returning the path
+/// of the file that the trace should be written to.
+pub fn start_from_env() -> Option<PathBuf> {
+  let path = std::env::var_os(TRACE_FILE_ENV)?;
+  start();
+  return Some(PathBuf::from(path));
+}
+
+/// Whether spans are being recorded.
+pub fn is_recording() -> bool {
+  return RECORDING_ENABLED.load(Ordering::Acquire);
+}
+
+/// Stops recording and returns the trace that was recorded, which is empty
+/// if nothing was being recorded.
+pub fn stop() -> Trace {
+  RECORDING_ENABLED.store(false, Ordering::Release);
+  return match lock().take() {
+    Some(recording) => Trace {
+      events: recording.events,
+      thread_names: recording.thread_names,
+      dropped: recording.dropped,
+    },
+    None => Trace::default(),
+  };
+}
+
+/// Records a span that was entered at the instant and has just closed.
+pub(crate) fn record(
+  name: &str,
+  category: &str,
+  entered_at: Instant,
+  duration: Duration,
+) {
+  let thread = THREAD_ID.with(|id| {
+    if id.get() == 0 {
+      id.set(NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed));
+    }
+    return id.get();
+  });
+
+  let mut recording = lock();
+  let recording = match recording.as_mut() {
+    Some(recording) => recording,
+    None => return,
+  };
+  if recording.events.len() >= MAX_EVENTS {
+    recording.dropped += 1;
+    return;
+  }
+  recording.thread_names.entry(thread).or_insert_with(|| {
+    let current = std::thread::current();
+    return match current.name() {
+      Some(name) => name.to_string(),
+      None => format!("thread {}", thread),
+    };
+  });
+  recording.events.push(TraceEvent {
+    name: name.to_string(),
+    category: category.to_string(),
+    thread,
+    start: entered_at.saturating_duration_since(recording.started_at),
+    duration,
+  });
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    collections::BTreeMap,
+    time::Duration,
+  };
+
+  use super::{
+    is_recording,
+    start,
+    stop,
+    Trace,
+    TraceEvent,
+  };
+
+  #[test]
+  fn traces_are_written_in_the_chrome_format() {
+    let trace = Trace {
+      events: vec![TraceEvent {
+        name: "present \"frame\"".to_string(),
+        category: "lambda::render".to_string(),
+        thread: 1,
+        start: Duration::from_micros(1500),
+        duration: Duration::from_nanos(2500),
+      }],
+      thread_names: BTreeMap::from([(1, "main".to_string())]),
+      dropped: 0,
+    };
+    let json = trace.to_json();
+    let pid = std::process::id();
+
+    assert!(json.starts_with("{\"traceEvents\":["));
+    assert!(json.contains(&format!(
+      "{{\"name\":\"thread_name\",\"ph\":\"M\",\"pid\":{},\"tid\":1,\
+       \"args\":{{\"name\":\"main\"}}}}",
+      pid
+    )));
+    assert!(json.contains(&format!(
+      "{{\"name\":\"present \\\"frame\\\"\",\"cat\":\"lambda::render\",\
+       \"ph\":\"X\",\"ts\":1500.000,\"dur\":2.500,\"pid\":{},\"tid\":1}}",
+      pid
+    )));
+  }
+
+  #[test]
+  fn spans_are_recorded_while_recording() {
+    start();
+    assert!(is_recording());
+    {
+      let _frame = crate::span!("chrome_trace_test_frame");
+      let _update = crate::span!("chrome_trace_test_update");
+    }
+    let trace = stop();
+    assert!(!is_recording());
+
+    // Spans from tests running on other threads may be recorded too.
+    let events: Vec<&TraceEvent> = trace
+      .events
+      .iter()
+      .filter(|event| event.name.starts_with("chrome_trace_test"))
+      .collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].name, "chrome_trace_test_update");
+    assert_eq!(events[0].thread, events[1].thread);
+    assert!(events[1].start <= events[0].start);
+    assert!(events[1].duration >= events[0].duration);
+    assert!(trace.thread_names.contains_key(&events[0].thread));
+    assert_eq!(stop(), Trace::default());
+  }
+}
@@ -0,0 +1,169 @@
+//! Filters that choose the level records are logged at based upon the module
+//! path they were logged from, parsed from directives such as
+//! `info,lambda::render=trace,wgpu=warn`.
+
+use crate::LogLevel;
+
+/// The most verbose level that records from a target are logged at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelFilter {
+  /// No records are logged.
+  Off,
+  /// Records at or above the level are logged.
+  Level(LogLevel),
+}
+
+impl LevelFilter {
+  /// Parses a level name such as `info` or `off`, ignoring case.
+  pub fn parse(name: &str) -> Result<Self, String> {
+    return match name.trim().to_ascii_lowercase().as_str() {
+      "off" => Ok(LevelFilter::Off),
+      "trace" => Ok(LevelFilter::Level(LogLevel::TRACE)),
+      "debug" => Ok(LevelFilter::Level(LogLevel::DEBUG)),
+      "info" => Ok(LevelFilter::Level(LogLevel::INFO)),
+      "warn" | "warning" => Ok(LevelFilter::Level(LogLevel::WARN)),
+      "error" => Ok(LevelFilter::Level(LogLevel::ERROR)),
+      "fatal" => Ok(LevelFilter::Level(LogLevel::FATAL)),
+      _ => Err(format!("{} isn't a log level.", name.trim())),
+    };
+  }
+
+  /// Whether or not records at the level pass the filter.
+  pub fn allows(&self, level: LogLevel) -> bool {
+    return match self {
+      LevelFilter::Off => false,
+      LevelFilter::Level(minimum) => level >= *minimum,
+    };
+  }
+}
+
+/// A set of directives that each set the level of a module & its submodules,
+/// along with an optional level for targets no directive matches.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FilterSpec {
+  default: Option<LevelFilter>,
+  directives: Vec<(String, LevelFilter)>,
+}
+
+impl FilterSpec {
+  /// Creates a filter without any directives, which defers to the level of
+  /// the logger for every target.
+  pub fn new() -> Self {
+    return Self::default();
+  }
+
+  /// Parses comma separated directives. A directive is either a level, which
+  /// sets the default level, `module=level`, or a module on its own, which
+  /// logs everything from the module.
+  pub fn parse(spec: &str) -> Result<Self, String> {
+    let mut filter = Self::new();
+    for directive in spec.split(',').map(str::trim) {
+      if directive.is_empty() {
+        continue;
+      }
+
+      filter = match directive.split_once('=') {
+        Some((module, level)) => {
+          let module = module.trim();
+          if module.is_empty() {
+            return Err(format!("{} is missing a module.", directive));
+          }
+          filter.with_directive(module, LevelFilter::parse(level)?)
+        }
+        None => match LevelFilter::parse(directive) {
+          Ok(level) => filter.with_default(level),
+          Err(_) => filter
+            .with_directive(directive, LevelFilter::Level(LogLevel::TRACE)),
+        },
+      };
+    }
+    return Ok(filter);
+  }
+
+  /// The level of targets that no directive matches.
+  pub fn with_default(mut self, level: LevelFilter) -> Self {
+    self.default = Some(level);
+    return self;
+  }
+
+  /// Sets the level of the module & its submodules, replacing any directive
+  /// for the same module.
+  pub fn with_directive(mut self, module: &str, level: LevelFilter) -> Self {
+    self.directives.retain(|(existing, _)| existing != module);
+    self.directives.push((module.to_string(), level));
+
+    // The most specific modules are matched first.
+    self
+      .directives
+      .sort_by(|(a, _), (b, _)| b.len().cmp(&a.len()).then(a.cmp(b)));
+    return self;
+  }
+
+  /// The level of the most specific directive that matches the target, or the
+  /// default level if none do. Returns None if the filter has neither.
+  pub fn level_for(&self, target: &str) -> Option<LevelFilter> {
+    let directive = self.directives.iter().find(|(module, _)| {
+      return match target.strip_prefix(module.as_str()) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::"),
+        None => false,
+      };
+    });
+    return directive.map(|(_, level)| *level).or(self.default);
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    FilterSpec,
+    LevelFilter,
+  };
+  use crate::LogLevel;
+
+  #[test]
+  fn directives_are_parsed() {
+    let filter =
+      FilterSpec::parse("info, lambda::render=trace,wgpu=off,winit").unwrap();
+    assert_eq!(
+      filter.level_for("lambda::render::mesh"),
+      Some(LevelFilter::Level(LogLevel::TRACE))
+    );
+    assert_eq!(
+      filter.level_for("lambda::renderer"),
+      Some(LevelFilter::Level(LogLevel::INFO))
+    );
+    assert_eq!(filter.level_for("wgpu"), Some(LevelFilter::Off));
+    assert_eq!(
+      filter.level_for("winit::window"),
+      Some(LevelFilter::Level(LogLevel::TRACE))
+    );
+
+    assert!(FilterSpec::parse("lambda=loud").is_err());
+    assert!(FilterSpec::parse("=info").is_err());
+    assert_eq!(FilterSpec::parse("").unwrap().level_for("lambda"), None);
+  }
+
+  #[test]
+  fn specific_directives_take_precedence() {
+    let filter = FilterSpec::new()
+      .with_directive("lambda::render::text", LevelFilter::Off)
+      .with_directive("lambda", LevelFilter::Level(LogLevel::WARN))
+      .with_directive("lambda::render", LevelFilter::Level(LogLevel::DEBUG));
+    assert_eq!(
+      filter.level_for("lambda::render::text"),
+      Some(LevelFilter::Off)
+    );
+    assert_eq!(
+      filter.level_for("lambda::render::mesh"),
+      Some(LevelFilter::Level(LogLevel::DEBUG))
+    );
+    assert_eq!(
+      filter.level_for("lambda::ecs"),
+      Some(LevelFilter::Level(LogLevel::WARN))
+    );
+    assert!(!LevelFilter::Level(LogLevel::WARN).allows(LogLevel::INFO));
+    assert!(LevelFilter::Level(LogLevel::WARN).allows(LogLevel::ERROR));
+  }
+}
@@ -1,21 +1,40 @@
 //! Log handling implementations for the logger.
 
 use std::{
+  collections::VecDeque,
   fmt::Debug,
   fs::OpenOptions,
   io::Write,
+  sync::{
+    Arc,
+    Condvar,
+    Mutex,
+    PoisonError,
+  },
+  thread::JoinHandle,
   time::SystemTime,
 };
 
-use crate::LogLevel;
+use crate::{
+  LogLevel,
+  Record,
+  Value,
+};
+
+/// Receives every record logged at or above the level of the logger that
+/// it's been added to.
+pub trait Handler: Send {
+  fn log(&mut self, record: &Record);
 
-pub trait Handler {
-  fn trace(&mut self, message: String);
-  fn debug(&mut self, message: String);
-  fn info(&mut self, message: String);
-  fn warn(&mut self, message: String);
-  fn error(&mut self, message: String);
-  fn fatal(&mut self, message: String);
+  /// Writes any records that the handler has buffered.
+  fn flush(&mut self) {}
+}
+
+fn timestamp() -> u64 {
+  return SystemTime::now()
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .unwrap()
+    .as_secs();
 }
 
 /// A handler that logs to a file.
@@ -35,15 +54,15 @@ impl FileHandler {
   }
 
   /// Logs a message to the file.
-  fn log(&mut self, log_level: LogLevel, message: String) {
-    let timestamp = SystemTime::now()
-      .duration_since(SystemTime::UNIX_EPOCH)
-      .unwrap()
-      .as_secs();
-
-    let log_message = format!("[{}]-[{:?}]: {}", timestamp, log_level, message);
+  fn write(&mut self, record: &Record) {
+    let log_message = format!(
+      "[{}]-[{:?}]: {}",
+      timestamp(),
+      record.level,
+      record.message_with_fields()
+    );
 
-    let colored_message = match log_level {
+    let colored_message = match record.level {
       LogLevel::TRACE => format!("\x1B[37m{}\x1B[0m", log_message),
       LogLevel::DEBUG => format!("\x1B[35m{}\x1B[0m", log_message),
       LogLevel::INFO => format!("\x1B[32m{}\x1B[0m", log_message),
@@ -58,8 +77,17 @@ impl FileHandler {
     if self.log_buffer.len() < 10 {
       return;
     }
+    self.flush_buffer();
+  }
 
-    let log_message = self.log_buffer.join("\n");
+  /// Appends every buffered message to the file.
+  fn flush_buffer(&mut self) {
+    if self.log_buffer.is_empty() {
+      return;
+    }
+
+    let mut log_message = self.log_buffer.join("\n");
+    log_message.push('\n');
 
     let mut file = OpenOptions::new()
       .append(true)
@@ -76,28 +104,12 @@ impl FileHandler {
 }
 
 impl Handler for FileHandler {
-  fn trace(&mut self, message: String) {
-    self.log(LogLevel::TRACE, message)
-  }
-
-  fn debug(&mut self, message: String) {
-    self.log(LogLevel::DEBUG, message)
-  }
-
-  fn info(&mut self, message: String) {
-    self.log(LogLevel::INFO, message)
-  }
-
-  fn warn(&mut self, message: String) {
-    self.log(LogLevel::WARN, message)
-  }
-
-  fn error(&mut self, message: String) {
-    self.log(LogLevel::ERROR, message)
+  fn log(&mut self, record: &Record) {
+    self.write(record);
   }
 
-  fn fatal(&mut self, message: String) {
-    self.log(LogLevel::FATAL, message)
+  fn flush(&mut self) {
+    self.flush_buffer();
   }
 }
 
@@ -113,18 +125,16 @@ impl ConsoleHandler {
     };
   }
 
-  fn log(&mut self, log_level: LogLevel, message: String) {
-    let timestamp = SystemTime::now()
-      .duration_since(SystemTime::UNIX_EPOCH)
-      .unwrap()
-      .as_secs();
-
+  fn write(&mut self, record: &Record) {
     let log_message = format!(
       "[{}]-[{:?}]-[{}]: {}",
-      timestamp, log_level, self.name, message
+      timestamp(),
+      record.level,
+      self.name,
+      record.message_with_fields()
     );
 
-    let colored_message = match log_level {
+    let colored_message = match record.level {
       LogLevel::TRACE => format!("\x1B[37m{}\x1B[0m", log_message),
       LogLevel::DEBUG => format!("\x1B[35m{}\x1B[0m", log_message),
       LogLevel::INFO => format!("\x1B[32m{}\x1B[0m", log_message),
@@ -138,27 +148,586 @@ impl ConsoleHandler {
 }
 
 impl Handler for ConsoleHandler {
-  fn trace(&mut self, message: String) {
-    self.log(LogLevel::TRACE, message);
+  fn log(&mut self, record: &Record) {
+    self.write(record);
+  }
+}
+
+/// A handler that writes every record as a single line JSON object, with the
+/// fields of the record emitted as members of the object.
+pub struct JsonHandler {
+  output: Box<dyn Write + Send>,
+}
+
+impl JsonHandler {
+  /// Creates a handler that writes records to the output.
+  pub fn new(output: Box<dyn Write + Send>) -> Self {
+    return Self { output };
+  }
+
+  /// Creates a handler that writes records to stdout.
+  pub fn stdout() -> Self {
+    return Self::new(Box::new(std::io::stdout()));
+  }
+}
+
+/// Escapes the text as the contents of a JSON string. Used by everything that
+/// writes JSON by hand, so that there's a single escaper to keep correct.
+pub fn escape_json(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for character in text.chars() {
+    match character {
+      '"' => escaped.push_str("\\\""),
+      '\\' => escaped.push_str("\\\\"),
+      '\n' => escaped.push_str("\\n"),
+      '\r' => escaped.push_str("\\r"),
+      '\t' => escaped.push_str("\\t"),
+      character if (character as u32) < 0x20 => {
+        escaped.push_str(&format!("\\u{:04x}", character as u32))
+      }
+      character => escaped.push(character),
+    }
+  }
+  return escaped;
+}
+
+fn format_json_value(value: &Value) -> String {
+  return match value {
+    Value::Boolean(value) => value.to_string(),
+    Value::Integer(value) => value.to_string(),
+    Value::Unsigned(value) => value.to_string(),
+    // JSON has no representation of NaN or infinity.
+    Value::Float(value) if !value.is_finite() => "null".to_string(),
+    Value::Float(value) => value.to_string(),
+    Value::String(value) => format!("\"{}\"", escape_json(value)),
+  };
+}
+
+/// Formats the record as a JSON object. Fields that share a name with one of
+/// the members every record has are ignored.
+fn format_json(record: &Record, timestamp: u64) -> String {
+  let mut json = format!(
+    "{{\"timestamp\":{},\"level\":\"{:?}\",\"target\":\"{}\",\
+     \"message\":\"{}\"",
+    timestamp,
+    record.level,
+    escape_json(&record.target),
+    escape_json(&record.message)
+  );
+  for (key, value) in record.fields.iter() {
+    if matches!(key.as_str(), "timestamp" | "level" | "target" | "message") {
+      continue;
+    }
+    json.push_str(&format!(
+      ",\"{}\":{}",
+      escape_json(key),
+      format_json_value(value)
+    ));
+  }
+  json.push('}');
+  return json;
+}
+
+impl Handler for JsonHandler {
+  fn log(&mut self, record: &Record) {
+    let json = format_json(record, timestamp());
+    writeln!(self.output, "{}", json).expect("Unable to write data");
+  }
+
+  fn flush(&mut self) {
+    self.output.flush().expect("Unable to write data");
+  }
+}
+
+// ------------------------------- RING BUFFER ---------------------------------
+
+struct History {
+  records: VecDeque<Record>,
+  capacity: usize,
+  total: u64,
+}
+
+/// The records kept by a `RingBufferHandler`, which can be read from any
+/// thread while the handler is owned by a logger.
+#[derive(Clone)]
+pub struct LogHistory {
+  history: Arc<Mutex<History>>,
+}
+
+impl LogHistory {
+  fn lock(&self) -> std::sync::MutexGuard<'_, History> {
+    return self
+      .history
+      .lock()
+      .expect("The log history lock was poisoned.");
+  }
+
+  /// The records that are kept, from oldest to newest.
+  pub fn records(&self) -> Vec<Record> {
+    return self.lock().records.iter().cloned().collect();
+  }
+
+  /// The number of records that have ever been handled, including records
+  /// which are no longer kept.
+  pub fn total(&self) -> u64 {
+    return self.lock().total;
+  }
+
+  /// Discards every record that is kept.
+  pub fn clear(&self) {
+    self.lock().records.clear();
+  }
+}
+
+/// A handler that keeps the most recent records in memory, discarding the
+/// oldest record once it's full.
+pub struct RingBufferHandler {
+  history: LogHistory,
+}
+
+impl RingBufferHandler {
+  /// Creates a handler that keeps up to the given number of records.
+  pub fn new(capacity: usize) -> Self {
+    return Self {
+      history: LogHistory {
+        history: Arc::new(Mutex::new(History {
+          records: VecDeque::with_capacity(capacity),
+          capacity: capacity.max(1),
+          total: 0,
+        })),
+      },
+    };
+  }
+
+  /// The records kept by the handler.
+  pub fn history(&self) -> LogHistory {
+    return self.history.clone();
+  }
+}
+
+impl Handler for RingBufferHandler {
+  fn log(&mut self, record: &Record) {
+    let mut history = self.history.lock();
+    if history.records.len() == history.capacity {
+      history.records.pop_front();
+    }
+    history.records.push_back(record.clone());
+    history.total += 1;
+  }
+}
+
+// ---------------------------------- ASYNC ------------------------------------
+
+/// What an `AsyncHandler` does with a record logged while its queue is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+  /// Waits on the logging thread until the queue has room.
+  Block,
+  /// Discards the oldest queued record to make room for the new one.
+  DropOldest,
+  /// Discards the new record.
+  DropNewest,
+}
+
+enum Message {
+  Record(Record),
+  /// Asks the worker to flush the handler, after which the ticket is marked
+  /// as flushed.
+  Flush(u64),
+}
+
+struct Queue {
+  messages: VecDeque<Message>,
+  /// The number of records within the messages, which is what the capacity
+  /// limits.
+  records: usize,
+  dropped: u64,
+  next_ticket: u64,
+  flushed: u64,
+  closed: bool,
+}
+
+struct Shared {
+  queue: Mutex<Queue>,
+  capacity: usize,
+  /// Signalled when a message is queued or the handler is closed.
+  queued: Condvar,
+  /// Signalled when a record is taken from the queue or a flush finishes.
+  taken: Condvar,
+}
+
+impl Shared {
+  fn lock(&self) -> std::sync::MutexGuard<'_, Queue> {
+    return self.queue.lock().expect("The log queue lock was poisoned.");
+  }
+}
+
+/// Builds an `AsyncHandler`, which defaults to a queue of 1024 records that
+/// blocks when full.
+pub struct AsyncHandlerBuilder {
+  capacity: usize,
+  overflow: OverflowPolicy,
+}
+
+impl AsyncHandlerBuilder {
+  pub fn new() -> Self {
+    return Self {
+      capacity: 1024,
+      overflow: OverflowPolicy::Block,
+    };
+  }
+
+  /// The number of records that can be queued before the overflow policy is
+  /// applied.
+  pub fn with_capacity(mut self, capacity: usize) -> Self {
+    self.capacity = capacity.max(1);
+    return self;
+  }
+
+  /// What to do with records logged while the queue is full.
+  pub fn with_overflow_policy(mut self, overflow: OverflowPolicy) -> Self {
+    self.overflow = overflow;
+    return self;
+  }
+
+  /// Starts the thread that passes queued records to the handler.
+  pub fn build(self, handler: impl Handler + 'static) -> AsyncHandler {
+    let shared = Arc::new(Shared {
+      queue: Mutex::new(Queue {
+        messages: VecDeque::with_capacity(self.capacity),
+        records: 0,
+        dropped: 0,
+        next_ticket: 0,
+        flushed: 0,
+        closed: false,
+      }),
+      capacity: self.capacity,
+      queued: Condvar::new(),
+      taken: Condvar::new(),
+    });
+
+    let worker_shared = shared.clone();
+    let worker = std::thread::Builder::new()
+      .name("lambda-logging".to_string())
+      .spawn(move || run_worker(worker_shared, handler))
+      .expect("Failed to spawn the logging thread.");
+
+    return AsyncHandler {
+      shared,
+      overflow: self.overflow,
+      worker: Some(worker),
+    };
   }
+}
 
-  fn debug(&mut self, message: String) {
-    self.log(LogLevel::DEBUG, message);
+/// Closes the queue once the worker exits, including when the handler
+/// panics, so that threads waiting on the worker stop waiting.
+struct WorkerExit(Arc<Shared>);
+
+impl Drop for WorkerExit {
+  fn drop(&mut self) {
+    // The lock is never held while the handler runs, but the queue is
+    // closed even if it was poisoned.
+    let mut queue =
+      self.0.queue.lock().unwrap_or_else(PoisonError::into_inner);
+    queue.closed = true;
+    drop(queue);
+    self.0.taken.notify_all();
   }
+}
 
-  fn info(&mut self, message: String) {
-    self.log(LogLevel::INFO, message);
+/// Passes queued messages to the handler until the queue is closed & empty.
+fn run_worker(shared: Arc<Shared>, mut handler: impl Handler) {
+  let _exit = WorkerExit(shared.clone());
+  loop {
+    let message = {
+      let mut queue = shared.lock();
+      while queue.messages.is_empty() && !queue.closed {
+        queue = shared
+          .queued
+          .wait(queue)
+          .expect("The log queue was poisoned.");
+      }
+      match queue.messages.pop_front() {
+        Some(message) => {
+          if let Message::Record(_) = message {
+            queue.records -= 1;
+          }
+          message
+        }
+        None => break,
+      }
+    };
+    shared.taken.notify_all();
+
+    match message {
+      Message::Record(record) => handler.log(&record),
+      Message::Flush(ticket) => {
+        handler.flush();
+        shared.lock().flushed = ticket;
+        shared.taken.notify_all();
+      }
+    }
   }
+  handler.flush();
+}
 
-  fn warn(&mut self, message: String) {
-    self.log(LogLevel::WARN, message);
+/// Wraps a handler so that records are formatted & written on a background
+/// thread instead of the thread that logged them. Every queued record is
+/// written before the handler is dropped.
+pub struct AsyncHandler {
+  shared: Arc<Shared>,
+  overflow: OverflowPolicy,
+  worker: Option<JoinHandle<()>>,
+}
+
+impl AsyncHandler {
+  /// The number of records that have been discarded because the queue was
+  /// full.
+  pub fn dropped(&self) -> u64 {
+    return self.shared.lock().dropped;
   }
+}
+
+impl Handler for AsyncHandler {
+  /// Records logged once the worker has exited, such as after the wrapped
+  /// handler panicked, are dropped.
+  fn log(&mut self, record: &Record) {
+    let mut queue = self.shared.lock();
+    if queue.records >= self.shared.capacity {
+      match self.overflow {
+        OverflowPolicy::Block => {
+          while queue.records >= self.shared.capacity && !queue.closed {
+            queue = self
+              .shared
+              .taken
+              .wait(queue)
+              .expect("The log queue was poisoned.");
+          }
+        }
+        OverflowPolicy::DropOldest => {
+          let oldest = queue
+            .messages
+            .iter()
+            .position(|message| matches!(message, Message::Record(_)))
+            .expect("A full queue contains records.");
+          queue.messages.remove(oldest);
+          queue.records -= 1;
+          queue.dropped += 1;
+        }
+        OverflowPolicy::DropNewest => {
+          queue.dropped += 1;
+          return;
+        }
+      }
+    }
 
-  fn error(&mut self, message: String) {
-    self.log(LogLevel::ERROR, message);
+    if queue.closed {
+      queue.dropped += 1;
+      return;
+    }
+
+    queue.messages.push_back(Message::Record(record.clone()));
+    queue.records += 1;
+    drop(queue);
+    self.shared.queued.notify_one();
   }
 
-  fn fatal(&mut self, message: String) {
-    self.log(LogLevel::FATAL, message);
+  /// Blocks until every record queued so far has been written & the wrapped
+  /// handler has been flushed, or until the worker has exited.
+  fn flush(&mut self) {
+    let mut queue = self.shared.lock();
+    queue.next_ticket += 1;
+    let ticket = queue.next_ticket;
+    queue.messages.push_back(Message::Flush(ticket));
+    self.shared.queued.notify_one();
+    while queue.flushed < ticket && !queue.closed {
+      queue = self
+        .shared
+        .taken
+        .wait(queue)
+        .expect("The log queue was poisoned.");
+    }
+  }
+}
+
+impl Drop for AsyncHandler {
+  fn drop(&mut self) {
+    self.shared.lock().closed = true;
+    self.shared.queued.notify_one();
+    if let Some(worker) = self.worker.take() {
+      worker.join().ok();
+    }
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{
+    Arc,
+    Mutex,
+  };
+
+  use super::{
+    format_json,
+    AsyncHandlerBuilder,
+    Handler,
+    OverflowPolicy,
+    RingBufferHandler,
+  };
+  use crate::{
+    LogLevel,
+    Record,
+  };
+
+  /// Records the messages it receives, waiting for the gate to open before
+  /// handling each record so that tests control when the queue drains.
+  struct GatedHandler {
+    gate: Arc<Mutex<()>>,
+    messages: Arc<Mutex<Vec<String>>>,
+  }
+
+  impl Handler for GatedHandler {
+    fn log(&mut self, record: &Record) {
+      let _gate = self.gate.lock().unwrap();
+      self.messages.lock().unwrap().push(record.message.clone());
+    }
+  }
+
+  fn record(message: &str) -> Record {
+    return Record::new(LogLevel::INFO, "test", message.to_string());
+  }
+
+  /// Logs a record the worker takes, then fills the queue while the worker is
+  /// stuck behind the gate, and returns the messages once the handler drops.
+  fn overflow(policy: OverflowPolicy) -> (Vec<String>, u64) {
+    let gate = Arc::new(Mutex::new(()));
+    let messages = Arc::new(Mutex::new(vec![]));
+    let closed = gate.lock().unwrap();
+    let mut handler = AsyncHandlerBuilder::new()
+      .with_capacity(2)
+      .with_overflow_policy(policy)
+      .build(GatedHandler {
+        gate: gate.clone(),
+        messages: messages.clone(),
+      });
+
+    handler.log(&record("taken"));
+    while handler.shared.lock().records > 0 {
+      std::thread::yield_now();
+    }
+    for message in ["a", "b", "c"] {
+      handler.log(&record(message));
+    }
+    let dropped = handler.dropped();
+
+    drop(closed);
+    drop(handler);
+    let messages = messages.lock().unwrap().clone();
+    return (messages, dropped);
+  }
+
+  #[test]
+  fn full_queues_drop_records_by_policy() {
+    assert_eq!(
+      overflow(OverflowPolicy::DropOldest),
+      (vec!["taken".into(), "b".into(), "c".into()], 1)
+    );
+    assert_eq!(
+      overflow(OverflowPolicy::DropNewest),
+      (vec!["taken".into(), "a".into(), "b".into()], 1)
+    );
+  }
+
+  #[test]
+  fn async_handlers_flush_every_record() {
+    let messages = Arc::new(Mutex::new(vec![]));
+    let mut handler =
+      AsyncHandlerBuilder::new()
+        .with_capacity(4)
+        .build(GatedHandler {
+          gate: Arc::new(Mutex::new(())),
+          messages: messages.clone(),
+        });
+
+    for index in 0..32 {
+      handler.log(&record(&index.to_string()));
+    }
+    handler.flush();
+    assert_eq!(messages.lock().unwrap().len(), 32);
+    assert_eq!(handler.dropped(), 0);
+
+    handler.log(&record("last"));
+    drop(handler);
+    assert_eq!(messages.lock().unwrap().last().unwrap(), "last");
+  }
+
+  struct PanickingHandler;
+
+  impl Handler for PanickingHandler {
+    fn log(&mut self, _record: &Record) {
+      panic!("The handler failed to write the record.");
+    }
+  }
+
+  #[test]
+  fn async_handlers_stop_waiting_once_the_worker_panics() {
+    let mut handler = AsyncHandlerBuilder::new()
+      .with_capacity(1)
+      .build(PanickingHandler);
+
+    // The queue fills up once the worker has panicked, which would block
+    // logging & flushing forever if they waited on the worker.
+    for message in ["a", "b", "c"] {
+      handler.log(&record(message));
+    }
+    handler.flush();
+    handler.log(&record("after"));
+    handler.flush();
+    assert!(handler.dropped() > 0);
+  }
+
+  #[test]
+  fn fields_are_rendered_after_the_message() {
+    let record = Record::new(LogLevel::INFO, "lambda", "Frame".to_string())
+      .with_field("frame_ms", 16.5)
+      .with_field("resource", "mesh");
+    assert_eq!(
+      record.message_with_fields(),
+      "Frame frame_ms=16.5 resource=\"mesh\""
+    );
+  }
+
+  #[test]
+  fn ring_buffers_keep_the_newest_records() {
+    let mut handler = RingBufferHandler::new(2);
+    let history = handler.history();
+    for message in ["a", "b", "c"] {
+      handler.log(&record(message));
+    }
+    let messages = history
+      .records()
+      .into_iter()
+      .map(|record| record.message)
+      .collect::<Vec<_>>();
+    assert_eq!(messages, vec!["b", "c"]);
+    assert_eq!(history.total(), 3);
+  }
+
+  #[test]
+  fn fields_are_emitted_as_json_members() {
+    let record =
+      Record::new(LogLevel::WARN, "lambda::render", "a \"b\"\n".into())
+        .with_field("id", 7_u32)
+        .with_field("ok", false)
+        .with_field("ratio", f32::NAN)
+        .with_field("level", "ignored");
+    assert_eq!(
+      format_json(&record, 3),
+      "{\"timestamp\":3,\"level\":\"WARN\",\"target\":\"lambda::render\",\
+       \"message\":\"a \\\"b\\\"\\n\",\"id\":7,\"ok\":false,\"ratio\":null}"
+    );
   }
 }
@@ -1,9 +1,42 @@
 //! A simple logging library for lambda-rs crates.
 
-use std::fmt::Debug;
+use std::{
+  fmt::{
+    self,
+    Display,
+  },
+  sync::{
+    atomic::{
+      AtomicUsize,
+      Ordering,
+    },
+    Mutex,
+    MutexGuard,
+    OnceLock,
+    PoisonError,
+  },
+};
 
+use filter::{
+  FilterSpec,
+  LevelFilter,
+};
+
+/// Recording of spans as Chrome trace events.
+pub mod chrome_trace;
+/// Per module filtering of log records.
+pub mod filter;
 /// A trait for handling log messages.
 pub mod handler;
+/// Spans for timing sections of code.
+pub mod span;
+
+/// Forwards records logged through the `log` crate to the global logger.
+#[cfg(feature = "log-bridge")]
+pub mod log_bridge;
+
+/// The environment variable that the global logger reads its filter from.
+pub const FILTER_ENV: &str = "LAMBDA_LOG";
 
 /// The log level for the logger.
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
@@ -16,10 +49,110 @@ pub enum LogLevel {
   FATAL,
 }
 
+/// The value of a field attached to a log record.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+  Boolean(bool),
+  Integer(i64),
+  Unsigned(u64),
+  Float(f64),
+  String(String),
+}
+
+impl Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    return match self {
+      Value::Boolean(value) => write!(f, "{}", value),
+      Value::Integer(value) => write!(f, "{}", value),
+      Value::Unsigned(value) => write!(f, "{}", value),
+      Value::Float(value) => write!(f, "{}", value),
+      Value::String(value) => write!(f, "{:?}", value),
+    };
+  }
+}
+
+macro_rules! impl_value_from {
+  ($variant:ident, $target:ty, [$($source:ty),*]) => {
+    $(
+      impl From<$source> for Value {
+        fn from(value: $source) -> Self {
+          return Value::$variant(value as $target);
+        }
+      }
+    )*
+  };
+}
+
+impl_value_from!(Integer, i64, [i8, i16, i32, i64, isize]);
+impl_value_from!(Unsigned, u64, [u8, u16, u32, u64, usize]);
+impl_value_from!(Float, f64, [f32, f64]);
+
+impl From<bool> for Value {
+  fn from(value: bool) -> Self {
+    return Value::Boolean(value);
+  }
+}
+
+impl From<&str> for Value {
+  fn from(value: &str) -> Self {
+    return Value::String(value.to_string());
+  }
+}
+
+impl From<String> for Value {
+  fn from(value: String) -> Self {
+    return Value::String(value);
+  }
+}
+
+impl From<&String> for Value {
+  fn from(value: &String) -> Self {
+    return Value::String(value.clone());
+  }
+}
+
+/// A single message sent to the logger along with the level it was logged
+/// at, the target it was logged from, and any fields attached to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+  pub level: LogLevel,
+  pub target: String,
+  pub message: String,
+  pub fields: Vec<(String, Value)>,
+}
+
+impl Record {
+  /// Creates a record without any fields.
+  pub fn new(level: LogLevel, target: &str, message: String) -> Self {
+    return Self {
+      level,
+      target: target.to_string(),
+      message,
+      fields: Vec::new(),
+    };
+  }
+
+  /// Attaches a field to the record.
+  pub fn with_field(mut self, key: &str, value: impl Into<Value>) -> Self {
+    self.fields.push((key.to_string(), value.into()));
+    return self;
+  }
+
+  /// The message followed by the fields of the record as `key=value` pairs.
+  pub fn message_with_fields(&self) -> String {
+    let mut message = self.message.clone();
+    for (key, value) in self.fields.iter() {
+      message.push_str(&format!(" {}={}", key, value));
+    }
+    return message;
+  }
+}
+
 /// Logger implementation.
 pub struct Logger {
   name: String,
   level: LogLevel,
+  filter: Option<FilterSpec>,
   handlers: Vec<Box<dyn handler::Handler>>,
 }
 
@@ -29,25 +162,36 @@ impl Logger {
     Self {
       name: name.to_string(),
       level,
+      filter: None,
       handlers: Vec::new(),
     }
   }
 
-  /// Returns the global logger.
-  pub fn global() -> &'static mut Self {
-    // TODO(vmarcella): Fix the instantiation for the global logger.
-    unsafe {
-      if LOGGER.is_none() {
-        LOGGER = Some(Logger {
-          level: LogLevel::TRACE,
-          name: "lambda-rs".to_string(),
-          handlers: vec![Box::new(handler::ConsoleHandler::new("lambda-rs"))],
-        });
+  /// Returns the global logger, whose filter is parsed from the `LAMBDA_LOG`
+  /// environment variable when it's first used. The logger is locked until
+  /// the returned guard is dropped, so it can be used from any thread but
+  /// mustn't be held while logging again.
+  pub fn global() -> MutexGuard<'static, Self> {
+    let mut invalid_filter = None;
+    let logger = LOGGER.get_or_init(|| {
+      let mut logger = Logger::new(LogLevel::TRACE, "lambda-rs");
+      logger.add_handler(Box::new(handler::ConsoleHandler::new("lambda-rs")));
+      if let Ok(spec) = std::env::var(FILTER_ENV) {
+        match FilterSpec::parse(&spec) {
+          Ok(filter) => logger.set_filter(filter),
+          Err(error) => invalid_filter = Some(error),
+        }
       }
-    };
-    return unsafe { &mut LOGGER }
-      .as_mut()
-      .expect("Logger not initialized");
+      return Mutex::new(logger);
+    });
+    // A handler that panicked while logging doesn't leave the logger in an
+    // invalid state, so the lock is recovered from poisoning.
+    let mut logger = logger.lock().unwrap_or_else(PoisonError::into_inner);
+
+    if let Some(error) = invalid_filter {
+      logger.warn(format!("Ignoring {}: {}", FILTER_ENV, error));
+    }
+    return logger;
   }
 
   /// Adds a handler to the logger. Handlers are called in the order they
@@ -56,120 +200,405 @@ impl Logger {
     self.handlers.push(handler);
   }
 
-  fn compare_levels(&self, level: LogLevel) -> bool {
-    level as u8 >= self.level as u8
+  /// Sets the level of targets that the filter doesn't match.
+  pub fn set_level(&mut self, level: LogLevel) {
+    self.level = level;
+    FILTER_GENERATION.fetch_add(1, Ordering::Relaxed);
   }
 
-  /// Logs a trace message to all handlers.
-  pub fn trace(&mut self, message: String) {
-    if !self.compare_levels(LogLevel::TRACE) {
+  /// Sets the filter that chooses the level of each target, replacing any
+  /// existing filter.
+  pub fn set_filter(&mut self, filter: FilterSpec) {
+    self.filter = Some(filter);
+    FILTER_GENERATION.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// The level that records from the target are logged at.
+  pub fn level_for(&self, target: &str) -> LevelFilter {
+    return self
+      .filter
+      .as_ref()
+      .and_then(|filter| filter.level_for(target))
+      .unwrap_or(LevelFilter::Level(self.level));
+  }
+
+  /// Whether or not records at the level from the target are logged.
+  pub fn enabled(&self, level: LogLevel, target: &str) -> bool {
+    return self.level_for(target).allows(level);
+  }
+
+  /// Sends the record to all handlers if it passes the filter, exiting the
+  /// program if the record is fatal.
+  pub fn log(&mut self, record: Record) {
+    if !self.enabled(record.level, &record.target) {
       return;
     }
+    self.dispatch(record);
+  }
 
+  /// Sends the record to all handlers without filtering it. Used by the
+  /// logging macros, which filter records through their callsite.
+  #[doc(hidden)]
+  pub fn dispatch(&mut self, record: Record) {
     for handler in self.handlers.iter_mut() {
-      handler.trace(message.clone());
+      handler.log(&record);
     }
-  }
 
-  /// Logs a debug message to all handlers.
-  pub fn debug(&mut self, message: String) {
-    if !self.compare_levels(LogLevel::DEBUG) {
-      return;
+    if record.level == LogLevel::FATAL {
+      self.flush();
+      std::process::exit(1);
     }
+  }
+
+  /// Flushes every handler, which blocks until asynchronous handlers have
+  /// written all of their queued records.
+  pub fn flush(&mut self) {
     for handler in self.handlers.iter_mut() {
-      handler.debug(message.clone());
+      handler.flush();
     }
   }
 
+  /// Logs a trace message to all handlers.
+  pub fn trace(&mut self, message: String) {
+    let record = Record::new(LogLevel::TRACE, &self.name, message);
+    self.log(record);
+  }
+
+  /// Logs a debug message to all handlers.
+  pub fn debug(&mut self, message: String) {
+    let record = Record::new(LogLevel::DEBUG, &self.name, message);
+    self.log(record);
+  }
+
   /// Logs an info message to all handlers.
   pub fn info(&mut self, message: String) {
-    if !self.compare_levels(LogLevel::INFO) {
-      return;
-    }
-
-    for handler in self.handlers.iter_mut() {
-      handler.info(message.clone());
-    }
+    let record = Record::new(LogLevel::INFO, &self.name, message);
+    self.log(record);
   }
 
   /// Logs a warning to all handlers.
   pub fn warn(&mut self, message: String) {
-    if !self.compare_levels(LogLevel::WARN) {
-      return;
-    }
-    for handler in self.handlers.iter_mut() {
-      handler.warn(message.clone());
-    }
+    let record = Record::new(LogLevel::WARN, &self.name, message);
+    self.log(record);
   }
 
   /// Logs an error to all handlers.
   pub fn error(&mut self, message: String) {
-    if !self.compare_levels(LogLevel::ERROR) {
-      return;
-    }
-
-    for handler in self.handlers.iter_mut() {
-      handler.error(message.clone());
-    }
+    let record = Record::new(LogLevel::ERROR, &self.name, message);
+    self.log(record);
   }
 
   ///  Logs a fatal error to all handlers and exits the program.
   pub fn fatal(&mut self, message: String) {
-    if !self.compare_levels(LogLevel::FATAL) {
-      return;
-    }
+    let record = Record::new(LogLevel::FATAL, &self.name, message);
+    self.log(record);
+  }
+}
 
-    for handler in self.handlers.iter_mut() {
-      handler.fatal(message.clone());
+static LOGGER: OnceLock<Mutex<Logger>> = OnceLock::new();
+
+/// Incremented whenever the level or filter of a logger changes, which
+/// invalidates the levels cached by every callsite.
+static FILTER_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+const UNCACHED: usize = 0b111;
+const OFF: usize = 0b110;
+
+/// The location of a logging macro, which caches the level of its target so
+/// that the filter only has to be searched again once it changes. Targets are
+/// expected to be the same every time a callsite logs.
+#[doc(hidden)]
+pub struct Callsite {
+  /// The generation the level was cached at, shifted past the cached level.
+  state: AtomicUsize,
+}
+
+impl Callsite {
+  pub const fn new() -> Self {
+    return Self {
+      state: AtomicUsize::new(UNCACHED),
+    };
+  }
+
+  /// Whether or not the logger logs records at the level from the target.
+  pub fn enabled(
+    &self,
+    logger: &Logger,
+    level: LogLevel,
+    target: &str,
+  ) -> bool {
+    let generation = FILTER_GENERATION.load(Ordering::Relaxed);
+    let state = self.state.load(Ordering::Relaxed);
+    let cached = state & UNCACHED;
+    if state >> 3 == generation && cached != UNCACHED {
+      return cached != OFF && level as usize >= cached;
     }
-    std::process::exit(1);
+
+    let filter = logger.level_for(target);
+    let cached = match filter {
+      LevelFilter::Off => OFF,
+      LevelFilter::Level(level) => level as usize,
+    };
+    self
+      .state
+      .store((generation << 3) | cached, Ordering::Relaxed);
+    return filter.allows(level);
   }
 }
 
-pub(crate) static mut LOGGER: Option<Logger> = None;
+impl Default for Callsite {
+  fn default() -> Self {
+    return Self::new();
+  }
+}
 
-/// Trace logging macro using the global logger instance.
+/// Builds a record from the fields & message given to one of the logging
+/// macros and sends it to the global logger. Fields are munched one at a time
+/// until only the message is left, and the message is only formatted if the
+/// filter of the global logger allows it.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log {
+  (
+    $level:ident,
+    $target:expr,
+    [$($fields:tt)*],
+    $key:ident = $value:expr,
+    $($rest:tt)+
+  ) => {
+    $crate::__log!(
+      $level,
+      $target,
+      [$($fields)* (stringify!($key).to_string(), $crate::Value::from($value)),],
+      $($rest)+
+    )
+  };
+  ($level:ident, $target:expr, [$($fields:tt)*], $($arg:tt)+) => {{
+    static CALLSITE: $crate::Callsite = $crate::Callsite::new();
+    let target: &str = &$target;
+    let enabled = CALLSITE.enabled(
+      &$crate::Logger::global(),
+      $crate::LogLevel::$level,
+      target,
+    );
+    // The logger is unlocked while the record is built, so that formatting
+    // the message can log too.
+    if enabled {
+      let record = $crate::Record {
+        level: $crate::LogLevel::$level,
+        target: target.to_string(),
+        message: format!($($arg)+),
+        fields: vec![$($fields)*],
+      };
+      $crate::Logger::global().dispatch(record);
+    }
+  }};
+}
+
+/// Trace logging macro using the global logger instance. Fields can be
+/// attached after a target with `trace!(target, key = value, "message")`.
 #[macro_export]
 macro_rules! trace {
-  ($($arg:tt)*) => {
-      logging::Logger::global().trace(format!("{}", format_args!($($arg)*)));
+  ($target:expr, $key:ident = $($rest:tt)+) => {
+    $crate::__log!(TRACE, $target, [], $key = $($rest)+);
+  };
+  ($($arg:tt)+) => {
+    $crate::__log!(TRACE, module_path!(), [], $($arg)+);
   };
 }
 
-/// Trace logging macro using the global logger instance.
+/// Debug logging macro using the global logger instance. Fields can be
+/// attached after a target with `debug!(target, key = value, "message")`.
 #[macro_export]
 macro_rules! debug {
-  ($($arg:tt)*) => {
-      logging::Logger::global().debug(format!("{}", format_args!($($arg)*)));
+  ($target:expr, $key:ident = $($rest:tt)+) => {
+    $crate::__log!(DEBUG, $target, [], $key = $($rest)+);
+  };
+  ($($arg:tt)+) => {
+    $crate::__log!(DEBUG, module_path!(), [], $($arg)+);
   };
 }
 
-/// Trace logging macro using the global logger instance.
+/// Info logging macro using the global logger instance. Fields can be
+/// attached after a target with `info!(target, key = value, "message")`.
 #[macro_export]
 macro_rules! info {
-  ($($arg:tt)*) => {
-      logging::Logger::global().info(format!("{}", format_args!($($arg)*)));
+  ($target:expr, $key:ident = $($rest:tt)+) => {
+    $crate::__log!(INFO, $target, [], $key = $($rest)+);
+  };
+  ($($arg:tt)+) => {
+    $crate::__log!(INFO, module_path!(), [], $($arg)+);
   };
 }
 
-// Define logging macros that use the global logger instance
+/// Warning logging macro using the global logger instance. Fields can be
+/// attached after a target with `warn!(target, key = value, "message")`.
 #[macro_export]
 macro_rules! warn {
-  ($($arg:tt)*) => {
-      logging::Logger::global().warn(format!("{}", format_args!($($arg)*)));
+  ($target:expr, $key:ident = $($rest:tt)+) => {
+    $crate::__log!(WARN, $target, [], $key = $($rest)+);
+  };
+  ($($arg:tt)+) => {
+    $crate::__log!(WARN, module_path!(), [], $($arg)+);
   };
 }
 
+/// Error logging macro using the global logger instance. Fields can be
+/// attached after a target with `error!(target, key = value, "message")`.
 #[macro_export]
 macro_rules! error {
-  ($($arg:tt)*) => {
-      logging::Logger::global().error(format!("{}", format_args!($($arg)*)));
+  ($target:expr, $key:ident = $($rest:tt)+) => {
+    $crate::__log!(ERROR, $target, [], $key = $($rest)+);
+  };
+  ($($arg:tt)+) => {
+    $crate::__log!(ERROR, module_path!(), [], $($arg)+);
   };
 }
 
+/// Fatal logging macro using the global logger instance, which exits the
+/// program after logging. Fields can be attached after a target with
+/// `fatal!(target, key = value, "message")`.
 #[macro_export]
 macro_rules! fatal {
-  ($($arg:tt)*) => {
-      logging::Logger::global().fatal(format!("{}", format_args!($($arg)*)));
+  ($target:expr, $key:ident = $($rest:tt)+) => {
+    $crate::__log!(FATAL, $target, [], $key = $($rest)+);
+  };
+  ($($arg:tt)+) => {
+    $crate::__log!(FATAL, module_path!(), [], $($arg)+);
+  };
+}
+
+/// Enters a span that's closed when the returned guard is dropped, which must
+/// be bound to a named variable such as `let _span = span!("render");`. The
+/// target defaults to the calling module.
+#[macro_export]
+macro_rules! span {
+  ($name:expr) => {
+    $crate::span::Span::enter(module_path!(), &$name)
+  };
+  ($target:expr, $name:expr) => {
+    $crate::span::Span::enter(&$target, &$name)
+  };
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::sync::{
+    Arc,
+    Mutex,
   };
+
+  use super::{
+    filter::FilterSpec,
+    handler::Handler,
+    Callsite,
+    LogLevel,
+    Logger,
+    Record,
+    Value,
+  };
+
+  struct RecordingHandler {
+    records: Arc<Mutex<Vec<Record>>>,
+  }
+
+  impl Handler for RecordingHandler {
+    fn log(&mut self, record: &Record) {
+      self.records.lock().unwrap().push(record.clone());
+    }
+  }
+
+  #[test]
+  fn macros_attach_fields_to_records() {
+    let records = Arc::new(Mutex::new(vec![]));
+    Logger::global().add_handler(Box::new(RecordingHandler {
+      records: records.clone(),
+    }));
+
+    let frame = 3;
+    crate::info!("Loaded {} meshes", 2);
+    crate::debug!("lambda::render", frame = frame, build_ms = 1.5, "Built");
+    crate::warn!("lambda::assets", path = "mesh.obj", "Missing {}", "mesh");
+
+    // Tests running in parallel log through the same global logger, so only
+    // the records from the targets logged to here are checked.
+    let targets = [module_path!(), "lambda::render", "lambda::assets"];
+    let records = records
+      .lock()
+      .unwrap()
+      .iter()
+      .filter(|record| targets.contains(&record.target.as_str()))
+      .cloned()
+      .collect::<Vec<_>>();
+    assert_eq!(records[0].level, LogLevel::INFO);
+    assert_eq!(records[0].target, module_path!());
+    assert_eq!(records[0].message, "Loaded 2 meshes");
+    assert!(records[0].fields.is_empty());
+
+    assert_eq!(records[1].target, "lambda::render");
+    assert_eq!(records[1].message, "Built");
+    assert_eq!(
+      records[1].fields,
+      vec![
+        ("frame".to_string(), Value::Integer(3)),
+        ("build_ms".to_string(), Value::Float(1.5)),
+      ]
+    );
+
+    assert_eq!(records[2].level, LogLevel::WARN);
+    assert_eq!(records[2].message, "Missing mesh");
+    assert_eq!(
+      records[2].fields,
+      vec![("path".to_string(), Value::String("mesh.obj".to_string()))]
+    );
+  }
+
+  #[test]
+  fn records_can_be_logged_from_many_threads() {
+    let records = Arc::new(Mutex::new(vec![]));
+    Logger::global().add_handler(Box::new(RecordingHandler {
+      records: records.clone(),
+    }));
+
+    let threads = (0..4)
+      .map(|thread| {
+        return std::thread::spawn(move || {
+          for record in 0..50 {
+            crate::info!("lambda::jobs", thread = thread, "Record {}", record);
+          }
+        });
+      })
+      .collect::<Vec<_>>();
+    for thread in threads {
+      thread.join().unwrap();
+    }
+
+    let records = records.lock().unwrap();
+    let logged = records
+      .iter()
+      .filter(|record| record.target == "lambda::jobs")
+      .count();
+    assert_eq!(logged, 200);
+  }
+
+  #[test]
+  fn callsites_cache_levels_until_the_filter_changes() {
+    let mut logger = Logger::new(LogLevel::INFO, "test");
+    let callsite = Callsite::new();
+    assert!(!callsite.enabled(&logger, LogLevel::DEBUG, "lambda::render"));
+    assert!(callsite.enabled(&logger, LogLevel::INFO, "lambda::render"));
+
+    logger.set_filter(FilterSpec::parse("lambda::render=trace").unwrap());
+    assert!(callsite.enabled(&logger, LogLevel::TRACE, "lambda::render"));
+
+    logger.set_filter(FilterSpec::parse("debug,lambda=off").unwrap());
+    assert!(!callsite.enabled(&logger, LogLevel::FATAL, "lambda::render"));
+    assert!(logger.enabled(LogLevel::DEBUG, "wgpu"));
+
+    logger.set_filter(FilterSpec::new());
+    logger.set_level(LogLevel::ERROR);
+    assert!(!callsite.enabled(&logger, LogLevel::WARN, "lambda::render"));
+  }
 }
@@ -0,0 +1,87 @@
+//! A bridge from the `log` facade to the global logger, so that records from
+//! dependencies which log through `log` are handled like lambda's own.
+
+use crate::{
+  LogLevel,
+  Logger,
+  Record,
+};
+
+struct LogBridge;
+
+static BRIDGE: LogBridge = LogBridge;
+
+fn level_from(level: log::Level) -> LogLevel {
+  return match level {
+    log::Level::Error => LogLevel::ERROR,
+    log::Level::Warn => LogLevel::WARN,
+    log::Level::Info => LogLevel::INFO,
+    log::Level::Debug => LogLevel::DEBUG,
+    log::Level::Trace => LogLevel::TRACE,
+  };
+}
+
+impl log::Log for LogBridge {
+  fn enabled(&self, metadata: &log::Metadata) -> bool {
+    return Logger::global()
+      .enabled(level_from(metadata.level()), metadata.target());
+  }
+
+  fn log(&self, record: &log::Record) {
+    if !self.enabled(record.metadata()) {
+      return;
+    }
+
+    let record = Record::new(
+      level_from(record.level()),
+      record.target(),
+      record.args().to_string(),
+    );
+    Logger::global().dispatch(record);
+  }
+
+  fn flush(&self) {
+    Logger::global().flush();
+  }
+}
+
+/// Installs the bridge as the logger of the `log` facade, after which records
+/// logged through `log` are filtered & handled by the global logger. Fails if
+/// another logger has already been installed.
+pub fn install() -> Result<(), String> {
+  log::set_logger(&BRIDGE).map_err(|_| {
+    return "A logger has already been installed for the log crate."
+      .to_string();
+  })?;
+  log::set_max_level(log::LevelFilter::Trace);
+  return Ok(());
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    install,
+    level_from,
+  };
+  use crate::{
+    filter::FilterSpec,
+    LogLevel,
+    Logger,
+  };
+
+  #[test]
+  fn log_records_are_filtered_by_target() {
+    assert_eq!(level_from(log::Level::Warn), LogLevel::WARN);
+    assert_eq!(level_from(log::Level::Trace), LogLevel::TRACE);
+
+    install().unwrap();
+    assert!(install().is_err());
+
+    Logger::global().set_filter(FilterSpec::parse("wgpu_core=warn").unwrap());
+    assert!(!log::log_enabled!(target: "wgpu_core::device", log::Level::Info));
+    assert!(log::log_enabled!(target: "wgpu_core::device", log::Level::Error));
+    assert!(log::log_enabled!(target: "winit", log::Level::Trace));
+  }
+}
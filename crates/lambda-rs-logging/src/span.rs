@@ -0,0 +1,230 @@
+//! Spans that time sections of code. Entering a span returns a guard that
+//! closes the span when it's dropped, and spans entered while another span is
+//! open on the same thread are nested within it. Opening & closing a span
+//! logs a trace record, and the time spent within every span is summarized
+//! by `report`. Spans closed while `chrome_trace` is recording are recorded
+//! as trace events too.
+
+use std::{
+  cell::RefCell,
+  collections::HashMap,
+  marker::PhantomData,
+  sync::Mutex,
+  time::{
+    Duration,
+    Instant,
+  },
+};
+
+use crate::{
+  chrome_trace,
+  LogLevel,
+  Logger,
+  Record,
+};
+
+thread_local! {
+  /// The names of the spans open on the current thread, from outermost to
+  /// innermost.
+  static OPEN_SPANS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+static SUMMARIES: Mutex<Option<HashMap<String, SpanSummary>>> =
+  Mutex::new(None);
+
+/// The time spent within every span entered at the same path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanSummary {
+  /// The names of the span & the spans it was nested within, separated by
+  /// `/`.
+  pub path: String,
+  pub count: u64,
+  pub total: Duration,
+  pub min: Duration,
+  pub max: Duration,
+}
+
+impl SpanSummary {
+  /// The average time spent within the span.
+  pub fn mean(&self) -> Duration {
+    let mean = self.total.as_nanos() / self.count.max(1) as u128;
+    return Duration::from_nanos(mean as u64);
+  }
+}
+
+/// Guards an open span, which is closed when the guard is dropped. Guards
+/// must be dropped on the thread that entered them.
+#[must_use = "The span is closed as soon as its guard is dropped."]
+pub struct Span {
+  target: String,
+  name: String,
+  path: String,
+  depth: usize,
+  entered_at: Instant,
+  /// Spans are nested per thread, so guards can't be sent between threads.
+  _thread: PhantomData<*const ()>,
+}
+
+impl Span {
+  /// Opens a span nested within the spans open on the current thread.
+  pub fn enter(target: &str, name: &str) -> Self {
+    let (path, depth) = OPEN_SPANS.with(|spans| {
+      let mut spans = spans.borrow_mut();
+      spans.push(name.to_string());
+      return (spans.join("/"), spans.len() - 1);
+    });
+
+    Logger::global().log(
+      Record::new(LogLevel::TRACE, target, format!("Entered {}", path))
+        .with_field("span", name)
+        .with_field("span_event", "open")
+        .with_field("depth", depth),
+    );
+
+    return Self {
+      target: target.to_string(),
+      name: name.to_string(),
+      path,
+      depth,
+      entered_at: Instant::now(),
+      _thread: PhantomData,
+    };
+  }
+
+  /// The names of the span & the spans it's nested within, separated by `/`.
+  pub fn path(&self) -> &str {
+    return &self.path;
+  }
+
+  /// The time since the span was entered.
+  pub fn elapsed(&self) -> Duration {
+    return self.entered_at.elapsed();
+  }
+}
+
+impl Drop for Span {
+  fn drop(&mut self) {
+    let elapsed = self.elapsed();
+
+    // Closing a span also closes any spans nested within it whose guards
+    // haven't been dropped yet.
+    OPEN_SPANS.with(|spans| spans.borrow_mut().truncate(self.depth));
+
+    {
+      let mut summaries =
+        SUMMARIES.lock().expect("The span lock was poisoned.");
+      let summary = summaries
+        .get_or_insert_with(HashMap::new)
+        .entry(self.path.clone())
+        .or_insert_with(|| SpanSummary {
+          path: self.path.clone(),
+          count: 0,
+          total: Duration::ZERO,
+          min: Duration::MAX,
+          max: Duration::ZERO,
+        });
+      summary.count += 1;
+      summary.total += elapsed;
+      summary.min = summary.min.min(elapsed);
+      summary.max = summary.max.max(elapsed);
+    }
+
+    if chrome_trace::is_recording() {
+      chrome_trace::record(&self.name, &self.target, self.entered_at, elapsed);
+    }
+
+    Logger::global().log(
+      Record::new(
+        LogLevel::TRACE,
+        &self.target,
+        format!("Exited {} after {:?}", self.path, elapsed),
+      )
+      .with_field("span", self.name.as_str())
+      .with_field("span_event", "close")
+      .with_field("depth", self.depth)
+      .with_field("elapsed_us", elapsed.as_micros() as u64),
+    );
+  }
+}
+
+/// The time spent within every span closed since the last reset, ordered by
+/// path so that nested spans follow the spans they're nested within.
+pub fn report() -> Vec<SpanSummary> {
+  let summaries = SUMMARIES.lock().expect("The span lock was poisoned.");
+  let mut report = summaries
+    .as_ref()
+    .map(|summaries| summaries.values().cloned().collect::<Vec<_>>())
+    .unwrap_or_default();
+  report.sort_by(|a, b| a.path.cmp(&b.path));
+  return report;
+}
+
+/// Discards the time spent within every span closed so far.
+pub fn reset() {
+  SUMMARIES
+    .lock()
+    .expect("The span lock was poisoned.")
+    .take();
+}
+
+/// Logs the report as a single info record, with a line per span.
+pub fn log_report() {
+  let mut message = String::from("Time spent per span:");
+  for summary in report() {
+    message.push_str(&format!(
+      "\n  {}: {} calls, {:?} total, {:?} mean, {:?} min, {:?} max",
+      summary.path,
+      summary.count,
+      summary.total,
+      summary.mean(),
+      summary.min,
+      summary.max
+    ));
+  }
+  Logger::global().log(Record::new(LogLevel::INFO, module_path!(), message));
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::report;
+
+  #[test]
+  fn spans_nest_per_thread() {
+    {
+      let frame = crate::span!("span_test_frame");
+      assert_eq!(frame.path(), "span_test_frame");
+      for _ in 0..3 {
+        let update = crate::span!("update");
+        assert_eq!(update.path(), "span_test_frame/update");
+      }
+
+      std::thread::spawn(|| {
+        let worker = crate::span!("span_test_worker");
+        assert_eq!(worker.path(), "span_test_worker");
+      })
+      .join()
+      .unwrap();
+    }
+
+    let report = report()
+      .into_iter()
+      .filter(|summary| summary.path.starts_with("span_test"))
+      .collect::<Vec<_>>();
+    let paths = report
+      .iter()
+      .map(|summary| (summary.path.as_str(), summary.count))
+      .collect::<Vec<_>>();
+    assert_eq!(
+      paths,
+      vec![
+        ("span_test_frame", 1),
+        ("span_test_frame/update", 3),
+        ("span_test_worker", 1),
+      ]
+    );
+    assert!(report[0].total >= report[1].total);
+    assert!(report[1].min <= report[1].mean());
+  }
+}
@@ -0,0 +1,137 @@
+//! Text access to the system clipboard through the clipboard utility of the
+//! platform: pbcopy & pbpaste on macOS, PowerShell on Windows, and wl-copy,
+//! xclip, or xsel on Linux & BSDs.
+
+use std::{
+  io::Write,
+  process::{
+    Command,
+    Stdio,
+  },
+};
+
+/// A clipboard utility, as the program followed by its arguments.
+type Utility = (&'static str, &'static [&'static str]);
+
+#[cfg(target_os = "macos")]
+fn read_utilities() -> Vec<Utility> {
+  return vec![("pbpaste", &[])];
+}
+
+#[cfg(target_os = "macos")]
+fn write_utilities() -> Vec<Utility> {
+  return vec![("pbcopy", &[])];
+}
+
+#[cfg(target_os = "windows")]
+fn read_utilities() -> Vec<Utility> {
+  return vec![(
+    "powershell",
+    &["-NoProfile", "-Command", "Get-Clipboard -Raw"],
+  )];
+}
+
+#[cfg(target_os = "windows")]
+fn write_utilities() -> Vec<Utility> {
+  return vec![(
+    "powershell",
+    &[
+      "-NoProfile",
+      "-Command",
+      "Set-Clipboard -Value ([Console]::In.ReadToEnd())",
+    ],
+  )];
+}
+
+/// Wayland's utility is preferred within Wayland sessions, since the X11
+/// utilities only reach the clipboard of XWayland applications.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn read_utilities() -> Vec<Utility> {
+  let mut utilities: Vec<Utility> = vec![
+    ("xclip", &["-selection", "clipboard", "-out"]),
+    ("xsel", &["--clipboard", "--output"]),
+  ];
+  if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+    utilities.insert(0, ("wl-paste", &["--no-newline"]));
+  }
+  return utilities;
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn write_utilities() -> Vec<Utility> {
+  let mut utilities: Vec<Utility> = vec![
+    ("xclip", &["-selection", "clipboard", "-in"]),
+    ("xsel", &["--clipboard", "--input"]),
+  ];
+  if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+    utilities.insert(0, ("wl-copy", &[]));
+  }
+  return utilities;
+}
+
+/// The text on the clipboard, using the first clipboard utility that's
+/// installed.
+pub fn get_text() -> Result<String, String> {
+  let mut errors = vec![];
+  for (program, arguments) in read_utilities() {
+    let output = Command::new(program)
+      .args(arguments)
+      .stdin(Stdio::null())
+      .stderr(Stdio::null())
+      .output();
+    match output {
+      Ok(output) if output.status.success() => {
+        return String::from_utf8(output.stdout)
+          .map_err(|_| "The clipboard doesn't contain text.".to_string());
+      }
+      Ok(output) => {
+        errors.push(format!("{} exited with {}", program, output.status))
+      }
+      Err(error) => errors.push(format!("{}: {}", program, error)),
+    }
+  }
+  return Err(format!(
+    "Failed to read the clipboard. {}",
+    errors.join(", ")
+  ));
+}
+
+/// Replaces the text on the clipboard, using the first clipboard utility
+/// that's installed.
+pub fn set_text(text: &str) -> Result<(), String> {
+  let mut errors = vec![];
+  for (program, arguments) in write_utilities() {
+    let child = Command::new(program)
+      .args(arguments)
+      .stdin(Stdio::piped())
+      .stdout(Stdio::null())
+      .stderr(Stdio::null())
+      .spawn();
+    let mut child = match child {
+      Ok(child) => child,
+      Err(error) => {
+        errors.push(format!("{}: {}", program, error));
+        continue;
+      }
+    };
+
+    // The utility only reads the text once its input is closed.
+    let written = child
+      .stdin
+      .take()
+      .map(|mut stdin| stdin.write_all(text.as_bytes()))
+      .unwrap_or(Ok(()));
+    match (written, child.wait()) {
+      (Ok(()), Ok(status)) if status.success() => return Ok(()),
+      (Err(error), _) => errors.push(format!("{}: {}", program, error)),
+      (_, Ok(status)) => {
+        errors.push(format!("{} exited with {}", program, status))
+      }
+      (_, Err(error)) => errors.push(format!("{}: {}", program, error)),
+    }
+  }
+  return Err(format!(
+    "Failed to write the clipboard. {}",
+    errors.join(", ")
+  ));
+}
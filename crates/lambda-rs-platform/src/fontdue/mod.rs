@@ -0,0 +1,84 @@
+//! Font loading & glyph rasterization. Wrapper around the fontdue crate.
+
+use fontdue::{
+  Font as FontdueFont,
+  FontSettings,
+};
+
+/// A glyph that has been rasterized into a single channel coverage bitmap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RasterizedGlyph {
+  /// The width of the bitmap in pixels.
+  pub width: usize,
+  /// The height of the bitmap in pixels.
+  pub height: usize,
+  /// Horizontal offset from the pen position to the left edge of the bitmap.
+  pub offset_x: i32,
+  /// Vertical offset from the baseline to the bottom edge of the bitmap,
+  /// where positive values are above the baseline.
+  pub offset_y: i32,
+  /// How far the pen should move horizontally after drawing the glyph.
+  pub advance: f32,
+  /// Row major coverage values, where 0 is empty and 255 is fully covered.
+  pub coverage: Vec<u8>,
+}
+
+/// Vertical metrics for laying out lines of text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LineMetrics {
+  /// Distance from the baseline to the top of the tallest glyph.
+  pub ascent: f32,
+  /// Distance from the baseline to the bottom of the lowest glyph. Negative
+  /// when below the baseline.
+  pub descent: f32,
+  /// The distance between the baselines of two consecutive lines.
+  pub line_height: f32,
+}
+
+/// A TrueType or OpenType font that glyphs can be rasterized from.
+pub struct Font {
+  font: FontdueFont,
+}
+
+impl Font {
+  /// Parses a font from the raw bytes of a TTF or OTF file.
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+    return match FontdueFont::from_bytes(bytes, FontSettings::default()) {
+      Ok(font) => Ok(Self { font }),
+      Err(error) => Err(format!("Failed to load the font: {}", error)),
+    };
+  }
+
+  /// Loads a font from the TTF or OTF file at the given path.
+  pub fn from_file(path: &str) -> Result<Self, String> {
+    return match std::fs::read(path) {
+      Ok(bytes) => Self::from_bytes(&bytes),
+      Err(error) => Err(format!("Failed to read the font {}: {}", path, error)),
+    };
+  }
+
+  /// Rasterizes the character at the given size in pixels.
+  pub fn rasterize(&self, character: char, size: f32) -> RasterizedGlyph {
+    let (metrics, coverage) = self.font.rasterize(character, size);
+    return RasterizedGlyph {
+      width: metrics.width,
+      height: metrics.height,
+      offset_x: metrics.xmin,
+      offset_y: metrics.ymin,
+      advance: metrics.advance_width,
+      coverage,
+    };
+  }
+
+  /// Horizontal line metrics at the given size in pixels if the font
+  /// provides them.
+  pub fn line_metrics(&self, size: f32) -> Option<LineMetrics> {
+    return self.font.horizontal_line_metrics(size).map(|metrics| {
+      return LineMetrics {
+        ascent: metrics.ascent,
+        descent: metrics.descent,
+        line_height: metrics.new_line_size,
+      };
+    });
+  }
+}
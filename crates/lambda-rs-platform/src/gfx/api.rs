@@ -1,18 +1,43 @@
 //! GPU API exports to set the platforms primary rendering API for rendering
 //! implementations to use.
 
+/// The rendering backends that lambda can be compiled with. Only one backend
+/// is compiled into a build, which is chosen through the `gfx-with-*`
+/// features or detected from the platform.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+  Vulkan,
+  Metal,
+  Dx12,
+  Dx11,
+  Gl,
+  Empty,
+}
+
 cfg_if::cfg_if! {
 if #[cfg(any(feature = "gfx-with-gl", all(feature = "detect-platform", unix, not(target_os="macos")) ))] {
   pub use gfx_backend_gl as RenderingAPI;
+  pub const RENDERING_API_NAME: &str = "OpenGL";
+  pub const RENDERING_BACKEND: Backend = Backend::Gl;
 } else if #[cfg(any(feature = "gfx-with-metal", all(feature = "detect-platform", target_os="macos")))] {
   pub use gfx_backend_metal as RenderingAPI;
+  pub const RENDERING_API_NAME: &str = "Metal";
+  pub const RENDERING_BACKEND: Backend = Backend::Metal;
 } else if #[cfg(feature = "gfx-with-vulkan")] {
   pub use gfx_backend_vulkan as RenderingAPI;
+  pub const RENDERING_API_NAME: &str = "Vulkan";
+  pub const RENDERING_BACKEND: Backend = Backend::Vulkan;
 } else if #[cfg(feature = "gfx-with-dx11")] {
   pub use gfx_backend_dx11 as RenderingAPI;
+  pub const RENDERING_API_NAME: &str = "DirectX 11";
+  pub const RENDERING_BACKEND: Backend = Backend::Dx11;
 } else if #[cfg(any(feature = "gfx-with-dx12", all(windows, feature = "detect-platform")))] {
   pub use gfx_backend_dx12 as RenderingAPI;
+  pub const RENDERING_API_NAME: &str = "DirectX 12";
+  pub const RENDERING_BACKEND: Backend = Backend::Dx12;
 } else {
     pub use gfx_backend_empty as RenderingAPI;
+    pub const RENDERING_API_NAME: &str = "Empty";
+    pub const RENDERING_BACKEND: Backend = Backend::Empty;
   }
 }
@@ -1,3 +1,5 @@
+use std::cell::RefCell;
+
 use gfx_hal::{
   memory::{
     Segment,
@@ -12,6 +14,7 @@ use super::gpu::Gpu;
 // Reuse gfx-hal buffer usage & properties for now.
 pub type Usage = gfx_hal::buffer::Usage;
 pub type Properties = gfx_hal::memory::Properties;
+pub type IndexType = gfx_hal::IndexType;
 
 /// The type of buffers that can be allocated on the GPU.
 #[derive(Debug, Clone, Copy)]
@@ -24,12 +27,15 @@ pub enum BufferType {
 
 /// A buffer is a block of memory that can be used to store data that can be
 /// accessed by the GPU.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub struct Buffer<RenderBackend: Backend> {
   buffer: RenderBackend::Buffer,
-  memory: RenderBackend::Memory,
+  memory: RefCell<RenderBackend::Memory>,
+  length: usize,
   stride: usize,
   buffer_type: BufferType,
+  usage: Usage,
+  properties: Properties,
 }
 
 impl<RenderBackend: Backend> Buffer<RenderBackend> {
@@ -37,7 +43,9 @@ impl<RenderBackend: Backend> Buffer<RenderBackend> {
   /// created it.
   pub fn destroy(self, gpu: &Gpu<RenderBackend>) {
     unsafe {
-      gpu.internal_logical_device().free_memory(self.memory);
+      gpu
+        .internal_logical_device()
+        .free_memory(self.memory.into_inner());
       gpu.internal_logical_device().destroy_buffer(self.buffer);
     }
   }
@@ -46,6 +54,109 @@ impl<RenderBackend: Backend> Buffer<RenderBackend> {
   pub fn stride(&self) -> usize {
     return self.stride;
   }
+
+  /// The length of the buffer in bytes.
+  pub fn length(&self) -> usize {
+    return self.length;
+  }
+
+  /// The usage the buffer was built with.
+  pub fn usage(&self) -> Usage {
+    return self.usage;
+  }
+
+  /// The memory properties the buffer was built with.
+  pub fn properties(&self) -> Properties {
+    return self.properties;
+  }
+
+  /// Overwrites the start of the buffer with the data. The buffer must have
+  /// been built with CPU visible properties and the GPU must not be reading
+  /// from it, such as once the previous frame's submission has completed.
+  pub fn write<Data: Sized>(
+    &self,
+    gpu: &Gpu<RenderBackend>,
+    data: &[Data],
+  ) -> Result<(), &'static str> {
+    let bytes = unsafe {
+      std::slice::from_raw_parts(
+        data.as_ptr() as *const u8,
+        std::mem::size_of_val(data),
+      )
+    };
+    return self.write_bytes(gpu, 0, bytes);
+  }
+
+  /// Overwrites the bytes of the buffer starting at the offset. The same
+  /// requirements as `write` apply.
+  pub fn write_bytes(
+    &self,
+    gpu: &Gpu<RenderBackend>,
+    offset: usize,
+    bytes: &[u8],
+  ) -> Result<(), &'static str> {
+    if offset + bytes.len() > self.length {
+      logging::error!(
+        "Cannot write {} bytes at offset {} into a buffer of {} bytes.",
+        bytes.len(),
+        offset,
+        self.length
+      );
+      return Err("The data is larger than the buffer.");
+    }
+
+    let logical_device = gpu.internal_logical_device();
+    let mut memory = self.memory.borrow_mut();
+    unsafe {
+      let mapped_memory = logical_device
+        .map_memory(&mut *memory, Segment::ALL)
+        .map_err(|_| "Failed to map memory.")?;
+      std::ptr::copy_nonoverlapping(
+        bytes.as_ptr(),
+        mapped_memory.add(offset),
+        bytes.len(),
+      );
+      let memory_flush = logical_device
+        .flush_mapped_memory_ranges(std::iter::once((&*memory, Segment::ALL)))
+        .map_err(|_| "Failed to flush memory.");
+      logical_device.unmap_memory(&mut *memory);
+      return memory_flush;
+    }
+  }
+
+  /// Reads the bytes of the buffer starting at the offset. The buffer must
+  /// have been built with CPU visible properties and the GPU must have
+  /// finished writing to it.
+  pub fn read_bytes(
+    &self,
+    gpu: &Gpu<RenderBackend>,
+    offset: usize,
+    length: usize,
+  ) -> Result<Vec<u8>, &'static str> {
+    if offset + length > self.length {
+      return Err("The range is outside of the buffer.");
+    }
+
+    let logical_device = gpu.internal_logical_device();
+    let mut memory = self.memory.borrow_mut();
+    unsafe {
+      let mapped_memory = logical_device
+        .map_memory(&mut *memory, Segment::ALL)
+        .map_err(|_| "Failed to map memory.")?;
+      let bytes = logical_device
+        .invalidate_mapped_memory_ranges(std::iter::once((
+          &*memory,
+          Segment::ALL,
+        )))
+        .map(|_| {
+          return std::slice::from_raw_parts(mapped_memory.add(offset), length)
+            .to_vec();
+        })
+        .map_err(|_| "Failed to invalidate memory.");
+      logical_device.unmap_memory(&mut *memory);
+      return bytes;
+    }
+  }
 }
 
 impl<RenderBackend: Backend> Buffer<RenderBackend> {
@@ -210,9 +321,12 @@ impl BufferBuilder {
 
     return Ok(Buffer {
       buffer,
-      memory: buffer_memory,
+      memory: RefCell::new(buffer_memory),
+      length: self.buffer_length,
       stride: std::mem::size_of::<Data>(),
       buffer_type: self.buffer_type,
+      usage: self.usage,
+      properties: self.properties,
     });
   }
 }
@@ -5,6 +5,8 @@ use std::{
   rc::Rc,
 };
 
+/// A region of bytes to copy between buffers.
+pub use gfx_hal::command::BufferCopy;
 use gfx_hal::{
   command::ClearValue,
   device::Device,
@@ -82,6 +84,12 @@ pub enum Command<RenderBackend: gfx_hal::Backend> {
     surface: Rc<super::surface::Surface<RenderBackend>>,
     frame_buffer: Rc<super::framebuffer::Framebuffer<RenderBackend>>,
     viewport: ViewPort,
+    /// The color images that are rendered into after the surface, in the
+    /// order of the render pass's color attachments.
+    color_images: Vec<Rc<super::image::ColorImage<RenderBackend>>>,
+    /// The depth & stencil image of render passes that have a depth &
+    /// stencil attachment.
+    depth_stencil: Option<Rc<super::image::DepthStencilImage<RenderBackend>>>,
   },
   /// Ends a currently active render pass.
   EndRenderPass,
@@ -91,6 +99,12 @@ pub enum Command<RenderBackend: gfx_hal::Backend> {
   Draw {
     vertices: Range<u32>,
   },
+  /// Draws the indices of the bound index buffer, offsetting every index by
+  /// the base vertex.
+  DrawIndexed {
+    indices: Range<u32>,
+    base_vertex: i32,
+  },
   PushConstants {
     pipeline: Rc<RenderPipeline<RenderBackend>>,
     stage: super::pipeline::PipelineStage,
@@ -100,6 +114,23 @@ pub enum Command<RenderBackend: gfx_hal::Backend> {
   BindVertexBuffer {
     buffer: Rc<super::buffer::Buffer<RenderBackend>>,
   },
+  BindIndexBuffer {
+    buffer: Rc<super::buffer::Buffer<RenderBackend>>,
+    index_type: super::buffer::IndexType,
+  },
+  /// Sets the value that stencil tests compare against & that the `Replace`
+  /// stencil operation writes, for both faces.
+  SetStencilReference {
+    reference: u32,
+  },
+  /// Copies regions of the source buffer into the destination buffer outside
+  /// of a render pass. The copies are visible to every later command and to
+  /// the CPU once the submission has completed.
+  CopyBuffer {
+    source: Rc<super::buffer::Buffer<RenderBackend>>,
+    destination: Rc<super::buffer::Buffer<RenderBackend>>,
+    regions: Vec<BufferCopy>,
+  },
   EndRecording,
 }
 
@@ -152,28 +183,57 @@ impl<'command_pool, RenderBackend: gfx_hal::Backend>
           frame_buffer,
           surface,
           viewport,
-        } => self.command_buffer.begin_render_pass(
-          render_pass.internal_render_pass(),
-          frame_buffer.internal_frame_buffer(),
-          viewport.internal_viewport().rect,
-          vec![gfx_hal::command::RenderAttachmentInfo::<RenderBackend> {
+          color_images,
+          depth_stencil,
+        } => {
+          let clear_colors = render_pass.clear_colors();
+          let color_clear_value = |index: usize| ClearValue {
+            color: gfx_hal::command::ClearColor {
+              float32: clear_colors[index],
+            },
+          };
+          let mut attachments = vec![gfx_hal::command::RenderAttachmentInfo::<
+            RenderBackend,
+          > {
             image_view: surface
               .internal_surface_image()
               .expect("No internal surface set when beginning the render pass.")
               .borrow(),
-            clear_value: ClearValue {
-              color: gfx_hal::command::ClearColor {
-                float32: [0.0, 0.0, 0.0, 1.0],
+            clear_value: color_clear_value(0),
+          }];
+          for (index, color_image) in color_images.iter().enumerate() {
+            attachments.push(gfx_hal::command::RenderAttachmentInfo {
+              image_view: color_image.internal_image_view(),
+              clear_value: color_clear_value(index + 1),
+            });
+          }
+          if let Some(depth_stencil) = depth_stencil.as_ref() {
+            attachments.push(gfx_hal::command::RenderAttachmentInfo {
+              image_view: depth_stencil.internal_image_view(),
+              clear_value: ClearValue {
+                depth_stencil: gfx_hal::command::ClearDepthStencil {
+                  depth: render_pass.depth_clear_value(),
+                  stencil: render_pass.stencil_clear_value(),
+                },
               },
-            },
-          }]
-          .into_iter(),
-          gfx_hal::command::SubpassContents::Inline,
-        ),
+            });
+          }
+
+          self.command_buffer.begin_render_pass(
+            render_pass.internal_render_pass(),
+            frame_buffer.internal_frame_buffer(),
+            viewport.internal_viewport().rect,
+            attachments.into_iter(),
+            gfx_hal::command::SubpassContents::Inline,
+          )
+        }
         Command::AttachGraphicsPipeline { pipeline } => self
           .command_buffer
           .bind_graphics_pipeline(pipeline.internal_pipeline()),
         Command::EndRenderPass => self.command_buffer.end_render_pass(),
+        Command::SetStencilReference { reference } => self
+          .command_buffer
+          .set_stencil_reference(gfx_hal::pso::Face::all(), reference),
         Command::PushConstants {
           pipeline,
           stage,
@@ -188,6 +248,17 @@ impl<'command_pool, RenderBackend: gfx_hal::Backend>
         Command::Draw { vertices } => {
           self.command_buffer.draw(vertices.clone(), 0..1)
         }
+        Command::DrawIndexed {
+          indices,
+          base_vertex,
+        } => self.command_buffer.draw_indexed(indices, base_vertex, 0..1),
+        Command::BindIndexBuffer { buffer, index_type } => {
+          self.command_buffer.bind_index_buffer(
+            buffer.internal_buffer(),
+            gfx_hal::buffer::SubRange::WHOLE,
+            index_type,
+          )
+        }
         Command::BindVertexBuffer { buffer } => {
           self.command_buffer.bind_vertex_buffers(
             0,
@@ -195,6 +266,44 @@ impl<'command_pool, RenderBackend: gfx_hal::Backend>
               .into_iter(),
           )
         }
+        Command::CopyBuffer {
+          source,
+          destination,
+          regions,
+        } => {
+          use gfx_hal::{
+            buffer::Access,
+            memory::{
+              Barrier,
+              Dependencies,
+            },
+            pso::PipelineStage,
+          };
+
+          self.command_buffer.copy_buffer(
+            source.internal_buffer(),
+            destination.internal_buffer(),
+            regions.into_iter(),
+          );
+          self.command_buffer.pipeline_barrier(
+            PipelineStage::TRANSFER
+              ..PipelineStage::VERTEX_INPUT
+                | PipelineStage::VERTEX_SHADER
+                | PipelineStage::FRAGMENT_SHADER
+                | PipelineStage::TRANSFER
+                | PipelineStage::HOST,
+            Dependencies::empty(),
+            std::iter::once(Barrier::AllBuffers(
+              Access::TRANSFER_WRITE
+                ..Access::INDEX_BUFFER_READ
+                  | Access::VERTEX_BUFFER_READ
+                  | Access::UNIFORM_READ
+                  | Access::SHADER_READ
+                  | Access::TRANSFER_READ
+                  | Access::HOST_READ,
+            )),
+          );
+        }
         Command::EndRecording => self.command_buffer.finish(),
       }
     }
@@ -5,6 +5,10 @@ use gfx_hal::{
 
 use super::{
   gpu::Gpu,
+  image::{
+    ColorImage,
+    DepthStencilImage,
+  },
   render_pass::RenderPass,
   surface::Surface,
 };
@@ -32,11 +36,37 @@ impl<RenderBackend: gfx_hal::Backend> Framebuffer<RenderBackend> {
     return &self.frame_buffer;
   }
 }
-pub struct FramebufferBuilder {}
+pub struct FramebufferBuilder {
+  colors: Vec<gfx_hal::image::FramebufferAttachment>,
+  depth_stencil: Option<gfx_hal::image::FramebufferAttachment>,
+}
 
 impl FramebufferBuilder {
   pub fn new() -> Self {
-    return Self {};
+    return Self {
+      colors: vec![],
+      depth_stencil: None,
+    };
+  }
+
+  /// Renders into the color image after the surface & the color images
+  /// added before it, in the order of the render pass's color attachments.
+  pub fn with_color_image<RenderBackend: gfx_hal::Backend>(
+    mut self,
+    image: &ColorImage<RenderBackend>,
+  ) -> Self {
+    self.colors.push(image.internal_framebuffer_attachment());
+    return self;
+  }
+
+  /// Renders into the depth & stencil image alongside the surface, for
+  /// render passes with a depth & stencil attachment.
+  pub fn with_depth_stencil_image<RenderBackend: gfx_hal::Backend>(
+    mut self,
+    image: &DepthStencilImage<RenderBackend>,
+  ) -> Self {
+    self.depth_stencil = Some(image.internal_framebuffer_attachment());
+    return self;
   }
 
   /// Build a frame buffer on a given GPU for the given surface.
@@ -56,7 +86,9 @@ impl FramebufferBuilder {
         .internal_logical_device()
         .create_framebuffer(
           render_pass.internal_render_pass(),
-          vec![image].into_iter(),
+          std::iter::once(image)
+            .chain(self.colors)
+            .chain(self.depth_stencil),
           Extent {
             width,
             height,
@@ -9,6 +9,11 @@ use gfx_hal::{
     QueueGroup,
   },
 };
+pub use gfx_hal::{
+  adapter::DeviceType,
+  Features,
+  Limits,
+};
 #[cfg(test)]
 use mockall::automock;
 
@@ -21,25 +26,125 @@ use super::{
   surface,
 };
 
+/// Which kind of adapter to prefer when more than one can render.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerPreference {
+  /// Prefer discrete GPUs over integrated ones.
+  HighPerformance,
+  /// Prefer integrated GPUs over discrete ones.
+  LowPower,
+}
+
+/// How suitable an adapter of the device type is, where lower ranks are
+/// preferred. Software adapters are only ranked when a fallback adapter is
+/// forced, in which case they're the only adapters ranked.
+fn rank_device_type(
+  device_type: &DeviceType,
+  power_preference: Option<PowerPreference>,
+  force_fallback_adapter: bool,
+) -> Option<u8> {
+  if force_fallback_adapter {
+    return match device_type {
+      DeviceType::Cpu => Some(0),
+      _ => None,
+    };
+  }
+
+  return match (device_type, power_preference) {
+    (_, None) => Some(0),
+    (DeviceType::DiscreteGpu, Some(PowerPreference::HighPerformance)) => {
+      Some(0)
+    }
+    (DeviceType::IntegratedGpu, Some(PowerPreference::HighPerformance)) => {
+      Some(1)
+    }
+    (DeviceType::IntegratedGpu, Some(PowerPreference::LowPower)) => Some(0),
+    (DeviceType::DiscreteGpu, Some(PowerPreference::LowPower)) => Some(1),
+    (DeviceType::VirtualGpu, _) => Some(2),
+    (DeviceType::Other, _) => Some(3),
+    (DeviceType::Cpu, _) => Some(4),
+  };
+}
+
+/// The index of the device type with the best rank, preferring earlier
+/// adapters when ranks are tied.
+fn choose_adapter(
+  device_types: &[DeviceType],
+  power_preference: Option<PowerPreference>,
+  force_fallback_adapter: bool,
+) -> Option<usize> {
+  return device_types
+    .iter()
+    .enumerate()
+    .filter_map(|(index, device_type)| {
+      return rank_device_type(
+        device_type,
+        power_preference,
+        force_fallback_adapter,
+      )
+      .map(|rank| (rank, index));
+    })
+    .min()
+    .map(|(_, index)| index);
+}
+
 /// GpuBuilder for constructing a GPU
 pub struct GpuBuilder {
   render_queue_type: RenderQueueType,
+  power_preference: Option<PowerPreference>,
+  force_fallback_adapter: bool,
+  required_features: Features,
+  optional_features: Features,
 }
 
 impl GpuBuilder {
   /// Create a new GpuBuilder to configure and build a GPU to use for rendering.
+  /// The first adapter that can render is used unless a preference is given.
   pub fn new() -> Self {
     return Self {
       render_queue_type: RenderQueueType::Graphical,
+      power_preference: None,
+      force_fallback_adapter: false,
+      required_features: Features::empty(),
+      optional_features: Features::empty(),
     };
   }
 
+  /// Prefer adapters that match the power preference.
+  pub fn with_power_preference(
+    mut self,
+    power_preference: PowerPreference,
+  ) -> Self {
+    self.power_preference = Some(power_preference);
+    return self;
+  }
+
+  /// Only use software adapters, which is useful for testing on machines
+  /// without a GPU.
+  pub fn with_force_fallback_adapter(mut self) -> Self {
+    self.force_fallback_adapter = true;
+    return self;
+  }
+
   /// Set the type of queue to use for rendering. The GPU defaults to graphical.
   pub fn with_render_queue_type(mut self, queue_type: RenderQueueType) -> Self {
     self.render_queue_type = queue_type;
     return self;
   }
 
+  /// Features that the GPU must support. Adapters without them aren't used.
+  pub fn with_required_features(mut self, features: Features) -> Self {
+    self.required_features = features;
+    return self;
+  }
+
+  /// Features that are enabled if the chosen adapter supports them. Check
+  /// `Gpu::features` to find out which were enabled.
+  pub fn with_optional_features(mut self, features: Features) -> Self {
+    self.optional_features = features;
+    return self;
+  }
+
   /// If passing in a surface, the gpu will be built using the queue that best
   /// supports both the render queue & surface.
   pub fn build<RenderBackend: gfx_hal::Backend>(
@@ -49,19 +154,57 @@ impl GpuBuilder {
   ) -> Result<Gpu<RenderBackend>, String> {
     match (surface, self.render_queue_type) {
       (Some(surface), RenderQueueType::Graphical) => {
-        let adapter = instance.first_adapter();
+        // Only adapters with every required feature & a queue family that can
+        // render to the surface are considered.
+        let mut candidates = instance
+          .enumerate_adapters()
+          .into_iter()
+          .filter(|adapter| {
+            return adapter
+              .physical_device
+              .features()
+              .contains(self.required_features);
+          })
+          .filter_map(|adapter| {
+            let queue_family = adapter
+              .queue_families
+              .iter()
+              .find(|family| {
+                return surface.can_support_queue_family(family)
+                  && family.queue_type().supports_graphics();
+              })?
+              .id();
+            return Some((adapter, queue_family));
+          })
+          .collect::<Vec<_>>();
 
-        let queue_family = adapter
-          .queue_families
+        let device_types = candidates
           .iter()
-          .find(|family| {
-            return surface.can_support_queue_family(family)
-              && family.queue_type().supports_graphics();
-          })
-          .expect("No compatible queue family found.")
-          .id();
+          .map(|(adapter, _)| adapter.info.device_type.clone())
+          .collect::<Vec<_>>();
+        let index = choose_adapter(
+          &device_types,
+          self.power_preference,
+          self.force_fallback_adapter,
+        )
+        .ok_or_else(|| {
+          let adapter = match self.force_fallback_adapter {
+            true => "software adapter",
+            false => "adapter",
+          };
+          return match self.required_features.is_empty() {
+            true => format!("No {} can render to the surface.", adapter),
+            false => format!(
+              "No {} with the features {:?} can render to the surface.",
+              adapter, self.required_features
+            ),
+          };
+        })?;
 
-        return Ok(Gpu::new(adapter, queue_family));
+        let (adapter, queue_family) = candidates.swap_remove(index);
+        let features = self.required_features
+          | (self.optional_features & adapter.physical_device.features());
+        return Ok(Gpu::new(adapter, queue_family, features));
       }
       (Some(_surface), RenderQueueType::Compute) => {
         todo!("Support a Compute based GPU.")
@@ -76,6 +219,30 @@ pub struct Gpu<B: gfx_hal::Backend> {
   adapter: gfx_hal::adapter::Adapter<B>,
   gpu: gfx_hal::adapter::Gpu<B>,
   queue_group: QueueGroup<B>,
+  features: Features,
+  limits: Limits,
+}
+
+/// Information about the physical device that a GPU was created from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GpuInfo {
+  pub name: String,
+  pub vendor: usize,
+  pub device: usize,
+  pub device_type: DeviceType,
+}
+
+impl GpuInfo {
+  pub(super) fn from_adapter_info(
+    info: &gfx_hal::adapter::AdapterInfo,
+  ) -> Self {
+    return Self {
+      name: info.name.clone(),
+      vendor: info.vendor,
+      device: info.device,
+      device_type: info.device_type.clone(),
+    };
+  }
 }
 
 /// The render queue types that the GPU can use for
@@ -89,11 +256,12 @@ pub enum RenderQueueType {
 
 impl<RenderBackend: gfx_hal::Backend> Gpu<RenderBackend> {
   /// Instantiates a new GPU given an adapter that is implemented by the GPUs
-  /// current rendering backend B. A new GPU does not come with a command pool
-  /// unless specified.
+  /// current rendering backend B, opening the device with the features. A new
+  /// GPU does not come with a command pool unless specified.
   pub(super) fn new(
     adapter: Adapter<RenderBackend>,
     queue_family: gfx_hal::queue::QueueFamilyId,
+    features: Features,
   ) -> Self {
     let queue_family = adapter
       .queue_families
@@ -101,10 +269,11 @@ impl<RenderBackend: gfx_hal::Backend> Gpu<RenderBackend> {
       .find(|family| family.id() == queue_family)
       .expect("Failed to find the queue family requested for the GPU.");
 
+    let limits = adapter.physical_device.properties().limits;
     let mut gpu = unsafe {
       adapter
         .physical_device
-        .open(&[(queue_family, &[1.0])], gfx_hal::Features::empty())
+        .open(&[(queue_family, &[1.0])], features)
         .expect("Failed to open the device.")
     };
 
@@ -114,9 +283,62 @@ impl<RenderBackend: gfx_hal::Backend> Gpu<RenderBackend> {
       adapter,
       gpu,
       queue_group,
+      features,
+      limits,
     };
   }
 
+  /// Information about the physical device backing the GPU.
+  pub fn info(&self) -> GpuInfo {
+    return GpuInfo::from_adapter_info(&self.adapter.info);
+  }
+
+  /// The total size in bytes of the memory heaps that are local to the
+  /// device, which is how much memory the GPU reports having.
+  pub fn device_local_memory(&self) -> u64 {
+    return self
+      .adapter
+      .physical_device
+      .memory_properties()
+      .memory_heaps
+      .iter()
+      .filter(|heap| {
+        return heap
+          .flags
+          .contains(gfx_hal::memory::HeapFlags::DEVICE_LOCAL);
+      })
+      .map(|heap| heap.size)
+      .sum();
+  }
+
+  /// The features that were enabled when the device was opened.
+  pub fn features(&self) -> Features {
+    return self.features;
+  }
+
+  /// Whether or not every one of the features was enabled.
+  pub fn supports(&self, features: Features) -> bool {
+    return self.features.contains(features);
+  }
+
+  /// The resource limits of the physical device, such as the largest push
+  /// constant range and whether timestamp queries are supported.
+  pub fn limits(&self) -> &Limits {
+    return &self.limits;
+  }
+
+  /// Starts capturing the work submitted to the GPU with a graphics debugger
+  /// such as RenderDoc. Backends only load the RenderDoc in-application API
+  /// in debug builds, and log a warning when it isn't present.
+  pub fn start_capture(&self) {
+    gfx_hal::device::Device::start_capture(&self.gpu.device);
+  }
+
+  /// Stops the capture started by `start_capture`.
+  pub fn stop_capture(&self) {
+    gfx_hal::device::Device::stop_capture(&self.gpu.device);
+  }
+
   /// Submits a command buffer to the GPU.
   pub fn submit_command_buffer<'render_context>(
     &mut self,
@@ -222,4 +444,35 @@ mod tests {
 
   #[test]
   fn test_gpu_builder_build() {}
+
+  #[test]
+  fn test_adapters_are_chosen_by_preference() {
+    use super::{
+      choose_adapter,
+      DeviceType,
+      PowerPreference,
+    };
+
+    let device_types = [
+      DeviceType::Cpu,
+      DeviceType::IntegratedGpu,
+      DeviceType::DiscreteGpu,
+    ];
+
+    assert_eq!(choose_adapter(&device_types, None, false), Some(0));
+    assert_eq!(
+      choose_adapter(
+        &device_types,
+        Some(PowerPreference::HighPerformance),
+        false
+      ),
+      Some(2)
+    );
+    assert_eq!(
+      choose_adapter(&device_types, Some(PowerPreference::LowPower), false),
+      Some(1)
+    );
+    assert_eq!(choose_adapter(&device_types, None, true), Some(0));
+    assert_eq!(choose_adapter(&device_types[1..], None, true), None);
+  }
 }
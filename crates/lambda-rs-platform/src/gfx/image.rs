@@ -0,0 +1,356 @@
+//! Images allocated on the GPU that render passes render into alongside the
+//! surface, such as depth & stencil buffers and G-buffers.
+
+use gfx_hal::{
+  adapter::PhysicalDevice,
+  device::Device,
+  format::{
+    Aspects,
+    Format,
+    ImageFeature,
+    Swizzle,
+  },
+  image::{
+    FramebufferAttachment,
+    Kind,
+    SubresourceRange,
+    Tiling,
+    Usage,
+    ViewCapabilities,
+    ViewKind,
+  },
+  memory::{
+    Properties,
+    SparseFlags,
+  },
+  Backend,
+  MemoryTypeId,
+};
+
+use super::gpu::Gpu;
+
+/// The depth & stencil formats that are tried in order of preference. Every
+/// format has an 8 bit stencil.
+const DEPTH_STENCIL_FORMATS: [Format; 2] =
+  [Format::D24UnormS8Uint, Format::D32SfloatS8Uint];
+
+/// The depth & stencil format that the GPU can render into, if any.
+pub fn depth_stencil_format<RenderBackend: Backend>(
+  gpu: &Gpu<RenderBackend>,
+) -> Option<Format> {
+  return DEPTH_STENCIL_FORMATS.iter().copied().find(|format| {
+    return gpu
+      .internal_physical_device()
+      .format_properties(Some(*format))
+      .optimal_tiling
+      .contains(ImageFeature::DEPTH_STENCIL_ATTACHMENT);
+  });
+}
+
+/// Builder for a depth & stencil image the size of the surface.
+pub struct DepthStencilImageBuilder {
+  width: u32,
+  height: u32,
+}
+
+impl DepthStencilImageBuilder {
+  pub fn new() -> Self {
+    return Self {
+      width: 1,
+      height: 1,
+    };
+  }
+
+  /// The size of the image in pixels, which must match the surface it's
+  /// rendered alongside.
+  pub fn with_size(mut self, width: u32, height: u32) -> Self {
+    self.width = width.max(1);
+    self.height = height.max(1);
+    return self;
+  }
+
+  pub fn build<RenderBackend: Backend>(
+    self,
+    gpu: &Gpu<RenderBackend>,
+  ) -> Result<DepthStencilImage<RenderBackend>, String> {
+    let format = depth_stencil_format(gpu).ok_or_else(|| {
+      return "The GPU doesn't support rendering into a depth & stencil image."
+        .to_string();
+    })?;
+    let image = allocate_image(
+      gpu,
+      (self.width, self.height),
+      format,
+      Usage::DEPTH_STENCIL_ATTACHMENT,
+      Aspects::DEPTH | Aspects::STENCIL,
+      "depth",
+    )?;
+    return Ok(DepthStencilImage { image, format });
+  }
+}
+
+/// An image with the memory bound to it & a view of its aspects.
+#[derive(Debug)]
+struct AllocatedImage<RenderBackend: Backend> {
+  image: RenderBackend::Image,
+  memory: RenderBackend::Memory,
+  view: RenderBackend::ImageView,
+  size: (u32, u32),
+  memory_size: u64,
+}
+
+impl<RenderBackend: Backend> AllocatedImage<RenderBackend> {
+  fn destroy(self, gpu: &Gpu<RenderBackend>) {
+    let device = gpu.internal_logical_device();
+    unsafe {
+      device.destroy_image_view(self.view);
+      device.destroy_image(self.image);
+      device.free_memory(self.memory);
+    }
+  }
+}
+
+/// Allocates a 2D image in device local memory, where the kind of image is
+/// named in errors.
+fn allocate_image<RenderBackend: Backend>(
+  gpu: &Gpu<RenderBackend>,
+  (width, height): (u32, u32),
+  format: Format,
+  usage: Usage,
+  aspects: Aspects,
+  kind: &str,
+) -> Result<AllocatedImage<RenderBackend>, String> {
+  let device = gpu.internal_logical_device();
+
+  let mut image = unsafe {
+    device.create_image(
+      Kind::D2(width, height, 1, 1),
+      1,
+      format,
+      Tiling::Optimal,
+      usage,
+      SparseFlags::empty(),
+      ViewCapabilities::empty(),
+    )
+  }
+  .map_err(|error| format!("Failed to create a {} image: {}", kind, error))?;
+
+  let requirements = unsafe { device.get_image_requirements(&image) };
+  let memory_type = gpu
+    .internal_physical_device()
+    .memory_properties()
+    .memory_types
+    .iter()
+    .enumerate()
+    .find(|(id, memory_type)| {
+      return requirements.type_mask & (1 << id) != 0
+        && memory_type.properties.contains(Properties::DEVICE_LOCAL);
+    })
+    .map(|(id, _)| MemoryTypeId(id));
+
+  let memory = match memory_type.map(|memory_type| unsafe {
+    device.allocate_memory(memory_type, requirements.size)
+  }) {
+    Some(Ok(memory)) => memory,
+    _ => {
+      unsafe { device.destroy_image(image) };
+      return Err(format!("Failed to allocate memory for a {} image.", kind));
+    }
+  };
+
+  let view = unsafe {
+    device
+      .bind_image_memory(&memory, 0, &mut image)
+      .map_err(|error| error.to_string())
+      .and_then(|_| {
+        return device
+          .create_image_view(
+            &image,
+            ViewKind::D2,
+            format,
+            Swizzle::NO,
+            usage,
+            SubresourceRange {
+              aspects,
+              ..Default::default()
+            },
+          )
+          .map_err(|error| error.to_string());
+      })
+  };
+
+  return match view {
+    Ok(view) => Ok(AllocatedImage {
+      image,
+      memory,
+      view,
+      size: (width, height),
+      memory_size: requirements.size,
+    }),
+    Err(error) => {
+      unsafe {
+        device.destroy_image(image);
+        device.free_memory(memory);
+      }
+      Err(format!("Failed to create a {} image view: {}", kind, error))
+    }
+  };
+}
+
+/// A depth & stencil image that render passes can render into.
+#[derive(Debug)]
+pub struct DepthStencilImage<RenderBackend: Backend> {
+  image: AllocatedImage<RenderBackend>,
+  format: Format,
+}
+
+impl<RenderBackend: Backend> DepthStencilImage<RenderBackend> {
+  pub fn format(&self) -> Format {
+    return self.format;
+  }
+
+  /// The width & height of the image in pixels.
+  pub fn size(&self) -> (u32, u32) {
+    return self.image.size;
+  }
+
+  /// The size in bytes of the memory allocated for the image.
+  pub fn memory_size(&self) -> u64 {
+    return self.image.memory_size;
+  }
+
+  /// Destroys the image with the GPU that created it.
+  pub fn destroy(self, gpu: &Gpu<RenderBackend>) {
+    self.image.destroy(gpu);
+  }
+}
+
+impl<RenderBackend: Backend> DepthStencilImage<RenderBackend> {
+  pub(super) fn internal_framebuffer_attachment(
+    &self,
+  ) -> FramebufferAttachment {
+    return FramebufferAttachment {
+      usage: Usage::DEPTH_STENCIL_ATTACHMENT,
+      view_caps: ViewCapabilities::empty(),
+      format: self.format,
+    };
+  }
+
+  pub(super) fn internal_image_view(&self) -> &RenderBackend::ImageView {
+    return &self.image.view;
+  }
+}
+
+// ------------------------------- COLOR IMAGES --------------------------------
+
+/// Whether or not the GPU can render into color images of the format.
+pub fn supports_color_attachment<RenderBackend: Backend>(
+  gpu: &Gpu<RenderBackend>,
+  format: Format,
+) -> bool {
+  return gpu
+    .internal_physical_device()
+    .format_properties(Some(format))
+    .optimal_tiling
+    .contains(ImageFeature::COLOR_ATTACHMENT);
+}
+
+/// Builder for a color image that render passes render into alongside the
+/// surface, such as the images of a G-buffer.
+pub struct ColorImageBuilder {
+  width: u32,
+  height: u32,
+  format: Format,
+}
+
+impl ColorImageBuilder {
+  pub fn new() -> Self {
+    return Self {
+      width: 1,
+      height: 1,
+      format: Format::Rgba8Unorm,
+    };
+  }
+
+  /// The size of the image in pixels, which must match the surface it's
+  /// rendered alongside.
+  pub fn with_size(mut self, width: u32, height: u32) -> Self {
+    self.width = width.max(1);
+    self.height = height.max(1);
+    return self;
+  }
+
+  /// The format of the image. Defaults to `Rgba8Unorm`.
+  pub fn with_format(mut self, format: Format) -> Self {
+    self.format = format;
+    return self;
+  }
+
+  pub fn build<RenderBackend: Backend>(
+    self,
+    gpu: &Gpu<RenderBackend>,
+  ) -> Result<ColorImage<RenderBackend>, String> {
+    if !supports_color_attachment(gpu, self.format) {
+      return Err(format!(
+        "The GPU doesn't support rendering into {:?} images.",
+        self.format
+      ));
+    }
+    let image = allocate_image(
+      gpu,
+      (self.width, self.height),
+      self.format,
+      Usage::COLOR_ATTACHMENT,
+      Aspects::COLOR,
+      "color",
+    )?;
+    return Ok(ColorImage {
+      image,
+      format: self.format,
+    });
+  }
+}
+
+/// A color image that render passes can render into.
+#[derive(Debug)]
+pub struct ColorImage<RenderBackend: Backend> {
+  image: AllocatedImage<RenderBackend>,
+  format: Format,
+}
+
+impl<RenderBackend: Backend> ColorImage<RenderBackend> {
+  pub fn format(&self) -> Format {
+    return self.format;
+  }
+
+  /// The width & height of the image in pixels.
+  pub fn size(&self) -> (u32, u32) {
+    return self.image.size;
+  }
+
+  /// The size in bytes of the memory allocated for the image.
+  pub fn memory_size(&self) -> u64 {
+    return self.image.memory_size;
+  }
+
+  /// Destroys the image with the GPU that created it.
+  pub fn destroy(self, gpu: &Gpu<RenderBackend>) {
+    self.image.destroy(gpu);
+  }
+}
+
+impl<RenderBackend: Backend> ColorImage<RenderBackend> {
+  pub(super) fn internal_framebuffer_attachment(
+    &self,
+  ) -> FramebufferAttachment {
+    return FramebufferAttachment {
+      usage: Usage::COLOR_ATTACHMENT,
+      view_caps: ViewCapabilities::empty(),
+      format: self.format,
+    };
+  }
+
+  pub(super) fn internal_image_view(&self) -> &RenderBackend::ImageView {
+    return &self.image.view;
+  }
+}
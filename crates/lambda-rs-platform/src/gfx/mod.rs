@@ -7,6 +7,7 @@ pub mod command;
 pub mod fence;
 pub mod framebuffer;
 pub mod gpu;
+pub mod image;
 pub mod pipeline;
 pub mod render_pass;
 pub mod resource;
@@ -65,10 +66,14 @@ impl<RenderBackend: Backend> Instance<RenderBackend> {
     return self.gfx_hal_instance.enumerate_adapters();
   }
 
-  pub(super) fn first_adapter(
-    &self,
-  ) -> gfx_hal::adapter::Adapter<RenderBackend> {
-    return self.gfx_hal_instance.enumerate_adapters().remove(0);
+  /// Information about every adapter that the instance can create a GPU
+  /// from.
+  pub fn adapters(&self) -> Vec<gpu::GpuInfo> {
+    return self
+      .enumerate_adapters()
+      .iter()
+      .map(|adapter| gpu::GpuInfo::from_adapter_info(&adapter.info))
+      .collect();
   }
 
   pub(super) fn create_surface(
@@ -8,9 +8,11 @@ use gfx_hal::{
     BlendState,
     ColorBlendDesc,
     ColorMask,
+    DepthStencilDesc,
     EntryPoint,
     Face,
     GraphicsPipelineDesc,
+    PolygonMode,
     PrimitiveAssemblerDesc,
     Rasterizer,
   },
@@ -28,24 +30,43 @@ use super::{
 };
 
 /// Builder for a gfx-hal backed render pipeline.
-pub struct RenderPipelineBuilder<RenderBackend: Backend> {
+pub struct RenderPipelineBuilder<'cache, RenderBackend: Backend> {
   pipeline_layout: Option<RenderBackend::PipelineLayout>,
   push_constants: Vec<PushConstantUpload>,
   buffers: Vec<Buffer<RenderBackend>>,
   attributes: Vec<VertexAttribute>,
+  pipeline_cache: Option<&'cache PipelineCache<RenderBackend>>,
+  stencil_test: Option<StencilTest>,
+  color_writes: bool,
+  wireframe: bool,
 }
 
 pub type PipelineStage = gfx_hal::pso::ShaderStageFlags;
 
+pub use gfx_hal::pso::{
+  Comparison,
+  Sided,
+  State,
+  StencilFace,
+  StencilOp,
+  StencilTest,
+};
+
 pub type PushConstantUpload = (PipelineStage, Range<u32>);
 
-impl<RenderBackend: Backend> RenderPipelineBuilder<RenderBackend> {
+impl<'cache, RenderBackend: Backend>
+  RenderPipelineBuilder<'cache, RenderBackend>
+{
   pub fn new() -> Self {
     return Self {
       pipeline_layout: None,
       push_constants: Vec::new(),
       buffers: Vec::new(),
       attributes: Vec::new(),
+      pipeline_cache: None,
+      stencil_test: None,
+      color_writes: true,
+      wireframe: false,
     };
   }
 
@@ -79,6 +100,38 @@ impl<RenderBackend: Backend> RenderPipelineBuilder<RenderBackend> {
     return self;
   }
 
+  /// Creates the pipeline with the pipeline cache so that the driver can
+  /// reuse the work of pipelines it created before.
+  pub fn with_pipeline_cache(
+    mut self,
+    pipeline_cache: &'cache PipelineCache<RenderBackend>,
+  ) -> Self {
+    self.pipeline_cache = Some(pipeline_cache);
+    return self;
+  }
+
+  /// Tests & writes the stencil of the render pass's depth & stencil
+  /// attachment. Reference values that are `State::Dynamic` are set with
+  /// `Command::SetStencilReference`.
+  pub fn with_stencil_test(mut self, stencil_test: StencilTest) -> Self {
+    self.stencil_test = Some(stencil_test);
+    return self;
+  }
+
+  /// Whether or not the pipeline writes color, such as to disable color
+  /// writes for pipelines that only write the stencil.
+  pub fn with_color_writes(mut self, color_writes: bool) -> Self {
+    self.color_writes = color_writes;
+    return self;
+  }
+
+  /// Whether or not only the edges of triangles are rasterized, which needs
+  /// the GPU to be opened with `Features::NON_FILL_POLYGON_MODE`.
+  pub fn with_wireframe(mut self, wireframe: bool) -> Self {
+    self.wireframe = wireframe;
+    return self;
+  }
+
   /// Builds a render pipeline based on your builder configuration. You can
   /// configure a render pipeline to be however you'd like it to be.
   pub fn build(
@@ -122,6 +175,10 @@ impl<RenderBackend: Backend> RenderPipelineBuilder<RenderBackend> {
       primitive_assembler.internal_primitive_assembler(),
       Rasterizer {
         cull_face: Face::BACK,
+        polygon_mode: match self.wireframe {
+          true => PolygonMode::Line,
+          false => PolygonMode::Fill,
+        },
         ..Rasterizer::FILL
       },
       fragment_entry,
@@ -132,15 +189,31 @@ impl<RenderBackend: Backend> RenderPipelineBuilder<RenderBackend> {
       },
     );
 
-    pipeline_desc.blender.targets.push(ColorBlendDesc {
-      mask: ColorMask::ALL,
-      blend: Some(BlendState::ALPHA),
-    });
+    pipeline_desc.depth_stencil = DepthStencilDesc {
+      depth: None,
+      depth_bounds: false,
+      stencil: self.stencil_test,
+    };
+
+    // Only the surface is alpha blended, so that color attachments after it
+    // store what fragment shaders output as is.
+    let mask = match self.color_writes {
+      true => ColorMask::ALL,
+      false => ColorMask::empty(),
+    };
+    for index in 0..render_pass.color_attachment_count() {
+      pipeline_desc.blender.targets.push(ColorBlendDesc {
+        mask,
+        blend: (index == 0).then_some(BlendState::ALPHA),
+      });
+    }
 
     let pipeline = unsafe {
-      let pipeline_build_result = gpu
-        .internal_logical_device()
-        .create_graphics_pipeline(&pipeline_desc, None);
+      let pipeline_build_result =
+        gpu.internal_logical_device().create_graphics_pipeline(
+          &pipeline_desc,
+          self.pipeline_cache.map(|cache| &cache.cache),
+        );
 
       match pipeline_build_result {
         Ok(pipeline) => pipeline,
@@ -195,3 +268,64 @@ impl<RenderBackend: Backend> RenderPipeline<RenderBackend> {
     return &self.pipeline;
   }
 }
+
+/// Builder for a pipeline cache, which lets the driver reuse the work of
+/// pipelines that were created before, such as in a previous run.
+pub struct PipelineCacheBuilder {
+  data: Option<Vec<u8>>,
+}
+
+impl PipelineCacheBuilder {
+  pub fn new() -> Self {
+    return Self { data: None };
+  }
+
+  /// The data of a pipeline cache that was previously created with the same
+  /// GPU & driver. Drivers ignore data that they didn't create.
+  pub fn with_data(mut self, data: Vec<u8>) -> Self {
+    self.data = Some(data);
+    return self;
+  }
+
+  pub fn build<RenderBackend: Backend>(
+    self,
+    gpu: &Gpu<RenderBackend>,
+  ) -> Result<PipelineCache<RenderBackend>, String> {
+    let cache = unsafe {
+      gpu
+        .internal_logical_device()
+        .create_pipeline_cache(self.data.as_deref())
+    }
+    .map_err(|error| format!("Failed to create a pipeline cache: {}", error))?;
+    return Ok(PipelineCache { cache });
+  }
+}
+
+/// The pipelines a driver has created, which can be saved & loaded across
+/// runs.
+#[derive(Debug)]
+pub struct PipelineCache<RenderBackend: Backend> {
+  cache: RenderBackend::PipelineCache,
+}
+
+impl<RenderBackend: Backend> PipelineCache<RenderBackend> {
+  /// The data of the cache that can be passed to
+  /// `PipelineCacheBuilder::with_data` in a later run.
+  pub fn data(&self, gpu: &Gpu<RenderBackend>) -> Result<Vec<u8>, String> {
+    return unsafe {
+      gpu
+        .internal_logical_device()
+        .get_pipeline_cache_data(&self.cache)
+    }
+    .map_err(|error| format!("Failed to read the pipeline cache: {}", error));
+  }
+
+  /// Destroys the pipeline cache with the GPU that created it.
+  pub fn destroy(self, gpu: &Gpu<RenderBackend>) {
+    unsafe {
+      gpu
+        .internal_logical_device()
+        .destroy_pipeline_cache(self.cache);
+    }
+  }
+}
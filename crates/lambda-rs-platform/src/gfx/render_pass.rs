@@ -1,4 +1,7 @@
-use gfx_hal::device::Device;
+use gfx_hal::{
+  device::Device,
+  format::Aspects,
+};
 
 use super::{
   gpu::Gpu,
@@ -7,7 +10,7 @@ use super::{
 
 // ----------------------- RENDER ATTACHMENT OPERATIONS ------------------------
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum Operations {
   DontCare,
   Load,
@@ -43,8 +46,12 @@ impl Operations {
 pub struct AttachmentBuilder {
   samples: u8,
   color_format: Option<ColorFormat>,
+  clear_color: Option<[f32; 4]>,
   load_operation: gfx_hal::pass::AttachmentLoadOp,
   store_operation: gfx_hal::pass::AttachmentStoreOp,
+  stencil_load_operation: gfx_hal::pass::AttachmentLoadOp,
+  stencil_store_operation: gfx_hal::pass::AttachmentStoreOp,
+  layouts: std::ops::Range<ImageLayoutHint>,
 }
 
 /// builder for a render attachment
@@ -53,8 +60,12 @@ impl AttachmentBuilder {
     return Self {
       samples: 0,
       color_format: None,
+      clear_color: None,
       load_operation: gfx_hal::pass::AttachmentLoadOp::DontCare,
       store_operation: gfx_hal::pass::AttachmentStoreOp::DontCare,
+      stencil_load_operation: gfx_hal::pass::AttachmentLoadOp::DontCare,
+      stencil_store_operation: gfx_hal::pass::AttachmentStoreOp::DontCare,
+      layouts: ImageLayoutHint::Undefined..ImageLayoutHint::Present,
     };
   }
 
@@ -70,6 +81,13 @@ impl AttachmentBuilder {
     return self;
   }
 
+  /// The color that a color attachment is cleared to when it's loaded with
+  /// `Operations::Clear`, instead of the clear color of the render pass.
+  pub fn with_clear_color(mut self, clear_color: [f32; 4]) -> Self {
+    self.clear_color = Some(clear_color);
+    return self;
+  }
+
   /// Sets the load operation for the attachment.
   pub fn on_load(mut self, operation: Operations) -> Self {
     self.load_operation = operation.to_gfx_hal_load_operation();
@@ -82,6 +100,31 @@ impl AttachmentBuilder {
     return self;
   }
 
+  /// Sets the load operation for the stencil of a depth & stencil
+  /// attachment.
+  pub fn on_stencil_load(mut self, operation: Operations) -> Self {
+    self.stencil_load_operation = operation.to_gfx_hal_load_operation();
+    return self;
+  }
+
+  /// Sets the store operation for the stencil of a depth & stencil
+  /// attachment.
+  pub fn on_stencil_store(mut self, operation: Operations) -> Self {
+    self.stencil_store_operation = operation.to_gfx_hal_store_operation();
+    return self;
+  }
+
+  /// Sets the layout the attachment is in before & after the render pass.
+  /// Attachments that load their previous contents must not start out as
+  /// undefined.
+  pub fn with_layouts(
+    mut self,
+    layouts: std::ops::Range<ImageLayoutHint>,
+  ) -> Self {
+    self.layouts = layouts;
+    return self;
+  }
+
   /// Builds a render attachment that can be used within a render pass.
   pub fn build(self) -> Attachment {
     return Attachment {
@@ -92,22 +135,36 @@ impl AttachmentBuilder {
           self.load_operation,
           self.store_operation,
         ),
-        stencil_ops: gfx_hal::pass::AttachmentOps::DONT_CARE,
-        layouts: gfx_hal::image::Layout::Undefined
-          ..gfx_hal::image::Layout::Present,
+        stencil_ops: gfx_hal::pass::AttachmentOps::new(
+          self.stencil_load_operation,
+          self.stencil_store_operation,
+        ),
+        layouts: self.layouts,
       },
+      clear_color: self.clear_color,
     };
   }
 }
 
 pub struct Attachment {
   attachment: gfx_hal::pass::Attachment,
+  clear_color: Option<[f32; 4]>,
 }
 
 impl Attachment {
   fn gfx_hal_attachment(&self) -> gfx_hal::pass::Attachment {
     return self.attachment.clone();
   }
+
+  /// Whether or not the attachment is a depth or stencil attachment.
+  fn is_depth_stencil(&self) -> bool {
+    return self.attachment.format.is_some_and(|format| {
+      return format
+        .surface_desc()
+        .aspects
+        .intersects(Aspects::DEPTH | Aspects::STENCIL);
+    });
+  }
 }
 
 // ------------------------------ RENDER SUBPASS -------------------------------
@@ -115,22 +172,34 @@ impl Attachment {
 pub use gfx_hal::image::Layout as ImageLayoutHint;
 
 pub struct SubpassBuilder {
-  color_attachment: Option<(usize, ImageLayoutHint)>,
+  color_attachments: Vec<gfx_hal::pass::AttachmentRef>,
+  depth_stencil_attachment: bool,
 }
 
 impl SubpassBuilder {
   pub fn new() -> Self {
     return Self {
-      color_attachment: None,
+      color_attachments: vec![],
+      depth_stencil_attachment: false,
     };
   }
 
+  /// Renders into the depth & stencil attachment that follows the color
+  /// attachments.
+  pub fn with_depth_stencil_attachment(mut self) -> Self {
+    self.depth_stencil_attachment = true;
+    return self;
+  }
+
+  /// Renders into the color attachment, whose fragment shader output location
+  /// is the number of color attachments added before it. Subpasses without
+  /// color attachments render into the first attachment.
   pub fn with_color_attachment(
     mut self,
     attachment_index: usize,
     layout: ImageLayoutHint,
   ) -> Self {
-    self.color_attachment = Some((attachment_index, layout));
+    self.color_attachments.push((attachment_index, layout));
     return self;
   }
   pub fn with_inputs() {
@@ -143,51 +212,88 @@ impl SubpassBuilder {
     todo!("Implement preservation support for subpasses")
   }
 
-  pub fn build<'a>(self) -> Subpass<'a> {
+  pub fn build(self) -> Subpass {
+    let colors = match self.color_attachments.is_empty() {
+      true => vec![(0, ImageLayoutHint::ColorAttachmentOptimal)],
+      false => self.color_attachments,
+    };
+    // Depth & stencil attachments follow the color attachments.
+    let depth_stencil = self.depth_stencil_attachment.then_some((
+      colors.len(),
+      ImageLayoutHint::DepthStencilAttachmentOptimal,
+    ));
     return Subpass {
-      subpass: gfx_hal::pass::SubpassDesc {
-        colors: &[(0, ImageLayoutHint::ColorAttachmentOptimal)],
-        depth_stencil: None,
-        inputs: &[],
-        resolves: &[],
-        preserves: &[],
-      },
+      colors,
+      depth_stencil,
     };
   }
 }
 
-pub struct Subpass<'a> {
-  subpass: gfx_hal::pass::SubpassDesc<'a>,
+pub struct Subpass {
+  colors: Vec<gfx_hal::pass::AttachmentRef>,
+  depth_stencil: Option<gfx_hal::pass::AttachmentRef>,
 }
 
-impl<'a> Subpass<'a> {
-  fn gfx_hal_subpass(self) -> gfx_hal::pass::SubpassDesc<'a> {
-    return self.subpass;
+impl Subpass {
+  fn gfx_hal_subpass(&self) -> gfx_hal::pass::SubpassDesc<'_> {
+    return gfx_hal::pass::SubpassDesc {
+      colors: &self.colors,
+      depth_stencil: self.depth_stencil.as_ref(),
+      inputs: &[],
+      resolves: &[],
+      preserves: &[],
+    };
   }
 }
 
 // -------------------------------- RENDER PASS --------------------------------
 
-pub struct RenderPassBuilder<'builder> {
+pub struct RenderPassBuilder {
   attachments: Vec<Attachment>,
-  subpasses: Vec<Subpass<'builder>>,
+  subpasses: Vec<Subpass>,
+  clear_color: [f32; 4],
+  depth_clear_value: f32,
+  stencil_clear_value: u32,
 }
 
-impl<'builder> RenderPassBuilder<'builder> {
+impl RenderPassBuilder {
   pub fn new() -> Self {
     return Self {
       attachments: vec![],
       subpasses: vec![],
+      clear_color: [0.0, 0.0, 0.0, 1.0],
+      depth_clear_value: 1.0,
+      stencil_clear_value: 0,
     };
   }
 
+  /// The color that color attachments without a clear color of their own are
+  /// cleared to when they're loaded with `Operations::Clear`. Defaults to
+  /// opaque black.
+  pub fn with_clear_color(mut self, clear_color: [f32; 4]) -> Self {
+    self.clear_color = clear_color;
+    return self;
+  }
+
+  /// The depth that depth attachments are cleared to. Defaults to 1.0.
+  pub fn with_depth_clear_value(mut self, depth_clear_value: f32) -> Self {
+    self.depth_clear_value = depth_clear_value;
+    return self;
+  }
+
+  /// The value that stencil attachments are cleared to. Defaults to 0.
+  pub fn with_stencil_clear_value(mut self, stencil_clear_value: u32) -> Self {
+    self.stencil_clear_value = stencil_clear_value;
+    return self;
+  }
+
   /// Adds an attachment to the render pass. Can add multiple.
   pub fn add_attachment(mut self, attachment: Attachment) -> Self {
     self.attachments.push(attachment);
     return self;
   }
 
-  pub fn add_subpass(mut self, subpass: Subpass<'builder>) -> Self {
+  pub fn add_subpass(mut self, subpass: Subpass) -> Self {
     self.subpasses.push(subpass);
     return self;
   }
@@ -204,44 +310,83 @@ impl<'builder> RenderPassBuilder<'builder> {
         .on_load(Operations::Clear)
         .on_store(Operations::Store)
         .with_color_format(ColorFormat::Rgba8Srgb)
-        .build()
-        .gfx_hal_attachment()],
-      false => self
-        .attachments
-        .into_iter()
-        .map(|attachment| attachment.gfx_hal_attachment())
-        .collect(),
+        .build()],
+      false => self.attachments,
     };
+    let has_depth_stencil = attachments
+      .iter()
+      .any(|attachment| attachment.is_depth_stencil());
+    let clear_colors = attachments
+      .iter()
+      .filter(|attachment| !attachment.is_depth_stencil())
+      .map(|attachment| attachment.clear_color.unwrap_or(self.clear_color))
+      .collect::<Vec<_>>();
 
     // If there are no subpass descriptions, use a stub subpass attachment
     let subpasses = match self.subpasses.is_empty() {
-      true => vec![SubpassBuilder::new().build().gfx_hal_subpass()],
-      false => self
-        .subpasses
-        .into_iter()
-        .map(|subpass| subpass.gfx_hal_subpass())
-        .collect(),
+      true => vec![SubpassBuilder::new().build()],
+      false => self.subpasses,
     };
 
     let render_pass = unsafe {
       gpu.internal_logical_device().create_render_pass(
-        attachments.into_iter(),
-        subpasses.into_iter(),
+        attachments
+          .iter()
+          .map(|attachment| attachment.gfx_hal_attachment()),
+        subpasses.iter().map(|subpass| subpass.gfx_hal_subpass()),
         vec![].into_iter(),
       )
     }
     .expect("The GPU does not have enough memory to allocate a render pass.");
 
-    return RenderPass { render_pass };
+    return RenderPass {
+      render_pass,
+      clear_colors,
+      depth_clear_value: self.depth_clear_value,
+      stencil_clear_value: self.stencil_clear_value,
+      has_depth_stencil,
+    };
   }
 }
 
 #[derive(Debug)]
 pub struct RenderPass<RenderBackend: gfx_hal::Backend> {
   render_pass: RenderBackend::RenderPass,
+  clear_colors: Vec<[f32; 4]>,
+  depth_clear_value: f32,
+  stencil_clear_value: u32,
+  has_depth_stencil: bool,
 }
 
 impl<RenderBackend: gfx_hal::Backend> RenderPass<RenderBackend> {
+  /// The colors that the color attachments are cleared to, in the order of
+  /// the attachments.
+  pub fn clear_colors(&self) -> &[[f32; 4]] {
+    return &self.clear_colors;
+  }
+
+  /// The number of color attachments, which pipelines built with the render
+  /// pass have a fragment shader output for.
+  pub fn color_attachment_count(&self) -> usize {
+    return self.clear_colors.len();
+  }
+
+  /// The depth that depth attachments are cleared to.
+  pub fn depth_clear_value(&self) -> f32 {
+    return self.depth_clear_value;
+  }
+
+  /// The value that stencil attachments are cleared to.
+  pub fn stencil_clear_value(&self) -> u32 {
+    return self.stencil_clear_value;
+  }
+
+  /// Whether or not the render pass renders into a depth & stencil image
+  /// alongside the surface.
+  pub fn has_depth_stencil(&self) -> bool {
+    return self.has_depth_stencil;
+  }
+
   pub fn destroy(self, gpu: &Gpu<RenderBackend>) {
     unsafe {
       gpu
@@ -1,5 +1,8 @@
 /// ColorFormat for the surface.
-pub use gfx_hal::format::Format as ColorFormat;
+pub use gfx_hal::{
+  format::Format as ColorFormat,
+  window::PresentMode,
+};
 use gfx_hal::{
   window::{
     PresentationSurface,
@@ -143,6 +146,14 @@ impl<RenderBackend: gfx_hal::Backend> Surface<RenderBackend> {
     instance.destroy_surface(self.gfx_hal_surface);
   }
 
+  /// The formats that the GPU can present to the surface with.
+  pub fn supported_formats(
+    &self,
+    gpu: &Gpu<RenderBackend>,
+  ) -> Vec<ColorFormat> {
+    return self.get_supported_formats(gpu.internal_physical_device());
+  }
+
   /// Get the size of the surface's extent. Will only return a size if a
   /// swapchain has been applied to the surface to render with.
   pub fn size(&self) -> Option<(u32, u32)> {
@@ -153,15 +164,145 @@ impl<RenderBackend: gfx_hal::Backend> Surface<RenderBackend> {
   }
 }
 
+impl Swapchain {
+  /// The color format of the images within the swapchain.
+  pub fn format(&self) -> ColorFormat {
+    return self.format;
+  }
+
+  /// The present mode the swapchain will use when presenting images.
+  pub fn present_mode(&self) -> PresentMode {
+    return self.config.present_mode;
+  }
+}
+
+// ------------------------------ PRESENT MODES --------------------------------
+
+/// The present mode to request from the surface, trading latency for tearing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresentModePreference {
+  /// Waits for vertical blank & never tears.
+  Fifo,
+  /// Replaces the queued image with the newest image, which doesn't tear but
+  /// renders frames that are never presented.
+  Mailbox,
+  /// Presents immediately, which has the lowest latency but may tear.
+  Immediate,
+  /// Mailbox when it's supported, otherwise Immediate.
+  AutoNoVsync,
+}
+
+impl PresentModePreference {
+  /// The present mode to use out of the modes that a surface supports. Falls
+  /// back to FIFO, which every surface supports.
+  pub fn resolve(&self, supported: PresentMode) -> PresentMode {
+    let preferred: &[PresentMode] = match self {
+      PresentModePreference::Fifo => &[],
+      PresentModePreference::Mailbox => &[PresentMode::MAILBOX],
+      PresentModePreference::Immediate => &[PresentMode::IMMEDIATE],
+      PresentModePreference::AutoNoVsync => {
+        &[PresentMode::MAILBOX, PresentMode::IMMEDIATE]
+      }
+    };
+    return preferred
+      .iter()
+      .copied()
+      .find(|mode| supported.contains(*mode))
+      .unwrap_or(PresentMode::FIFO);
+  }
+}
+
+/// The number of swapchain images needed so that the frame latency can be
+/// reached, within the image counts the surface supports.
+fn image_count_for_latency(
+  frame_latency: u32,
+  supported: &std::ops::RangeInclusive<u32>,
+) -> u32 {
+  return (frame_latency + 1).clamp(*supported.start(), *supported.end());
+}
+
+// ------------------------------- COLOR SPACES --------------------------------
+
+/// The color space that images are presented in. Color spaces are chosen
+/// through the surface format, so HDR color spaces are only available on
+/// displays that support their formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+  /// 8-bit color that's converted to sRGB when written.
+  Srgb,
+  /// 8-bit color that's presented as written.
+  Linear,
+  /// 10-bit color for HDR10 displays.
+  Hdr10,
+  /// 16-bit floating point color for scRGB displays.
+  ExtendedLinear,
+}
+
+impl ColorSpace {
+  /// The surface formats that present in the color space, in order of
+  /// preference.
+  pub fn formats(&self) -> &'static [ColorFormat] {
+    return match self {
+      ColorSpace::Srgb => &[ColorFormat::Bgra8Srgb, ColorFormat::Rgba8Srgb],
+      ColorSpace::Linear => &[ColorFormat::Bgra8Unorm, ColorFormat::Rgba8Unorm],
+      ColorSpace::Hdr10 => {
+        &[ColorFormat::A2b10g10r10Unorm, ColorFormat::A2r10g10b10Unorm]
+      }
+      ColorSpace::ExtendedLinear => &[ColorFormat::Rgba16Sfloat],
+    };
+  }
+
+  /// The color space that a surface format presents in, if it's a surface
+  /// format that lambda knows of.
+  pub fn of(format: ColorFormat) -> Option<Self> {
+    return [
+      ColorSpace::Srgb,
+      ColorSpace::Linear,
+      ColorSpace::Hdr10,
+      ColorSpace::ExtendedLinear,
+    ]
+    .into_iter()
+    .find(|color_space| color_space.formats().contains(&format));
+  }
+}
+
+/// Chooses the requested format if it's supported, otherwise the most
+/// preferred supported format of the requested color space. Returns None when
+/// neither are supported.
+fn choose_surface_format(
+  supported: &[ColorFormat],
+  format: Option<ColorFormat>,
+  color_space: Option<ColorSpace>,
+) -> Option<ColorFormat> {
+  let requested = format.filter(|format| supported.contains(format));
+  return requested.or_else(|| {
+    return color_space?
+      .formats()
+      .iter()
+      .copied()
+      .find(|format| supported.contains(format));
+  });
+}
+
 // ------------------------------ SWAPCHAIN BUILDER ----------------------------
 
 pub struct SwapchainBuilder {
   size: (u32, u32),
+  present_mode: Option<PresentModePreference>,
+  frame_latency: Option<u32>,
+  format: Option<ColorFormat>,
+  color_space: Option<ColorSpace>,
 }
 
 impl SwapchainBuilder {
   pub fn new() -> Self {
-    return Self { size: (480, 360) };
+    return Self {
+      size: (480, 360),
+      present_mode: None,
+      frame_latency: None,
+      format: None,
+      color_space: None,
+    };
   }
 
   /// Set the size of the swapchain for the surface image.
@@ -170,6 +311,37 @@ impl SwapchainBuilder {
     return self;
   }
 
+  /// The present mode to request. The surface's preferred mode is used when
+  /// none is set.
+  pub fn with_present_mode(
+    mut self,
+    present_mode: PresentModePreference,
+  ) -> Self {
+    self.present_mode = Some(present_mode);
+    return self;
+  }
+
+  /// The number of frames that can be queued for presentation ahead of the
+  /// frame being displayed. Lower latencies reduce input lag, while higher
+  /// latencies smooth over frames that take longer to render.
+  pub fn with_frame_latency(mut self, frame_latency: u32) -> Self {
+    self.frame_latency = Some(frame_latency);
+    return self;
+  }
+
+  /// The format of the swapchain images, used when the surface supports it.
+  pub fn with_format(mut self, format: ColorFormat) -> Self {
+    self.format = Some(format);
+    return self;
+  }
+
+  /// The color space to present in when no supported format was set. The
+  /// surface's first supported format is used when neither are supported.
+  pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+    self.color_space = Some(color_space);
+    return self;
+  }
+
   pub fn build<RenderBackend: Backend>(
     self,
     gpu: &Gpu<RenderBackend>,
@@ -177,7 +349,12 @@ impl SwapchainBuilder {
   ) -> Swapchain {
     let physical_device = gpu.internal_physical_device();
     let caps = surface.gfx_hal_surface.capabilities(physical_device);
-    let format = surface.get_first_supported_format(physical_device);
+    let format = choose_surface_format(
+      &surface.get_supported_formats(physical_device),
+      self.format,
+      self.color_space,
+    )
+    .unwrap_or_else(|| surface.get_first_supported_format(physical_device));
     let (width, height) = self.size;
 
     let mut swapchain_config = gfx_hal::window::SwapchainConfig::from_caps(
@@ -186,10 +363,22 @@ impl SwapchainBuilder {
       gfx_hal::window::Extent2D { width, height },
     );
 
+    if let Some(present_mode) = self.present_mode {
+      swapchain_config.present_mode = present_mode.resolve(caps.present_modes);
+    }
+
     // TODO(vmarcella) Profile the performance on MacOS to see if this slows
     // down frame times.
-    if caps.image_count.contains(&3) {
-      swapchain_config.image_count = 3;
+    match self.frame_latency {
+      Some(frame_latency) => {
+        swapchain_config.image_count =
+          image_count_for_latency(frame_latency, &caps.image_count);
+      }
+      None => {
+        if caps.image_count.contains(&3) {
+          swapchain_config.image_count = 3;
+        }
+      }
     }
 
     return Swapchain {
@@ -220,6 +409,86 @@ mod tests {
 
     let swapchain_builder = SwapchainBuilder::new().with_size(1920, 1080);
     assert_eq!(swapchain_builder.size, (1920, 1080));
+
+    let swapchain_builder = SwapchainBuilder::new()
+      .with_present_mode(PresentModePreference::Mailbox)
+      .with_frame_latency(1);
+    assert_eq!(
+      swapchain_builder.present_mode,
+      Some(PresentModePreference::Mailbox)
+    );
+    assert_eq!(swapchain_builder.frame_latency, Some(1));
+  }
+
+  #[test]
+  fn test_present_mode_preferences_fall_back_to_fifo() {
+    let supported = PresentMode::FIFO | PresentMode::IMMEDIATE;
+    assert_eq!(
+      PresentModePreference::Mailbox.resolve(supported),
+      PresentMode::FIFO
+    );
+    assert_eq!(
+      PresentModePreference::Immediate.resolve(supported),
+      PresentMode::IMMEDIATE
+    );
+    assert_eq!(
+      PresentModePreference::AutoNoVsync.resolve(supported),
+      PresentMode::IMMEDIATE
+    );
+    assert_eq!(
+      PresentModePreference::AutoNoVsync
+        .resolve(supported | PresentMode::MAILBOX),
+      PresentMode::MAILBOX
+    );
+    assert_eq!(
+      PresentModePreference::Fifo.resolve(supported),
+      PresentMode::FIFO
+    );
+  }
+
+  #[test]
+  fn test_surface_formats_are_chosen_by_color_space() {
+    let supported = [
+      ColorFormat::Bgra8Unorm,
+      ColorFormat::Bgra8Srgb,
+      ColorFormat::A2b10g10r10Unorm,
+    ];
+    assert_eq!(
+      choose_surface_format(&supported, None, Some(ColorSpace::Srgb)),
+      Some(ColorFormat::Bgra8Srgb)
+    );
+    assert_eq!(
+      choose_surface_format(&supported, None, Some(ColorSpace::Hdr10)),
+      Some(ColorFormat::A2b10g10r10Unorm)
+    );
+    assert_eq!(
+      choose_surface_format(
+        &supported,
+        Some(ColorFormat::Bgra8Unorm),
+        Some(ColorSpace::Srgb)
+      ),
+      Some(ColorFormat::Bgra8Unorm)
+    );
+    assert_eq!(
+      choose_surface_format(
+        &supported,
+        Some(ColorFormat::Rgba16Sfloat),
+        Some(ColorSpace::ExtendedLinear)
+      ),
+      None
+    );
+    assert_eq!(
+      ColorSpace::of(ColorFormat::Rgba16Sfloat),
+      Some(ColorSpace::ExtendedLinear)
+    );
+    assert_eq!(ColorSpace::of(ColorFormat::R8Unorm), None);
+  }
+
+  #[test]
+  fn test_frame_latency_is_clamped_to_the_image_counts() {
+    assert_eq!(image_count_for_latency(1, &(2..=8)), 2);
+    assert_eq!(image_count_for_latency(0, &(2..=8)), 2);
+    assert_eq!(image_count_for_latency(3, &(2..=3)), 3);
   }
 
   #[test]
@@ -14,6 +14,16 @@ impl ViewPort {
   pub(super) fn internal_viewport(&self) -> gfx_hal::pso::Viewport {
     return self.viewport.clone();
   }
+
+  /// The position of the top left corner of the viewport in pixels.
+  pub fn coordinates(&self) -> (i16, i16) {
+    return (self.viewport.rect.x, self.viewport.rect.y);
+  }
+
+  /// The width & height of the viewport in pixels.
+  pub fn dimensions(&self) -> (u32, u32) {
+    return (self.viewport.rect.w as u32, self.viewport.rect.h as u32);
+  }
 }
 
 /// A builder for `Viewport`.
@@ -1,5 +1,10 @@
+pub mod clipboard;
+pub mod fontdue;
 pub mod gfx;
+pub mod miniz;
+pub mod net;
 pub mod obj;
+pub mod png;
 pub mod rand;
 pub mod shaderc;
 pub mod winit;
@@ -0,0 +1,22 @@
+//! DEFLATE compression. Wrapper around the miniz_oxide crate.
+
+use miniz_oxide::{
+  deflate::compress_to_vec,
+  inflate::decompress_to_vec_with_limit,
+};
+
+/// The compression level that balances compression speed & ratio.
+pub const DEFAULT_LEVEL: u8 = 6;
+
+/// Compresses the bytes with DEFLATE at a level between 0 (no compression)
+/// and 10 (the smallest output).
+pub fn compress(bytes: &[u8], level: u8) -> Vec<u8> {
+  return compress_to_vec(bytes, level.min(10));
+}
+
+/// Decompresses DEFLATE compressed bytes, failing if the output would be
+/// larger than the given size.
+pub fn decompress(bytes: &[u8], max_size: usize) -> Result<Vec<u8>, String> {
+  return decompress_to_vec_with_limit(bytes, max_size)
+    .map_err(|status| format!("Failed to decompress the data: {:?}", status));
+}
@@ -0,0 +1,347 @@
+//! Non-blocking UDP sockets that frame every datagram as a packet on a
+//! channel. Wrapper around the UDP sockets of the standard library.
+
+pub use std::net::SocketAddr;
+use std::{
+  collections::HashMap,
+  io::ErrorKind,
+};
+
+/// Identifies lambda packets, so that stray datagrams are ignored.
+const PROTOCOL_ID: u32 = 0x4c4d_4e31;
+
+/// The size of the header in front of every payload.
+const HEADER_SIZE: usize = 11;
+
+/// The largest payload that can be sent in a packet, which keeps packets
+/// below the MTU of most networks so that they aren't fragmented.
+pub const MAX_PAYLOAD_SIZE: usize = 1200;
+
+/// How packets on a channel are delivered. Neither channel resends lost
+/// packets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Channel {
+  /// Packets are delivered in the order they arrive, which may differ from
+  /// the order they were sent.
+  Unreliable,
+  /// Packets that arrive after a packet sent later than them are dropped,
+  /// such as for snapshots of state where only the latest matters.
+  Ordered,
+}
+
+impl Channel {
+  fn id(self) -> u8 {
+    return match self {
+      Channel::Unreliable => 0,
+      Channel::Ordered => 1,
+    };
+  }
+
+  fn from_id(id: u8) -> Option<Self> {
+    return match id {
+      0 => Some(Channel::Unreliable),
+      1 => Some(Channel::Ordered),
+      _ => None,
+    };
+  }
+}
+
+// ---------------------------------- PACKET -----------------------------------
+
+/// A payload framed with the channel it was sent on and its sequence number
+/// within that channel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+  pub channel: Channel,
+  pub sequence: u32,
+  pub payload: Vec<u8>,
+}
+
+impl Packet {
+  /// Encodes the packet as the protocol id, channel, sequence, and length of
+  /// the payload in little endian, followed by the payload.
+  pub fn encode(&self) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + self.payload.len());
+    bytes.extend_from_slice(&PROTOCOL_ID.to_le_bytes());
+    bytes.push(self.channel.id());
+    bytes.extend_from_slice(&self.sequence.to_le_bytes());
+    bytes.extend_from_slice(&(self.payload.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(&self.payload);
+    return bytes;
+  }
+
+  /// Decodes a packet, failing if the bytes aren't a whole lambda packet.
+  pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+    if bytes.len() < HEADER_SIZE {
+      return Err(format!("A packet of {} bytes has no header.", bytes.len()));
+    }
+    let u32_at = |offset: usize| {
+      let mut word = [0; 4];
+      word.copy_from_slice(&bytes[offset..offset + 4]);
+      return u32::from_le_bytes(word);
+    };
+    if u32_at(0) != PROTOCOL_ID {
+      return Err("The packet isn't a lambda packet.".to_string());
+    }
+    let channel = Channel::from_id(bytes[4])
+      .ok_or_else(|| format!("{} isn't a channel.", bytes[4]))?;
+    let length = u16::from_le_bytes([bytes[9], bytes[10]]) as usize;
+    if bytes.len() != HEADER_SIZE + length {
+      return Err(format!(
+        "The packet has {} bytes of payload, but its header expects {}.",
+        bytes.len() - HEADER_SIZE,
+        length
+      ));
+    }
+
+    return Ok(Self {
+      channel,
+      sequence: u32_at(5),
+      payload: bytes[HEADER_SIZE..].to_vec(),
+    });
+  }
+}
+
+// ----------------------------------- SOCKET ----------------------------------
+
+/// A payload received from a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Message {
+  pub peer: SocketAddr,
+  pub channel: Channel,
+  pub payload: Vec<u8>,
+}
+
+/// Builder for UDP sockets.
+pub struct UdpSocketBuilder {
+  address: SocketAddr,
+}
+
+impl UdpSocketBuilder {
+  /// A new builder binds to any port on every IPv4 interface.
+  pub fn new() -> Self {
+    return Self {
+      address: SocketAddr::from(([0, 0, 0, 0], 0)),
+    };
+  }
+
+  /// The address to bind the socket to, where port 0 binds to any free port.
+  pub fn with_address(mut self, address: SocketAddr) -> Self {
+    self.address = address;
+    return self;
+  }
+
+  /// Binds a non-blocking socket to the address.
+  pub fn build(self) -> Result<UdpSocket, String> {
+    let socket = std::net::UdpSocket::bind(self.address).map_err(|error| {
+      format!("Failed to bind a UDP socket to {}: {}", self.address, error)
+    })?;
+    socket.set_nonblocking(true).map_err(|error| {
+      format!("Failed to make the socket non-blocking: {}", error)
+    })?;
+    return Ok(UdpSocket {
+      socket,
+      sent: HashMap::new(),
+      received: HashMap::new(),
+    });
+  }
+}
+
+/// A non-blocking UDP socket that sends & receives packets.
+pub struct UdpSocket {
+  socket: std::net::UdpSocket,
+  /// The sequence of the next packet sent to each peer on each channel.
+  sent: HashMap<(SocketAddr, Channel), u32>,
+  /// The sequence of the latest packet received from each peer on each
+  /// channel.
+  received: HashMap<(SocketAddr, Channel), u32>,
+}
+
+impl UdpSocket {
+  /// The address the socket is bound to, including the port it was given
+  /// when bound to port 0.
+  pub fn local_address(&self) -> Result<SocketAddr, String> {
+    return self.socket.local_addr().map_err(|error| {
+      format!("Failed to get the socket's address: {}", error)
+    });
+  }
+
+  /// Sends the payload to the peer without waiting. Payloads larger than
+  /// `MAX_PAYLOAD_SIZE` are rejected.
+  pub fn send(
+    &mut self,
+    peer: SocketAddr,
+    channel: Channel,
+    payload: &[u8],
+  ) -> Result<(), String> {
+    if payload.len() > MAX_PAYLOAD_SIZE {
+      return Err(format!(
+        "A payload of {} bytes is larger than the maximum of {} bytes.",
+        payload.len(),
+        MAX_PAYLOAD_SIZE
+      ));
+    }
+    let sequence = self.sent.entry((peer, channel)).or_insert(0);
+    let packet = Packet {
+      channel,
+      sequence: *sequence,
+      payload: payload.to_vec(),
+    };
+    *sequence = sequence.wrapping_add(1);
+
+    return match self.socket.send_to(&packet.encode(), peer) {
+      Ok(_) => Ok(()),
+      Err(error) => {
+        Err(format!("Failed to send a packet to {}: {}", peer, error))
+      }
+    };
+  }
+
+  /// Receives every packet that has arrived since the last call without
+  /// waiting. Datagrams that aren't lambda packets and packets on the
+  /// ordered channel that arrived out of order are dropped.
+  pub fn receive(&mut self) -> Vec<Message> {
+    let mut messages = vec![];
+    let mut buffer = [0; HEADER_SIZE + MAX_PAYLOAD_SIZE];
+    loop {
+      let (length, peer) = match self.socket.recv_from(&mut buffer) {
+        Ok(received) => received,
+        Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+        // Some platforms report that a previous send was refused on the
+        // next receive, which shouldn't stop receiving from other peers.
+        Err(error) if error.kind() == ErrorKind::ConnectionReset => continue,
+        Err(error) => {
+          logging::warn!("Failed to receive a packet: {}", error);
+          break;
+        }
+      };
+
+      let packet = match Packet::decode(&buffer[..length]) {
+        Ok(packet) => packet,
+        Err(error) => {
+          logging::debug!("Dropped a datagram from {}: {}", peer, error);
+          continue;
+        }
+      };
+
+      if packet.channel == Channel::Ordered {
+        let latest = self.received.get(&(peer, packet.channel));
+        if latest.is_some_and(|latest| !is_newer(packet.sequence, *latest)) {
+          continue;
+        }
+        self
+          .received
+          .insert((peer, packet.channel), packet.sequence);
+      }
+
+      messages.push(Message {
+        peer,
+        channel: packet.channel,
+        payload: packet.payload,
+      });
+    }
+    return messages;
+  }
+
+  /// Forgets the sequences of a peer, such as once it has disconnected, so
+  /// that it can reconnect from the same address.
+  pub fn forget_peer(&mut self, peer: SocketAddr) {
+    self.sent.retain(|(address, _), _| *address != peer);
+    self.received.retain(|(address, _), _| *address != peer);
+  }
+}
+
+/// Whether the sequence was sent after the other, accounting for sequences
+/// wrapping around.
+fn is_newer(sequence: u32, other: u32) -> bool {
+  let difference = sequence.wrapping_sub(other);
+  return difference != 0 && difference < u32::MAX / 2;
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    is_newer,
+    Channel,
+    Packet,
+    SocketAddr,
+    UdpSocketBuilder,
+    MAX_PAYLOAD_SIZE,
+  };
+
+  #[test]
+  fn packets_are_framed_and_validated() {
+    let packet = Packet {
+      channel: Channel::Ordered,
+      sequence: 7,
+      payload: vec![1, 2, 3],
+    };
+    let bytes = packet.encode();
+    assert_eq!(Packet::decode(&bytes), Ok(packet));
+    assert!(Packet::decode(&bytes[..bytes.len() - 1]).is_err());
+    assert!(Packet::decode(&[0; 16]).is_err());
+
+    assert!(is_newer(1, 0));
+    assert!(is_newer(0, u32::MAX));
+    assert!(!is_newer(5, 5));
+    assert!(!is_newer(4, 5));
+  }
+
+  #[test]
+  fn ordered_packets_that_arrive_late_are_dropped() {
+    let loopback = SocketAddr::from(([127, 0, 0, 1], 0));
+    let mut receiver = UdpSocketBuilder::new()
+      .with_address(loopback)
+      .build()
+      .unwrap();
+    let mut sender = UdpSocketBuilder::new()
+      .with_address(loopback)
+      .build()
+      .unwrap();
+    let address = receiver.local_address().unwrap();
+
+    sender.send(address, Channel::Unreliable, b"hello").unwrap();
+    sender.send(address, Channel::Ordered, b"first").unwrap();
+    sender.send(address, Channel::Ordered, b"second").unwrap();
+    // Replay the first ordered packet, as if it was delayed by the network.
+    let stale = Packet {
+      channel: Channel::Ordered,
+      sequence: 0,
+      payload: b"late".to_vec(),
+    };
+    sender.socket.send_to(&stale.encode(), address).unwrap();
+    sender.socket.send_to(b"not a packet", address).unwrap();
+
+    // Loopback datagrams arrive almost immediately, but not synchronously.
+    let mut messages = vec![];
+    for _ in 0..100 {
+      messages.extend(receiver.receive());
+      if messages.len() >= 3 {
+        break;
+      }
+      std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    messages.extend(receiver.receive());
+
+    let payloads: Vec<&[u8]> = messages
+      .iter()
+      .map(|message| message.payload.as_slice())
+      .collect();
+    assert_eq!(
+      payloads,
+      vec![
+        b"hello".as_slice(),
+        b"first".as_slice(),
+        b"second".as_slice()
+      ]
+    );
+    assert_eq!(messages[0].peer, sender.local_address().unwrap());
+
+    assert!(sender
+      .send(address, Channel::Unreliable, &[0; MAX_PAYLOAD_SIZE + 1])
+      .is_err());
+  }
+}
@@ -1,10 +1,25 @@
 use std::{
   fs::File,
-  io::BufReader,
+  io::{
+    BufRead,
+    BufReader,
+    Read,
+  },
+  ops::Range,
+  path::{
+    Path,
+    PathBuf,
+  },
 };
 
 use obj::{
   load_obj,
+  raw::{
+    material::MtlColor,
+    object::Polygon,
+    parse_mtl,
+    parse_obj,
+  },
   Obj,
   TexturedVertex,
 };
@@ -24,3 +39,340 @@ pub fn load_textured_obj_from_file(path: &str) -> Obj<TexturedVertex> {
   let obj = load_obj(reader).expect("Failed to load obj file.");
   return obj;
 }
+
+// ------------------------------ OBJ MATERIALS --------------------------------
+
+/// A vertex of an OBJ model loaded alongside its materials.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ObjVertex {
+  pub position: [f32; 3],
+  pub normal: [f32; 3],
+  pub tex_coords: [f32; 2],
+}
+
+/// A material defined within an MTL library referenced by an OBJ file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjMaterial {
+  pub name: String,
+  pub diffuse_color: [f32; 3],
+  /// The path of the diffuse texture, resolved relative to the OBJ file.
+  pub diffuse_texture: Option<PathBuf>,
+}
+
+/// A run of triangles within an OBJ model that belong to the same object or
+/// group and share the same material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjSubMesh {
+  pub name: String,
+  /// Index into the materials of the model, if a material was used.
+  pub material: Option<usize>,
+  /// The range of vertices within the model that make up the submesh.
+  pub vertices: Range<usize>,
+}
+
+/// An OBJ model triangulated into a single list of vertices that is split
+/// into submeshes by object, group, and material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjModel {
+  pub vertices: Vec<ObjVertex>,
+  pub submeshes: Vec<ObjSubMesh>,
+  pub materials: Vec<ObjMaterial>,
+}
+
+/// Loads an OBJ file along with the MTL libraries that it references, which
+/// are resolved relative to the directory containing the OBJ file.
+pub fn load_obj_with_materials_from_file(
+  path: &str,
+) -> Result<ObjModel, String> {
+  let file = File::open(path)
+    .map_err(|error| format!("Failed to open {}: {}", path, error))?;
+  let directory = Path::new(path)
+    .parent()
+    .map(Path::to_path_buf)
+    .unwrap_or_default();
+  return load_obj_with_materials(BufReader::new(file), &directory);
+}
+
+/// Parses an OBJ model with materials from the input. MTL libraries and
+/// textures are resolved relative to the given directory.
+pub fn load_obj_with_materials<Input: Read>(
+  input: Input,
+  directory: &Path,
+) -> Result<ObjModel, String> {
+  return load_obj_with_materials_from(input, directory, |path: &Path| {
+    return std::fs::read(path).map_err(|error| {
+      format!("Failed to open the MTL file {}: {}", path.display(), error)
+    });
+  });
+}
+
+/// Parses an OBJ model with materials from the input, reading the MTL
+/// libraries that it references with the given function instead of from
+/// disk. MTL libraries and textures are resolved relative to the given
+/// directory.
+pub fn load_obj_with_materials_from<Input: Read>(
+  mut input: Input,
+  directory: &Path,
+  mut read_file: impl FnMut(&Path) -> Result<Vec<u8>, String>,
+) -> Result<ObjModel, String> {
+  let mut source = String::new();
+  input
+    .read_to_string(&mut source)
+    .map_err(|error| format!("Failed to read the OBJ file: {}", error))?;
+
+  // The OBJ parser only tracks the name of the last object, so objects are
+  // parsed as groups in order to split multi-object files into submeshes.
+  let source = source
+    .lines()
+    .map(|line| match line.trim_start().strip_prefix("o ") {
+      Some(name) => {
+        format!(
+          "g {}",
+          name.split_whitespace().collect::<Vec<_>>().join("_")
+        )
+      }
+      None => line.to_string(),
+    })
+    .collect::<Vec<_>>()
+    .join("\n");
+
+  let raw = parse_obj(source.as_bytes())
+    .map_err(|error| format!("Failed to parse the OBJ file: {}", error))?;
+
+  let mut materials = vec![];
+  for library in raw.material_libraries.iter() {
+    let path = directory.join(library);
+    materials.extend(parse_material_library(
+      read_file(&path)?.as_slice(),
+      path.parent().unwrap_or(Path::new("")),
+    )?);
+  }
+
+  // Determine which group & material every polygon belongs to.
+  let mut polygon_groups = vec![None; raw.polygons.len()];
+  for (name, group) in raw.groups.iter() {
+    for range in group.polygons.iter() {
+      let end = range.end.min(raw.polygons.len());
+      for polygon in polygon_groups[range.start..end].iter_mut() {
+        *polygon = Some(name.as_str());
+      }
+    }
+  }
+
+  let mut polygon_materials = vec![None; raw.polygons.len()];
+  for (name, mesh) in raw.meshes.iter() {
+    let material = materials
+      .iter()
+      .position(|material: &ObjMaterial| &material.name == name);
+    for range in mesh.polygons.iter() {
+      let end = range.end.min(raw.polygons.len());
+      for polygon in polygon_materials[range.start..end].iter_mut() {
+        *polygon = material;
+      }
+    }
+  }
+
+  let position = |index: usize| {
+    let (x, y, z, _) = raw.positions[index];
+    return [x, y, z];
+  };
+  let normal = |index: usize| {
+    let (x, y, z) = raw.normals[index];
+    return [x, y, z];
+  };
+  // OBJ texture coordinates start at the bottom left of the image while
+  // textures are sampled from the top left.
+  let tex_coords = |index: usize| {
+    let (u, v, _) = raw.tex_coords[index];
+    return [u, 1.0 - v];
+  };
+
+  let mut vertices = vec![];
+  let mut submeshes: Vec<ObjSubMesh> = vec![];
+
+  for (index, polygon) in raw.polygons.iter().enumerate() {
+    let polygon_vertices: Vec<ObjVertex> = match polygon {
+      Polygon::P(points) => points
+        .iter()
+        .map(|p| ObjVertex {
+          position: position(*p),
+          normal: [0.0; 3],
+          tex_coords: [0.0; 2],
+        })
+        .collect(),
+      Polygon::PT(points) => points
+        .iter()
+        .map(|(p, t)| ObjVertex {
+          position: position(*p),
+          normal: [0.0; 3],
+          tex_coords: tex_coords(*t),
+        })
+        .collect(),
+      Polygon::PN(points) => points
+        .iter()
+        .map(|(p, n)| ObjVertex {
+          position: position(*p),
+          normal: normal(*n),
+          tex_coords: [0.0; 2],
+        })
+        .collect(),
+      Polygon::PTN(points) => points
+        .iter()
+        .map(|(p, t, n)| ObjVertex {
+          position: position(*p),
+          normal: normal(*n),
+          tex_coords: tex_coords(*t),
+        })
+        .collect(),
+    };
+
+    let name = polygon_groups[index].unwrap_or("default");
+    let material = polygon_materials[index];
+    let continues_submesh = submeshes.last().is_some_and(|submesh| {
+      return submesh.name == name && submesh.material == material;
+    });
+    if !continues_submesh {
+      submeshes.push(ObjSubMesh {
+        name: name.to_string(),
+        material,
+        vertices: vertices.len()..vertices.len(),
+      });
+    }
+
+    // Polygons are triangulated as fans around their first vertex.
+    for corner in 1..polygon_vertices.len().saturating_sub(1) {
+      vertices.push(polygon_vertices[0]);
+      vertices.push(polygon_vertices[corner]);
+      vertices.push(polygon_vertices[corner + 1]);
+    }
+
+    if let Some(submesh) = submeshes.last_mut() {
+      submesh.vertices.end = vertices.len();
+    }
+  }
+
+  submeshes.retain(|submesh| !submesh.vertices.is_empty());
+
+  return Ok(ObjModel {
+    vertices,
+    submeshes,
+    materials,
+  });
+}
+
+/// Parses every material within an MTL library. Materials without a diffuse
+/// color are white.
+fn parse_material_library<Input: BufRead>(
+  input: Input,
+  directory: &Path,
+) -> Result<Vec<ObjMaterial>, String> {
+  let raw = parse_mtl(input)
+    .map_err(|error| format!("Failed to parse the MTL file: {}", error))?;
+
+  let mut materials = raw
+    .materials
+    .into_iter()
+    .map(|(name, material)| {
+      let diffuse_color = match material.diffuse {
+        Some(MtlColor::Rgb(r, g, b)) => [r, g, b],
+        _ => [1.0, 1.0, 1.0],
+      };
+      return ObjMaterial {
+        name,
+        diffuse_color,
+        diffuse_texture: material
+          .diffuse_map
+          .map(|texture| directory.join(texture.file)),
+      };
+    })
+    .collect::<Vec<_>>();
+
+  // Materials are stored in a hash map by the parser.
+  materials.sort_by(|a, b| a.name.cmp(&b.name));
+  return Ok(materials);
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use super::{
+    load_obj_with_materials,
+    load_obj_with_materials_from_file,
+    parse_material_library,
+  };
+
+  const MATERIALS: &str = "
+newmtl red
+Kd 1.0 0.0 0.0
+
+newmtl textured
+Kd 0.5 0.5 0.5
+map_Kd textures/crate.png
+";
+
+  const MODEL: &str = "
+mtllib materials.mtl
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.0 1.0 0.0
+v 0.0 1.0 0.0
+vt 0.0 0.0
+vt 1.0 1.0
+vn 0.0 0.0 1.0
+o first
+usemtl red
+f 1/1/1 2/1/1 3/2/1 4/2/1
+usemtl textured
+f 1/1/1 2/1/1 3/2/1
+o second
+f 1//1 3//1 4//1
+";
+
+  #[test]
+  fn materials_are_parsed() {
+    let materials =
+      parse_material_library(MATERIALS.as_bytes(), Path::new("assets"))
+        .unwrap();
+
+    assert_eq!(materials.len(), 2);
+    assert_eq!(materials[0].name, "red");
+    assert_eq!(materials[0].diffuse_color, [1.0, 0.0, 0.0]);
+    assert_eq!(materials[0].diffuse_texture, None);
+    assert_eq!(
+      materials[1].diffuse_texture,
+      Some(Path::new("assets").join("textures/crate.png"))
+    );
+  }
+
+  #[test]
+  fn models_are_split_into_submeshes() {
+    let directory = std::env::temp_dir().join("lambda-obj-materials");
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("materials.mtl"), MATERIALS).unwrap();
+
+    let model = load_obj_with_materials(MODEL.as_bytes(), &directory).unwrap();
+
+    // The quad is triangulated into two triangles.
+    assert_eq!(model.vertices.len(), 12);
+    assert_eq!(model.materials.len(), 2);
+
+    let submeshes = &model.submeshes;
+    assert_eq!(submeshes.len(), 3);
+    assert_eq!(submeshes[0].name, "first");
+    assert_eq!(submeshes[0].material, Some(0));
+    assert_eq!(submeshes[0].vertices, 0..6);
+    assert_eq!(submeshes[1].material, Some(1));
+    assert_eq!(submeshes[1].vertices, 6..9);
+    assert_eq!(submeshes[2].name, "second");
+    assert_eq!(submeshes[2].vertices, 9..12);
+
+    // Texture coordinates are flipped vertically.
+    assert_eq!(model.vertices[0].tex_coords, [0.0, 1.0]);
+    assert_eq!(model.vertices[2].tex_coords, [1.0, 0.0]);
+    assert_eq!(model.vertices[9].tex_coords, [0.0, 0.0]);
+    assert_eq!(model.vertices[9].normal, [0.0, 0.0, 1.0]);
+
+    assert!(load_obj_with_materials_from_file("missing.obj").is_err());
+  }
+}
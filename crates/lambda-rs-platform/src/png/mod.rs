@@ -0,0 +1,105 @@
+//! PNG image decoding & encoding. Wrapper around the png crate.
+
+use png::{
+  BitDepth,
+  ColorType,
+  Decoder,
+  Encoder,
+  Transformations,
+};
+
+/// An image with 8 bit RGBA pixels stored row by row from the top.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+  pub width: u32,
+  pub height: u32,
+  pub pixels: Vec<u8>,
+}
+
+impl Image {
+  /// Creates a fully transparent image.
+  pub fn new(width: u32, height: u32) -> Self {
+    return Self {
+      width,
+      height,
+      pixels: vec![0; width as usize * height as usize * 4],
+    };
+  }
+}
+
+/// Decodes a PNG of any color type & bit depth into an RGBA image.
+pub fn decode(bytes: &[u8]) -> Result<Image, String> {
+  let mut decoder = Decoder::new(bytes);
+  decoder.set_transformations(Transformations::normalize_to_color8());
+  let mut reader = decoder
+    .read_info()
+    .map_err(|error| format!("Failed to read the PNG header: {}", error))?;
+
+  let mut buffer = vec![0; reader.output_buffer_size()];
+  let info = reader
+    .next_frame(&mut buffer)
+    .map_err(|error| format!("Failed to decode the PNG: {}", error))?;
+  let decoded = &buffer[..info.line_size * info.height as usize];
+
+  let channels: &[usize] = match info.color_type {
+    ColorType::Rgba => &[0, 1, 2, 3],
+    ColorType::Rgb => &[0, 1, 2],
+    ColorType::GrayscaleAlpha => &[0, 0, 0, 1],
+    ColorType::Grayscale => &[0, 0, 0],
+    ColorType::Indexed => {
+      return Err("The PNG's palette couldn't be expanded.".to_string())
+    }
+  };
+  let samples = info.color_type.samples();
+
+  let mut pixels =
+    Vec::with_capacity(info.width as usize * info.height as usize * 4);
+  for row in decoded.chunks_exact(info.line_size) {
+    for pixel in row[..info.width as usize * samples].chunks_exact(samples) {
+      pixels.extend(channels.iter().map(|channel| pixel[*channel]));
+      if channels.len() == 3 {
+        pixels.push(u8::MAX);
+      }
+    }
+  }
+
+  return Ok(Image {
+    width: info.width,
+    height: info.height,
+    pixels,
+  });
+}
+
+/// Encodes the RGBA image as a PNG.
+pub fn encode(image: &Image) -> Result<Vec<u8>, String> {
+  let mut bytes = vec![];
+  let mut encoder = Encoder::new(&mut bytes, image.width, image.height);
+  encoder.set_color(ColorType::Rgba);
+  encoder.set_depth(BitDepth::Eight);
+  encoder
+    .write_header()
+    .and_then(|mut writer| writer.write_image_data(&image.pixels))
+    .map_err(|error| format!("Failed to encode the PNG: {}", error))?;
+  return Ok(bytes);
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    decode,
+    encode,
+    Image,
+  };
+
+  #[test]
+  fn images_survive_a_round_trip() {
+    let mut image = Image::new(3, 2);
+    image.pixels[4..8].copy_from_slice(&[255, 128, 0, 64]);
+
+    let decoded = decode(&encode(&image).unwrap()).unwrap();
+    assert_eq!(decoded, image);
+    assert!(decode(b"not a png").is_err());
+  }
+}
@@ -4,6 +4,13 @@ use shaderc;
 /// Export supported shader kinds.
 pub use shaderc::ShaderKind;
 
+/// Identifies the compiler that shaders are compiled with, so that shaders
+/// compiled by a different compiler can be told apart.
+pub fn compiler_version() -> String {
+  let (major, minor) = shaderc::get_spirv_version();
+  return format!("shaderc 0.7 (SPIR-V {}.{})", major, minor);
+}
+
 /// Builder for the shaderc platform shader compiler.
 pub struct ShaderCompilerBuilder {}
 
@@ -0,0 +1,333 @@
+//! Keys that are named after their position on a US keyboard, independent of
+//! the version of winit that reports them. Winit's key codes are converted
+//! here so that upgrading winit doesn't change the keys applications match
+//! on.
+
+use winit::event::VirtualKeyCode;
+
+/// A key on the keyboard. Keys that aren't listed are `Unidentified` and can
+/// still be told apart by their scan code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+  Digit0,
+  Digit1,
+  Digit2,
+  Digit3,
+  Digit4,
+  Digit5,
+  Digit6,
+  Digit7,
+  Digit8,
+  Digit9,
+  A,
+  B,
+  C,
+  D,
+  E,
+  F,
+  G,
+  H,
+  I,
+  J,
+  K,
+  L,
+  M,
+  N,
+  O,
+  P,
+  Q,
+  R,
+  S,
+  T,
+  U,
+  V,
+  W,
+  X,
+  Y,
+  Z,
+  F1,
+  F2,
+  F3,
+  F4,
+  F5,
+  F6,
+  F7,
+  F8,
+  F9,
+  F10,
+  F11,
+  F12,
+  F13,
+  F14,
+  F15,
+  F16,
+  F17,
+  F18,
+  F19,
+  F20,
+  F21,
+  F22,
+  F23,
+  F24,
+  Escape,
+  Tab,
+  CapsLock,
+  Space,
+  Enter,
+  Backspace,
+  Insert,
+  Delete,
+  Home,
+  End,
+  PageUp,
+  PageDown,
+  ArrowLeft,
+  ArrowUp,
+  ArrowRight,
+  ArrowDown,
+  PrintScreen,
+  ScrollLock,
+  Pause,
+  /// The key that opens the context menu, next to the right super key.
+  ContextMenu,
+  LeftShift,
+  RightShift,
+  LeftControl,
+  RightControl,
+  LeftAlt,
+  RightAlt,
+  /// The Windows or Command key on the left.
+  LeftSuper,
+  /// The Windows or Command key on the right.
+  RightSuper,
+  /// The `` ` `` & `~` key.
+  Grave,
+  Minus,
+  Equals,
+  LeftBracket,
+  RightBracket,
+  Backslash,
+  Semicolon,
+  Apostrophe,
+  Comma,
+  Period,
+  Slash,
+  NumLock,
+  Numpad0,
+  Numpad1,
+  Numpad2,
+  Numpad3,
+  Numpad4,
+  Numpad5,
+  Numpad6,
+  Numpad7,
+  Numpad8,
+  Numpad9,
+  NumpadAdd,
+  NumpadSubtract,
+  NumpadMultiply,
+  NumpadDivide,
+  NumpadDecimal,
+  NumpadComma,
+  NumpadEquals,
+  NumpadEnter,
+  /// The extra key left of `Z` on ISO keyboards.
+  IntlBackslash,
+  /// The `ろ` key on Japanese & the `/` key on Brazilian keyboards.
+  IntlRo,
+  IntlYen,
+  Convert,
+  NonConvert,
+  KanaMode,
+  KanjiMode,
+  Copy,
+  Cut,
+  Paste,
+  MediaPlayPause,
+  MediaStop,
+  MediaTrackNext,
+  MediaTrackPrevious,
+  AudioVolumeUp,
+  AudioVolumeDown,
+  AudioVolumeMute,
+  Unidentified,
+}
+
+impl From<VirtualKeyCode> for Key {
+  fn from(virtual_key: VirtualKeyCode) -> Self {
+    return match virtual_key {
+      VirtualKeyCode::Key0 => Key::Digit0,
+      VirtualKeyCode::Key1 => Key::Digit1,
+      VirtualKeyCode::Key2 => Key::Digit2,
+      VirtualKeyCode::Key3 => Key::Digit3,
+      VirtualKeyCode::Key4 => Key::Digit4,
+      VirtualKeyCode::Key5 => Key::Digit5,
+      VirtualKeyCode::Key6 => Key::Digit6,
+      VirtualKeyCode::Key7 => Key::Digit7,
+      VirtualKeyCode::Key8 => Key::Digit8,
+      VirtualKeyCode::Key9 => Key::Digit9,
+      VirtualKeyCode::A => Key::A,
+      VirtualKeyCode::B => Key::B,
+      VirtualKeyCode::C => Key::C,
+      VirtualKeyCode::D => Key::D,
+      VirtualKeyCode::E => Key::E,
+      VirtualKeyCode::F => Key::F,
+      VirtualKeyCode::G => Key::G,
+      VirtualKeyCode::H => Key::H,
+      VirtualKeyCode::I => Key::I,
+      VirtualKeyCode::J => Key::J,
+      VirtualKeyCode::K => Key::K,
+      VirtualKeyCode::L => Key::L,
+      VirtualKeyCode::M => Key::M,
+      VirtualKeyCode::N => Key::N,
+      VirtualKeyCode::O => Key::O,
+      VirtualKeyCode::P => Key::P,
+      VirtualKeyCode::Q => Key::Q,
+      VirtualKeyCode::R => Key::R,
+      VirtualKeyCode::S => Key::S,
+      VirtualKeyCode::T => Key::T,
+      VirtualKeyCode::U => Key::U,
+      VirtualKeyCode::V => Key::V,
+      VirtualKeyCode::W => Key::W,
+      VirtualKeyCode::X => Key::X,
+      VirtualKeyCode::Y => Key::Y,
+      VirtualKeyCode::Z => Key::Z,
+      VirtualKeyCode::F1 => Key::F1,
+      VirtualKeyCode::F2 => Key::F2,
+      VirtualKeyCode::F3 => Key::F3,
+      VirtualKeyCode::F4 => Key::F4,
+      VirtualKeyCode::F5 => Key::F5,
+      VirtualKeyCode::F6 => Key::F6,
+      VirtualKeyCode::F7 => Key::F7,
+      VirtualKeyCode::F8 => Key::F8,
+      VirtualKeyCode::F9 => Key::F9,
+      VirtualKeyCode::F10 => Key::F10,
+      VirtualKeyCode::F11 => Key::F11,
+      VirtualKeyCode::F12 => Key::F12,
+      VirtualKeyCode::F13 => Key::F13,
+      VirtualKeyCode::F14 => Key::F14,
+      VirtualKeyCode::F15 => Key::F15,
+      VirtualKeyCode::F16 => Key::F16,
+      VirtualKeyCode::F17 => Key::F17,
+      VirtualKeyCode::F18 => Key::F18,
+      VirtualKeyCode::F19 => Key::F19,
+      VirtualKeyCode::F20 => Key::F20,
+      VirtualKeyCode::F21 => Key::F21,
+      VirtualKeyCode::F22 => Key::F22,
+      VirtualKeyCode::F23 => Key::F23,
+      VirtualKeyCode::F24 => Key::F24,
+      VirtualKeyCode::Escape => Key::Escape,
+      VirtualKeyCode::Tab => Key::Tab,
+      VirtualKeyCode::Capital => Key::CapsLock,
+      VirtualKeyCode::Space => Key::Space,
+      VirtualKeyCode::Return => Key::Enter,
+      VirtualKeyCode::Back => Key::Backspace,
+      VirtualKeyCode::Insert => Key::Insert,
+      VirtualKeyCode::Delete => Key::Delete,
+      VirtualKeyCode::Home => Key::Home,
+      VirtualKeyCode::End => Key::End,
+      VirtualKeyCode::PageUp => Key::PageUp,
+      VirtualKeyCode::PageDown => Key::PageDown,
+      VirtualKeyCode::Left => Key::ArrowLeft,
+      VirtualKeyCode::Up => Key::ArrowUp,
+      VirtualKeyCode::Right => Key::ArrowRight,
+      VirtualKeyCode::Down => Key::ArrowDown,
+      VirtualKeyCode::Snapshot | VirtualKeyCode::Sysrq => Key::PrintScreen,
+      VirtualKeyCode::Scroll => Key::ScrollLock,
+      VirtualKeyCode::Pause => Key::Pause,
+      VirtualKeyCode::Apps => Key::ContextMenu,
+      VirtualKeyCode::LShift => Key::LeftShift,
+      VirtualKeyCode::RShift => Key::RightShift,
+      VirtualKeyCode::LControl => Key::LeftControl,
+      VirtualKeyCode::RControl => Key::RightControl,
+      VirtualKeyCode::LAlt => Key::LeftAlt,
+      VirtualKeyCode::RAlt => Key::RightAlt,
+      VirtualKeyCode::LWin => Key::LeftSuper,
+      VirtualKeyCode::RWin => Key::RightSuper,
+      VirtualKeyCode::Grave => Key::Grave,
+      VirtualKeyCode::Minus => Key::Minus,
+      VirtualKeyCode::Equals => Key::Equals,
+      VirtualKeyCode::LBracket => Key::LeftBracket,
+      VirtualKeyCode::RBracket => Key::RightBracket,
+      VirtualKeyCode::Backslash => Key::Backslash,
+      VirtualKeyCode::Semicolon => Key::Semicolon,
+      VirtualKeyCode::Apostrophe => Key::Apostrophe,
+      VirtualKeyCode::Comma => Key::Comma,
+      VirtualKeyCode::Period => Key::Period,
+      VirtualKeyCode::Slash => Key::Slash,
+      VirtualKeyCode::Numlock => Key::NumLock,
+      VirtualKeyCode::Numpad0 => Key::Numpad0,
+      VirtualKeyCode::Numpad1 => Key::Numpad1,
+      VirtualKeyCode::Numpad2 => Key::Numpad2,
+      VirtualKeyCode::Numpad3 => Key::Numpad3,
+      VirtualKeyCode::Numpad4 => Key::Numpad4,
+      VirtualKeyCode::Numpad5 => Key::Numpad5,
+      VirtualKeyCode::Numpad6 => Key::Numpad6,
+      VirtualKeyCode::Numpad7 => Key::Numpad7,
+      VirtualKeyCode::Numpad8 => Key::Numpad8,
+      VirtualKeyCode::Numpad9 => Key::Numpad9,
+      VirtualKeyCode::NumpadAdd => Key::NumpadAdd,
+      VirtualKeyCode::NumpadSubtract => Key::NumpadSubtract,
+      VirtualKeyCode::NumpadMultiply => Key::NumpadMultiply,
+      VirtualKeyCode::NumpadDivide => Key::NumpadDivide,
+      VirtualKeyCode::NumpadDecimal => Key::NumpadDecimal,
+      VirtualKeyCode::NumpadComma => Key::NumpadComma,
+      VirtualKeyCode::NumpadEquals => Key::NumpadEquals,
+      VirtualKeyCode::NumpadEnter => Key::NumpadEnter,
+      VirtualKeyCode::OEM102 => Key::IntlBackslash,
+      VirtualKeyCode::AbntC1 => Key::IntlRo,
+      VirtualKeyCode::Yen => Key::IntlYen,
+      VirtualKeyCode::Convert => Key::Convert,
+      VirtualKeyCode::NoConvert => Key::NonConvert,
+      VirtualKeyCode::Kana => Key::KanaMode,
+      VirtualKeyCode::Kanji => Key::KanjiMode,
+      VirtualKeyCode::Copy => Key::Copy,
+      VirtualKeyCode::Cut => Key::Cut,
+      VirtualKeyCode::Paste => Key::Paste,
+      VirtualKeyCode::PlayPause => Key::MediaPlayPause,
+      VirtualKeyCode::MediaStop => Key::MediaStop,
+      VirtualKeyCode::NextTrack => Key::MediaTrackNext,
+      VirtualKeyCode::PrevTrack => Key::MediaTrackPrevious,
+      VirtualKeyCode::VolumeUp => Key::AudioVolumeUp,
+      VirtualKeyCode::VolumeDown => Key::AudioVolumeDown,
+      VirtualKeyCode::Mute => Key::AudioVolumeMute,
+      // Keys that only some layouts or keyboards have a physical key for.
+      _ => Key::Unidentified,
+    };
+  }
+}
+
+/// Converts the key code of a keyboard input, which winit doesn't always
+/// know.
+pub fn key_from_virtual_key_code(virtual_key: Option<VirtualKeyCode>) -> Key {
+  return match virtual_key {
+    Some(virtual_key) => Key::from(virtual_key),
+    None => Key::Unidentified,
+  };
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use winit::event::VirtualKeyCode;
+
+  use super::{
+    key_from_virtual_key_code,
+    Key,
+  };
+
+  #[test]
+  fn winit_key_codes_are_converted() {
+    assert_eq!(Key::from(VirtualKeyCode::Key1), Key::Digit1);
+    assert_eq!(Key::from(VirtualKeyCode::Return), Key::Enter);
+    assert_eq!(Key::from(VirtualKeyCode::LWin), Key::LeftSuper);
+    assert_eq!(Key::from(VirtualKeyCode::Capital), Key::CapsLock);
+    assert_eq!(Key::from(VirtualKeyCode::WebHome), Key::Unidentified);
+    assert_eq!(key_from_virtual_key_code(None), Key::Unidentified);
+    assert_eq!(
+      key_from_virtual_key_code(Some(VirtualKeyCode::Grave)),
+      Key::Grave
+    );
+  }
+}
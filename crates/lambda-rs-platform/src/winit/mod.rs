@@ -1,8 +1,11 @@
 //! Winit wrapper to easily construct cross platform windows
 
+pub mod input;
+
 use winit::{
   dpi::{
     LogicalSize,
+    PhysicalPosition,
     PhysicalSize,
   },
   event::Event,
@@ -14,7 +17,9 @@ use winit::{
     EventLoopWindowTarget,
   },
   monitor::MonitorHandle,
+  platform::run_return::EventLoopExtRunReturn,
   window::{
+    Fullscreen,
     Window,
     WindowBuilder,
   },
@@ -24,10 +29,16 @@ use winit::{
 /// as possible. The exports from this module are not guaranteed to be stable.
 pub mod winit_exports {
   pub use winit::{
+    dpi::{
+      LogicalPosition,
+      PhysicalPosition,
+    },
     event::{
       ElementState,
       Event,
+      Ime,
       MouseButton,
+      MouseScrollDelta,
       VirtualKeyCode,
       WindowEvent,
     },
@@ -37,6 +48,7 @@ pub mod winit_exports {
       EventLoopProxy,
       EventLoopWindowTarget,
     },
+    monitor::MonitorHandle,
   };
 }
 
@@ -64,6 +76,11 @@ pub struct WindowProperties {
   pub name: String,
   pub dimensions: (u32, u32),
   pub monitor_handle: MonitorHandle,
+  /// The position of the top left corner of the window on the desktop in
+  /// physical pixels, or None to let the windowing system place it.
+  pub position: Option<(i32, i32)>,
+  /// Whether or not the window covers its monitor without borders.
+  pub fullscreen: bool,
 }
 
 /// Metadata for Lambda window sizing that supports Copy and Move operations.
@@ -139,14 +156,27 @@ impl WindowHandleBuilder {
       name,
       dimensions,
       monitor_handle,
+      position,
+      fullscreen,
     } = window_properties;
 
     // TODO(ahlawat) = Find out if there's a better way to do this. Looks kinda ugly.
     self = self.with_window_size(dimensions, monitor_handle.scale_factor());
 
-    let window_handle = WindowBuilder::new()
+    let mut window_builder = WindowBuilder::new()
       .with_title(name)
-      .with_inner_size(self.size.logical)
+      .with_inner_size(self.size.logical);
+    if let Some((x, y)) = position {
+      window_builder =
+        window_builder.with_position(PhysicalPosition::new(x, y));
+    }
+    if fullscreen {
+      window_builder = window_builder.with_fullscreen(Some(
+        Fullscreen::Borderless(Some(monitor_handle.clone())),
+      ));
+    }
+
+    let window_handle = window_builder
       .build(&lambda_loop.event_loop)
       .expect("Failed creation of window handle");
 
@@ -219,4 +249,13 @@ impl<E: 'static + std::fmt::Debug> Loop<E> {
   {
     self.event_loop.run(callback);
   }
+
+  /// Uses the winit event loop to run until the control flow exits, then
+  /// returns the exit code it exited with.
+  pub fn run_until_exit<Callback>(mut self, callback: Callback) -> i32
+  where
+    Callback: FnMut(Event<E>, &EventLoopWindowTarget<E>, &mut ControlFlow),
+  {
+    return self.event_loop.run_return(callback);
+  }
 }
@@ -0,0 +1,184 @@
+//! Compares the SIMD matrix fast path against the previous array
+//! implementations and scalar versions of the same operations. Run with `cargo bench -p lambda-rs --bench math`.
+
+use std::{
+  hint::black_box,
+  time::{
+    Duration,
+    Instant,
+  },
+};
+
+use lambda::math::{
+  matrix::Matrix,
+  simd::{
+    Mat4,
+    Vec4,
+  },
+};
+
+const ITERATIONS: u32 = 1_000_000;
+
+const MATRIX: [[f32; 4]; 4] = [
+  [2.0, 0.0, 1.0, 3.0],
+  [1.0, 3.0, 0.0, -1.0],
+  [0.0, 1.0, 4.0, 2.0],
+  [1.0, 0.0, 2.0, 5.0],
+];
+
+/// The multiply used by 4x4 arrays before the fast path, which transposes the
+/// other matrix and takes the dot product of every row & column.
+fn previous_multiply(a: &[[f32; 4]; 4], b: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+  let mut transposed = [[0.0; 4]; 4];
+  for (i, row) in transposed.iter_mut().enumerate() {
+    for (j, value) in row.iter_mut().enumerate() {
+      *value = b[j][i];
+    }
+  }
+
+  let mut result = [[0.0; 4]; 4];
+  for (i, row) in result.iter_mut().enumerate() {
+    for (j, value) in row.iter_mut().enumerate() {
+      *value = a[i]
+        .iter()
+        .zip(transposed[j].iter())
+        .map(|(a, b)| a * b)
+        .sum();
+    }
+  }
+  return result;
+}
+
+/// The determinant used by 4x4 arrays before the fast path, which allocates
+/// every submatrix during Laplace expansion.
+fn previous_determinant(matrix: &[Vec<f32>]) -> f32 {
+  if matrix.len() == 2 {
+    return matrix[0][0] * matrix[1][1] - matrix[0][1] * matrix[1][0];
+  }
+
+  let mut result = 0.0;
+  for i in 0..matrix.len() {
+    let submatrix: Vec<Vec<f32>> = matrix[1..]
+      .iter()
+      .map(|row| {
+        return row
+          .iter()
+          .enumerate()
+          .filter(|(k, _)| *k != i)
+          .map(|(_, value)| *value)
+          .collect();
+      })
+      .collect();
+    result += matrix[0][i]
+      * previous_determinant(&submatrix)
+      * (-1.0_f32).powi(i as i32);
+  }
+  return result;
+}
+
+/// A scalar Gauss-Jordan inverse without pivoting.
+fn scalar_inverse(matrix: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+  let mut matrix = *matrix;
+  let mut result = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+  ];
+  for column in 0..4 {
+    let pivot = matrix[column][column];
+    for j in 0..4 {
+      matrix[column][j] /= pivot;
+      result[column][j] /= pivot;
+    }
+    for row in (0..4).filter(|row| *row != column) {
+      let factor = matrix[row][column];
+      for j in 0..4 {
+        matrix[row][j] -= factor * matrix[column][j];
+        result[row][j] -= factor * result[column][j];
+      }
+    }
+  }
+  return result;
+}
+
+fn scalar_transform(matrix: &[[f32; 4]; 4], vector: &[f32; 4]) -> [f32; 4] {
+  let mut result = [0.0; 4];
+  for (value, row) in result.iter_mut().zip(matrix.iter()) {
+    *value = row.iter().zip(vector.iter()).map(|(a, b)| a * b).sum();
+  }
+  return result;
+}
+
+fn bench<Output>(
+  name: &str,
+  mut operation: impl FnMut() -> Output,
+) -> Duration {
+  let start = Instant::now();
+  for _ in 0..ITERATIONS {
+    black_box(operation());
+  }
+  let elapsed = start.elapsed();
+  println!(
+    "{:<28} {:>8.2} ns/iter",
+    name,
+    elapsed.as_nanos() as f64 / ITERATIONS as f64
+  );
+  return elapsed;
+}
+
+fn compare(name: &str, scalar: Duration, simd: Duration) {
+  println!(
+    "{:<28} {:>8.2}x speedup\n",
+    name,
+    scalar.as_secs_f64() / simd.as_secs_f64()
+  );
+}
+
+fn main() {
+  // Inputs are passed through black_box on every iteration so that the
+  // compiler can't hoist the operations out of the loops.
+  let (a, b) = (MATRIX, MATRIX.transpose());
+  let vector = [1.0, -2.0, 0.5, 1.0];
+  let (fast_a, fast_b) = (Mat4::from(black_box(a)), Mat4::from(black_box(b)));
+  let fast_vector = Vec4::from(black_box(vector));
+
+  let previous = bench("previous multiply", || {
+    return previous_multiply(&black_box(a), &black_box(b));
+  });
+  let simd = bench("Mat4::multiply", || {
+    return black_box(fast_a).multiply(&black_box(fast_b));
+  });
+  bench("[[f32; 4]; 4]::multiply", || {
+    black_box(a).multiply(&black_box(b))
+  });
+  compare("multiply", previous, simd);
+
+  let rows: Vec<Vec<f32>> = a.iter().map(|row| row.to_vec()).collect();
+  let previous = bench("previous determinant", || {
+    return previous_determinant(black_box(&rows));
+  });
+  let simd = bench("Mat4::determinant", || black_box(fast_a).determinant());
+  bench("[[f32; 4]; 4]::determinant", || black_box(a).determinant());
+  compare("determinant", previous, simd);
+
+  // Matrices didn't support inverse or transform before the fast path, so
+  // they're compared against straightforward scalar implementations. The
+  // scalar inverse doesn't pivot or detect singular matrices, so it's a lower
+  // bound rather than an equivalent implementation.
+  let scalar = bench("scalar inverse", || scalar_inverse(&black_box(a)));
+  let simd = bench("Mat4::inverse", || black_box(fast_a).inverse());
+  bench("[[f32; 4]; 4]::inverse", || black_box(a).inverse());
+  compare("inverse", scalar, simd);
+
+  let scalar = bench("scalar transform", || {
+    return scalar_transform(&black_box(a), &black_box(vector));
+  });
+  let simd = bench("Mat4::transform", || {
+    return black_box(fast_a).transform(&black_box(fast_vector));
+  });
+  bench("[[f32; 4]; 4]::transform", || {
+    return black_box(a).transform(&black_box(vector));
+  });
+  compare("transform", scalar, simd);
+}
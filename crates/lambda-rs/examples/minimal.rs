@@ -16,7 +16,10 @@ fn main() {
         .with_dimensions(800, 600)
         .with_name("Minimal window");
     })
+    .with_shutdown_hook(|exit_code| {
+      logging::info!("The minimal demo exited with {}.", exit_code);
+    })
     .build();
 
-  start_runtime(runtime);
+  std::process::exit(start_runtime(runtime));
 }
@@ -0,0 +1,282 @@
+use lambda::{
+  component::Component,
+  events::{
+    Events,
+    WindowEvent,
+  },
+  logging,
+  perf_hud::PerfHudComponent,
+  render::{
+    buffer::BufferBuilder,
+    command::RenderCommand,
+    particles::{
+      Curve,
+      EmitterConfig,
+      ParticleEmitter,
+    },
+    pipeline::RenderPipelineBuilder,
+    render_pass::RenderPassBuilder,
+    shader::{
+      Shader,
+      ShaderBuilder,
+      ShaderKind,
+      VirtualShader,
+    },
+    viewport,
+    RenderContext,
+    ResourceId,
+  },
+  runtime::start_runtime,
+  runtimes::{
+    application::ComponentResult,
+    ApplicationRuntimeBuilder,
+  },
+};
+
+// ------------------------------ SHADER SOURCE --------------------------------
+
+const VERTEX_SHADER_SOURCE: &str = r#"
+#version 450
+
+layout (location = 0) in vec3 vertex_position;
+layout (location = 1) in vec3 vertex_normal;
+layout (location = 2) in vec3 vertex_color;
+
+layout (location = 0) out vec3 frag_color;
+
+void main() {
+  gl_Position = vec4(vertex_position, 1.0);
+  frag_color = vertex_color;
+}
+
+"#;
+
+const FRAGMENT_SHADER_SOURCE: &str = r#"
+#version 450
+
+layout (location = 0) in vec3 frag_color;
+
+layout (location = 0) out vec4 fragment_color;
+
+void main() {
+  fragment_color = vec4(frag_color, 1.0);
+}
+
+"#;
+
+// ------------------------------- EMITTERS ------------------------------------
+
+const CAMERA_RIGHT: [f32; 3] = [1.0, 0.0, 0.0];
+const CAMERA_UP: [f32; 3] = [0.0, 1.0, 0.0];
+
+/// Slow, large particles that grow & fade into the background while drifting
+/// with the wind.
+fn smoke() -> EmitterConfig {
+  return EmitterConfig::new()
+    .with_position([0.0, -0.6, 0.0])
+    .with_spawn_rate(40.0)
+    .with_max_particles(128)
+    .with_lifetime(1.5..2.5)
+    .with_speed(0.2..0.4)
+    .with_velocity_cone([0.0, 1.0, 0.0], 0.35)
+    .with_acceleration([0.08, 0.05, 0.0])
+    .with_size(Curve::linear(0.05, 0.35))
+    .with_color(
+      Curve::linear([0.35, 0.35, 0.35], [0.0, 0.0, 0.0])
+        .with_key(0.2, [0.5, 0.5, 0.5]),
+    )
+    .with_seed(1);
+}
+
+/// Fast, small particles that fall under gravity while cooling from yellow
+/// to red.
+fn sparks() -> EmitterConfig {
+  return EmitterConfig::new()
+    .with_position([0.0, -0.6, 0.0])
+    .with_spawn_rate(120.0)
+    .with_max_particles(192)
+    .with_lifetime(0.5..1.2)
+    .with_speed(0.8..1.4)
+    .with_velocity_cone([0.0, 1.0, 0.0], 0.6)
+    .with_acceleration([0.0, -2.0, 0.0])
+    .with_size(Curve::linear(0.025, 0.0))
+    .with_color(
+      Curve::linear([1.0, 1.0, 0.6], [0.8, 0.1, 0.0])
+        .with_key(0.3, [1.0, 0.6, 0.1]),
+    )
+    .with_seed(2);
+}
+
+// --------------------------------- COMPONENT ---------------------------------
+
+pub struct ParticlesExample {
+  vertex_shader: Shader,
+  fragment_shader: Shader,
+  emitters: Vec<ParticleEmitter>,
+  render_pass: Option<ResourceId>,
+  render_pipelines: Vec<ResourceId>,
+  width: u32,
+  height: u32,
+}
+
+impl Component<ComponentResult, String> for ParticlesExample {
+  fn on_attach(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    let render_pass = RenderPassBuilder::new().build(render_context);
+
+    // Every emitter is drawn from its own vertex buffer, which is updated
+    // with the emitter's particles every frame.
+    for emitter in self.emitters.iter() {
+      let mesh = emitter.build_mesh();
+      let pipeline = RenderPipelineBuilder::new()
+        .with_buffer(
+          BufferBuilder::build_from_mesh(&mesh, render_context)
+            .expect("Failed to create the particle buffer."),
+          mesh.attributes().to_vec(),
+        )
+        .build(
+          render_context,
+          &render_pass,
+          &self.vertex_shader,
+          Some(&self.fragment_shader),
+        );
+      self
+        .render_pipelines
+        .push(render_context.attach_pipeline(pipeline));
+    }
+
+    self.render_pass = Some(render_context.attach_render_pass(render_pass));
+    logging::info!("Attached {} particle emitters.", self.emitters.len());
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_detach(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_event(&mut self, event: Events) -> Result<ComponentResult, String> {
+    if let Events::Window {
+      event: WindowEvent::Resize { width, height },
+      ..
+    } = event
+    {
+      self.width = width;
+      self.height = height;
+    }
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_update(
+    &mut self,
+    last_frame: &std::time::Duration,
+  ) -> Result<ComponentResult, String> {
+    for emitter in self.emitters.iter_mut() {
+      emitter.update(last_frame);
+    }
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_render(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Vec<RenderCommand> {
+    let viewport =
+      viewport::ViewportBuilder::new().build(self.width, self.height);
+
+    let mut commands = vec![
+      RenderCommand::SetViewports {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetScissors {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetPipeline {
+        pipeline: self.render_pipelines[0],
+      },
+      RenderCommand::BeginRenderPass {
+        render_pass: self
+          .render_pass
+          .expect("Cannot begin the render pass when it doesn't exist."),
+        viewport,
+      },
+    ];
+
+    // Smoke is drawn first so that sparks are drawn on top of it.
+    for (emitter, pipeline) in
+      self.emitters.iter().zip(self.render_pipelines.iter())
+    {
+      commands.push(RenderCommand::SetPipeline {
+        pipeline: *pipeline,
+      });
+      commands.extend(emitter.draw_commands(
+        *pipeline,
+        0,
+        CAMERA_RIGHT,
+        CAMERA_UP,
+      ));
+    }
+
+    commands.push(RenderCommand::EndRenderPass);
+    return commands;
+  }
+}
+
+impl Default for ParticlesExample {
+  fn default() -> Self {
+    let vertex_shader = VirtualShader::Source {
+      source: VERTEX_SHADER_SOURCE.to_string(),
+      kind: ShaderKind::Vertex,
+      entry_point: "main".to_string(),
+      name: "particles".to_string(),
+    };
+
+    let fragment_shader = VirtualShader::Source {
+      source: FRAGMENT_SHADER_SOURCE.to_string(),
+      kind: ShaderKind::Fragment,
+      entry_point: "main".to_string(),
+      name: "particles".to_string(),
+    };
+
+    let mut builder = ShaderBuilder::new();
+    return Self {
+      vertex_shader: builder.build(vertex_shader),
+      fragment_shader: builder.build(fragment_shader),
+      emitters: vec![
+        ParticleEmitter::new(smoke()),
+        ParticleEmitter::new(sparks()),
+      ],
+      render_pass: None,
+      render_pipelines: vec![],
+      width: 800,
+      height: 600,
+    };
+  }
+}
+
+fn main() {
+  let runtime = ApplicationRuntimeBuilder::new("Particles Example")
+    .with_window_configured_as(move |window_builder| {
+      return window_builder
+        .with_dimensions(800, 600)
+        .with_name("Particles Example");
+    })
+    .with_renderer_configured_as(|renderer_builder| {
+      return renderer_builder.with_render_timeout(1_000_000_000);
+    })
+    .with_component(move |runtime, particles: ParticlesExample| {
+      return (runtime, particles);
+    })
+    .with_component(move |runtime, perf_hud: PerfHudComponent| {
+      return (runtime, perf_hud);
+    })
+    .build();
+
+  start_runtime(runtime);
+}
@@ -0,0 +1,122 @@
+//! Draws a bouncing ball, a paddle, & a spinning line with the immediate mode
+//! 2D API, which needs one call per shape and no pipelines or buffers.
+
+use std::time::Duration;
+
+use lambda::{
+  component::Component,
+  events::Events,
+  render::{
+    color::Color,
+    command::RenderCommand,
+    render2d::Render2D,
+    RenderContext,
+  },
+  runtime::start_runtime,
+  runtimes::{
+    application::ComponentResult,
+    ApplicationRuntimeBuilder,
+  },
+};
+
+const BALL_RADIUS: f32 = 24.0;
+
+pub struct ShapesComponent {
+  render2d: Render2D,
+  ball: [f32; 2],
+  velocity: [f32; 2],
+  angle: f32,
+}
+
+impl Component<ComponentResult, String> for ShapesComponent {
+  fn on_attach(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    self.render2d.attach(render_context)?;
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_detach(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_event(&mut self, event: Events) -> Result<ComponentResult, String> {
+    self.render2d.on_event(&event);
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_update(
+    &mut self,
+    last_frame: &Duration,
+  ) -> Result<ComponentResult, String> {
+    let seconds = last_frame.as_secs_f32();
+    let size = self.render2d.size();
+    for ((position, velocity), bound) in
+      self.ball.iter_mut().zip(self.velocity.iter_mut()).zip(size)
+    {
+      *position += *velocity * seconds;
+      if *position < BALL_RADIUS || *position > bound - BALL_RADIUS {
+        *velocity = -*velocity;
+        *position = position.clamp(BALL_RADIUS, bound - BALL_RADIUS);
+      }
+    }
+    self.angle += seconds;
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_render(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Vec<RenderCommand> {
+    let [width, height] = self.render2d.size();
+    let center = [width / 2.0, height / 2.0];
+    let (sin, cos) = self.angle.sin_cos();
+
+    self.render2d.draw_rect(
+      [self.ball[0] - 80.0, height - 40.0],
+      [160.0, 16.0],
+      Color::from_hex("#4d9aff").unwrap(),
+    );
+    self.render2d.draw_line(
+      [center[0] - cos * 120.0, center[1] - sin * 120.0],
+      [center[0] + cos * 120.0, center[1] + sin * 120.0],
+      6.0,
+      Color::WHITE,
+    );
+    self
+      .render2d
+      .draw_circle(self.ball, BALL_RADIUS, Color::ORANGE);
+    return self.render2d.finish();
+  }
+}
+
+impl Default for ShapesComponent {
+  fn default() -> Self {
+    return Self {
+      render2d: Render2D::new()
+        .with_clear_color(Color::from_srgb8(13, 13, 26, 255)),
+      ball: [200.0, 150.0],
+      velocity: [240.0, 180.0],
+      angle: 0.0,
+    };
+  }
+}
+
+fn main() {
+  let runtime = ApplicationRuntimeBuilder::new("2D Shapes Demo")
+    .with_window_configured_as(move |window_builder| {
+      return window_builder
+        .with_dimensions(800, 600)
+        .with_name("2D Shapes Window");
+    })
+    .with_component(move |runtime, shapes: ShapesComponent| {
+      return (runtime, shapes);
+    })
+    .build();
+
+  start_runtime(runtime);
+}
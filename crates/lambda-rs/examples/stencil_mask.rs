@@ -0,0 +1,259 @@
+use lambda::{
+  component::Component,
+  events::{
+    Events,
+    WindowEvent,
+  },
+  render::{
+    command::RenderCommand,
+    pipeline::{
+      self,
+      Comparison,
+      PipelineStage,
+      StencilState,
+    },
+    render_pass,
+    shader::{
+      Shader,
+      ShaderBuilder,
+      ShaderKind,
+      VirtualShader,
+    },
+    viewport,
+    RenderContext,
+    ResourceId,
+  },
+  runtime::start_runtime,
+  runtimes::{
+    application::ComponentResult,
+    ApplicationRuntimeBuilder,
+  },
+};
+
+/// The stencil value that the portal is written with & that the scene behind
+/// the portal is tested against.
+const PORTAL: u32 = 1;
+
+/// Draws a portal into the stencil, then draws one scene inside of the
+/// portal and another scene outside of it.
+pub struct StencilMaskComponent {
+  vertex_shader: Shader,
+  fragment_shader: Shader,
+  render_pass: Option<ResourceId>,
+  mask_pipeline: Option<ResourceId>,
+  inside_pipeline: Option<ResourceId>,
+  outside_pipeline: Option<ResourceId>,
+  width: u32,
+  height: u32,
+  elapsed: f32,
+}
+
+impl Component<ComponentResult, String> for StencilMaskComponent {
+  fn on_attach(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    let render_pass = render_pass::RenderPassBuilder::new()
+      .with_stencil()
+      .with_stencil_clear_value(0)
+      .build(render_context);
+
+    let push_constants_size = std::mem::size_of::<PushConstant>() as u32;
+    let pipeline_with_stencil = |stencil: StencilState, color_writes: bool| {
+      return pipeline::RenderPipelineBuilder::new()
+        .with_push_constant(PipelineStage::VERTEX, push_constants_size)
+        .with_stencil(stencil)
+        .with_color_writes(color_writes);
+    };
+
+    // The portal is only written into the stencil, so it isn't visible.
+    let mask_pipeline =
+      pipeline_with_stencil(StencilState::write_reference(), false).try_build(
+        render_context,
+        &render_pass,
+        &self.vertex_shader,
+        Some(&self.fragment_shader),
+      )?;
+    let inside_pipeline =
+      pipeline_with_stencil(StencilState::test(Comparison::Equal), true)
+        .try_build(
+          render_context,
+          &render_pass,
+          &self.vertex_shader,
+          Some(&self.fragment_shader),
+        )?;
+    let outside_pipeline =
+      pipeline_with_stencil(StencilState::test(Comparison::NotEqual), true)
+        .try_build(
+          render_context,
+          &render_pass,
+          &self.vertex_shader,
+          Some(&self.fragment_shader),
+        )?;
+
+    self.render_pass = Some(render_context.attach_render_pass(render_pass));
+    self.mask_pipeline = Some(render_context.attach_pipeline(mask_pipeline));
+    self.inside_pipeline =
+      Some(render_context.attach_pipeline(inside_pipeline));
+    self.outside_pipeline =
+      Some(render_context.attach_pipeline(outside_pipeline));
+
+    logging::info!("Attached the StencilMaskComponent.");
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_detach(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_render(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Vec<RenderCommand> {
+    let viewport =
+      viewport::ViewportBuilder::new().build(self.width, self.height);
+    let sway = self.elapsed.sin() * 0.5;
+
+    let mut commands = vec![
+      RenderCommand::SetViewports {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetScissors {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::BeginRenderPass {
+        render_pass: self.render_pass.expect("No render pass is attached."),
+        viewport: viewport.clone(),
+      },
+      RenderCommand::SetStencilReference { reference: PORTAL },
+    ];
+
+    // Write the portal, then draw the same triangle on both sides of it so
+    // that it changes color as it passes through the portal.
+    let passes = [
+      (self.mask_pipeline, [1.0, 1.0, 1.0, 1.0], [0.0, 0.0], 1.2),
+      (self.inside_pipeline, [0.2, 0.8, 1.0, 1.0], [sway, 0.1], 0.9),
+      (
+        self.outside_pipeline,
+        [1.0, 0.4, 0.2, 1.0],
+        [sway, 0.1],
+        0.9,
+      ),
+    ];
+    for (pipeline, color, pos, scale) in passes {
+      let pipeline = pipeline.expect("No render pipeline is attached.");
+      let push_constant = PushConstant {
+        color,
+        pos,
+        scale: [scale, scale],
+      };
+      commands.push(RenderCommand::SetPipeline { pipeline });
+      commands.push(RenderCommand::PushConstants {
+        pipeline,
+        stage: PipelineStage::VERTEX,
+        offset: 0,
+        bytes: Vec::from(push_constants_to_bytes(&push_constant)),
+      });
+      commands.push(RenderCommand::Draw { vertices: 0..3 });
+    }
+
+    commands.push(RenderCommand::EndRenderPass);
+    return commands;
+  }
+
+  fn on_event(&mut self, event: Events) -> Result<ComponentResult, String> {
+    if let Events::Window {
+      event: WindowEvent::Resize { width, height },
+      ..
+    } = event
+    {
+      self.width = width;
+      self.height = height;
+    }
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_update(
+    &mut self,
+    last_frame: &std::time::Duration,
+  ) -> Result<ComponentResult, String> {
+    self.elapsed += last_frame.as_secs_f32();
+    return Ok(ComponentResult::Success);
+  }
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct PushConstant {
+  color: [f32; 4],
+  pos: [f32; 2],
+  scale: [f32; 2],
+}
+
+pub fn push_constants_to_bytes(push_constants: &PushConstant) -> &[u32] {
+  let bytes = unsafe {
+    let size_in_bytes = std::mem::size_of::<PushConstant>();
+    let size_in_u32 = size_in_bytes / std::mem::size_of::<u32>();
+    let ptr = push_constants as *const PushConstant as *const u32;
+    std::slice::from_raw_parts(ptr, size_in_u32)
+  };
+
+  return bytes;
+}
+
+impl Default for StencilMaskComponent {
+  fn default() -> Self {
+    let triangle_vertex = VirtualShader::Source {
+      source: include_str!("../assets/shaders/triangles.vert").to_string(),
+      kind: ShaderKind::Vertex,
+      name: String::from("triangles"),
+      entry_point: String::from("main"),
+    };
+
+    let triangle_fragment = VirtualShader::Source {
+      source: include_str!("../assets/shaders/triangles.frag").to_string(),
+      kind: ShaderKind::Fragment,
+      name: String::from("triangles"),
+      entry_point: String::from("main"),
+    };
+
+    let mut builder = ShaderBuilder::new();
+    let vertex_shader = builder.build(triangle_vertex);
+    let fragment_shader = builder.build(triangle_fragment);
+
+    return StencilMaskComponent {
+      vertex_shader,
+      fragment_shader,
+      render_pass: None,
+      mask_pipeline: None,
+      inside_pipeline: None,
+      outside_pipeline: None,
+      width: 800,
+      height: 600,
+      elapsed: 0.0,
+    };
+  }
+}
+
+fn main() {
+  let runtime = ApplicationRuntimeBuilder::new("Stencil Mask Demo")
+    .with_renderer_configured_as(move |render_context_builder| {
+      return render_context_builder.with_render_timeout(1_000_000_000);
+    })
+    .with_window_configured_as(move |window_builder| {
+      return window_builder
+        .with_dimensions(800, 600)
+        .with_name("Stencil Mask");
+    })
+    .with_component(move |runtime, component: StencilMaskComponent| {
+      return (runtime, component);
+    })
+    .build();
+
+  start_runtime(runtime);
+}
@@ -0,0 +1,68 @@
+//! Renders a spinning triangle directly into the terminal using the
+//! experimental terminal render target. No window or GPU is required, which
+//! makes this example useful for verifying lambda over an SSH session.
+
+use std::{
+  io::stdout,
+  thread::sleep,
+  time::Duration,
+};
+
+use lambda::render::{
+  mesh::MeshBuilder,
+  terminal::TerminalCanvasBuilder,
+  vertex::VertexBuilder,
+};
+
+/// Creates a rotation around the z axis as a column major matrix.
+fn make_rotation(angle: f32) -> [[f32; 4]; 4] {
+  let (sin, cos) = angle.sin_cos();
+  return [
+    [cos, sin, 0.0, 0.0],
+    [-sin, cos, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+  ];
+}
+
+fn main() {
+  let mut mesh_builder = MeshBuilder::new();
+  mesh_builder
+    .with_vertex(
+      VertexBuilder::new()
+        .with_position([0.0, 0.8, 0.0])
+        .with_color([1.0, 0.0, 0.0])
+        .build(),
+    )
+    .with_vertex(
+      VertexBuilder::new()
+        .with_position([-0.8, -0.6, 0.0])
+        .with_color([0.0, 1.0, 0.0])
+        .build(),
+    )
+    .with_vertex(
+      VertexBuilder::new()
+        .with_position([0.8, -0.6, 0.0])
+        .with_color([0.0, 0.0, 1.0])
+        .build(),
+    );
+  let mesh = mesh_builder.build();
+
+  let mut canvas = TerminalCanvasBuilder::new()
+    .with_dimensions(64, 64)
+    .with_clear_color([0.05, 0.05, 0.1])
+    .build();
+
+  // Clear the terminal once before presenting frames over each other.
+  print!("\x1B[2J");
+
+  let mut output = stdout();
+  for frame in 0..300 {
+    canvas.clear();
+    canvas.draw_mesh(&mesh, &make_rotation(frame as f32 * 0.05));
+    canvas
+      .present(&mut output)
+      .expect("Failed to present the frame to the terminal.");
+    sleep(Duration::from_millis(16));
+  }
+}
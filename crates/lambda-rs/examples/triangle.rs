@@ -86,26 +86,22 @@ impl Component<ComponentResult, String> for DemoComponent {
         WindowEvent::Close => {
           logging::info!("Window closed");
         }
+        _ => {}
       },
       Events::Keyboard { event, issued_at } => match event {
-        Key::Pressed {
-          scan_code,
-          virtual_key,
-        } => {
-          logging::debug!("Key pressed: {:?}", virtual_key);
+        Key::Pressed { scan_code, key, .. } => {
+          logging::debug!("Key pressed: {:?}", key);
+        }
+        Key::Released { scan_code, key, .. } => {
+          logging::debug!("Key released: {:?}", key);
         }
-        Key::Released {
-          scan_code,
-          virtual_key,
-        } => {
-          logging::debug!("Key released: {:?}", virtual_key);
+        Key::ModifierPressed { modifier, key, .. } => {
+          logging::debug!("Modifier pressed: {:?}", key);
         }
-        Key::ModifierPressed {
-          modifier,
-          virtual_key,
-        } => {
-          logging::debug!("Modifier pressed: {:?}", virtual_key);
+        Key::TextInput { text } => {
+          logging::debug!("Text input: {:?}", text);
         }
+        _ => {}
       },
       Events::Component { event, issued_at } => match event {
         ComponentEvent::Attached { name } => {
@@ -114,6 +110,9 @@ impl Component<ComponentResult, String> for DemoComponent {
         ComponentEvent::Detached { name } => {
           logging::debug!("Component detached: {:?}", name);
         }
+        ComponentEvent::Errored { name, message } => {
+          logging::error!("Component {:?} errored: {}", name, message);
+        }
       },
       _ => {}
     };
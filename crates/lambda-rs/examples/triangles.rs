@@ -3,9 +3,9 @@ use lambda::{
   events::{
     Events,
     Key,
-    VirtualKey,
     WindowEvent,
   },
+  input,
   render::{
     command::RenderCommand,
     pipeline::{
@@ -168,23 +168,21 @@ impl Component<ComponentResult, String> for TrianglesComponent {
         WindowEvent::Close => {
           logging::info!("Window closed");
         }
+        _ => {}
       },
       Events::Component { event, issued_at } => todo!(),
       Events::Keyboard { event, issued_at } => match event {
-        Key::Pressed {
-          scan_code,
-          virtual_key,
-        } => match virtual_key {
-          Some(VirtualKey::W) => {
+        Key::Pressed { scan_code, key, .. } => match key {
+          input::Key::W => {
             self.position.1 -= 0.01;
           }
-          Some(VirtualKey::S) => {
+          input::Key::S => {
             self.position.1 += 0.01;
           }
-          Some(VirtualKey::A) => {
+          input::Key::A => {
             self.position.0 -= 0.01;
           }
-          Some(VirtualKey::D) => {
+          input::Key::D => {
             self.position.0 += 0.01;
           }
           _ => {}
@@ -0,0 +1,586 @@
+//! Asset loading. The `AssetServer` loads assets from disk on worker threads
+//! and immediately returns typed handles to them, which can be used to check
+//! on the state of the load and access the asset once it has loaded. Assets
+//! are deduplicated by their path & type, and can optionally be reloaded when
+//! their files change. Assets are read through a `Vfs`, so they can be
+//! loaded from mounted directories or packs.
+
+pub mod pack;
+pub mod vfs;
+
+use std::{
+  any::{
+    Any,
+    TypeId,
+  },
+  collections::HashMap,
+  fmt,
+  hash::{
+    Hash,
+    Hasher,
+  },
+  marker::PhantomData,
+  path::{
+    Path,
+    PathBuf,
+  },
+  sync::{
+    atomic::{
+      AtomicBool,
+      Ordering,
+    },
+    mpsc,
+    Arc,
+    Condvar,
+    Mutex,
+  },
+  thread,
+  time::{
+    Duration,
+    SystemTime,
+  },
+};
+
+use self::vfs::{
+  normalize_path,
+  Vfs,
+};
+use crate::render::{
+  mesh::{
+    Mesh,
+    MeshBuilder,
+  },
+  shader::{
+    ShaderKind,
+    VirtualShader,
+  },
+};
+
+// --------------------------------- ASSET -------------------------------------
+
+/// A type that can be loaded by the asset server from the file at a virtual
+/// path.
+pub trait Asset: Sized + Send + Sync + 'static {
+  fn load(path: &Path, files: &Vfs) -> Result<Self, String>;
+}
+
+/// Meshes are loaded from OBJ files along with their materials, which are
+/// read from the same filesystem.
+impl Asset for Mesh {
+  fn load(path: &Path, files: &Vfs) -> Result<Self, String> {
+    return MeshBuilder::new().build_from_obj_bytes_with_materials(
+      &files.read(path)?,
+      path.parent().unwrap_or(Path::new("")),
+      |path| files.read(path),
+    );
+  }
+}
+
+/// Shaders are loaded from GLSL source files, whose kind is determined by
+/// their extension (vert, frag, or comp) and whose entry point is main.
+impl Asset for VirtualShader {
+  fn load(path: &Path, files: &Vfs) -> Result<Self, String> {
+    let kind = match path.extension().and_then(|extension| extension.to_str()) {
+      Some("vert") => ShaderKind::Vertex,
+      Some("frag") => ShaderKind::Fragment,
+      Some("comp") => ShaderKind::Compute,
+      _ => {
+        return Err(format!(
+          "{} doesn't have a shader extension (vert, frag, or comp).",
+          path.display()
+        ))
+      }
+    };
+
+    return Ok(VirtualShader::Source {
+      source: files.read_to_string(path)?,
+      kind,
+      name: path
+        .file_stem()
+        .map_or(String::new(), |name| name.to_string_lossy().to_string()),
+      entry_point: String::from("main"),
+    });
+  }
+}
+
+/// Text files.
+impl Asset for String {
+  fn load(path: &Path, files: &Vfs) -> Result<Self, String> {
+    return files.read_to_string(path);
+  }
+}
+
+/// The raw bytes of files, such as the images of textures or the samples of
+/// sounds, that are decoded by the subsystems that use them.
+impl Asset for Vec<u8> {
+  fn load(path: &Path, files: &Vfs) -> Result<Self, String> {
+    return files.read(path);
+  }
+}
+
+// --------------------------------- HANDLE ------------------------------------
+
+/// A handle to an asset of type A, which is cheap to copy and remains valid
+/// for the lifetime of the asset server that created it.
+pub struct Handle<A> {
+  id: usize,
+  asset: PhantomData<fn() -> A>,
+}
+
+impl<A> Clone for Handle<A> {
+  fn clone(&self) -> Self {
+    *self
+  }
+}
+
+impl<A> Copy for Handle<A> {}
+
+impl<A> PartialEq for Handle<A> {
+  fn eq(&self, other: &Self) -> bool {
+    return self.id == other.id;
+  }
+}
+
+impl<A> Eq for Handle<A> {}
+
+impl<A> Hash for Handle<A> {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    self.id.hash(state);
+  }
+}
+
+impl<A> fmt::Debug for Handle<A> {
+  fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+    return formatter.debug_tuple("Handle").field(&self.id).finish();
+  }
+}
+
+/// The state of an asset's latest load.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LoadState {
+  Loading,
+  Loaded,
+  Failed(String),
+}
+
+// --------------------------------- SERVER ------------------------------------
+
+type LoadedAsset = Arc<dyn Any + Send + Sync>;
+type Loader =
+  Arc<dyn Fn(&Path, &Vfs) -> Result<LoadedAsset, String> + Send + Sync>;
+
+struct Entry {
+  path: PathBuf,
+  loader: Loader,
+  state: LoadState,
+  asset: Option<LoadedAsset>,
+  version: u64,
+  modified: Option<SystemTime>,
+}
+
+struct Assets {
+  entries: Vec<Entry>,
+  lookup: HashMap<(String, TypeId), usize>,
+}
+
+struct Shared {
+  files: Vfs,
+  assets: Mutex<Assets>,
+  finished: Condvar,
+  running: AtomicBool,
+}
+
+impl Shared {
+  fn assets(&self) -> std::sync::MutexGuard<'_, Assets> {
+    return self
+      .assets
+      .lock()
+      .expect("The asset server lock was poisoned.");
+  }
+}
+
+/// Loads the asset of the entry on a worker thread.
+fn load_entry(shared: &Shared, id: usize) {
+  let (path, loader) = {
+    let assets = shared.assets();
+    let entry = &assets.entries[id];
+    (entry.path.clone(), entry.loader.clone())
+  };
+
+  let modified = shared.files.modified(&path);
+  let result = loader(&path, &shared.files);
+
+  let mut assets = shared.assets();
+  let entry = &mut assets.entries[id];
+  entry.modified = modified;
+  match result {
+    Ok(asset) => {
+      logging::debug!("Loaded the asset {}", path.display());
+      entry.asset = Some(asset);
+      entry.state = LoadState::Loaded;
+      entry.version += 1;
+    }
+    Err(error) => {
+      logging::error!("Failed to load the asset {}: {}", path.display(), error);
+      entry.state = LoadState::Failed(error);
+    }
+  }
+  shared.finished.notify_all();
+}
+
+/// Queues the assets whose files have changed since they were last loaded.
+fn queue_changed(shared: &Shared, jobs: &mpsc::Sender<usize>) {
+  let mut assets = shared.assets();
+  for (id, entry) in assets.entries.iter_mut().enumerate() {
+    if entry.state == LoadState::Loading {
+      continue;
+    }
+
+    let modified = shared.files.modified(&entry.path);
+    if modified.is_some() && modified != entry.modified {
+      logging::info!("Reloading the changed asset {}", entry.path.display());
+      entry.state = LoadState::Loading;
+      entry.modified = modified;
+      if jobs.send(id).is_err() {
+        return;
+      }
+    }
+  }
+}
+
+/// Builds an asset server.
+pub struct AssetServerBuilder {
+  files: Vfs,
+  workers: usize,
+  hot_reload: Option<Duration>,
+}
+
+impl AssetServerBuilder {
+  /// Creates a builder for an asset server that loads assets relative to the
+  /// working directory with a worker thread per core, up to four.
+  pub fn new() -> Self {
+    let workers = thread::available_parallelism()
+      .map_or(1, |workers| workers.get())
+      .min(4);
+    return Self {
+      files: Vfs::new().with_directory("", "."),
+      workers,
+      hot_reload: None,
+    };
+  }
+
+  /// Loads assets from the directory instead of the working directory.
+  pub fn with_root(mut self, root: impl AsRef<Path>) -> Self {
+    self.files = Vfs::new().with_directory("", root);
+    return self;
+  }
+
+  /// Loads assets from the filesystem instead of the working directory.
+  pub fn with_files(mut self, files: Vfs) -> Self {
+    self.files = files;
+    return self;
+  }
+
+  /// The number of threads that load assets, which is at least one.
+  pub fn with_workers(mut self, workers: usize) -> Self {
+    self.workers = workers.max(1);
+    return self;
+  }
+
+  /// Checks for assets whose files have changed at the given interval and
+  /// reloads them.
+  pub fn with_hot_reload(mut self, interval: Duration) -> Self {
+    self.hot_reload = Some(interval);
+    return self;
+  }
+
+  pub fn build(self) -> AssetServer {
+    let shared = Arc::new(Shared {
+      files: self.files,
+      assets: Mutex::new(Assets {
+        entries: vec![],
+        lookup: HashMap::new(),
+      }),
+      finished: Condvar::new(),
+      running: AtomicBool::new(true),
+    });
+
+    let (jobs, receiver) = mpsc::channel::<usize>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers = (0..self.workers)
+      .map(|index| {
+        let shared = shared.clone();
+        let receiver = receiver.clone();
+        return thread::Builder::new()
+          .name(format!("lambda-assets-{}", index))
+          .spawn(move || loop {
+            let job = receiver
+              .lock()
+              .expect("The asset job lock was poisoned.")
+              .recv();
+            match job {
+              Ok(id) => load_entry(&shared, id),
+              Err(_) => return,
+            }
+          })
+          .expect("Failed to spawn an asset worker thread.");
+      })
+      .collect();
+
+    let watcher = self.hot_reload.map(|interval| {
+      let shared = shared.clone();
+      let jobs = jobs.clone();
+      return thread::Builder::new()
+        .name(String::from("lambda-assets-watcher"))
+        .spawn(move || {
+          // Sleeps in short steps so that dropping the server isn't delayed
+          // by long intervals.
+          let step = interval.min(Duration::from_millis(50));
+          let mut waited = Duration::ZERO;
+          while shared.running.load(Ordering::Acquire) {
+            thread::sleep(step);
+            waited += step;
+            if waited >= interval {
+              waited = Duration::ZERO;
+              queue_changed(&shared, &jobs);
+            }
+          }
+        })
+        .expect("Failed to spawn the asset watcher thread.");
+    });
+
+    return AssetServer {
+      shared,
+      jobs: Some(jobs),
+      workers,
+      watcher,
+    };
+  }
+}
+
+/// Loads assets on worker threads. Dropping the server waits for the assets
+/// that are being loaded to finish.
+pub struct AssetServer {
+  shared: Arc<Shared>,
+  jobs: Option<mpsc::Sender<usize>>,
+  workers: Vec<thread::JoinHandle<()>>,
+  watcher: Option<thread::JoinHandle<()>>,
+}
+
+impl AssetServer {
+  /// Starts loading the asset at the path with its `Asset` implementation and
+  /// returns a handle to it. Loading the same path as the same type again
+  /// returns the handle of the first load.
+  pub fn load<A: Asset>(&self, path: impl AsRef<Path>) -> Handle<A> {
+    return self.load_with(path, A::load);
+  }
+
+  /// Starts loading the asset at the path with the given loader, such as to
+  /// configure how a mesh is built. Loading the same path as the same type
+  /// again returns the handle of the first load, even if it used a different
+  /// loader.
+  pub fn load_with<A: Asset>(
+    &self,
+    path: impl AsRef<Path>,
+    loader: impl Fn(&Path, &Vfs) -> Result<A, String> + Send + Sync + 'static,
+  ) -> Handle<A> {
+    let path = normalize_path(path.as_ref());
+    let key = (path.clone(), TypeId::of::<A>());
+
+    let mut assets = self.shared.assets();
+    if let Some(id) = assets.lookup.get(&key) {
+      return Handle {
+        id: *id,
+        asset: PhantomData,
+      };
+    }
+
+    let id = assets.entries.len();
+    assets.entries.push(Entry {
+      path: PathBuf::from(path),
+      loader: Arc::new(move |path: &Path, files: &Vfs| {
+        return loader(path, files).map(|asset| Arc::new(asset) as LoadedAsset);
+      }),
+      state: LoadState::Loading,
+      asset: None,
+      version: 0,
+      modified: None,
+    });
+    assets.lookup.insert(key, id);
+    drop(assets);
+
+    self.queue(id);
+    return Handle {
+      id,
+      asset: PhantomData,
+    };
+  }
+
+  fn queue(&self, id: usize) {
+    self
+      .jobs
+      .as_ref()
+      .expect("The asset server has shut down.")
+      .send(id)
+      .expect("The asset worker threads have stopped.");
+  }
+
+  /// Reloads the asset, even if its file hasn't changed. The previous version
+  /// of the asset remains available until the reload finishes.
+  pub fn reload<A>(&self, handle: &Handle<A>) {
+    let mut assets = self.shared.assets();
+    let entry = &mut assets.entries[handle.id];
+    if entry.state == LoadState::Loading {
+      return;
+    }
+    entry.state = LoadState::Loading;
+    drop(assets);
+    self.queue(handle.id);
+  }
+
+  /// The state of the asset's latest load.
+  pub fn state<A>(&self, handle: &Handle<A>) -> LoadState {
+    return self.shared.assets().entries[handle.id].state.clone();
+  }
+
+  /// The latest version of the asset that loaded successfully, if any.
+  pub fn get<A: Asset>(&self, handle: &Handle<A>) -> Option<Arc<A>> {
+    return self.shared.assets().entries[handle.id]
+      .asset
+      .clone()
+      .and_then(|asset| asset.downcast().ok());
+  }
+
+  /// The number of times the asset has loaded successfully, which changes
+  /// whenever it's reloaded so that resources created from it can be
+  /// recreated.
+  pub fn version<A>(&self, handle: &Handle<A>) -> u64 {
+    return self.shared.assets().entries[handle.id].version;
+  }
+
+  /// The filesystem that assets are loaded from.
+  pub fn files(&self) -> &Vfs {
+    return &self.shared.files;
+  }
+
+  /// The virtual path that the asset is loaded from.
+  pub fn path<A>(&self, handle: &Handle<A>) -> PathBuf {
+    return self.shared.assets().entries[handle.id].path.clone();
+  }
+
+  /// Blocks until the asset has finished loading and returns the state of the
+  /// load.
+  pub fn wait<A>(&self, handle: &Handle<A>) -> LoadState {
+    let mut assets = self.shared.assets();
+    while assets.entries[handle.id].state == LoadState::Loading {
+      assets = self
+        .shared
+        .finished
+        .wait(assets)
+        .expect("The asset server lock was poisoned.");
+    }
+    return assets.entries[handle.id].state.clone();
+  }
+}
+
+impl Drop for AssetServer {
+  fn drop(&mut self) {
+    self.shared.running.store(false, Ordering::Release);
+    if let Some(watcher) = self.watcher.take() {
+      watcher.join().ok();
+    }
+
+    // Workers stop once every sender of the job queue has been dropped.
+    self.jobs.take();
+    for worker in self.workers.drain(..) {
+      worker.join().ok();
+    }
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    path::PathBuf,
+    time::{
+      Duration,
+      Instant,
+    },
+  };
+
+  use super::{
+    AssetServerBuilder,
+    LoadState,
+  };
+
+  fn test_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!(
+      "lambda-assets-{}-{}",
+      name,
+      std::process::id()
+    ));
+    std::fs::create_dir_all(&directory).unwrap();
+    return directory;
+  }
+
+  #[test]
+  fn assets_load_on_workers_and_are_deduplicated() {
+    let directory = test_directory("load");
+    std::fs::write(directory.join("hello.txt"), "hello").unwrap();
+    let server = AssetServerBuilder::new()
+      .with_root(&directory)
+      .with_workers(2)
+      .build();
+
+    let text = server.load::<String>("hello.txt");
+    let bytes = server.load::<Vec<u8>>("hello.txt");
+    assert_eq!(server.load::<String>("hello.txt"), text);
+    assert_eq!(server.wait(&text), LoadState::Loaded);
+    assert_eq!(server.wait(&bytes), LoadState::Loaded);
+    assert_eq!(server.get(&text).unwrap().as_str(), "hello");
+    assert_eq!(server.get(&bytes).unwrap().as_slice(), b"hello");
+    assert_eq!(server.version(&text), 1);
+
+    let missing = server.load::<String>("missing.txt");
+    assert!(matches!(server.wait(&missing), LoadState::Failed(_)));
+    assert!(server.get(&missing).is_none());
+
+    let custom =
+      server.load_with("./hello.txt", |path, _| Ok(path.display().to_string()));
+    assert_eq!(custom, text);
+
+    std::fs::remove_dir_all(directory).ok();
+  }
+
+  #[test]
+  fn changed_assets_are_reloaded() {
+    let directory = test_directory("reload");
+    let path = directory.join("reload.txt");
+    std::fs::write(&path, "first").unwrap();
+    let server = AssetServerBuilder::new()
+      .with_root(&directory)
+      .with_hot_reload(Duration::from_millis(10))
+      .build();
+
+    let text = server.load::<String>("reload.txt");
+    assert_eq!(server.wait(&text), LoadState::Loaded);
+
+    // Waits until the file's modification time is guaranteed to change.
+    std::thread::sleep(Duration::from_millis(20));
+    std::fs::write(&path, "second").unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while server.version(&text) < 2 && Instant::now() < deadline {
+      std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(server.get(&text).unwrap().as_str(), "second");
+
+    server.reload(&text);
+    assert_eq!(server.wait(&text), LoadState::Loaded);
+    assert_eq!(server.version(&text), 3);
+
+    std::fs::remove_dir_all(directory).ok();
+  }
+}
@@ -0,0 +1,392 @@
+//! The asset pack format, which stores many files within a single compressed
+//! archive that can be mounted into a `Vfs`.
+//!
+//! Packs start with the magic bytes `LPAK`, the format version, and the
+//! number of files as little endian u32s. A table of contents follows with an
+//! entry per file containing the hash of its path, the offset & stored size
+//! of its data, its decompressed size, whether it's compressed, and its path.
+//! The data of every file follows the table of contents.
+
+use std::{
+  collections::HashMap,
+  fs::File,
+  io::{
+    Read,
+    Seek,
+    SeekFrom,
+  },
+  path::{
+    Path,
+    PathBuf,
+  },
+  sync::Mutex,
+  time::SystemTime,
+};
+
+use lambda_platform::miniz;
+
+use super::vfs::{
+  normalize_path,
+  Mount,
+};
+
+const MAGIC: &[u8; 4] = b"LPAK";
+const VERSION: u32 = 1;
+const HEADER_SIZE: u64 = 12;
+/// The hash, offset, stored size, size, compression flag, and path length of
+/// an entry within the table of contents.
+const ENTRY_SIZE: u64 = 8 * 4 + 1 + 2;
+
+/// Hashes a normalized path with 64 bit FNV-1a, which is how files are looked
+/// up within packs.
+pub fn hash_path(path: &str) -> u64 {
+  let mut hash: u64 = 0xcbf29ce484222325;
+  for byte in path.bytes() {
+    hash ^= byte as u64;
+    hash = hash.wrapping_mul(0x100000001b3);
+  }
+  return hash;
+}
+
+// --------------------------------- BUILDER -----------------------------------
+
+/// Builds an asset pack from files in memory or on disk.
+pub struct PackBuilder {
+  files: Vec<(String, Vec<u8>)>,
+  level: u8,
+}
+
+impl PackBuilder {
+  pub fn new() -> Self {
+    return Self {
+      files: vec![],
+      level: miniz::DEFAULT_LEVEL,
+    };
+  }
+
+  /// The DEFLATE compression level between 0 (no compression) and 10 (the
+  /// smallest pack).
+  pub fn with_compression_level(mut self, level: u8) -> Self {
+    self.level = level.min(10);
+    return self;
+  }
+
+  /// Adds a file to the pack at the given path, replacing any file that was
+  /// already added at the same path.
+  pub fn with_file(mut self, path: &str, bytes: Vec<u8>) -> Self {
+    let path = normalize_path(Path::new(path));
+    self.files.retain(|(existing, _)| *existing != path);
+    self.files.push((path, bytes));
+    return self;
+  }
+
+  /// Adds every file within the directory and its subdirectories, with paths
+  /// relative to the directory.
+  pub fn with_directory(mut self, directory: &Path) -> Result<Self, String> {
+    let mut directories = vec![directory.to_path_buf()];
+    let mut paths = vec![];
+    while let Some(current) = directories.pop() {
+      let entries = std::fs::read_dir(&current).map_err(|error| {
+        format!("Failed to read {}: {}", current.display(), error)
+      })?;
+      for entry in entries {
+        let path = entry
+          .map_err(|error| {
+            format!("Failed to read {}: {}", current.display(), error)
+          })?
+          .path();
+        match path.is_dir() {
+          true => directories.push(path),
+          false => paths.push(path),
+        }
+      }
+    }
+
+    // Files are added in order of their paths so that packs are reproducible.
+    paths.sort();
+    for path in paths {
+      let bytes = std::fs::read(&path).map_err(|error| {
+        format!("Failed to read {}: {}", path.display(), error)
+      })?;
+      let relative = path
+        .strip_prefix(directory)
+        .expect("Files are within the directory being packed.");
+      self = self.with_file(&normalize_path(relative), bytes);
+    }
+    return Ok(self);
+  }
+
+  /// The normalized paths of the files that have been added.
+  pub fn paths(&self) -> Vec<&str> {
+    return self.files.iter().map(|(path, _)| path.as_str()).collect();
+  }
+
+  /// Encodes the files into a pack. Fails if two paths share the same hash.
+  pub fn build(&self) -> Result<Vec<u8>, String> {
+    let mut hashes = HashMap::new();
+    for (path, _) in self.files.iter() {
+      if let Some(other) = hashes.insert(hash_path(path), path) {
+        return Err(format!("{} and {} have the same hash.", path, other));
+      }
+    }
+
+    // Files are only stored compressed when compressing them saves space.
+    let stored = self
+      .files
+      .iter()
+      .map(|(_, bytes)| {
+        let compressed = miniz::compress(bytes, self.level);
+        return match compressed.len() < bytes.len() {
+          true => (compressed, true),
+          false => (bytes.clone(), false),
+        };
+      })
+      .collect::<Vec<_>>();
+
+    let table_size = self.files.iter().fold(0, |size, (path, _)| {
+      return size + ENTRY_SIZE + path.len() as u64;
+    });
+
+    let mut pack = vec![];
+    pack.extend_from_slice(MAGIC);
+    pack.extend_from_slice(&VERSION.to_le_bytes());
+    pack.extend_from_slice(&(self.files.len() as u32).to_le_bytes());
+
+    let mut offset = HEADER_SIZE + table_size;
+    for ((path, bytes), (data, compressed)) in self.files.iter().zip(&stored) {
+      let path_length = u16::try_from(path.len())
+        .map_err(|_| format!("The path {} is too long to be packed.", path))?;
+      pack.extend_from_slice(&hash_path(path).to_le_bytes());
+      pack.extend_from_slice(&offset.to_le_bytes());
+      pack.extend_from_slice(&(data.len() as u64).to_le_bytes());
+      pack.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+      pack.push(*compressed as u8);
+      pack.extend_from_slice(&path_length.to_le_bytes());
+      pack.extend_from_slice(path.as_bytes());
+      offset += data.len() as u64;
+    }
+
+    for (data, _) in stored.iter() {
+      pack.extend_from_slice(data);
+    }
+    return Ok(pack);
+  }
+
+  /// Encodes the files into a pack and writes it to the path.
+  pub fn write(&self, path: &Path) -> Result<(), String> {
+    return std::fs::write(path, self.build()?).map_err(|error| {
+      format!("Failed to write the pack {}: {}", path.display(), error)
+    });
+  }
+}
+
+// ---------------------------------- PACK -------------------------------------
+
+struct PackEntry {
+  path: String,
+  offset: u64,
+  stored_size: u64,
+  size: u64,
+  compressed: bool,
+}
+
+/// A pack opened from disk, whose files are read as they're requested.
+pub struct Pack {
+  path: PathBuf,
+  file: Mutex<File>,
+  entries: HashMap<u64, PackEntry>,
+}
+
+fn read_bytes<const N: usize>(
+  input: &mut impl Read,
+  path: &Path,
+) -> Result<[u8; N], String> {
+  let mut bytes = [0; N];
+  input.read_exact(&mut bytes).map_err(|error| {
+    format!("Failed to read the pack {}: {}", path.display(), error)
+  })?;
+  return Ok(bytes);
+}
+
+impl Pack {
+  /// Opens the pack at the path and reads its table of contents.
+  pub fn open(path: &Path) -> Result<Self, String> {
+    let mut file = File::open(path).map_err(|error| {
+      format!("Failed to open the pack {}: {}", path.display(), error)
+    })?;
+
+    if &read_bytes::<4>(&mut file, path)? != MAGIC {
+      return Err(format!("{} isn't an asset pack.", path.display()));
+    }
+    let version = u32::from_le_bytes(read_bytes(&mut file, path)?);
+    if version != VERSION {
+      return Err(format!(
+        "{} uses version {} of the pack format, but only version {} is \
+         supported.",
+        path.display(),
+        version,
+        VERSION
+      ));
+    }
+
+    let count = u32::from_le_bytes(read_bytes(&mut file, path)?);
+    let mut entries = HashMap::with_capacity(count as usize);
+    for _ in 0..count {
+      let hash = u64::from_le_bytes(read_bytes(&mut file, path)?);
+      let offset = u64::from_le_bytes(read_bytes(&mut file, path)?);
+      let stored_size = u64::from_le_bytes(read_bytes(&mut file, path)?);
+      let size = u64::from_le_bytes(read_bytes(&mut file, path)?);
+      let [compressed] = read_bytes::<1>(&mut file, path)?;
+      let path_length = u16::from_le_bytes(read_bytes(&mut file, path)?);
+
+      let mut entry_path = vec![0; path_length as usize];
+      file.read_exact(&mut entry_path).map_err(|error| {
+        format!("Failed to read the pack {}: {}", path.display(), error)
+      })?;
+      let entry_path = String::from_utf8(entry_path).map_err(|_| {
+        format!("{} contains a path that isn't UTF-8.", path.display())
+      })?;
+
+      entries.insert(
+        hash,
+        PackEntry {
+          path: entry_path,
+          offset,
+          stored_size,
+          size,
+          compressed: compressed != 0,
+        },
+      );
+    }
+
+    return Ok(Self {
+      path: path.to_path_buf(),
+      file: Mutex::new(file),
+      entries,
+    });
+  }
+
+  /// The paths of every file within the pack.
+  pub fn paths(&self) -> Vec<&str> {
+    let mut paths = self
+      .entries
+      .values()
+      .map(|entry| entry.path.as_str())
+      .collect::<Vec<_>>();
+    paths.sort();
+    return paths;
+  }
+
+  /// Reads and decompresses the file at the normalized path, if it's in the
+  /// pack.
+  pub fn read_file(&self, path: &str) -> Option<Result<Vec<u8>, String>> {
+    let entry = self
+      .entries
+      .get(&hash_path(path))
+      .filter(|entry| entry.path == path)?;
+
+    let mut stored = vec![0; entry.stored_size as usize];
+    let result = {
+      let mut file = self.file.lock().expect("The pack lock was poisoned.");
+      file
+        .seek(SeekFrom::Start(entry.offset))
+        .and_then(|_| file.read_exact(&mut stored))
+    };
+    if let Err(error) = result {
+      return Some(Err(format!(
+        "Failed to read {} from the pack {}: {}",
+        path,
+        self.path.display(),
+        error
+      )));
+    }
+
+    return match entry.compressed {
+      true => Some(miniz::decompress(&stored, entry.size as usize)),
+      false => Some(Ok(stored)),
+    };
+  }
+}
+
+impl Mount for Pack {
+  fn contains(&self, path: &str) -> bool {
+    return self
+      .entries
+      .get(&hash_path(path))
+      .is_some_and(|entry| entry.path == path);
+  }
+
+  fn read(&self, path: &str) -> Option<Result<Vec<u8>, String>> {
+    return self.read_file(path);
+  }
+
+  /// Packs are shipped rather than edited, so their files are never reloaded.
+  fn modified(&self, _path: &str) -> Option<SystemTime> {
+    return None;
+  }
+
+  fn source(&self) -> String {
+    return self.path.display().to_string();
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    hash_path,
+    Pack,
+    PackBuilder,
+  };
+
+  #[test]
+  fn packs_round_trip() {
+    let directory =
+      std::env::temp_dir().join(format!("lambda-pack-{}", std::process::id()));
+    std::fs::create_dir_all(directory.join("source/shaders")).unwrap();
+    std::fs::write(directory.join("source/shaders/mesh.vert"), "void main")
+      .unwrap();
+    let repeated = "lambda ".repeat(100);
+    std::fs::write(directory.join("source/text.txt"), &repeated).unwrap();
+
+    let builder = PackBuilder::new()
+      .with_directory(&directory.join("source"))
+      .unwrap()
+      .with_file("./data/../raw.bin", vec![1, 2, 3]);
+    assert_eq!(
+      builder.paths(),
+      vec!["shaders/mesh.vert", "text.txt", "raw.bin"]
+    );
+
+    // Repetitive files are compressed.
+    let bytes = builder.build().unwrap();
+    assert!(bytes.len() < repeated.len());
+
+    let path = directory.join("assets.pack");
+    builder.write(&path).unwrap();
+    let pack = Pack::open(&path).unwrap();
+    assert_eq!(
+      pack.paths(),
+      vec!["raw.bin", "shaders/mesh.vert", "text.txt"]
+    );
+    assert_eq!(
+      pack.read_file("text.txt").unwrap().unwrap(),
+      repeated.as_bytes()
+    );
+    assert_eq!(pack.read_file("raw.bin").unwrap().unwrap(), vec![1, 2, 3]);
+    assert!(pack.read_file("missing.txt").is_none());
+
+    std::fs::write(&path, b"LPAK\x09\x00\x00\x00").unwrap();
+    assert!(Pack::open(&path).is_err());
+
+    std::fs::remove_dir_all(directory).ok();
+  }
+
+  #[test]
+  fn path_hashes() {
+    assert_eq!(hash_path(""), 0xcbf29ce484222325);
+    assert_eq!(hash_path("a"), 0xaf63dc4c8601ec8c);
+    assert_ne!(hash_path("a/b"), hash_path("b/a"));
+  }
+}
@@ -0,0 +1,245 @@
+//! A virtual filesystem that assets are loaded from. Directories & packs are
+//! mounted at virtual paths, so assets are found through the same paths no
+//! matter the working directory or whether they were shipped in a pack.
+
+use std::{
+  path::{
+    Component,
+    Path,
+    PathBuf,
+  },
+  time::SystemTime,
+};
+
+use super::pack::Pack;
+
+/// Normalizes a path into a virtual path, whose components are separated by
+/// `/` and never contain `.` or `..`. Roots & prefixes are removed, so every
+/// virtual path is relative to the root of the filesystem.
+pub fn normalize_path(path: &Path) -> String {
+  let mut components: Vec<String> = vec![];
+  for component in path.components() {
+    match component {
+      Component::Normal(name) => {
+        components.push(name.to_string_lossy().replace('\\', "/"))
+      }
+      Component::ParentDir => {
+        components.pop();
+      }
+      Component::CurDir | Component::RootDir | Component::Prefix(_) => {}
+    }
+  }
+  return components.join("/");
+}
+
+/// A source of files that can be mounted into a `Vfs`. Paths given to mounts
+/// are normalized & relative to where they were mounted.
+pub trait Mount: Send + Sync {
+  /// Whether or not the mount contains the file at the path.
+  fn contains(&self, path: &str) -> bool;
+
+  /// Reads the file at the path, or returns None if the mount doesn't contain
+  /// it.
+  fn read(&self, path: &str) -> Option<Result<Vec<u8>, String>>;
+
+  /// When the file at the path was last modified, if it's known.
+  fn modified(&self, path: &str) -> Option<SystemTime>;
+
+  /// Describes where the files of the mount come from, such as the
+  /// directory or pack that was mounted.
+  fn source(&self) -> String {
+    return "a custom mount".to_string();
+  }
+}
+
+/// Mounts a directory on disk.
+pub struct DirectoryMount {
+  root: PathBuf,
+}
+
+impl DirectoryMount {
+  pub fn new(root: impl AsRef<Path>) -> Self {
+    return Self {
+      root: root.as_ref().to_path_buf(),
+    };
+  }
+}
+
+impl Mount for DirectoryMount {
+  fn contains(&self, path: &str) -> bool {
+    return self.root.join(path).is_file();
+  }
+
+  fn read(&self, path: &str) -> Option<Result<Vec<u8>, String>> {
+    let path = self.root.join(path);
+    if !path.is_file() {
+      return None;
+    }
+    return Some(std::fs::read(&path).map_err(|error| {
+      format!("Failed to read {}: {}", path.display(), error)
+    }));
+  }
+
+  fn modified(&self, path: &str) -> Option<SystemTime> {
+    return std::fs::metadata(self.root.join(path))
+      .and_then(|metadata| metadata.modified())
+      .ok();
+  }
+
+  fn source(&self) -> String {
+    return self.root.display().to_string();
+  }
+}
+
+/// Mounts that files are read from, where files in later mounts take
+/// precedence over files at the same path in earlier mounts.
+pub struct Vfs {
+  mounts: Vec<(String, Box<dyn Mount>)>,
+}
+
+impl Vfs {
+  /// Creates a filesystem without any mounts.
+  pub fn new() -> Self {
+    return Self { mounts: vec![] };
+  }
+
+  /// Mounts the directory at the virtual path.
+  pub fn with_directory(self, at: &str, directory: impl AsRef<Path>) -> Self {
+    return self.with_mount(at, DirectoryMount::new(directory));
+  }
+
+  /// Opens the pack and mounts it at the virtual path.
+  pub fn with_pack(
+    self,
+    at: &str,
+    pack: impl AsRef<Path>,
+  ) -> Result<Self, String> {
+    return Ok(self.with_mount(at, Pack::open(pack.as_ref())?));
+  }
+
+  /// Mounts a source of files at the virtual path.
+  pub fn with_mount(mut self, at: &str, mount: impl Mount + 'static) -> Self {
+    self
+      .mounts
+      .push((normalize_path(Path::new(at)), Box::new(mount)));
+    return self;
+  }
+
+  /// Every mount as its virtual path followed by its source, in the order
+  /// they were mounted, such as `/textures (assets/textures)`.
+  pub fn mount_points(&self) -> Vec<String> {
+    return self
+      .mounts
+      .iter()
+      .map(|(at, mount)| format!("/{} ({})", at, mount.source()))
+      .collect();
+  }
+
+  /// The mount with the highest precedence that contains the file at the
+  /// normalized path, along with the path relative to the mount.
+  fn find<'a>(&'a self, path: &'a str) -> Option<(&'a dyn Mount, &'a str)> {
+    return self.mounts.iter().rev().find_map(|(at, mount)| {
+      let relative = match at.is_empty() {
+        true => Some(path),
+        false => path
+          .strip_prefix(at.as_str())
+          .and_then(|relative| relative.strip_prefix('/')),
+      }?;
+      return match mount.contains(relative) {
+        true => Some((mount.as_ref(), relative)),
+        false => None,
+      };
+    });
+  }
+
+  /// Reads the file at the virtual path from the mount with the highest
+  /// precedence that contains it.
+  pub fn read(&self, path: impl AsRef<Path>) -> Result<Vec<u8>, String> {
+    let path = normalize_path(path.as_ref());
+    return match self.find(&path) {
+      Some((mount, relative)) => mount.read(relative).unwrap_or_else(|| {
+        return Err(format!("{} was removed while being read.", path));
+      }),
+      None => Err(format!("{} wasn't found in any mount.", path)),
+    };
+  }
+
+  /// Reads the file at the virtual path as UTF-8 text.
+  pub fn read_to_string(
+    &self,
+    path: impl AsRef<Path>,
+  ) -> Result<String, String> {
+    let path = path.as_ref();
+    return String::from_utf8(self.read(path)?)
+      .map_err(|_| format!("{} isn't valid UTF-8.", path.display()));
+  }
+
+  /// Whether or not any mount contains the file at the virtual path.
+  pub fn exists(&self, path: impl AsRef<Path>) -> bool {
+    let path = normalize_path(path.as_ref());
+    return self.find(&path).is_some();
+  }
+
+  /// When the file at the virtual path was last modified, if it's known.
+  pub fn modified(&self, path: impl AsRef<Path>) -> Option<SystemTime> {
+    let path = normalize_path(path.as_ref());
+    return self
+      .find(&path)
+      .and_then(|(mount, relative)| mount.modified(relative));
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use super::{
+    normalize_path,
+    Vfs,
+  };
+  use crate::assets::pack::PackBuilder;
+
+  #[test]
+  fn paths_are_normalized() {
+    assert_eq!(normalize_path(Path::new("./a/b/../c.txt")), "a/c.txt");
+    assert_eq!(normalize_path(Path::new("/a//b/")), "a/b");
+    assert_eq!(normalize_path(Path::new("../../a")), "a");
+  }
+
+  #[test]
+  fn mounts_are_searched_by_precedence() {
+    let directory =
+      std::env::temp_dir().join(format!("lambda-vfs-{}", std::process::id()));
+    std::fs::create_dir_all(directory.join("loose")).unwrap();
+    std::fs::write(directory.join("loose/shared.txt"), "loose").unwrap();
+    std::fs::write(directory.join("loose/only.txt"), "only").unwrap();
+    PackBuilder::new()
+      .with_file("shared.txt", b"packed".to_vec())
+      .write(&directory.join("assets.pack"))
+      .unwrap();
+
+    let vfs = Vfs::new()
+      .with_directory("assets", directory.join("loose"))
+      .with_pack("assets", directory.join("assets.pack"))
+      .unwrap();
+
+    assert_eq!(vfs.read_to_string("assets/shared.txt").unwrap(), "packed");
+    assert_eq!(vfs.read_to_string("./assets/only.txt").unwrap(), "only");
+    assert!(vfs.modified("assets/only.txt").is_some());
+    assert!(vfs.modified("assets/shared.txt").is_none());
+    assert!(vfs.exists("assets/only.txt"));
+    assert!(!vfs.exists("only.txt"));
+    assert!(vfs.read("assets/missing.txt").is_err());
+    assert_eq!(
+      vfs.mount_points(),
+      vec![
+        format!("/assets ({})", directory.join("loose").display()),
+        format!("/assets ({})", directory.join("assets.pack").display()),
+      ]
+    );
+
+    std::fs::remove_dir_all(directory).ok();
+  }
+}
@@ -0,0 +1,8 @@
+//! Reading & writing text on the system clipboard, such as to copy from or
+//! paste into a text field. Clipboard access goes through the platform's
+//! clipboard utility, so it fails with an error when none is installed.
+
+pub use lambda_platform::clipboard::{
+  get_text,
+  set_text,
+};
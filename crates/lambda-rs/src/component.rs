@@ -1,5 +1,15 @@
 use std::{
+  any::Any,
+  cell::{
+    Cell,
+    RefCell,
+  },
   fmt::Debug,
+  panic::{
+    catch_unwind,
+    AssertUnwindSafe,
+  },
+  rc::Rc,
   time::Duration,
 };
 
@@ -42,3 +52,385 @@ where
     render_context: &mut RenderContext,
   ) -> Vec<RenderCommand>;
 }
+
+// ---------------------------- COMPONENT REGISTRY -----------------------------
+
+/// A component stored within the registry alongside the information needed
+/// to order and query it.
+struct RegisteredComponent<R, E>
+where
+  R: Sized + Debug,
+  E: Sized + Debug,
+{
+  name: String,
+  dependencies: Vec<String>,
+  component: Rc<RefCell<dyn Component<R, E>>>,
+  /// The same component as above, kept type erased for downcasting queries.
+  any: Rc<dyn Any>,
+}
+
+/// Storage for the components of a runtime. Components are registered under
+/// unique names and may declare the names of other components that they
+/// depend on. Once the order has been resolved, components are attached,
+/// updated, and rendered after their dependencies and detached before them.
+/// Components with no dependency relationship keep their registration order.
+pub struct ComponentRegistry<R, E>
+where
+  R: Sized + Debug,
+  E: Sized + Debug,
+{
+  components: Vec<RegisteredComponent<R, E>>,
+}
+
+impl<R, E> ComponentRegistry<R, E>
+where
+  R: Sized + Debug,
+  E: Sized + Debug,
+{
+  /// Creates an empty registry.
+  pub fn new() -> Self {
+    return Self { components: vec![] };
+  }
+
+  /// Registers a component under the given name with the names of the
+  /// components it depends on. Returns a shared handle to the component or an
+  /// error if the name is already in use.
+  pub fn register<T: Component<R, E> + 'static>(
+    &mut self,
+    name: &str,
+    dependencies: &[&str],
+    component: T,
+  ) -> Result<Rc<RefCell<T>>, String> {
+    if self.contains(name) {
+      return Err(format!(
+        "A component named {} has already been registered.",
+        name
+      ));
+    }
+
+    let component = Rc::new(RefCell::new(component));
+    self.components.push(RegisteredComponent {
+      name: name.to_string(),
+      dependencies: dependencies.iter().map(|name| name.to_string()).collect(),
+      component: component.clone(),
+      any: component.clone(),
+    });
+    return Ok(component);
+  }
+
+  /// Returns a name for a component that isn't in use yet, appending a
+  /// numerical suffix to the base name when required.
+  pub fn unique_name(&self, base_name: &str) -> String {
+    let mut name = base_name.to_string();
+    let mut suffix = 1;
+    while self.contains(&name) {
+      name = format!("{}#{}", base_name, suffix);
+      suffix += 1;
+    }
+    return name;
+  }
+
+  /// Query a component by name and type. Returns None if no component is
+  /// registered with the name or if it isn't of type T.
+  pub fn get<T: 'static>(&self, name: &str) -> Option<Rc<RefCell<T>>> {
+    return self
+      .components
+      .iter()
+      .find(|registered| registered.name == name)
+      .and_then(|registered| registered.any.clone().downcast().ok());
+  }
+
+  /// Whether or not a component has been registered under the name.
+  pub fn contains(&self, name: &str) -> bool {
+    return self
+      .components
+      .iter()
+      .any(|registered| registered.name == name);
+  }
+
+  /// The number of registered components.
+  pub fn len(&self) -> usize {
+    return self.components.len();
+  }
+
+  /// Whether or not the registry has any components.
+  pub fn is_empty(&self) -> bool {
+    return self.components.is_empty();
+  }
+
+  /// The names of the registered components in their current order.
+  pub fn names(&self) -> Vec<&str> {
+    return self
+      .components
+      .iter()
+      .map(|registered| registered.name.as_str())
+      .collect();
+  }
+
+  /// The registered components and their names in their current order.
+  pub fn named_components(
+    &self,
+  ) -> impl DoubleEndedIterator<Item = (&str, &Rc<RefCell<dyn Component<R, E>>>)>
+  {
+    return self
+      .components
+      .iter()
+      .map(|registered| (registered.name.as_str(), &registered.component));
+  }
+
+  /// Removes the component registered under the name from the registry,
+  /// returning it if it was found.
+  pub fn remove(
+    &mut self,
+    name: &str,
+  ) -> Option<Rc<RefCell<dyn Component<R, E>>>> {
+    let index = self
+      .components
+      .iter()
+      .position(|registered| registered.name == name)?;
+    return Some(self.components.remove(index).component);
+  }
+
+  /// The registered components in their current order.
+  pub fn components(
+    &self,
+  ) -> impl DoubleEndedIterator<Item = &Rc<RefCell<dyn Component<R, E>>>> {
+    return self
+      .components
+      .iter()
+      .map(|registered| &registered.component);
+  }
+
+  /// Orders the components so that every component comes after the
+  /// components it depends on. Fails if a dependency hasn't been registered
+  /// or if the dependencies contain a cycle.
+  pub fn resolve_order(&mut self) -> Result<(), String> {
+    for registered in self.components.iter() {
+      for dependency in registered.dependencies.iter() {
+        if !self.contains(dependency) {
+          return Err(format!(
+            "The component {} depends on {}, which has not been registered.",
+            registered.name, dependency
+          ));
+        }
+      }
+    }
+
+    let mut unresolved: Vec<RegisteredComponent<R, E>> =
+      self.components.drain(..).collect();
+    let mut resolved: Vec<RegisteredComponent<R, E>> = vec![];
+
+    while !unresolved.is_empty() {
+      // Take the earliest registered component whose dependencies have all
+      // been resolved to keep the ordering deterministic.
+      let next = unresolved.iter().position(|candidate| {
+        return candidate.dependencies.iter().all(|dependency| {
+          return resolved
+            .iter()
+            .any(|registered| &registered.name == dependency);
+        });
+      });
+
+      match next {
+        Some(index) => resolved.push(unresolved.remove(index)),
+        None => {
+          let names: Vec<&str> = unresolved
+            .iter()
+            .map(|registered| registered.name.as_str())
+            .collect();
+          let error = format!(
+            "Components have circular dependencies: {}",
+            names.join(", ")
+          );
+          resolved.append(&mut unresolved);
+          self.components = resolved;
+          return Err(error);
+        }
+      }
+    }
+
+    self.components = resolved;
+    return Ok(());
+  }
+}
+
+thread_local! {
+  /// The number of callbacks running on the thread whose panics are being
+  /// caught.
+  static CATCHING_PANICS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Whether or not a panic on the current thread would be caught by
+/// `catch_panic`, such as for panic hooks that shouldn't treat the panic as a
+/// crash.
+pub fn is_catching_panic() -> bool {
+  return CATCHING_PANICS.with(|catching| catching.get() > 0);
+}
+
+/// Runs a callback and catches any panic that occurs within it. The thread is
+/// marked as catching panics while the callback runs, so that panic hooks can
+/// tell panics that are recovered from apart from crashes.
+pub fn catch_panic<T>(
+  callback: impl FnOnce() -> T,
+) -> Result<T, Box<dyn Any + Send>> {
+  CATCHING_PANICS.with(|catching| catching.set(catching.get() + 1));
+  let result = catch_unwind(AssertUnwindSafe(callback));
+  CATCHING_PANICS.with(|catching| catching.set(catching.get() - 1));
+  return result;
+}
+
+/// Runs a component callback and catches any panic that occurs within it,
+/// returning the panic message as an error. This allows runtimes to detach a
+/// misbehaving component instead of taking down the entire application.
+pub fn catch_component_panic<T>(
+  callback: impl FnOnce() -> T,
+) -> Result<T, String> {
+  return catch_panic(callback).map_err(|payload| {
+    return panic_message(payload.as_ref()).unwrap_or_else(|| {
+      return "The component panicked with an unknown payload.".to_string();
+    });
+  });
+}
+
+/// The message that a panic was raised with, if its payload is a string.
+pub fn panic_message(payload: &dyn Any) -> Option<String> {
+  return match payload.downcast_ref::<&str>() {
+    Some(message) => Some(message.to_string()),
+    None => payload.downcast_ref::<String>().cloned(),
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::{
+    catch_component_panic,
+    is_catching_panic,
+    Component,
+    ComponentRegistry,
+  };
+  use crate::{
+    events::Events,
+    render::{
+      command::RenderCommand,
+      RenderContext,
+    },
+  };
+
+  #[derive(Default)]
+  struct Counter {
+    updates: u32,
+  }
+
+  impl Component<(), String> for Counter {
+    fn on_attach(&mut self, _: &mut RenderContext) -> Result<(), String> {
+      return Ok(());
+    }
+
+    fn on_detach(&mut self, _: &mut RenderContext) -> Result<(), String> {
+      return Ok(());
+    }
+
+    fn on_event(&mut self, _: Events) -> Result<(), String> {
+      return Ok(());
+    }
+
+    fn on_update(&mut self, _: &Duration) -> Result<(), String> {
+      self.updates += 1;
+      return Ok(());
+    }
+
+    fn on_render(&mut self, _: &mut RenderContext) -> Vec<RenderCommand> {
+      return vec![];
+    }
+  }
+
+  #[test]
+  fn registry_orders_components_after_dependencies() {
+    let mut registry: ComponentRegistry<(), String> = ComponentRegistry::new();
+    registry
+      .register("overlay", &["physics", "audio"], Counter::default())
+      .unwrap();
+    registry.register("audio", &[], Counter::default()).unwrap();
+    registry
+      .register("physics", &["input"], Counter::default())
+      .unwrap();
+    registry.register("input", &[], Counter::default()).unwrap();
+
+    registry.resolve_order().unwrap();
+    assert_eq!(
+      registry.names(),
+      vec!["audio", "input", "physics", "overlay"]
+    );
+  }
+
+  #[test]
+  fn registry_rejects_invalid_registrations() {
+    let mut registry: ComponentRegistry<(), String> = ComponentRegistry::new();
+    registry.register("a", &["b"], Counter::default()).unwrap();
+    assert!(registry.register("a", &[], Counter::default()).is_err());
+    assert_eq!(registry.unique_name("a"), "a#1");
+
+    // Missing dependencies.
+    assert!(registry.resolve_order().is_err());
+
+    // Circular dependencies.
+    registry.register("b", &["a"], Counter::default()).unwrap();
+    let error = registry.resolve_order().unwrap_err();
+    assert!(error.contains("a, b"));
+    assert_eq!(registry.len(), 2);
+  }
+
+  #[test]
+  fn registry_queries_components_by_name_and_type() {
+    let mut registry: ComponentRegistry<(), String> = ComponentRegistry::new();
+    let handle = registry
+      .register("counter", &[], Counter::default())
+      .unwrap();
+
+    for component in registry.components() {
+      component
+        .borrow_mut()
+        .on_update(&Duration::from_millis(16))
+        .unwrap();
+    }
+
+    assert_eq!(handle.borrow().updates, 1);
+    assert_eq!(
+      registry.get::<Counter>("counter").unwrap().borrow().updates,
+      1
+    );
+    assert!(registry.get::<String>("counter").is_none());
+    assert!(registry.get::<Counter>("missing").is_none());
+  }
+
+  #[test]
+  fn registry_removes_components() {
+    let mut registry: ComponentRegistry<(), String> = ComponentRegistry::new();
+    registry.register("a", &[], Counter::default()).unwrap();
+    registry.register("b", &[], Counter::default()).unwrap();
+
+    assert!(registry.remove("a").is_some());
+    assert!(registry.remove("a").is_none());
+    let names: Vec<&str> =
+      registry.named_components().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["b"]);
+  }
+
+  #[test]
+  fn catch_component_panic_returns_panic_messages() {
+    assert!(!is_catching_panic());
+    assert_eq!(catch_component_panic(is_catching_panic), Ok(true));
+    assert!(!is_catching_panic());
+    assert_eq!(catch_component_panic(|| 5), Ok(5));
+    assert_eq!(
+      catch_component_panic(|| panic!("static message")),
+      Err::<(), String>("static message".to_string())
+    );
+    assert_eq!(
+      catch_component_panic(|| panic!("formatted {}", 5)),
+      Err::<(), String>("formatted 5".to_string())
+    );
+  }
+}
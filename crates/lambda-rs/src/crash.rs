@@ -0,0 +1,259 @@
+//! Crash reports that are written when the application panics. A report
+//! contains the panic message & backtrace, the most recent log records, and
+//! the environment that the runtime was initialized with, such as the GPU
+//! and surface configuration, so that bug reports can be acted on.
+
+use std::{
+  backtrace::Backtrace,
+  fmt::Write,
+  panic::Location,
+  path::{
+    Path,
+    PathBuf,
+  },
+  sync::{
+    atomic::{
+      AtomicU64,
+      Ordering,
+    },
+    Arc,
+    Mutex,
+  },
+  time::{
+    SystemTime,
+    UNIX_EPOCH,
+  },
+};
+
+use logging::{
+  handler::{
+    LogHistory,
+    RingBufferHandler,
+  },
+  Record,
+};
+
+use crate::component::{
+  is_catching_panic,
+  panic_message,
+};
+
+/// The number of recent log records that are included in a report.
+const LOG_CAPACITY: usize = 200;
+
+/// Distinguishes reports written within the same millisecond.
+static REPORT_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Writes a crash report to a directory whenever a thread panics.
+#[derive(Clone)]
+pub struct CrashReporter {
+  directory: PathBuf,
+  history: LogHistory,
+  environment: Arc<Mutex<Option<String>>>,
+}
+
+impl CrashReporter {
+  /// Installs a panic hook that writes reports to the directory, and adds
+  /// the handler that recent log records are kept by to the global logger.
+  /// The previous panic hook still runs after the report is written. Panics
+  /// that are caught by `catch_panic`, such as those of component callbacks
+  /// & jobs that are recovered from, aren't crashes and are only passed to
+  /// the previous hook.
+  pub fn install(directory: impl Into<PathBuf>) -> Self {
+    let handler = RingBufferHandler::new(LOG_CAPACITY);
+    let reporter = Self {
+      directory: directory.into(),
+      history: handler.history(),
+      environment: Arc::new(Mutex::new(None)),
+    };
+    logging::Logger::global().add_handler(Box::new(handler));
+
+    let previous_hook = std::panic::take_hook();
+    let hook_reporter = reporter.clone();
+    std::panic::set_hook(Box::new(move |info| {
+      if is_catching_panic() {
+        previous_hook(info);
+        return;
+      }
+
+      let message = panic_message(info.payload()).unwrap_or_else(|| {
+        return "The panic had an unknown payload.".to_string();
+      });
+      match hook_reporter.write_report(&message, info.location()) {
+        Ok(path) => {
+          eprintln!("A crash report was written to {}", path.display());
+        }
+        Err(error) => eprintln!("Failed to write a crash report: {}", error),
+      }
+      previous_hook(info);
+    }));
+    return reporter;
+  }
+
+  /// The directory that reports are written to.
+  pub fn directory(&self) -> &Path {
+    return &self.directory;
+  }
+
+  /// Describes the environment in every report written from now on, such as
+  /// with the initialization report of a runtime.
+  pub fn set_environment(&self, environment: String) {
+    *self
+      .environment
+      .lock()
+      .expect("The crash report environment lock was poisoned.") =
+      Some(environment);
+  }
+
+  /// Writes a report for the panic with a backtrace of the current thread,
+  /// returning the path of the report.
+  pub fn write_report(
+    &self,
+    message: &str,
+    location: Option<&Location>,
+  ) -> Result<PathBuf, String> {
+    // The lock may have been poisoned by the thread that is panicking.
+    let environment = match self.environment.lock() {
+      Ok(environment) => environment.clone(),
+      Err(poisoned) => poisoned.into_inner().clone(),
+    };
+    let report = format_report(
+      message,
+      location,
+      &Backtrace::force_capture().to_string(),
+      environment.as_deref(),
+      &self.history.records(),
+    );
+
+    let timestamp = SystemTime::now()
+      .duration_since(UNIX_EPOCH)
+      .map(|elapsed| elapsed.as_millis())
+      .unwrap_or(0);
+    let path = self.directory.join(format!(
+      "crash-{}-{}.txt",
+      timestamp,
+      REPORT_COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&self.directory)
+      .and_then(|_| std::fs::write(&path, report))
+      .map_err(|error| {
+        format!("Failed to write {}: {}", path.display(), error)
+      })?;
+    return Ok(path);
+  }
+}
+
+/// Formats a report with a section for the panic, the environment, the
+/// backtrace, and the log records, oldest first.
+fn format_report(
+  message: &str,
+  location: Option<&Location>,
+  backtrace: &str,
+  environment: Option<&str>,
+  records: &[Record],
+) -> String {
+  let mut report = String::new();
+  let thread = std::thread::current();
+  // Writing to a string can't fail, so the results are ignored.
+  writeln!(report, "== panic").ok();
+  writeln!(report, "thread: {}", thread.name().unwrap_or("unnamed")).ok();
+  writeln!(report, "message: {}", message).ok();
+  if let Some(location) = location {
+    writeln!(
+      report,
+      "location: {}:{}:{}",
+      location.file(),
+      location.line(),
+      location.column()
+    )
+    .ok();
+  }
+
+  writeln!(report, "\n== environment").ok();
+  match environment {
+    Some(environment) => writeln!(report, "{}", environment.trim_end()).ok(),
+    None => writeln!(report, "The runtime hadn't been initialized.").ok(),
+  };
+
+  writeln!(report, "\n== backtrace\n{}", backtrace.trim_end()).ok();
+
+  writeln!(report, "\n== log ({} most recent records)", records.len()).ok();
+  for record in records {
+    writeln!(
+      report,
+      "[{:?}] {}: {}",
+      record.level,
+      record.target,
+      record.message_with_fields()
+    )
+    .ok();
+  }
+  return report;
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use logging::{
+    LogLevel,
+    Record,
+  };
+
+  use super::{
+    format_report,
+    CrashReporter,
+  };
+  use crate::jobs::JobPoolBuilder;
+
+  #[test]
+  fn reports_contain_every_section() {
+    let records = vec![
+      Record::new(LogLevel::INFO, "lambda", "Loaded".to_string()),
+      Record::new(LogLevel::WARN, "demo", "Slow frame".to_string())
+        .with_field("ms", 40_u32),
+    ];
+    let report = format_report(
+      "index out of bounds",
+      None,
+      "0: main",
+      Some("Initialized the runtime: demo\n"),
+      &records,
+    );
+
+    assert!(report.contains("message: index out of bounds"));
+    assert!(report.contains("== environment\nInitialized the runtime: demo\n"));
+    assert!(report.contains("== backtrace\n0: main"));
+    assert!(report.contains("== log (2 most recent records)"));
+    assert!(report.ends_with("[WARN] demo: Slow frame ms=40\n"));
+    assert!(format_report("", None, "", None, &[])
+      .contains("The runtime hadn't been initialized."));
+  }
+
+  #[test]
+  fn caught_job_panics_write_no_reports() {
+    let directory = std::env::temp_dir()
+      .join(format!("lambda-crash-{}", std::process::id()));
+    CrashReporter::install(directory.clone());
+
+    let pool = JobPoolBuilder::new().with_workers(1).build();
+    let panicked =
+      pool.spawn_joinable(|| -> u32 { panic!("recovered job panic") });
+    assert_eq!(panicked.join(), Err("recovered job panic".to_string()));
+
+    // Reports of panics from other tests may be written to the directory.
+    let reports = std::fs::read_dir(&directory)
+      .map(|entries| {
+        return entries
+          .filter_map(Result::ok)
+          .filter(|entry| {
+            return std::fs::read_to_string(entry.path())
+              .is_ok_and(|report| report.contains("recovered job panic"));
+          })
+          .count();
+      })
+      .unwrap_or(0);
+    assert_eq!(reports, 0);
+    std::fs::remove_dir_all(&directory).ok();
+  }
+}
@@ -0,0 +1,644 @@
+//! A lightweight entity component system. Entities are handles to rows of
+//! components that are stored per type, systems are functions that run over
+//! the world in the order they're scheduled, and resources are singletons
+//! shared between systems. `EcsComponent` runs a world inside of a runtime so
+//! that other components can share its entity data.
+
+use std::{
+  any::{
+    Any,
+    TypeId,
+  },
+  collections::HashMap,
+  time::Duration,
+};
+
+use crate::{
+  component::Component,
+  events::Events,
+  render::{
+    command::RenderCommand,
+    RenderContext,
+  },
+  runtimes::application::ComponentResult,
+};
+
+// --------------------------------- ENTITY ------------------------------------
+
+/// A handle to an entity within a world. Handles of despawned entities are
+/// never reused, so they stop matching once their entity is despawned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Entity {
+  index: u32,
+  generation: u32,
+}
+
+// -------------------------------- STORAGE ------------------------------------
+
+/// Type erased access to the storage of a single component type.
+trait Storage {
+  fn remove_entity(&mut self, entity: Entity);
+  fn as_any(&self) -> &dyn Any;
+  fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Densely packed components of a single type along with the entities that
+/// own them. Entities are mapped to their component through a sparse array
+/// indexed by the entity index.
+struct ComponentStorage<T> {
+  components: Vec<T>,
+  entities: Vec<Entity>,
+  indices: Vec<Option<usize>>,
+}
+
+impl<T> ComponentStorage<T> {
+  fn new() -> Self {
+    return Self {
+      components: vec![],
+      entities: vec![],
+      indices: vec![],
+    };
+  }
+
+  fn index_of(&self, entity: Entity) -> Option<usize> {
+    let index = (*self.indices.get(entity.index as usize)?)?;
+    if self.entities[index] != entity {
+      return None;
+    }
+    return Some(index);
+  }
+
+  fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+    if let Some(index) = self.index_of(entity) {
+      return Some(std::mem::replace(&mut self.components[index], component));
+    }
+
+    let slot = entity.index as usize;
+    if self.indices.len() <= slot {
+      self.indices.resize(slot + 1, None);
+    }
+    self.indices[slot] = Some(self.components.len());
+    self.components.push(component);
+    self.entities.push(entity);
+    return None;
+  }
+
+  fn remove(&mut self, entity: Entity) -> Option<T> {
+    let index = self.index_of(entity)?;
+    self.indices[entity.index as usize] = None;
+    self.entities.swap_remove(index);
+
+    // The last component was moved into the removed component's place.
+    if let Some(moved) = self.entities.get(index) {
+      self.indices[moved.index as usize] = Some(index);
+    }
+    return Some(self.components.swap_remove(index));
+  }
+
+  fn get(&self, entity: Entity) -> Option<&T> {
+    return self.index_of(entity).map(|index| &self.components[index]);
+  }
+
+  fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+    return self
+      .index_of(entity)
+      .map(|index| &mut self.components[index]);
+  }
+}
+
+impl<T: 'static> Storage for ComponentStorage<T> {
+  fn remove_entity(&mut self, entity: Entity) {
+    self.remove(entity);
+  }
+
+  fn as_any(&self) -> &dyn Any {
+    return self;
+  }
+
+  fn as_any_mut(&mut self) -> &mut dyn Any {
+    return self;
+  }
+}
+
+// --------------------------------- WORLD -------------------------------------
+
+/// Storage for entities, their components, and resources.
+pub struct World {
+  generations: Vec<u32>,
+  alive: Vec<bool>,
+  free: Vec<u32>,
+  storages: HashMap<TypeId, Box<dyn Storage>>,
+  resources: HashMap<TypeId, Box<dyn Any>>,
+}
+
+impl World {
+  /// Creates an empty world.
+  pub fn new() -> Self {
+    return Self {
+      generations: vec![],
+      alive: vec![],
+      free: vec![],
+      storages: HashMap::new(),
+      resources: HashMap::new(),
+    };
+  }
+
+  /// Creates a new entity without any components.
+  pub fn spawn(&mut self) -> Entity {
+    let index = match self.free.pop() {
+      Some(index) => index,
+      None => {
+        self.generations.push(0);
+        self.alive.push(false);
+        (self.generations.len() - 1) as u32
+      }
+    };
+    self.alive[index as usize] = true;
+    return Entity {
+      index,
+      generation: self.generations[index as usize],
+    };
+  }
+
+  /// Despawns the entity and drops its components. Returns false if the
+  /// entity was already despawned.
+  pub fn despawn(&mut self, entity: Entity) -> bool {
+    if !self.is_alive(entity) {
+      return false;
+    }
+
+    for storage in self.storages.values_mut() {
+      storage.remove_entity(entity);
+    }
+
+    let index = entity.index as usize;
+    self.alive[index] = false;
+    self.generations[index] = self.generations[index].wrapping_add(1);
+    self.free.push(entity.index);
+    return true;
+  }
+
+  /// Whether or not the entity hasn't been despawned.
+  pub fn is_alive(&self, entity: Entity) -> bool {
+    let index = entity.index as usize;
+    return self.alive.get(index).copied().unwrap_or(false)
+      && self.generations[index] == entity.generation;
+  }
+
+  /// The number of entities that are alive.
+  pub fn len(&self) -> usize {
+    return self.alive.iter().filter(|alive| **alive).count();
+  }
+
+  /// Whether or not the world has any entities that are alive.
+  pub fn is_empty(&self) -> bool {
+    return self.len() == 0;
+  }
+
+  fn storage<T: 'static>(&self) -> Option<&ComponentStorage<T>> {
+    return self
+      .storages
+      .get(&TypeId::of::<T>())
+      .and_then(|storage| storage.as_any().downcast_ref());
+  }
+
+  fn storage_mut<T: 'static>(&mut self) -> Option<&mut ComponentStorage<T>> {
+    return self
+      .storages
+      .get_mut(&TypeId::of::<T>())
+      .and_then(|storage| storage.as_any_mut().downcast_mut());
+  }
+
+  /// Adds the component to the entity, returning the component of the same
+  /// type that it replaced. Components added to despawned entities are
+  /// dropped and returned.
+  pub fn insert<T: 'static>(
+    &mut self,
+    entity: Entity,
+    component: T,
+  ) -> Option<T> {
+    if !self.is_alive(entity) {
+      return Some(component);
+    }
+
+    return self
+      .storages
+      .entry(TypeId::of::<T>())
+      .or_insert_with(|| Box::new(ComponentStorage::<T>::new()))
+      .as_any_mut()
+      .downcast_mut::<ComponentStorage<T>>()
+      .expect("Component storage has the wrong type.")
+      .insert(entity, component);
+  }
+
+  /// Removes the component from the entity, returning it if it was present.
+  pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+    return self.storage_mut::<T>()?.remove(entity);
+  }
+
+  pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+    return self.storage::<T>()?.get(entity);
+  }
+
+  pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+    return self.storage_mut::<T>()?.get_mut(entity);
+  }
+
+  /// Every entity with a component of type T.
+  pub fn query<T: 'static>(&self) -> impl Iterator<Item = (Entity, &T)> {
+    return self.storage::<T>().into_iter().flat_map(|storage| {
+      return storage
+        .entities
+        .iter()
+        .copied()
+        .zip(storage.components.iter());
+    });
+  }
+
+  /// Every entity with a component of type T, which can be modified.
+  pub fn query_mut<T: 'static>(
+    &mut self,
+  ) -> impl Iterator<Item = (Entity, &mut T)> {
+    return self.storage_mut::<T>().into_iter().flat_map(|storage| {
+      return storage
+        .entities
+        .iter()
+        .copied()
+        .zip(storage.components.iter_mut());
+    });
+  }
+
+  /// Every entity with components of both type A and type B.
+  pub fn query2<A: 'static, B: 'static>(
+    &self,
+  ) -> impl Iterator<Item = (Entity, &A, &B)> {
+    let others = self.storage::<B>();
+    return self.query::<A>().filter_map(move |(entity, a)| {
+      return others
+        .and_then(|others| others.get(entity))
+        .map(|b| (entity, a, b));
+    });
+  }
+
+  /// Calls the function with every entity that has components of both type A
+  /// and type B, both of which can be modified. Panics if A and B are the
+  /// same type.
+  pub fn query2_mut<A: 'static, B: 'static>(
+    &mut self,
+    mut function: impl FnMut(Entity, &mut A, &mut B),
+  ) {
+    assert_ne!(
+      TypeId::of::<A>(),
+      TypeId::of::<B>(),
+      "Cannot query the same component type mutably twice."
+    );
+
+    // The storage of B is taken out of the world while A is borrowed.
+    let mut others = match self.storages.remove(&TypeId::of::<B>()) {
+      Some(others) => others,
+      None => return,
+    };
+
+    if let Some(storage) = self.storage_mut::<A>() {
+      let others = others
+        .as_any_mut()
+        .downcast_mut::<ComponentStorage<B>>()
+        .expect("Component storage has the wrong type.");
+      for (entity, a) in
+        storage.entities.iter().zip(storage.components.iter_mut())
+      {
+        if let Some(b) = others.get_mut(*entity) {
+          function(*entity, a, b);
+        }
+      }
+    }
+
+    self.storages.insert(TypeId::of::<B>(), others);
+  }
+
+  /// Adds a resource shared by every system, returning the resource of the
+  /// same type that it replaced.
+  pub fn insert_resource<T: 'static>(&mut self, resource: T) -> Option<T> {
+    return self
+      .resources
+      .insert(TypeId::of::<T>(), Box::new(resource))
+      .and_then(|previous| previous.downcast().ok())
+      .map(|previous| *previous);
+  }
+
+  pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+    return self
+      .resources
+      .remove(&TypeId::of::<T>())
+      .and_then(|resource| resource.downcast().ok())
+      .map(|resource| *resource);
+  }
+
+  pub fn resource<T: 'static>(&self) -> Option<&T> {
+    return self
+      .resources
+      .get(&TypeId::of::<T>())
+      .and_then(|resource| resource.downcast_ref());
+  }
+
+  pub fn resource_mut<T: 'static>(&mut self) -> Option<&mut T> {
+    return self
+      .resources
+      .get_mut(&TypeId::of::<T>())
+      .and_then(|resource| resource.downcast_mut());
+  }
+}
+
+// -------------------------------- SCHEDULE -----------------------------------
+
+/// A system that updates the world with the duration of the last frame.
+pub type System = Box<dyn FnMut(&mut World, &Duration)>;
+
+/// A system that produces render commands from the world.
+pub type RenderSystem =
+  Box<dyn FnMut(&mut World, &mut RenderContext) -> Vec<RenderCommand>>;
+
+/// Named systems that are run over a world in the order they were added.
+pub struct Schedule {
+  systems: Vec<(String, System)>,
+}
+
+impl Schedule {
+  /// Creates an empty schedule.
+  pub fn new() -> Self {
+    return Self { systems: vec![] };
+  }
+
+  /// Adds a system to the end of the schedule.
+  pub fn with_system(
+    mut self,
+    name: &str,
+    system: impl FnMut(&mut World, &Duration) + 'static,
+  ) -> Self {
+    self.systems.push((name.to_string(), Box::new(system)));
+    return self;
+  }
+
+  /// The names of the systems in the order that they run.
+  pub fn names(&self) -> Vec<&str> {
+    return self.systems.iter().map(|(name, _)| name.as_str()).collect();
+  }
+
+  /// Runs every system over the world.
+  pub fn run(&mut self, world: &mut World, last_frame: &Duration) {
+    for (_, system) in self.systems.iter_mut() {
+      system(world, last_frame);
+    }
+  }
+}
+
+// ------------------------------- COMPONENT -----------------------------------
+
+/// The events that a runtime sent to an `EcsComponent` since its last update,
+/// which is available to systems as a resource.
+#[derive(Clone, Debug, Default)]
+pub struct ReceivedEvents {
+  events: Vec<Events>,
+}
+
+impl ReceivedEvents {
+  pub fn events(&self) -> &[Events] {
+    return &self.events;
+  }
+}
+
+/// Runs a world within a runtime. Update systems run every frame and render
+/// systems produce the commands rendered every frame, while events received
+/// from the runtime are exposed to systems through `ReceivedEvents`. Other
+/// components can share the world's data by querying the runtime for this
+/// component.
+pub struct EcsComponent {
+  world: World,
+  schedule: Schedule,
+  render_systems: Vec<(String, RenderSystem)>,
+}
+
+impl Default for EcsComponent {
+  fn default() -> Self {
+    let mut world = World::new();
+    world.insert_resource(ReceivedEvents::default());
+    return Self {
+      world,
+      schedule: Schedule::new(),
+      render_systems: vec![],
+    };
+  }
+}
+
+impl EcsComponent {
+  /// Configures the world before the component is attached, such as to spawn
+  /// entities or insert resources.
+  pub fn with_world(mut self, configure: impl FnOnce(&mut World)) -> Self {
+    configure(&mut self.world);
+    return self;
+  }
+
+  /// Adds a system that runs every frame after the systems added before it.
+  pub fn with_system(
+    mut self,
+    name: &str,
+    system: impl FnMut(&mut World, &Duration) + 'static,
+  ) -> Self {
+    self.schedule = self.schedule.with_system(name, system);
+    return self;
+  }
+
+  /// Adds a system whose render commands are rendered every frame after the
+  /// commands of the render systems added before it.
+  pub fn with_render_system(
+    mut self,
+    name: &str,
+    system: impl FnMut(&mut World, &mut RenderContext) -> Vec<RenderCommand>
+      + 'static,
+  ) -> Self {
+    self
+      .render_systems
+      .push((name.to_string(), Box::new(system)));
+    return self;
+  }
+
+  pub fn world(&self) -> &World {
+    return &self.world;
+  }
+
+  pub fn world_mut(&mut self) -> &mut World {
+    return &mut self.world;
+  }
+
+  pub fn schedule(&self) -> &Schedule {
+    return &self.schedule;
+  }
+}
+
+impl Component<ComponentResult, String> for EcsComponent {
+  fn on_attach(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_detach(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_event(&mut self, event: Events) -> Result<ComponentResult, String> {
+    match self.world.resource_mut::<ReceivedEvents>() {
+      Some(received) => received.events.push(event),
+      None => {
+        self.world.insert_resource(ReceivedEvents {
+          events: vec![event],
+        });
+      }
+    }
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_update(
+    &mut self,
+    last_frame: &Duration,
+  ) -> Result<ComponentResult, String> {
+    self.schedule.run(&mut self.world, last_frame);
+    if let Some(received) = self.world.resource_mut::<ReceivedEvents>() {
+      received.events.clear();
+    }
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_render(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Vec<RenderCommand> {
+    let mut commands = vec![];
+    for (_, system) in self.render_systems.iter_mut() {
+      commands.extend(system(&mut self.world, render_context));
+    }
+    return commands;
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::{
+    EcsComponent,
+    ReceivedEvents,
+    Schedule,
+    World,
+  };
+  use crate::{
+    component::Component,
+    events::{
+      Events,
+      RuntimeEvent,
+    },
+  };
+
+  #[derive(Debug, PartialEq)]
+  struct Position(f32);
+
+  #[derive(Debug, PartialEq)]
+  struct Velocity(f32);
+
+  #[test]
+  fn entities_and_components() {
+    let mut world = World::new();
+    let first = world.spawn();
+    let second = world.spawn();
+
+    assert_eq!(world.insert(first, Position(1.0)), None);
+    assert_eq!(world.insert(first, Position(2.0)), Some(Position(1.0)));
+    world.insert(second, Position(3.0));
+    world.insert(second, Velocity(1.0));
+
+    assert_eq!(world.get::<Position>(first), Some(&Position(2.0)));
+    assert_eq!(world.get::<Velocity>(first), None);
+    assert_eq!(world.remove::<Position>(first), Some(Position(2.0)));
+    assert_eq!(world.get::<Position>(second), Some(&Position(3.0)));
+
+    // Despawned handles stop matching, even once their slot is reused.
+    assert!(world.despawn(second));
+    assert!(!world.despawn(second));
+    let third = world.spawn();
+    assert_ne!(third, second);
+    assert_eq!(world.get::<Position>(second), None);
+    assert_eq!(world.insert(second, Position(4.0)), Some(Position(4.0)));
+    assert_eq!(world.get::<Position>(third), None);
+    assert_eq!(world.len(), 2);
+  }
+
+  #[test]
+  fn queries_and_schedules() {
+    let mut world = World::new();
+    for index in 0..4 {
+      let entity = world.spawn();
+      world.insert(entity, Position(index as f32));
+      if index % 2 == 0 {
+        world.insert(entity, Velocity(10.0));
+      }
+    }
+    world.insert_resource(0_u32);
+
+    let mut schedule = Schedule::new()
+      .with_system("movement", |world: &mut World, last_frame: &Duration| {
+        let seconds = last_frame.as_secs_f32();
+        world.query2_mut::<Position, Velocity>(|_, position, velocity| {
+          position.0 += velocity.0 * seconds;
+        });
+      })
+      .with_system("count", |world: &mut World, _: &Duration| {
+        let moving = world.query2::<Position, Velocity>().count() as u32;
+        *world.resource_mut::<u32>().unwrap() += moving;
+      });
+    assert_eq!(schedule.names(), vec!["movement", "count"]);
+
+    schedule.run(&mut world, &Duration::from_millis(500));
+    let positions: Vec<f32> = world
+      .query::<Position>()
+      .map(|(_, position)| position.0)
+      .collect();
+    assert_eq!(positions, vec![5.0, 1.0, 7.0, 3.0]);
+    assert_eq!(world.resource::<u32>(), Some(&2));
+
+    for (_, velocity) in world.query_mut::<Velocity>() {
+      velocity.0 = 0.0;
+    }
+    assert!(world
+      .query::<Velocity>()
+      .all(|(_, velocity)| velocity.0 == 0.0));
+  }
+
+  #[test]
+  fn component_forwards_events_to_systems() {
+    let mut component = EcsComponent::default()
+      .with_world(|world| {
+        world.insert_resource(0_usize);
+      })
+      .with_system("events", |world: &mut World, _: &Duration| {
+        let count = world.resource::<ReceivedEvents>().unwrap().events().len();
+        *world.resource_mut::<usize>().unwrap() += count;
+      });
+
+    component
+      .on_event(Events::Runtime {
+        event: RuntimeEvent::Initialized,
+        issued_at: std::time::Instant::now(),
+      })
+      .unwrap();
+    component.on_update(&Duration::ZERO).unwrap();
+    component.on_update(&Duration::ZERO).unwrap();
+
+    assert_eq!(component.world().resource::<usize>(), Some(&1));
+  }
+}
@@ -1,12 +1,26 @@
 //! Event definitions for lambda runtimes and applications.
 
-use std::time::Instant;
+use std::{
+  path::PathBuf,
+  time::Instant,
+};
+
+use crate::input;
 
 /// events generated by kernel interactions with the component.
 #[derive(Debug, Clone)]
 pub enum ComponentEvent {
-  Attached { name: String },
-  Detached { name: String },
+  Attached {
+    name: String,
+  },
+  Detached {
+    name: String,
+  },
+  /// Emitted when a component panics. The component is detached afterwards.
+  Errored {
+    name: String,
+    message: String,
+  },
 }
 
 /// Window events are generated in response to window events coming from
@@ -14,7 +28,28 @@ pub enum ComponentEvent {
 #[derive(Debug, Clone)]
 pub enum WindowEvent {
   Close,
-  Resize { width: u32, height: u32 },
+  Resize {
+    width: u32,
+    height: u32,
+  },
+  /// Emitted once the runtime starts & whenever the window moves to a
+  /// monitor with a different scale factor, which is the number of physical
+  /// pixels per logical pixel. A resize with the new size in physical pixels
+  /// follows a change.
+  ScaleFactorChanged {
+    scale_factor: f64,
+  },
+  /// Emitted when a file is dropped onto the window. Dropping several files
+  /// emits an event for every file.
+  FileDropped {
+    path: PathBuf,
+  },
+  /// Emitted when a file is dragged over the window, before it's dropped.
+  FileHovered {
+    path: PathBuf,
+  },
+  /// Emitted when the hovered files leave the window without being dropped.
+  HoverCancelled,
 }
 
 /// Runtime events are generated by the Runtimes themselves.
@@ -22,10 +57,20 @@ pub enum WindowEvent {
 pub enum RuntimeEvent {
   Initialized,
   Shutdown,
-  ComponentPanic { message: String },
+  ComponentPanic {
+    message: String,
+  },
+  /// Emitted when the window can no longer be rendered to, such as when it's
+  /// occluded or the application is sent to the background. Components
+  /// aren't updated or rendered until the runtime resumes, so they should
+  /// pause work that depends on time passing, like audio or timers.
+  Suspended,
+  /// Emitted once the window can be rendered to again after being suspended.
+  Resumed,
 }
 
 /// Exports the winit virtual key codes to this namespace for convenience.
+/// Deprecated in favor of `input::Key`, which doesn't change with winit.
 pub use lambda_platform::winit::winit_exports::VirtualKeyCode as VirtualKey;
 
 /// Keyboard events are generated in response to keyboard events coming from
@@ -35,22 +80,42 @@ pub enum Key {
   /// Emitted when a key is pressed.
   Pressed {
     scan_code: u32,
+    key: input::Key,
+    #[deprecated(note = "Match on `key`, which doesn't change with winit.")]
     virtual_key: Option<VirtualKey>,
   },
   /// Emitted when a key is released.
   Released {
     scan_code: u32,
+    key: input::Key,
+    #[deprecated(note = "Match on `key`, which doesn't change with winit.")]
     virtual_key: Option<VirtualKey>,
   },
   /// Emitted when a modifier key is pressed.
   ModifierPressed {
     modifier: u32,
+    key: input::Key,
+    #[deprecated(note = "Match on `key`, which doesn't change with winit.")]
     virtual_key: VirtualKey,
   },
+  /// Emitted when text is typed, or committed by an input method once it
+  /// has finished composing. Unlike key presses, the text accounts for the
+  /// keyboard layout, dead keys, and modifiers, so text fields should insert
+  /// it as is.
+  TextInput { text: String },
+  /// Emitted while an input method composes text that hasn't been committed
+  /// yet, which text fields should display in place of the previous
+  /// composition. The cursor is the byte range within the text to highlight,
+  /// and empty text ends the composition. Only emitted for windows built
+  /// with `WindowBuilder::with_ime`.
+  ImePreedit {
+    text: String,
+    cursor: Option<(usize, usize)>,
+  },
 }
 
 /// Mouse buttons.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Button {
   Left,
   Right,
@@ -59,10 +124,14 @@ pub enum Button {
 }
 
 /// Mouse events are generated in response to mouse events coming from the
-/// windowing system. The coordinates are in logical pixels.
+/// windowing system. The coordinates are in logical pixels, which are
+/// converted from the physical pixels of the windowing system with the
+/// window's scale factor. Buttons are pressed & released at the position
+/// the cursor last moved to.
 #[derive(Debug, Clone)]
 pub enum Mouse {
-  /// Emitted when the mouse cursor is moved within the window.
+  /// Emitted when the mouse cursor is moved within the window, along with
+  /// how far it moved since it last moved within the window.
   Moved {
     x: f64,
     y: f64,
@@ -70,8 +139,10 @@ pub enum Mouse {
     dy: f64,
     device_id: u32,
   },
-  /// Emitted when the mouse wheel is scrolled.
-  Scrolled { device_id: u32 },
+  /// Emitted when the mouse wheel is scrolled by the delta in lines, which
+  /// is positive when the scrolled content moves right or down, such as when
+  /// the wheel is scrolled up.
+  Scrolled { dx: f64, dy: f64, device_id: u32 },
   /// Emitted when a mouse button is pressed.
   Pressed {
     x: f64,
@@ -0,0 +1,5 @@
+//! Input types that stay the same when the windowing system that reports
+//! them is upgraded.
+
+/// A key on the keyboard, named after its position on a US keyboard.
+pub use lambda_platform::winit::input::Key;
@@ -0,0 +1,364 @@
+//! A pool of worker threads for running work off of the main thread, such as
+//! decoding assets or processing meshes. Jobs can be fire & forget, joined
+//! through a handle, or deliver their result to a callback that runs on the
+//! main thread once the pool's callbacks are run, which the application
+//! runtime does at the start of every frame.
+
+use std::{
+  any::Any,
+  cell::RefCell,
+  collections::HashMap,
+  sync::{
+    mpsc,
+    Arc,
+    Mutex,
+  },
+  thread,
+};
+
+use crate::component::{
+  catch_panic,
+  panic_message,
+};
+
+type Job = Box<dyn FnOnce() + Send>;
+type JobOutput = Box<dyn Any + Send>;
+type Callback = Box<dyn FnOnce(JobOutput)>;
+
+/// Work that has finished & needs to be handed to the main thread.
+enum Completion {
+  /// The output of the job with the id, or the message it panicked with.
+  Job {
+    id: u64,
+    output: Result<JobOutput, String>,
+  },
+  /// A callback sent from another thread to run on the main thread.
+  MainThread(Job),
+}
+
+/// Runs the job and returns the message that it panicked with, if any.
+fn catch_job_panic<T>(job: impl FnOnce() -> T) -> Result<T, String> {
+  return catch_panic(job).map_err(|payload| {
+    return panic_message(payload.as_ref()).unwrap_or_else(|| {
+      return "The job panicked with an unknown payload.".to_string();
+    });
+  });
+}
+
+/// Builds a job pool.
+pub struct JobPoolBuilder {
+  workers: usize,
+}
+
+impl JobPoolBuilder {
+  /// Creates a builder for a job pool with a worker thread for every core
+  /// besides the one the main thread runs on.
+  pub fn new() -> Self {
+    let workers = thread::available_parallelism()
+      .map_or(1, |workers| workers.get().saturating_sub(1))
+      .max(1);
+    return Self { workers };
+  }
+
+  /// The number of worker threads, which is at least one.
+  pub fn with_workers(mut self, workers: usize) -> Self {
+    self.workers = workers.max(1);
+    return self;
+  }
+
+  pub fn build(self) -> JobPool {
+    let (jobs, receiver) = mpsc::channel::<Job>();
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers = (0..self.workers)
+      .map(|index| {
+        let receiver = receiver.clone();
+        return thread::Builder::new()
+          .name(format!("lambda-jobs-{}", index))
+          .spawn(move || loop {
+            let job = receiver
+              .lock()
+              .expect("The job queue lock was poisoned.")
+              .recv();
+            match job {
+              Ok(job) => job(),
+              Err(_) => return,
+            }
+          })
+          .expect("Failed to spawn a job worker thread.");
+      })
+      .collect();
+
+    let (completed, completions) = mpsc::channel();
+    return JobPool {
+      jobs: Some(jobs),
+      workers,
+      completed,
+      completions,
+      callbacks: RefCell::new(HashMap::new()),
+      next_id: RefCell::new(0),
+    };
+  }
+}
+
+/// Runs jobs on worker threads. Dropping the pool waits for the jobs that
+/// have been spawned to finish, but doesn't run their callbacks.
+pub struct JobPool {
+  jobs: Option<mpsc::Sender<Job>>,
+  workers: Vec<thread::JoinHandle<()>>,
+  completed: mpsc::Sender<Completion>,
+  completions: mpsc::Receiver<Completion>,
+  callbacks: RefCell<HashMap<u64, Callback>>,
+  next_id: RefCell<u64>,
+}
+
+impl JobPool {
+  /// Runs the job on a worker thread without waiting for it. Panics within
+  /// the job are logged.
+  pub fn spawn(&self, job: impl FnOnce() + Send + 'static) {
+    self.queue(Box::new(move || {
+      if let Err(message) = catch_job_panic(job) {
+        logging::error!("A job panicked: {}", message);
+      }
+    }));
+  }
+
+  /// Runs the job on a worker thread and returns a handle for waiting on its
+  /// result.
+  pub fn spawn_joinable<T: Send + 'static>(
+    &self,
+    job: impl FnOnce() -> T + Send + 'static,
+  ) -> JobHandle<T> {
+    let (sender, receiver) = mpsc::channel();
+    self.queue(Box::new(move || {
+      sender.send(catch_job_panic(job)).ok();
+    }));
+    return JobHandle {
+      receiver,
+      result: None,
+    };
+  }
+
+  /// Runs the job on a worker thread and then passes its result to the
+  /// callback on the main thread once the callbacks of the pool are run.
+  /// Callbacks aren't run if the job panics.
+  pub fn spawn_then<T: Send + 'static>(
+    &self,
+    job: impl FnOnce() -> T + Send + 'static,
+    callback: impl FnOnce(T) + 'static,
+  ) {
+    let id = {
+      let mut next_id = self.next_id.borrow_mut();
+      *next_id += 1;
+      *next_id
+    };
+    self.callbacks.borrow_mut().insert(
+      id,
+      Box::new(move |output: JobOutput| {
+        let output = output
+          .downcast::<T>()
+          .expect("The job output doesn't match its callback.");
+        callback(*output);
+      }),
+    );
+
+    let completed = self.completed.clone();
+    self.queue(Box::new(move || {
+      let output = catch_job_panic(job).map(|output| {
+        return Box::new(output) as JobOutput;
+      });
+      completed.send(Completion::Job { id, output }).ok();
+    }));
+  }
+
+  /// A sender that jobs can use to queue callbacks onto the main thread.
+  pub fn main_thread_sender(&self) -> MainThreadSender {
+    return MainThreadSender {
+      completed: self.completed.clone(),
+    };
+  }
+
+  /// Runs the callbacks of the jobs that have finished & the callbacks sent
+  /// to the main thread, in the order they completed. Returns the number of
+  /// callbacks that ran.
+  pub fn run_callbacks(&self) -> usize {
+    let mut ran = 0;
+    while let Ok(completion) = self.completions.try_recv() {
+      match completion {
+        Completion::Job { id, output } => {
+          let callback = self.callbacks.borrow_mut().remove(&id);
+          match (callback, output) {
+            (Some(callback), Ok(output)) => {
+              callback(output);
+              ran += 1;
+            }
+            (_, Err(message)) => {
+              logging::error!("A job panicked: {}", message);
+            }
+            (None, Ok(_)) => {}
+          }
+        }
+        Completion::MainThread(callback) => {
+          callback();
+          ran += 1;
+        }
+      }
+    }
+    return ran;
+  }
+
+  /// The number of worker threads that jobs run on.
+  pub fn worker_count(&self) -> usize {
+    return self.workers.len();
+  }
+
+  /// The number of jobs whose callbacks haven't run yet.
+  pub fn pending_callbacks(&self) -> usize {
+    return self.callbacks.borrow().len();
+  }
+
+  fn queue(&self, job: Job) {
+    self
+      .jobs
+      .as_ref()
+      .expect("The job pool has shut down.")
+      .send(job)
+      .expect("The job workers have stopped.");
+  }
+}
+
+impl Default for JobPool {
+  fn default() -> Self {
+    return JobPoolBuilder::new().build();
+  }
+}
+
+impl Drop for JobPool {
+  fn drop(&mut self) {
+    // Workers stop once every sender of the job queue has been dropped.
+    self.jobs.take();
+    for worker in self.workers.drain(..) {
+      worker.join().ok();
+    }
+  }
+}
+
+/// Queues callbacks from any thread to run on the main thread the next time
+/// the callbacks of the job pool are run.
+#[derive(Clone)]
+pub struct MainThreadSender {
+  completed: mpsc::Sender<Completion>,
+}
+
+impl MainThreadSender {
+  /// Queues the callback, which is dropped if the job pool no longer exists.
+  pub fn send(&self, callback: impl FnOnce() + Send + 'static) {
+    self
+      .completed
+      .send(Completion::MainThread(Box::new(callback)))
+      .ok();
+  }
+}
+
+/// A handle to the result of a job spawned with `spawn_joinable`.
+pub struct JobHandle<T> {
+  receiver: mpsc::Receiver<Result<T, String>>,
+  result: Option<Result<T, String>>,
+}
+
+impl<T> JobHandle<T> {
+  /// Whether the job has finished, without waiting for it.
+  pub fn is_finished(&mut self) -> bool {
+    if self.result.is_none() {
+      self.result = self.receiver.try_recv().ok();
+    }
+    return self.result.is_some();
+  }
+
+  /// The result of the job if it has finished, without waiting for it. The
+  /// result is an error with the panic message if the job panicked.
+  pub fn try_join(&mut self) -> Option<Result<T, String>> {
+    self.is_finished();
+    return self.result.take();
+  }
+
+  /// Waits for the job to finish and returns its result, which is an error
+  /// with the panic message if the job panicked.
+  pub fn join(mut self) -> Result<T, String> {
+    if let Some(result) = self.result.take() {
+      return result;
+    }
+    return self
+      .receiver
+      .recv()
+      .unwrap_or_else(|_| Err("The job never finished.".to_string()));
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    cell::RefCell,
+    rc::Rc,
+    sync::{
+      atomic::{
+        AtomicUsize,
+        Ordering,
+      },
+      Arc,
+    },
+  };
+
+  use super::JobPoolBuilder;
+
+  #[test]
+  fn joinable_jobs_return_results_and_panics() {
+    let pool = JobPoolBuilder::new().with_workers(2).build();
+    let sum = pool.spawn_joinable(|| (1..=10).sum::<u32>());
+    let panicked = pool.spawn_joinable(|| -> u32 { panic!("decode failed") });
+
+    assert_eq!(sum.join(), Ok(55));
+    assert_eq!(panicked.join(), Err("decode failed".to_string()));
+  }
+
+  #[test]
+  fn callbacks_run_on_the_main_thread_when_drained() {
+    let pool = JobPoolBuilder::new().with_workers(1).build();
+    let results = Rc::new(RefCell::new(vec![]));
+
+    let received = results.clone();
+    pool.spawn_then(|| 21 * 2, move |value| received.borrow_mut().push(value));
+    assert_eq!(pool.pending_callbacks(), 1);
+
+    // Waiting on a later job on the same worker guarantees the first job has
+    // completed.
+    pool.spawn_joinable(|| ()).join().unwrap();
+    assert!(results.borrow().is_empty());
+
+    assert_eq!(pool.run_callbacks(), 1);
+    assert_eq!(*results.borrow(), vec![42]);
+    assert_eq!(pool.pending_callbacks(), 0);
+
+    let sender = pool.main_thread_sender();
+    pool
+      .spawn_joinable(move || sender.send(|| {}))
+      .join()
+      .unwrap();
+    assert_eq!(pool.run_callbacks(), 1);
+  }
+
+  #[test]
+  fn dropping_the_pool_waits_for_jobs() {
+    let finished = Arc::new(AtomicUsize::new(0));
+    let pool = JobPoolBuilder::new().with_workers(2).build();
+    for _ in 0..8 {
+      let finished = finished.clone();
+      pool.spawn(move || {
+        finished.fetch_add(1, Ordering::SeqCst);
+      });
+    }
+    drop(pool);
+    assert_eq!(finished.load(Ordering::SeqCst), 8);
+  }
+}
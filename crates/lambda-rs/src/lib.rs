@@ -1,12 +1,27 @@
 //! Lambda is a simple, fast, and safe compute engine written in Rust.
 
+pub mod assets;
+pub mod clipboard;
 pub mod component;
+pub mod crash;
+pub mod ecs;
 pub mod events;
+pub mod input;
+pub mod jobs;
+pub mod log_console;
 pub mod math;
+pub mod net;
+pub mod perf_hud;
+pub mod persistence;
 pub mod render;
 pub mod runtime;
 pub mod runtimes;
+pub mod scene;
+pub mod time;
+pub mod tweakables;
 
+/// The args module parses command line arguments for Lambda applications.
+pub use args;
 /// The logging module provides a simple logging interface for Lambda
 /// applications.
 pub use logging;
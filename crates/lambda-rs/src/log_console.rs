@@ -0,0 +1,633 @@
+//! An in-window console that shows the most recent records logged through
+//! the global logger, so errors are visible in builds that aren't attached to
+//! a terminal. Lines are drawn with the text renderer on top of everything
+//! drawn before the console.
+//!
+//! The console is toggled with the grave key, scrolled with page up, page
+//! down, home, & end, paused with the pause key, and cycles the minimum level
+//! of the lines it shows with F2.
+
+use std::time::Duration;
+
+use logging::{
+  handler::{
+    LogHistory,
+    RingBufferHandler,
+  },
+  LogLevel,
+  Record,
+};
+
+use crate::{
+  component::Component,
+  events::{
+    Events,
+    Key,
+    WindowEvent,
+  },
+  input,
+  render::{
+    buffer::BufferBuilder,
+    command::RenderCommand,
+    mesh::MeshBuilder,
+    pipeline::RenderPipelineBuilder,
+    render_pass::RenderPassBuilder,
+    shader::{
+      ShaderBuilder,
+      ShaderKind,
+      VirtualShader,
+    },
+    text::{
+      FontAtlas,
+      TextRenderer,
+    },
+    vertex::Vertex,
+    viewport::ViewportBuilder,
+    RenderContext,
+    ResourceId,
+  },
+  runtimes::application::ComponentResult,
+};
+
+/// Draws vertices given in clip space with their color, which is shared by
+/// the in-window overlays.
+pub(crate) const VERTEX_SHADER_SOURCE: &str = r#"
+#version 450
+
+layout (location = 0) in vec3 vertex_position;
+layout (location = 1) in vec3 vertex_normal;
+layout (location = 2) in vec3 vertex_color;
+
+layout (location = 0) out vec3 frag_color;
+
+void main() {
+  gl_Position = vec4(vertex_position, 1.0);
+  frag_color = vertex_color;
+}
+"#;
+
+pub(crate) const FRAGMENT_SHADER_SOURCE: &str = r#"
+#version 450
+
+layout (location = 0) in vec3 frag_color;
+
+layout (location = 0) out vec4 fragment_color;
+
+void main() {
+  fragment_color = vec4(frag_color, 1.0);
+}
+"#;
+
+/// The number of records kept by the handler the console installs.
+const HISTORY_CAPACITY: usize = 1024;
+
+/// The number of lines shown by default.
+const DEFAULT_LINES: usize = 16;
+
+/// The number of vertices the console can draw by default.
+const DEFAULT_VERTEX_CAPACITY: usize = 1 << 17;
+
+/// Pixels between the edge of the panel & its text.
+const PANEL_PADDING: f32 = 8.0;
+
+const PANEL_COLOR: [f32; 3] = [0.05, 0.05, 0.08];
+
+/// The color lines are drawn with based upon their level.
+fn level_color(level: LogLevel) -> [f32; 3] {
+  return match level {
+    LogLevel::TRACE => [0.6, 0.6, 0.6],
+    LogLevel::DEBUG => [0.8, 0.5, 0.9],
+    LogLevel::INFO => [0.5, 0.9, 0.5],
+    LogLevel::WARN => [0.95, 0.85, 0.3],
+    LogLevel::ERROR | LogLevel::FATAL => [1.0, 0.35, 0.35],
+  };
+}
+
+/// The level after the given level, wrapping back around to trace.
+fn next_level(level: LogLevel) -> LogLevel {
+  return match level {
+    LogLevel::TRACE => LogLevel::DEBUG,
+    LogLevel::DEBUG => LogLevel::INFO,
+    LogLevel::INFO => LogLevel::WARN,
+    LogLevel::WARN => LogLevel::ERROR,
+    LogLevel::ERROR => LogLevel::FATAL,
+    LogLevel::FATAL => LogLevel::TRACE,
+  };
+}
+
+/// Shows recent log records within the window. A ring buffer handler is
+/// added to the global logger when the console is created.
+pub struct LogConsoleComponent {
+  history: LogHistory,
+  /// The records shown while the console is paused.
+  paused: Option<Vec<Record>>,
+  visible: bool,
+  minimum_level: LogLevel,
+  search: String,
+  /// The number of lines scrolled up from the newest line.
+  scroll: usize,
+  lines: usize,
+  font: Option<FontAtlas>,
+  vertex_capacity: usize,
+  render_pass: Option<ResourceId>,
+  render_pipeline: Option<ResourceId>,
+  width: u32,
+  height: u32,
+  /// The scale factor of the window, which the panel & lines are scaled by.
+  pixels_per_point: f32,
+  /// Whether the lines changed since the vertex buffer was last updated.
+  dirty: bool,
+  seen_records: u64,
+  vertex_count: u32,
+}
+
+impl LogConsoleComponent {
+  /// Creates a visible console and adds the handler it reads records from to
+  /// the global logger. Nothing is drawn until a font is given.
+  pub fn new() -> Self {
+    let handler = RingBufferHandler::new(HISTORY_CAPACITY);
+    let history = handler.history();
+    logging::Logger::global().add_handler(Box::new(handler));
+
+    return Self {
+      history,
+      paused: None,
+      visible: true,
+      minimum_level: LogLevel::TRACE,
+      search: String::new(),
+      scroll: 0,
+      lines: DEFAULT_LINES,
+      font: None,
+      vertex_capacity: DEFAULT_VERTEX_CAPACITY,
+      render_pass: None,
+      render_pipeline: None,
+      width: 800,
+      height: 600,
+      pixels_per_point: 1.0,
+      dirty: true,
+      seen_records: 0,
+      vertex_count: 0,
+    };
+  }
+
+  /// The font that lines are drawn with.
+  pub fn with_font(mut self, font: FontAtlas) -> Self {
+    self.font = Some(font);
+    return self;
+  }
+
+  /// The number of lines shown at once.
+  pub fn with_lines(mut self, lines: usize) -> Self {
+    self.lines = lines.max(1);
+    return self;
+  }
+
+  /// The most vertices the console draws, after which lines are cut off.
+  pub fn with_vertex_capacity(mut self, vertex_capacity: usize) -> Self {
+    self.vertex_capacity = vertex_capacity.max(6);
+    return self;
+  }
+
+  /// Only show records at or above the level.
+  pub fn with_minimum_level(mut self, level: LogLevel) -> Self {
+    self.set_minimum_level(level);
+    return self;
+  }
+
+  /// Only show records whose target or message contain the query.
+  pub fn with_search(mut self, query: &str) -> Self {
+    self.set_search(query);
+    return self;
+  }
+
+  pub fn set_minimum_level(&mut self, level: LogLevel) {
+    self.minimum_level = level;
+    self.scroll = 0;
+    self.dirty = true;
+  }
+
+  pub fn minimum_level(&self) -> LogLevel {
+    return self.minimum_level;
+  }
+
+  /// Only show records whose target or message contain the query, ignoring
+  /// case. An empty query shows every record.
+  pub fn set_search(&mut self, query: &str) {
+    self.search = query.to_lowercase();
+    self.scroll = 0;
+    self.dirty = true;
+  }
+
+  pub fn search(&self) -> &str {
+    return &self.search;
+  }
+
+  pub fn set_visible(&mut self, visible: bool) {
+    self.visible = visible;
+    self.dirty = true;
+  }
+
+  pub fn is_visible(&self) -> bool {
+    return self.visible;
+  }
+
+  /// Freezes the lines shown while paused, so they can be read without new
+  /// records scrolling them away.
+  pub fn set_paused(&mut self, paused: bool) {
+    self.paused = match paused {
+      true => Some(self.history.records()),
+      false => None,
+    };
+    self.dirty = true;
+  }
+
+  pub fn is_paused(&self) -> bool {
+    return self.paused.is_some();
+  }
+
+  /// Scrolls towards older lines, stopping at the oldest line.
+  pub fn scroll_up(&mut self, lines: usize) {
+    let matching = self.matching_records().len();
+    let limit = matching.saturating_sub(self.lines);
+    self.scroll = self.scroll.saturating_add(lines).min(limit);
+    self.dirty = true;
+  }
+
+  /// Scrolls towards newer lines, stopping at the newest line.
+  pub fn scroll_down(&mut self, lines: usize) {
+    self.scroll = self.scroll.saturating_sub(lines);
+    self.dirty = true;
+  }
+
+  /// The records that pass the level filter & search, from oldest to newest.
+  fn matching_records(&self) -> Vec<Record> {
+    let records = match &self.paused {
+      Some(records) => records.clone(),
+      None => self.history.records(),
+    };
+    return records
+      .into_iter()
+      .filter(|record| record.level >= self.minimum_level)
+      .filter(|record| {
+        return self.search.is_empty()
+          || record.message.to_lowercase().contains(&self.search)
+          || record.target.to_lowercase().contains(&self.search);
+      })
+      .collect();
+  }
+
+  /// The lines currently shown by the console along with their levels, from
+  /// oldest to newest.
+  pub fn visible_lines(&self) -> Vec<(LogLevel, String)> {
+    let records = self.matching_records();
+    let end = records.len().saturating_sub(self.scroll);
+    let start = end.saturating_sub(self.lines);
+    return records[start..end]
+      .iter()
+      .map(|record| {
+        return (
+          record.level,
+          format!(
+            "[{:?}] {}: {}",
+            record.level,
+            record.target,
+            record.message_with_fields()
+          ),
+        );
+      })
+      .collect();
+  }
+
+  /// Builds the panel & its lines as a triangle list that fits within the
+  /// vertex capacity.
+  fn build_vertices(&self, font: &FontAtlas) -> Vec<Vertex> {
+    let lines = self.visible_lines();
+    let line_height = font.line_height();
+    let panel_height = (PANEL_PADDING * 2.0 + line_height * self.lines as f32)
+      * self.pixels_per_point;
+
+    let mut vertices = panel_vertices(
+      (self.width as f32, self.height as f32),
+      (self.width as f32).max(1.0),
+      panel_height.min(self.height as f32),
+    );
+
+    let renderer = TextRenderer::new(self.width, self.height)
+      .with_pixels_per_point(self.pixels_per_point);
+    for (index, (level, line)) in lines.iter().enumerate() {
+      // Only the first line of multiline messages is shown so that every
+      // record takes up a single line.
+      let line = line.lines().next().unwrap_or_default();
+      let position =
+        (PANEL_PADDING, PANEL_PADDING + line_height * index as f32);
+      let mesh = renderer
+        .with_color(level_color(*level))
+        .build_mesh(font, line, position);
+      vertices.extend_from_slice(mesh.vertices());
+    }
+
+    // Only whole triangles are drawn.
+    let capacity = self.vertex_capacity - self.vertex_capacity % 3;
+    vertices.truncate(capacity);
+    return vertices;
+  }
+}
+
+/// Two triangles covering the top of the screen, with positions converted
+/// from pixels into clip space the same way as the text renderer.
+fn panel_vertices(
+  screen_size: (f32, f32),
+  width: f32,
+  height: f32,
+) -> Vec<Vertex> {
+  let vertex = |x: f32, y: f32| {
+    return Vertex {
+      position: [
+        x / screen_size.0 * 2.0 - 1.0,
+        y / screen_size.1 * 2.0 - 1.0,
+        0.0,
+      ],
+      normal: [0.0, 0.0, 1.0],
+      color: PANEL_COLOR,
+      tex_coords: [0.0, 0.0],
+      tangent: [0.0, 0.0, 0.0, 0.0],
+    };
+  };
+  return vec![
+    vertex(0.0, 0.0),
+    vertex(0.0, height),
+    vertex(width, 0.0),
+    vertex(width, 0.0),
+    vertex(0.0, height),
+    vertex(width, height),
+  ];
+}
+
+impl Default for LogConsoleComponent {
+  fn default() -> Self {
+    return Self::new();
+  }
+}
+
+impl Component<ComponentResult, String> for LogConsoleComponent {
+  fn on_attach(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    let font = match &self.font {
+      Some(font) => font,
+      None => {
+        logging::warn!("The log console has no font, so it won't be drawn.");
+        return Ok(ComponentResult::Success);
+      }
+    };
+
+    // The vertex buffer has room for the most vertices the console draws and
+    // is updated whenever the lines change.
+    let attributes = TextRenderer::new(self.width, self.height)
+      .build_mesh(font, "", (0.0, 0.0))
+      .attributes()
+      .to_vec();
+    let mut mesh_builder = MeshBuilder::new();
+    for _ in 0..self.vertex_capacity {
+      mesh_builder.with_vertex(Vertex {
+        position: [0.0; 3],
+        normal: [0.0; 3],
+        color: [0.0; 3],
+        tex_coords: [0.0; 2],
+        tangent: [0.0; 4],
+      });
+    }
+    mesh_builder.with_attributes(attributes);
+    let mesh = mesh_builder.build();
+
+    let mut builder = ShaderBuilder::new();
+    let vertex_shader = builder.build(VirtualShader::Source {
+      source: VERTEX_SHADER_SOURCE.to_string(),
+      kind: ShaderKind::Vertex,
+      entry_point: "main".to_string(),
+      name: "log_console".to_string(),
+    });
+    let fragment_shader = builder.build(VirtualShader::Source {
+      source: FRAGMENT_SHADER_SOURCE.to_string(),
+      kind: ShaderKind::Fragment,
+      entry_point: "main".to_string(),
+      name: "log_console".to_string(),
+    });
+
+    let render_pass = RenderPassBuilder::new()
+      .with_preserved_contents()
+      .build(render_context);
+    let pipeline = RenderPipelineBuilder::new()
+      .with_buffer(
+        BufferBuilder::build_from_mesh(&mesh, render_context)
+          .map_err(|error| error.to_string())?,
+        mesh.attributes().to_vec(),
+      )
+      .build(
+        render_context,
+        &render_pass,
+        &vertex_shader,
+        Some(&fragment_shader),
+      );
+
+    self.render_pass = Some(render_context.attach_render_pass(render_pass));
+    self.render_pipeline = Some(render_context.attach_pipeline(pipeline));
+    self.dirty = true;
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_detach(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_event(&mut self, event: Events) -> Result<ComponentResult, String> {
+    match event {
+      Events::Window {
+        event: WindowEvent::Resize { width, height },
+        ..
+      } => {
+        self.width = width;
+        self.height = height;
+        self.dirty = true;
+      }
+      Events::Window {
+        event: WindowEvent::ScaleFactorChanged { scale_factor },
+        ..
+      } => {
+        self.pixels_per_point = scale_factor as f32;
+        self.dirty = true;
+      }
+      Events::Keyboard {
+        event: Key::Pressed { key, .. },
+        ..
+      } => match key {
+        input::Key::Grave => self.set_visible(!self.visible),
+        input::Key::Pause => self.set_paused(!self.is_paused()),
+        input::Key::F2 => {
+          self.set_minimum_level(next_level(self.minimum_level))
+        }
+        input::Key::PageUp => self.scroll_up(self.lines),
+        input::Key::PageDown => self.scroll_down(self.lines),
+        input::Key::Home => self.scroll_up(usize::MAX),
+        input::Key::End => self.scroll_down(self.scroll),
+        _ => {}
+      },
+      _ => {}
+    }
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_update(
+    &mut self,
+    _last_frame: &Duration,
+  ) -> Result<ComponentResult, String> {
+    let total = self.history.total();
+    if total != self.seen_records {
+      self.seen_records = total;
+      self.dirty |= !self.is_paused();
+    }
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_render(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Vec<RenderCommand> {
+    let (render_pass, pipeline, font) =
+      match (self.render_pass, self.render_pipeline, &self.font) {
+        (Some(render_pass), Some(pipeline), Some(font)) => {
+          (render_pass, pipeline, font)
+        }
+        _ => return vec![],
+      };
+    if !self.visible {
+      return vec![];
+    }
+
+    let mut commands = vec![];
+    if self.dirty {
+      let vertices = self.build_vertices(font);
+      self.vertex_count = vertices.len() as u32;
+      commands.push(RenderCommand::UpdateVertexBuffer {
+        pipeline,
+        buffer: 0,
+        vertices,
+      });
+      self.dirty = false;
+    }
+
+    let viewport = ViewportBuilder::new().build(self.width, self.height);
+    commands.extend([
+      RenderCommand::SetViewports {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetScissors {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetPipeline { pipeline },
+      RenderCommand::BeginRenderPass {
+        render_pass,
+        viewport,
+      },
+      RenderCommand::BindVertexBuffer {
+        pipeline,
+        buffer: 0,
+      },
+      RenderCommand::Draw {
+        vertices: 0..self.vertex_count,
+      },
+      RenderCommand::EndRenderPass,
+    ]);
+    return commands;
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use logging::{
+    handler::{
+      Handler,
+      RingBufferHandler,
+    },
+    LogLevel,
+    Record,
+  };
+
+  use super::LogConsoleComponent;
+
+  /// A console reading from its own handler instead of the global logger's.
+  fn console(messages: &[(LogLevel, &str)]) -> LogConsoleComponent {
+    let mut handler = RingBufferHandler::new(16);
+    for (level, message) in messages {
+      handler.log(&Record::new(*level, "lambda::test", message.to_string()));
+    }
+    let mut console = LogConsoleComponent::new().with_lines(2);
+    console.history = handler.history();
+    return console;
+  }
+
+  fn messages(console: &LogConsoleComponent) -> Vec<String> {
+    return console
+      .visible_lines()
+      .into_iter()
+      .map(|(_, line)| line)
+      .collect();
+  }
+
+  #[test]
+  fn lines_are_filtered_and_scrolled() {
+    let mut console = console(&[
+      (LogLevel::INFO, "Loaded mesh"),
+      (LogLevel::WARN, "Missing texture"),
+      (LogLevel::DEBUG, "Frame"),
+      (LogLevel::ERROR, "Lost device"),
+    ]);
+    assert_eq!(
+      messages(&console),
+      vec![
+        "[DEBUG] lambda::test: Frame",
+        "[ERROR] lambda::test: Lost device"
+      ]
+    );
+
+    console.scroll_up(10);
+    assert_eq!(
+      messages(&console),
+      vec![
+        "[INFO] lambda::test: Loaded mesh",
+        "[WARN] lambda::test: Missing texture"
+      ]
+    );
+
+    console.set_minimum_level(LogLevel::WARN);
+    assert_eq!(console.visible_lines().len(), 2);
+    assert_eq!(console.visible_lines()[0].0, LogLevel::WARN);
+
+    console.set_search("MESH");
+    console.set_minimum_level(LogLevel::TRACE);
+    assert_eq!(messages(&console), vec!["[INFO] lambda::test: Loaded mesh"]);
+  }
+
+  #[test]
+  fn paused_consoles_ignore_new_records() {
+    let mut handler = RingBufferHandler::new(16);
+    let mut console = LogConsoleComponent::new();
+    console.history = handler.history();
+
+    handler.log(&Record::new(LogLevel::INFO, "test", "first".to_string()));
+    console.set_paused(true);
+    handler.log(&Record::new(LogLevel::INFO, "test", "second".to_string()));
+    assert_eq!(console.visible_lines().len(), 1);
+
+    console.set_paused(false);
+    assert_eq!(console.visible_lines().len(), 2);
+  }
+}
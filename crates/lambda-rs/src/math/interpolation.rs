@@ -0,0 +1,207 @@
+//! Interpolation & easing functions for animation.
+
+use std::f32::consts::PI;
+
+use super::vector::Vector;
+
+/// Linearly interpolates from a to b, where t = 0 is a and t = 1 is b. t isn't
+/// clamped, so values outside of 0..1 extrapolate.
+pub fn lerp(a: f32, b: f32, t: f32) -> f32 {
+  return a + (b - a) * t;
+}
+
+/// The inverse of lerp, which returns how far the value is between a and b.
+/// Returns 0 when a and b are equal.
+pub fn inverse_lerp(a: f32, b: f32, value: f32) -> f32 {
+  if a == b {
+    return 0.0;
+  }
+  return (value - a) / (b - a);
+}
+
+/// Linearly interpolates between two vectors of any size.
+pub fn lerp_vector<V: Vector<Scalar = f32>>(a: &V, b: &V, t: f32) -> V {
+  return a.add(&b.subtract(a).scale(t));
+}
+
+/// Smoothly maps x from 0 at the first edge to 1 at the second edge with a
+/// cubic curve whose slope is 0 at both edges.
+pub fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+  let t = inverse_lerp(edge0, edge1, x).clamp(0.0, 1.0);
+  return t * t * (3.0 - 2.0 * t);
+}
+
+/// Like smoothstep, but uses a quintic curve whose second derivative is also
+/// 0 at both edges.
+pub fn smootherstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+  let t = inverse_lerp(edge0, edge1, x).clamp(0.0, 1.0);
+  return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+/// Evaluates a cubic bezier curve at t, which starts at p0, ends at p3, and is
+/// pulled towards the control points p1 and p2.
+pub fn cubic_bezier(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+  let u = 1.0 - t;
+  return u * u * u * p0
+    + 3.0 * u * u * t * p1
+    + 3.0 * u * t * t * p2
+    + t * t * t * p3;
+}
+
+/// Evaluates a cubic bezier curve of vectors of any size at t.
+pub fn cubic_bezier_vector<V: Vector<Scalar = f32>>(
+  points: [&V; 4],
+  t: f32,
+) -> V {
+  let u = 1.0 - t;
+  let [p0, p1, p2, p3] = points;
+  return p0
+    .scale(u * u * u)
+    .add(&p1.scale(3.0 * u * u * t))
+    .add(&p2.scale(3.0 * u * t * t))
+    .add(&p3.scale(t * t * t));
+}
+
+// --------------------------------- EASING ------------------------------------
+
+/// Easing curves that map the progress of an animation from 0..1 onto 0..1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Easing {
+  Linear,
+  QuadIn,
+  QuadOut,
+  QuadInOut,
+  CubicIn,
+  CubicOut,
+  CubicInOut,
+  SineIn,
+  SineOut,
+  SineInOut,
+  /// A CSS style curve from (0, 0) to (1, 1) with the control points (x1, y1)
+  /// and (x2, y2). x1 and x2 are clamped between 0 and 1.
+  CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+  /// Eases the progress t, which is clamped between 0 and 1.
+  pub fn apply(&self, t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    return match *self {
+      Easing::Linear => t,
+      Easing::QuadIn => t * t,
+      Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+      Easing::QuadInOut => {
+        if t < 0.5 {
+          2.0 * t * t
+        } else {
+          1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+        }
+      }
+      Easing::CubicIn => t * t * t,
+      Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+      Easing::CubicInOut => {
+        if t < 0.5 {
+          4.0 * t * t * t
+        } else {
+          1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+        }
+      }
+      Easing::SineIn => 1.0 - (t * PI / 2.0).cos(),
+      Easing::SineOut => (t * PI / 2.0).sin(),
+      Easing::SineInOut => -((t * PI).cos() - 1.0) / 2.0,
+      Easing::CubicBezier(x1, y1, x2, y2) => {
+        let (x1, x2) = (x1.clamp(0.0, 1.0), x2.clamp(0.0, 1.0));
+
+        // Find the curve parameter whose x is t by bisection, which converges
+        // for every valid curve since x is monotonic when x1 & x2 are in 0..1.
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..32 {
+          let middle = (low + high) / 2.0;
+          if cubic_bezier(0.0, x1, x2, 1.0, middle) < t {
+            low = middle;
+          } else {
+            high = middle;
+          }
+        }
+        cubic_bezier(0.0, y1, y2, 1.0, (low + high) / 2.0)
+      }
+    };
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    cubic_bezier,
+    cubic_bezier_vector,
+    inverse_lerp,
+    lerp,
+    lerp_vector,
+    smootherstep,
+    smoothstep,
+    Easing,
+  };
+  use crate::assert_approximately_equal;
+
+  #[test]
+  fn interpolation() {
+    assert_eq!(lerp(2.0, 4.0, 0.25), 2.5);
+    assert_eq!(lerp(2.0, 4.0, 2.0), 6.0);
+    assert_eq!(inverse_lerp(2.0, 4.0, 2.5), 0.25);
+    assert_eq!(inverse_lerp(1.0, 1.0, 3.0), 0.0);
+    assert_eq!(lerp_vector(&[0.0, 2.0], &[2.0, 4.0], 0.5), [1.0, 3.0]);
+
+    assert_eq!(smoothstep(0.0, 2.0, -1.0), 0.0);
+    assert_eq!(smoothstep(0.0, 2.0, 1.0), 0.5);
+    assert_eq!(smoothstep(0.0, 2.0, 3.0), 1.0);
+    assert_eq!(smootherstep(0.0, 1.0, 0.5), 0.5);
+    assert!(smootherstep(0.0, 1.0, 0.1) < smoothstep(0.0, 1.0, 0.1));
+  }
+
+  #[test]
+  fn bezier_curves() {
+    assert_eq!(cubic_bezier(1.0, 5.0, -3.0, 2.0, 0.0), 1.0);
+    assert_eq!(cubic_bezier(1.0, 5.0, -3.0, 2.0, 1.0), 2.0);
+    assert_eq!(cubic_bezier(0.0, 1.0, 2.0, 3.0, 0.5), 1.5);
+
+    let points = [[0.0, 0.0], [0.0, 1.0], [1.0, 1.0], [1.0, 0.0]];
+    let middle = cubic_bezier_vector(
+      [&points[0], &points[1], &points[2], &points[3]],
+      0.5,
+    );
+    assert_eq!(middle, [0.5, 0.75]);
+  }
+
+  #[test]
+  fn easing_curves() {
+    let curves = [
+      Easing::Linear,
+      Easing::QuadIn,
+      Easing::QuadOut,
+      Easing::QuadInOut,
+      Easing::CubicIn,
+      Easing::CubicOut,
+      Easing::CubicInOut,
+      Easing::SineIn,
+      Easing::SineOut,
+      Easing::SineInOut,
+      Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+    ];
+
+    for curve in curves {
+      assert_approximately_equal!(curve.apply(0.0), 0.0, 1e-5);
+      assert_approximately_equal!(curve.apply(1.0), 1.0, 1e-5);
+      assert_eq!(curve.apply(-1.0), curve.apply(0.0));
+    }
+
+    assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+    assert_approximately_equal!(Easing::CubicInOut.apply(0.5), 0.5, 1e-6);
+
+    // A linear bezier curve eases linearly.
+    let linear =
+      Easing::CubicBezier(1.0 / 3.0, 1.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0);
+    assert_approximately_equal!(linear.apply(0.3), 0.3, 1e-5);
+  }
+}
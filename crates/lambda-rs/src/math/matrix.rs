@@ -3,12 +3,19 @@
 use lambda_platform::rand::get_uniformly_random_floats_between;
 
 use super::{
+  quaternion::Quaternion,
+  simd::{
+    Mat4,
+    Vec4,
+  },
   turns_to_radians,
   vector::Vector,
 };
 
 // -------------------------------- MATRIX -------------------------------------
 
+const SINGULAR_MATRIX: &str = "Cannot invert a singular matrix";
+
 /// Matrix trait which defines the basic operations that can be performed on a
 /// matrix. Lambda currently implements this trait for f32 arrays of arrays
 /// for any size.
@@ -18,6 +25,9 @@ pub trait Matrix<V: Vector> {
   fn multiply(&self, other: &Self) -> Self;
   fn transpose(&self) -> Self;
   fn inverse(&self) -> Self;
+  fn try_inverse(&self) -> Result<Self, String>
+  where
+    Self: Sized;
   fn transform(&self, other: &V) -> V;
   fn determinant(&self) -> f32;
   fn size(&self) -> (usize, usize);
@@ -260,15 +270,168 @@ pub fn identity_matrix<
   return result;
 }
 
+// ------------------------------ DECOMPOSITION --------------------------------
+
+/// The translation, rotation, and scale that make up an affine transform.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Decomposition {
+  pub translation: [f32; 3],
+  pub rotation: Quaternion,
+  pub scale: [f32; 3],
+}
+
+/// Returns an error unless the matrix is affine, meaning that its bottom row is
+/// [0, 0, 0, 1]. Translations are stored in the last column, the same layout
+/// produced by `translation_matrix`.
+fn check_affine(matrix: &[[f32; 4]; 4]) -> Result<(), String> {
+  let is_affine = matrix[3]
+    .iter()
+    .zip([0.0, 0.0, 0.0, 1.0].iter())
+    .all(|(value, expected)| (value - expected).abs() <= f32::EPSILON);
+
+  if !is_affine {
+    return Err(format!(
+      "Matrix isn't affine, its bottom row is {:?}",
+      matrix[3]
+    ));
+  }
+  return Ok(());
+}
+
+/// Inverts an affine matrix, such as a view or model matrix, by inverting its
+/// upper 3x3 matrix and translation separately. Cheaper than a general inverse
+/// and returns an error if the matrix isn't affine or is singular.
+pub fn affine_inverse(matrix: &[[f32; 4]; 4]) -> Result<[[f32; 4]; 4], String> {
+  check_affine(matrix)?;
+
+  let m = |row: usize, column: usize| matrix[row][column];
+  let cofactors = [
+    [
+      m(1, 1) * m(2, 2) - m(1, 2) * m(2, 1),
+      m(0, 2) * m(2, 1) - m(0, 1) * m(2, 2),
+      m(0, 1) * m(1, 2) - m(0, 2) * m(1, 1),
+    ],
+    [
+      m(1, 2) * m(2, 0) - m(1, 0) * m(2, 2),
+      m(0, 0) * m(2, 2) - m(0, 2) * m(2, 0),
+      m(0, 2) * m(1, 0) - m(0, 0) * m(1, 2),
+    ],
+    [
+      m(1, 0) * m(2, 1) - m(1, 1) * m(2, 0),
+      m(0, 1) * m(2, 0) - m(0, 0) * m(2, 1),
+      m(0, 0) * m(1, 1) - m(0, 1) * m(1, 0),
+    ],
+  ];
+  let determinant = m(0, 0) * cofactors[0][0]
+    + m(0, 1) * cofactors[1][0]
+    + m(0, 2) * cofactors[2][0];
+
+  if determinant.abs() <= f32::EPSILON || !determinant.is_finite() {
+    return Err(SINGULAR_MATRIX.to_string());
+  }
+
+  let mut result = [[0.0; 4]; 4];
+  for (row, cofactor_row) in result.iter_mut().zip(cofactors.iter()) {
+    for (value, cofactor) in row.iter_mut().zip(cofactor_row.iter()) {
+      *value = cofactor / determinant;
+    }
+  }
+
+  // The inverse translation is the original translation moved back through
+  // the inverse rotation & scale.
+  for row in result.iter_mut().take(3) {
+    row[3] = -(0..3).map(|j| row[j] * matrix[j][3]).sum::<f32>();
+  }
+  result[3][3] = 1.0;
+  return Ok(result);
+}
+
+/// Decomposes an affine matrix into its translation, rotation, and scale.
+/// Mirrored matrices are represented with a negative x scale. Returns an error
+/// if the matrix isn't affine, has a zero scale along any axis, or is sheared.
+pub fn decompose(matrix: &[[f32; 4]; 4]) -> Result<Decomposition, String> {
+  check_affine(matrix)?;
+
+  let translation = [matrix[0][3], matrix[1][3], matrix[2][3]];
+  let mut axes = [[0.0; 3]; 3];
+  for (axis, column) in axes.iter_mut().zip(0..3) {
+    *axis = [matrix[0][column], matrix[1][column], matrix[2][column]];
+  }
+
+  let mut scale = [axes[0].length(), axes[1].length(), axes[2].length()];
+  if scale.iter().any(|scale| *scale <= f32::EPSILON) {
+    return Err(format!(
+      "Cannot decompose a matrix with a zero scale of {:?}",
+      scale
+    ));
+  }
+
+  if axes[0].cross(&axes[1]).dot(&axes[2]) < 0.0 {
+    scale[0] = -scale[0];
+  }
+
+  for (axis, scale) in axes.iter_mut().zip(scale.iter()) {
+    *axis = axis.scale(1.0 / scale);
+  }
+
+  let tolerance = 1e-4;
+  let is_orthogonal = axes[0].dot(&axes[1]).abs() < tolerance
+    && axes[0].dot(&axes[2]).abs() < tolerance
+    && axes[1].dot(&axes[2]).abs() < tolerance;
+  if !is_orthogonal {
+    return Err("Cannot decompose a sheared matrix".to_string());
+  }
+
+  let mut rotation = [[0.0; 3]; 3];
+  for (row, values) in rotation.iter_mut().enumerate() {
+    for (value, axis) in values.iter_mut().zip(axes.iter()) {
+      *value = axis[row];
+    }
+  }
+
+  return Ok(Decomposition {
+    translation,
+    rotation: Quaternion::from_rotation_matrix(rotation),
+    scale,
+  });
+}
+
 // -------------------------- ARRAY IMPLEMENTATION -----------------------------
 
+/// Copies a 4x4 matrix into a `Mat4` so that it can use the SIMD fast path.
+#[inline]
+fn to_mat4<Array: AsRef<[V]>, V: AsRef<[f32]>>(matrix: &Array) -> Option<Mat4> {
+  let rows = matrix.as_ref();
+  if rows.len() != 4 || rows.iter().any(|row| row.as_ref().len() != 4) {
+    return None;
+  }
+
+  let mut result = [[0.0; 4]; 4];
+  for (row, values) in result.iter_mut().zip(rows.iter()) {
+    row.copy_from_slice(values.as_ref());
+  }
+  return Some(Mat4::from_rows(result));
+}
+
+/// Copies a `Mat4` back into a 4x4 matrix.
+#[inline]
+fn from_mat4<Array: AsMut<[V]> + Default, V: AsMut<[f32]>>(
+  matrix: &Mat4,
+) -> Array {
+  let mut result = Array::default();
+  for (row, values) in result.as_mut().iter_mut().zip(matrix.to_rows()) {
+    row.as_mut().copy_from_slice(&values);
+  }
+  return result;
+}
+
 /// Matrix implementations for arrays of f32 arrays. Including the trait Matrix into
 /// your code will allow you to use these function implementation for any array
 /// of f32 arrays.
 impl<Array, V> Matrix<V> for Array
 where
   Array: AsMut<[V]> + AsRef<[V]> + Default,
-  V: AsMut<[f32]> + AsRef<[f32]> + Vector<Scalar = f32> + Sized,
+  V: AsMut<[f32]> + AsRef<[f32]> + Vector<Scalar = f32> + Default + Sized,
 {
   fn add(&self, other: &Self) -> Self {
     let mut result = Self::default();
@@ -291,7 +454,14 @@ where
     return result;
   }
 
+  /// Multiplies the matrices. 4x4 matrices use the SIMD fast path.
   fn multiply(&self, other: &Self) -> Self {
+    if let (Some(a), Some(b)) =
+      (to_mat4::<Self, V>(self), to_mat4::<Self, V>(other))
+    {
+      return from_mat4::<Self, V>(&a.multiply(&b));
+    }
+
     let mut result = Self::default();
 
     // We transpose the other matrix to convert the columns into rows, allowing
@@ -318,16 +488,120 @@ where
     return result;
   }
 
+  /// Inverts the matrix. Panics if the matrix isn't square or is singular.
   fn inverse(&self) -> Self {
-    todo!()
+    return match self.try_inverse() {
+      Ok(inverse) => inverse,
+      Err(error) => panic!("{}", error),
+    };
+  }
+
+  /// Inverts the matrix using the SIMD fast path for 4x4 matrices and
+  /// Gauss-Jordan elimination for any other square matrix. Returns an error
+  /// if the matrix isn't square or is singular.
+  fn try_inverse(&self) -> Result<Self, String> {
+    if let Some(matrix) = to_mat4::<Self, V>(self) {
+      return match matrix.inverse() {
+        Some(inverse) => Ok(from_mat4::<Self, V>(&inverse)),
+        None => Err(SINGULAR_MATRIX.to_string()),
+      };
+    }
+
+    let (rows, columns) = self.size();
+    if rows != columns {
+      return Err(format!(
+        "Cannot invert a non-square {}x{} matrix",
+        rows, columns
+      ));
+    }
+
+    let mut matrix = Self::default();
+    let mut result = Self::default();
+    for i in 0..rows {
+      for j in 0..columns {
+        matrix.update(i, j, self.at(i, j));
+        result.update(i, j, if i == j { 1.0 } else { 0.0 });
+      }
+    }
+
+    for column in 0..columns {
+      // Pivot on the largest remaining value in the column for stability.
+      let pivot = (column..rows)
+        .max_by(|a, b| {
+          return matrix
+            .at(*a, column)
+            .abs()
+            .total_cmp(&matrix.at(*b, column).abs());
+        })
+        .unwrap();
+
+      let pivot_value = matrix.at(pivot, column);
+      if pivot_value.abs() <= f32::EPSILON {
+        return Err(SINGULAR_MATRIX.to_string());
+      }
+
+      for j in 0..columns {
+        let (a, b) = (matrix.at(column, j), matrix.at(pivot, j));
+        matrix.update(column, j, b);
+        matrix.update(pivot, j, a);
+        let (a, b) = (result.at(column, j), result.at(pivot, j));
+        result.update(column, j, b);
+        result.update(pivot, j, a);
+      }
+
+      for j in 0..columns {
+        matrix.update(column, j, matrix.at(column, j) / pivot_value);
+        result.update(column, j, result.at(column, j) / pivot_value);
+      }
+
+      for row in (0..rows).filter(|row| *row != column) {
+        let factor = matrix.at(row, column);
+        for j in 0..columns {
+          matrix.update(
+            row,
+            j,
+            matrix.at(row, j) - factor * matrix.at(column, j),
+          );
+          result.update(
+            row,
+            j,
+            result.at(row, j) - factor * result.at(column, j),
+          );
+        }
+      }
+    }
+    return Ok(result);
   }
 
+  /// Transforms a column vector by the matrix (self * other). 4x4 matrices
+  /// use the SIMD fast path.
   fn transform(&self, other: &V) -> V {
-    todo!()
+    if let (Some(matrix), 4) = (to_mat4::<Self, V>(self), other.size()) {
+      let vector =
+        Vec4::new(other.at(0), other.at(1), other.at(2), other.at(3));
+      let transformed: [f32; 4] = matrix.transform(&vector).into();
+
+      let mut result = V::default();
+      for (i, value) in transformed.iter().enumerate() {
+        result.update(i, *value);
+      }
+      return result;
+    }
+
+    let mut result = V::default();
+    for (i, row) in self.as_ref().iter().enumerate() {
+      result.update(i, row.dot(other));
+    }
+    return result;
   }
 
   /// Computes the determinant of any square matrix using Laplace expansion.
+  /// 4x4 matrices use the SIMD fast path.
   fn determinant(&self) -> f32 {
+    if let Some(matrix) = to_mat4::<Self, V>(self) {
+      return matrix.determinant();
+    }
+
     let (width, height) =
       (self.as_ref()[0].as_ref().len(), self.as_ref().len());
 
@@ -391,16 +665,25 @@ where
 #[cfg(test)]
 mod tests {
 
+  use std::f32::consts::FRAC_PI_2;
+
   use super::{
+    affine_inverse,
+    decompose,
     filled_matrix,
+    identity_matrix,
     perspective_matrix,
     rotate_matrix,
     submatrix,
     Matrix,
   };
-  use crate::math::{
-    matrix::translation_matrix,
-    turns_to_radians,
+  use crate::{
+    assert_approximately_equal,
+    math::{
+      matrix::translation_matrix,
+      quaternion::Quaternion,
+      turns_to_radians,
+    },
   };
 
   #[test]
@@ -448,6 +731,94 @@ mod tests {
     assert_eq!(m2.determinant(), -306.0);
   }
 
+  #[test]
+  fn square_matrix_inverse() {
+    let m = [[4.0, 7.0], [2.0, 6.0]];
+    assert_eq!(m.inverse(), [[0.6, -0.7], [-0.2, 0.4]]);
+
+    let m = [
+      [1.0, 0.0, 0.0, 5.0],
+      [0.0, 2.0, 0.0, 0.0],
+      [0.0, 0.0, 4.0, 0.0],
+      [0.0, 0.0, 0.0, 1.0],
+    ];
+    let expected = [
+      [1.0, 0.0, 0.0, -5.0],
+      [0.0, 0.5, 0.0, 0.0],
+      [0.0, 0.0, 0.25, 0.0],
+      [0.0, 0.0, 0.0, 1.0],
+    ];
+    assert_eq!(m.inverse(), expected);
+    assert_eq!(m.transform(&[1.0, 1.0, 1.0, 1.0]), [6.0, 2.0, 4.0, 1.0]);
+
+    let singular = [[1.0, 2.0], [2.0, 4.0]];
+    assert!(std::panic::catch_unwind(|| singular.inverse()).is_err());
+  }
+
+  #[test]
+  fn singular_and_non_square_inverses_are_errors() {
+    assert!([[1.0, 2.0], [2.0, 4.0]].try_inverse().is_err());
+    assert!([[0.0; 4]; 4].try_inverse().is_err());
+    assert!([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]].try_inverse().is_err());
+
+    let inverse = [[0.0, 1.0], [1.0, 0.0]].try_inverse().unwrap();
+    assert_eq!(inverse, [[0.0, 1.0], [1.0, 0.0]]);
+  }
+
+  #[test]
+  fn affine_inverse_and_decomposition() {
+    let rotation = Quaternion::from_axis_angle([0.0, 0.0, 1.0], FRAC_PI_2);
+    let rotation_columns = rotation.to_matrix4();
+    let scale = [2.0, 3.0, 4.0];
+
+    let mut matrix: [[f32; 4]; 4] = translation_matrix([1.0, 2.0, 3.0]);
+    for row in 0..3 {
+      for column in 0..3 {
+        matrix[row][column] = rotation_columns[column][row] * scale[column];
+      }
+    }
+
+    let inverse = affine_inverse(&matrix).unwrap();
+    let identity = matrix.multiply(&inverse);
+    for (row, values) in identity.iter().enumerate() {
+      for (column, value) in values.iter().enumerate() {
+        let expected = if row == column { 1.0 } else { 0.0 };
+        assert_approximately_equal!(value, expected, 1e-5);
+      }
+    }
+
+    let decomposition = decompose(&matrix).unwrap();
+    assert_eq!(decomposition.translation, [1.0, 2.0, 3.0]);
+    for (actual, expected) in decomposition.scale.iter().zip(scale.iter()) {
+      assert_approximately_equal!(actual, expected, 1e-5);
+    }
+    assert_approximately_equal!(
+      decomposition.rotation.dot(&rotation).abs(),
+      1.0,
+      1e-5
+    );
+
+    // Mirroring is represented with a negative x scale.
+    let mut mirrored: [[f32; 4]; 4] = identity_matrix(4, 4);
+    mirrored[1][1] = -1.0;
+    let decomposition = decompose(&mirrored).unwrap();
+    assert_eq!(decomposition.scale, [-1.0, 1.0, 1.0]);
+
+    let mut singular = matrix;
+    singular[0][0] = 0.0;
+    singular[1][0] = 0.0;
+    assert!(affine_inverse(&singular).is_err());
+    assert!(decompose(&singular).is_err());
+
+    let mut sheared: [[f32; 4]; 4] = identity_matrix(4, 4);
+    sheared[0][1] = 1.0;
+    assert!(decompose(&sheared).is_err());
+
+    let mut projective = matrix;
+    projective[3] = [0.0, 0.0, -1.0, 0.0];
+    assert!(affine_inverse(&projective).is_err());
+  }
+
   #[test]
   fn non_square_matrix_determinant() {
     let m = [[3.0, 8.0], [4.0, 6.0], [0.0, 1.0]];
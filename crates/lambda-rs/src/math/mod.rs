@@ -1,6 +1,11 @@
 //! Lambda Math Types and operations
 
+pub mod interpolation;
 pub mod matrix;
+pub mod noise;
+pub mod quaternion;
+pub mod ray;
+pub mod simd;
 pub mod vector;
 
 pub enum Angle {
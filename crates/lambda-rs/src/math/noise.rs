@@ -0,0 +1,379 @@
+//! Seeded gradient noise for procedural generation. Perlin and simplex noise
+//! are provided in two & three dimensions along with fractal brownian motion
+//! for layering octaves of either.
+
+use super::interpolation::lerp;
+
+/// The gradients used by 3D noise, which point at the edges of a cube. 2D
+/// noise uses the first eight, which point at the edges & corners of a square.
+const GRADIENTS: [[f32; 3]; 12] = [
+  [1.0, 1.0, 0.0],
+  [-1.0, 1.0, 0.0],
+  [1.0, -1.0, 0.0],
+  [-1.0, -1.0, 0.0],
+  [1.0, 0.0, 1.0],
+  [-1.0, 0.0, 1.0],
+  [1.0, 0.0, -1.0],
+  [-1.0, 0.0, -1.0],
+  [0.0, 1.0, 1.0],
+  [0.0, -1.0, 1.0],
+  [0.0, 1.0, -1.0],
+  [0.0, -1.0, -1.0],
+];
+
+/// Skew factors that map between the square & simplex grids in 2D and 3D.
+const SKEW_2D: f32 = 0.366_025_42; // (sqrt(3) - 1) / 2
+const UNSKEW_2D: f32 = 0.211_324_87; // (3 - sqrt(3)) / 6
+const SKEW_3D: f32 = 1.0 / 3.0;
+const UNSKEW_3D: f32 = 1.0 / 6.0;
+
+// ---------------------------------- NOISE ------------------------------------
+
+/// A source of gradient noise. Noise with the same seed always produces the
+/// same values, and every function returns values between roughly -1 and 1.
+#[derive(Clone)]
+pub struct Noise {
+  permutation: [u8; 512],
+}
+
+impl Noise {
+  /// Creates noise whose gradients are shuffled by the seed.
+  pub fn new(seed: u64) -> Self {
+    let mut table: [u8; 256] = [0; 256];
+    for (index, value) in table.iter_mut().enumerate() {
+      *value = index as u8;
+    }
+
+    // Fisher-Yates shuffle driven by splitmix64, which is enough to spread
+    // nearby seeds apart without depending on a random number crate.
+    let mut state = seed;
+    for i in (1..table.len()).rev() {
+      state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+      let mut z = state;
+      z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+      z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+      z ^= z >> 31;
+      table.swap(i, (z % (i as u64 + 1)) as usize);
+    }
+
+    let mut permutation = [0; 512];
+    for (index, value) in permutation.iter_mut().enumerate() {
+      *value = table[index % 256];
+    }
+    return Self { permutation };
+  }
+
+  fn hash(&self, x: i32, y: i32, z: i32) -> usize {
+    let p = &self.permutation;
+    let x = (x & 255) as usize;
+    let y = (y & 255) as usize;
+    let z = (z & 255) as usize;
+    return p[p[p[x] as usize + y] as usize + z] as usize;
+  }
+
+  fn gradient_2d(&self, x: i32, y: i32) -> [f32; 2] {
+    let [gx, gy, _] = GRADIENTS[self.hash(x, y, 0) % 8];
+    return [gx, gy];
+  }
+
+  fn gradient_3d(&self, x: i32, y: i32, z: i32) -> [f32; 3] {
+    return GRADIENTS[self.hash(x, y, z) % 12];
+  }
+
+  /// Perlin noise, which is 0 at every integer coordinate.
+  pub fn perlin_2d(&self, x: f32, y: f32) -> f32 {
+    let (cell_x, cell_y) = (x.floor(), y.floor());
+    let (fx, fy) = (x - cell_x, y - cell_y);
+    let (ix, iy) = (cell_x as i32, cell_y as i32);
+
+    let corner = |dx: i32, dy: i32| {
+      let [gx, gy] = self.gradient_2d(ix + dx, iy + dy);
+      return gx * (fx - dx as f32) + gy * (fy - dy as f32);
+    };
+
+    let (u, v) = (fade(fx), fade(fy));
+    let bottom = lerp(corner(0, 0), corner(1, 0), u);
+    let top = lerp(corner(0, 1), corner(1, 1), u);
+    return lerp(bottom, top, v).clamp(-1.0, 1.0);
+  }
+
+  /// Perlin noise, which is 0 at every integer coordinate.
+  pub fn perlin_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+    let (cell_x, cell_y, cell_z) = (x.floor(), y.floor(), z.floor());
+    let (fx, fy, fz) = (x - cell_x, y - cell_y, z - cell_z);
+    let (ix, iy, iz) = (cell_x as i32, cell_y as i32, cell_z as i32);
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+      let [gx, gy, gz] = self.gradient_3d(ix + dx, iy + dy, iz + dz);
+      return gx * (fx - dx as f32)
+        + gy * (fy - dy as f32)
+        + gz * (fz - dz as f32);
+    };
+
+    let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+    let near = lerp(
+      lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+      lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+      v,
+    );
+    let far = lerp(
+      lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+      lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+      v,
+    );
+    return lerp(near, far, w).clamp(-1.0, 1.0);
+  }
+
+  /// Simplex noise, which has fewer directional artifacts than Perlin noise
+  /// and is cheaper to compute in higher dimensions.
+  pub fn simplex_2d(&self, x: f32, y: f32) -> f32 {
+    // Find the simplex cell containing the point.
+    let skew = (x + y) * SKEW_2D;
+    let (i, j) = ((x + skew).floor(), (y + skew).floor());
+    let unskew = (i + j) * UNSKEW_2D;
+    let x0 = x - (i - unskew);
+    let y0 = y - (j - unskew);
+
+    // The middle corner depends on which triangle of the cell the point is in.
+    let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+    let offsets = [
+      (0, 0, x0, y0),
+      (
+        i1,
+        j1,
+        x0 - i1 as f32 + UNSKEW_2D,
+        y0 - j1 as f32 + UNSKEW_2D,
+      ),
+      (1, 1, x0 - 1.0 + 2.0 * UNSKEW_2D, y0 - 1.0 + 2.0 * UNSKEW_2D),
+    ];
+
+    let (i, j) = (i as i32, j as i32);
+    let mut total = 0.0;
+    for (di, dj, dx, dy) in offsets {
+      let falloff = 0.5 - dx * dx - dy * dy;
+      if falloff > 0.0 {
+        let [gx, gy] = self.gradient_2d(i + di, j + dj);
+        total += falloff.powi(4) * (gx * dx + gy * dy);
+      }
+    }
+    return (70.0 * total).clamp(-1.0, 1.0);
+  }
+
+  /// Simplex noise, which has fewer directional artifacts than Perlin noise
+  /// and is cheaper to compute in higher dimensions.
+  pub fn simplex_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+    let skew = (x + y + z) * SKEW_3D;
+    let (i, j, k) =
+      ((x + skew).floor(), (y + skew).floor(), (z + skew).floor());
+    let unskew = (i + j + k) * UNSKEW_3D;
+    let x0 = x - (i - unskew);
+    let y0 = y - (j - unskew);
+    let z0 = z - (k - unskew);
+
+    // Order the axes to find which of the six tetrahedra contains the point.
+    let (second, third) = if x0 >= y0 {
+      if y0 >= z0 {
+        ([1, 0, 0], [1, 1, 0])
+      } else if x0 >= z0 {
+        ([1, 0, 0], [1, 0, 1])
+      } else {
+        ([0, 0, 1], [1, 0, 1])
+      }
+    } else if y0 < z0 {
+      ([0, 0, 1], [0, 1, 1])
+    } else if x0 < z0 {
+      ([0, 1, 0], [0, 1, 1])
+    } else {
+      ([0, 1, 0], [1, 1, 0])
+    };
+
+    let (i, j, k) = (i as i32, j as i32, k as i32);
+    let mut total = 0.0;
+    for (index, corner) in
+      [[0, 0, 0], second, third, [1, 1, 1]].iter().enumerate()
+    {
+      let unskew = index as f32 * UNSKEW_3D;
+      let dx = x0 - corner[0] as f32 + unskew;
+      let dy = y0 - corner[1] as f32 + unskew;
+      let dz = z0 - corner[2] as f32 + unskew;
+
+      let falloff = 0.6 - dx * dx - dy * dy - dz * dz;
+      if falloff > 0.0 {
+        let [gx, gy, gz] =
+          self.gradient_3d(i + corner[0], j + corner[1], k + corner[2]);
+        total += falloff.powi(4) * (gx * dx + gy * dy + gz * dz);
+      }
+    }
+    return (32.0 * total).clamp(-1.0, 1.0);
+  }
+
+  /// Layers octaves of 2D noise from the given function, such as
+  /// `Noise::perlin_2d`, into fractal noise.
+  pub fn fbm_2d(
+    &self,
+    x: f32,
+    y: f32,
+    fbm: &Fbm,
+    noise: fn(&Noise, f32, f32) -> f32,
+  ) -> f32 {
+    return fbm.layer(|frequency| noise(self, x * frequency, y * frequency));
+  }
+
+  /// Layers octaves of 3D noise from the given function, such as
+  /// `Noise::simplex_3d`, into fractal noise.
+  pub fn fbm_3d(
+    &self,
+    x: f32,
+    y: f32,
+    z: f32,
+    fbm: &Fbm,
+    noise: fn(&Noise, f32, f32, f32) -> f32,
+  ) -> f32 {
+    return fbm.layer(|frequency| {
+      return noise(self, x * frequency, y * frequency, z * frequency);
+    });
+  }
+}
+
+/// The quintic curve used to blend between Perlin noise gradients.
+fn fade(t: f32) -> f32 {
+  return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+// ----------------------------------- FBM -------------------------------------
+
+/// Settings for fractal brownian motion, which sums octaves of noise that
+/// increase in frequency and decrease in amplitude.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fbm {
+  octaves: u32,
+  lacunarity: f32,
+  gain: f32,
+}
+
+impl Fbm {
+  /// Six octaves that each double in frequency and halve in amplitude.
+  pub fn new() -> Self {
+    return Self {
+      octaves: 6,
+      lacunarity: 2.0,
+      gain: 0.5,
+    };
+  }
+
+  /// The number of octaves to sum. At least one octave is always used.
+  pub fn with_octaves(mut self, octaves: u32) -> Self {
+    self.octaves = octaves.max(1);
+    return self;
+  }
+
+  /// How much the frequency is multiplied by for each octave.
+  pub fn with_lacunarity(mut self, lacunarity: f32) -> Self {
+    self.lacunarity = lacunarity;
+    return self;
+  }
+
+  /// How much the amplitude is multiplied by for each octave.
+  pub fn with_gain(mut self, gain: f32) -> Self {
+    self.gain = gain;
+    return self;
+  }
+
+  /// Sums the octaves of noise, normalized by the total amplitude so that the
+  /// result stays within the range of the noise.
+  fn layer(&self, noise: impl Fn(f32) -> f32) -> f32 {
+    let (mut frequency, mut amplitude) = (1.0, 1.0);
+    let (mut total, mut total_amplitude) = (0.0, 0.0);
+    for _ in 0..self.octaves {
+      total += noise(frequency) * amplitude;
+      total_amplitude += amplitude;
+      frequency *= self.lacunarity;
+      amplitude *= self.gain;
+    }
+    return total / total_amplitude;
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    Fbm,
+    Noise,
+  };
+
+  /// Samples a grid of points that avoids integer coordinates.
+  fn samples() -> impl Iterator<Item = (f32, f32, f32)> {
+    return (0..1000).map(|index| {
+      let t = index as f32;
+      return (t * 0.173 - 40.0, t * 0.311 + 0.5, t * -0.097);
+    });
+  }
+
+  #[test]
+  fn noise_is_seeded() {
+    let (a, b) = (Noise::new(1), Noise::new(2));
+    let other_a = Noise::new(1);
+
+    let mut differences = 0;
+    for (x, y, z) in samples() {
+      assert_eq!(a.perlin_3d(x, y, z), other_a.perlin_3d(x, y, z));
+      assert_eq!(a.simplex_2d(x, y), other_a.simplex_2d(x, y));
+      if a.simplex_3d(x, y, z) != b.simplex_3d(x, y, z) {
+        differences += 1;
+      }
+    }
+    assert!(differences > 900);
+  }
+
+  #[test]
+  fn noise_is_bounded_and_continuous() {
+    let noise = Noise::new(42);
+    let mut has_positive = false;
+    let mut has_negative = false;
+
+    for (x, y, z) in samples() {
+      for value in [
+        noise.perlin_2d(x, y),
+        noise.perlin_3d(x, y, z),
+        noise.simplex_2d(x, y),
+        noise.simplex_3d(x, y, z),
+      ] {
+        assert!((-1.0..=1.0).contains(&value), "{} is out of range", value);
+        has_positive |= value > 0.1;
+        has_negative |= value < -0.1;
+      }
+
+      // Nearby points have similar values.
+      let step = 0.001;
+      assert!(
+        (noise.perlin_2d(x, y) - noise.perlin_2d(x + step, y)).abs() < 0.01
+      );
+      assert!(
+        (noise.simplex_3d(x, y, z) - noise.simplex_3d(x, y, z + step)).abs()
+          < 0.01
+      );
+    }
+    assert!(has_positive && has_negative);
+
+    assert_eq!(noise.perlin_2d(3.0, -7.0), 0.0);
+    assert_eq!(noise.perlin_3d(3.0, -7.0, 12.0), 0.0);
+  }
+
+  #[test]
+  fn fbm_layers_octaves() {
+    let noise = Noise::new(7);
+    let single = Fbm::new().with_octaves(0);
+    assert_eq!(
+      noise.fbm_2d(0.3, 0.6, &single, Noise::perlin_2d),
+      noise.perlin_2d(0.3, 0.6)
+    );
+
+    let fbm = Fbm::new().with_lacunarity(2.5).with_gain(0.6);
+    for (x, y, z) in samples() {
+      let value = noise.fbm_3d(x, y, z, &fbm, Noise::simplex_3d);
+      assert!((-1.0..=1.0).contains(&value));
+    }
+  }
+}
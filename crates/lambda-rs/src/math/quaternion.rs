@@ -0,0 +1,427 @@
+//! Quaternions for representing rotations without gimbal lock.
+
+use std::ops::Mul;
+
+/// Below this dot product between two rotations slerp is used, and above it
+/// the rotations are close enough that nlerp is indistinguishable & stable.
+const SLERP_THRESHOLD: f32 = 0.9995;
+
+/// A rotation represented as a unit quaternion, where w is the scalar part.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+  pub x: f32,
+  pub y: f32,
+  pub z: f32,
+  pub w: f32,
+}
+
+impl Quaternion {
+  /// Creates a quaternion from its components. The quaternion isn't
+  /// normalized.
+  pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+    return Self { x, y, z, w };
+  }
+
+  /// The quaternion that doesn't rotate.
+  pub fn identity() -> Self {
+    return Self::new(0.0, 0.0, 0.0, 1.0);
+  }
+
+  /// A rotation of the angle in radians around the axis, which doesn't need
+  /// to be normalized. The rotation is counter-clockwise when looking down the
+  /// axis. A zero length axis produces the identity.
+  pub fn from_axis_angle(axis: [f32; 3], angle: f32) -> Self {
+    let length =
+      (axis[0] * axis[0] + axis[1] * axis[1] + axis[2] * axis[2]).sqrt();
+    if length <= f32::EPSILON {
+      return Self::identity();
+    }
+
+    let (sin, cos) = (angle / 2.0).sin_cos();
+    let scale = sin / length;
+    return Self::new(axis[0] * scale, axis[1] * scale, axis[2] * scale, cos);
+  }
+
+  /// The rotation of a 3x3 orthonormal rotation matrix given as rows. The
+  /// result is normalized, so small amounts of drift in the matrix are
+  /// tolerated.
+  pub fn from_rotation_matrix(rows: [[f32; 3]; 3]) -> Self {
+    let m = rows;
+    let trace = m[0][0] + m[1][1] + m[2][2];
+
+    // Divide by the largest of the four components to stay numerically stable.
+    let quaternion = if trace > 0.0 {
+      let s = (trace + 1.0).sqrt() * 2.0;
+      Self::new(
+        (m[2][1] - m[1][2]) / s,
+        (m[0][2] - m[2][0]) / s,
+        (m[1][0] - m[0][1]) / s,
+        0.25 * s,
+      )
+    } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+      let s = (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt() * 2.0;
+      Self::new(
+        0.25 * s,
+        (m[0][1] + m[1][0]) / s,
+        (m[0][2] + m[2][0]) / s,
+        (m[2][1] - m[1][2]) / s,
+      )
+    } else if m[1][1] > m[2][2] {
+      let s = (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt() * 2.0;
+      Self::new(
+        (m[0][1] + m[1][0]) / s,
+        0.25 * s,
+        (m[1][2] + m[2][1]) / s,
+        (m[0][2] - m[2][0]) / s,
+      )
+    } else {
+      let s = (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt() * 2.0;
+      Self::new(
+        (m[0][2] + m[2][0]) / s,
+        (m[1][2] + m[2][1]) / s,
+        0.25 * s,
+        (m[1][0] - m[0][1]) / s,
+      )
+    };
+    return quaternion.normalize();
+  }
+
+  /// A rotation of euler angles in radians that are applied around the x,
+  /// then y, and then z axes (The same order as `scene::Transform`).
+  pub fn from_euler(rotation: [f32; 3]) -> Self {
+    let x = Self::from_axis_angle([1.0, 0.0, 0.0], rotation[0]);
+    let y = Self::from_axis_angle([0.0, 1.0, 0.0], rotation[1]);
+    let z = Self::from_axis_angle([0.0, 0.0, 1.0], rotation[2]);
+    return z * y * x;
+  }
+
+  /// The euler angles in radians that produce this rotation when applied
+  /// around the x, then y, and then z axes. When the rotation around y is
+  /// ±90 degrees the x & z axes line up, in which case the rotation around z
+  /// is 0.
+  pub fn to_euler(&self) -> [f32; 3] {
+    let rotation = self.normalize().rotation_rows();
+    let sin_y = (-rotation[2][0]).clamp(-1.0, 1.0);
+
+    if sin_y.abs() > 0.9999 {
+      let x = (sin_y * rotation[0][1]).atan2(rotation[1][1]);
+      return [x, sin_y.asin(), 0.0];
+    }
+
+    return [
+      rotation[2][1].atan2(rotation[2][2]),
+      sin_y.asin(),
+      rotation[1][0].atan2(rotation[0][0]),
+    ];
+  }
+
+  /// The length of the quaternion, which is 1 for rotations.
+  pub fn length(&self) -> f32 {
+    return self.dot(self).sqrt();
+  }
+
+  /// The quaternion scaled to unit length. Quaternions too short to be
+  /// normalized produce the identity.
+  pub fn normalize(&self) -> Self {
+    let length = self.length();
+    if length <= f32::EPSILON || !length.is_finite() {
+      return Self::identity();
+    }
+    return Self::new(
+      self.x / length,
+      self.y / length,
+      self.z / length,
+      self.w / length,
+    );
+  }
+
+  /// The 4 dimensional dot product of two quaternions.
+  pub fn dot(&self, other: &Self) -> f32 {
+    return self.x * other.x
+      + self.y * other.y
+      + self.z * other.z
+      + self.w * other.w;
+  }
+
+  /// The quaternion with the opposite rotation, assuming unit length.
+  pub fn conjugate(&self) -> Self {
+    return Self::new(-self.x, -self.y, -self.z, self.w);
+  }
+
+  /// The multiplicative inverse of the quaternion. Quaternions too short to
+  /// be inverted produce the identity.
+  pub fn inverse(&self) -> Self {
+    let length_squared = self.dot(self);
+    if length_squared <= f32::EPSILON {
+      return Self::identity();
+    }
+    let conjugate = self.conjugate();
+    return Self::new(
+      conjugate.x / length_squared,
+      conjugate.y / length_squared,
+      conjugate.z / length_squared,
+      conjugate.w / length_squared,
+    );
+  }
+
+  /// The rotation that applies `other` and then `self`.
+  pub fn multiply(&self, other: &Self) -> Self {
+    let (a, b) = (self, other);
+    return Self::new(
+      a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+      a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+      a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+      a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+    );
+  }
+
+  /// Rotates a vector by the quaternion.
+  pub fn rotate_vector(&self, vector: [f32; 3]) -> [f32; 3] {
+    let rotation = self.normalize().rotation_rows();
+    let mut result = [0.0; 3];
+    for (row, value) in result.iter_mut().enumerate() {
+      *value = rotation[row][0] * vector[0]
+        + rotation[row][1] * vector[1]
+        + rotation[row][2] * vector[2];
+    }
+    return result;
+  }
+
+  /// The 3x3 rotation matrix of a unit quaternion as rows.
+  fn rotation_rows(&self) -> [[f32; 3]; 3] {
+    let Self { x, y, z, w } = *self;
+    return [
+      [
+        1.0 - 2.0 * (y * y + z * z),
+        2.0 * (x * y - z * w),
+        2.0 * (x * z + y * w),
+      ],
+      [
+        2.0 * (x * y + z * w),
+        1.0 - 2.0 * (x * x + z * z),
+        2.0 * (y * z - x * w),
+      ],
+      [
+        2.0 * (x * z - y * w),
+        2.0 * (y * z + x * w),
+        1.0 - 2.0 * (x * x + y * y),
+      ],
+    ];
+  }
+
+  /// The column major 4x4 rotation matrix of the quaternion, which is the
+  /// layout expected by shaders.
+  pub fn to_matrix4(&self) -> [[f32; 4]; 4] {
+    let rotation = self.normalize().rotation_rows();
+    let mut matrix = [[0.0; 4]; 4];
+    for (row, values) in rotation.iter().enumerate() {
+      for (column, value) in values.iter().enumerate() {
+        matrix[column][row] = *value;
+      }
+    }
+    matrix[3][3] = 1.0;
+    return matrix;
+  }
+
+  /// Linearly interpolates between two rotations along the shortest path and
+  /// normalizes the result. Faster than slerp, but the rotation speed isn't
+  /// constant across the interpolation.
+  pub fn nlerp(&self, other: &Self, t: f32) -> Self {
+    let other = self.shortest_path_to(other);
+    return Self::new(
+      self.x + (other.x - self.x) * t,
+      self.y + (other.y - self.y) * t,
+      self.z + (other.z - self.z) * t,
+      self.w + (other.w - self.w) * t,
+    )
+    .normalize();
+  }
+
+  /// Spherically interpolates between two rotations along the shortest path
+  /// at a constant rotation speed.
+  pub fn slerp(&self, other: &Self, t: f32) -> Self {
+    let start = self.normalize();
+    let end = start.shortest_path_to(&other.normalize());
+
+    let cosine = start.dot(&end).clamp(-1.0, 1.0);
+    if cosine > SLERP_THRESHOLD {
+      return start.nlerp(&end, t);
+    }
+
+    let angle = cosine.acos();
+    let sin = angle.sin();
+    let start_weight = ((1.0 - t) * angle).sin() / sin;
+    let end_weight = (t * angle).sin() / sin;
+    return Self::new(
+      start.x * start_weight + end.x * end_weight,
+      start.y * start_weight + end.y * end_weight,
+      start.z * start_weight + end.z * end_weight,
+      start.w * start_weight + end.w * end_weight,
+    );
+  }
+
+  /// Quaternions q and -q are the same rotation, so interpolating takes the
+  /// short way around by flipping the other quaternion when they're more than
+  /// 180 degrees apart.
+  fn shortest_path_to(&self, other: &Self) -> Self {
+    if self.dot(other) < 0.0 {
+      return Self::new(-other.x, -other.y, -other.z, -other.w);
+    }
+    return *other;
+  }
+}
+
+impl Default for Quaternion {
+  fn default() -> Self {
+    return Self::identity();
+  }
+}
+
+impl Mul for Quaternion {
+  type Output = Quaternion;
+
+  fn mul(self, other: Quaternion) -> Quaternion {
+    return self.multiply(&other);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::f32::consts::{
+    FRAC_PI_2,
+    FRAC_PI_4,
+    PI,
+  };
+
+  use super::Quaternion;
+  use crate::assert_approximately_equal;
+
+  fn assert_vectors_close(actual: [f32; 3], expected: [f32; 3]) {
+    for (actual, expected) in actual.iter().zip(expected.iter()) {
+      assert_approximately_equal!(actual, expected, 1e-5);
+    }
+  }
+
+  #[test]
+  fn axis_angle_rotations() {
+    let rotation = Quaternion::from_axis_angle([0.0, 0.0, 2.0], FRAC_PI_2);
+    assert_approximately_equal!(rotation.length(), 1.0, 1e-6);
+    assert_vectors_close(
+      rotation.rotate_vector([1.0, 0.0, 0.0]),
+      [0.0, 1.0, 0.0],
+    );
+
+    // Zero length axes don't rotate.
+    let identity = Quaternion::from_axis_angle([0.0; 3], PI);
+    assert_eq!(identity, Quaternion::identity());
+
+    let matrix = rotation.to_matrix4();
+    assert_vectors_close(
+      [matrix[0][0], matrix[0][1], matrix[0][2]],
+      [0.0, 1.0, 0.0],
+    );
+    assert_eq!(matrix[3], [0.0, 0.0, 0.0, 1.0]);
+  }
+
+  #[test]
+  fn normalization_edge_cases() {
+    assert_eq!(
+      Quaternion::new(0.0, 0.0, 0.0, 0.0).normalize(),
+      Quaternion::identity()
+    );
+    assert_eq!(
+      Quaternion::new(f32::INFINITY, 0.0, 0.0, 1.0).normalize(),
+      Quaternion::identity()
+    );
+    assert_eq!(
+      Quaternion::new(0.0, 0.0, 0.0, 0.0).inverse(),
+      Quaternion::identity()
+    );
+
+    let scaled = Quaternion::new(0.0, 0.0, 0.0, 4.0);
+    assert_eq!(scaled.normalize(), Quaternion::identity());
+    assert_eq!(scaled.inverse(), Quaternion::new(0.0, 0.0, 0.0, 0.25));
+
+    // Non unit quaternions still rotate vectors without scaling them.
+    let rotation = Quaternion::from_axis_angle([0.0, 1.0, 0.0], FRAC_PI_2)
+      .multiply(&Quaternion::new(0.0, 0.0, 0.0, 3.0));
+    assert_vectors_close(
+      rotation.rotate_vector([1.0, 0.0, 0.0]),
+      [0.0, 0.0, -1.0],
+    );
+  }
+
+  #[test]
+  fn multiplication_composes_rotations() {
+    let x = Quaternion::from_axis_angle([1.0, 0.0, 0.0], FRAC_PI_2);
+    let z = Quaternion::from_axis_angle([0.0, 0.0, 1.0], FRAC_PI_2);
+
+    // Rotating around x and then z moves y onto z and then leaves it there.
+    let rotation = z * x;
+    assert_vectors_close(
+      rotation.rotate_vector([0.0, 1.0, 0.0]),
+      [0.0, 0.0, 1.0],
+    );
+    assert_vectors_close(
+      rotation.rotate_vector([1.0, 0.0, 0.0]),
+      [0.0, 1.0, 0.0],
+    );
+
+    let undone = rotation * rotation.inverse();
+    assert_approximately_equal!(undone.w, 1.0, 1e-6);
+  }
+
+  #[test]
+  fn euler_conversions() {
+    let rotation = [0.3, -0.7, 1.2];
+    let quaternion = Quaternion::from_euler(rotation);
+    assert_vectors_close(quaternion.to_euler(), rotation);
+
+    // The y axis rotation is ±90 degrees so the x & z axes are aligned.
+    let locked = Quaternion::from_euler([0.5, FRAC_PI_2, 0.0]);
+    let euler = locked.to_euler();
+    assert_vectors_close(
+      Quaternion::from_euler(euler).rotate_vector([0.3, 0.5, 0.7]),
+      locked.rotate_vector([0.3, 0.5, 0.7]),
+    );
+  }
+
+  #[test]
+  fn rotation_matrix_conversions() {
+    // Half turns exercise every branch of the conversion.
+    for axis in [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]] {
+      for angle in [0.5, PI] {
+        let rotation = Quaternion::from_axis_angle(axis, angle);
+        let converted =
+          Quaternion::from_rotation_matrix(rotation.rotation_rows());
+        assert_approximately_equal!(converted.dot(&rotation).abs(), 1.0, 1e-5);
+      }
+    }
+  }
+
+  #[test]
+  fn interpolation() {
+    let start = Quaternion::identity();
+    let end = Quaternion::from_axis_angle([0.0, 1.0, 0.0], FRAC_PI_2);
+    let halfway = Quaternion::from_axis_angle([0.0, 1.0, 0.0], FRAC_PI_4);
+
+    let slerped = start.slerp(&end, 0.5);
+    assert_approximately_equal!(slerped.dot(&halfway), 1.0, 1e-5);
+    assert_approximately_equal!(
+      start.nlerp(&end, 0.5).dot(&halfway),
+      1.0,
+      1e-5
+    );
+    assert_eq!(start.slerp(&end, 0.0), start);
+
+    // The negated quaternion is the same rotation, so the interpolation still
+    // takes the short path.
+    let negated = Quaternion::new(-end.x, -end.y, -end.z, -end.w);
+    let slerped = start.slerp(&negated, 0.5);
+    assert_approximately_equal!(slerped.dot(&halfway).abs(), 1.0, 1e-5);
+
+    // Nearly identical rotations fall back to nlerp.
+    let close = Quaternion::from_axis_angle([0.0, 1.0, 0.0], 1e-4);
+    assert_approximately_equal!(start.slerp(&close, 0.5).length(), 1.0, 1e-6);
+  }
+}
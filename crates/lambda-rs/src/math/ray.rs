@@ -0,0 +1,382 @@
+//! Rays and intersection tests for picking objects with the mouse. Camera
+//! matrices are column major (The layout expected by shaders) and normalized
+//! device coordinates have y pointing up with depth between 0 and 1.
+
+use super::{
+  matrix::Matrix,
+  vector::Vector,
+};
+
+/// Distances below this are treated as zero to avoid self intersections.
+const EPSILON: f32 = 1e-6;
+
+// ---------------------------------- SHAPES -----------------------------------
+
+/// An axis aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+  pub min: [f32; 3],
+  pub max: [f32; 3],
+}
+
+impl Aabb {
+  pub fn new(min: [f32; 3], max: [f32; 3]) -> Self {
+    return Self { min, max };
+  }
+
+  /// The smallest box containing every point, or None if there are no points.
+  pub fn from_points(
+    points: impl IntoIterator<Item = [f32; 3]>,
+  ) -> Option<Self> {
+    let mut points = points.into_iter();
+    let first = points.next()?;
+    let mut bounds = Self::new(first, first);
+    for point in points {
+      for (axis, value) in point.iter().enumerate() {
+        bounds.min[axis] = bounds.min[axis].min(*value);
+        bounds.max[axis] = bounds.max[axis].max(*value);
+      }
+    }
+    return Some(bounds);
+  }
+
+  pub fn center(&self) -> [f32; 3] {
+    return self.min.add(&self.max).scale(0.5);
+  }
+}
+
+/// A sphere.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sphere {
+  pub center: [f32; 3],
+  pub radius: f32,
+}
+
+impl Sphere {
+  pub fn new(center: [f32; 3], radius: f32) -> Self {
+    return Self { center, radius };
+  }
+}
+
+/// An infinite plane containing every point p where dot(normal, p) equals the
+/// distance.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+  pub normal: [f32; 3],
+  pub distance: f32,
+}
+
+impl Plane {
+  /// The plane through the point facing along the normal. Panics if the
+  /// normal has a length of zero.
+  pub fn from_point_normal(point: [f32; 3], normal: [f32; 3]) -> Self {
+    let normal = normal.normalize();
+    return Self {
+      normal,
+      distance: normal.dot(&point),
+    };
+  }
+}
+
+/// Where a ray hit a triangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TriangleHit {
+  /// The distance along the ray to the hit.
+  pub distance: f32,
+  /// The weights of each corner of the triangle at the hit, which can be used
+  /// to interpolate vertex attributes.
+  pub barycentric: [f32; 3],
+}
+
+// ----------------------------------- RAY -------------------------------------
+
+/// A half line starting at the origin and extending along the direction, which
+/// always has unit length.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+  origin: [f32; 3],
+  direction: [f32; 3],
+}
+
+impl Ray {
+  /// Creates a ray, normalizing the direction. Panics if the direction has a
+  /// length of zero.
+  pub fn new(origin: [f32; 3], direction: [f32; 3]) -> Self {
+    return Self {
+      origin,
+      direction: direction.normalize(),
+    };
+  }
+
+  /// The ray through a position on the screen in pixels, measured from the
+  /// top left of a screen with the given size. The ray starts on the near
+  /// plane of the camera. Returns an error if the view projection matrix
+  /// can't be inverted.
+  pub fn from_screen(
+    screen_position: [f32; 2],
+    screen_size: [f32; 2],
+    view: &[[f32; 4]; 4],
+    projection: &[[f32; 4]; 4],
+  ) -> Result<Self, String> {
+    let x = 2.0 * screen_position[0] / screen_size[0] - 1.0;
+    let y = 1.0 - 2.0 * screen_position[1] / screen_size[1];
+
+    // The math module treats arrays as rows, so the column major
+    // projection * view is computed in reverse and inverted as is.
+    let inverse = view.multiply(projection).try_inverse()?;
+    let near = unproject(&inverse, [x, y, 0.0])?;
+    let far = unproject(&inverse, [x, y, 1.0])?;
+
+    let direction = far.subtract(&near);
+    if direction.length() <= EPSILON {
+      return Err("The camera's near & far planes overlap".to_string());
+    }
+    return Ok(Self::new(near, direction));
+  }
+
+  pub fn origin(&self) -> [f32; 3] {
+    return self.origin;
+  }
+
+  pub fn direction(&self) -> [f32; 3] {
+    return self.direction;
+  }
+
+  /// The point at the distance along the ray.
+  pub fn at(&self, distance: f32) -> [f32; 3] {
+    return self.origin.add(&self.direction.scale(distance));
+  }
+
+  /// The distance to where the ray enters the box, or 0 if the ray starts
+  /// inside of it.
+  pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+    let mut near = 0.0_f32;
+    let mut far = f32::INFINITY;
+
+    for axis in 0..3 {
+      let origin = self.origin[axis];
+      let direction = self.direction[axis];
+
+      if direction.abs() <= EPSILON {
+        // Parallel rays only hit if they start between the slab's planes.
+        if origin < aabb.min[axis] || origin > aabb.max[axis] {
+          return None;
+        }
+        continue;
+      }
+
+      let a = (aabb.min[axis] - origin) / direction;
+      let b = (aabb.max[axis] - origin) / direction;
+      near = near.max(a.min(b));
+      far = far.min(a.max(b));
+      if near > far {
+        return None;
+      }
+    }
+    return Some(near);
+  }
+
+  /// The distance to where the ray enters the sphere, or to where the ray
+  /// leaves the sphere if it starts inside of it.
+  pub fn intersect_sphere(&self, sphere: &Sphere) -> Option<f32> {
+    let offset = self.origin.subtract(&sphere.center);
+    let b = offset.dot(&self.direction);
+    let c = offset.dot(&offset) - sphere.radius * sphere.radius;
+
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+      return None;
+    }
+
+    let root = discriminant.sqrt();
+    let (near, far) = (-b - root, -b + root);
+    if near >= 0.0 {
+      return Some(near);
+    }
+    if far >= 0.0 {
+      return Some(far);
+    }
+    return None;
+  }
+
+  /// The distance to where the ray crosses the plane from either side.
+  pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+    let facing = plane.normal.dot(&self.direction);
+    if facing.abs() <= EPSILON {
+      return None;
+    }
+
+    let distance = (plane.distance - plane.normal.dot(&self.origin)) / facing;
+    if distance < 0.0 {
+      return None;
+    }
+    return Some(distance);
+  }
+
+  /// Where the ray hits the triangle from either side, using the
+  /// Möller-Trumbore algorithm.
+  pub fn intersect_triangle(
+    &self,
+    triangle: [[f32; 3]; 3],
+  ) -> Option<TriangleHit> {
+    let [a, b, c] = triangle;
+    let edge_ab = b.subtract(&a);
+    let edge_ac = c.subtract(&a);
+
+    let p = self.direction.cross(&edge_ac);
+    let determinant = edge_ab.dot(&p);
+    if determinant.abs() <= EPSILON {
+      // The ray is parallel to the triangle or the triangle is degenerate.
+      return None;
+    }
+
+    let inverse_determinant = 1.0 / determinant;
+    let offset = self.origin.subtract(&a);
+    let u = offset.dot(&p) * inverse_determinant;
+    if !(0.0..=1.0).contains(&u) {
+      return None;
+    }
+
+    let q = offset.cross(&edge_ab);
+    let v = self.direction.dot(&q) * inverse_determinant;
+    if v < 0.0 || u + v > 1.0 {
+      return None;
+    }
+
+    let distance = edge_ac.dot(&q) * inverse_determinant;
+    if distance < EPSILON {
+      return None;
+    }
+
+    return Some(TriangleHit {
+      distance,
+      barycentric: [1.0 - u - v, u, v],
+    });
+  }
+}
+
+/// Transforms a point in normalized device coordinates by a column major
+/// inverse view projection matrix and divides by w.
+fn unproject(
+  inverse: &[[f32; 4]; 4],
+  point: [f32; 3],
+) -> Result<[f32; 3], String> {
+  let [x, y, z] = point;
+  let mut result = [0.0; 4];
+  for (row, value) in result.iter_mut().enumerate() {
+    *value = inverse[0][row] * x
+      + inverse[1][row] * y
+      + inverse[2][row] * z
+      + inverse[3][row];
+  }
+
+  let w = result[3];
+  if w.abs() <= EPSILON {
+    return Err("Cannot unproject a point at infinity".to_string());
+  }
+  return Ok([result[0] / w, result[1] / w, result[2] / w]);
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    Aabb,
+    Plane,
+    Ray,
+    Sphere,
+  };
+  use crate::assert_approximately_equal;
+
+  const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+  ];
+
+  #[test]
+  fn rays_from_the_screen() {
+    // An orthographic camera looking down +z that is moved back along z.
+    let mut view = IDENTITY;
+    view[3] = [0.0, 0.0, 5.0, 1.0];
+    let mut projection = IDENTITY;
+    projection[0][0] = 0.5;
+    projection[2][2] = 0.1;
+
+    let ray =
+      Ray::from_screen([50.0, 25.0], [100.0, 100.0], &view, &projection)
+        .unwrap();
+    let [x, y, z] = ray.origin();
+    assert_approximately_equal!(x, 0.0, 1e-5);
+    assert_approximately_equal!(y, 0.5, 1e-5);
+    assert_approximately_equal!(z, -5.0, 1e-5);
+    assert_eq!(ray.direction(), [0.0, 0.0, 1.0]);
+
+    assert!(
+      Ray::from_screen([0.0, 0.0], [1.0, 1.0], &view, &[[0.0; 4]; 4]).is_err()
+    );
+  }
+
+  #[test]
+  fn ray_shape_intersections() {
+    let ray = Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 2.0]);
+
+    let aabb =
+      Aabb::from_points([[-1.0, -1.0, -1.0], [1.0, 1.0, 1.0]]).unwrap();
+    assert_eq!(ray.intersect_aabb(&aabb), Some(4.0));
+    assert_eq!(
+      Ray::new([0.0; 3], [1.0, 0.0, 0.0]).intersect_aabb(&aabb),
+      Some(0.0)
+    );
+    assert_eq!(
+      Ray::new([2.0, 0.0, -5.0], [0.0, 0.0, 1.0]).intersect_aabb(&aabb),
+      None
+    );
+    assert_eq!(Aabb::from_points([]), None);
+
+    let sphere = Sphere::new([0.0, 0.0, 0.0], 2.0);
+    assert_eq!(ray.intersect_sphere(&sphere), Some(3.0));
+    assert_eq!(
+      Ray::new([0.0; 3], [0.0, 1.0, 0.0]).intersect_sphere(&sphere),
+      Some(2.0)
+    );
+    assert_eq!(
+      Ray::new([0.0, 0.0, 5.0], [0.0, 0.0, 1.0]).intersect_sphere(&sphere),
+      None
+    );
+
+    let plane = Plane::from_point_normal([0.0, 0.0, 1.0], [0.0, 0.0, -3.0]);
+    assert_eq!(ray.intersect_plane(&plane), Some(6.0));
+    assert_eq!(
+      Ray::new([0.0; 3], [1.0, 0.0, 0.0]).intersect_plane(&plane),
+      None
+    );
+  }
+
+  #[test]
+  fn ray_triangle_intersections() {
+    let triangle = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+    let ray = Ray::new([0.25, 0.5, -1.0], [0.0, 0.0, 1.0]);
+
+    let hit = ray.intersect_triangle(triangle).unwrap();
+    assert_approximately_equal!(hit.distance, 1.0, 1e-6);
+    let [a, b, c] = hit.barycentric;
+    assert_approximately_equal!(a, 0.25, 1e-6);
+    assert_approximately_equal!(b, 0.25, 1e-6);
+    assert_approximately_equal!(c, 0.5, 1e-6);
+    assert_eq!(ray.at(hit.distance), [0.25, 0.5, 0.0]);
+
+    // Misses outside of the triangle, behind the ray, and parallel to it.
+    assert!(Ray::new([0.75, 0.75, -1.0], [0.0, 0.0, 1.0])
+      .intersect_triangle(triangle)
+      .is_none());
+    assert!(Ray::new([0.25, 0.25, 1.0], [0.0, 0.0, 1.0])
+      .intersect_triangle(triangle)
+      .is_none());
+    assert!(Ray::new([0.25, 0.25, -1.0], [1.0, 0.0, 0.0])
+      .intersect_triangle(triangle)
+      .is_none());
+  }
+}
@@ -0,0 +1,584 @@
+//! Stack allocated vector & matrix types for the math that runs every frame.
+//! Four wide operations use SSE on x86_64 and NEON on aarch64, both of which
+//! are always available on those targets, and fall back to scalar math
+//! everywhere else. Matrices are stored as rows, the same layout used by the
+//! `Matrix` trait for arrays.
+
+#[cfg(target_arch = "aarch64")]
+use std::arch::aarch64::{
+  vaddq_f32,
+  vdupq_n_f32,
+  vld1q_f32,
+  vmulq_f32,
+  vst1q_f32,
+  vsubq_f32,
+};
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::{
+  _mm_add_ps,
+  _mm_load_ps,
+  _mm_mul_ps,
+  _mm_set1_ps,
+  _mm_store_ps,
+  _mm_sub_ps,
+};
+use std::ops::{
+  Add,
+  Mul,
+  Neg,
+  Sub,
+};
+
+// --------------------------------- LANES -------------------------------------
+
+/// Four f32 lanes aligned for SIMD loads & stores.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C, align(16))]
+struct Lanes([f32; 4]);
+
+/// Implements a lane wise operation using the SSE intrinsic on x86_64, the
+/// NEON intrinsic on aarch64, and the scalar operator everywhere else.
+macro_rules! lane_operation {
+  ($name:ident, $sse:ident, $neon:ident, $operator:tt) => {
+    #[inline]
+    fn $name(self, other: Lanes) -> Lanes {
+      let mut result = Lanes::default();
+
+      #[cfg(target_arch = "x86_64")]
+      unsafe {
+        let value = $sse(_mm_load_ps(self.0.as_ptr()), _mm_load_ps(other.0.as_ptr()));
+        _mm_store_ps(result.0.as_mut_ptr(), value);
+      }
+
+      #[cfg(target_arch = "aarch64")]
+      unsafe {
+        let value = $neon(vld1q_f32(self.0.as_ptr()), vld1q_f32(other.0.as_ptr()));
+        vst1q_f32(result.0.as_mut_ptr(), value);
+      }
+
+      #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+      for (lane, (a, b)) in
+        result.0.iter_mut().zip(self.0.iter().zip(other.0.iter()))
+      {
+        *lane = a $operator b;
+      }
+
+      return result;
+    }
+  };
+}
+
+impl Lanes {
+  #[inline]
+  fn new(x: f32, y: f32, z: f32, w: f32) -> Lanes {
+    return Lanes([x, y, z, w]);
+  }
+
+  /// Lanes that all contain the same value.
+  #[inline]
+  fn splat(value: f32) -> Lanes {
+    let mut result = Lanes::default();
+
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+      _mm_store_ps(result.0.as_mut_ptr(), _mm_set1_ps(value));
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+      vst1q_f32(result.0.as_mut_ptr(), vdupq_n_f32(value));
+    }
+
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+      result.0 = [value; 4];
+    }
+
+    return result;
+  }
+
+  lane_operation!(add, _mm_add_ps, vaddq_f32, +);
+  lane_operation!(subtract, _mm_sub_ps, vsubq_f32, -);
+  lane_operation!(multiply, _mm_mul_ps, vmulq_f32, *);
+
+  /// The sum of every lane.
+  #[inline]
+  fn sum(self) -> f32 {
+    let [x, y, z, w] = self.0;
+    return (x + y) + (z + w);
+  }
+
+  #[inline]
+  fn dot(self, other: Lanes) -> f32 {
+    return self.multiply(other).sum();
+  }
+}
+
+// --------------------------------- VEC2 --------------------------------------
+
+/// A two component vector. Two lanes are too narrow to benefit from SIMD, so
+/// the operations are scalar.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec2 {
+  x: f32,
+  y: f32,
+}
+
+impl Vec2 {
+  pub fn new(x: f32, y: f32) -> Self {
+    return Self { x, y };
+  }
+
+  pub fn x(&self) -> f32 {
+    return self.x;
+  }
+
+  pub fn y(&self) -> f32 {
+    return self.y;
+  }
+
+  pub fn dot(&self, other: &Self) -> f32 {
+    return self.x * other.x + self.y * other.y;
+  }
+
+  pub fn length(&self) -> f32 {
+    return self.dot(self).sqrt();
+  }
+
+  /// The vector scaled to unit length. Panics for zero length vectors.
+  pub fn normalize(&self) -> Self {
+    let length = self.length();
+    assert_ne!(length, 0.0, "Cannot normalize a zero length vector");
+    return *self * (1.0 / length);
+  }
+}
+
+impl Add for Vec2 {
+  type Output = Vec2;
+
+  fn add(self, other: Vec2) -> Vec2 {
+    return Vec2::new(self.x + other.x, self.y + other.y);
+  }
+}
+
+impl Sub for Vec2 {
+  type Output = Vec2;
+
+  fn sub(self, other: Vec2) -> Vec2 {
+    return Vec2::new(self.x - other.x, self.y - other.y);
+  }
+}
+
+impl Mul<f32> for Vec2 {
+  type Output = Vec2;
+
+  fn mul(self, scalar: f32) -> Vec2 {
+    return Vec2::new(self.x * scalar, self.y * scalar);
+  }
+}
+
+impl Neg for Vec2 {
+  type Output = Vec2;
+
+  fn neg(self) -> Vec2 {
+    return Vec2::new(-self.x, -self.y);
+  }
+}
+
+impl From<[f32; 2]> for Vec2 {
+  fn from([x, y]: [f32; 2]) -> Self {
+    return Vec2::new(x, y);
+  }
+}
+
+impl From<Vec2> for [f32; 2] {
+  fn from(vector: Vec2) -> Self {
+    return [vector.x, vector.y];
+  }
+}
+
+// ------------------------------ VEC3 & VEC4 ----------------------------------
+
+/// Implements the arithmetic operators shared by the four lane vectors.
+macro_rules! lane_vector_operators {
+  ($vector:ident) => {
+    impl Add for $vector {
+      type Output = $vector;
+
+      fn add(self, other: $vector) -> $vector {
+        return $vector(self.0.add(other.0));
+      }
+    }
+
+    impl Sub for $vector {
+      type Output = $vector;
+
+      fn sub(self, other: $vector) -> $vector {
+        return $vector(self.0.subtract(other.0));
+      }
+    }
+
+    impl Mul<f32> for $vector {
+      type Output = $vector;
+
+      fn mul(self, scalar: f32) -> $vector {
+        return $vector(self.0.multiply(Lanes::splat(scalar)));
+      }
+    }
+
+    impl Neg for $vector {
+      type Output = $vector;
+
+      fn neg(self) -> $vector {
+        return $vector(Lanes::default().subtract(self.0));
+      }
+    }
+
+    impl $vector {
+      pub fn x(&self) -> f32 {
+        return self.0 .0[0];
+      }
+
+      pub fn y(&self) -> f32 {
+        return self.0 .0[1];
+      }
+
+      pub fn z(&self) -> f32 {
+        return self.0 .0[2];
+      }
+
+      pub fn dot(&self, other: &Self) -> f32 {
+        return self.0.dot(other.0);
+      }
+
+      pub fn length(&self) -> f32 {
+        return self.dot(self).sqrt();
+      }
+
+      /// The vector scaled to unit length. Panics for zero length vectors.
+      pub fn normalize(&self) -> Self {
+        let length = self.length();
+        assert_ne!(length, 0.0, "Cannot normalize a zero length vector");
+        return *self * (1.0 / length);
+      }
+    }
+  };
+}
+
+/// A three component vector padded to four lanes. The padding lane is always
+/// zero so that it doesn't contribute to dot products or lengths.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec3(Lanes);
+
+impl Vec3 {
+  pub fn new(x: f32, y: f32, z: f32) -> Self {
+    return Self(Lanes::new(x, y, z, 0.0));
+  }
+
+  pub fn cross(&self, other: &Self) -> Self {
+    let [ax, ay, az, _] = self.0 .0;
+    let [bx, by, bz, _] = other.0 .0;
+    let a = Lanes::new(ay, az, ax, 0.0).multiply(Lanes::new(bz, bx, by, 0.0));
+    let b = Lanes::new(az, ax, ay, 0.0).multiply(Lanes::new(by, bz, bx, 0.0));
+    return Self(a.subtract(b));
+  }
+}
+
+lane_vector_operators!(Vec3);
+
+impl From<[f32; 3]> for Vec3 {
+  fn from([x, y, z]: [f32; 3]) -> Self {
+    return Vec3::new(x, y, z);
+  }
+}
+
+impl From<Vec3> for [f32; 3] {
+  fn from(vector: Vec3) -> Self {
+    let [x, y, z, _] = vector.0 .0;
+    return [x, y, z];
+  }
+}
+
+/// A four component vector.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vec4(Lanes);
+
+impl Vec4 {
+  pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+    return Self(Lanes::new(x, y, z, w));
+  }
+
+  pub fn w(&self) -> f32 {
+    return self.0 .0[3];
+  }
+}
+
+lane_vector_operators!(Vec4);
+
+impl From<[f32; 4]> for Vec4 {
+  fn from(array: [f32; 4]) -> Self {
+    return Self(Lanes(array));
+  }
+}
+
+impl From<Vec4> for [f32; 4] {
+  fn from(vector: Vec4) -> Self {
+    return vector.0 .0;
+  }
+}
+
+// --------------------------------- MAT4 --------------------------------------
+
+/// A 4x4 matrix stored as rows.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Mat4 {
+  rows: [Lanes; 4],
+}
+
+impl Mat4 {
+  pub fn identity() -> Self {
+    return Self::from_rows([
+      [1.0, 0.0, 0.0, 0.0],
+      [0.0, 1.0, 0.0, 0.0],
+      [0.0, 0.0, 1.0, 0.0],
+      [0.0, 0.0, 0.0, 1.0],
+    ]);
+  }
+
+  #[inline]
+  pub fn from_rows(rows: [[f32; 4]; 4]) -> Self {
+    return Self {
+      rows: rows.map(Lanes),
+    };
+  }
+
+  #[inline]
+  pub fn to_rows(&self) -> [[f32; 4]; 4] {
+    return self.rows.map(|row| row.0);
+  }
+
+  /// The value at the given row & column.
+  pub fn at(&self, row: usize, column: usize) -> f32 {
+    return self.rows[row].0[column];
+  }
+
+  #[inline]
+  pub fn transpose(&self) -> Self {
+    let mut result = Self::default();
+    for (i, row) in result.rows.iter_mut().enumerate() {
+      for (j, value) in row.0.iter_mut().enumerate() {
+        *value = self.rows[j].0[i];
+      }
+    }
+    return result;
+  }
+
+  /// Multiplies the matrices (self * other). Every row of the result is a
+  /// combination of the rows of the other matrix weighted by a row of this
+  /// matrix.
+  #[inline]
+  pub fn multiply(&self, other: &Self) -> Self {
+    let mut result = Self::default();
+    for (result_row, row) in result.rows.iter_mut().zip(self.rows.iter()) {
+      let [a, b, c, d] = row.0;
+      *result_row = Lanes::splat(a)
+        .multiply(other.rows[0])
+        .add(Lanes::splat(b).multiply(other.rows[1]))
+        .add(Lanes::splat(c).multiply(other.rows[2]))
+        .add(Lanes::splat(d).multiply(other.rows[3]));
+    }
+    return result;
+  }
+
+  /// Transforms a column vector by the matrix (self * vector).
+  #[inline]
+  pub fn transform(&self, vector: &Vec4) -> Vec4 {
+    return Vec4::new(
+      self.rows[0].dot(vector.0),
+      self.rows[1].dot(vector.0),
+      self.rows[2].dot(vector.0),
+      self.rows[3].dot(vector.0),
+    );
+  }
+
+  #[inline]
+  pub fn determinant(&self) -> f32 {
+    let (s, c) = self.subfactors();
+    return s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1]
+      + s[5] * c[0];
+  }
+
+  /// The inverse of the matrix, or None if the matrix is singular.
+  #[inline]
+  pub fn inverse(&self) -> Option<Self> {
+    let (s, c) = self.subfactors();
+    let determinant = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2]
+      - s[4] * c[1]
+      + s[5] * c[0];
+
+    if determinant.abs() <= f32::EPSILON * f32::EPSILON
+      || !determinant.is_finite()
+    {
+      return None;
+    }
+
+    let m = self.to_rows();
+    // Each row of the inverse is built from the three columns that exclude
+    // it. The first two lanes are cofactors of rows 2 & 3 and the last two
+    // are cofactors of rows 0 & 1.
+    let column = |index: usize| {
+      return Lanes::new(m[1][index], m[0][index], m[3][index], m[2][index]);
+    };
+    let factor =
+      |index: usize| Lanes::new(c[index], c[index], s[index], s[index]);
+
+    const COLUMNS: [[usize; 3]; 4] =
+      [[1, 2, 3], [0, 2, 3], [0, 1, 3], [0, 1, 2]];
+    const FACTORS: [[usize; 3]; 4] =
+      [[5, 4, 3], [5, 2, 1], [4, 2, 0], [3, 1, 0]];
+
+    let scale = 1.0 / determinant;
+    let mut result = Self::default();
+    for (row, result_row) in result.rows.iter_mut().enumerate() {
+      let [a, b, c] = COLUMNS[row];
+      let [x, y, z] = FACTORS[row];
+      let cofactors = column(a)
+        .multiply(factor(x))
+        .subtract(column(b).multiply(factor(y)))
+        .add(column(c).multiply(factor(z)));
+
+      let sign = if row % 2 == 0 { scale } else { -scale };
+      *result_row = cofactors.multiply(Lanes::new(sign, -sign, sign, -sign));
+    }
+    return Some(result);
+  }
+
+  /// The 2x2 determinants of the top two rows (s) & bottom two rows (c) that
+  /// are shared by the determinant & inverse.
+  #[inline]
+  fn subfactors(&self) -> ([f32; 6], [f32; 6]) {
+    let m = self.to_rows();
+    let pair = |top: usize, bottom: usize, a: usize, b: usize| {
+      return m[top][a] * m[bottom][b] - m[bottom][a] * m[top][b];
+    };
+    let s = [
+      pair(0, 1, 0, 1),
+      pair(0, 1, 0, 2),
+      pair(0, 1, 0, 3),
+      pair(0, 1, 1, 2),
+      pair(0, 1, 1, 3),
+      pair(0, 1, 2, 3),
+    ];
+    let c = [
+      pair(2, 3, 0, 1),
+      pair(2, 3, 0, 2),
+      pair(2, 3, 0, 3),
+      pair(2, 3, 1, 2),
+      pair(2, 3, 1, 3),
+      pair(2, 3, 2, 3),
+    ];
+    return (s, c);
+  }
+}
+
+impl Mul for Mat4 {
+  type Output = Mat4;
+
+  fn mul(self, other: Mat4) -> Mat4 {
+    return self.multiply(&other);
+  }
+}
+
+impl Mul<Vec4> for Mat4 {
+  type Output = Vec4;
+
+  fn mul(self, vector: Vec4) -> Vec4 {
+    return self.transform(&vector);
+  }
+}
+
+impl From<[[f32; 4]; 4]> for Mat4 {
+  fn from(rows: [[f32; 4]; 4]) -> Self {
+    return Mat4::from_rows(rows);
+  }
+}
+
+impl From<Mat4> for [[f32; 4]; 4] {
+  fn from(matrix: Mat4) -> Self {
+    return matrix.to_rows();
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    Mat4,
+    Vec2,
+    Vec3,
+    Vec4,
+  };
+  use crate::{
+    assert_approximately_equal,
+    math::matrix::Matrix,
+  };
+
+  const MATRIX: [[f32; 4]; 4] = [
+    [2.0, 0.0, 1.0, 3.0],
+    [1.0, 3.0, 0.0, -1.0],
+    [0.0, 1.0, 4.0, 2.0],
+    [1.0, 0.0, 2.0, 5.0],
+  ];
+
+  #[test]
+  fn vector_operations() {
+    let a = Vec3::new(1.0, 0.0, 0.0);
+    let b = Vec3::new(0.0, 1.0, 0.0);
+    assert_eq!(a.cross(&b), Vec3::new(0.0, 0.0, 1.0));
+    assert_eq!(a + b - a * 2.0, Vec3::new(-1.0, 1.0, 0.0));
+    assert_eq!(<[f32; 3]>::from(-b), [0.0, -1.0, 0.0]);
+
+    assert_eq!(
+      Vec4::new(1.0, 2.0, 3.0, 4.0).dot(&Vec4::new(1.0, 1.0, 1.0, 1.0)),
+      10.0
+    );
+    assert_eq!(Vec2::new(3.0, 4.0).length(), 5.0);
+    assert_approximately_equal!(
+      Vec3::new(0.0, 3.0, 4.0).normalize().z(),
+      0.8,
+      1e-6
+    );
+  }
+
+  #[test]
+  fn matrices_match_the_array_implementation() {
+    let other = MATRIX.transpose();
+    let fast = Mat4::from(MATRIX) * Mat4::from(other);
+    assert_eq!(fast.to_rows(), MATRIX.multiply(&other));
+
+    let vector = Vec4::new(1.0, -2.0, 0.5, 1.0);
+    let transformed = Mat4::from(MATRIX) * vector;
+    assert_eq!(<[f32; 4]>::from(transformed), [5.5, -6.0, 2.0, 7.0]);
+    assert_eq!(Mat4::from(MATRIX).transpose().to_rows(), other);
+  }
+
+  #[test]
+  fn matrix_inverse() {
+    let matrix = Mat4::from(MATRIX);
+    let inverse = matrix.inverse().unwrap();
+    let identity = (matrix * inverse).to_rows();
+    for (row, expected) in identity.iter().zip(Mat4::identity().to_rows()) {
+      for (value, expected) in row.iter().zip(expected) {
+        assert_approximately_equal!(value, expected, 1e-5);
+      }
+    }
+
+    let determinant = matrix.determinant();
+    assert_approximately_equal!(determinant, 62.0, 1e-4);
+
+    let mut singular = MATRIX;
+    singular[3] = singular[0];
+    assert_eq!(Mat4::from(singular).inverse(), None);
+  }
+}
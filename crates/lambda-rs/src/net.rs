@@ -0,0 +1,548 @@
+//! Replication of transforms over UDP. A `ReplicationServer` broadcasts
+//! snapshots of the transforms of replicated objects to every client at a
+//! fixed tick rate, and each `ReplicationClient` keeps the latest snapshot
+//! that it has received. Objects are identified by network ids that the
+//! application assigns, such as from the index of their entity.
+
+use std::{
+  collections::HashMap,
+  time::Duration,
+};
+
+pub use lambda_platform::net::{
+  Channel,
+  Message,
+  SocketAddr,
+  UdpSocket,
+  UdpSocketBuilder,
+  MAX_PAYLOAD_SIZE,
+};
+
+use crate::{
+  scene::Transform,
+  time::FixedTimestep,
+};
+
+/// The first byte of every replication payload.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MessageKind {
+  Connect = 0,
+  Disconnect = 1,
+  Snapshot = 2,
+}
+
+impl MessageKind {
+  fn from_byte(byte: u8) -> Option<Self> {
+    return match byte {
+      0 => Some(MessageKind::Connect),
+      1 => Some(MessageKind::Disconnect),
+      2 => Some(MessageKind::Snapshot),
+      _ => None,
+    };
+  }
+}
+
+/// The kind, tick, part, part count, and transform count of every snapshot
+/// payload.
+const SNAPSHOT_HEADER_SIZE: usize = 1 + 4 + 2 + 2 + 2;
+
+/// The network id followed by the translation, rotation, and scale.
+const TRANSFORM_SIZE: usize = 4 + 9 * 4;
+
+/// The most transforms that fit within a single packet. Snapshots with more
+/// transforms are split into parts.
+const TRANSFORMS_PER_PART: usize =
+  (MAX_PAYLOAD_SIZE - SNAPSHOT_HEADER_SIZE) / TRANSFORM_SIZE;
+
+// --------------------------------- SNAPSHOT ----------------------------------
+
+/// The transforms of every replicated object at a tick of the server.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+  pub tick: u32,
+  pub transforms: HashMap<u32, Transform>,
+}
+
+impl Snapshot {
+  /// Encodes the snapshot into payloads that each fit within a packet.
+  fn encode(&self) -> Vec<Vec<u8>> {
+    let mut transforms: Vec<(&u32, &Transform)> =
+      self.transforms.iter().collect();
+    transforms.sort_by_key(|(id, _)| **id);
+    let parts: Vec<&[(&u32, &Transform)]> = match transforms.is_empty() {
+      true => vec![&[]],
+      false => transforms.chunks(TRANSFORMS_PER_PART).collect(),
+    };
+
+    return parts
+      .iter()
+      .enumerate()
+      .map(|(index, part)| {
+        let mut payload = Vec::with_capacity(
+          SNAPSHOT_HEADER_SIZE + part.len() * TRANSFORM_SIZE,
+        );
+        payload.push(MessageKind::Snapshot as u8);
+        payload.extend_from_slice(&self.tick.to_le_bytes());
+        payload.extend_from_slice(&(index as u16).to_le_bytes());
+        payload.extend_from_slice(&(parts.len() as u16).to_le_bytes());
+        payload.extend_from_slice(&(part.len() as u16).to_le_bytes());
+        for (id, transform) in part.iter() {
+          payload.extend_from_slice(&id.to_le_bytes());
+          for value in transform
+            .translation
+            .iter()
+            .chain(transform.rotation.iter())
+            .chain(transform.scale.iter())
+          {
+            payload.extend_from_slice(&value.to_le_bytes());
+          }
+        }
+        return payload;
+      })
+      .collect();
+  }
+}
+
+/// A part of a snapshot decoded from a payload.
+#[derive(Debug, PartialEq)]
+struct SnapshotPart {
+  tick: u32,
+  index: u16,
+  count: u16,
+  transforms: Vec<(u32, Transform)>,
+}
+
+impl SnapshotPart {
+  fn decode(payload: &[u8]) -> Result<Self, String> {
+    if payload.len() < SNAPSHOT_HEADER_SIZE {
+      return Err("The snapshot is missing its header.".to_string());
+    }
+    let u16_at = |offset: usize| {
+      return u16::from_le_bytes([payload[offset], payload[offset + 1]]);
+    };
+    let u32_at = |offset: usize| {
+      let mut word = [0; 4];
+      word.copy_from_slice(&payload[offset..offset + 4]);
+      return u32::from_le_bytes(word);
+    };
+
+    let (index, count, length) = (u16_at(5), u16_at(7), u16_at(9) as usize);
+    if index >= count
+      || payload.len() != SNAPSHOT_HEADER_SIZE + length * TRANSFORM_SIZE
+    {
+      return Err("The snapshot's header doesn't match its size.".to_string());
+    }
+
+    let transforms = payload[SNAPSHOT_HEADER_SIZE..]
+      .chunks_exact(TRANSFORM_SIZE)
+      .map(|bytes| {
+        let value = |index: usize| {
+          let offset = 4 + index * 4;
+          let mut word = [0; 4];
+          word.copy_from_slice(&bytes[offset..offset + 4]);
+          return f32::from_le_bytes(word);
+        };
+        let mut id = [0; 4];
+        id.copy_from_slice(&bytes[..4]);
+        let transform = Transform {
+          translation: [value(0), value(1), value(2)],
+          rotation: [value(3), value(4), value(5)],
+          scale: [value(6), value(7), value(8)],
+        };
+        return (u32::from_le_bytes(id), transform);
+      })
+      .collect();
+
+    return Ok(Self {
+      tick: u32_at(1),
+      index,
+      count,
+      transforms,
+    });
+  }
+}
+
+// ----------------------------- REPLICATION SERVER ----------------------------
+
+/// Builder for replication servers.
+pub struct ReplicationServerBuilder {
+  tick_rate: u32,
+  max_clients: usize,
+}
+
+impl ReplicationServerBuilder {
+  /// A new server broadcasts 20 snapshots per second to at most 16
+  /// clients.
+  pub fn new() -> Self {
+    return Self {
+      tick_rate: 20,
+      max_clients: 16,
+    };
+  }
+
+  /// The number of snapshots broadcast per second.
+  pub fn with_tick_rate(mut self, tick_rate: u32) -> Self {
+    self.tick_rate = tick_rate;
+    return self;
+  }
+
+  /// The most clients that can connect at once. Clients that connect once
+  /// the server is full are ignored.
+  pub fn with_max_clients(mut self, max_clients: usize) -> Self {
+    self.max_clients = max_clients;
+    return self;
+  }
+
+  /// Binds the server to the address that clients connect to.
+  pub fn build(self, address: SocketAddr) -> Result<ReplicationServer, String> {
+    return Ok(ReplicationServer {
+      socket: UdpSocketBuilder::new().with_address(address).build()?,
+      clients: vec![],
+      max_clients: self.max_clients,
+      timestep: FixedTimestep::from_rate(self.tick_rate).with_max_steps(1),
+      tick: 0,
+    });
+  }
+}
+
+/// Broadcasts snapshots of transforms to the clients that have connected.
+pub struct ReplicationServer {
+  socket: UdpSocket,
+  clients: Vec<SocketAddr>,
+  max_clients: usize,
+  timestep: FixedTimestep,
+  tick: u32,
+}
+
+impl ReplicationServer {
+  /// The address that clients connect to.
+  pub fn local_address(&self) -> Result<SocketAddr, String> {
+    return self.socket.local_address();
+  }
+
+  /// The addresses of the clients that are connected.
+  pub fn clients(&self) -> &[SocketAddr] {
+    return &self.clients;
+  }
+
+  /// The tick of the last snapshot that was broadcast.
+  pub fn tick(&self) -> u32 {
+    return self.tick;
+  }
+
+  /// Accepts clients that have connected or disconnected, and broadcasts a
+  /// snapshot of the transforms once a tick has passed. Ticks that were
+  /// missed during long frames aren't broadcast, since only the latest
+  /// snapshot matters. Returns whether a snapshot was broadcast.
+  pub fn update(
+    &mut self,
+    delta: Duration,
+    transforms: impl FnOnce() -> HashMap<u32, Transform>,
+  ) -> Result<bool, String> {
+    for message in self.socket.receive() {
+      match message
+        .payload
+        .first()
+        .copied()
+        .and_then(MessageKind::from_byte)
+      {
+        Some(MessageKind::Connect)
+          if !self.clients.contains(&message.peer)
+            && self.clients.len() < self.max_clients =>
+        {
+          logging::info!("{} connected.", message.peer);
+          self.clients.push(message.peer);
+        }
+        Some(MessageKind::Disconnect) => {
+          if self.clients.contains(&message.peer) {
+            logging::info!("{} disconnected.", message.peer);
+          }
+          self.clients.retain(|client| *client != message.peer);
+          self.socket.forget_peer(message.peer);
+        }
+        _ => {}
+      }
+    }
+
+    if self.timestep.advance(delta) == 0 {
+      return Ok(false);
+    }
+    self.tick = self.tick.wrapping_add(1);
+    self.broadcast(&Snapshot {
+      tick: self.tick,
+      transforms: transforms(),
+    })?;
+    return Ok(true);
+  }
+
+  /// Sends the snapshot to every client immediately. The parts of a snapshot
+  /// are sent unreliably, since the ordered channel would drop parts that
+  /// arrive after a later part of the same snapshot.
+  pub fn broadcast(&mut self, snapshot: &Snapshot) -> Result<(), String> {
+    let payloads = snapshot.encode();
+    for client in self.clients.iter() {
+      for payload in payloads.iter() {
+        self.socket.send(*client, Channel::Unreliable, payload)?;
+      }
+    }
+    return Ok(());
+  }
+}
+
+// ----------------------------- REPLICATION CLIENT ----------------------------
+
+/// Receives the snapshots broadcast by a replication server.
+pub struct ReplicationClient {
+  socket: UdpSocket,
+  server: SocketAddr,
+  latest: Option<Snapshot>,
+  pending: Option<PendingSnapshot>,
+}
+
+/// The parts received so far of the newest snapshot that hasn't been fully
+/// received yet.
+struct PendingSnapshot {
+  tick: u32,
+  parts: Vec<Option<Vec<(u32, Transform)>>>,
+}
+
+impl ReplicationClient {
+  /// Binds a socket to any port and connects to the server at the address.
+  pub fn connect(server: SocketAddr) -> Result<Self, String> {
+    let address = match server {
+      SocketAddr::V4(_) => SocketAddr::from(([0, 0, 0, 0], 0)),
+      SocketAddr::V6(_) => SocketAddr::from(([0; 16], 0)),
+    };
+    let mut client = Self {
+      socket: UdpSocketBuilder::new().with_address(address).build()?,
+      server,
+      latest: None,
+      pending: None,
+    };
+    client.send(MessageKind::Connect)?;
+    return Ok(client);
+  }
+
+  /// Receives the snapshots that have arrived and returns whether a newer
+  /// snapshot was received. The connection is requested again until the
+  /// first snapshot arrives, since the request may have been lost.
+  pub fn update(&mut self) -> Result<bool, String> {
+    if self.latest.is_none() {
+      self.send(MessageKind::Connect)?;
+    }
+
+    let mut received = false;
+    for message in self.socket.receive() {
+      if message.peer != self.server
+        || message.payload.first() != Some(&(MessageKind::Snapshot as u8))
+      {
+        continue;
+      }
+      match SnapshotPart::decode(&message.payload) {
+        Ok(part) => received |= self.receive_part(part),
+        Err(error) => logging::debug!("Dropped a snapshot: {}", error),
+      }
+    }
+    return Ok(received);
+  }
+
+  /// Stores the part, completing the snapshot once every part has arrived.
+  /// Parts can arrive in any order, and parts of snapshots that are older
+  /// than the latest or pending snapshot are ignored.
+  fn receive_part(&mut self, part: SnapshotPart) -> bool {
+    let latest = self.latest.as_ref().map(|latest| latest.tick);
+    let pending = self.pending.as_ref().map(|pending| pending.tick);
+    if latest.is_some_and(|latest| !is_newer_tick(part.tick, latest))
+      || pending.is_some_and(|pending| is_newer_tick(pending, part.tick))
+    {
+      return false;
+    }
+    let count = part.count as usize;
+    if !self.pending.as_ref().is_some_and(|pending| {
+      return pending.tick == part.tick && pending.parts.len() == count;
+    }) {
+      self.pending = Some(PendingSnapshot {
+        tick: part.tick,
+        parts: vec![None; count],
+      });
+    }
+
+    let pending = self.pending.as_mut().expect("A snapshot is pending.");
+    pending.parts[part.index as usize] = Some(part.transforms);
+    if pending.parts.iter().any(Option::is_none) {
+      return false;
+    }
+
+    let pending = self.pending.take().expect("A snapshot is pending.");
+    self.latest = Some(Snapshot {
+      tick: pending.tick,
+      transforms: pending.parts.into_iter().flatten().flatten().collect(),
+    });
+    return true;
+  }
+
+  /// The latest snapshot that was fully received.
+  pub fn latest(&self) -> Option<&Snapshot> {
+    return self.latest.as_ref();
+  }
+
+  /// The transform of the object in the latest snapshot.
+  pub fn transform(&self, id: u32) -> Option<Transform> {
+    return self
+      .latest
+      .as_ref()
+      .and_then(|snapshot| snapshot.transforms.get(&id).copied());
+  }
+
+  fn send(&mut self, kind: MessageKind) -> Result<(), String> {
+    return self
+      .socket
+      .send(self.server, Channel::Unreliable, &[kind as u8]);
+  }
+}
+
+/// Whether the tick comes after the other, accounting for ticks wrapping
+/// around.
+fn is_newer_tick(tick: u32, other: u32) -> bool {
+  let difference = tick.wrapping_sub(other);
+  return difference != 0 && difference < u32::MAX / 2;
+}
+
+impl Drop for ReplicationClient {
+  /// Lets the server know that the client has disconnected.
+  fn drop(&mut self) {
+    self.send(MessageKind::Disconnect).ok();
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    collections::HashMap,
+    time::Duration,
+  };
+
+  use super::{
+    ReplicationClient,
+    ReplicationServerBuilder,
+    Snapshot,
+    SnapshotPart,
+    SocketAddr,
+    TRANSFORMS_PER_PART,
+  };
+  use crate::scene::Transform;
+
+  fn transforms(count: u32) -> HashMap<u32, Transform> {
+    return (0..count)
+      .map(|id| {
+        let transform = Transform::new()
+          .with_translation([id as f32, 1.0, 2.0])
+          .with_rotation([0.0, id as f32 * 0.1, 0.0]);
+        return (id, transform);
+      })
+      .collect();
+  }
+
+  #[test]
+  fn large_snapshots_are_split_into_parts() {
+    let snapshot = Snapshot {
+      tick: 9,
+      transforms: transforms(TRANSFORMS_PER_PART as u32 + 1),
+    };
+    let payloads = snapshot.encode();
+    assert_eq!(payloads.len(), 2);
+
+    let parts: Vec<SnapshotPart> = payloads
+      .iter()
+      .map(|payload| SnapshotPart::decode(payload).unwrap())
+      .collect();
+    assert_eq!(parts[1].index, 1);
+    assert_eq!(parts[1].count, 2);
+    assert_eq!(parts[1].transforms.len(), 1);
+    let decoded: HashMap<_, _> =
+      parts.into_iter().flat_map(|part| part.transforms).collect();
+    assert_eq!(decoded, snapshot.transforms);
+
+    assert!(SnapshotPart::decode(&payloads[0][..20]).is_err());
+    assert_eq!(Snapshot::default().encode().len(), 1);
+  }
+
+  #[test]
+  fn clients_receive_snapshots_at_the_tick_rate() {
+    let mut server = ReplicationServerBuilder::new()
+      .with_tick_rate(10)
+      .build(SocketAddr::from(([127, 0, 0, 1], 0)))
+      .unwrap();
+    let address = server.local_address().unwrap();
+    let mut client = ReplicationClient::connect(address).unwrap();
+
+    // Less than a tick passes, so nothing is broadcast.
+    let broadcast = server
+      .update(Duration::from_millis(50), || transforms(3))
+      .unwrap();
+    assert!(!broadcast);
+
+    let mut received = false;
+    for _ in 0..100 {
+      server
+        .update(Duration::from_millis(100), || transforms(60))
+        .unwrap();
+      if client.update().unwrap() {
+        received = true;
+        break;
+      }
+      std::thread::sleep(Duration::from_millis(5));
+    }
+    assert!(received);
+    assert_eq!(server.clients().len(), 1);
+    assert_eq!(client.latest().unwrap().transforms.len(), 60);
+    assert_eq!(client.transform(7), transforms(60).get(&7).copied());
+
+    drop(client);
+    for _ in 0..100 {
+      server.update(Duration::ZERO, HashMap::new).unwrap();
+      if server.clients().is_empty() {
+        break;
+      }
+      std::thread::sleep(Duration::from_millis(5));
+    }
+    assert!(server.clients().is_empty());
+  }
+
+  #[test]
+  fn parts_of_older_snapshots_are_ignored() {
+    let server = ReplicationServerBuilder::new()
+      .build(SocketAddr::from(([127, 0, 0, 1], 0)))
+      .unwrap();
+    let mut client =
+      ReplicationClient::connect(server.local_address().unwrap()).unwrap();
+    let parts = |tick: u32, count: u32| {
+      return Snapshot {
+        tick,
+        transforms: transforms(count),
+      }
+      .encode()
+      .iter()
+      .map(|payload| SnapshotPart::decode(payload).unwrap())
+      .collect::<Vec<_>>();
+    };
+
+    let count = TRANSFORMS_PER_PART as u32 + 1;
+    let (mut older, mut newer) = (parts(1, count), parts(2, count));
+    assert!(!client.receive_part(newer.remove(1)));
+    assert!(!client.receive_part(older.remove(0)));
+    assert!(client.receive_part(newer.remove(0)));
+    assert!(!client.receive_part(older.remove(0)));
+    assert_eq!(client.latest().unwrap().tick, 2);
+
+    // Ticks wrap around once they reach the largest tick.
+    let mut client =
+      ReplicationClient::connect(server.local_address().unwrap()).unwrap();
+    assert!(client.receive_part(parts(u32::MAX, 1).remove(0)));
+    assert!(client.receive_part(parts(0, 2).remove(0)));
+    assert!(!client.receive_part(parts(u32::MAX, 3).remove(0)));
+    assert_eq!(client.latest().unwrap().transforms.len(), 2);
+  }
+}
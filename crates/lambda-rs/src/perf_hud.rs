@@ -0,0 +1,519 @@
+//! An in-window overlay that shows how long recent frames took, a graph of
+//! their frame times, the work the last frame submitted to the GPU, the
+//! memory used by the process, and the time spent within logging spans. The
+//! HUD records its own frame statistics, so it can be added to any
+//! application without other setup. Text is only drawn once the HUD is given
+//! a font, while the graph is always drawn.
+//!
+//! The HUD is toggled with F3 by default.
+
+use std::{
+  cmp::Reverse,
+  time::Duration,
+};
+
+use crate::{
+  component::Component,
+  events::{
+    Events,
+    Key,
+    WindowEvent,
+  },
+  input,
+  log_console::{
+    FRAGMENT_SHADER_SOURCE,
+    VERTEX_SHADER_SOURCE,
+  },
+  render::{
+    buffer::BufferBuilder,
+    command::RenderCommand,
+    counters::FrameCounters,
+    mesh::MeshBuilder,
+    pipeline::RenderPipelineBuilder,
+    render_pass::RenderPassBuilder,
+    shader::{
+      ShaderBuilder,
+      ShaderKind,
+      VirtualShader,
+    },
+    text::{
+      FontAtlas,
+      TextRenderer,
+    },
+    vertex::{
+      Vertex,
+      VertexAttribute,
+      VertexElement,
+    },
+    viewport::ViewportBuilder,
+    ColorFormat,
+    RenderContext,
+    ResourceId,
+  },
+  runtimes::{
+    application::ComponentResult,
+    pacing::FrameStatistics,
+  },
+};
+
+/// The number of recent frames that are graphed & summarized.
+const GRAPH_FRAMES: usize = 120;
+
+/// The frame time that fills the height of the graph (30 fps).
+const GRAPH_SCALE: Duration = Duration::from_nanos(33_333_333);
+
+/// Frames at or below this time are drawn in green (60 fps).
+const GOOD_FRAME_TIME: Duration = Duration::from_nanos(16_666_667);
+
+/// The number of spans shown by default.
+const DEFAULT_SPAN_LINES: usize = 6;
+
+/// The number of vertices the HUD can draw.
+const VERTEX_CAPACITY: usize = 1 << 16;
+
+/// The size of the panel & graph in points.
+const PANEL_WIDTH: f32 = 280.0;
+const GRAPH_HEIGHT: f32 = 48.0;
+
+/// Points between the edge of the panel & its contents.
+const PANEL_PADDING: f32 = 8.0;
+
+const PANEL_COLOR: [f32; 3] = [0.05, 0.05, 0.08];
+const TEXT_COLOR: [f32; 3] = [0.9, 0.9, 0.9];
+
+/// The color that a frame is graphed in based upon how long it took.
+fn frame_color(frame_time: Duration) -> [f32; 3] {
+  if frame_time <= GOOD_FRAME_TIME {
+    return [0.4, 0.9, 0.4];
+  }
+  if frame_time <= GRAPH_SCALE {
+    return [0.95, 0.85, 0.3];
+  }
+  return [1.0, 0.35, 0.35];
+}
+
+/// The memory resident for the process in bytes, where the operating system
+/// reports it through procfs.
+fn resident_memory() -> Option<u64> {
+  let status = std::fs::read_to_string("/proc/self/status").ok()?;
+  let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+  let kibibytes = line.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+  return Some(kibibytes * 1024);
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+  return duration.as_secs_f64() * 1000.0;
+}
+
+/// Shows frame & GPU statistics in the top right corner of the window.
+pub struct PerfHudComponent {
+  font: Option<FontAtlas>,
+  toggle_key: input::Key,
+  visible: bool,
+  span_lines: usize,
+  statistics: FrameStatistics,
+  counters: FrameCounters,
+  render_pass: Option<ResourceId>,
+  render_pipeline: Option<ResourceId>,
+  width: u32,
+  height: u32,
+  pixels_per_point: f32,
+  vertex_count: u32,
+}
+
+impl PerfHudComponent {
+  /// Creates a visible HUD without a font.
+  pub fn new() -> Self {
+    return Self {
+      font: None,
+      toggle_key: input::Key::F3,
+      visible: true,
+      span_lines: DEFAULT_SPAN_LINES,
+      statistics: FrameStatistics::new(GRAPH_FRAMES),
+      counters: FrameCounters::default(),
+      render_pass: None,
+      render_pipeline: None,
+      width: 800,
+      height: 600,
+      pixels_per_point: 1.0,
+      vertex_count: 0,
+    };
+  }
+
+  /// The font that statistics are drawn with.
+  pub fn with_font(mut self, font: FontAtlas) -> Self {
+    self.font = Some(font);
+    return self;
+  }
+
+  /// The key that shows & hides the HUD.
+  pub fn with_toggle_key(mut self, toggle_key: input::Key) -> Self {
+    self.toggle_key = toggle_key;
+    return self;
+  }
+
+  /// The most spans that are shown, starting with the span that the most
+  /// time was spent within.
+  pub fn with_span_lines(mut self, span_lines: usize) -> Self {
+    self.span_lines = span_lines;
+    return self;
+  }
+
+  pub fn set_visible(&mut self, visible: bool) {
+    self.visible = visible;
+  }
+
+  pub fn is_visible(&self) -> bool {
+    return self.visible;
+  }
+
+  /// The frame statistics recorded by the HUD.
+  pub fn statistics(&self) -> &FrameStatistics {
+    return &self.statistics;
+  }
+
+  /// The lines of text that the HUD shows.
+  pub fn lines(&self) -> Vec<String> {
+    let statistics = &self.statistics;
+    let counters = &self.counters;
+    let mut lines = vec![
+      format!(
+        "frame: {:.2} ms ({:.0} fps)",
+        milliseconds(statistics.average_frame_time()),
+        statistics.frames_per_second()
+      ),
+      format!(
+        "p99: {:.2} ms  max: {:.2} ms",
+        milliseconds(statistics.percentile_frame_time(99.0)),
+        milliseconds(statistics.percentile_frame_time(100.0))
+      ),
+      format!(
+        "draws: {}  triangles: {}  passes: {}",
+        counters.draw_calls, counters.triangles, counters.render_passes
+      ),
+      match resident_memory() {
+        Some(bytes) => {
+          format!("memory: {:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+        }
+        None => "memory: unknown".to_string(),
+      },
+    ];
+
+    let mut spans = logging::span::report();
+    spans.sort_by_key(|summary| Reverse(summary.total));
+    lines.extend(spans.iter().take(self.span_lines).map(|summary| {
+      return format!(
+        "{}: {:.2} ms",
+        summary.path,
+        milliseconds(summary.mean())
+      );
+    }));
+    return lines;
+  }
+
+  /// Builds the panel, graph, & lines as a triangle list that fits within
+  /// the vertex capacity.
+  fn build_vertices(&self) -> Vec<Vertex> {
+    let scale = self.pixels_per_point;
+    let screen = (self.width as f32, self.height as f32);
+    let lines = match &self.font {
+      Some(_) => self.lines(),
+      None => vec![],
+    };
+    let line_height = self
+      .font
+      .as_ref()
+      .map_or(0.0, |font| font.line_height() * lines.len() as f32);
+
+    let panel_width = PANEL_WIDTH * scale;
+    let panel_height =
+      (PANEL_PADDING * 2.0 + GRAPH_HEIGHT + line_height) * scale;
+    let left = (screen.0 - panel_width).max(0.0);
+    let mut vertices =
+      quad_vertices(screen, [left, 0.0, screen.0, panel_height], PANEL_COLOR);
+
+    // Frames are graphed from oldest on the left to newest on the right.
+    let padding = PANEL_PADDING * scale;
+    let graph_width = panel_width - padding * 2.0;
+    let graph_bottom = padding + GRAPH_HEIGHT * scale;
+    let bar_width = graph_width / GRAPH_FRAMES as f32;
+    let frame_times: Vec<Duration> = self.statistics.frame_times().collect();
+    let first_bar = GRAPH_FRAMES - frame_times.len();
+    for (index, frame_time) in frame_times.iter().enumerate() {
+      let fill =
+        (frame_time.as_secs_f32() / GRAPH_SCALE.as_secs_f32()).min(1.0);
+      let bar_left = left + padding + (first_bar + index) as f32 * bar_width;
+      vertices.extend(quad_vertices(
+        screen,
+        [
+          bar_left,
+          graph_bottom - fill * GRAPH_HEIGHT * scale,
+          bar_left + bar_width,
+          graph_bottom,
+        ],
+        frame_color(*frame_time),
+      ));
+    }
+
+    if let Some(font) = &self.font {
+      let renderer = TextRenderer::new(self.width, self.height)
+        .with_pixels_per_point(scale)
+        .with_color(TEXT_COLOR);
+      for (index, line) in lines.iter().enumerate() {
+        let position = (
+          left / scale + PANEL_PADDING,
+          PANEL_PADDING * 2.0
+            + GRAPH_HEIGHT
+            + font.line_height() * index as f32,
+        );
+        let mesh = renderer.build_mesh(font, line, position);
+        vertices.extend_from_slice(mesh.vertices());
+      }
+    }
+
+    // Only whole triangles are drawn.
+    vertices.truncate(VERTEX_CAPACITY - VERTEX_CAPACITY % 3);
+    return vertices;
+  }
+}
+
+/// Two triangles covering the rectangle given in pixels as [left, top,
+/// right, bottom], converted into clip space like the text renderer does.
+fn quad_vertices(
+  screen: (f32, f32),
+  rectangle: [f32; 4],
+  color: [f32; 3],
+) -> Vec<Vertex> {
+  let vertex = |x: f32, y: f32| {
+    return Vertex {
+      position: [x / screen.0 * 2.0 - 1.0, y / screen.1 * 2.0 - 1.0, 0.0],
+      normal: [0.0, 0.0, 1.0],
+      color,
+      tex_coords: [0.0, 0.0],
+      tangent: [0.0, 0.0, 0.0, 0.0],
+    };
+  };
+  let [left, top, right, bottom] = rectangle;
+  return vec![
+    vertex(left, top),
+    vertex(left, bottom),
+    vertex(right, top),
+    vertex(right, top),
+    vertex(left, bottom),
+    vertex(right, bottom),
+  ];
+}
+
+impl Default for PerfHudComponent {
+  fn default() -> Self {
+    return Self::new();
+  }
+}
+
+impl Component<ComponentResult, String> for PerfHudComponent {
+  fn on_attach(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    let mut mesh_builder = MeshBuilder::new();
+    for _ in 0..VERTEX_CAPACITY {
+      mesh_builder.with_vertex(Vertex {
+        position: [0.0; 3],
+        normal: [0.0; 3],
+        color: [0.0; 3],
+        tex_coords: [0.0; 2],
+        tangent: [0.0; 4],
+      });
+    }
+    mesh_builder.with_attributes(
+      [0, 12, 24]
+        .iter()
+        .enumerate()
+        .map(|(location, offset)| {
+          return VertexAttribute {
+            location: location as u32,
+            offset: 0,
+            element: VertexElement {
+              format: ColorFormat::Rgb32Sfloat,
+              offset: *offset,
+            },
+          };
+        })
+        .collect(),
+    );
+    let mesh = mesh_builder.build();
+
+    let mut builder = ShaderBuilder::new();
+    let vertex_shader = builder.build(VirtualShader::Source {
+      source: VERTEX_SHADER_SOURCE.to_string(),
+      kind: ShaderKind::Vertex,
+      entry_point: "main".to_string(),
+      name: "perf_hud".to_string(),
+    });
+    let fragment_shader = builder.build(VirtualShader::Source {
+      source: FRAGMENT_SHADER_SOURCE.to_string(),
+      kind: ShaderKind::Fragment,
+      entry_point: "main".to_string(),
+      name: "perf_hud".to_string(),
+    });
+
+    let render_pass = RenderPassBuilder::new()
+      .with_preserved_contents()
+      .build(render_context);
+    let pipeline = RenderPipelineBuilder::new()
+      .with_buffer(
+        BufferBuilder::build_from_mesh(&mesh, render_context)
+          .map_err(|error| error.to_string())?,
+        mesh.attributes().to_vec(),
+      )
+      .build(
+        render_context,
+        &render_pass,
+        &vertex_shader,
+        Some(&fragment_shader),
+      );
+
+    self.render_pass = Some(render_context.attach_render_pass(render_pass));
+    self.render_pipeline = Some(render_context.attach_pipeline(pipeline));
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_detach(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_event(&mut self, event: Events) -> Result<ComponentResult, String> {
+    match event {
+      Events::Window {
+        event: WindowEvent::Resize { width, height },
+        ..
+      } => {
+        self.width = width;
+        self.height = height;
+      }
+      Events::Window {
+        event: WindowEvent::ScaleFactorChanged { scale_factor },
+        ..
+      } => {
+        self.pixels_per_point = scale_factor as f32;
+      }
+      Events::Keyboard {
+        event: Key::Pressed { key, .. },
+        ..
+      } if key == self.toggle_key => self.set_visible(!self.visible),
+      _ => {}
+    }
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_update(
+    &mut self,
+    last_frame: &Duration,
+  ) -> Result<ComponentResult, String> {
+    self.statistics.record(*last_frame);
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_render(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Vec<RenderCommand> {
+    let (render_pass, pipeline) = match (self.render_pass, self.render_pipeline)
+    {
+      (Some(render_pass), Some(pipeline)) => (render_pass, pipeline),
+      _ => return vec![],
+    };
+    if !self.visible {
+      return vec![];
+    }
+
+    // The statistics change every frame, so the HUD is rebuilt every frame.
+    self.counters = render_context.frame_counters();
+    let vertices = self.build_vertices();
+    self.vertex_count = vertices.len() as u32;
+
+    let viewport = ViewportBuilder::new().build(self.width, self.height);
+    return vec![
+      RenderCommand::UpdateVertexBuffer {
+        pipeline,
+        buffer: 0,
+        vertices,
+      },
+      RenderCommand::SetViewports {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetScissors {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetPipeline { pipeline },
+      RenderCommand::BeginRenderPass {
+        render_pass,
+        viewport,
+      },
+      RenderCommand::BindVertexBuffer {
+        pipeline,
+        buffer: 0,
+      },
+      RenderCommand::Draw {
+        vertices: 0..self.vertex_count,
+      },
+      RenderCommand::EndRenderPass,
+    ];
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::{
+    frame_color,
+    PerfHudComponent,
+    GRAPH_FRAMES,
+  };
+  use crate::render::counters::FrameCounters;
+
+  #[test]
+  fn lines_summarize_frames_and_gpu_work() {
+    let mut hud = PerfHudComponent::new();
+    for _ in 0..4 {
+      hud.statistics.record(Duration::from_millis(10));
+    }
+    hud.statistics.record(Duration::from_millis(30));
+    hud.counters = FrameCounters {
+      draw_calls: 3,
+      vertices: 36,
+      triangles: 12,
+      render_passes: 2,
+      pipeline_changes: 2,
+    };
+
+    let lines = hud.lines();
+    assert_eq!(lines[0], "frame: 14.00 ms (71 fps)");
+    assert_eq!(lines[1], "p99: 30.00 ms  max: 30.00 ms");
+    assert_eq!(lines[2], "draws: 3  triangles: 12  passes: 2");
+    assert!(lines[3].starts_with("memory: "));
+  }
+
+  #[test]
+  fn graph_holds_recent_frames_without_a_font() {
+    let mut hud = PerfHudComponent::new();
+    for _ in 0..GRAPH_FRAMES + 10 {
+      hud.statistics.record(Duration::from_millis(10));
+    }
+
+    // A panel plus a bar for every graphed frame.
+    assert_eq!(hud.build_vertices().len(), (1 + GRAPH_FRAMES) * 6);
+    assert_ne!(
+      frame_color(Duration::from_millis(10)),
+      frame_color(Duration::from_millis(50))
+    );
+  }
+}
@@ -0,0 +1,338 @@
+//! State that is saved between runs of an application, such as the pose of a
+//! camera. Anything that implements `Saveable` can be registered with a
+//! `SaveBundle` under a unique name. The bundle writes the state of
+//! everything registered to a single file and restores it from that file on
+//! the next run.
+
+use std::{
+  cell::RefCell,
+  collections::BTreeMap,
+  path::{
+    Path,
+    PathBuf,
+  },
+  rc::Rc,
+};
+
+use crate::scene::Transform;
+
+/// Identifies save bundle files.
+const MAGIC: &[u8; 4] = b"LSAV";
+
+/// The version of the save bundle format, which is incremented whenever the
+/// format changes.
+const FORMAT_VERSION: u32 = 1;
+
+// --------------------------------- SAVEABLE ----------------------------------
+
+/// State that can be saved as bytes and restored from them on a later run.
+pub trait Saveable {
+  /// Serializes the state into bytes.
+  fn save_state(&self) -> Vec<u8>;
+
+  /// Restores the state from bytes that were previously saved. Fails if the
+  /// bytes can't be deserialized, such as when they were saved by an older
+  /// version of the application.
+  fn restore_state(&mut self, bytes: &[u8]) -> Result<(), String>;
+}
+
+/// Transforms are saved as their translation, rotation, and scale in little
+/// endian.
+impl Saveable for Transform {
+  fn save_state(&self) -> Vec<u8> {
+    return self
+      .translation
+      .iter()
+      .chain(self.rotation.iter())
+      .chain(self.scale.iter())
+      .flat_map(|value| value.to_le_bytes())
+      .collect();
+  }
+
+  fn restore_state(&mut self, bytes: &[u8]) -> Result<(), String> {
+    if bytes.len() != 9 * 4 {
+      return Err(format!(
+        "A transform is 36 bytes, but {} bytes were saved.",
+        bytes.len()
+      ));
+    }
+    let values: Vec<f32> = bytes
+      .chunks_exact(4)
+      .map(|word| f32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+      .collect();
+    self.translation = [values[0], values[1], values[2]];
+    self.rotation = [values[3], values[4], values[5]];
+    self.scale = [values[6], values[7], values[8]];
+    return Ok(());
+  }
+}
+
+// -------------------------------- SAVE BUNDLE --------------------------------
+
+/// A set of named entries of saved state, optionally backed by a file.
+/// Entries in the file that nothing has registered for are kept, so that
+/// state isn't lost when a run doesn't use everything that was saved.
+pub struct SaveBundle {
+  file: Option<PathBuf>,
+  entries: BTreeMap<String, Vec<u8>>,
+  saveables: Vec<(String, Rc<RefCell<dyn Saveable>>)>,
+}
+
+impl SaveBundle {
+  /// Creates an empty bundle that isn't backed by a file.
+  pub fn new() -> Self {
+    return Self {
+      file: None,
+      entries: BTreeMap::new(),
+      saveables: vec![],
+    };
+  }
+
+  /// Backs the bundle with the file at the path, loading its entries if it
+  /// exists. The file is created once the bundle is saved.
+  pub fn load_file(&mut self, path: impl Into<PathBuf>) -> Result<(), String> {
+    let path = path.into();
+    self.file = Some(path.clone());
+    if !path.exists() {
+      return Ok(());
+    }
+
+    let bytes = std::fs::read(&path).map_err(|error| {
+      format!("Failed to read {}: {}", path.display(), error)
+    })?;
+    self.entries = decode(&bytes)
+      .map_err(|error| format!("{}: {}", path.display(), error))?;
+    return Ok(());
+  }
+
+  /// The file backing the bundle.
+  pub fn file(&self) -> Option<&Path> {
+    return self.file.as_deref();
+  }
+
+  /// Registers state to be saved under the name. Fails if the name is
+  /// already in use.
+  pub fn register<T: Saveable + 'static>(
+    &mut self,
+    name: &str,
+    saveable: Rc<RefCell<T>>,
+  ) -> Result<(), String> {
+    if self
+      .saveables
+      .iter()
+      .any(|(registered, _)| registered == name)
+    {
+      return Err(format!(
+        "Saved state named {} has already been registered.",
+        name
+      ));
+    }
+    self.saveables.push((name.to_string(), saveable));
+    return Ok(());
+  }
+
+  /// The bytes saved under the name, such as for state that has to be
+  /// restored before it's registered.
+  pub fn entry(&self, name: &str) -> Option<&[u8]> {
+    return self.entries.get(name).map(Vec::as_slice);
+  }
+
+  /// Sets the bytes saved under the name.
+  pub fn set_entry(&mut self, name: &str, bytes: Vec<u8>) {
+    self.entries.insert(name.to_string(), bytes);
+  }
+
+  /// Restores all registered state that has an entry. State that fails to
+  /// restore keeps its current value, and the failures are returned
+  /// together once everything else has been restored.
+  pub fn restore(&mut self) -> Result<(), String> {
+    let mut failures = vec![];
+    for (name, saveable) in self.saveables.iter() {
+      let bytes = match self.entries.get(name) {
+        Some(bytes) => bytes,
+        None => continue,
+      };
+      if let Err(error) = saveable.borrow_mut().restore_state(bytes) {
+        failures.push(format!("{}: {}", name, error));
+      }
+    }
+
+    return match failures.is_empty() {
+      true => Ok(()),
+      false => Err(format!(
+        "Failed to restore saved state. {}",
+        failures.join("; ")
+      )),
+    };
+  }
+
+  /// Captures the state of everything registered and writes the bundle to
+  /// its file, if it's backed by one. The file is replaced only once the
+  /// new bundle has been written, so a failed save keeps the last one.
+  pub fn save(&mut self) -> Result<(), String> {
+    for (name, saveable) in self.saveables.iter() {
+      self
+        .entries
+        .insert(name.clone(), saveable.borrow().save_state());
+    }
+
+    let path = match self.file.as_ref() {
+      Some(path) => path,
+      None => return Ok(()),
+    };
+    let temporary = path.with_extension("tmp");
+    std::fs::write(&temporary, self.to_bytes())
+      .and_then(|_| std::fs::rename(&temporary, path))
+      .map_err(|error| {
+        format!("Failed to write {}: {}", path.display(), error)
+      })?;
+    return Ok(());
+  }
+
+  /// Encodes the entries as the magic bytes, format version, and entry
+  /// count, followed by the length-prefixed name and bytes of every entry,
+  /// all in little endian.
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+    for (name, entry) in self.entries.iter() {
+      bytes.extend_from_slice(&(name.len() as u32).to_le_bytes());
+      bytes.extend_from_slice(name.as_bytes());
+      bytes.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+      bytes.extend_from_slice(entry);
+    }
+    return bytes;
+  }
+}
+
+impl Default for SaveBundle {
+  fn default() -> Self {
+    return Self::new();
+  }
+}
+
+/// Decodes the entries of a save bundle.
+fn decode(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>, String> {
+  let mut offset = 0;
+  if take(bytes, &mut offset, 4)? != MAGIC {
+    return Err("The file isn't a save bundle.".to_string());
+  }
+  let version = take_u32(bytes, &mut offset)?;
+  if version != FORMAT_VERSION {
+    return Err(format!(
+      "The save bundle is version {}, but only version {} is supported.",
+      version, FORMAT_VERSION
+    ));
+  }
+
+  let count = take_u32(bytes, &mut offset)?;
+  let mut entries = BTreeMap::new();
+  for _ in 0..count {
+    let length = take_u32(bytes, &mut offset)? as usize;
+    let name = String::from_utf8(take(bytes, &mut offset, length)?.to_vec())
+      .map_err(|_| "The name of an entry isn't UTF-8.".to_string())?;
+    let length = take_u32(bytes, &mut offset)? as usize;
+    entries.insert(name, take(bytes, &mut offset, length)?.to_vec());
+  }
+  if offset != bytes.len() {
+    return Err("The save bundle has bytes after its entries.".to_string());
+  }
+  return Ok(entries);
+}
+
+/// Takes the next bytes from the offset, advancing it past them.
+fn take<'a>(
+  bytes: &'a [u8],
+  offset: &mut usize,
+  length: usize,
+) -> Result<&'a [u8], String> {
+  let taken = bytes
+    .get(*offset..*offset + length)
+    .ok_or_else(|| "The save bundle is truncated.".to_string())?;
+  *offset += length;
+  return Ok(taken);
+}
+
+fn take_u32(bytes: &[u8], offset: &mut usize) -> Result<u32, String> {
+  let word = take(bytes, offset, 4)?;
+  return Ok(u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    cell::RefCell,
+    rc::Rc,
+  };
+
+  use super::{
+    decode,
+    SaveBundle,
+    Saveable,
+  };
+  use crate::scene::Transform;
+
+  #[test]
+  fn bundles_round_trip_and_keep_unregistered_entries() {
+    let camera = Rc::new(RefCell::new(
+      Transform::new()
+        .with_translation([1.0, 2.0, 3.0])
+        .with_rotation([0.0, 0.5, 0.0]),
+    ));
+    let mut bundle = SaveBundle::new();
+    bundle.register("camera", camera.clone()).unwrap();
+    assert!(bundle.register("camera", camera.clone()).is_err());
+    bundle.set_entry("unused", vec![7, 8]);
+    bundle.save().unwrap();
+
+    let bytes = bundle.to_bytes();
+    let entries = decode(&bytes).unwrap();
+    assert_eq!(entries["camera"], camera.borrow().save_state());
+    assert_eq!(entries["unused"], vec![7, 8]);
+    assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+    assert!(decode(b"nope").is_err());
+
+    let restored = Rc::new(RefCell::new(Transform::new()));
+    let mut bundle = SaveBundle::new();
+    bundle.entries = entries;
+    bundle.register("camera", restored.clone()).unwrap();
+    bundle.restore().unwrap();
+    assert_eq!(*restored.borrow(), *camera.borrow());
+  }
+
+  #[test]
+  fn bundles_are_saved_to_and_loaded_from_files() {
+    let path = std::env::temp_dir()
+      .join(format!("lambda-save-bundle-{}.bin", std::process::id()));
+    std::fs::remove_file(&path).ok();
+
+    let pose = Transform::new().with_translation([4.0, 5.0, 6.0]);
+    let mut bundle = SaveBundle::new();
+    bundle.load_file(&path).unwrap();
+    bundle
+      .register("camera", Rc::new(RefCell::new(pose)))
+      .unwrap();
+    bundle.set_entry("broken", vec![1, 2, 3]);
+    bundle.save().unwrap();
+
+    let camera = Rc::new(RefCell::new(Transform::new()));
+    let broken = Rc::new(RefCell::new(Transform::new()));
+    let mut bundle = SaveBundle::new();
+    bundle.load_file(&path).unwrap();
+    bundle.register("camera", camera.clone()).unwrap();
+    bundle.register("broken", broken.clone()).unwrap();
+
+    // State that fails to restore is reported without stopping the rest.
+    let error = bundle.restore().unwrap_err();
+    assert!(error.contains("broken"));
+    assert_eq!(*camera.borrow(), pose);
+    assert_eq!(*broken.borrow(), Transform::new());
+
+    std::fs::write(&path, b"LSAV").unwrap();
+    assert!(SaveBundle::new().load_file(&path).is_err());
+    std::fs::remove_file(&path).ok();
+  }
+}
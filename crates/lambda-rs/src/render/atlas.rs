@@ -0,0 +1,720 @@
+//! Texture atlases that pack many images into a single RGBA image, along with
+//! a JSON manifest of where every image was placed. Atlases are built ahead
+//! of time by the lambda-atlas tool and loaded at runtime with `AtlasLoader`
+//! or through the asset server.
+
+use std::path::Path;
+
+use lambda_platform::png::{
+  self,
+  Image,
+};
+use logging::handler::escape_json;
+
+use crate::assets::{
+  vfs::{
+    normalize_path,
+    Vfs,
+  },
+  Asset,
+};
+
+// ------------------------------- ATLAS REGION --------------------------------
+
+/// Where an image was placed within an atlas. Transparent borders are
+/// trimmed from images before they're packed, so the region records how to
+/// restore the image's original size & placement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AtlasRegion {
+  /// The name of the image, which is its path relative to the directory it
+  /// was packed from without the extension.
+  pub name: String,
+  /// The top left corner of the trimmed image within the atlas.
+  pub position: (u32, u32),
+  /// The (width, height) of the trimmed image within the atlas.
+  pub size: (u32, u32),
+  /// The top left & bottom right texture coordinates of the region.
+  pub uv: [f32; 4],
+  /// The offset of the trimmed image from the top left corner of the
+  /// original image.
+  pub trim_offset: (u32, u32),
+  /// The (width, height) of the original image before it was trimmed.
+  pub source_size: (u32, u32),
+}
+
+// ------------------------------- ATLAS BUILDER -------------------------------
+
+/// Builder for texture atlases.
+pub struct AtlasBuilder {
+  width: u32,
+  padding: u32,
+  trim: bool,
+  images: Vec<(String, Image)>,
+}
+
+impl AtlasBuilder {
+  /// A new atlas builder packs images into an atlas that is 1024 pixels wide,
+  /// with a pixel of padding between them and their transparent borders
+  /// trimmed.
+  pub fn new() -> Self {
+    return Self {
+      width: 1024,
+      padding: 1,
+      trim: true,
+      images: vec![],
+    };
+  }
+
+  /// The width of the atlas in pixels. The height of the atlas grows to fit
+  /// all of the images.
+  pub fn with_width(mut self, width: u32) -> Self {
+    self.width = width;
+    return self;
+  }
+
+  /// Empty pixels placed between images within the atlas, which keeps
+  /// filtering from bleeding neighbouring images into each other.
+  pub fn with_padding(mut self, padding: u32) -> Self {
+    self.padding = padding;
+    return self;
+  }
+
+  /// Whether to trim the fully transparent borders of images before packing
+  /// them.
+  pub fn with_trimming(mut self, trim: bool) -> Self {
+    self.trim = trim;
+    return self;
+  }
+
+  /// Adds an image to the atlas under the given name.
+  pub fn with_image(mut self, name: &str, image: Image) -> Self {
+    self.images.push((name.to_string(), image));
+    return self;
+  }
+
+  /// Adds every PNG within the directory and its subdirectories, named by
+  /// their paths relative to the directory without the extension.
+  pub fn with_directory(mut self, directory: &Path) -> Result<Self, String> {
+    let mut directories = vec![directory.to_path_buf()];
+    let mut paths = vec![];
+    while let Some(current) = directories.pop() {
+      let entries = std::fs::read_dir(&current).map_err(|error| {
+        format!("Failed to read {}: {}", current.display(), error)
+      })?;
+      for entry in entries {
+        let path = entry
+          .map_err(|error| {
+            format!("Failed to read {}: {}", current.display(), error)
+          })?
+          .path();
+        if path.is_dir() {
+          directories.push(path);
+        } else if path.extension().is_some_and(|extension| extension == "png") {
+          paths.push(path);
+        }
+      }
+    }
+
+    // Images are added in order of their paths so that atlases are
+    // reproducible.
+    paths.sort();
+    for path in paths {
+      let bytes = std::fs::read(&path).map_err(|error| {
+        format!("Failed to read {}: {}", path.display(), error)
+      })?;
+      let image = png::decode(&bytes)
+        .map_err(|error| format!("{}: {}", path.display(), error))?;
+      let relative = path
+        .strip_prefix(directory)
+        .expect("Images are within the directory being packed.")
+        .with_extension("");
+      self = self.with_image(&normalize_path(&relative), image);
+    }
+    return Ok(self);
+  }
+
+  /// Packs the images into rows of the atlas, tallest first. Fails if two
+  /// images share a name or an image is wider than the atlas.
+  pub fn build(self) -> Result<TextureAtlas, String> {
+    let mut trimmed = vec![];
+    for (index, (name, image)) in self.images.iter().enumerate() {
+      if self.images[..index].iter().any(|(other, _)| other == name) {
+        return Err(format!("Two images are named {}.", name));
+      }
+      let bounds = match self.trim {
+        true => opaque_bounds(image),
+        false => (0, 0, image.width, image.height),
+      };
+      if bounds.2 + self.padding * 2 > self.width {
+        return Err(format!(
+          "{} is {} pixels wide and can't fit in an atlas {} pixels wide.",
+          name, bounds.2, self.width
+        ));
+      }
+      trimmed.push((name, image, bounds));
+    }
+    trimmed.sort_by(|(a_name, _, a), (b_name, _, b)| {
+      return b.3.cmp(&a.3).then(a_name.cmp(b_name));
+    });
+
+    let mut placements = vec![];
+    let (mut x, mut y, mut row_height) = (self.padding, self.padding, 0);
+    for (_, _, (_, _, width, height)) in trimmed.iter() {
+      if x + width + self.padding > self.width {
+        x = self.padding;
+        y += row_height + self.padding;
+        row_height = 0;
+      }
+      placements.push((x, y));
+      x += width + self.padding;
+      row_height = row_height.max(*height);
+    }
+
+    let mut atlas = Image::new(self.width, y + row_height + self.padding);
+    let mut regions = vec![];
+    for ((name, image, bounds), (x, y)) in trimmed.into_iter().zip(placements) {
+      let (left, top, width, height) = bounds;
+      for row in 0..height {
+        let source = ((top + row) * image.width + left) as usize * 4;
+        let destination = ((y + row) * atlas.width + x) as usize * 4;
+        let length = width as usize * 4;
+        atlas.pixels[destination..destination + length]
+          .copy_from_slice(&image.pixels[source..source + length]);
+      }
+
+      regions.push(AtlasRegion {
+        name: name.clone(),
+        position: (x, y),
+        size: (width, height),
+        uv: uv_rect((x, y), (width, height), (atlas.width, atlas.height)),
+        trim_offset: (left, top),
+        source_size: (image.width, image.height),
+      });
+    }
+    regions.sort_by(|a, b| a.name.cmp(&b.name));
+
+    return Ok(TextureAtlas {
+      image: atlas,
+      regions,
+    });
+  }
+}
+
+impl Default for AtlasBuilder {
+  fn default() -> Self {
+    return Self::new();
+  }
+}
+
+/// The (left, top, width, height) of the pixels within the image that aren't
+/// fully transparent, which is empty for fully transparent images.
+fn opaque_bounds(image: &Image) -> (u32, u32, u32, u32) {
+  let (mut left, mut top) = (image.width, image.height);
+  let (mut right, mut bottom) = (0, 0);
+  for (index, pixel) in image.pixels.chunks_exact(4).enumerate() {
+    if pixel[3] == 0 {
+      continue;
+    }
+    let (x, y) = (index as u32 % image.width, index as u32 / image.width);
+    left = left.min(x);
+    top = top.min(y);
+    right = right.max(x + 1);
+    bottom = bottom.max(y + 1);
+  }
+
+  if right == 0 {
+    return (0, 0, 0, 0);
+  }
+  return (left, top, right - left, bottom - top);
+}
+
+/// The texture coordinates of the top left & bottom right corners of a
+/// region within an atlas.
+fn uv_rect(
+  position: (u32, u32),
+  size: (u32, u32),
+  dimensions: (u32, u32),
+) -> [f32; 4] {
+  let (width, height) =
+    (dimensions.0.max(1) as f32, dimensions.1.max(1) as f32);
+  return [
+    position.0 as f32 / width,
+    position.1 as f32 / height,
+    (position.0 + size.0) as f32 / width,
+    (position.1 + size.1) as f32 / height,
+  ];
+}
+
+// ------------------------------- TEXTURE ATLAS -------------------------------
+
+/// An RGBA image that images have been packed into & where each of them was
+/// placed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureAtlas {
+  image: Image,
+  regions: Vec<AtlasRegion>,
+}
+
+impl TextureAtlas {
+  /// The (width, height) of the atlas in pixels.
+  pub fn dimensions(&self) -> (u32, u32) {
+    return (self.image.width, self.image.height);
+  }
+
+  /// The RGBA image that the images were packed into.
+  pub fn image(&self) -> &Image {
+    return &self.image;
+  }
+
+  /// Every region within the atlas, sorted by name.
+  pub fn regions(&self) -> &[AtlasRegion] {
+    return &self.regions;
+  }
+
+  /// The region of the image with the given name, if it was packed.
+  pub fn region(&self, name: &str) -> Option<&AtlasRegion> {
+    return self
+      .regions
+      .binary_search_by(|region| region.name.as_str().cmp(name))
+      .ok()
+      .map(|index| &self.regions[index]);
+  }
+
+  /// Encodes the atlas image as a PNG.
+  pub fn encode_image(&self) -> Result<Vec<u8>, String> {
+    return png::encode(&self.image);
+  }
+
+  /// The JSON manifest of the atlas, which refers to the atlas image by a
+  /// path relative to the manifest.
+  pub fn manifest(&self, image_path: &str) -> String {
+    let regions: Vec<String> = self
+      .regions
+      .iter()
+      .map(|region| {
+        return format!(
+          "    {{\"name\": \"{}\", \"position\": [{}, {}], \
+           \"size\": [{}, {}], \"uv\": [{}, {}, {}, {}], \
+           \"trim_offset\": [{}, {}], \"source_size\": [{}, {}]}}",
+          escape_json(&region.name),
+          region.position.0,
+          region.position.1,
+          region.size.0,
+          region.size.1,
+          region.uv[0],
+          region.uv[1],
+          region.uv[2],
+          region.uv[3],
+          region.trim_offset.0,
+          region.trim_offset.1,
+          region.source_size.0,
+          region.source_size.1
+        );
+      })
+      .collect();
+
+    return format!(
+      "{{\n  \"image\": \"{}\",\n  \"size\": [{}, {}],\n  \"regions\": [\n{}\n  \
+       ]\n}}\n",
+      escape_json(image_path),
+      self.image.width,
+      self.image.height,
+      regions.join(",\n")
+    );
+  }
+}
+
+// ------------------------------- ATLAS LOADER --------------------------------
+
+/// Loads texture atlases from their JSON manifests & PNG images.
+pub struct AtlasLoader {}
+
+impl AtlasLoader {
+  pub fn new() -> Self {
+    return Self {};
+  }
+
+  /// Loads the atlas whose manifest is at the given path.
+  pub fn load_from_file(&self, path: &Path) -> Result<TextureAtlas, String> {
+    let read = |path: &Path| {
+      return std::fs::read(path).map_err(|error| {
+        format!("Failed to read {}: {}", path.display(), error)
+      });
+    };
+    let manifest = String::from_utf8(read(path)?).map_err(|_| {
+      format!("The atlas manifest {} isn't UTF-8.", path.display())
+    })?;
+    return self.load_from_manifest(
+      &manifest,
+      path.parent().unwrap_or(Path::new("")),
+      read,
+    );
+  }
+
+  /// Loads an atlas from its manifest, reading the image it refers to with
+  /// the callback. The image path is relative to the directory.
+  pub fn load_from_manifest(
+    &self,
+    manifest: &str,
+    directory: &Path,
+    read: impl Fn(&Path) -> Result<Vec<u8>, String>,
+  ) -> Result<TextureAtlas, String> {
+    let invalid = |reason: &str| format!("Invalid atlas manifest: {}", reason);
+    let manifest = json::parse(manifest).map_err(|error| invalid(&error))?;
+
+    let image_path = manifest
+      .get("image")
+      .and_then(json::Value::as_str)
+      .ok_or_else(|| invalid("missing the image path"))?;
+    let size = manifest
+      .get("size")
+      .and_then(json::Value::as_pair)
+      .ok_or_else(|| invalid("missing the atlas size"))?;
+    let image = png::decode(&read(&directory.join(image_path))?)?;
+    if (image.width, image.height) != size {
+      return Err(invalid(&format!(
+        "the image is {}x{} but the manifest expects {}x{}",
+        image.width, image.height, size.0, size.1
+      )));
+    }
+
+    let mut regions = vec![];
+    for region in manifest
+      .get("regions")
+      .and_then(json::Value::as_array)
+      .ok_or_else(|| invalid("missing the regions"))?
+    {
+      let name = region
+        .get("name")
+        .and_then(json::Value::as_str)
+        .ok_or_else(|| invalid("a region is missing its name"))?;
+      let pair = |key: &str| {
+        return region
+          .get(key)
+          .and_then(json::Value::as_pair)
+          .ok_or_else(|| invalid(&format!("{} is missing its {}", name, key)));
+      };
+      let (position, region_size) = (pair("position")?, pair("size")?);
+      if position.0 + region_size.0 > size.0
+        || position.1 + region_size.1 > size.1
+      {
+        return Err(invalid(&format!("{} is outside of the atlas", name)));
+      }
+
+      regions.push(AtlasRegion {
+        name: name.to_string(),
+        position,
+        size: region_size,
+        uv: uv_rect(position, region_size, size),
+        trim_offset: pair("trim_offset")?,
+        source_size: pair("source_size")?,
+      });
+    }
+    regions.sort_by(|a, b| a.name.cmp(&b.name));
+
+    return Ok(TextureAtlas { image, regions });
+  }
+}
+
+impl Default for AtlasLoader {
+  fn default() -> Self {
+    return Self::new();
+  }
+}
+
+/// Atlases are loaded from their JSON manifests, whose images are read from
+/// the same filesystem.
+impl Asset for TextureAtlas {
+  fn load(path: &Path, files: &Vfs) -> Result<Self, String> {
+    return AtlasLoader::new().load_from_manifest(
+      &files.read_to_string(path)?,
+      path.parent().unwrap_or(Path::new("")),
+      |path| files.read(path),
+    );
+  }
+}
+
+// ----------------------------------- JSON ------------------------------------
+
+/// Just enough JSON to read atlas manifests.
+mod json {
+  use std::{
+    iter::Peekable,
+    str::Chars,
+  };
+
+  #[derive(Clone, Debug, PartialEq)]
+  pub enum Value {
+    Null,
+    Boolean(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+  }
+
+  impl Value {
+    /// The member of an object with the given key.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+      return match self {
+        Value::Object(members) => members
+          .iter()
+          .find(|(member, _)| member == key)
+          .map(|(_, value)| value),
+        _ => None,
+      };
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+      return match self {
+        Value::String(value) => Some(value),
+        _ => None,
+      };
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+      return match self {
+        Value::Array(values) => Some(values),
+        _ => None,
+      };
+    }
+
+    /// An array of two unsigned integers.
+    pub fn as_pair(&self) -> Option<(u32, u32)> {
+      let integer = |value: &Value| match value {
+        Value::Number(number)
+          if number.fract() == 0.0
+            && (0.0..=u32::MAX as f64).contains(number) =>
+        {
+          Some(*number as u32)
+        }
+        _ => None,
+      };
+      return match self.as_array()?.as_slice() {
+        [x, y] => Some((integer(x)?, integer(y)?)),
+        _ => None,
+      };
+    }
+  }
+
+  /// Parses a JSON document.
+  pub fn parse(text: &str) -> Result<Value, String> {
+    let mut characters = text.chars().peekable();
+    let value = parse_value(&mut characters)?;
+    skip_whitespace(&mut characters);
+    return match characters.next() {
+      Some(character) => {
+        Err(format!("unexpected {:?} after the document", character))
+      }
+      None => Ok(value),
+    };
+  }
+
+  fn skip_whitespace(characters: &mut Peekable<Chars>) {
+    while characters
+      .next_if(|character| character.is_whitespace())
+      .is_some()
+    {}
+  }
+
+  fn expect(
+    characters: &mut Peekable<Chars>,
+    text: &str,
+  ) -> Result<(), String> {
+    for expected in text.chars() {
+      if characters.next() != Some(expected) {
+        return Err(format!("expected {}", text));
+      }
+    }
+    return Ok(());
+  }
+
+  fn parse_value(characters: &mut Peekable<Chars>) -> Result<Value, String> {
+    skip_whitespace(characters);
+    return match characters.peek() {
+      Some('{') => parse_object(characters),
+      Some('[') => {
+        characters.next();
+        let mut values = vec![];
+        skip_whitespace(characters);
+        if characters.next_if_eq(&']').is_some() {
+          return Ok(Value::Array(values));
+        }
+        loop {
+          values.push(parse_value(characters)?);
+          skip_whitespace(characters);
+          match characters.next() {
+            Some(',') => continue,
+            Some(']') => return Ok(Value::Array(values)),
+            _ => return Err("expected , or ] within an array".to_string()),
+          }
+        }
+      }
+      Some('"') => parse_string(characters).map(Value::String),
+      Some('t') => expect(characters, "true").map(|_| Value::Boolean(true)),
+      Some('f') => expect(characters, "false").map(|_| Value::Boolean(false)),
+      Some('n') => expect(characters, "null").map(|_| Value::Null),
+      Some(_) => {
+        let mut number = String::new();
+        while let Some(character) = characters.next_if(|character| {
+          return character.is_ascii_digit() || "+-.eE".contains(*character);
+        }) {
+          number.push(character);
+        }
+        number
+          .parse()
+          .map(Value::Number)
+          .map_err(|_| format!("{:?} isn't a value", number))
+      }
+      None => Err("unexpected end of the document".to_string()),
+    };
+  }
+
+  fn parse_object(characters: &mut Peekable<Chars>) -> Result<Value, String> {
+    characters.next();
+    let mut members = vec![];
+    skip_whitespace(characters);
+    if characters.next_if_eq(&'}').is_some() {
+      return Ok(Value::Object(members));
+    }
+    loop {
+      skip_whitespace(characters);
+      let key = parse_string(characters)?;
+      skip_whitespace(characters);
+      expect(characters, ":")?;
+      members.push((key, parse_value(characters)?));
+      skip_whitespace(characters);
+      match characters.next() {
+        Some(',') => continue,
+        Some('}') => return Ok(Value::Object(members)),
+        _ => return Err("expected , or } within an object".to_string()),
+      }
+    }
+  }
+
+  fn parse_string(characters: &mut Peekable<Chars>) -> Result<String, String> {
+    expect(characters, "\"")?;
+    let mut string = String::new();
+    loop {
+      match characters.next() {
+        Some('"') => return Ok(string),
+        Some('\\') => match characters.next() {
+          Some('n') => string.push('\n'),
+          Some('r') => string.push('\r'),
+          Some('t') => string.push('\t'),
+          Some('b') => string.push('\u{8}'),
+          Some('f') => string.push('\u{c}'),
+          Some('u') => {
+            let code: String = characters.by_ref().take(4).collect();
+            let character = u32::from_str_radix(&code, 16)
+              .ok()
+              .and_then(char::from_u32)
+              .ok_or_else(|| format!("\\u{} isn't a character", code))?;
+            string.push(character);
+          }
+          Some(character) => string.push(character),
+          None => return Err("unterminated string".to_string()),
+        },
+        Some(character) => string.push(character),
+        None => return Err("unterminated string".to_string()),
+      }
+    }
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use lambda_platform::png::{
+    self,
+    Image,
+  };
+
+  use super::{
+    AtlasBuilder,
+    AtlasLoader,
+  };
+
+  /// An image with an opaque rectangle at the offset.
+  fn image(
+    size: (u32, u32),
+    offset: (u32, u32),
+    opaque: (u32, u32),
+    color: u8,
+  ) -> Image {
+    let mut image = Image::new(size.0, size.1);
+    for y in offset.1..offset.1 + opaque.1 {
+      for x in offset.0..offset.0 + opaque.0 {
+        let index = ((y * size.0 + x) * 4) as usize;
+        image.pixels[index..index + 4].copy_from_slice(&[color, 0, 0, 255]);
+      }
+    }
+    return image;
+  }
+
+  #[test]
+  fn images_are_trimmed_and_packed_without_overlapping() {
+    let atlas = AtlasBuilder::new()
+      .with_width(16)
+      .with_image("hero", image((8, 8), (2, 1), (4, 6), 10))
+      .with_image("coin", image((4, 4), (0, 0), (4, 4), 20))
+      .with_image("empty", Image::new(4, 4))
+      .build()
+      .unwrap();
+
+    let hero = atlas.region("hero").unwrap();
+    assert_eq!(hero.size, (4, 6));
+    assert_eq!(hero.trim_offset, (2, 1));
+    assert_eq!(hero.source_size, (8, 8));
+    assert_eq!(hero.position, (1, 1));
+
+    let coin = atlas.region("coin").unwrap();
+    assert_eq!(coin.position, (6, 1));
+    assert_eq!(atlas.region("empty").unwrap().size, (0, 0));
+    assert_eq!(atlas.dimensions(), (16, 8));
+
+    let pixel = |(x, y): (u32, u32)| {
+      return atlas.image().pixels[((y * 16 + x) * 4) as usize];
+    };
+    assert_eq!(pixel(hero.position), 10);
+    assert_eq!(pixel((coin.position.0 + 3, coin.position.1 + 3)), 20);
+    assert_eq!(coin.uv, [6.0 / 16.0, 1.0 / 8.0, 10.0 / 16.0, 5.0 / 8.0]);
+
+    assert!(AtlasBuilder::new()
+      .with_width(4)
+      .with_image("wide", image((8, 1), (0, 0), (8, 1), 1))
+      .build()
+      .is_err());
+  }
+
+  #[test]
+  fn manifests_load_back_into_the_same_atlas() {
+    let atlas = AtlasBuilder::new()
+      .with_width(32)
+      .with_image("ui/button \"pressed\"", image((6, 3), (1, 1), (4, 2), 1))
+      .with_image("ui/icon", image((5, 5), (0, 0), (5, 5), 2))
+      .build()
+      .unwrap();
+    let manifest = atlas.manifest("atlas.png");
+    let encoded = atlas.encode_image().unwrap();
+
+    let loaded = AtlasLoader::new()
+      .load_from_manifest(&manifest, Path::new("sprites"), |path| {
+        assert_eq!(path, Path::new("sprites/atlas.png"));
+        return Ok(encoded.clone());
+      })
+      .unwrap();
+    assert_eq!(loaded, atlas);
+
+    let mismatched = png::encode(&Image::new(2, 2)).unwrap();
+    assert!(AtlasLoader::new()
+      .load_from_manifest(&manifest, Path::new(""), |_| Ok(mismatched.clone()))
+      .is_err());
+    assert!(AtlasLoader::new()
+      .load_from_manifest("{\"image\": ", Path::new(""), |_| Ok(vec![]))
+      .is_err());
+  }
+}
@@ -14,13 +14,21 @@ use std::rc::Rc;
 // publicly use Properties and Usage from buffer.rs
 pub use lambda_platform::gfx::buffer::{
   BufferType,
+  IndexType,
   Properties,
   Usage,
 };
 use logging;
 
 use super::{
-  mesh::Mesh,
+  memory::{
+    AllocationId,
+    MemoryCategory,
+  },
+  mesh::{
+    Mesh,
+    MeshIndices,
+  },
   vertex::Vertex,
   RenderContext,
 };
@@ -30,6 +38,11 @@ use super::{
 pub struct Buffer {
   buffer: Rc<internal::Buffer<super::internal::RenderBackend>>,
   buffer_type: BufferType,
+  index_buffer: Option<(
+    Rc<internal::Buffer<super::internal::RenderBackend>>,
+    IndexType,
+  )>,
+  allocations: Vec<AllocationId>,
 }
 
 /// Public interface for a buffer.
@@ -37,9 +50,40 @@ impl Buffer {
   /// Destroy the buffer and all it's resources with the render context that
   /// created it.
   pub fn destroy(self, render_context: &RenderContext) {
+    for allocation in self.allocations {
+      render_context.untrack_allocation(allocation);
+    }
     Rc::try_unwrap(self.buffer)
       .expect("Failed to get inside buffer")
       .destroy(render_context.internal_gpu());
+
+    if let Some((index_buffer, _)) = self.index_buffer {
+      Rc::try_unwrap(index_buffer)
+        .expect("Failed to get inside index buffer")
+        .destroy(render_context.internal_gpu());
+    }
+  }
+
+  /// The number of elements the buffer has room for, such as vertices.
+  pub fn element_count(&self) -> u32 {
+    return (self.buffer.length() / self.buffer.stride().max(1)) as u32;
+  }
+
+  /// The number of indices within the index buffer that was built alongside
+  /// this buffer, if there is one.
+  pub fn index_count(&self) -> Option<u32> {
+    return self.index_buffer.as_ref().map(|(index_buffer, _)| {
+      return (index_buffer.length() / index_buffer.stride().max(1)) as u32;
+    });
+  }
+
+  /// The type of the indices within the index buffer that was built
+  /// alongside this buffer, if there is one.
+  pub fn index_type(&self) -> Option<IndexType> {
+    return self
+      .index_buffer
+      .as_ref()
+      .map(|(_, index_type)| *index_type);
   }
 }
 
@@ -57,6 +101,15 @@ impl Buffer {
   ) -> &internal::Buffer<super::internal::RenderBackend> {
     return &self.buffer;
   }
+
+  pub(super) fn internal_index_buffer_rc(
+    &self,
+  ) -> Option<(
+    Rc<internal::Buffer<super::internal::RenderBackend>>,
+    IndexType,
+  )> {
+    return self.index_buffer.clone();
+  }
 }
 
 /// A buffer is a block of memory that can be used to store data that can be
@@ -65,6 +118,17 @@ impl Buffer {
 pub struct BufferBuilder {
   buffer_builder: internal::BufferBuilder,
   buffer_type: BufferType,
+  label: String,
+}
+
+/// The category that the memory of a buffer is reported under.
+fn memory_category(buffer_type: BufferType) -> MemoryCategory {
+  return match buffer_type {
+    BufferType::Vertex => MemoryCategory::VertexBuffer,
+    BufferType::Index => MemoryCategory::IndexBuffer,
+    BufferType::Uniform => MemoryCategory::UniformBuffer,
+    BufferType::Storage => MemoryCategory::StorageBuffer,
+  };
 }
 
 impl BufferBuilder {
@@ -73,10 +137,13 @@ impl BufferBuilder {
     return Self {
       buffer_builder: internal::BufferBuilder::new(),
       buffer_type: BufferType::Vertex,
+      label: "unlabeled".to_string(),
     };
   }
 
   /// Builds a buffer from a given mesh and allocates it's memory on to the GPU.
+  /// Indexed meshes also have their indices allocated into an index buffer
+  /// that is bound with `RenderCommand::BindIndexBuffer`.
   pub fn build_from_mesh(
     mesh: &Mesh,
     render_context: &mut RenderContext,
@@ -94,17 +161,83 @@ impl BufferBuilder {
         mesh.vertices().to_vec(),
       );
 
-    match internal_buffer {
-      Ok(internal_buffer) => {
-        return Ok(Buffer {
-          buffer: Rc::new(internal_buffer),
-          buffer_type: BufferType::Vertex,
-        });
-      }
+    let internal_buffer = match internal_buffer {
+      Ok(internal_buffer) => internal_buffer,
       Err(_) => {
         return Err("Failed to create buffer from mesh.");
       }
+    };
+
+    let index_buffer = match mesh.indices() {
+      Some(indices) => {
+        match Self::build_index_buffer(indices, render_context) {
+          Ok(index_buffer) => Some(index_buffer),
+          Err(error) => {
+            internal_buffer.destroy(render_context.internal_gpu());
+            return Err(error);
+          }
+        }
+      }
+      None => None,
+    };
+
+    let mut allocations = vec![render_context.track_allocation(
+      MemoryCategory::VertexBuffer,
+      "mesh",
+      internal_buffer.length() as u64,
+    )];
+    if let Some((index_buffer, _)) = index_buffer.as_ref() {
+      allocations.push(render_context.track_allocation(
+        MemoryCategory::IndexBuffer,
+        "mesh",
+        index_buffer.length() as u64,
+      ));
     }
+
+    return Ok(Buffer {
+      buffer: Rc::new(internal_buffer),
+      buffer_type: BufferType::Vertex,
+      index_buffer,
+      allocations,
+    });
+  }
+
+  /// Allocates the indices of a mesh into an index buffer on the GPU.
+  fn build_index_buffer(
+    indices: &MeshIndices,
+    render_context: &mut RenderContext,
+  ) -> Result<
+    (
+      Rc<internal::Buffer<super::internal::RenderBackend>>,
+      IndexType,
+    ),
+    &'static str,
+  > {
+    let mut buffer_builder = internal::BufferBuilder::new();
+    buffer_builder
+      .with_usage(Usage::INDEX)
+      .with_properties(Properties::CPU_VISIBLE)
+      .with_buffer_type(BufferType::Index);
+
+    let (index_buffer, index_type) = match indices {
+      MeshIndices::U16(indices) => (
+        buffer_builder
+          .with_length(std::mem::size_of_val(indices.as_slice()))
+          .build(render_context.internal_mutable_gpu(), indices.clone()),
+        IndexType::U16,
+      ),
+      MeshIndices::U32(indices) => (
+        buffer_builder
+          .with_length(std::mem::size_of_val(indices.as_slice()))
+          .build(render_context.internal_mutable_gpu(), indices.clone()),
+        IndexType::U32,
+      ),
+    };
+
+    return match index_buffer {
+      Ok(index_buffer) => Ok((Rc::new(index_buffer), index_type)),
+      Err(_) => Err("Failed to create index buffer from mesh."),
+    };
   }
 
   /// Sets the length of the buffer (In bytes).
@@ -120,6 +253,12 @@ impl BufferBuilder {
     return self;
   }
 
+  /// Sets the label that the buffer's memory is reported under.
+  pub fn with_label(&mut self, label: &str) -> &mut Self {
+    self.label = label.to_string();
+    return self;
+  }
+
   /// Sets the usage of the buffer.
   pub fn with_usage(&mut self, usage: Usage) -> &mut Self {
     self.buffer_builder.with_usage(usage);
@@ -148,9 +287,16 @@ impl BufferBuilder {
           "Buffer allocation for {:?} succeeded.",
           self.buffer_type
         );
+        let allocation = render_context.track_allocation(
+          memory_category(self.buffer_type),
+          &self.label,
+          buffer.length() as u64,
+        );
         return Ok(Buffer {
           buffer: Rc::new(buffer),
           buffer_type: self.buffer_type,
+          index_buffer: None,
+          allocations: vec![allocation],
         });
       }
       Err(error) => {
@@ -0,0 +1,176 @@
+//! An on-disk cache for compiled shaders & pipeline caches, so that work
+//! which only depends on its inputs isn't repeated on every launch.
+//!
+//! Entries are files within the cache directory that start with the magic
+//! bytes `LCCH`, the format version, the hash of what produced the entry
+//! (such as the shader compiler or the GPU & driver), and the length of the
+//! data. Entries whose format or producer differ are treated as missing and
+//! are replaced when they're stored again.
+
+use std::{
+  fs,
+  path::PathBuf,
+};
+
+const MAGIC: &[u8; 4] = b"LCCH";
+const VERSION: u32 = 1;
+const HEADER_SIZE: usize = 4 + 4 + 8 + 8;
+
+/// Hashes bytes with 64 bit FNV-1a, which is stable across runs & platforms.
+pub fn content_hash(bytes: &[u8]) -> u64 {
+  let mut hash: u64 = 0xcbf29ce484222325;
+  for byte in bytes {
+    hash ^= *byte as u64;
+    hash = hash.wrapping_mul(0x100000001b3);
+  }
+  return hash;
+}
+
+/// A directory of cache entries, addressed by a name that's usually the
+/// content hash of the entry's inputs.
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+  directory: PathBuf,
+}
+
+impl DiskCache {
+  /// A cache within the directory, which is created once an entry is stored.
+  pub fn new(directory: impl Into<PathBuf>) -> Self {
+    return Self {
+      directory: directory.into(),
+    };
+  }
+
+  pub fn directory(&self) -> &PathBuf {
+    return &self.directory;
+  }
+
+  fn path(&self, name: &str) -> PathBuf {
+    return self.directory.join(format!("{}.bin", name));
+  }
+
+  /// The data of the entry, or None if the entry is missing or was produced
+  /// by a different producer or version of the cache.
+  pub fn load(&self, name: &str, producer: u64) -> Option<Vec<u8>> {
+    let entry = fs::read(self.path(name)).ok()?;
+    let data = decode_entry(&entry, producer);
+    if data.is_none() {
+      logging::debug!("Invalidated the cache entry {}.", name);
+    }
+    return data;
+  }
+
+  /// Stores the data of the entry, replacing any entry with the same name.
+  pub fn store(
+    &self,
+    name: &str,
+    producer: u64,
+    data: &[u8],
+  ) -> Result<(), String> {
+    fs::create_dir_all(&self.directory).map_err(|error| {
+      return format!(
+        "Failed to create the cache directory {}: {}",
+        self.directory.display(),
+        error
+      );
+    })?;
+
+    // Entries are written in full before replacing the previous entry so
+    // that an interrupted write never leaves a truncated entry behind.
+    let path = self.path(name);
+    let partial_path = path.with_extension("partial");
+    return fs::write(&partial_path, encode_entry(producer, data))
+      .and_then(|_| fs::rename(&partial_path, &path))
+      .map_err(|error| {
+        return format!(
+          "Failed to store the cache entry {}: {}",
+          path.display(),
+          error
+        );
+      });
+  }
+
+  /// Removes every entry within the cache.
+  pub fn clear(&self) -> Result<(), String> {
+    return match fs::remove_dir_all(&self.directory) {
+      Ok(()) => Ok(()),
+      Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+      Err(error) => Err(format!(
+        "Failed to clear the cache directory {}: {}",
+        self.directory.display(),
+        error
+      )),
+    };
+  }
+}
+
+fn encode_entry(producer: u64, data: &[u8]) -> Vec<u8> {
+  let mut entry = Vec::with_capacity(HEADER_SIZE + data.len());
+  entry.extend_from_slice(MAGIC);
+  entry.extend_from_slice(&VERSION.to_le_bytes());
+  entry.extend_from_slice(&producer.to_le_bytes());
+  entry.extend_from_slice(&(data.len() as u64).to_le_bytes());
+  entry.extend_from_slice(data);
+  return entry;
+}
+
+fn decode_entry(entry: &[u8], producer: u64) -> Option<Vec<u8>> {
+  if entry.len() < HEADER_SIZE || &entry[0..4] != MAGIC {
+    return None;
+  }
+
+  let read_u64 = |at: usize| {
+    return u64::from_le_bytes(entry[at..at + 8].try_into().unwrap());
+  };
+  let version = u32::from_le_bytes(entry[4..8].try_into().unwrap());
+  let length = read_u64(16) as usize;
+  if version != VERSION
+    || read_u64(8) != producer
+    || entry.len() - HEADER_SIZE != length
+  {
+    return None;
+  }
+  return Some(entry[HEADER_SIZE..].to_vec());
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    content_hash,
+    decode_entry,
+    encode_entry,
+    DiskCache,
+  };
+
+  #[test]
+  fn entries_are_invalidated_by_their_producer() {
+    let entry = encode_entry(7, b"spirv");
+    assert_eq!(decode_entry(&entry, 7), Some(b"spirv".to_vec()));
+    assert_eq!(decode_entry(&entry, 8), None);
+    assert_eq!(decode_entry(&entry[..entry.len() - 1], 7), None);
+
+    let mut other_version = entry.clone();
+    other_version[4] = 0;
+    assert_eq!(decode_entry(&other_version, 7), None);
+    assert_eq!(decode_entry(b"LCCH", 7), None);
+  }
+
+  #[test]
+  fn entries_are_stored_in_the_directory() {
+    let directory = std::env::temp_dir()
+      .join(format!("lambda-cache-test-{}", std::process::id()));
+    let cache = DiskCache::new(&directory);
+    let name = format!("{:016x}", content_hash(b"void main() {}"));
+    assert_eq!(cache.load(&name, 1), None);
+
+    cache.store(&name, 1, b"binary").unwrap();
+    assert_eq!(cache.load(&name, 1), Some(b"binary".to_vec()));
+    assert_eq!(cache.load(&name, 2), None);
+
+    cache.clear().unwrap();
+    assert!(!directory.exists());
+    assert!(cache.clear().is_ok());
+  }
+}
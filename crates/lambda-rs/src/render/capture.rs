@@ -0,0 +1,102 @@
+//! Frame captures with graphics debuggers such as RenderDoc. A capture is
+//! either triggered, which captures the next whole frame, or begun & ended
+//! manually around any number of frames. Both kinds can overlap, in which
+//! case the GPU capture lasts until neither is active.
+
+/// Tracks which captures are active so that the GPU capture is only started
+/// & stopped once.
+#[derive(Debug, Default)]
+pub(super) struct FrameCapture {
+  triggered: bool,
+  capturing_frame: bool,
+  capturing_manually: bool,
+}
+
+impl FrameCapture {
+  /// Whether or not a GPU capture is active.
+  pub(super) fn is_capturing(&self) -> bool {
+    return self.capturing_frame || self.capturing_manually;
+  }
+
+  /// Captures the next frame once it begins.
+  pub(super) fn trigger(&mut self) {
+    self.triggered = true;
+  }
+
+  /// Begins a frame, returning whether or not the GPU capture should start.
+  pub(super) fn begin_frame(&mut self) -> bool {
+    if !self.triggered || self.capturing_frame {
+      return false;
+    }
+    self.triggered = false;
+    let start = !self.is_capturing();
+    self.capturing_frame = true;
+    return start;
+  }
+
+  /// Ends a frame, returning whether or not the GPU capture should stop.
+  pub(super) fn end_frame(&mut self) -> bool {
+    if !self.capturing_frame {
+      return false;
+    }
+    self.capturing_frame = false;
+    return !self.is_capturing();
+  }
+
+  /// Begins a manual capture, returning whether or not the GPU capture should
+  /// start.
+  pub(super) fn begin(&mut self) -> bool {
+    let start = !self.is_capturing();
+    self.capturing_manually = true;
+    return start;
+  }
+
+  /// Ends a manual capture, returning whether or not the GPU capture should
+  /// stop.
+  pub(super) fn end(&mut self) -> bool {
+    if !self.capturing_manually {
+      return false;
+    }
+    self.capturing_manually = false;
+    return !self.is_capturing();
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::FrameCapture;
+
+  #[test]
+  fn triggered_captures_span_the_next_frame() {
+    let mut capture = FrameCapture::default();
+    assert!(!capture.begin_frame());
+
+    capture.trigger();
+    assert!(!capture.is_capturing());
+    assert!(capture.begin_frame());
+    assert!(capture.is_capturing());
+    assert!(capture.end_frame());
+    assert!(!capture.is_capturing());
+
+    assert!(!capture.begin_frame());
+    assert!(!capture.end_frame());
+  }
+
+  #[test]
+  fn overlapping_captures_start_and_stop_once() {
+    let mut capture = FrameCapture::default();
+    assert!(capture.begin());
+    assert!(!capture.begin());
+
+    capture.trigger();
+    assert!(!capture.begin_frame());
+    assert!(!capture.end_frame());
+    assert!(capture.is_capturing());
+
+    assert!(capture.end());
+    assert!(!capture.end());
+    assert!(!capture.is_capturing());
+  }
+}
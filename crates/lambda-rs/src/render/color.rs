@@ -0,0 +1,376 @@
+//! Colors that know which color space their channels are in. Colors are
+//! stored as linear RGB with straight alpha, which is what shaders blend in
+//! and what sRGB surfaces expect to be written, and can be created from or
+//! converted to sRGB, hex strings, HSV, and OKLab.
+
+/// A linear RGB color with straight (Not premultiplied) alpha.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+  pub r: f32,
+  pub g: f32,
+  pub b: f32,
+  pub a: f32,
+}
+
+// -------------------------------- PALETTE ------------------------------------
+
+impl Color {
+  pub const TRANSPARENT: Color = Color::from_linear(0.0, 0.0, 0.0, 0.0);
+  pub const BLACK: Color = Color::from_linear(0.0, 0.0, 0.0, 1.0);
+  pub const WHITE: Color = Color::from_linear(1.0, 1.0, 1.0, 1.0);
+  /// sRGB `#808080`, which is perceptually halfway between black & white.
+  pub const GRAY: Color = Color::from_linear(0.21586, 0.21586, 0.21586, 1.0);
+  pub const RED: Color = Color::from_linear(1.0, 0.0, 0.0, 1.0);
+  pub const GREEN: Color = Color::from_linear(0.0, 1.0, 0.0, 1.0);
+  pub const BLUE: Color = Color::from_linear(0.0, 0.0, 1.0, 1.0);
+  pub const YELLOW: Color = Color::from_linear(1.0, 1.0, 0.0, 1.0);
+  pub const CYAN: Color = Color::from_linear(0.0, 1.0, 1.0, 1.0);
+  pub const MAGENTA: Color = Color::from_linear(1.0, 0.0, 1.0, 1.0);
+  /// sRGB `#ff8000`.
+  pub const ORANGE: Color = Color::from_linear(1.0, 0.21586, 0.0, 1.0);
+  /// sRGB `#8000ff`.
+  pub const PURPLE: Color = Color::from_linear(0.21586, 0.0, 1.0, 1.0);
+}
+
+// ------------------------------ CONVERSIONS ----------------------------------
+
+impl Color {
+  /// Creates a color from linear RGB channels.
+  pub const fn from_linear(r: f32, g: f32, b: f32, a: f32) -> Self {
+    return Self { r, g, b, a };
+  }
+
+  /// Creates a color from sRGB encoded channels, such as those picked in an
+  /// image editor. Alpha is always linear.
+  pub fn from_srgb(r: f32, g: f32, b: f32, a: f32) -> Self {
+    return Self::from_linear(
+      srgb_to_linear(r),
+      srgb_to_linear(g),
+      srgb_to_linear(b),
+      a,
+    );
+  }
+
+  /// Creates a color from 8-bit sRGB encoded channels.
+  pub fn from_srgb8(r: u8, g: u8, b: u8, a: u8) -> Self {
+    return Self::from_srgb(
+      r as f32 / 255.0,
+      g as f32 / 255.0,
+      b as f32 / 255.0,
+      a as f32 / 255.0,
+    );
+  }
+
+  /// Parses an sRGB hex color in the form `#rgb`, `#rgba`, `#rrggbb`, or
+  /// `#rrggbbaa`, where the leading `#` is optional.
+  pub fn from_hex(hex: &str) -> Result<Self, String> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    let invalid = || format!("{} isn't a hex color.", hex);
+    if !digits.is_ascii() {
+      return Err(invalid());
+    }
+
+    let width = match digits.len() {
+      3 | 4 => 1,
+      6 | 8 => 2,
+      _ => return Err(invalid()),
+    };
+    let mut channels = [255_u8; 4];
+    for (index, channel) in
+      channels.iter_mut().enumerate().take(digits.len() / width)
+    {
+      let digit = &digits[index * width..(index + 1) * width];
+      let value = u8::from_str_radix(digit, 16).map_err(|_| invalid())?;
+      // A single digit is repeated, so `f` is the same as `ff`.
+      *channel = if width == 1 { value * 17 } else { value };
+    }
+    return Ok(Self::from_srgb8(
+      channels[0],
+      channels[1],
+      channels[2],
+      channels[3],
+    ));
+  }
+
+  /// Creates a color from a hue in degrees and a saturation & value between
+  /// 0 and 1, which describe the sRGB encoded color.
+  pub fn from_hsv(hue: f32, saturation: f32, value: f32, alpha: f32) -> Self {
+    let hue = hue.rem_euclid(360.0) / 60.0;
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+      0 => (chroma, x, 0.0),
+      1 => (x, chroma, 0.0),
+      2 => (0.0, chroma, x),
+      3 => (0.0, x, chroma),
+      4 => (x, 0.0, chroma),
+      _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+    return Self::from_srgb(r + m, g + m, b + m, alpha);
+  }
+
+  /// Creates a color from OKLab lightness & a/b coordinates, a perceptual
+  /// color space in which colors are blended without shifting hue.
+  pub fn from_oklab(lightness: f32, a: f32, b: f32, alpha: f32) -> Self {
+    let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m = (lightness - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    return Self::from_linear(
+      4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+      -1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s,
+      -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+      alpha,
+    );
+  }
+
+  /// The linear RGBA channels.
+  pub fn to_linear(&self) -> [f32; 4] {
+    return [self.r, self.g, self.b, self.a];
+  }
+
+  /// The linear RGB channels, as stored in vertex colors.
+  pub fn to_rgb(&self) -> [f32; 3] {
+    return [self.r, self.g, self.b];
+  }
+
+  /// The sRGB encoded RGBA channels.
+  pub fn to_srgb(&self) -> [f32; 4] {
+    return [
+      linear_to_srgb(self.r),
+      linear_to_srgb(self.g),
+      linear_to_srgb(self.b),
+      self.a,
+    ];
+  }
+
+  /// The 8-bit sRGB encoded RGBA channels, clamped to between 0 and 255.
+  pub fn to_srgb8(&self) -> [u8; 4] {
+    return self
+      .to_srgb()
+      .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8);
+  }
+
+  /// The sRGB hex string of the color in the form `#rrggbbaa`.
+  pub fn to_hex(&self) -> String {
+    let [r, g, b, a] = self.to_srgb8();
+    return format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a);
+  }
+
+  /// The hue in degrees and the saturation & value of the sRGB encoded
+  /// color.
+  pub fn to_hsv(&self) -> [f32; 3] {
+    let [r, g, b, _] = self.to_srgb();
+    let max = r.max(g).max(b);
+    let chroma = max - r.min(g).min(b);
+    let hue = if chroma <= f32::EPSILON {
+      0.0
+    } else if max == r {
+      60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+      60.0 * ((b - r) / chroma + 2.0)
+    } else {
+      60.0 * ((r - g) / chroma + 4.0)
+    };
+    let saturation = if max <= f32::EPSILON {
+      0.0
+    } else {
+      chroma / max
+    };
+    return [hue, saturation, max];
+  }
+
+  /// The OKLab lightness & a/b coordinates of the color.
+  pub fn to_oklab(&self) -> [f32; 3] {
+    let l =
+      (0.412_221_46 * self.r + 0.536_332_55 * self.g + 0.051_445_995 * self.b)
+        .cbrt();
+    let m =
+      (0.211_903_5 * self.r + 0.680_699_5 * self.g + 0.107_396_96 * self.b)
+        .cbrt();
+    let s =
+      (0.088_302_46 * self.r + 0.281_718_85 * self.g + 0.629_978_7 * self.b)
+        .cbrt();
+    return [
+      0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+      1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+      0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ];
+  }
+}
+
+// -------------------------------- BLENDING -----------------------------------
+
+impl Color {
+  /// The color with a different alpha.
+  pub fn with_alpha(mut self, alpha: f32) -> Self {
+    self.a = alpha;
+    return self;
+  }
+
+  /// The color with its RGB channels multiplied by its alpha, for pipelines
+  /// that blend with premultiplied alpha.
+  pub fn premultiplied(&self) -> Self {
+    return Self::from_linear(
+      self.r * self.a,
+      self.g * self.a,
+      self.b * self.a,
+      self.a,
+    );
+  }
+
+  /// Reverses `premultiplied`. Fully transparent colors stay black.
+  pub fn unpremultiplied(&self) -> Self {
+    if self.a <= f32::EPSILON {
+      return Self::TRANSPARENT;
+    }
+    return Self::from_linear(
+      self.r / self.a,
+      self.g / self.a,
+      self.b / self.a,
+      self.a,
+    );
+  }
+
+  /// Blends between two colors in linear RGB, which is how the GPU blends.
+  pub fn lerp(&self, other: Color, t: f32) -> Self {
+    let blend = |from: f32, to: f32| from + (to - from) * t;
+    return Self::from_linear(
+      blend(self.r, other.r),
+      blend(self.g, other.g),
+      blend(self.b, other.b),
+      blend(self.a, other.a),
+    );
+  }
+
+  /// Blends between two colors in OKLab, which keeps the blend perceptually
+  /// even, such as for gradients.
+  pub fn lerp_oklab(&self, other: Color, t: f32) -> Self {
+    let from = self.to_oklab();
+    let to = other.to_oklab();
+    let blend = |index: usize| from[index] + (to[index] - from[index]) * t;
+    return Self::from_oklab(
+      blend(0),
+      blend(1),
+      blend(2),
+      self.a + (other.a - self.a) * t,
+    );
+  }
+}
+
+impl Default for Color {
+  /// Opaque black.
+  fn default() -> Self {
+    return Self::BLACK;
+  }
+}
+
+/// Linear RGB channels with an opaque alpha.
+impl From<[f32; 3]> for Color {
+  fn from([r, g, b]: [f32; 3]) -> Self {
+    return Self::from_linear(r, g, b, 1.0);
+  }
+}
+
+/// Linear RGBA channels.
+impl From<[f32; 4]> for Color {
+  fn from([r, g, b, a]: [f32; 4]) -> Self {
+    return Self::from_linear(r, g, b, a);
+  }
+}
+
+impl From<Color> for [f32; 3] {
+  fn from(color: Color) -> Self {
+    return color.to_rgb();
+  }
+}
+
+impl From<Color> for [f32; 4] {
+  fn from(color: Color) -> Self {
+    return color.to_linear();
+  }
+}
+
+/// Decodes an sRGB channel with the sRGB transfer function.
+pub fn srgb_to_linear(channel: f32) -> f32 {
+  if channel <= 0.04045 {
+    return channel / 12.92;
+  }
+  return ((channel + 0.055) / 1.055).powf(2.4);
+}
+
+/// Encodes a linear channel with the sRGB transfer function.
+pub fn linear_to_srgb(channel: f32) -> f32 {
+  if channel <= 0.003_130_8 {
+    return channel * 12.92;
+  }
+  return 1.055 * channel.powf(1.0 / 2.4) - 0.055;
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::Color;
+  use crate::assert_approximately_equal;
+
+  fn assert_colors_close(actual: Color, expected: Color) {
+    for (actual, expected) in
+      actual.to_linear().iter().zip(expected.to_linear())
+    {
+      assert_approximately_equal!(*actual, expected, 1e-3);
+    }
+  }
+
+  #[test]
+  fn hex_colors_are_decoded_from_srgb() {
+    assert_eq!(Color::from_hex("#ffffff"), Ok(Color::WHITE));
+    assert_eq!(Color::from_hex("f00"), Ok(Color::RED));
+    assert_colors_close(Color::from_hex("#808080").unwrap(), Color::GRAY);
+    assert_colors_close(
+      Color::from_hex("#ff800080").unwrap(),
+      Color::ORANGE.with_alpha(128.0 / 255.0),
+    );
+    assert_eq!(Color::from_hex("#ff8000").unwrap().to_hex(), "#ff8000ff");
+    assert!(Color::from_hex("#12345").is_err());
+    assert!(Color::from_hex("#gg0000").is_err());
+  }
+
+  #[test]
+  fn colors_round_trip_through_hsv_and_oklab() {
+    assert_colors_close(
+      Color::from_hsv(30.0, 1.0, 1.0, 1.0),
+      Color::from_srgb(1.0, 0.5, 0.0, 1.0),
+    );
+    assert_colors_close(Color::from_hsv(-120.0, 1.0, 1.0, 1.0), Color::BLUE);
+    let [hue, saturation, value] = Color::PURPLE.to_hsv();
+    assert_approximately_equal!(hue, 270.0, 0.5);
+    assert_approximately_equal!(saturation, 1.0, 1e-3);
+    assert_approximately_equal!(value, 1.0, 1e-3);
+
+    let [lightness, a, b] = Color::WHITE.to_oklab();
+    assert_approximately_equal!(lightness, 1.0, 1e-3);
+    assert_approximately_equal!(a, 0.0, 1e-3);
+    assert_approximately_equal!(b, 0.0, 1e-3);
+    for color in [Color::ORANGE, Color::CYAN, Color::GRAY] {
+      let [lightness, a, b] = color.to_oklab();
+      assert_colors_close(Color::from_oklab(lightness, a, b, 1.0), color);
+    }
+  }
+
+  #[test]
+  fn premultiplying_scales_by_alpha() {
+    let color = Color::ORANGE.with_alpha(0.5);
+    let premultiplied = color.premultiplied();
+    assert_eq!(premultiplied.to_rgb(), [0.5, 0.10793, 0.0]);
+    assert_colors_close(premultiplied.unpremultiplied(), color);
+    assert_eq!(
+      Color::TRANSPARENT.premultiplied().unpremultiplied(),
+      Color::TRANSPARENT
+    );
+    assert_colors_close(
+      Color::BLACK.lerp(Color::WHITE, 0.25),
+      Color::from_linear(0.25, 0.25, 0.25, 1.0),
+    );
+  }
+}
@@ -10,7 +10,7 @@ use super::{
 };
 
 /// Commands that are used to render a frame within the RenderContext.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RenderCommand {
   /// sets the viewports for the render context.
   SetViewports {
@@ -44,11 +44,95 @@ pub enum RenderCommand {
     pipeline: super::ResourceId,
     buffer: u32,
   },
+  /// Binds the index buffer that was built alongside a vertex buffer of the
+  /// render pipeline.
+  BindIndexBuffer {
+    pipeline: super::ResourceId,
+    buffer: u32,
+  },
+  /// Sets the value that the stencil tests of pipelines compare against,
+  /// and that `StencilOp::Replace` writes into the stencil. Can only be set
+  /// within a render pass built with a stencil, and lasts until the render
+  /// pass ends.
+  SetStencilReference { reference: u32 },
   /// Draws a graphical primitive.
   Draw { vertices: Range<u32> },
+  /// Draws a graphical primitive using the indices of the bound index buffer.
+  /// Every index is offset by the base vertex.
+  DrawIndexed {
+    indices: Range<u32>,
+    base_vertex: i32,
+  },
+  /// Overwrites the start of a vertex buffer of the render pipeline, such as
+  /// to animate vertices every frame. Updates are applied once the previous
+  /// frame has finished rendering and before any other command of the frame
+  /// is recorded.
+  UpdateVertexBuffer {
+    pipeline: super::ResourceId,
+    buffer: u32,
+    vertices: Vec<super::vertex::Vertex>,
+  },
 }
 
 impl RenderCommand {
+  /// Checks that the resources the command uses are still attached to the
+  /// render context.
+  pub(super) fn validate(
+    &self,
+    render_context: &RenderContext,
+  ) -> Result<(), String> {
+    let (pipeline, buffer) = match self {
+      RenderCommand::BeginRenderPass { render_pass, .. } => {
+        return match render_context.render_passes.contains(*render_pass) {
+          true => Ok(()),
+          false => Err(format!("Render pass {} isn't attached.", render_pass)),
+        };
+      }
+      RenderCommand::SetPipeline { pipeline }
+      | RenderCommand::PushConstants { pipeline, .. } => (pipeline, None),
+      RenderCommand::BindVertexBuffer { pipeline, buffer }
+      | RenderCommand::BindIndexBuffer { pipeline, buffer }
+      | RenderCommand::UpdateVertexBuffer {
+        pipeline, buffer, ..
+      } => (pipeline, Some(*buffer)),
+      _ => return Ok(()),
+    };
+
+    let render_pipeline = render_context
+      .render_pipelines
+      .get(*pipeline)
+      .ok_or_else(|| format!("Render pipeline {} isn't attached.", pipeline))?;
+    return match buffer {
+      Some(buffer) if buffer as usize >= render_pipeline.buffers().len() => {
+        Err(format!(
+          "Render pipeline {} doesn't have vertex buffer {}.",
+          pipeline, buffer
+        ))
+      }
+      _ => Ok(()),
+    };
+  }
+
+  /// Resolves the fractional viewports of the command against the size of
+  /// the surface.
+  pub(super) fn resolve_viewports(
+    &mut self,
+    surface_width: u32,
+    surface_height: u32,
+  ) {
+    let viewports = match self {
+      RenderCommand::SetViewports { viewports, .. }
+      | RenderCommand::SetScissors { viewports, .. } => viewports.iter_mut(),
+      RenderCommand::BeginRenderPass { viewport, .. } => {
+        std::slice::from_mut(viewport).iter_mut()
+      }
+      _ => return,
+    };
+    for viewport in viewports {
+      *viewport = viewport.resolve(surface_width, surface_height);
+    }
+  }
+
   /// Converts the RenderCommand into a platform compatible render command.
   pub(super) fn into_platform_command(
     &self,
@@ -80,12 +164,30 @@ impl RenderCommand {
         viewport,
       } => {
         let surface = render_context.internal_surface();
+        let color_attachments = render_context
+          .get_render_pass(*render_pass)
+          .color_attachments()
+          .to_vec();
+        let color_images = color_attachments
+          .iter()
+          .map(|attachment| {
+            return render_context
+              .color_attachment_image(attachment)
+              .unwrap_or_else(|error| panic!("{}", error));
+          })
+          .collect::<Vec<_>>();
         let frame_buffer = render_context.allocate_and_get_frame_buffer(
           render_context
             .get_render_pass(*render_pass)
             .into_gfx_render_pass()
             .as_ref(),
+          &color_images,
         );
+        let depth_stencil =
+          match render_context.get_render_pass(*render_pass).has_stencil() {
+            true => render_context.depth_stencil.clone(),
+            false => None,
+          };
 
         PlatformRenderCommand::BeginRenderPass {
           render_pass: render_context
@@ -94,6 +196,8 @@ impl RenderCommand {
           surface: surface.clone(),
           frame_buffer: frame_buffer.clone(),
           viewport: viewport.clone_gfx_viewport(),
+          color_images,
+          depth_stencil,
         }
       }
       RenderCommand::EndRenderPass => PlatformRenderCommand::EndRenderPass,
@@ -133,9 +237,36 @@ impl RenderCommand {
             .internal_buffer_rc(),
         }
       }
+      RenderCommand::BindIndexBuffer { pipeline, buffer } => {
+        let (buffer, index_type) = render_context
+          .render_pipelines
+          .get(*pipeline)
+          .unwrap()
+          .buffers()
+          .get(*buffer as usize)
+          .unwrap()
+          .internal_index_buffer_rc()
+          .expect("The buffer was built without an index buffer.");
+        PlatformRenderCommand::BindIndexBuffer { buffer, index_type }
+      }
+      RenderCommand::SetStencilReference { reference } => {
+        PlatformRenderCommand::SetStencilReference {
+          reference: *reference,
+        }
+      }
       RenderCommand::Draw { vertices } => PlatformRenderCommand::Draw {
         vertices: vertices.clone(),
       },
+      RenderCommand::DrawIndexed {
+        indices,
+        base_vertex,
+      } => PlatformRenderCommand::DrawIndexed {
+        indices: indices.clone(),
+        base_vertex: *base_vertex,
+      },
+      RenderCommand::UpdateVertexBuffer { .. } => {
+        unreachable!("Vertex buffers are updated before recording commands.")
+      }
     };
   }
 }
@@ -0,0 +1,77 @@
+//! Counts of the work that the render commands of a frame submitted to the
+//! GPU, for profiling overlays & tests.
+
+use super::command::RenderCommand;
+
+/// The work submitted to the GPU during a frame. Triangles are counted as if
+/// every draw is a triangle list.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameCounters {
+  pub draw_calls: u32,
+  pub vertices: u64,
+  pub triangles: u64,
+  pub render_passes: u32,
+  pub pipeline_changes: u32,
+}
+
+impl FrameCounters {
+  /// Adds the work of the command to the counters.
+  pub(super) fn record(&mut self, command: &RenderCommand) {
+    match command {
+      RenderCommand::Draw { vertices } => {
+        self.record_draw(vertices.len() as u64);
+      }
+      RenderCommand::DrawIndexed { indices, .. } => {
+        self.record_draw(indices.len() as u64);
+      }
+      RenderCommand::BeginRenderPass { .. } => self.render_passes += 1,
+      RenderCommand::SetPipeline { .. } => self.pipeline_changes += 1,
+      _ => {}
+    }
+  }
+
+  fn record_draw(&mut self, vertices: u64) {
+    self.draw_calls += 1;
+    self.vertices += vertices;
+    self.triangles += vertices / 3;
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::FrameCounters;
+  use crate::render::{
+    command::RenderCommand,
+    ResourceId,
+  };
+
+  #[test]
+  fn draws_passes_and_pipelines_are_counted() {
+    let pipeline = ResourceId::new(0, 0);
+    let mut counters = FrameCounters::default();
+    for command in [
+      RenderCommand::SetPipeline { pipeline },
+      RenderCommand::Draw { vertices: 0..6 },
+      RenderCommand::DrawIndexed {
+        indices: 3..12,
+        base_vertex: 0,
+      },
+      RenderCommand::EndRenderPass,
+    ] {
+      counters.record(&command);
+    }
+
+    assert_eq!(
+      counters,
+      FrameCounters {
+        draw_calls: 2,
+        vertices: 15,
+        triangles: 5,
+        render_passes: 0,
+        pipeline_changes: 1,
+      }
+    );
+  }
+}
@@ -0,0 +1,322 @@
+//! Immediate mode debug shape drawing. Shapes are accumulated every frame
+//! into a single triangle list so that they can be uploaded and drawn in one
+//! pass, and then cleared before the next frame.
+
+use std::f32::consts::TAU;
+
+use super::{
+  color::Color,
+  command::RenderCommand,
+  mesh::{
+    Mesh,
+    MeshBuilder,
+  },
+  vertex::{
+    Vertex,
+    VertexAttribute,
+    VertexElement,
+  },
+  ColorFormat,
+  ResourceId,
+};
+
+/// The color used for the x axis by DebugDraw::axes.
+pub const AXIS_X_COLOR: [f32; 3] = [1.0, 0.0, 0.0];
+
+/// The color used for the y axis by DebugDraw::axes.
+pub const AXIS_Y_COLOR: [f32; 3] = [0.0, 1.0, 0.0];
+
+/// Accumulates lines and wireframe shapes with per vertex colors. Positions
+/// are two dimensional and in the same space as the pipeline the shapes are
+/// drawn with. Lines are expanded into quads since pipelines only assemble
+/// triangle lists.
+pub struct DebugDraw {
+  line_width: f32,
+  vertices: Vec<Vertex>,
+}
+
+impl DebugDraw {
+  /// Creates an empty debug draw with a line width of 0.01.
+  pub fn new() -> Self {
+    return Self {
+      line_width: 0.01,
+      vertices: vec![],
+    };
+  }
+
+  /// The width of lines in the same units as the positions being drawn.
+  pub fn with_line_width(mut self, line_width: f32) -> Self {
+    self.line_width = line_width;
+    return self;
+  }
+
+  /// Adds a line with a color at both ends that is blended across the line.
+  pub fn line(
+    &mut self,
+    start: [f32; 2],
+    end: [f32; 2],
+    start_color: impl Into<Color>,
+    end_color: impl Into<Color>,
+  ) {
+    let start_color = start_color.into().to_rgb();
+    let end_color = end_color.into().to_rgb();
+    let direction = [end[0] - start[0], end[1] - start[1]];
+    let length =
+      (direction[0] * direction[0] + direction[1] * direction[1]).sqrt();
+    if length <= f32::EPSILON {
+      return;
+    }
+
+    let half_width = self.line_width / 2.0;
+    let normal = [
+      -direction[1] / length * half_width,
+      direction[0] / length * half_width,
+    ];
+
+    let vertex = |point: [f32; 2], offset: f32, color: [f32; 3]| {
+      return Vertex {
+        position: [
+          point[0] + normal[0] * offset,
+          point[1] + normal[1] * offset,
+          0.0,
+        ],
+        normal: [0.0, 0.0, 1.0],
+        color,
+        tex_coords: [0.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+      };
+    };
+
+    let start_left = vertex(start, 1.0, start_color);
+    let start_right = vertex(start, -1.0, start_color);
+    let end_left = vertex(end, 1.0, end_color);
+    let end_right = vertex(end, -1.0, end_color);
+
+    self.vertices.extend_from_slice(&[
+      start_left,
+      start_right,
+      end_left,
+      end_left,
+      start_right,
+      end_right,
+    ]);
+  }
+
+  /// Adds a line with a single color.
+  pub fn colored_line(
+    &mut self,
+    start: [f32; 2],
+    end: [f32; 2],
+    color: impl Into<Color>,
+  ) {
+    let color = color.into();
+    self.line(start, end, color, color);
+  }
+
+  /// Adds a line that starts at the origin and points in the direction of
+  /// the vector, which is useful for visualizing velocities.
+  pub fn vector(
+    &mut self,
+    origin: [f32; 2],
+    vector: [f32; 2],
+    color: impl Into<Color>,
+  ) {
+    self.colored_line(
+      origin,
+      [origin[0] + vector[0], origin[1] + vector[1]],
+      color,
+    );
+  }
+
+  /// Adds the outline of a box rotated around its center by the rotation in
+  /// radians.
+  pub fn wire_box(
+    &mut self,
+    center: [f32; 2],
+    half_extents: [f32; 2],
+    rotation: f32,
+    color: impl Into<Color>,
+  ) {
+    let color = color.into();
+    let (sin, cos) = rotation.sin_cos();
+    let corner = |x: f32, y: f32| {
+      let (x, y) = (x * half_extents[0], y * half_extents[1]);
+      return [center[0] + x * cos - y * sin, center[1] + x * sin + y * cos];
+    };
+
+    let corners = [
+      corner(-1.0, -1.0),
+      corner(1.0, -1.0),
+      corner(1.0, 1.0),
+      corner(-1.0, 1.0),
+    ];
+    for index in 0..corners.len() {
+      self.colored_line(
+        corners[index],
+        corners[(index + 1) % corners.len()],
+        color,
+      );
+    }
+  }
+
+  /// Adds the outline of a circle approximated with the given number of line
+  /// segments (At least 3).
+  pub fn circle(
+    &mut self,
+    center: [f32; 2],
+    radius: f32,
+    segments: u32,
+    color: impl Into<Color>,
+  ) {
+    let color = color.into();
+    let segments = segments.max(3);
+    let point = |segment: u32| {
+      let angle = segment as f32 / segments as f32 * TAU;
+      return [
+        center[0] + radius * angle.cos(),
+        center[1] + radius * angle.sin(),
+      ];
+    };
+
+    for segment in 0..segments {
+      self.colored_line(point(segment), point(segment + 1), color);
+    }
+  }
+
+  /// Adds the x and y axes of a coordinate frame at the origin, rotated by
+  /// the rotation in radians.
+  pub fn axes(&mut self, origin: [f32; 2], rotation: f32, length: f32) {
+    let (sin, cos) = rotation.sin_cos();
+    self.vector(origin, [cos * length, sin * length], AXIS_X_COLOR);
+    self.vector(origin, [-sin * length, cos * length], AXIS_Y_COLOR);
+  }
+
+  /// Removes all shapes. This should be called once the shapes for a frame
+  /// have been drawn.
+  pub fn clear(&mut self) {
+    self.vertices.clear();
+  }
+
+  /// Whether or not any shapes have been added since the last clear.
+  pub fn is_empty(&self) -> bool {
+    return self.vertices.is_empty();
+  }
+
+  /// The vertices of all accumulated shapes as a triangle list.
+  pub fn vertices(&self) -> &[Vertex] {
+    return &self.vertices;
+  }
+
+  /// Builds a mesh of all accumulated shapes that uses the same vertex
+  /// attributes as meshes loaded from OBJ files.
+  pub fn build_mesh(&self) -> Mesh {
+    let mut mesh_builder = MeshBuilder::new();
+    for vertex in self.vertices.iter() {
+      mesh_builder.with_vertex(*vertex);
+    }
+
+    mesh_builder.with_attributes(vec![
+      VertexAttribute {
+        location: 0,
+        offset: 0,
+        element: VertexElement {
+          format: ColorFormat::Rgb32Sfloat,
+          offset: 0,
+        },
+      },
+      VertexAttribute {
+        location: 1,
+        offset: 0,
+        element: VertexElement {
+          format: ColorFormat::Rgb32Sfloat,
+          offset: 12,
+        },
+      },
+      VertexAttribute {
+        location: 2,
+        offset: 0,
+        element: VertexElement {
+          format: ColorFormat::Rgb32Sfloat,
+          offset: 24,
+        },
+      },
+    ]);
+
+    return mesh_builder.build();
+  }
+
+  /// Commands for drawing every accumulated shape in a single draw call once
+  /// the mesh has been attached to the pipeline at the given buffer index.
+  pub fn draw_commands(
+    &self,
+    pipeline: ResourceId,
+    buffer: u32,
+  ) -> Vec<RenderCommand> {
+    if self.is_empty() {
+      return vec![];
+    }
+
+    return vec![
+      RenderCommand::BindVertexBuffer { pipeline, buffer },
+      RenderCommand::Draw {
+        vertices: 0..self.vertices.len() as u32,
+      },
+    ];
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    DebugDraw,
+    AXIS_X_COLOR,
+    AXIS_Y_COLOR,
+  };
+  use crate::render::ResourceId;
+
+  fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+    for (actual, expected) in actual.iter().zip(expected.iter()) {
+      assert!((actual - expected).abs() < 1e-5, "{:?}", actual);
+    }
+  }
+
+  #[test]
+  fn lines_are_expanded_into_quads() {
+    let mut debug_draw = DebugDraw::new().with_line_width(0.2);
+    debug_draw.line([0.0, 0.0], [1.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+
+    let vertices = debug_draw.vertices();
+    assert_eq!(vertices.len(), 6);
+    assert_close(vertices[0].position, [0.0, 0.1, 0.0]);
+    assert_close(vertices[1].position, [0.0, -0.1, 0.0]);
+    assert_close(vertices[5].position, [1.0, -0.1, 0.0]);
+    assert_eq!(vertices[0].color, [1.0, 0.0, 0.0]);
+    assert_eq!(vertices[5].color, [0.0, 0.0, 1.0]);
+
+    // Degenerate lines are skipped.
+    debug_draw.line([1.0, 1.0], [1.0, 1.0], [1.0; 3], [1.0; 3]);
+    assert_eq!(debug_draw.vertices().len(), 6);
+  }
+
+  #[test]
+  fn shapes_accumulate_until_cleared() {
+    let mut debug_draw = DebugDraw::new();
+    assert!(debug_draw
+      .draw_commands(ResourceId::new(0, 0), 0)
+      .is_empty());
+
+    debug_draw.wire_box([0.0, 0.0], [0.5, 0.5], 0.0, [1.0; 3]);
+    debug_draw.circle([0.0, 0.0], 0.5, 16, [1.0; 3]);
+    debug_draw.axes([0.0, 0.0], 0.0, 1.0);
+    assert_eq!(debug_draw.vertices().len(), (4 + 16 + 2) * 6);
+    assert_eq!(debug_draw.build_mesh().vertices().len(), 132);
+    assert_eq!(debug_draw.draw_commands(ResourceId::new(0, 0), 0).len(), 2);
+
+    let axis_vertices = &debug_draw.vertices()[120..];
+    assert_eq!(axis_vertices[0].color, AXIS_X_COLOR);
+    assert_eq!(axis_vertices[6].color, AXIS_Y_COLOR);
+
+    debug_draw.clear();
+    assert!(debug_draw.is_empty());
+  }
+}
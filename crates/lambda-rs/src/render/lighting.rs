@@ -0,0 +1,554 @@
+//! Forward lighting with directional, point, and spot lights. A LightSet
+//! holds every light in a scene and packs them into a std140 layout that can
+//! be uploaded to shaders, and can also evaluate the lighting on the CPU for
+//! vertex lit meshes.
+
+use super::super::math::vector::Vector;
+
+/// The maximum number of lights that are packed into the uniform data of a
+/// LightSet.
+pub const MAX_LIGHTS: usize = 16;
+
+/// The size in bytes of the uniform data packed by `LightSet::uniform_data`.
+/// The layout matches the following std140 GLSL uniform block:
+///
+/// ```glsl
+/// struct Light {
+///   vec4 position_type;   // xyz = position, w = type (See below)
+///   vec4 direction_range; // xyz = direction, w = range
+///   vec4 color_intensity; // xyz = color, w = intensity
+///   vec4 cone;            // x = cos(inner angle), y = cos(outer angle)
+/// };
+///
+/// layout (std140) uniform LightSet {
+///   vec4 ambient;         // xyz = ambient color
+///   uvec4 light_count;    // x = number of lights
+///   Light lights[MAX_LIGHTS];
+/// };
+/// ```
+///
+/// The type of a light is 0 for directional lights, 1 for point lights, and 2
+/// for spot lights.
+pub const LIGHT_SET_UNIFORM_SIZE: usize = 32 + MAX_LIGHTS * 64;
+
+// ----------------------------------- LIGHTS ----------------------------------
+
+/// A light infinitely far away that lights everything from one direction,
+/// such as the sun.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectionalLight {
+  /// The direction the light travels in.
+  pub direction: [f32; 3],
+  pub color: [f32; 3],
+  pub intensity: f32,
+  /// Whether the light should be rendered into a shadow map once shadows are
+  /// enabled for the light set.
+  pub casts_shadows: bool,
+}
+
+impl DirectionalLight {
+  /// A white directional light with an intensity of 1.
+  pub fn new(direction: [f32; 3]) -> Self {
+    return Self {
+      direction,
+      color: [1.0, 1.0, 1.0],
+      intensity: 1.0,
+      casts_shadows: false,
+    };
+  }
+}
+
+/// A light emitted in every direction from a position, such as a light bulb.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PointLight {
+  pub position: [f32; 3],
+  pub color: [f32; 3],
+  pub intensity: f32,
+  /// The distance at which the light no longer has any effect.
+  pub range: f32,
+}
+
+impl PointLight {
+  /// A white point light with an intensity of 1.
+  pub fn new(position: [f32; 3], range: f32) -> Self {
+    return Self {
+      position,
+      color: [1.0, 1.0, 1.0],
+      intensity: 1.0,
+      range,
+    };
+  }
+}
+
+/// A light emitted in a cone from a position, such as a flashlight.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpotLight {
+  pub position: [f32; 3],
+  /// The direction the cone points in.
+  pub direction: [f32; 3],
+  pub color: [f32; 3],
+  pub intensity: f32,
+  pub range: f32,
+  /// The angle in radians from the center of the cone at which the light
+  /// starts to fade out.
+  pub inner_angle: f32,
+  /// The angle in radians from the center of the cone at which the light has
+  /// completely faded out.
+  pub outer_angle: f32,
+}
+
+impl SpotLight {
+  /// A white spot light with an intensity of 1 that fades out over the last
+  /// quarter of its cone.
+  pub fn new(
+    position: [f32; 3],
+    direction: [f32; 3],
+    range: f32,
+    angle: f32,
+  ) -> Self {
+    return Self {
+      position,
+      direction,
+      color: [1.0, 1.0, 1.0],
+      intensity: 1.0,
+      range,
+      inner_angle: angle * 0.75,
+      outer_angle: angle,
+    };
+  }
+}
+
+/// Any of the lights supported by a light set.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Light {
+  Directional(DirectionalLight),
+  Point(PointLight),
+  Spot(SpotLight),
+}
+
+impl From<DirectionalLight> for Light {
+  fn from(light: DirectionalLight) -> Self {
+    return Light::Directional(light);
+  }
+}
+
+impl From<PointLight> for Light {
+  fn from(light: PointLight) -> Self {
+    return Light::Point(light);
+  }
+}
+
+impl From<SpotLight> for Light {
+  fn from(light: SpotLight) -> Self {
+    return Light::Spot(light);
+  }
+}
+
+/// Attenuation that falls off with the square of the distance and smoothly
+/// reaches zero at the range of the light.
+fn attenuation(distance: f32, range: f32) -> f32 {
+  if range <= 0.0 || distance >= range {
+    return 0.0;
+  }
+  let window = (1.0 - (distance / range).powi(4)).clamp(0.0, 1.0);
+  return window * window / (distance * distance + 1.0);
+}
+
+/// Normalizes a vector, returning the zero vector for zero length vectors.
+fn normalize_or_zero(vector: [f32; 3]) -> [f32; 3] {
+  return match vector.length() > f32::EPSILON {
+    true => vector.normalize(),
+    false => [0.0; 3],
+  };
+}
+
+impl Light {
+  /// The diffuse light received by a surface at the position with the given
+  /// normal, which is expected to be normalized.
+  pub fn irradiance(&self, position: [f32; 3], normal: [f32; 3]) -> [f32; 3] {
+    let (to_light, color, intensity) = match self {
+      Light::Directional(light) => (
+        normalize_or_zero(light.direction).scale(-1.0),
+        light.color,
+        light.intensity,
+      ),
+      Light::Point(light) => {
+        let offset = light.position.subtract(&position);
+        let falloff = attenuation(offset.length(), light.range);
+        (
+          normalize_or_zero(offset),
+          light.color,
+          light.intensity * falloff,
+        )
+      }
+      Light::Spot(light) => {
+        let offset = light.position.subtract(&position);
+        let to_light = normalize_or_zero(offset);
+        let falloff = attenuation(offset.length(), light.range);
+
+        let cosine = -normalize_or_zero(light.direction).dot(&to_light);
+        let (inner, outer) = (light.inner_angle.cos(), light.outer_angle.cos());
+        let t = ((cosine - outer) / (inner - outer).max(f32::EPSILON))
+          .clamp(0.0, 1.0);
+        let cone = t * t * (3.0 - 2.0 * t);
+
+        (to_light, light.color, light.intensity * falloff * cone)
+      }
+    };
+
+    let diffuse = normal.dot(&to_light).max(0.0) * intensity;
+    return color.scale(diffuse);
+  }
+
+  /// The light packed into 16 std140 words.
+  fn uniform_data(&self) -> [f32; 16] {
+    let mut data = [0.0; 16];
+    match self {
+      Light::Directional(light) => {
+        data[4..7].copy_from_slice(&light.direction);
+        data[8..11].copy_from_slice(&light.color);
+        data[11] = light.intensity;
+      }
+      Light::Point(light) => {
+        data[0..3].copy_from_slice(&light.position);
+        data[3] = 1.0;
+        data[7] = light.range;
+        data[8..11].copy_from_slice(&light.color);
+        data[11] = light.intensity;
+      }
+      Light::Spot(light) => {
+        data[0..3].copy_from_slice(&light.position);
+        data[3] = 2.0;
+        data[4..7].copy_from_slice(&light.direction);
+        data[7] = light.range;
+        data[8..11].copy_from_slice(&light.color);
+        data[11] = light.intensity;
+        data[12] = light.inner_angle.cos();
+        data[13] = light.outer_angle.cos();
+      }
+    }
+    return data;
+  }
+}
+
+// ---------------------------------- SHADOWS ----------------------------------
+
+impl DirectionalLight {
+  /// The column major view projection matrix of an orthographic frustum that
+  /// looks down the light's direction and encloses the sphere with the given
+  /// center & radius, for rendering a shadow map with the given resolution.
+  /// Depth is mapped from 0 (nearest to the light) to 1.
+  ///
+  /// The frustum is stable as the camera moves & rotates: it's sized from a
+  /// bounding sphere so it doesn't change size when the camera rotates, and
+  /// its center is snapped to shadow map texels so that shadow edges don't
+  /// shimmer when the camera moves.
+  pub fn shadow_view_projection(
+    &self,
+    center: [f32; 3],
+    radius: f32,
+    shadow_map_size: u32,
+  ) -> [[f32; 4]; 4] {
+    let forward = match normalize_or_zero(self.direction) {
+      [0.0, 0.0, 0.0] => [0.0, -1.0, 0.0],
+      forward => forward,
+    };
+    let reference_up = match forward[1].abs() > 0.99 {
+      true => [1.0, 0.0, 0.0],
+      false => [0.0, 1.0, 0.0],
+    };
+    let right = normalize_or_zero(forward.cross(&reference_up));
+    let up = right.cross(&forward);
+
+    let radius = radius.max(f32::EPSILON);
+    let texel_size = 2.0 * radius / shadow_map_size.max(1) as f32;
+    let snap = |value: f32| (value / texel_size).round() * texel_size;
+    let center_x = snap(right.dot(&center));
+    let center_y = snap(up.dot(&center));
+    let near = forward.dot(&center) - radius;
+
+    let rows = [
+      [right[0], right[1], right[2], -center_x].scale(1.0 / radius),
+      [up[0], up[1], up[2], -center_y].scale(1.0 / radius),
+      [forward[0], forward[1], forward[2], -near].scale(0.5 / radius),
+      [0.0, 0.0, 0.0, 1.0],
+    ];
+
+    let mut matrix = [[0.0; 4]; 4];
+    for (row, values) in rows.iter().enumerate() {
+      for (column, value) in values.iter().enumerate() {
+        matrix[column][row] = *value;
+      }
+    }
+    return matrix;
+  }
+}
+
+// --------------------------------- LIGHT SET ---------------------------------
+
+/// Identifies a light that has been added to a light set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LightId(u32);
+
+/// The collection of lights that illuminate a scene. Components can share a
+/// light set (I.E. through an `Rc<RefCell<LightSet>>`) and add, update, or
+/// remove their lights every frame.
+#[derive(Clone, Debug)]
+pub struct LightSet {
+  ambient: [f32; 3],
+  lights: Vec<(LightId, Light)>,
+  next_id: u32,
+}
+
+impl LightSet {
+  /// A new light set without any lights and a dim ambient light.
+  pub fn new() -> Self {
+    return Self {
+      ambient: [0.03, 0.03, 0.03],
+      lights: vec![],
+      next_id: 0,
+    };
+  }
+
+  /// The color of the ambient light that lights every surface equally.
+  pub fn with_ambient(mut self, ambient: [f32; 3]) -> Self {
+    self.ambient = ambient;
+    return self;
+  }
+
+  /// The color of the ambient light.
+  pub fn ambient(&self) -> [f32; 3] {
+    return self.ambient;
+  }
+
+  /// Adds a light to the set, returning the id used to update or remove it.
+  pub fn add<L: Into<Light>>(&mut self, light: L) -> LightId {
+    let id = LightId(self.next_id);
+    self.next_id += 1;
+    self.lights.push((id, light.into()));
+    return id;
+  }
+
+  /// Gets a light within the set.
+  pub fn get(&self, id: LightId) -> Option<&Light> {
+    return self
+      .lights
+      .iter()
+      .find(|(light_id, _)| *light_id == id)
+      .map(|(_, light)| light);
+  }
+
+  /// Gets a mutable reference to a light within the set.
+  pub fn get_mut(&mut self, id: LightId) -> Option<&mut Light> {
+    return self
+      .lights
+      .iter_mut()
+      .find(|(light_id, _)| *light_id == id)
+      .map(|(_, light)| light);
+  }
+
+  /// Replaces a light within the set. Returns false if the light isn't in
+  /// the set.
+  pub fn update<L: Into<Light>>(&mut self, id: LightId, light: L) -> bool {
+    return match self.get_mut(id) {
+      Some(existing) => {
+        *existing = light.into();
+        true
+      }
+      None => false,
+    };
+  }
+
+  /// Removes a light from the set.
+  pub fn remove(&mut self, id: LightId) -> Option<Light> {
+    let index = self
+      .lights
+      .iter()
+      .position(|(light_id, _)| *light_id == id)?;
+    return Some(self.lights.remove(index).1);
+  }
+
+  /// Removes every light from the set.
+  pub fn clear(&mut self) {
+    self.lights.clear();
+  }
+
+  /// The number of lights within the set.
+  pub fn len(&self) -> usize {
+    return self.lights.len();
+  }
+
+  /// Whether or not the set has any lights.
+  pub fn is_empty(&self) -> bool {
+    return self.lights.is_empty();
+  }
+
+  /// Every light in the order they were added.
+  pub fn lights(&self) -> impl Iterator<Item = &Light> {
+    return self.lights.iter().map(|(_, light)| light);
+  }
+
+  /// The first directional light that casts shadows, which is the light a
+  /// shadow depth pass should be rendered from.
+  pub fn shadow_caster(&self) -> Option<&DirectionalLight> {
+    return self.lights().find_map(|light| match light {
+      Light::Directional(light) if light.casts_shadows => Some(light),
+      _ => None,
+    });
+  }
+
+  /// The view projection matrix for rendering the shadow map of the set's
+  /// shadow caster over the sphere with the given center & radius. See
+  /// `DirectionalLight::shadow_view_projection`.
+  pub fn shadow_view_projection(
+    &self,
+    center: [f32; 3],
+    radius: f32,
+    shadow_map_size: u32,
+  ) -> Option<[[f32; 4]; 4]> {
+    return self.shadow_caster().map(|light| {
+      return light.shadow_view_projection(center, radius, shadow_map_size);
+    });
+  }
+
+  /// Evaluates the lighting of a surface on the CPU, returning the lit color.
+  pub fn shade(
+    &self,
+    position: [f32; 3],
+    normal: [f32; 3],
+    base_color: [f32; 3],
+  ) -> [f32; 3] {
+    let normal = normalize_or_zero(normal);
+    let light = self.lights().fold(self.ambient, |total, light| {
+      return total.add(&light.irradiance(position, normal));
+    });
+
+    return [
+      base_color[0] * light[0],
+      base_color[1] * light[1],
+      base_color[2] * light[2],
+    ];
+  }
+
+  /// The lights packed into `LIGHT_SET_UNIFORM_SIZE` bytes of std140 uniform
+  /// data. Only the first `MAX_LIGHTS` lights are included.
+  pub fn uniform_data(&self) -> Vec<u32> {
+    let light_count = self.lights.len().min(MAX_LIGHTS);
+
+    let mut data = Vec::with_capacity(LIGHT_SET_UNIFORM_SIZE / 4);
+    data.extend(self.ambient.iter().map(|value| value.to_bits()));
+    data.push(0);
+    data.extend([light_count as u32, 0, 0, 0]);
+
+    for (_, light) in self.lights.iter().take(light_count) {
+      data.extend(light.uniform_data().iter().map(|value| value.to_bits()));
+    }
+    data.resize(LIGHT_SET_UNIFORM_SIZE / 4, 0);
+    return data;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    DirectionalLight,
+    Light,
+    LightSet,
+    PointLight,
+    SpotLight,
+    LIGHT_SET_UNIFORM_SIZE,
+  };
+
+  fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+    for (actual, expected) in actual.iter().zip(expected.iter()) {
+      assert!((actual - expected).abs() < 1e-4, "{:?}", actual);
+    }
+  }
+
+  #[test]
+  fn lights_illuminate_surfaces() {
+    let up = [0.0, 1.0, 0.0];
+    let sun = Light::from(DirectionalLight::new([0.0, -1.0, 0.0]));
+    assert_close(sun.irradiance([0.0; 3], up), [1.0, 1.0, 1.0]);
+    assert_close(sun.irradiance([0.0; 3], [0.0, -1.0, 0.0]), [0.0; 3]);
+
+    // Point lights fade out before reaching their range.
+    let bulb = Light::from(PointLight::new([0.0, 1.0, 0.0], 10.0));
+    assert!(bulb.irradiance([0.0; 3], up)[0] > 0.4);
+    assert_close(bulb.irradiance([0.0, -9.5, 0.0], up), [0.0; 3]);
+
+    let spot =
+      Light::from(SpotLight::new([0.0, 1.0, 0.0], [0.0, -1.0, 0.0], 10.0, 0.5));
+    assert!(spot.irradiance([0.0; 3], up)[0] > 0.4);
+    assert_close(spot.irradiance([2.0, 0.0, 0.0], up), [0.0; 3]);
+  }
+
+  #[test]
+  fn light_sets_manage_lights() {
+    let mut lights = LightSet::new().with_ambient([0.1, 0.1, 0.1]);
+    let sun = lights.add(DirectionalLight::new([0.0, -1.0, 0.0]));
+    let bulb = lights.add(PointLight::new([0.0, 1.0, 0.0], 5.0));
+    assert_eq!(lights.len(), 2);
+    assert!(lights.shadow_caster().is_none());
+
+    let mut shadowed = DirectionalLight::new([0.0, -1.0, 0.0]);
+    shadowed.casts_shadows = true;
+    shadowed.intensity = 0.5;
+    assert!(lights.update(sun, shadowed));
+    assert_eq!(lights.shadow_caster(), Some(&shadowed));
+
+    assert!(lights.remove(bulb).is_some());
+    assert!(lights.get(bulb).is_none());
+    assert!(!lights.update(bulb, PointLight::new([0.0; 3], 1.0)));
+
+    let color = lights.shade([0.0; 3], [0.0, 2.0, 0.0], [1.0, 0.5, 0.0]);
+    assert_close(color, [0.6, 0.3, 0.0]);
+
+    let data = lights.uniform_data();
+    assert_eq!(data.len() * 4, LIGHT_SET_UNIFORM_SIZE);
+    assert_eq!(f32::from_bits(data[0]), 0.1);
+    assert_eq!(data[4], 1);
+    // The directional light's direction & intensity.
+    assert_eq!(f32::from_bits(data[8 + 5]), -1.0);
+    assert_eq!(f32::from_bits(data[8 + 11]), 0.5);
+  }
+
+  #[test]
+  fn shadow_frustums_enclose_and_snap() {
+    let transform = |matrix: [[f32; 4]; 4], point: [f32; 3]| {
+      let mut result = [0.0; 3];
+      for (row, value) in result.iter_mut().enumerate() {
+        *value = matrix[0][row] * point[0]
+          + matrix[1][row] * point[1]
+          + matrix[2][row] * point[2]
+          + matrix[3][row];
+      }
+      return result;
+    };
+
+    let mut sun = DirectionalLight::new([0.0, -1.0, 0.0]);
+    let mut lights = LightSet::new();
+    lights.add(sun);
+    assert!(lights.shadow_view_projection([0.0; 3], 1.0, 1024).is_none());
+
+    sun.casts_shadows = true;
+    lights.clear();
+    lights.add(sun);
+    let matrix = lights
+      .shadow_view_projection([0.0, 0.0, 0.0], 10.0, 1024)
+      .unwrap();
+
+    // The top of the sphere is nearest to the light.
+    let top = transform(matrix, [0.0, 10.0, 0.0]);
+    assert!(top[2].abs() < 1e-5, "{:?}", top);
+    let bottom = transform(matrix, [0.0, -10.0, 0.0]);
+    assert!((bottom[2] - 1.0).abs() < 1e-5, "{:?}", bottom);
+    let center = transform(matrix, [0.0; 3]);
+    assert!(center[0].abs() < 1e-5 && center[1].abs() < 1e-5);
+
+    // Moving the center by less than half a texel doesn't move the frustum.
+    let texel = 20.0 / 1024.0;
+    let moved = sun.shadow_view_projection([texel * 0.4, 0.0, 0.0], 10.0, 1024);
+    assert_eq!(moved, matrix);
+  }
+}
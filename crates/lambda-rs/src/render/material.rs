@@ -0,0 +1,265 @@
+//! Materials describing the surface of meshes with metallic/roughness PBR
+//! parameters. Material parameters are uploaded to shaders as push constants
+//! since pipelines don't support descriptor sets yet. Texture paths are
+//! recorded on materials so that they can be bound once textures are
+//! supported.
+
+use std::path::{
+  Path,
+  PathBuf,
+};
+
+use super::{
+  command::RenderCommand,
+  mesh::MeshMaterial,
+  pipeline::PipelineStage,
+  ResourceId,
+};
+
+/// The size in bytes of the parameters uploaded by `Material::push_constants`.
+/// The parameters are laid out as the following GLSL push constant block
+/// members (Starting at the offset they're pushed to):
+///
+/// ```glsl
+/// vec4 base_color;
+/// vec4 emissive_metallic; // xyz = emissive, w = metallic
+/// vec4 roughness;         // x = roughness, yzw = unused
+/// ```
+pub const MATERIAL_PUSH_CONSTANT_SIZE: u32 = 48;
+
+/// The surface properties of a mesh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+  name: String,
+  base_color: [f32; 4],
+  metallic: f32,
+  roughness: f32,
+  emissive: [f32; 3],
+  base_color_texture: Option<PathBuf>,
+  metallic_roughness_texture: Option<PathBuf>,
+  normal_texture: Option<PathBuf>,
+  emissive_texture: Option<PathBuf>,
+}
+
+impl Material {
+  /// The name of the material.
+  pub fn name(&self) -> &str {
+    return &self.name;
+  }
+
+  /// The linear RGBA base color of the material.
+  pub fn base_color(&self) -> [f32; 4] {
+    return self.base_color;
+  }
+
+  /// How metallic the material is, from 0 (dielectric) to 1 (metal).
+  pub fn metallic(&self) -> f32 {
+    return self.metallic;
+  }
+
+  /// How rough the material is, from 0 (smooth) to 1 (rough).
+  pub fn roughness(&self) -> f32 {
+    return self.roughness;
+  }
+
+  /// The linear RGB color emitted by the material.
+  pub fn emissive(&self) -> [f32; 3] {
+    return self.emissive;
+  }
+
+  /// The path of the base color texture, if the material has one.
+  pub fn base_color_texture(&self) -> Option<&Path> {
+    return self.base_color_texture.as_deref();
+  }
+
+  /// The path of the metallic/roughness texture, if the material has one.
+  pub fn metallic_roughness_texture(&self) -> Option<&Path> {
+    return self.metallic_roughness_texture.as_deref();
+  }
+
+  /// The path of the normal map, if the material has one.
+  pub fn normal_texture(&self) -> Option<&Path> {
+    return self.normal_texture.as_deref();
+  }
+
+  /// The path of the emissive texture, if the material has one.
+  pub fn emissive_texture(&self) -> Option<&Path> {
+    return self.emissive_texture.as_deref();
+  }
+
+  /// The material's parameters packed into `MATERIAL_PUSH_CONSTANT_SIZE`
+  /// bytes of push constant data.
+  pub fn push_constant_data(&self) -> Vec<u32> {
+    let [r, g, b, a] = self.base_color;
+    let [er, eg, eb] = self.emissive;
+    return [
+      r,
+      g,
+      b,
+      a,
+      er,
+      eg,
+      eb,
+      self.metallic,
+      self.roughness,
+      0.0,
+      0.0,
+      0.0,
+    ]
+    .iter()
+    .map(|value| value.to_bits())
+    .collect();
+  }
+
+  /// A command that uploads the material's parameters to the pipeline's push
+  /// constants at the given stage(s) and offset in bytes.
+  pub fn push_constants(
+    &self,
+    pipeline: ResourceId,
+    stage: PipelineStage,
+    offset: u32,
+  ) -> RenderCommand {
+    return RenderCommand::PushConstants {
+      pipeline,
+      stage,
+      offset,
+      bytes: self.push_constant_data(),
+    };
+  }
+}
+
+/// Builder for materials.
+pub struct MaterialBuilder {
+  material: Material,
+}
+
+impl MaterialBuilder {
+  /// A new material builder produces a white, fully rough dielectric.
+  pub fn new(name: &str) -> Self {
+    return Self {
+      material: Material {
+        name: name.to_string(),
+        base_color: [1.0, 1.0, 1.0, 1.0],
+        metallic: 0.0,
+        roughness: 1.0,
+        emissive: [0.0, 0.0, 0.0],
+        base_color_texture: None,
+        metallic_roughness_texture: None,
+        normal_texture: None,
+        emissive_texture: None,
+      },
+    };
+  }
+
+  /// A builder for a material loaded alongside a mesh, using its diffuse
+  /// color & texture as the base color.
+  pub fn from_mesh_material(mesh_material: &MeshMaterial) -> Self {
+    let [r, g, b] = mesh_material.diffuse_color();
+    let mut builder =
+      Self::new(mesh_material.name()).with_base_color([r, g, b, 1.0]);
+    builder.material.base_color_texture =
+      mesh_material.diffuse_texture().map(Path::to_path_buf);
+    return builder;
+  }
+
+  /// The linear RGBA base color of the material.
+  pub fn with_base_color(mut self, base_color: [f32; 4]) -> Self {
+    self.material.base_color = base_color;
+    return self;
+  }
+
+  /// How metallic the material is. Clamped between 0 and 1.
+  pub fn with_metallic(mut self, metallic: f32) -> Self {
+    self.material.metallic = metallic.clamp(0.0, 1.0);
+    return self;
+  }
+
+  /// How rough the material is. Clamped between 0 and 1.
+  pub fn with_roughness(mut self, roughness: f32) -> Self {
+    self.material.roughness = roughness.clamp(0.0, 1.0);
+    return self;
+  }
+
+  /// The linear RGB color emitted by the material.
+  pub fn with_emissive(mut self, emissive: [f32; 3]) -> Self {
+    self.material.emissive = emissive;
+    return self;
+  }
+
+  /// The path of the base color texture.
+  pub fn with_base_color_texture(mut self, path: &str) -> Self {
+    self.material.base_color_texture = Some(PathBuf::from(path));
+    return self;
+  }
+
+  /// The path of the metallic/roughness texture, which stores roughness in
+  /// the green channel and metalness in the blue channel.
+  pub fn with_metallic_roughness_texture(mut self, path: &str) -> Self {
+    self.material.metallic_roughness_texture = Some(PathBuf::from(path));
+    return self;
+  }
+
+  /// The path of the tangent space normal map.
+  pub fn with_normal_texture(mut self, path: &str) -> Self {
+    self.material.normal_texture = Some(PathBuf::from(path));
+    return self;
+  }
+
+  /// The path of the emissive texture.
+  pub fn with_emissive_texture(mut self, path: &str) -> Self {
+    self.material.emissive_texture = Some(PathBuf::from(path));
+    return self;
+  }
+
+  /// Builds the material.
+  pub fn build(self) -> Material {
+    return self.material;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    MaterialBuilder,
+    MATERIAL_PUSH_CONSTANT_SIZE,
+  };
+  use crate::render::{
+    command::RenderCommand,
+    pipeline::PipelineStage,
+    ResourceId,
+  };
+
+  #[test]
+  fn materials_are_packed_into_push_constants() {
+    let material = MaterialBuilder::new("gold")
+      .with_base_color([1.0, 0.8, 0.2, 1.0])
+      .with_metallic(2.0)
+      .with_roughness(0.25)
+      .with_emissive([0.1, 0.0, 0.0])
+      .with_normal_texture("gold_normal.png")
+      .build();
+
+    assert_eq!(material.metallic(), 1.0);
+    assert_eq!(material.base_color_texture(), None);
+    assert!(material.normal_texture().is_some());
+
+    let data = material.push_constant_data();
+    assert_eq!(data.len() * 4, MATERIAL_PUSH_CONSTANT_SIZE as usize);
+    assert_eq!(f32::from_bits(data[1]), 0.8);
+    assert_eq!(f32::from_bits(data[4]), 0.1);
+    assert_eq!(f32::from_bits(data[7]), 1.0);
+    assert_eq!(f32::from_bits(data[8]), 0.25);
+
+    match material.push_constants(
+      ResourceId::new(0, 0),
+      PipelineStage::FRAGMENT,
+      64,
+    ) {
+      RenderCommand::PushConstants { offset, bytes, .. } => {
+        assert_eq!(offset, 64);
+        assert_eq!(bytes, data);
+      }
+      command => panic!("Unexpected command {:?}", command),
+    }
+  }
+}
@@ -0,0 +1,256 @@
+//! Tracking of the memory that a render context allocates on the GPU, so that
+//! leaks & oversized resources show up before the GPU runs out of memory.
+
+use std::{
+  cmp::Reverse,
+  fmt::{
+    Display,
+    Formatter,
+  },
+};
+
+/// What an allocation on the GPU is used for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MemoryCategory {
+  VertexBuffer,
+  IndexBuffer,
+  UniformBuffer,
+  StorageBuffer,
+  StagingBuffer,
+  ReadbackBuffer,
+  DepthStencil,
+  ColorAttachment,
+}
+
+/// A single allocation on the GPU.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Allocation {
+  pub category: MemoryCategory,
+  pub label: String,
+  pub bytes: u64,
+}
+
+/// The memory allocated for a category of allocations.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CategoryUsage {
+  pub category: MemoryCategory,
+  pub bytes: u64,
+  pub allocations: usize,
+}
+
+/// The memory allocated by a render context, aggregated per category.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MemoryReport {
+  pub total_bytes: u64,
+  /// The budget set on the render context, if any.
+  pub budget: Option<u64>,
+  /// The memory local to the GPU as reported by the adapter, if known.
+  pub device_local_bytes: Option<u64>,
+  /// The categories that have allocations, in the order they're declared.
+  pub categories: Vec<CategoryUsage>,
+  /// Every live allocation, from largest to smallest.
+  pub allocations: Vec<Allocation>,
+}
+
+fn mebibytes(bytes: u64) -> f64 {
+  return bytes as f64 / (1024.0 * 1024.0);
+}
+
+impl Display for MemoryReport {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "GPU memory: {:.2} MiB", mebibytes(self.total_bytes))?;
+    if let Some(budget) = self.budget {
+      write!(f, " of a {:.2} MiB budget", mebibytes(budget))?;
+    }
+    for usage in self.categories.iter() {
+      write!(
+        f,
+        "\n  {:?}: {:.2} MiB in {} allocations",
+        usage.category,
+        mebibytes(usage.bytes),
+        usage.allocations
+      )?;
+    }
+    return Ok(());
+  }
+}
+
+/// Identifies an allocation tracked by a `MemoryTracker`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct AllocationId(u64);
+
+/// Tracks the live allocations of a render context & warns once they exceed
+/// the budget or the memory of the GPU.
+#[derive(Debug, Default)]
+pub(super) struct MemoryTracker {
+  allocations: Vec<(AllocationId, Allocation)>,
+  next_id: u64,
+  total_bytes: u64,
+  budget: Option<u64>,
+  device_local_bytes: Option<u64>,
+  /// Whether the last allocation left the total over a limit, so that a
+  /// warning is only logged when the total crosses it.
+  over_limit: bool,
+}
+
+impl MemoryTracker {
+  pub(super) fn new(budget: Option<u64>, device_local_bytes: u64) -> Self {
+    return Self {
+      budget,
+      device_local_bytes: Some(device_local_bytes).filter(|bytes| *bytes > 0),
+      ..Default::default()
+    };
+  }
+
+  /// Starts tracking an allocation and returns the id to stop tracking it
+  /// with. Logs a warning if the allocation exceeds a limit.
+  pub(super) fn track(
+    &mut self,
+    category: MemoryCategory,
+    label: &str,
+    bytes: u64,
+  ) -> AllocationId {
+    let id = AllocationId(self.next_id);
+    self.next_id += 1;
+    self.total_bytes += bytes;
+    self.allocations.push((
+      id,
+      Allocation {
+        category,
+        label: label.to_string(),
+        bytes,
+      },
+    ));
+
+    let limit = self.exceeded_limit();
+    if let (Some((name, limit)), false) = (limit, self.over_limit) {
+      logging::warn!(
+        "GPU allocations exceed the {} of {:.2} MiB after allocating {:.2} \
+         MiB for {:?} {}.\n{}",
+        name,
+        mebibytes(limit),
+        mebibytes(bytes),
+        category,
+        label,
+        self.report()
+      );
+    }
+    self.over_limit = limit.is_some();
+    return id;
+  }
+
+  /// Stops tracking the allocation once it's freed.
+  pub(super) fn untrack(&mut self, id: AllocationId) {
+    let index = self
+      .allocations
+      .iter()
+      .position(|(allocation_id, _)| *allocation_id == id);
+    if let Some(index) = index {
+      let (_, allocation) = self.allocations.swap_remove(index);
+      self.total_bytes -= allocation.bytes;
+      self.over_limit = self.exceeded_limit().is_some();
+    }
+  }
+
+  /// The name & size of the smallest limit that the total exceeds.
+  fn exceeded_limit(&self) -> Option<(&'static str, u64)> {
+    return [
+      ("budget", self.budget),
+      ("memory of the GPU", self.device_local_bytes),
+    ]
+    .into_iter()
+    .filter_map(|(name, limit)| limit.map(|limit| (name, limit)))
+    .filter(|(_, limit)| self.total_bytes > *limit)
+    .min_by_key(|(_, limit)| *limit);
+  }
+
+  pub(super) fn report(&self) -> MemoryReport {
+    let mut categories: Vec<CategoryUsage> = vec![];
+    for (_, allocation) in self.allocations.iter() {
+      match categories
+        .iter_mut()
+        .find(|usage| usage.category == allocation.category)
+      {
+        Some(usage) => {
+          usage.bytes += allocation.bytes;
+          usage.allocations += 1;
+        }
+        None => categories.push(CategoryUsage {
+          category: allocation.category,
+          bytes: allocation.bytes,
+          allocations: 1,
+        }),
+      }
+    }
+    categories.sort_by_key(|usage| usage.category);
+
+    let mut allocations: Vec<Allocation> = self
+      .allocations
+      .iter()
+      .map(|(_, allocation)| allocation.clone())
+      .collect();
+    allocations.sort_by_key(|allocation| Reverse(allocation.bytes));
+
+    return MemoryReport {
+      total_bytes: self.total_bytes,
+      budget: self.budget,
+      device_local_bytes: self.device_local_bytes,
+      categories,
+      allocations,
+    };
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    MemoryCategory,
+    MemoryTracker,
+  };
+
+  #[test]
+  fn allocations_are_aggregated_per_category() {
+    let mut tracker = MemoryTracker::new(None, 0);
+    let mesh = tracker.track(MemoryCategory::VertexBuffer, "mesh", 300);
+    tracker.track(MemoryCategory::IndexBuffer, "mesh", 100);
+    tracker.track(MemoryCategory::VertexBuffer, "text", 200);
+
+    let report = tracker.report();
+    assert_eq!(report.total_bytes, 600);
+    assert_eq!(report.device_local_bytes, None);
+    assert_eq!(report.categories.len(), 2);
+    assert_eq!(report.categories[0].category, MemoryCategory::VertexBuffer);
+    assert_eq!(report.categories[0].bytes, 500);
+    assert_eq!(report.categories[0].allocations, 2);
+    assert_eq!(report.allocations[0].label, "mesh");
+    assert_eq!(report.allocations[0].bytes, 300);
+
+    tracker.untrack(mesh);
+    let report = tracker.report();
+    assert_eq!(report.total_bytes, 300);
+    assert_eq!(report.categories[0].bytes, 200);
+  }
+
+  #[test]
+  fn limits_are_crossed_once() {
+    let mut tracker = MemoryTracker::new(Some(1000), 4000);
+    tracker.track(MemoryCategory::StagingBuffer, "staging", 800);
+    assert_eq!(tracker.exceeded_limit(), None);
+
+    let texture = tracker.track(MemoryCategory::DepthStencil, "depth", 400);
+    assert_eq!(tracker.exceeded_limit(), Some(("budget", 1000)));
+    assert!(tracker.over_limit);
+
+    tracker.untrack(texture);
+    assert!(!tracker.over_limit);
+
+    tracker.track(MemoryCategory::VertexBuffer, "huge", 5000);
+    assert_eq!(tracker.exceeded_limit(), Some(("budget", 1000)));
+    assert!(tracker
+      .report()
+      .to_string()
+      .contains("of a 0.00 MiB budget"));
+  }
+}
@@ -1,8 +1,26 @@
 //! Mesh Implementation
 
-use lambda_platform::obj::load_textured_obj_from_file;
+use std::{
+  collections::HashMap,
+  ops::Range,
+  path::{
+    Path,
+    PathBuf,
+  },
+};
+
+use lambda_platform::obj::{
+  load_obj_with_materials_from,
+  load_obj_with_materials_from_file,
+  load_textured_obj_from_file,
+  ObjModel,
+};
 
 use super::{
+  super::math::{
+    ray::Aabb,
+    vector::Vector,
+  },
   vertex::{
     Vertex,
     VertexAttribute,
@@ -11,13 +29,274 @@ use super::{
   ColorFormat,
 };
 
+// -------------------------------- Materials ---------------------------------
+
+/// A material that a mesh was loaded with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshMaterial {
+  name: String,
+  diffuse_color: [f32; 3],
+  diffuse_texture: Option<PathBuf>,
+}
+
+impl MeshMaterial {
+  /// The name of the material.
+  pub fn name(&self) -> &str {
+    return &self.name;
+  }
+
+  /// The diffuse color of the material.
+  pub fn diffuse_color(&self) -> [f32; 3] {
+    return self.diffuse_color;
+  }
+
+  /// The path of the diffuse texture of the material, if it has one.
+  pub fn diffuse_texture(&self) -> Option<&Path> {
+    return self.diffuse_texture.as_deref();
+  }
+}
+
+/// A range of vertices within a mesh that belong to the same object and are
+/// drawn with the same material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubMesh {
+  name: String,
+  material: Option<usize>,
+  vertices: Range<u32>,
+}
+
+impl SubMesh {
+  /// The name of the object or group the submesh was loaded from.
+  pub fn name(&self) -> &str {
+    return &self.name;
+  }
+
+  /// The index of the submesh's material within the mesh's materials.
+  pub fn material(&self) -> Option<usize> {
+    return self.material;
+  }
+
+  /// The range of vertices to draw for the submesh. When the mesh is indexed
+  /// this is the range of indices to draw instead.
+  pub fn vertices(&self) -> Range<u32> {
+    return self.vertices.clone();
+  }
+}
+
+// --------------------------------- Indices ----------------------------------
+
+/// The indices of an indexed mesh. Meshes with few enough vertices to be
+/// indexed with 16 bits use 16 bit indices.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeshIndices {
+  U16(Vec<u16>),
+  U32(Vec<u32>),
+}
+
+impl MeshIndices {
+  /// Selects the smallest index type capable of indexing every vertex.
+  fn from_indices(indices: Vec<u32>, vertex_count: usize) -> Self {
+    if vertex_count <= u16::MAX as usize + 1 {
+      return MeshIndices::U16(
+        indices.into_iter().map(|index| index as u16).collect(),
+      );
+    }
+    return MeshIndices::U32(indices);
+  }
+
+  /// The number of indices.
+  pub fn len(&self) -> usize {
+    return match self {
+      MeshIndices::U16(indices) => indices.len(),
+      MeshIndices::U32(indices) => indices.len(),
+    };
+  }
+
+  /// Whether or not there are no indices.
+  pub fn is_empty(&self) -> bool {
+    return self.len() == 0;
+  }
+
+  /// Copies the indices into 32 bit indices.
+  pub fn to_vec(&self) -> Vec<u32> {
+    return match self {
+      MeshIndices::U16(indices) => {
+        indices.iter().map(|index| *index as u32).collect()
+      }
+      MeshIndices::U32(indices) => indices.clone(),
+    };
+  }
+}
+
+/// Removes duplicate vertices, returning the unique vertices along with the
+/// indices that reconstruct the original vertices. Vertices are only
+/// considered identical when every attribute is bitwise equal.
+fn deduplicate_vertices(vertices: &[Vertex]) -> (Vec<Vertex>, Vec<u32>) {
+  let mut unique_vertices = vec![];
+  let mut indices = Vec::with_capacity(vertices.len());
+  let mut vertex_indices: HashMap<[u32; 15], u32> = HashMap::new();
+
+  for vertex in vertices.iter() {
+    let mut key = [0; 15];
+    let attributes = vertex
+      .position
+      .iter()
+      .chain(vertex.normal.iter())
+      .chain(vertex.color.iter())
+      .chain(vertex.tex_coords.iter())
+      .chain(vertex.tangent.iter());
+    for (bits, attribute) in key.iter_mut().zip(attributes) {
+      *bits = attribute.to_bits();
+    }
+
+    let index = *vertex_indices.entry(key).or_insert_with(|| {
+      unique_vertices.push(*vertex);
+      return unique_vertices.len() as u32 - 1;
+    });
+    indices.push(index);
+  }
+
+  return (unique_vertices, indices);
+}
+
+// ----------------------------- Post processing ------------------------------
+
+/// Bitwise key for a vector so that vertices can be grouped by attributes.
+fn vector_key<const N: usize>(vector: [f32; N]) -> [u32; N] {
+  return vector.map(f32::to_bits);
+}
+
+/// Normalizes a vector, returning None for vectors that are too short to
+/// have a direction.
+fn try_normalize(vector: [f32; 3]) -> Option<[f32; 3]> {
+  return match vector.length() > 1e-12 {
+    true => Some(vector.normalize()),
+    false => None,
+  };
+}
+
+/// Generates normals for the vertices of a triangle list that don't have
+/// one. The normal of a vertex is the area weighted average of the faces
+/// touching its position whose normals are within `smooth_angle` radians of
+/// the vertex's own face, which keeps hard edges sharp.
+fn generate_normals(vertices: &mut [Vertex], smooth_angle: f32) {
+  let face_normals = vertices
+    .chunks_exact(3)
+    .map(|triangle| {
+      let edge1 = triangle[1].position.subtract(&triangle[0].position);
+      let edge2 = triangle[2].position.subtract(&triangle[0].position);
+      return edge1.cross(&edge2);
+    })
+    .collect::<Vec<_>>();
+
+  let mut corners_at_position: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+  for (corner, vertex) in vertices.iter().enumerate() {
+    corners_at_position
+      .entry(vector_key(vertex.position))
+      .or_default()
+      .push(corner);
+  }
+
+  let smooth_cosine = smooth_angle.cos();
+  for corner in 0..face_normals.len() * 3 {
+    if vertices[corner].normal.length() > 0.0 {
+      continue;
+    }
+
+    let face_normal = match try_normalize(face_normals[corner / 3]) {
+      Some(face_normal) => face_normal,
+      None => continue,
+    };
+
+    let mut normal = [0.0; 3];
+    for other in corners_at_position[&vector_key(vertices[corner].position)]
+      .iter()
+      .filter(|other| **other < face_normals.len() * 3)
+    {
+      let other_normal = face_normals[other / 3];
+      let facing = try_normalize(other_normal)
+        .is_some_and(|other| other.dot(&face_normal) >= smooth_cosine);
+      if facing {
+        normal = normal.add(&other_normal);
+      }
+    }
+
+    vertices[corner].normal = try_normalize(normal).unwrap_or(face_normal);
+  }
+}
+
+/// Generates tangents for the vertices of a triangle list from their texture
+/// coordinates. Tangents are accumulated across vertices that share a
+/// position, normal, and texture coordinate and are then orthogonalized
+/// against the normal.
+fn generate_tangents(vertices: &mut [Vertex]) {
+  let mut accumulated: HashMap<[u32; 8], ([f32; 3], [f32; 3])> = HashMap::new();
+  let key = |vertex: &Vertex| {
+    let mut key = [0; 8];
+    key[..3].copy_from_slice(&vector_key(vertex.position));
+    key[3..6].copy_from_slice(&vector_key(vertex.normal));
+    key[6..].copy_from_slice(&vector_key(vertex.tex_coords));
+    return key;
+  };
+
+  for triangle in vertices.chunks_exact(3) {
+    let edge1 = triangle[1].position.subtract(&triangle[0].position);
+    let edge2 = triangle[2].position.subtract(&triangle[0].position);
+    let uv1 = triangle[1].tex_coords.subtract(&triangle[0].tex_coords);
+    let uv2 = triangle[2].tex_coords.subtract(&triangle[0].tex_coords);
+
+    // Triangles without a texture mapping don't contribute a direction.
+    let determinant = uv1[0] * uv2[1] - uv2[0] * uv1[1];
+    if determinant.abs() <= f32::EPSILON {
+      continue;
+    }
+
+    let r = 1.0 / determinant;
+    let tangent = edge1.scale(uv2[1] * r).subtract(&edge2.scale(uv1[1] * r));
+    let bitangent = edge2.scale(uv1[0] * r).subtract(&edge1.scale(uv2[0] * r));
+
+    for vertex in triangle.iter() {
+      let sums = accumulated
+        .entry(key(vertex))
+        .or_insert(([0.0; 3], [0.0; 3]));
+      sums.0 = sums.0.add(&tangent);
+      sums.1 = sums.1.add(&bitangent);
+    }
+  }
+
+  for vertex in vertices.iter_mut() {
+    let (tangent, bitangent) = accumulated
+      .get(&key(vertex))
+      .copied()
+      .unwrap_or(([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]));
+    let normal = try_normalize(vertex.normal).unwrap_or([0.0, 0.0, 1.0]);
+
+    // Gram-Schmidt orthogonalization, falling back to any direction that's
+    // perpendicular to the normal.
+    let tangent =
+      try_normalize(tangent.subtract(&normal.scale(normal.dot(&tangent))))
+        .or_else(|| try_normalize(normal.cross(&[0.0, 1.0, 0.0])))
+        .or_else(|| try_normalize(normal.cross(&[1.0, 0.0, 0.0])))
+        .unwrap_or([1.0, 0.0, 0.0]);
+    let handedness = match normal.cross(&tangent).dot(&bitangent) < 0.0 {
+      true => -1.0,
+      false => 1.0,
+    };
+
+    vertex.tangent = [tangent[0], tangent[1], tangent[2], handedness];
+  }
+}
+
 // ---------------------------------- Mesh ------------------------------------
 
 /// Collection of vertices and indices that define a 3D object.
 #[derive(Debug)]
 pub struct Mesh {
   vertices: Vec<Vertex>,
+  indices: Option<MeshIndices>,
   attributes: Vec<VertexAttribute>,
+  submeshes: Vec<SubMesh>,
+  materials: Vec<MeshMaterial>,
 }
 
 impl Mesh {
@@ -26,10 +305,34 @@ impl Mesh {
     &self.vertices
   }
 
+  /// Gets the indices of the mesh, if the mesh is indexed.
+  pub fn indices(&self) -> Option<&MeshIndices> {
+    self.indices.as_ref()
+  }
+
+  /// The bounding box of the vertices, which can be used with `math::ray` to
+  /// pick meshes. Returns None if the mesh has no vertices.
+  pub fn bounds(&self) -> Option<Aabb> {
+    return Aabb::from_points(
+      self.vertices.iter().map(|vertex| vertex.position),
+    );
+  }
+
   /// Gets the attributes of the mesh.
   pub fn attributes(&self) -> &[VertexAttribute] {
     &self.attributes
   }
+
+  /// Gets the submeshes of the mesh. Empty unless the mesh was loaded from a
+  /// file containing multiple objects or materials.
+  pub fn submeshes(&self) -> &[SubMesh] {
+    &self.submeshes
+  }
+
+  /// Gets the materials referenced by the submeshes of the mesh.
+  pub fn materials(&self) -> &[MeshMaterial] {
+    &self.materials
+  }
 }
 
 // ------------------------------ MeshBuilder ---------------------------------
@@ -39,6 +342,10 @@ impl Mesh {
 pub struct MeshBuilder {
   capacity: usize,
   vertices: Vec<Vertex>,
+  indices: Option<Vec<u32>>,
+  indexed: bool,
+  smooth_angle: Option<f32>,
+  tangents: bool,
   attributes: Vec<VertexAttribute>,
 }
 
@@ -48,6 +355,10 @@ impl MeshBuilder {
     return Self {
       capacity: 0,
       vertices: Vec::new(),
+      indices: None,
+      indexed: false,
+      smooth_angle: None,
+      tangents: false,
       attributes: Vec::new(),
     };
   }
@@ -62,6 +373,8 @@ impl MeshBuilder {
         position: [0.0, 0.0, 0.0],
         normal: [0.0, 0.0, 0.0],
         color: [0.0, 0.0, 0.0],
+        tex_coords: [0.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
       },
     );
     return self;
@@ -73,6 +386,37 @@ impl MeshBuilder {
     return self;
   }
 
+  /// Specify the indices of the mesh, which index into the vertices that have
+  /// been added to the builder as a triangle list.
+  pub fn with_indices(&mut self, indices: Vec<u32>) -> &mut Self {
+    self.indices = Some(indices);
+    return self;
+  }
+
+  /// Deduplicate identical vertices when building the mesh and produce an
+  /// index buffer that reconstructs the original triangles, which can then
+  /// be drawn with `RenderCommand::DrawIndexed`.
+  pub fn with_indexing(&mut self) -> &mut Self {
+    self.indexed = true;
+    return self;
+  }
+
+  /// Generate normals for vertices that don't have one when building the
+  /// mesh. Faces sharing a vertex position are smoothed together when the
+  /// angle between them is at most `smooth_angle` radians, so an angle of 0
+  /// produces flat shading.
+  pub fn with_generated_normals(&mut self, smooth_angle: f32) -> &mut Self {
+    self.smooth_angle = Some(smooth_angle);
+    return self;
+  }
+
+  /// Generate tangents from the texture coordinates of the vertices when
+  /// building the mesh, for use with normal mapping.
+  pub fn with_tangents(&mut self) -> &mut Self {
+    self.tangents = true;
+    return self;
+  }
+
   /// Specify the attributes of the mesh. This is used to map the vertex data to
   /// the input of the vertex shader.
   pub fn with_attributes(
@@ -86,10 +430,63 @@ impl MeshBuilder {
   /// Builds a mesh from the vertices and indices that have been added to the
   /// builder and allocates the memory for the mesh on the GPU.
   pub fn build(&self) -> Mesh {
+    let (vertices, indices) =
+      self.process_vertices(self.vertices.clone(), self.indices.clone());
     return Mesh {
-      vertices: self.vertices.clone(),
+      vertices,
+      indices,
       attributes: self.attributes.clone(),
+      submeshes: vec![],
+      materials: vec![],
+    };
+  }
+
+  /// Generates normals & tangents when requested and then deduplicates the
+  /// vertices when indexing is enabled, remapping any existing indices to the
+  /// unique vertices.
+  fn process_vertices(
+    &self,
+    mut vertices: Vec<Vertex>,
+    mut indices: Option<Vec<u32>>,
+  ) -> (Vec<Vertex>, Option<MeshIndices>) {
+    let mut indexed = self.indexed;
+
+    if self.smooth_angle.is_some() || self.tangents {
+      // Generation works on triangle lists, so indexed vertices are expanded
+      // and then indexed again afterwards.
+      if let Some(triangle_indices) = indices.take() {
+        vertices = triangle_indices
+          .iter()
+          .map(|index| vertices[*index as usize])
+          .collect();
+        indexed = true;
+      }
+
+      if let Some(smooth_angle) = self.smooth_angle {
+        generate_normals(&mut vertices, smooth_angle);
+      }
+
+      if self.tangents {
+        generate_tangents(&mut vertices);
+      }
+    }
+
+    let (vertices, indices) = match (indexed, indices) {
+      (false, None) => return (vertices, None),
+      (false, Some(indices)) => (vertices, indices),
+      (true, None) => deduplicate_vertices(&vertices),
+      (true, Some(indices)) => {
+        let (unique_vertices, remapped) = deduplicate_vertices(&vertices);
+        let indices = indices
+          .into_iter()
+          .map(|index| remapped[index as usize])
+          .collect();
+        (unique_vertices, indices)
+      }
     };
+
+    let indices = MeshIndices::from_indices(indices, vertices.len());
+    return (vertices, Some(indices));
   }
 
   /// Builds a mesh from the vertices of an OBJ file. The mesh will have the same
@@ -98,22 +495,129 @@ impl MeshBuilder {
   pub fn build_from_obj(&self, file_path: &str) -> Mesh {
     let obj = load_textured_obj_from_file(file_path);
 
+    // The OBJ loader provides unique vertices & indices, which are expanded
+    // back into a triangle list before being indexed by the builder.
     let vertices = obj
-      .vertices
+      .indices
       .iter()
+      .map(|index| &obj.vertices[*index as usize])
       .map(|v| {
         return Vertex {
           position: v.position,
           normal: v.normal,
           color: [1.0, 1.0, 1.0],
+          tex_coords: [v.texture[0], 1.0 - v.texture[1]],
+          tangent: [0.0, 0.0, 0.0, 0.0],
         };
       })
       .collect::<Vec<Vertex>>();
+    let (vertices, indices) = self.process_vertices(vertices, None);
 
     // Returns a mesh with the given vertices with attributes for position,
     // normal, and color.
     return Mesh {
       vertices,
+      indices,
+      submeshes: vec![],
+      materials: vec![],
+      attributes: vec![
+        VertexAttribute {
+          location: 0,
+          offset: 0,
+          element: VertexElement {
+            format: ColorFormat::Rgb32Sfloat,
+            offset: 0,
+          },
+        },
+        VertexAttribute {
+          location: 1,
+          offset: 0,
+          element: VertexElement {
+            format: ColorFormat::Rgb32Sfloat,
+            offset: 12,
+          },
+        },
+        VertexAttribute {
+          location: 2,
+          offset: 0,
+          element: VertexElement {
+            format: ColorFormat::Rgb32Sfloat,
+            offset: 24,
+          },
+        },
+      ],
+    };
+  }
+
+  /// Builds a mesh from an OBJ file along with the MTL materials it
+  /// references. The mesh is split into a submesh per object and material,
+  /// vertices are colored with the diffuse color of their material, and
+  /// texture coordinates & tangents are provided at locations 3 and 4 in
+  /// addition to the attributes of `build_from_obj`.
+  pub fn build_from_obj_with_materials(
+    &self,
+    file_path: &str,
+  ) -> Result<Mesh, String> {
+    let model = load_obj_with_materials_from_file(file_path)?;
+    return Ok(self.build_from_obj_model(model));
+  }
+
+  /// Builds a mesh from the bytes of an OBJ file like
+  /// `build_from_obj_with_materials`, but reads the MTL libraries that it
+  /// references with the given function, such as from an asset pack.
+  /// Libraries & textures are resolved relative to the given directory.
+  pub fn build_from_obj_bytes_with_materials(
+    &self,
+    bytes: &[u8],
+    directory: &Path,
+    read_file: impl FnMut(&Path) -> Result<Vec<u8>, String>,
+  ) -> Result<Mesh, String> {
+    let model = load_obj_with_materials_from(bytes, directory, read_file)?;
+    return Ok(self.build_from_obj_model(model));
+  }
+
+  fn build_from_obj_model(&self, model: ObjModel) -> Mesh {
+    let materials = model
+      .materials
+      .into_iter()
+      .map(|material| MeshMaterial {
+        name: material.name,
+        diffuse_color: material.diffuse_color,
+        diffuse_texture: material.diffuse_texture,
+      })
+      .collect::<Vec<_>>();
+
+    let mut vertices = Vec::with_capacity(model.vertices.len());
+    let mut submeshes = Vec::with_capacity(model.submeshes.len());
+    for submesh in model.submeshes.into_iter() {
+      let color = submesh
+        .material
+        .map_or([1.0, 1.0, 1.0], |index| materials[index].diffuse_color);
+
+      vertices.extend(model.vertices[submesh.vertices.clone()].iter().map(
+        |vertex| Vertex {
+          position: vertex.position,
+          normal: vertex.normal,
+          color,
+          tex_coords: vertex.tex_coords,
+          tangent: [0.0, 0.0, 0.0, 0.0],
+        },
+      ));
+
+      submeshes.push(SubMesh {
+        name: submesh.name,
+        material: submesh.material,
+        vertices: submesh.vertices.start as u32..submesh.vertices.end as u32,
+      });
+    }
+
+    // Indices preserve the order of the vertices, so the vertex ranges of the
+    // submeshes are also their index ranges.
+    let (vertices, indices) = self.process_vertices(vertices, None);
+
+    return Mesh {
+      vertices,
+      indices,
       attributes: vec![
         VertexAttribute {
           location: 0,
@@ -139,7 +643,25 @@ impl MeshBuilder {
             offset: 24,
           },
         },
+        VertexAttribute {
+          location: 3,
+          offset: 0,
+          element: VertexElement {
+            format: ColorFormat::Rg32Sfloat,
+            offset: 36,
+          },
+        },
+        VertexAttribute {
+          location: 4,
+          offset: 0,
+          element: VertexElement {
+            format: ColorFormat::Rgba32Sfloat,
+            offset: 44,
+          },
+        },
       ],
+      submeshes,
+      materials,
     };
   }
 }
@@ -152,4 +674,108 @@ mod tests {
 
     assert_eq!(mesh.vertices.len(), 0);
   }
+
+  #[test]
+  fn mesh_indexing() {
+    use super::{
+      MeshBuilder,
+      MeshIndices,
+    };
+    use crate::render::vertex::VertexBuilder;
+
+    let vertex = |x: f32, y: f32| {
+      return VertexBuilder::new().with_position([x, y, 0.0]).build();
+    };
+
+    // Two triangles forming a quad share two of their vertices.
+    let mut builder = MeshBuilder::new();
+    for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)] {
+      builder.with_vertex(vertex(x, y));
+    }
+    for (x, y) in [(1.0, 1.0), (0.0, 1.0), (0.0, 0.0)] {
+      builder.with_vertex(vertex(x, y));
+    }
+
+    assert!(builder.build().indices().is_none());
+
+    let mesh = builder.with_indexing().build();
+    assert_eq!(mesh.vertices().len(), 4);
+    let bounds = mesh.bounds().unwrap();
+    assert_eq!((bounds.min, bounds.max), ([0.0; 3], [1.0, 1.0, 0.0]));
+    assert_eq!(
+      mesh.indices(),
+      Some(&MeshIndices::U16(vec![0, 1, 2, 2, 3, 0]))
+    );
+
+    // Explicit indices are remapped onto the unique vertices.
+    let mesh = builder.with_indices(vec![5, 4, 3]).build();
+    assert_eq!(mesh.vertices().len(), 4);
+    assert_eq!(mesh.indices().unwrap().to_vec(), vec![0, 3, 2]);
+
+    assert!(matches!(
+      MeshIndices::from_indices(vec![0], 65_536),
+      MeshIndices::U16(_)
+    ));
+    assert!(matches!(
+      MeshIndices::from_indices(vec![0], 65_537),
+      MeshIndices::U32(_)
+    ));
+  }
+
+  #[test]
+  fn mesh_normal_and_tangent_generation() {
+    use super::MeshBuilder;
+    use crate::render::vertex::VertexBuilder;
+
+    let vertex = |position: [f32; 3], tex_coords: [f32; 2]| {
+      return VertexBuilder::new()
+        .with_position(position)
+        .with_tex_coords(tex_coords)
+        .build();
+    };
+
+    // A quad in the xy plane & a triangle folded 90 degrees along its right
+    // edge.
+    let mut builder = MeshBuilder::new();
+    builder
+      .with_vertex(vertex([0.0, 0.0, 0.0], [0.0, 0.0]))
+      .with_vertex(vertex([1.0, 0.0, 0.0], [1.0, 0.0]))
+      .with_vertex(vertex([1.0, 1.0, 0.0], [1.0, 1.0]))
+      .with_vertex(vertex([1.0, 1.0, 0.0], [1.0, 1.0]))
+      .with_vertex(vertex([0.0, 1.0, 0.0], [0.0, 1.0]))
+      .with_vertex(vertex([0.0, 0.0, 0.0], [0.0, 0.0]))
+      .with_vertex(vertex([1.0, 0.0, 0.0], [0.0, 0.0]))
+      .with_vertex(vertex([1.0, 0.0, -1.0], [1.0, 0.0]))
+      .with_vertex(vertex([1.0, 1.0, 0.0], [0.0, 1.0]));
+
+    let flat = builder.with_generated_normals(0.1).with_tangents().build();
+    let vertices = flat.vertices();
+    for vertex in vertices[..6].iter() {
+      assert_eq!(vertex.normal, [0.0, 0.0, 1.0]);
+      assert_eq!(vertex.tangent, [1.0, 0.0, 0.0, 1.0]);
+    }
+    assert_eq!(vertices[6].normal, [1.0, 0.0, 0.0]);
+    assert_eq!(vertices[6].tangent, [0.0, 0.0, -1.0, 1.0]);
+
+    // Smoothing across the fold averages the normals of both faces.
+    let smooth = builder.with_generated_normals(std::f32::consts::PI).build();
+    let normal = smooth.vertices()[1].normal;
+    let expected = std::f32::consts::FRAC_1_SQRT_2;
+    assert!((normal[0] - expected).abs() < 1e-5, "{:?}", normal);
+    assert!((normal[2] - expected).abs() < 1e-5, "{:?}", normal);
+    assert_eq!(smooth.vertices()[0].normal, [0.0, 0.0, 1.0]);
+
+    // Vertices that already have normals are left untouched.
+    let mut builder = MeshBuilder::new();
+    for position in [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]] {
+      builder.with_vertex(
+        VertexBuilder::new()
+          .with_position(position)
+          .with_normal([0.0, 1.0, 0.0])
+          .build(),
+      );
+    }
+    let mesh = builder.with_generated_normals(0.0).build();
+    assert_eq!(mesh.vertices()[0].normal, [0.0, 1.0, 0.0]);
+  }
 }
@@ -2,18 +2,37 @@
 //! windowing.
 
 // Module Exports
+pub mod atlas;
 pub mod buffer;
+pub mod cache;
+mod capture;
+pub mod color;
 pub mod command;
+pub mod counters;
+pub mod debug_draw;
+pub mod lighting;
+pub mod material;
+pub mod memory;
 pub mod mesh;
+pub mod particles;
 pub mod pipeline;
+pub mod recording;
+pub mod render2d;
 pub mod render_pass;
+mod resource;
 pub mod shader;
+pub mod terminal;
+pub mod text;
+mod transfer;
+mod validation;
 pub mod vertex;
 pub mod viewport;
 pub mod window;
 
 use std::{
-  mem::swap,
+  cell::RefCell,
+  collections::HashMap,
+  path::PathBuf,
   rc::Rc,
 };
 
@@ -21,6 +40,24 @@ use std::{
 /// vertex buffers. They denote the size of the color channels and the number of
 /// channels being used.
 pub use lambda_platform::gfx::surface::ColorFormat;
+pub use lambda_platform::gfx::{
+  api::{
+    Backend,
+    RENDERING_BACKEND,
+  },
+  gpu::{
+    DeviceType,
+    Features,
+    GpuInfo,
+    Limits,
+    PowerPreference,
+  },
+  surface::{
+    ColorSpace,
+    PresentMode,
+    PresentModePreference,
+  },
+};
 use lambda_platform::gfx::{
   command::{
     Command,
@@ -31,11 +68,38 @@ use lambda_platform::gfx::{
   framebuffer::FramebufferBuilder,
   surface::SwapchainBuilder,
 };
+pub use validation::CommandError;
 
 use self::{
+  buffer::{
+    Buffer,
+    Usage,
+  },
+  cache::{
+    content_hash,
+    DiskCache,
+  },
+  capture::FrameCapture,
   command::RenderCommand,
+  counters::FrameCounters,
+  memory::{
+    AllocationId,
+    MemoryCategory,
+    MemoryReport,
+    MemoryTracker,
+  },
   pipeline::RenderPipeline,
+  recording::CommandRecording,
   render_pass::RenderPass,
+  resource::ResourcePool,
+  transfer::{
+    Readback,
+    StagingRing,
+  },
+};
+pub use self::{
+  resource::ResourceId,
+  transfer::ReadCallback,
 };
 
 /// A RenderContext is a localized rendering context that can be used to render
@@ -43,14 +107,43 @@ use self::{
 pub struct RenderContextBuilder {
   name: String,
   render_timeout: u64,
+  backend: Option<Backend>,
+  power_preference: Option<PowerPreference>,
+  force_fallback_adapter: bool,
+  required_features: Features,
+  optional_features: Features,
+  present_mode: Option<PresentModePreference>,
+  frame_latency: Option<u32>,
+  surface_format: Option<ColorFormat>,
+  color_space: Option<ColorSpace>,
+  staging_buffer_size: usize,
+  command_validation: bool,
+  cache_directory: Option<PathBuf>,
+  memory_budget: Option<u64>,
 }
 
+/// The name of the pipeline cache's entry within the disk cache.
+const PIPELINE_CACHE_ENTRY: &str = "pipelines";
+
 impl RenderContextBuilder {
   /// Create a new localized RenderContext with the given name.
   pub fn new(name: &str) -> Self {
     return Self {
       name: name.to_string(),
       render_timeout: 1_000_000_000,
+      backend: None,
+      power_preference: None,
+      force_fallback_adapter: false,
+      required_features: Features::empty(),
+      optional_features: Features::empty(),
+      present_mode: None,
+      frame_latency: None,
+      surface_format: None,
+      color_space: None,
+      staging_buffer_size: 8 * 1024 * 1024,
+      command_validation: cfg!(debug_assertions),
+      cache_directory: None,
+      memory_budget: None,
     };
   }
 
@@ -60,15 +153,143 @@ impl RenderContextBuilder {
     return self;
   }
 
+  /// The rendering backend to render with. Backends are compiled in through
+  /// the `with-*` features, so a warning is logged and the compiled backend
+  /// is used when it differs from the requested backend.
+  pub fn with_backend(mut self, backend: Backend) -> Self {
+    self.backend = Some(backend);
+    return self;
+  }
+
+  /// Prefer GPUs that match the power preference on machines with more than
+  /// one GPU. The first GPU that can render is used by default.
+  pub fn with_power_preference(
+    mut self,
+    power_preference: PowerPreference,
+  ) -> Self {
+    self.power_preference = Some(power_preference);
+    return self;
+  }
+
+  /// Only render with a software adapter.
+  pub fn with_force_fallback_adapter(mut self) -> Self {
+    self.force_fallback_adapter = true;
+    return self;
+  }
+
+  /// GPU features that rendering can't happen without, such as
+  /// `Features::SAMPLER_ANISOTROPY`. Building fails if no GPU supports them.
+  pub fn with_required_features(mut self, features: Features) -> Self {
+    self.required_features = features;
+    return self;
+  }
+
+  /// GPU features that are enabled when they're supported. The features that
+  /// were enabled are available through `RenderContext::features`.
+  pub fn with_optional_features(mut self, features: Features) -> Self {
+    self.optional_features = features;
+    return self;
+  }
+
+  /// The present mode to render with, falling back to FIFO when the surface
+  /// doesn't support it. The surface's preferred mode is used by default.
+  pub fn with_present_mode(
+    mut self,
+    present_mode: PresentModePreference,
+  ) -> Self {
+    self.present_mode = Some(present_mode);
+    return self;
+  }
+
+  /// The number of frames that can be queued ahead of the frame being
+  /// presented, within the swapchain image counts the surface supports.
+  pub fn with_frame_latency(mut self, frame_latency: u32) -> Self {
+    self.frame_latency = Some(frame_latency);
+    return self;
+  }
+
+  /// The format to present with when the surface supports it, such as
+  /// `ColorFormat::Bgra8Unorm` for linear output.
+  pub fn with_surface_format(mut self, surface_format: ColorFormat) -> Self {
+    self.surface_format = Some(surface_format);
+    return self;
+  }
+
+  /// The color space to present in when no supported surface format was
+  /// set, such as `ColorSpace::Hdr10` for HDR output on capable displays. A
+  /// warning is logged & the surface's preferred format is used when the
+  /// color space isn't supported.
+  pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+    self.color_space = Some(color_space);
+    return self;
+  }
+
+  /// The size in bytes of the CPU visible memory that `write_buffer` stages
+  /// writes in, half of which can be written every frame. Defaults to 8 MiB.
+  pub fn with_staging_buffer_size(
+    mut self,
+    staging_buffer_size: usize,
+  ) -> Self {
+    self.staging_buffer_size = staging_buffer_size;
+    return self;
+  }
+
+  /// Validate the commands of every frame before they're recorded, logging
+  /// an error for every invalid command & dropping the frame. Validation is
+  /// enabled by default in debug builds.
+  pub fn with_command_validation(mut self, command_validation: bool) -> Self {
+    self.command_validation = command_validation;
+    return self;
+  }
+
+  /// Persist the pipeline cache within the directory across runs, which is
+  /// also available through `RenderContext::cache` for caching shaders. The
+  /// pipeline cache is discarded when the GPU or rendering API changes.
+  pub fn with_cache_directory(mut self, directory: impl Into<PathBuf>) -> Self {
+    self.cache_directory = Some(directory.into());
+    return self;
+  }
+
+  /// Log a warning with a memory report once the memory allocated on the GPU
+  /// exceeds the budget in bytes. A warning is always logged once the GPU's
+  /// own memory is exceeded.
+  pub fn with_memory_budget(mut self, memory_budget: u64) -> Self {
+    self.memory_budget = Some(memory_budget);
+    return self;
+  }
+
   /// Builds a RenderContext and injects it into the application window.
-  /// Currently only supports building a Rendering Context utilizing the
-  /// systems primary GPU.
+  /// The GPU is chosen from the adapters that can render to the window using
+  /// the required features & power preference & fallback settings.
   pub fn build(self, window: &window::Window) -> RenderContext {
     let RenderContextBuilder {
       name,
       render_timeout,
+      backend,
+      power_preference,
+      force_fallback_adapter,
+      required_features,
+      optional_features,
+      present_mode,
+      frame_latency,
+      surface_format,
+      color_space,
+      staging_buffer_size,
+      command_validation,
+      cache_directory,
+      memory_budget,
     } = self;
 
+    if let Some(backend) =
+      backend.filter(|backend| *backend != RENDERING_BACKEND)
+    {
+      logging::warn!(
+        "{:?} was requested, but lambda was compiled with {:?}.",
+        backend,
+        RENDERING_BACKEND
+      );
+    }
+
     let mut instance = internal::InstanceBuilder::new()
       .build::<internal::RenderBackend>(name.as_str());
     let surface = Rc::new(
@@ -76,8 +297,17 @@ impl RenderContextBuilder {
     );
 
     // Build a GPU with a Graphical Render queue that can render to our surface.
-    let mut gpu = internal::GpuBuilder::new()
+    let mut gpu_builder = internal::GpuBuilder::new()
       .with_render_queue_type(internal::RenderQueueType::Graphical)
+      .with_required_features(required_features)
+      .with_optional_features(optional_features);
+    if let Some(power_preference) = power_preference {
+      gpu_builder = gpu_builder.with_power_preference(power_preference);
+    }
+    if force_fallback_adapter {
+      gpu_builder = gpu_builder.with_force_fallback_adapter();
+    }
+    let mut gpu = gpu_builder
       .build(&mut instance, Some(&surface))
       .expect("Failed to build a GPU with a graphical render queue.");
 
@@ -92,7 +322,28 @@ impl RenderContextBuilder {
     let render_semaphore =
       internal::RenderSemaphoreBuilder::new().build(&mut gpu);
 
-    return RenderContext {
+    let pipeline_cache = cache_directory.and_then(|directory| {
+      let cache = DiskCache::new(directory);
+      let mut builder = internal::PipelineCacheBuilder::new();
+      if let Some(data) =
+        cache.load(PIPELINE_CACHE_ENTRY, pipeline_cache_producer(&gpu))
+      {
+        builder = builder.with_data(data);
+      }
+      return match builder.build(&gpu) {
+        Ok(pipeline_cache) => Some((cache, pipeline_cache)),
+        Err(error) => {
+          logging::warn!("{}", error);
+          None
+        }
+      };
+    });
+
+    let memory = RefCell::new(MemoryTracker::new(
+      memory_budget,
+      gpu.device_local_memory(),
+    ));
+    let render_context = RenderContext {
       name,
       instance,
       gpu,
@@ -101,9 +352,44 @@ impl RenderContextBuilder {
       submission_fence: Some(submission_fence),
       render_semaphore: Some(render_semaphore),
       command_pool: Some(command_pool),
-      render_passes: vec![],
-      render_pipelines: vec![],
+      render_passes: ResourcePool::new(),
+      render_pipelines: ResourcePool::new(),
+      present_mode,
+      frame_latency,
+      surface_format,
+      color_space,
+      buffers: ResourcePool::new(),
+      staging_buffer_size,
+      staging: None,
+      staging_allocation: None,
+      readback: Readback::default(),
+      resource_owner: None,
+      retired: vec![],
+      frame_in_flight: false,
+      command_validation,
+      capture: FrameCapture::default(),
+      pipeline_cache,
+      depth_stencil: None,
+      depth_stencil_allocation: None,
+      color_attachments: HashMap::new(),
+      suspended: false,
+      frame_counters: FrameCounters::default(),
+      last_frame_counters: FrameCounters::default(),
+      memory,
+      recording: None,
     };
+
+    let chosen_format = render_context.surface_format();
+    if let Some(color_space) = color_space
+      .filter(|color_space| ColorSpace::of(chosen_format) != Some(*color_space))
+    {
+      logging::warn!(
+        "{:?} isn't supported by the surface, presenting with {:?} instead.",
+        color_space,
+        chosen_format
+      );
+    }
+    return render_context;
   }
 }
 
@@ -119,33 +405,246 @@ pub struct RenderContext {
     Option<internal::RenderSubmissionFence<internal::RenderBackend>>,
   render_semaphore: Option<internal::RenderSemaphore<internal::RenderBackend>>,
   command_pool: Option<internal::CommandPool<internal::RenderBackend>>,
-  render_passes: Vec<RenderPass>,
-  render_pipelines: Vec<RenderPipeline>,
+  render_passes: ResourcePool<RenderPass>,
+  render_pipelines: ResourcePool<RenderPipeline>,
+  present_mode: Option<PresentModePreference>,
+  frame_latency: Option<u32>,
+  surface_format: Option<ColorFormat>,
+  color_space: Option<ColorSpace>,
+  buffers: ResourcePool<Buffer>,
+  staging_buffer_size: usize,
+  staging: Option<StagingRing>,
+  staging_allocation: Option<AllocationId>,
+  readback: Readback,
+  resource_owner: Option<String>,
+  retired: Vec<RetiredResource>,
+  frame_in_flight: bool,
+  command_validation: bool,
+  capture: FrameCapture,
+  pipeline_cache:
+    Option<(DiskCache, internal::PipelineCache<internal::RenderBackend>)>,
+  depth_stencil:
+    Option<Rc<internal::DepthStencilImage<internal::RenderBackend>>>,
+  depth_stencil_allocation: Option<AllocationId>,
+  color_attachments: HashMap<
+    String,
+    (
+      Rc<internal::ColorImage<internal::RenderBackend>>,
+      AllocationId,
+    ),
+  >,
+  suspended: bool,
+  frame_counters: FrameCounters,
+  last_frame_counters: FrameCounters,
+  memory: RefCell<MemoryTracker>,
+  recording: Option<CommandRecording>,
 }
 
-pub type ResourceId = usize;
+/// A destroyed resource that the GPU may still be using. Retired resources
+/// are destroyed once the frame that was in flight has finished.
+enum RetiredResource {
+  RenderPass(RenderPass),
+  RenderPipeline(RenderPipeline),
+  Buffer(Buffer),
+}
+
+/// Information about every adapter available to the compiled rendering
+/// backend, which can be used to decide on a power preference before
+/// building a RenderContext.
+pub fn enumerate_adapters() -> Vec<GpuInfo> {
+  let instance = internal::InstanceBuilder::new()
+    .build::<internal::RenderBackend>("lambda-adapters");
+  return instance.adapters();
+}
+
+/// A summary of the rendering API, GPU, and surface configuration that a
+/// RenderContext selected when it was built.
+#[derive(Clone, Debug)]
+pub struct RenderContextInfo {
+  pub rendering_api: &'static str,
+  pub gpu: GpuInfo,
+  pub features: Features,
+  pub surface_format: ColorFormat,
+  pub present_mode: PresentMode,
+}
 
 impl RenderContext {
   /// Permanently transfer a render pipeline to the render context in exchange
   /// for a resource ID that you can use in render commands.
   pub fn attach_pipeline(&mut self, pipeline: RenderPipeline) -> ResourceId {
-    let index = self.render_pipelines.len();
-    self.render_pipelines.push(pipeline);
-    return index;
+    return self
+      .render_pipelines
+      .insert(pipeline, self.resource_owner.clone());
   }
 
   /// Permanently transfer a render pipeline to the render context in exchange
   /// for a resource ID that you can use in render commands.
   pub fn attach_render_pass(&mut self, render_pass: RenderPass) -> ResourceId {
-    let index = self.render_passes.len();
-    self.render_passes.push(render_pass);
-    return index;
+    return self
+      .render_passes
+      .insert(render_pass, self.resource_owner.clone());
+  }
+
+  /// Permanently transfer a buffer to the render context in exchange for a
+  /// resource ID that can be written to & read from.
+  pub fn attach_buffer(&mut self, buffer: Buffer) -> ResourceId {
+    return self.buffers.insert(buffer, self.resource_owner.clone());
+  }
+
+  /// Get the buffer with the resource ID that was provided upon attachment.
+  pub fn get_buffer(&self, id: ResourceId) -> &Buffer {
+    return self
+      .buffers
+      .get(id)
+      .unwrap_or_else(|| panic!("Buffer {} isn't attached.", id));
+  }
+
+  /// Destroys the render pipeline & its buffers once the GPU has finished
+  /// using them. The resource ID is invalid from then on.
+  pub fn destroy_render_pipeline(
+    &mut self,
+    id: ResourceId,
+  ) -> Result<(), String> {
+    let pipeline = self
+      .render_pipelines
+      .remove(id)
+      .ok_or_else(|| format!("Render pipeline {} isn't attached.", id))?;
+    self.retired.push(RetiredResource::RenderPipeline(pipeline));
+    return Ok(());
+  }
+
+  /// Destroys the render pass once the GPU has finished using it. The
+  /// resource ID is invalid from then on.
+  pub fn destroy_render_pass(&mut self, id: ResourceId) -> Result<(), String> {
+    let render_pass = self
+      .render_passes
+      .remove(id)
+      .ok_or_else(|| format!("Render pass {} isn't attached.", id))?;
+    self.retired.push(RetiredResource::RenderPass(render_pass));
+    return Ok(());
+  }
+
+  /// Destroys the buffer once the GPU has finished using it. The resource ID
+  /// is invalid from then on.
+  pub fn destroy_buffer(&mut self, id: ResourceId) -> Result<(), String> {
+    let buffer = self
+      .buffers
+      .remove(id)
+      .ok_or_else(|| format!("Buffer {} isn't attached.", id))?;
+    self.retired.push(RetiredResource::Buffer(buffer));
+    return Ok(());
+  }
+
+  /// Records the owner of the resources attached from now on, such as the
+  /// name of the component being attached, so that they can be destroyed
+  /// along with the owner.
+  pub fn set_resource_owner(&mut self, owner: Option<&str>) {
+    self.resource_owner = owner.map(str::to_string);
+  }
+
+  /// Destroys every resource that was attached by the owner, returning the
+  /// number of resources destroyed.
+  pub fn destroy_resources_owned_by(&mut self, owner: &str) -> usize {
+    let pipelines = self.render_pipelines.owned_by(owner);
+    let render_passes = self.render_passes.owned_by(owner);
+    let buffers = self.buffers.owned_by(owner);
+    let destroyed = pipelines.len() + render_passes.len() + buffers.len();
+
+    for id in pipelines {
+      self.destroy_render_pipeline(id).ok();
+    }
+    for id in render_passes {
+      self.destroy_render_pass(id).ok();
+    }
+    for id in buffers {
+      self.destroy_buffer(id).ok();
+    }
+    return destroyed;
+  }
+
+  /// Writes the bytes into the buffer at the offset. Writes are staged & then
+  /// copied into the buffer at the start of the next frame, so the buffer
+  /// must have been built with `Usage::TRANSFER_DST`. Every write to a buffer
+  /// within a frame is batched into a single copy.
+  pub fn write_buffer(
+    &mut self,
+    id: ResourceId,
+    offset: usize,
+    bytes: &[u8],
+  ) -> Result<(), String> {
+    let buffer = self.buffer_with_usage(id, Usage::TRANSFER_DST)?;
+    transfer::validate_range(offset, bytes.len(), buffer.length())?;
+    if bytes.is_empty() {
+      return Ok(());
+    }
+
+    return self.staging_ring()?.stage(buffer, offset, bytes);
+  }
+
+  /// Reads length bytes from the buffer at the offset. The bytes are copied
+  /// at the end of the next frame & handed to the callback once that frame
+  /// has finished rendering, so the buffer must have been built with
+  /// `Usage::TRANSFER_SRC`.
+  pub fn read_buffer(
+    &mut self,
+    id: ResourceId,
+    offset: usize,
+    length: usize,
+    callback: impl FnOnce(Result<Vec<u8>, String>) + 'static,
+  ) -> Result<(), String> {
+    let buffer = self.buffer_with_usage(id, Usage::TRANSFER_SRC)?;
+    transfer::validate_range(offset, length, buffer.length())?;
+    return self.readback.queue(
+      &mut self.gpu,
+      self.memory.get_mut(),
+      buffer,
+      offset,
+      length,
+      Box::new(callback),
+    );
   }
 
   /// destroys the RenderContext and all associated resources.
   pub fn destroy(mut self) {
     logging::debug!("{} will now start destroying resources.", self.name);
 
+    // Resources are only destroyed once the GPU has finished the last frame.
+    if self.frame_in_flight {
+      self
+        .submission_fence
+        .as_mut()
+        .expect("Failed to get the submission fence.")
+        .block_until_ready(&mut self.gpu, None);
+      self.frame_in_flight = false;
+    }
+    if self.capture.is_capturing() {
+      self.gpu.stop_capture();
+    }
+    if let Some((cache, pipeline_cache)) = self.pipeline_cache.take() {
+      let stored = pipeline_cache.data(&self.gpu).and_then(|data| {
+        return cache.store(
+          PIPELINE_CACHE_ENTRY,
+          pipeline_cache_producer(&self.gpu),
+          &data,
+        );
+      });
+      if let Err(error) = stored {
+        logging::warn!("{}", error);
+      }
+      pipeline_cache.destroy(&self.gpu);
+    }
+    std::mem::take(&mut self.readback)
+      .destroy(&self.gpu, self.memory.get_mut());
+    self.destroy_retired_resources();
+    self.destroy_depth_stencil_image();
+    self.destroy_color_attachment_images();
+    if let Some(allocation) = self.staging_allocation.take() {
+      self.untrack_allocation(allocation);
+    }
+    if let Some(staging) = self.staging.take() {
+      staging.destroy(&self.gpu);
+    }
+
     // Destroy the submission fence and rendering semaphore.
     self
       .submission_fence
@@ -173,21 +672,20 @@ impl RenderContext {
       .destroy(&self.gpu);
 
     // Destroy render passes.
-    let mut render_passes = vec![];
-    swap(&mut self.render_passes, &mut render_passes);
-
-    for render_pass in render_passes {
+    for render_pass in self.render_passes.drain() {
       render_pass.destroy(&self);
     }
 
     // Destroy render pipelines.
-    let mut render_pipelines = vec![];
-    swap(&mut self.render_pipelines, &mut render_pipelines);
-
-    for render_pipeline in render_pipelines {
+    for render_pipeline in self.render_pipelines.drain() {
       render_pipeline.destroy(&self);
     }
 
+    // Destroy buffers.
+    for buffer in self.buffers.drain() {
+      buffer.destroy(&self);
+    }
+
     // Takes the inner surface and destroys it.
     let mut surface = Rc::try_unwrap(self.surface)
       .expect("Couldn't obtain the surface from the context.");
@@ -199,13 +697,23 @@ impl RenderContext {
   pub fn allocate_and_get_frame_buffer(
     &mut self,
     render_pass: &internal::RenderPass<internal::RenderBackend>,
+    color_images: &[Rc<internal::ColorImage<internal::RenderBackend>>],
   ) -> Rc<lambda_platform::gfx::framebuffer::Framebuffer<internal::RenderBackend>>
   {
-    let frame_buffer = FramebufferBuilder::new().build(
-      &mut self.gpu,
-      &render_pass,
-      self.surface.as_ref(),
-    );
+    let mut builder = FramebufferBuilder::new();
+    for color_image in color_images {
+      builder = builder.with_color_image(color_image.as_ref());
+    }
+    if render_pass.has_depth_stencil() {
+      builder = builder.with_depth_stencil_image(
+        self
+          .depth_stencil_image()
+          .unwrap_or_else(|error| panic!("{}", error))
+          .as_ref(),
+      );
+    }
+    let frame_buffer =
+      builder.build(&mut self.gpu, render_pass, self.surface.as_ref());
 
     // TODO(vmarcella): Update the framebuffer allocation to not be so hacky.
     // FBAs can only be allocated once a render pass has begun, but must be
@@ -214,33 +722,94 @@ impl RenderContext {
     return self.frame_buffer.as_ref().unwrap().clone();
   }
 
+  /// Checks the commands for pass pairing, pipeline & vertex buffer
+  /// bindings, push constant ranges, and draw ranges that exceed the bound
+  /// buffers, returning an error for every invalid command.
+  pub fn validate_commands(
+    &self,
+    commands: &[RenderCommand],
+  ) -> Result<(), Vec<CommandError>> {
+    let errors = validation::validate_commands(commands, self);
+    return match errors.is_empty() {
+      true => Ok(()),
+      false => Err(errors),
+    };
+  }
+
   /// Allocates a command buffer and records commands to the GPU. This is the
   /// primary entry point for submitting commands to the GPU and where rendering
   /// will occur.
-  pub fn render(&mut self, commands: Vec<RenderCommand>) {
+  pub fn render(&mut self, mut commands: Vec<RenderCommand>) {
+    if self.suspended {
+      logging::trace!("Dropping the frame's render commands while suspended.");
+      return;
+    }
+
+    // Fractional viewports are resolved before the commands are recorded, so
+    // that recordings play back at the pixels that were rendered.
+    if let Some((width, height)) = self.surface.size() {
+      for command in commands.iter_mut() {
+        command.resolve_viewports(width, height);
+      }
+    }
+
+    if let Some(recording) = self.recording.as_mut() {
+      recording.record(&commands);
+    }
+
+    // Commands that use destroyed resources would record invalid commands,
+    // so the frame is dropped instead.
+    if self.command_validation {
+      if let Err(errors) = self.validate_commands(&commands) {
+        for error in errors.iter() {
+          logging::error!("{}", error);
+        }
+        logging::error!(
+          "Dropping the frame's render commands after {} failed validation.",
+          errors.len()
+        );
+        return;
+      }
+    } else if let Some(error) = commands
+      .iter()
+      .find_map(|command| command.validate(self).err())
+    {
+      logging::error!("Dropping the frame's render commands: {}", error);
+      return;
+    }
+
     let (width, height) = self
       .surface
       .size()
       .expect("Surface has no size configured.");
 
-    let swapchain = SwapchainBuilder::new()
-      .with_size(width, height)
-      .build(&self.gpu, &self.surface);
-
-    if self.surface.needs_swapchain() {
-      Rc::get_mut(&mut self.surface)
-        .expect("Failed to get mutable reference to surface.")
-        .apply_swapchain(&self.gpu, swapchain, 1_000_000_000)
-        .expect("Failed to apply the swapchain to the surface.");
-    }
+    self.configure_swapchain(width, height);
 
     self
       .submission_fence
       .as_mut()
       .expect("Failed to get the submission fence.")
       .block_until_ready(&mut self.gpu, None);
+    self.frame_in_flight = false;
+    self.readback.complete(&self.gpu, self.memory.get_mut());
+    self.destroy_retired_resources();
 
-    let platform_command_list = commands
+    // Vertex buffers are updated once the GPU has finished reading from them
+    // for the previous frame.
+    let mut recorded_commands = Vec::with_capacity(commands.len());
+    for command in commands.into_iter() {
+      self.frame_counters.record(&command);
+      match command {
+        RenderCommand::UpdateVertexBuffer {
+          pipeline,
+          buffer,
+          vertices,
+        } => self.update_vertex_buffer(pipeline, buffer, &vertices),
+        command => recorded_commands.push(command),
+      }
+    }
+
+    let platform_command_list = recorded_commands
       .into_iter()
       .map(|command| command.into_platform_command(self))
       .collect();
@@ -259,8 +828,19 @@ impl RenderContext {
     // Start recording commands, issue the high level render commands
     // that came from an application, and then submit the commands to the GPU
     // for rendering.
+    // Staged writes are copied before the application's commands so that
+    // they can be used this frame, while reads are copied after them.
+    let staged_copies = self
+      .staging
+      .as_mut()
+      .map(|staging| staging.take_copies(&self.gpu))
+      .unwrap_or_default();
+    let readback_copies = self.readback.take_copies();
+
     command_buffer.issue_command(PlatformRenderCommand::BeginRecording);
+    command_buffer.issue_commands(staged_copies);
     command_buffer.issue_commands(platform_command_list);
+    command_buffer.issue_commands(readback_copies);
     command_buffer.issue_command(PlatformRenderCommand::EndRecording);
 
     self.gpu.submit_command_buffer(
@@ -268,15 +848,19 @@ impl RenderContext {
       vec![self.render_semaphore.as_ref().unwrap()],
       self.submission_fence.as_mut().unwrap(),
     );
+    self.frame_in_flight = true;
 
-    self
-      .gpu
-      .render_to_surface(
-        Rc::get_mut(&mut self.surface)
-          .expect("Failed to obtain a surface to render on."),
-        self.render_semaphore.as_mut().unwrap(),
-      )
-      .expect("Failed to render to the surface");
+    {
+      let _present = logging::span!("present");
+      self
+        .gpu
+        .render_to_surface(
+          Rc::get_mut(&mut self.surface)
+            .expect("Failed to obtain a surface to render on."),
+          self.render_semaphore.as_mut().unwrap(),
+        )
+        .expect("Failed to render to the surface");
+    }
 
     // Destroys the frame buffer after the commands have been submitted and the
     // frame buffer is no longer needed.
@@ -290,8 +874,364 @@ impl RenderContext {
     }
   }
 
+  /// Reconfigures the surface for the new size of the window. Sizes received
+  /// while suspended are ignored, since the surface is reconfigured at the
+  /// window's size once it resumes.
   pub fn resize(&mut self, width: u32, height: u32) {
-    let swapchain = SwapchainBuilder::new()
+    if self.suspended {
+      return;
+    }
+    self.configure_swapchain(width, height);
+  }
+
+  /// Releases the swapchain of the surface once the window can no longer be
+  /// presented to, such as when it's occluded or the application is sent to
+  /// the background. Frames are dropped until the render context resumes.
+  pub fn suspend(&mut self) {
+    if self.suspended {
+      return;
+    }
+    if self.frame_in_flight {
+      self
+        .submission_fence
+        .as_mut()
+        .expect("Failed to get the submission fence.")
+        .block_until_ready(&mut self.gpu, None);
+      self.frame_in_flight = false;
+    }
+    self.destroy_depth_stencil_image();
+    self.destroy_color_attachment_images();
+    Rc::get_mut(&mut self.surface)
+      .expect("Failed to get mutable reference to surface.")
+      .remove_swapchain(&self.gpu);
+    self.suspended = true;
+    logging::debug!("{} was suspended.", self.name);
+  }
+
+  /// Recreates the surface for the window once it can be presented to again,
+  /// since the window's previous surface may no longer be valid.
+  pub fn resume(&mut self, window: &window::Window) {
+    if !self.suspended {
+      return;
+    }
+    let surface = Rc::new(
+      internal::SurfaceBuilder::new()
+        .build(&self.instance, window.window_handle()),
+    );
+    Rc::try_unwrap(std::mem::replace(&mut self.surface, surface))
+      .expect("Couldn't obtain the suspended surface from the context.")
+      .destroy(&self.instance);
+    self.suspended = false;
+
+    let (width, height) = window.dimensions();
+    self.configure_swapchain(width, height);
+    logging::debug!("{} was resumed.", self.name);
+  }
+
+  /// Whether or not the render context is suspended & dropping frames.
+  pub fn is_suspended(&self) -> bool {
+    return self.suspended;
+  }
+
+  /// Changes the present mode & reconfigures the surface with it, falling
+  /// back to FIFO when the surface doesn't support the mode.
+  pub fn set_present_mode(&mut self, present_mode: PresentModePreference) {
+    self.present_mode = Some(present_mode);
+    if self.suspended {
+      return;
+    }
+    if let Some((width, height)) = self.surface.size() {
+      self.configure_swapchain(width, height);
+    }
+  }
+
+  /// The disk cache within the directory set by
+  /// `RenderContextBuilder::with_cache_directory`, such as for
+  /// `ShaderBuilder::with_cache`.
+  pub fn cache(&self) -> Option<&DiskCache> {
+    return self.pipeline_cache.as_ref().map(|(cache, _)| cache);
+  }
+
+  /// Captures the next frame with a graphics debugger such as RenderDoc when
+  /// one is attached. Frames are delimited by `begin_frame` & `end_frame`,
+  /// which the application runtime calls around the rendering of every frame.
+  pub fn trigger_capture(&mut self) {
+    logging::info!("Capturing the next frame.");
+    self.capture.trigger();
+  }
+
+  /// Begins capturing every frame rendered until `end_capture` is called.
+  pub fn begin_capture(&mut self) {
+    if self.capture.begin() {
+      self.gpu.start_capture();
+    }
+  }
+
+  /// Ends the capture begun by `begin_capture`.
+  pub fn end_capture(&mut self) {
+    if self.capture.end() {
+      self.gpu.stop_capture();
+    }
+  }
+
+  /// Whether or not the work submitted to the GPU is being captured.
+  pub fn is_capturing(&self) -> bool {
+    return self.capture.is_capturing();
+  }
+
+  /// Marks the start of a frame, which may be rendered over many calls to
+  /// `render`. Resets the frame counters & starts the capture triggered by
+  /// `trigger_capture`.
+  pub fn begin_frame(&mut self) {
+    self.frame_counters = FrameCounters::default();
+    if let Some(recording) = self.recording.as_mut() {
+      recording.begin_frame();
+    }
+    if self.capture.begin_frame() {
+      self.gpu.start_capture();
+    }
+  }
+
+  /// Marks the end of the frame begun by `begin_frame`.
+  pub fn end_frame(&mut self) {
+    self.last_frame_counters = self.frame_counters;
+    if self.capture.end_frame() {
+      self.gpu.stop_capture();
+      logging::info!("Captured the frame.");
+    }
+  }
+
+  /// Starts recording the commands that are rendered in every frame,
+  /// discarding any recording that was already in progress.
+  pub fn start_recording(&mut self) {
+    self.recording = Some(CommandRecording::new());
+  }
+
+  /// Stops recording and returns the commands that were rendered since the
+  /// recording started.
+  pub fn stop_recording(&mut self) -> CommandRecording {
+    return self.recording.take().unwrap_or_default();
+  }
+
+  /// The work that the render commands of the last whole frame submitted to
+  /// the GPU.
+  pub fn frame_counters(&self) -> FrameCounters {
+    return self.last_frame_counters;
+  }
+
+  /// The memory that's currently allocated on the GPU by the render context
+  /// & the buffers built with it.
+  pub fn memory_report(&self) -> MemoryReport {
+    return self.memory.borrow().report();
+  }
+
+  /// The GPU features that were enabled for the render context.
+  pub fn features(&self) -> Features {
+    return self.gpu.features();
+  }
+
+  /// Whether or not every one of the GPU features was enabled.
+  pub fn supports(&self, features: Features) -> bool {
+    return self.gpu.supports(features);
+  }
+
+  /// The resource limits of the GPU.
+  pub fn limits(&self) -> &Limits {
+    return self.gpu.limits();
+  }
+
+  /// The format that images are presented to the surface with. Render passes
+  /// built by lambda render in this format.
+  pub fn surface_format(&self) -> ColorFormat {
+    return self
+      .swapchain_builder()
+      .build(&self.gpu, &self.surface)
+      .format();
+  }
+
+  /// Every format that the surface can be presented to with.
+  pub fn supported_surface_formats(&self) -> Vec<ColorFormat> {
+    return self.surface.supported_formats(&self.gpu);
+  }
+
+  /// The format of the depth & stencil image that render passes built with a
+  /// stencil render into, or None if the GPU can't render with a stencil.
+  pub fn depth_stencil_format(&self) -> Option<ColorFormat> {
+    return lambda_platform::gfx::image::depth_stencil_format(&self.gpu);
+  }
+
+  /// Whether or not the GPU can render into color attachments of the format.
+  pub fn supports_color_attachment_format(&self, format: ColorFormat) -> bool {
+    return lambda_platform::gfx::image::supports_color_attachment(
+      &self.gpu, format,
+    );
+  }
+
+  /// Information about the rendering API, GPU, and swapchain configuration
+  /// used by the render context.
+  pub fn info(&self) -> RenderContextInfo {
+    let swapchain = self.swapchain_builder().build(&self.gpu, &self.surface);
+    return RenderContextInfo {
+      rendering_api: lambda_platform::gfx::api::RENDERING_API_NAME,
+      gpu: self.gpu.info(),
+      features: self.gpu.features(),
+      surface_format: swapchain.format(),
+      present_mode: swapchain.present_mode(),
+    };
+  }
+
+  /// The internal buffer of an attached buffer, if it was built with the
+  /// usage.
+  fn buffer_with_usage(
+    &self,
+    id: ResourceId,
+    usage: Usage,
+  ) -> Result<Rc<internal::Buffer<internal::RenderBackend>>, String> {
+    let buffer = self
+      .buffers
+      .get(id)
+      .ok_or_else(|| format!("Buffer {} isn't attached.", id))?
+      .internal_buffer_rc();
+    if !buffer.usage().contains(usage) {
+      return Err(format!(
+        "Buffer {} must be built with Usage::{:?} to be transferred.",
+        id, usage
+      ));
+    }
+    return Ok(buffer);
+  }
+
+  /// Destroys the retired resources. The GPU must have finished every frame
+  /// that could be using them.
+  fn destroy_retired_resources(&mut self) {
+    for resource in std::mem::take(&mut self.retired) {
+      match resource {
+        RetiredResource::RenderPass(render_pass) => render_pass.destroy(self),
+        RetiredResource::RenderPipeline(pipeline) => pipeline.destroy(self),
+        RetiredResource::Buffer(buffer) => buffer.destroy(self),
+      }
+    }
+  }
+
+  /// The depth & stencil image that's shared by every render pass with a
+  /// stencil, which is allocated at the size of the surface and reallocated
+  /// once the surface is resized. Only called once the previous frame has
+  /// finished, since that frame may have rendered into the image.
+  fn depth_stencil_image(
+    &mut self,
+  ) -> Result<Rc<internal::DepthStencilImage<internal::RenderBackend>>, String>
+  {
+    let size = self.surface.size().unwrap_or((1, 1));
+    if let Some(image) = self.depth_stencil.as_ref() {
+      if image.size() == size {
+        return Ok(image.clone());
+      }
+    }
+
+    self.destroy_depth_stencil_image();
+    let image = Rc::new(
+      internal::DepthStencilImageBuilder::new()
+        .with_size(size.0, size.1)
+        .build(&self.gpu)?,
+    );
+    self.depth_stencil_allocation = Some(self.track_allocation(
+      MemoryCategory::DepthStencil,
+      "depth & stencil",
+      image.memory_size(),
+    ));
+    self.depth_stencil = Some(image.clone());
+    return Ok(image);
+  }
+
+  fn destroy_depth_stencil_image(&mut self) {
+    if let Some(allocation) = self.depth_stencil_allocation.take() {
+      self.untrack_allocation(allocation);
+    }
+    if let Some(image) = self.depth_stencil.take() {
+      match Rc::try_unwrap(image) {
+        Ok(image) => image.destroy(&self.gpu),
+        Err(_) => logging::warn!(
+          "The depth & stencil image is still in use and can't be destroyed."
+        ),
+      }
+    }
+  }
+
+  /// The image of the named color attachment, which is shared by every
+  /// render pass with an attachment of that name. Images are allocated at
+  /// the size of the surface and reallocated once the surface is resized or
+  /// a render pass uses the name with another format.
+  pub(super) fn color_attachment_image(
+    &mut self,
+    attachment: &render_pass::ColorAttachment,
+  ) -> Result<Rc<internal::ColorImage<internal::RenderBackend>>, String> {
+    let size = self.surface.size().unwrap_or((1, 1));
+    if let Some((image, _)) = self.color_attachments.get(attachment.name()) {
+      if image.size() == size && image.format() == attachment.format() {
+        return Ok(image.clone());
+      }
+    }
+
+    self.destroy_color_attachment_image(attachment.name());
+    let image = Rc::new(
+      internal::ColorImageBuilder::new()
+        .with_size(size.0, size.1)
+        .with_format(attachment.format())
+        .build(&self.gpu)?,
+    );
+    let allocation = self.track_allocation(
+      MemoryCategory::ColorAttachment,
+      attachment.name(),
+      image.memory_size(),
+    );
+    self
+      .color_attachments
+      .insert(attachment.name().to_string(), (image.clone(), allocation));
+    return Ok(image);
+  }
+
+  fn destroy_color_attachment_image(&mut self, name: &str) {
+    let Some((image, allocation)) = self.color_attachments.remove(name) else {
+      return;
+    };
+    self.untrack_allocation(allocation);
+    match Rc::try_unwrap(image) {
+      Ok(image) => image.destroy(&self.gpu),
+      Err(_) => logging::warn!(
+        "The {} color attachment's image is still in use and can't be \
+         destroyed.",
+        name
+      ),
+    }
+  }
+
+  fn destroy_color_attachment_images(&mut self) {
+    let names = self.color_attachments.keys().cloned().collect::<Vec<_>>();
+    for name in names {
+      self.destroy_color_attachment_image(&name);
+    }
+  }
+
+  fn swapchain_builder(&self) -> SwapchainBuilder {
+    let mut builder = SwapchainBuilder::new();
+    if let Some(present_mode) = self.present_mode {
+      builder = builder.with_present_mode(present_mode);
+    }
+    if let Some(frame_latency) = self.frame_latency {
+      builder = builder.with_frame_latency(frame_latency);
+    }
+    if let Some(surface_format) = self.surface_format {
+      builder = builder.with_format(surface_format);
+    }
+    if let Some(color_space) = self.color_space {
+      builder = builder.with_color_space(color_space);
+    }
+    return builder;
+  }
+
+  fn configure_swapchain(&mut self, width: u32, height: u32) {
+    let swapchain = self
+      .swapchain_builder()
       .with_size(width, height)
       .build(&self.gpu, &self.surface);
 
@@ -303,16 +1243,82 @@ impl RenderContext {
     }
   }
 
+  /// Updates the vertex buffer of the pipeline. Vertex buffers built with
+  /// `Usage::TRANSFER_DST` are staged & copied alongside the frame's other
+  /// writes, while the rest are written directly.
+  fn update_vertex_buffer(
+    &mut self,
+    pipeline: ResourceId,
+    buffer: u32,
+    vertices: &[vertex::Vertex],
+  ) {
+    let result = match self
+      .render_pipelines
+      .get(pipeline)
+      .and_then(|pipeline| pipeline.buffers().get(buffer as usize))
+      .map(|vertex_buffer| vertex_buffer.internal_buffer_rc())
+    {
+      Some(vertex_buffer)
+        if vertex_buffer.usage().contains(Usage::TRANSFER_DST) =>
+      {
+        let bytes = vertex::Vertex::to_bytes(vertices);
+        transfer::validate_range(0, bytes.len(), vertex_buffer.length())
+          .and_then(|_| {
+            return self.staging_ring()?.stage(vertex_buffer, 0, &bytes);
+          })
+      }
+      Some(vertex_buffer) => vertex_buffer
+        .write(&self.gpu, vertices)
+        .map_err(|error| error.to_string()),
+      None => Err("The pipeline doesn't have the vertex buffer.".to_string()),
+    };
+
+    if let Err(error) = result {
+      logging::error!(
+        "Failed to update vertex buffer {} of pipeline {}: {}",
+        buffer,
+        pipeline,
+        error
+      );
+    }
+  }
+
+  /// The ring that writes are staged in, which is allocated by the first
+  /// write.
+  fn staging_ring(&mut self) -> Result<&mut StagingRing, String> {
+    if self.staging.is_none() {
+      self.staging =
+        Some(StagingRing::new(&mut self.gpu, self.staging_buffer_size)?);
+      self.staging_allocation = Some(self.track_allocation(
+        MemoryCategory::StagingBuffer,
+        "staging ring",
+        self.staging_buffer_size as u64,
+      ));
+    }
+    return Ok(
+      self
+        .staging
+        .as_mut()
+        .expect("The staging buffer wasn't allocated."),
+    );
+  }
+
   /// Get the render pass with the resource ID that was provided upon
   /// attachment.
   pub fn get_render_pass(&self, id: ResourceId) -> &RenderPass {
-    return &self.render_passes[id];
+    return self
+      .render_passes
+      .get(id)
+      .unwrap_or_else(|| panic!("Render pass {} isn't attached.", id));
   }
 
   /// Get the render pipeline with the resource ID that was provided upon
   /// attachment.
   pub fn get_render_pipeline(&mut self, id: ResourceId) -> &RenderPipeline {
-    return &self.render_pipelines[id];
+    return self
+      .render_pipelines
+      .get(id)
+      .unwrap_or_else(|| panic!("Render pipeline {} isn't attached.", id));
   }
 }
 
@@ -334,6 +1340,84 @@ impl RenderContext {
   ) -> Rc<lambda_platform::gfx::surface::Surface<internal::RenderBackend>> {
     return self.surface.clone();
   }
+
+  pub(super) fn internal_pipeline_cache(
+    &self,
+  ) -> Option<&internal::PipelineCache<internal::RenderBackend>> {
+    return self.pipeline_cache.as_ref().map(|(_, cache)| cache);
+  }
+
+  /// Records memory allocated on the GPU for the memory report.
+  pub(super) fn track_allocation(
+    &self,
+    category: MemoryCategory,
+    label: &str,
+    bytes: u64,
+  ) -> AllocationId {
+    return self.memory.borrow_mut().track(category, label, bytes);
+  }
+
+  pub(super) fn untrack_allocation(&self, allocation: AllocationId) {
+    self.memory.borrow_mut().untrack(allocation);
+  }
+}
+
+/// Identifies the rendering API & GPU that pipeline caches are created by.
+fn pipeline_cache_producer(
+  gpu: &internal::Gpu<internal::RenderBackend>,
+) -> u64 {
+  let info = gpu.info();
+  let producer = format!(
+    "{}\0{}\0{}\0{}",
+    lambda_platform::gfx::api::RENDERING_API_NAME,
+    info.name,
+    info.vendor,
+    info.device
+  );
+  return content_hash(producer.as_bytes());
+}
+
+impl validation::CommandResources for RenderContext {
+  fn has_render_pass(&self, id: ResourceId) -> bool {
+    return self.render_passes.contains(id);
+  }
+
+  fn render_pass_has_stencil(&self, id: ResourceId) -> bool {
+    return self
+      .render_passes
+      .get(id)
+      .is_some_and(|render_pass| render_pass.has_stencil());
+  }
+
+  fn render_pass_color_formats(&self, id: ResourceId) -> Vec<ColorFormat> {
+    return self
+      .render_passes
+      .get(id)
+      .map(|render_pass| render_pass.color_formats())
+      .unwrap_or_default();
+  }
+
+  fn pipeline_layout(
+    &self,
+    id: ResourceId,
+  ) -> Option<validation::PipelineLayout> {
+    let pipeline = self.render_pipelines.get(id)?;
+    return Some(validation::PipelineLayout {
+      push_constants: pipeline.push_constants().to_vec(),
+      vertex_counts: pipeline
+        .buffers()
+        .iter()
+        .map(|buffer| buffer.element_count())
+        .collect(),
+      index_counts: pipeline
+        .buffers()
+        .iter()
+        .map(|buffer| buffer.index_count())
+        .collect(),
+      stencil_pass: pipeline.has_stencil_pass(),
+      color_formats: pipeline.color_formats().to_vec(),
+    });
+  }
 }
 
 type PlatformRenderCommand = Command<internal::RenderBackend>;
@@ -345,6 +1429,7 @@ pub(crate) mod internal {
 
   pub use lambda_platform::{
     gfx::{
+      buffer::Buffer,
       command::{
         CommandBuffer,
         CommandBufferBuilder,
@@ -363,7 +1448,17 @@ pub(crate) mod internal {
         GpuBuilder,
         RenderQueueType,
       },
-      pipeline::RenderPipelineBuilder,
+      image::{
+        ColorImage,
+        ColorImageBuilder,
+        DepthStencilImage,
+        DepthStencilImageBuilder,
+      },
+      pipeline::{
+        PipelineCache,
+        PipelineCacheBuilder,
+        RenderPipelineBuilder,
+      },
       render_pass::{
         RenderPass,
         RenderPassBuilder,
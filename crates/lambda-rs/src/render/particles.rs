@@ -0,0 +1,572 @@
+//! CPU simulated particles. Emitters spawn particles at a rate within a cone,
+//! simulate them on the CPU, and batch every particle into camera facing
+//! quads so that each emitter is rendered with a single draw.
+
+use std::{
+  ops::Range,
+  time::Duration,
+};
+
+use super::{
+  command::RenderCommand,
+  mesh::{
+    Mesh,
+    MeshBuilder,
+  },
+  vertex::{
+    Vertex,
+    VertexAttribute,
+    VertexElement,
+  },
+  ColorFormat,
+  ResourceId,
+};
+use crate::math::{
+  interpolation::{
+    lerp,
+    lerp_vector,
+  },
+  vector::Vector,
+};
+
+/// The number of vertices that every particle is drawn with.
+pub const VERTICES_PER_PARTICLE: usize = 6;
+
+// --------------------------------- CURVES ------------------------------------
+
+/// Values keyed over the lifetime of a particle from 0 (spawned) to 1 (dead)
+/// that are linearly interpolated between keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve<T> {
+  keys: Vec<(f32, T)>,
+}
+
+impl<T: Copy> Curve<T> {
+  /// A curve with the same value over the entire lifetime.
+  pub fn constant(value: T) -> Self {
+    return Self {
+      keys: vec![(0.0, value)],
+    };
+  }
+
+  /// A curve from the start value to the end value.
+  pub fn linear(start: T, end: T) -> Self {
+    return Self {
+      keys: vec![(0.0, start), (1.0, end)],
+    };
+  }
+
+  /// Adds a key at the time between 0 and 1, keeping keys sorted by time.
+  pub fn with_key(mut self, time: f32, value: T) -> Self {
+    let time = time.clamp(0.0, 1.0);
+    let index = self.keys.partition_point(|(key, _)| *key <= time);
+    self.keys.insert(index, (time, value));
+    return self;
+  }
+
+  /// The keys surrounding the time and how far the time is between them.
+  fn segment(&self, time: f32) -> (T, T, f32) {
+    let time = time.clamp(0.0, 1.0);
+    let next = self.keys.partition_point(|(key, _)| *key <= time);
+    if next == 0 {
+      return (self.keys[0].1, self.keys[0].1, 0.0);
+    }
+    if next == self.keys.len() {
+      let last = self.keys[next - 1].1;
+      return (last, last, 0.0);
+    }
+
+    let (start_time, start) = self.keys[next - 1];
+    let (end_time, end) = self.keys[next];
+    return (start, end, (time - start_time) / (end_time - start_time));
+  }
+}
+
+impl Curve<f32> {
+  pub fn sample(&self, time: f32) -> f32 {
+    let (start, end, t) = self.segment(time);
+    return lerp(start, end, t);
+  }
+}
+
+impl Curve<[f32; 3]> {
+  pub fn sample(&self, time: f32) -> [f32; 3] {
+    let (start, end, t) = self.segment(time);
+    return lerp_vector(&start, &end, t);
+  }
+}
+
+// -------------------------------- EMITTER ------------------------------------
+
+/// Configures how an emitter spawns particles and how they change over their
+/// lifetime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmitterConfig {
+  position: [f32; 3],
+  spawn_rate: f32,
+  max_particles: usize,
+  lifetime: Range<f32>,
+  speed: Range<f32>,
+  direction: [f32; 3],
+  cone_angle: f32,
+  acceleration: [f32; 3],
+  size: Curve<f32>,
+  color: Curve<[f32; 3]>,
+  seed: u64,
+}
+
+impl EmitterConfig {
+  /// Creates a config that emits 10 white particles per second upwards, which
+  /// live for a second.
+  pub fn new() -> Self {
+    return Self {
+      position: [0.0, 0.0, 0.0],
+      spawn_rate: 10.0,
+      max_particles: 256,
+      lifetime: 1.0..1.0,
+      speed: 1.0..1.0,
+      direction: [0.0, 1.0, 0.0],
+      cone_angle: 0.0,
+      acceleration: [0.0, 0.0, 0.0],
+      size: Curve::constant(0.05),
+      color: Curve::constant([1.0, 1.0, 1.0]),
+      seed: 0x5eed,
+    };
+  }
+
+  /// Where particles are spawned.
+  pub fn with_position(mut self, position: [f32; 3]) -> Self {
+    self.position = position;
+    return self;
+  }
+
+  /// The number of particles spawned per second.
+  pub fn with_spawn_rate(mut self, spawn_rate: f32) -> Self {
+    self.spawn_rate = spawn_rate.max(0.0);
+    return self;
+  }
+
+  /// The most particles that can be alive at once, which determines the size
+  /// of the emitter's vertex buffer.
+  pub fn with_max_particles(mut self, max_particles: usize) -> Self {
+    self.max_particles = max_particles;
+    return self;
+  }
+
+  /// The range of seconds that particles live for.
+  pub fn with_lifetime(mut self, lifetime: Range<f32>) -> Self {
+    self.lifetime = lifetime;
+    return self;
+  }
+
+  /// The range of speeds that particles are spawned with.
+  pub fn with_speed(mut self, speed: Range<f32>) -> Self {
+    self.speed = speed;
+    return self;
+  }
+
+  /// Particles are spawned moving in a random direction within the cone
+  /// around the direction, whose angle from its center to its edge is in
+  /// radians. A zero direction is treated as upwards.
+  pub fn with_velocity_cone(
+    mut self,
+    direction: [f32; 3],
+    cone_angle: f32,
+  ) -> Self {
+    self.direction = match direction.length() > f32::EPSILON {
+      true => direction.normalize(),
+      false => [0.0, 1.0, 0.0],
+    };
+    self.cone_angle = cone_angle.abs();
+    return self;
+  }
+
+  /// The acceleration applied to every particle, such as gravity.
+  pub fn with_acceleration(mut self, acceleration: [f32; 3]) -> Self {
+    self.acceleration = acceleration;
+    return self;
+  }
+
+  /// The width & height of particles over their lifetime.
+  pub fn with_size(mut self, size: Curve<f32>) -> Self {
+    self.size = size;
+    return self;
+  }
+
+  /// The color of particles over their lifetime.
+  pub fn with_color(mut self, color: Curve<[f32; 3]>) -> Self {
+    self.color = color;
+    return self;
+  }
+
+  /// The seed of the random numbers used to spawn particles.
+  pub fn with_seed(mut self, seed: u64) -> Self {
+    self.seed = seed;
+    return self;
+  }
+}
+
+/// A simulated particle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Particle {
+  pub position: [f32; 3],
+  pub velocity: [f32; 3],
+  pub age: f32,
+  pub lifetime: f32,
+}
+
+impl Particle {
+  /// How far the particle is through its lifetime from 0 to 1.
+  pub fn progress(&self) -> f32 {
+    if self.lifetime <= 0.0 {
+      return 1.0;
+    }
+    return (self.age / self.lifetime).min(1.0);
+  }
+}
+
+/// Spawns & simulates particles from a config.
+pub struct ParticleEmitter {
+  config: EmitterConfig,
+  particles: Vec<Particle>,
+  spawn_debt: f32,
+  emitting: bool,
+  random: u64,
+}
+
+impl ParticleEmitter {
+  pub fn new(config: EmitterConfig) -> Self {
+    let random = config.seed;
+    return Self {
+      particles: Vec::with_capacity(config.max_particles),
+      config,
+      spawn_debt: 0.0,
+      emitting: true,
+      random,
+    };
+  }
+
+  pub fn config(&self) -> &EmitterConfig {
+    return &self.config;
+  }
+
+  /// Moves where new particles are spawned.
+  pub fn set_position(&mut self, position: [f32; 3]) {
+    self.config.position = position;
+  }
+
+  /// Stops or resumes spawning particles at the spawn rate. Particles that
+  /// are alive continue to be simulated.
+  pub fn set_emitting(&mut self, emitting: bool) {
+    self.emitting = emitting;
+  }
+
+  /// The particles that are alive.
+  pub fn particles(&self) -> &[Particle] {
+    return &self.particles;
+  }
+
+  /// A random number between 0 and 1 from splitmix64.
+  fn random(&mut self) -> f32 {
+    self.random = self.random.wrapping_add(0x9e3779b97f4a7c15);
+    let mut value = self.random;
+    value = (value ^ (value >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    value = (value ^ (value >> 27)).wrapping_mul(0x94d049bb133111eb);
+    value ^= value >> 31;
+    return (value >> 40) as f32 / (1u64 << 24) as f32;
+  }
+
+  fn random_between(&mut self, range: &Range<f32>) -> f32 {
+    let t = self.random();
+    return lerp(range.start, range.end, t);
+  }
+
+  /// A random direction within the velocity cone.
+  fn random_direction(&mut self) -> [f32; 3] {
+    let direction = self.config.direction;
+
+    // Any vector that isn't parallel to the direction forms a basis with it.
+    let helper = match direction[1].abs() < 0.99 {
+      true => [0.0, 1.0, 0.0],
+      false => [1.0, 0.0, 0.0],
+    };
+    let tangent = direction.cross(&helper).normalize();
+    let bitangent = direction.cross(&tangent);
+
+    // Directions are distributed uniformly over the cap of the cone.
+    let cos_angle = lerp(1.0, self.config.cone_angle.cos(), self.random());
+    let sin_angle = (1.0 - cos_angle * cos_angle).max(0.0).sqrt();
+    let around = self.random() * std::f32::consts::TAU;
+
+    return direction
+      .scale(cos_angle)
+      .add(&tangent.scale(sin_angle * around.cos()))
+      .add(&bitangent.scale(sin_angle * around.sin()));
+  }
+
+  /// Immediately spawns up to count particles, limited by the maximum number
+  /// of particles.
+  pub fn burst(&mut self, count: usize) {
+    let count = count.min(self.config.max_particles - self.particles.len());
+    for _ in 0..count {
+      let lifetime = self.random_between(&self.config.lifetime.clone());
+      let speed = self.random_between(&self.config.speed.clone());
+      let velocity = self.random_direction().scale(speed);
+      self.particles.push(Particle {
+        position: self.config.position,
+        velocity,
+        age: 0.0,
+        lifetime,
+      });
+    }
+  }
+
+  /// Ages & moves every particle, removes the particles that have died, and
+  /// spawns new particles at the spawn rate.
+  pub fn update(&mut self, last_frame: &Duration) {
+    let seconds = last_frame.as_secs_f32();
+    let acceleration = self.config.acceleration;
+
+    for particle in self.particles.iter_mut() {
+      particle.age += seconds;
+      particle.velocity = particle.velocity.add(&acceleration.scale(seconds));
+      particle.position =
+        particle.position.add(&particle.velocity.scale(seconds));
+    }
+    self
+      .particles
+      .retain(|particle| particle.age < particle.lifetime);
+
+    if self.emitting {
+      self.spawn_debt += self.config.spawn_rate * seconds;
+      let count = self.spawn_debt.floor();
+      self.spawn_debt -= count;
+      self.burst(count as usize);
+    }
+  }
+
+  /// The vertices of every particle as quads that face the camera, whose
+  /// right & up directions are given in the same space as the particles.
+  pub fn billboard_vertices(
+    &self,
+    camera_right: [f32; 3],
+    camera_up: [f32; 3],
+  ) -> Vec<Vertex> {
+    let normal = camera_right.cross(&camera_up);
+    let mut vertices =
+      Vec::with_capacity(self.particles.len() * VERTICES_PER_PARTICLE);
+
+    for particle in self.particles.iter() {
+      let progress = particle.progress();
+      let half_size = self.config.size.sample(progress) / 2.0;
+      let color = self.config.color.sample(progress);
+      let right = camera_right.scale(half_size);
+      let up = camera_up.scale(half_size);
+
+      let corner = |right_sign: f32, up_sign: f32| {
+        return Vertex {
+          position: particle
+            .position
+            .add(&right.scale(right_sign))
+            .add(&up.scale(up_sign)),
+          normal,
+          color,
+          tex_coords: [(right_sign + 1.0) / 2.0, (1.0 - up_sign) / 2.0],
+          tangent: [0.0, 0.0, 0.0, 0.0],
+        };
+      };
+
+      let (bottom_left, bottom_right) = (corner(-1.0, -1.0), corner(1.0, -1.0));
+      let (top_left, top_right) = (corner(-1.0, 1.0), corner(1.0, 1.0));
+      vertices.extend([
+        bottom_left,
+        bottom_right,
+        top_right,
+        bottom_left,
+        top_right,
+        top_left,
+      ]);
+    }
+    return vertices;
+  }
+
+  /// Builds a mesh with room for the vertices of the maximum number of
+  /// particles, whose buffer is updated every frame by `draw_commands`.
+  pub fn build_mesh(&self) -> Mesh {
+    let mut mesh_builder = MeshBuilder::new();
+    let empty = Vertex {
+      position: [0.0; 3],
+      normal: [0.0; 3],
+      color: [0.0; 3],
+      tex_coords: [0.0; 2],
+      tangent: [0.0; 4],
+    };
+    for _ in 0..self.config.max_particles.max(1) * VERTICES_PER_PARTICLE {
+      mesh_builder.with_vertex(empty);
+    }
+
+    mesh_builder.with_attributes(vec![
+      VertexAttribute {
+        location: 0,
+        offset: 0,
+        element: VertexElement {
+          format: ColorFormat::Rgb32Sfloat,
+          offset: 0,
+        },
+      },
+      VertexAttribute {
+        location: 1,
+        offset: 0,
+        element: VertexElement {
+          format: ColorFormat::Rgb32Sfloat,
+          offset: 12,
+        },
+      },
+      VertexAttribute {
+        location: 2,
+        offset: 0,
+        element: VertexElement {
+          format: ColorFormat::Rgb32Sfloat,
+          offset: 24,
+        },
+      },
+    ]);
+    return mesh_builder.build();
+  }
+
+  /// Commands that upload the particles into the buffer built from
+  /// `build_mesh` and draw all of them at once. The buffer must be attached
+  /// to the pipeline at the given index.
+  pub fn draw_commands(
+    &self,
+    pipeline: ResourceId,
+    buffer: u32,
+    camera_right: [f32; 3],
+    camera_up: [f32; 3],
+  ) -> Vec<RenderCommand> {
+    if self.particles.is_empty() {
+      return vec![];
+    }
+
+    let vertices = self.billboard_vertices(camera_right, camera_up);
+    let vertex_count = vertices.len() as u32;
+    return vec![
+      RenderCommand::UpdateVertexBuffer {
+        pipeline,
+        buffer,
+        vertices,
+      },
+      RenderCommand::BindVertexBuffer { pipeline, buffer },
+      RenderCommand::Draw {
+        vertices: 0..vertex_count,
+      },
+    ];
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::{
+    Curve,
+    EmitterConfig,
+    ParticleEmitter,
+    VERTICES_PER_PARTICLE,
+  };
+  use crate::{
+    assert_approximately_equal,
+    math::vector::Vector,
+    render::{
+      command::RenderCommand,
+      ResourceId,
+    },
+  };
+
+  #[test]
+  fn curves_interpolate_between_keys() {
+    let size = Curve::linear(1.0, 0.0).with_key(0.5, 2.0);
+    assert_eq!(size.sample(-1.0), 1.0);
+    assert_eq!(size.sample(0.25), 1.5);
+    assert_eq!(size.sample(0.75), 1.0);
+    assert_eq!(size.sample(2.0), 0.0);
+
+    let color = Curve::linear([1.0, 0.0, 0.0], [0.0, 0.0, 1.0]);
+    assert_eq!(color.sample(0.5), [0.5, 0.0, 0.5]);
+    assert_eq!(Curve::constant(3.0).sample(0.7), 3.0);
+  }
+
+  #[test]
+  fn emitters_spawn_and_simulate_particles() {
+    let config = EmitterConfig::new()
+      .with_spawn_rate(10.0)
+      .with_max_particles(8)
+      .with_lifetime(1.0..1.0)
+      .with_speed(2.0..2.0)
+      .with_velocity_cone([0.0, 1.0, 0.0], 0.5)
+      .with_acceleration([0.0, -1.0, 0.0]);
+    let mut emitter = ParticleEmitter::new(config);
+
+    emitter.update(&Duration::from_millis(500));
+    assert_eq!(emitter.particles().len(), 5);
+    for particle in emitter.particles() {
+      assert_approximately_equal!(particle.velocity.length(), 2.0, 1e-5);
+      assert!(particle.velocity[1] >= 2.0 * 0.5_f32.cos() - 1e-5);
+    }
+
+    // Particles are limited to the maximum & die once they're a second old.
+    emitter.update(&Duration::from_millis(200));
+    assert_eq!(emitter.particles().len(), 7);
+    emitter.update(&Duration::from_millis(200));
+    assert_eq!(emitter.particles().len(), 8);
+    emitter.set_emitting(false);
+    emitter.update(&Duration::from_secs(1));
+    assert!(emitter.particles().is_empty());
+
+    emitter.burst(100);
+    assert_eq!(emitter.particles().len(), 8);
+    emitter.update(&Duration::from_millis(100));
+    let particle = emitter.particles()[0];
+    assert_approximately_equal!(particle.progress(), 0.1, 1e-5);
+    assert!(particle.position[1] > 0.0);
+  }
+
+  #[test]
+  fn particles_are_batched_into_billboards() {
+    let config = EmitterConfig::new()
+      .with_max_particles(4)
+      .with_speed(0.0..0.0)
+      .with_size(Curve::constant(2.0))
+      .with_color(Curve::constant([1.0, 0.5, 0.0]));
+    let mut emitter = ParticleEmitter::new(config);
+    assert!(emitter
+      .draw_commands(ResourceId::new(0, 0), 0, [1.0, 0.0, 0.0], [0.0, 1.0, 0.0])
+      .is_empty());
+    assert_eq!(
+      emitter.build_mesh().vertices().len(),
+      4 * VERTICES_PER_PARTICLE
+    );
+
+    emitter.burst(2);
+    let vertices = emitter.billboard_vertices([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]);
+    assert_eq!(vertices.len(), 2 * VERTICES_PER_PARTICLE);
+    assert_eq!(vertices[0].position, [-1.0, -1.0, 0.0]);
+    assert_eq!(vertices[2].position, [1.0, 1.0, 0.0]);
+    assert_eq!(vertices[0].normal, [0.0, 0.0, 1.0]);
+    assert_eq!(vertices[0].color, [1.0, 0.5, 0.0]);
+
+    let commands = emitter.draw_commands(
+      ResourceId::new(3, 0),
+      1,
+      [1.0, 0.0, 0.0],
+      [0.0, 1.0, 0.0],
+    );
+    assert_eq!(commands.len(), 3);
+    match &commands[2] {
+      RenderCommand::Draw { vertices } => assert_eq!(*vertices, 0..12),
+      command => panic!("Expected a draw, got {:?}", command),
+    }
+  }
+}
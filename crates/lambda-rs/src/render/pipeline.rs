@@ -22,9 +22,24 @@ pub struct RenderPipeline {
     >,
   >,
   buffers: Vec<Rc<Buffer>>,
+  push_constants: Vec<PushConstantUpload>,
+  stencil: Option<StencilState>,
+  has_stencil_pass: bool,
+  color_formats: Vec<super::ColorFormat>,
 }
 
 impl RenderPipeline {
+  /// The stencil state the pipeline was built with, if it uses the stencil.
+  pub fn stencil(&self) -> Option<&StencilState> {
+    return self.stencil.as_ref();
+  }
+
+  /// Whether or not the pipeline was built with a render pass that has a
+  /// stencil, which it must be used within.
+  pub fn has_stencil_pass(&self) -> bool {
+    return self.has_stencil_pass;
+  }
+
   /// Destroy the render pipeline with the render context that created it.
   pub fn destroy(self, render_context: &RenderContext) {
     logging::trace!("Destroying render pipeline");
@@ -48,6 +63,16 @@ impl RenderPipeline {
     return &self.buffers;
   }
 
+  pub(super) fn push_constants(&self) -> &[PushConstantUpload] {
+    return &self.push_constants;
+  }
+
+  /// The formats of the color attachments after the surface of the render
+  /// pass that the pipeline was built with.
+  pub(super) fn color_formats(&self) -> &[super::ColorFormat] {
+    return &self.color_formats;
+  }
+
   pub(super) fn into_platform_render_pipeline(
     &self,
   ) -> Rc<lambda_platform::gfx::pipeline::RenderPipeline<RenderBackend>> {
@@ -55,16 +80,117 @@ impl RenderPipeline {
   }
 }
 
-use lambda_platform::gfx::pipeline::PushConstantUpload;
+use lambda_platform::gfx::pipeline::{
+  PushConstantUpload,
+  Sided,
+  State,
+  StencilFace,
+  StencilTest,
+};
 pub use lambda_platform::gfx::{
   assembler::VertexAttribute,
-  pipeline::PipelineStage,
+  pipeline::{
+    Comparison,
+    PipelineStage,
+    StencilOp,
+  },
 };
 
+/// How the fragments of one face are tested against & written to the
+/// stencil.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilFaceState {
+  /// Compares the reference value against the stencil.
+  pub compare: Comparison,
+  /// Applied when the comparison fails.
+  pub fail_op: StencilOp,
+  /// Applied when the comparison passes but the depth test fails.
+  pub depth_fail_op: StencilOp,
+  /// Applied when the comparison passes.
+  pub pass_op: StencilOp,
+}
+
+impl StencilFaceState {
+  /// Runs the pass operation when the comparison passes, keeping the stencil
+  /// otherwise.
+  pub fn new(compare: Comparison, pass_op: StencilOp) -> Self {
+    return Self {
+      compare,
+      fail_op: StencilOp::Keep,
+      depth_fail_op: StencilOp::Keep,
+      pass_op,
+    };
+  }
+}
+
+/// The stencil test of a pipeline. The reference value that's compared &
+/// written is set with `RenderCommand::SetStencilReference`, and is 0 until
+/// it's set within a render pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilState {
+  pub front: StencilFaceState,
+  pub back: StencilFaceState,
+  /// ANDed with the reference value & the stencil before they're compared.
+  pub read_mask: u8,
+  /// ANDed with the values that are written to the stencil.
+  pub write_mask: u8,
+}
+
+impl StencilState {
+  /// Applies the face state to both faces, reading & writing every bit.
+  pub fn new(face: StencilFaceState) -> Self {
+    return Self {
+      front: face,
+      back: face,
+      read_mask: 0xff,
+      write_mask: 0xff,
+    };
+  }
+
+  /// Writes the reference value wherever the pipeline draws, such as to draw
+  /// a mask.
+  pub fn write_reference() -> Self {
+    return Self::new(StencilFaceState::new(
+      Comparison::Always,
+      StencilOp::Replace,
+    ));
+  }
+
+  /// Only draws where the comparison between the reference value & the
+  /// stencil passes, leaving the stencil untouched. Comparing with
+  /// `Comparison::Equal` draws within a mask written with the reference.
+  pub fn test(compare: Comparison) -> Self {
+    let mut state = Self::new(StencilFaceState::new(compare, StencilOp::Keep));
+    state.write_mask = 0;
+    return state;
+  }
+
+  fn to_stencil_test(self) -> StencilTest {
+    let face = |face: StencilFaceState| StencilFace {
+      fun: face.compare,
+      op_fail: face.fail_op,
+      op_depth_fail: face.depth_fail_op,
+      op_pass: face.pass_op,
+    };
+    return StencilTest {
+      faces: Sided {
+        front: face(self.front),
+        back: face(self.back),
+      },
+      read_masks: State::Static(Sided::new(self.read_mask as u32)),
+      write_masks: State::Static(Sided::new(self.write_mask as u32)),
+      reference_values: State::Dynamic,
+    };
+  }
+}
+
 pub struct RenderPipelineBuilder {
   push_constants: Vec<PushConstantUpload>,
   buffers: Vec<Rc<Buffer>>,
   attributes: Vec<VertexAttribute>,
+  stencil: Option<StencilState>,
+  color_writes: bool,
+  wireframe: bool,
 }
 
 impl RenderPipelineBuilder {
@@ -74,9 +200,33 @@ impl RenderPipelineBuilder {
       push_constants: Vec::new(),
       buffers: Vec::new(),
       attributes: Vec::new(),
+      stencil: None,
+      color_writes: true,
+      wireframe: false,
     };
   }
 
+  /// Tests & writes the stencil of the render pass, which must be built with
+  /// `RenderPassBuilder::with_stencil`.
+  pub fn with_stencil(mut self, stencil: StencilState) -> Self {
+    self.stencil = Some(stencil);
+    return self;
+  }
+
+  /// Whether or not the pipeline writes color. Pipelines that only write a
+  /// stencil mask can disable color writes so the mask isn't visible.
+  pub fn with_color_writes(mut self, color_writes: bool) -> Self {
+    self.color_writes = color_writes;
+    return self;
+  }
+
+  /// Whether or not only the edges of triangles are drawn. Wireframes need
+  /// the render context to be built with `Features::NON_FILL_POLYGON_MODE`.
+  pub fn with_wireframe(mut self, wireframe: bool) -> Self {
+    self.wireframe = wireframe;
+    return self;
+  }
+
   /// Adds a buffer to the render pipeline.
   pub fn with_buffer(
     mut self,
@@ -99,7 +249,8 @@ impl RenderPipelineBuilder {
     return self;
   }
 
-  /// Builds a render pipeline based on your builder configuration.
+  /// Builds a render pipeline based on your builder configuration. Panics if
+  /// the configuration isn't supported by the GPU, see `try_build`.
   pub fn build(
     self,
     render_context: &mut RenderContext,
@@ -107,6 +258,41 @@ impl RenderPipelineBuilder {
     vertex_shader: &Shader,
     fragment_shader: Option<&Shader>,
   ) -> RenderPipeline {
+    return self
+      .try_build(render_context, render_pass, vertex_shader, fragment_shader)
+      .expect("Failed to build the render pipeline.");
+  }
+
+  /// Builds a render pipeline based on your builder configuration, returning
+  /// an error if the configuration exceeds the limits of the GPU.
+  pub fn try_build(
+    self,
+    render_context: &mut RenderContext,
+    render_pass: &super::render_pass::RenderPass,
+    vertex_shader: &Shader,
+    fragment_shader: Option<&Shader>,
+  ) -> Result<RenderPipeline, String> {
+    validate_push_constants(
+      &self.push_constants,
+      render_context.limits().max_push_constants_size,
+    )?;
+    if self.stencil.is_some() && !render_pass.has_stencil() {
+      return Err(
+        "The pipeline tests the stencil, but its render pass wasn't built \
+         with RenderPassBuilder::with_stencil."
+          .to_string(),
+      );
+    }
+    if self.wireframe
+      && !render_context.supports(super::Features::NON_FILL_POLYGON_MODE)
+    {
+      return Err(
+        "The pipeline draws wireframes, but the render context wasn't built \
+         with Features::NON_FILL_POLYGON_MODE."
+          .to_string(),
+      );
+    }
+
     logging::debug!("Building render pipeline");
 
     logging::debug!("Building vertex shader... ");
@@ -136,7 +322,14 @@ impl RenderPipelineBuilder {
       fragment_shader.map(|s| s.as_binary().len()).unwrap_or(0)
     );
 
-    let builder = lambda_platform::gfx::pipeline::RenderPipelineBuilder::new();
+    let mut builder =
+      lambda_platform::gfx::pipeline::RenderPipelineBuilder::new();
+    if let Some(pipeline_cache) = render_context.internal_pipeline_cache() {
+      builder = builder.with_pipeline_cache(pipeline_cache);
+    }
+    if let Some(stencil) = self.stencil {
+      builder = builder.with_stencil_test(stencil.to_stencil_test());
+    }
 
     let buffers = self.buffers;
     let internal_buffers = buffers
@@ -146,6 +339,8 @@ impl RenderPipelineBuilder {
 
     let render_pipeline = builder
       .with_push_constants(self.push_constants.clone())
+      .with_color_writes(self.color_writes)
+      .with_wireframe(self.wireframe)
       .build(
         render_context.internal_gpu(),
         render_pass.internal_render_pass(),
@@ -161,9 +356,53 @@ impl RenderPipelineBuilder {
       fragment_shader_module.destroy(render_context.internal_mutable_gpu());
     }
 
-    return RenderPipeline {
+    return Ok(RenderPipeline {
       pipeline: Rc::new(render_pipeline),
       buffers,
-    };
+      push_constants: self.push_constants,
+      stencil: self.stencil,
+      has_stencil_pass: render_pass.has_stencil(),
+      color_formats: render_pass.color_formats(),
+    });
+  }
+}
+
+/// Checks that every push constant fits within the push constant range the
+/// GPU supports.
+fn validate_push_constants(
+  push_constants: &[PushConstantUpload],
+  max_push_constants_size: usize,
+) -> Result<(), String> {
+  for (stage, range) in push_constants.iter() {
+    if range.end as usize > max_push_constants_size {
+      return Err(format!(
+        "The {:?} push constant is {} bytes, but the GPU only supports {} \
+         bytes of push constants.",
+        stage, range.end, max_push_constants_size
+      ));
+    }
+  }
+  return Ok(());
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    validate_push_constants,
+    PipelineStage,
+  };
+
+  #[test]
+  fn push_constants_are_validated_against_the_limit() {
+    let push_constants = vec![
+      (PipelineStage::VERTEX, 0..64),
+      (PipelineStage::FRAGMENT, 0..128),
+    ];
+    assert!(validate_push_constants(&push_constants, 128).is_ok());
+
+    let error = validate_push_constants(&push_constants, 64).unwrap_err();
+    assert!(error.contains("128 bytes"));
   }
 }
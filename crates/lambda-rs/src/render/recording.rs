@@ -0,0 +1,672 @@
+//! Recordings of the render commands submitted over a number of frames,
+//! written in a stable text form with a line per command. Recordings can be
+//! compared against a golden file checked into the repository, so that
+//! changes to how a demo renders show up as a diff without running on a
+//! GPU, and parsed back into commands to be played back.
+
+use std::{
+  fmt::Write,
+  ops::Range,
+  path::Path,
+};
+
+use super::{
+  command::RenderCommand,
+  pipeline::PipelineStage,
+  vertex::Vertex,
+  viewport::{
+    Viewport,
+    ViewportBuilder,
+  },
+  ResourceId,
+};
+
+/// The environment variable that golden files are written from instead of
+/// compared against when it's set.
+pub const UPDATE_GOLDEN_ENV: &str = "LAMBDA_UPDATE_GOLDEN";
+
+/// The names that pipeline stages are written with.
+const STAGE_NAMES: [(PipelineStage, &str); 6] = [
+  (PipelineStage::VERTEX, "vertex"),
+  (PipelineStage::HULL, "hull"),
+  (PipelineStage::DOMAIN, "domain"),
+  (PipelineStage::GEOMETRY, "geometry"),
+  (PipelineStage::FRAGMENT, "fragment"),
+  (PipelineStage::COMPUTE, "compute"),
+];
+
+/// The render commands of every frame that was recorded.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandRecording {
+  frames: Vec<Vec<RenderCommand>>,
+}
+
+impl CommandRecording {
+  /// Creates an empty recording.
+  pub fn new() -> Self {
+    return Self::default();
+  }
+
+  /// Starts a new frame that commands are recorded into.
+  pub fn begin_frame(&mut self) {
+    self.frames.push(vec![]);
+  }
+
+  /// Adds the commands to the current frame, starting the first frame if
+  /// none has begun.
+  pub fn record(&mut self, commands: &[RenderCommand]) {
+    if self.frames.is_empty() {
+      self.begin_frame();
+    }
+    self
+      .frames
+      .last_mut()
+      .expect("A frame was just started.")
+      .extend_from_slice(commands);
+  }
+
+  /// The commands of every frame, which can be played back by rendering
+  /// them with a render context.
+  pub fn frames(&self) -> &[Vec<RenderCommand>] {
+    return &self.frames;
+  }
+
+  /// The recording in its text form, with a header line for every frame
+  /// followed by a line for every command.
+  pub fn to_text(&self) -> String {
+    let mut text = String::new();
+    for (index, commands) in self.frames.iter().enumerate() {
+      writeln!(text, "frame {}", index).ok();
+      for command in commands {
+        write_command(&mut text, command);
+      }
+    }
+    return text;
+  }
+
+  /// Parses a recording from its text form. Blank lines & lines starting
+  /// with `#` are ignored.
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let mut recording = Self::new();
+    for (index, line) in text.lines().enumerate() {
+      let error = |message: String| {
+        return format!("Line {}: {}", index + 1, message);
+      };
+      let trimmed = line.trim();
+      if trimmed.is_empty() || trimmed.starts_with('#') {
+        continue;
+      }
+
+      let (name, fields) = trimmed.split_once(' ').unwrap_or((trimmed, ""));
+      // Frames are numbered in order, so the number is only for readers.
+      if name == "frame" {
+        recording.begin_frame();
+        continue;
+      }
+      let fields = Fields::parse(fields).map_err(error)?;
+      if name == "vertex" {
+        let vertices = match recording
+          .frames
+          .last_mut()
+          .and_then(|commands| commands.last_mut())
+        {
+          Some(RenderCommand::UpdateVertexBuffer { vertices, .. }) => vertices,
+          _ => {
+            return Err(error(
+              "A vertex must follow update_vertex_buffer.".to_string(),
+            ))
+          }
+        };
+        vertices.push(parse_vertex(&fields).map_err(error)?);
+        continue;
+      }
+
+      let command = parse_command(name, &fields).map_err(error)?;
+      recording.record(&[command]);
+    }
+    return Ok(recording);
+  }
+}
+
+// --------------------------------- WRITING -----------------------------------
+
+fn write_command(text: &mut String, command: &RenderCommand) {
+  // Writing to a string can't fail, so the results are ignored.
+  match command {
+    RenderCommand::SetViewports {
+      start_at,
+      viewports,
+    } => writeln!(
+      text,
+      "set_viewports start_at={} viewports={}",
+      start_at,
+      format_viewports(viewports)
+    ),
+    RenderCommand::SetScissors {
+      start_at,
+      viewports,
+    } => writeln!(
+      text,
+      "set_scissors start_at={} viewports={}",
+      start_at,
+      format_viewports(viewports)
+    ),
+    RenderCommand::SetPipeline { pipeline } => {
+      writeln!(text, "set_pipeline pipeline={}", pipeline)
+    }
+    RenderCommand::BeginRenderPass {
+      render_pass,
+      viewport,
+    } => writeln!(
+      text,
+      "begin_render_pass render_pass={} viewport={}",
+      render_pass,
+      format_viewports(std::slice::from_ref(viewport))
+    ),
+    RenderCommand::EndRenderPass => writeln!(text, "end_render_pass"),
+    RenderCommand::PushConstants {
+      pipeline,
+      stage,
+      offset,
+      bytes,
+    } => writeln!(
+      text,
+      "push_constants pipeline={} stage={} offset={} bytes={}",
+      pipeline,
+      format_stage(*stage),
+      offset,
+      format_list(bytes)
+    ),
+    RenderCommand::BindVertexBuffer { pipeline, buffer } => writeln!(
+      text,
+      "bind_vertex_buffer pipeline={} buffer={}",
+      pipeline, buffer
+    ),
+    RenderCommand::BindIndexBuffer { pipeline, buffer } => writeln!(
+      text,
+      "bind_index_buffer pipeline={} buffer={}",
+      pipeline, buffer
+    ),
+    RenderCommand::SetStencilReference { reference } => {
+      writeln!(text, "set_stencil_reference reference={}", reference)
+    }
+    RenderCommand::Draw { vertices } => {
+      writeln!(text, "draw vertices={}..{}", vertices.start, vertices.end)
+    }
+    RenderCommand::DrawIndexed {
+      indices,
+      base_vertex,
+    } => writeln!(
+      text,
+      "draw_indexed indices={}..{} base_vertex={}",
+      indices.start, indices.end, base_vertex
+    ),
+    RenderCommand::UpdateVertexBuffer {
+      pipeline,
+      buffer,
+      vertices,
+    } => {
+      writeln!(
+        text,
+        "update_vertex_buffer pipeline={} buffer={}",
+        pipeline, buffer
+      )
+      .ok();
+      for vertex in vertices {
+        writeln!(
+          text,
+          "  vertex position={} normal={} color={} tex_coords={} tangent={}",
+          format_list(&vertex.position),
+          format_list(&vertex.normal),
+          format_list(&vertex.color),
+          format_list(&vertex.tex_coords),
+          format_list(&vertex.tangent)
+        )
+        .ok();
+      }
+      Ok(())
+    }
+  }
+  .ok();
+}
+
+/// Formats the values separated by commas. Floats are written with the
+/// fewest digits that parse back into the same value.
+fn format_list<T: std::fmt::Debug>(values: &[T]) -> String {
+  return values
+    .iter()
+    .map(|value| format!("{:?}", value))
+    .collect::<Vec<String>>()
+    .join(",");
+}
+
+/// Formats every viewport as `x,y,width,height`, separated by semicolons.
+fn format_viewports(viewports: &[Viewport]) -> String {
+  return viewports
+    .iter()
+    .map(|viewport| {
+      let (x, y) = viewport.coordinates();
+      let (width, height) = viewport.dimensions();
+      return format!("{},{},{},{}", x, y, width, height);
+    })
+    .collect::<Vec<String>>()
+    .join(";");
+}
+
+/// Formats the stages as their names separated by `|`, with any stages that
+/// don't have a name written as hex bits.
+fn format_stage(stage: PipelineStage) -> String {
+  let mut names = vec![];
+  let mut remaining = stage;
+  for (flag, name) in STAGE_NAMES {
+    if remaining.contains(flag) {
+      names.push(name.to_string());
+      remaining.remove(flag);
+    }
+  }
+  if !remaining.is_empty() || names.is_empty() {
+    names.push(format!("{:#x}", remaining.bits()));
+  }
+  return names.join("|");
+}
+
+// --------------------------------- PARSING -----------------------------------
+
+/// The `key=value` fields of a line.
+struct Fields<'line> {
+  fields: Vec<(&'line str, &'line str)>,
+}
+
+impl<'line> Fields<'line> {
+  fn parse(line: &'line str) -> Result<Self, String> {
+    let mut fields = vec![];
+    for field in line.split_whitespace() {
+      let (key, value) = field
+        .split_once('=')
+        .ok_or_else(|| format!("{} isn't a key=value field.", field))?;
+      fields.push((key, value));
+    }
+    return Ok(Self { fields });
+  }
+
+  fn get(&self, key: &str) -> Result<&'line str, String> {
+    return self
+      .fields
+      .iter()
+      .find(|(field, _)| *field == key)
+      .map(|(_, value)| *value)
+      .ok_or_else(|| format!("The field {} is missing.", key));
+  }
+
+  fn parse_value<T: std::str::FromStr>(&self, key: &str) -> Result<T, String> {
+    let value = self.get(key)?;
+    return value
+      .parse()
+      .map_err(|_| format!("{}={} isn't a valid value.", key, value));
+  }
+
+  fn parse_list<T: std::str::FromStr>(
+    &self,
+    key: &str,
+  ) -> Result<Vec<T>, String> {
+    let value = self.get(key)?;
+    if value.is_empty() {
+      return Ok(vec![]);
+    }
+    return value
+      .split(',')
+      .map(|item| {
+        return item
+          .parse()
+          .map_err(|_| format!("{}={} isn't a valid list.", key, value));
+      })
+      .collect();
+  }
+
+  fn parse_array<const N: usize>(&self, key: &str) -> Result<[f32; N], String> {
+    let values: Vec<f32> = self.parse_list(key)?;
+    return values
+      .try_into()
+      .map_err(|_| format!("{} must have {} values.", key, N));
+  }
+
+  fn parse_range(&self, key: &str) -> Result<Range<u32>, String> {
+    let value = self.get(key)?;
+    let invalid = || format!("{}={} isn't a valid range.", key, value);
+    let (start, end) = value.split_once("..").ok_or_else(invalid)?;
+    return Ok(
+      start.parse().map_err(|_| invalid())?
+        ..end.parse().map_err(|_| invalid())?,
+    );
+  }
+
+  fn parse_resource(&self, key: &str) -> Result<ResourceId, String> {
+    let value = self.get(key)?;
+    let invalid = || format!("{}={} isn't a resource id.", key, value);
+    let (index, generation) = value.split_once('v').ok_or_else(invalid)?;
+    return Ok(ResourceId::new(
+      index.parse().map_err(|_| invalid())?,
+      generation.parse().map_err(|_| invalid())?,
+    ));
+  }
+
+  fn parse_viewports(&self, key: &str) -> Result<Vec<Viewport>, String> {
+    let value = self.get(key)?;
+    if value.is_empty() {
+      return Ok(vec![]);
+    }
+    let invalid = || format!("{}={} isn't a list of viewports.", key, value);
+    return value
+      .split(';')
+      .map(|viewport| {
+        let parts: Vec<&str> = viewport.split(',').collect();
+        if parts.len() != 4 {
+          return Err(invalid());
+        }
+        return Ok(
+          ViewportBuilder::new()
+            .with_coordinates(
+              parts[0].parse().map_err(|_| invalid())?,
+              parts[1].parse().map_err(|_| invalid())?,
+            )
+            .build(
+              parts[2].parse().map_err(|_| invalid())?,
+              parts[3].parse().map_err(|_| invalid())?,
+            ),
+        );
+      })
+      .collect();
+  }
+
+  fn parse_stage(&self, key: &str) -> Result<PipelineStage, String> {
+    let value = self.get(key)?;
+    let mut stage = PipelineStage::empty();
+    for name in value.split('|') {
+      let flag = match STAGE_NAMES.iter().find(|(_, known)| *known == name) {
+        Some((flag, _)) => *flag,
+        None => name
+          .strip_prefix("0x")
+          .and_then(|bits| u32::from_str_radix(bits, 16).ok())
+          .and_then(PipelineStage::from_bits)
+          .ok_or_else(|| format!("{} isn't a pipeline stage.", name))?,
+      };
+      stage |= flag;
+    }
+    return Ok(stage);
+  }
+}
+
+fn parse_command(name: &str, fields: &Fields) -> Result<RenderCommand, String> {
+  return Ok(match name {
+    "set_viewports" => RenderCommand::SetViewports {
+      start_at: fields.parse_value("start_at")?,
+      viewports: fields.parse_viewports("viewports")?,
+    },
+    "set_scissors" => RenderCommand::SetScissors {
+      start_at: fields.parse_value("start_at")?,
+      viewports: fields.parse_viewports("viewports")?,
+    },
+    "set_pipeline" => RenderCommand::SetPipeline {
+      pipeline: fields.parse_resource("pipeline")?,
+    },
+    "begin_render_pass" => RenderCommand::BeginRenderPass {
+      render_pass: fields.parse_resource("render_pass")?,
+      viewport: fields
+        .parse_viewports("viewport")?
+        .pop()
+        .ok_or_else(|| "The render pass needs a viewport.".to_string())?,
+    },
+    "end_render_pass" => RenderCommand::EndRenderPass,
+    "push_constants" => RenderCommand::PushConstants {
+      pipeline: fields.parse_resource("pipeline")?,
+      stage: fields.parse_stage("stage")?,
+      offset: fields.parse_value("offset")?,
+      bytes: fields.parse_list("bytes")?,
+    },
+    "bind_vertex_buffer" => RenderCommand::BindVertexBuffer {
+      pipeline: fields.parse_resource("pipeline")?,
+      buffer: fields.parse_value("buffer")?,
+    },
+    "bind_index_buffer" => RenderCommand::BindIndexBuffer {
+      pipeline: fields.parse_resource("pipeline")?,
+      buffer: fields.parse_value("buffer")?,
+    },
+    "set_stencil_reference" => RenderCommand::SetStencilReference {
+      reference: fields.parse_value("reference")?,
+    },
+    "draw" => RenderCommand::Draw {
+      vertices: fields.parse_range("vertices")?,
+    },
+    "draw_indexed" => RenderCommand::DrawIndexed {
+      indices: fields.parse_range("indices")?,
+      base_vertex: fields.parse_value("base_vertex")?,
+    },
+    "update_vertex_buffer" => RenderCommand::UpdateVertexBuffer {
+      pipeline: fields.parse_resource("pipeline")?,
+      buffer: fields.parse_value("buffer")?,
+      vertices: vec![],
+    },
+    _ => return Err(format!("{} isn't a render command.", name)),
+  });
+}
+
+fn parse_vertex(fields: &Fields) -> Result<Vertex, String> {
+  return Ok(Vertex {
+    position: fields.parse_array("position")?,
+    normal: fields.parse_array("normal")?,
+    color: fields.parse_array("color")?,
+    tex_coords: fields.parse_array("tex_coords")?,
+    tangent: fields.parse_array("tangent")?,
+  });
+}
+
+// ---------------------------------- GOLDEN -----------------------------------
+
+/// The lines that differ between the expected & actual text, prefixed with
+/// `-` for lines that were removed and `+` for lines that were added, or
+/// None if the texts are the same.
+pub fn diff_lines(expected: &str, actual: &str) -> Option<String> {
+  if expected == actual {
+    return None;
+  }
+  let expected: Vec<&str> = expected.lines().collect();
+  let actual: Vec<&str> = actual.lines().collect();
+
+  // The length of the longest common subsequence of the remaining lines.
+  let mut common = vec![vec![0_usize; actual.len() + 1]; expected.len() + 1];
+  for i in (0..expected.len()).rev() {
+    for j in (0..actual.len()).rev() {
+      common[i][j] = match expected[i] == actual[j] {
+        true => common[i + 1][j + 1] + 1,
+        false => common[i + 1][j].max(common[i][j + 1]),
+      };
+    }
+  }
+
+  let mut diff = String::new();
+  let (mut i, mut j) = (0, 0);
+  while i < expected.len() || j < actual.len() {
+    if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+      i += 1;
+      j += 1;
+    } else if i < expected.len()
+      && (j == actual.len() || common[i + 1][j] >= common[i][j + 1])
+    {
+      writeln!(diff, "{:>5} - {}", i + 1, expected[i]).ok();
+      i += 1;
+    } else {
+      writeln!(diff, "{:>5} + {}", j + 1, actual[j]).ok();
+      j += 1;
+    }
+  }
+  if diff.is_empty() {
+    // Only the line endings differ.
+    diff.push_str("The texts only differ in their line endings.\n");
+  }
+  return Some(diff);
+}
+
+/// Compares the text against the golden file at the path, returning the
+/// differences as an error. The golden file is written instead when the
+/// `LAMBDA_UPDATE_GOLDEN` environment variable is set.
+pub fn check_golden(path: &Path, actual: &str) -> Result<(), String> {
+  if std::env::var_os(UPDATE_GOLDEN_ENV).is_some() {
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent).map_err(|error| {
+        format!("Failed to create {}: {}", parent.display(), error)
+      })?;
+    }
+    return std::fs::write(path, actual).map_err(|error| {
+      format!("Failed to write {}: {}", path.display(), error)
+    });
+  }
+
+  let expected = std::fs::read_to_string(path).map_err(|error| {
+    format!(
+      "Failed to read the golden file {}: {}. Set {} to write it.",
+      path.display(),
+      error,
+      UPDATE_GOLDEN_ENV
+    )
+  })?;
+  return match diff_lines(&expected, actual) {
+    Some(diff) => Err(format!(
+      "The output doesn't match the golden file {}. Set {} to update it if \
+       the change is intended.\n{}",
+      path.display(),
+      UPDATE_GOLDEN_ENV,
+      diff
+    )),
+    None => Ok(()),
+  };
+}
+
+/// Panics with the differences if the text doesn't match the golden file at
+/// the path, for use within tests.
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+  if let Err(error) = check_golden(path.as_ref(), actual) {
+    panic!("{}", error);
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    check_golden,
+    diff_lines,
+    CommandRecording,
+    UPDATE_GOLDEN_ENV,
+  };
+  use crate::render::{
+    command::RenderCommand,
+    pipeline::PipelineStage,
+    vertex::Vertex,
+    viewport::ViewportBuilder,
+    ResourceId,
+  };
+
+  fn every_command() -> Vec<RenderCommand> {
+    let pipeline = ResourceId::new(1, 2);
+    return vec![
+      RenderCommand::SetViewports {
+        start_at: 0,
+        viewports: vec![
+          ViewportBuilder::new().build(800, 600),
+          ViewportBuilder::new().with_coordinates(-4, 8).build(16, 32),
+        ],
+      },
+      RenderCommand::SetScissors {
+        start_at: 1,
+        viewports: vec![],
+      },
+      RenderCommand::BeginRenderPass {
+        render_pass: ResourceId::new(0, 0),
+        viewport: ViewportBuilder::new().build(800, 600),
+      },
+      RenderCommand::SetPipeline { pipeline },
+      RenderCommand::PushConstants {
+        pipeline,
+        stage: PipelineStage::VERTEX | PipelineStage::FRAGMENT,
+        offset: 4,
+        bytes: vec![1, 0x3f80_0000],
+      },
+      RenderCommand::UpdateVertexBuffer {
+        pipeline,
+        buffer: 0,
+        vertices: vec![Vertex {
+          position: [0.1, -2.5, 1e-8],
+          normal: [0.0, 0.0, 1.0],
+          color: [1.0, 0.5, 0.25],
+          tex_coords: [0.0, 1.0],
+          tangent: [1.0, 0.0, 0.0, -1.0],
+        }],
+      },
+      RenderCommand::BindVertexBuffer {
+        pipeline,
+        buffer: 0,
+      },
+      RenderCommand::BindIndexBuffer {
+        pipeline,
+        buffer: 0,
+      },
+      RenderCommand::SetStencilReference { reference: 3 },
+      RenderCommand::Draw { vertices: 0..3 },
+      RenderCommand::DrawIndexed {
+        indices: 0..6,
+        base_vertex: -2,
+      },
+      RenderCommand::EndRenderPass,
+    ];
+  }
+
+  #[test]
+  fn recordings_round_trip_through_text() {
+    let mut recording = CommandRecording::new();
+    recording.record(&every_command());
+    recording.begin_frame();
+    recording.record(&[RenderCommand::Draw { vertices: 3..6 }]);
+
+    let text = recording.to_text();
+    assert!(text.starts_with(
+      "frame 0\nset_viewports start_at=0 viewports=0,0,800,600;-4,8,16,32\n"
+    ));
+    assert!(text.contains(
+      "push_constants pipeline=1v2 stage=vertex|fragment offset=4 \
+       bytes=1,1065353216\n"
+    ));
+    assert!(text.contains(
+      "  vertex position=0.1,-2.5,1e-8 normal=0.0,0.0,1.0 \
+       color=1.0,0.5,0.25 tex_coords=0.0,1.0 tangent=1.0,0.0,0.0,-1.0\n"
+    ));
+    assert!(text.ends_with("frame 1\ndraw vertices=3..6\n"));
+    assert_eq!(CommandRecording::parse(&text), Ok(recording));
+
+    assert!(CommandRecording::parse("clear color=red").is_err());
+    assert!(CommandRecording::parse("frame 0\n  vertex position=0,0,0")
+      .unwrap_err()
+      .starts_with("Line 2:"));
+  }
+
+  #[test]
+  fn golden_files_report_the_lines_that_changed() {
+    assert_eq!(diff_lines("a\nb\n", "a\nb\n"), None);
+    assert_eq!(
+      diff_lines("draw\nend\n", "draw\nbind\nend_pass\n"),
+      Some("    2 - end\n    2 + bind\n    3 + end_pass\n".to_string())
+    );
+
+    let path = std::env::temp_dir()
+      .join(format!("lambda-golden-{}.txt", std::process::id()));
+    std::fs::write(&path, "frame 0\ndraw vertices=0..3\n").unwrap();
+    if std::env::var_os(UPDATE_GOLDEN_ENV).is_none() {
+      assert_eq!(check_golden(&path, "frame 0\ndraw vertices=0..3\n"), Ok(()));
+      let error =
+        check_golden(&path, "frame 0\ndraw vertices=0..6\n").unwrap_err();
+      assert!(error
+        .ends_with("    2 - draw vertices=0..3\n    2 + draw vertices=0..6\n"));
+    }
+    std::fs::remove_file(&path).unwrap();
+  }
+}
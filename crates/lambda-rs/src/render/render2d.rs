@@ -0,0 +1,469 @@
+//! An immediate mode API for drawing 2D shapes & text with one call per
+//! shape. Shapes drawn during a frame are batched into a single triangle list
+//! that is uploaded and drawn by the commands returned from `finish`, so
+//! there are no pipelines or buffers to manage. Positions & sizes are in
+//! points from the top left of the window, like the text renderer.
+
+use std::f32::consts::TAU;
+
+use super::{
+  buffer::BufferBuilder,
+  color::Color,
+  command::RenderCommand,
+  mesh::MeshBuilder,
+  pipeline::RenderPipelineBuilder,
+  render_pass::RenderPassBuilder,
+  shader::{
+    ShaderBuilder,
+    ShaderKind,
+    VirtualShader,
+  },
+  text::{
+    FontAtlas,
+    TextRenderer,
+  },
+  vertex::{
+    Vertex,
+    VertexAttribute,
+    VertexElement,
+  },
+  viewport::ViewportBuilder,
+  ColorFormat,
+  RenderContext,
+  ResourceId,
+};
+use crate::{
+  events::{
+    Events,
+    WindowEvent,
+  },
+  log_console::{
+    FRAGMENT_SHADER_SOURCE,
+    VERTEX_SHADER_SOURCE,
+  },
+};
+
+/// The number of vertices that can be drawn in a frame by default.
+const DEFAULT_VERTEX_CAPACITY: usize = 1 << 16;
+
+/// The fewest & most triangles that circles are drawn with.
+const MIN_CIRCLE_SEGMENTS: u32 = 8;
+const MAX_CIRCLE_SEGMENTS: u32 = 128;
+
+/// Draws rectangles, circles, lines, & text that are batched every frame.
+pub struct Render2D {
+  width: u32,
+  height: u32,
+  pixels_per_point: f32,
+  clear_color: Option<[f32; 4]>,
+  vertex_capacity: usize,
+  font: Option<FontAtlas>,
+  vertices: Vec<Vertex>,
+  render_pass: Option<ResourceId>,
+  render_pipeline: Option<ResourceId>,
+}
+
+impl Render2D {
+  /// Creates a renderer that clears the window to black before drawing. The
+  /// size of the window is tracked through `on_event`.
+  pub fn new() -> Self {
+    return Self {
+      width: 1,
+      height: 1,
+      pixels_per_point: 1.0,
+      clear_color: Some([0.0, 0.0, 0.0, 1.0]),
+      vertex_capacity: DEFAULT_VERTEX_CAPACITY,
+      font: None,
+      vertices: vec![],
+      render_pass: None,
+      render_pipeline: None,
+    };
+  }
+
+  /// The color the window is cleared to before shapes are drawn.
+  pub fn with_clear_color(mut self, clear_color: impl Into<Color>) -> Self {
+    self.clear_color = Some(clear_color.into().to_linear());
+    return self;
+  }
+
+  /// Draw over what has already been rendered this frame instead of
+  /// clearing the window, such as for drawing a 2D overlay over a 3D scene.
+  pub fn with_preserved_contents(mut self) -> Self {
+    self.clear_color = None;
+    return self;
+  }
+
+  /// The most vertices that can be drawn in a frame, where rectangles &
+  /// lines use 6 vertices. Shapes past the capacity aren't drawn.
+  pub fn with_vertex_capacity(mut self, vertex_capacity: usize) -> Self {
+    self.vertex_capacity = vertex_capacity.max(3);
+    return self;
+  }
+
+  /// The font that text is drawn with. Text isn't drawn without a font.
+  pub fn with_font(mut self, font: FontAtlas) -> Self {
+    self.font = Some(font);
+    return self;
+  }
+
+  /// The size of the window in pixels.
+  pub fn set_screen_size(&mut self, width: u32, height: u32) {
+    self.width = width.max(1);
+    self.height = height.max(1);
+  }
+
+  /// The size of the window in points, which shapes can be placed within.
+  pub fn size(&self) -> [f32; 2] {
+    return [
+      self.width as f32 / self.pixels_per_point,
+      self.height as f32 / self.pixels_per_point,
+    ];
+  }
+
+  /// The number of pixels in a point, which is the scale factor of the
+  /// window.
+  pub fn set_pixels_per_point(&mut self, pixels_per_point: f32) {
+    self.pixels_per_point = pixels_per_point;
+  }
+
+  /// Creates the pipeline that shapes are drawn with. This should be called
+  /// from the `on_attach` of the component that draws with the renderer.
+  pub fn attach(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<(), String> {
+    let mut mesh_builder = MeshBuilder::new();
+    for _ in 0..self.vertex_capacity {
+      mesh_builder.with_vertex(Vertex {
+        position: [0.0; 3],
+        normal: [0.0; 3],
+        color: [0.0; 3],
+        tex_coords: [0.0; 2],
+        tangent: [0.0; 4],
+      });
+    }
+    mesh_builder.with_attributes(
+      [0, 12, 24]
+        .iter()
+        .enumerate()
+        .map(|(location, offset)| {
+          return VertexAttribute {
+            location: location as u32,
+            offset: 0,
+            element: VertexElement {
+              format: ColorFormat::Rgb32Sfloat,
+              offset: *offset,
+            },
+          };
+        })
+        .collect(),
+    );
+    let mesh = mesh_builder.build();
+
+    let mut builder = ShaderBuilder::new();
+    let vertex_shader = builder.build(VirtualShader::Source {
+      source: VERTEX_SHADER_SOURCE.to_string(),
+      kind: ShaderKind::Vertex,
+      entry_point: "main".to_string(),
+      name: "render2d".to_string(),
+    });
+    let fragment_shader = builder.build(VirtualShader::Source {
+      source: FRAGMENT_SHADER_SOURCE.to_string(),
+      kind: ShaderKind::Fragment,
+      entry_point: "main".to_string(),
+      name: "render2d".to_string(),
+    });
+
+    let render_pass = match self.clear_color {
+      Some(clear_color) => {
+        RenderPassBuilder::new().with_clear_color(clear_color)
+      }
+      None => RenderPassBuilder::new().with_preserved_contents(),
+    }
+    .build(render_context);
+    let pipeline = RenderPipelineBuilder::new()
+      .with_buffer(
+        BufferBuilder::build_from_mesh(&mesh, render_context)
+          .map_err(|error| error.to_string())?,
+        mesh.attributes().to_vec(),
+      )
+      .build(
+        render_context,
+        &render_pass,
+        &vertex_shader,
+        Some(&fragment_shader),
+      );
+
+    self.render_pass = Some(render_context.attach_render_pass(render_pass));
+    self.render_pipeline = Some(render_context.attach_pipeline(pipeline));
+    return Ok(());
+  }
+
+  /// Tracks the size & scale factor of the window. This should be called
+  /// from the `on_event` of the component that draws with the renderer.
+  pub fn on_event(&mut self, event: &Events) {
+    match event {
+      Events::Window {
+        event: WindowEvent::Resize { width, height },
+        ..
+      } => self.set_screen_size(*width, *height),
+      Events::Window {
+        event: WindowEvent::ScaleFactorChanged { scale_factor },
+        ..
+      } => self.set_pixels_per_point(*scale_factor as f32),
+      _ => {}
+    }
+  }
+
+  /// Draws a filled rectangle with its top left corner at the position.
+  pub fn draw_rect(
+    &mut self,
+    position: [f32; 2],
+    size: [f32; 2],
+    color: impl Into<Color>,
+  ) {
+    let color = color.into().to_rgb();
+    let [left, top] = position;
+    let [right, bottom] = [left + size[0], top + size[1]];
+    self.push_triangles(
+      &[
+        [left, top],
+        [left, bottom],
+        [right, top],
+        [right, top],
+        [left, bottom],
+        [right, bottom],
+      ],
+      color,
+    );
+  }
+
+  /// Draws a filled circle, with more triangles the larger it is.
+  pub fn draw_circle(
+    &mut self,
+    center: [f32; 2],
+    radius: f32,
+    color: impl Into<Color>,
+  ) {
+    let color = color.into().to_rgb();
+    let pixels = radius * self.pixels_per_point;
+    let segments = ((pixels.max(0.0).sqrt() * 4.0) as u32)
+      .clamp(MIN_CIRCLE_SEGMENTS, MAX_CIRCLE_SEGMENTS);
+    let point = |segment: u32| {
+      let angle = segment as f32 / segments as f32 * TAU;
+      return [
+        center[0] + radius * angle.cos(),
+        center[1] + radius * angle.sin(),
+      ];
+    };
+
+    let triangles: Vec<[f32; 2]> = (0..segments)
+      .flat_map(|segment| [center, point(segment), point(segment + 1)])
+      .collect();
+    self.push_triangles(&triangles, color);
+  }
+
+  /// Draws a line of the width, with square ends at the start & end.
+  pub fn draw_line(
+    &mut self,
+    start: [f32; 2],
+    end: [f32; 2],
+    width: f32,
+    color: impl Into<Color>,
+  ) {
+    let color = color.into().to_rgb();
+    let direction = [end[0] - start[0], end[1] - start[1]];
+    let length =
+      (direction[0] * direction[0] + direction[1] * direction[1]).sqrt();
+    if length <= f32::EPSILON {
+      return;
+    }
+
+    let half_width = width / 2.0;
+    let normal = [
+      -direction[1] / length * half_width,
+      direction[0] / length * half_width,
+    ];
+    let offset = |point: [f32; 2], side: f32| {
+      return [point[0] + normal[0] * side, point[1] + normal[1] * side];
+    };
+    self.push_triangles(
+      &[
+        offset(start, 1.0),
+        offset(start, -1.0),
+        offset(end, 1.0),
+        offset(end, 1.0),
+        offset(start, -1.0),
+        offset(end, -1.0),
+      ],
+      color,
+    );
+  }
+
+  /// Draws text with the top of its first line at the position. Nothing is
+  /// drawn until the renderer has a font.
+  pub fn draw_text(
+    &mut self,
+    text: &str,
+    position: [f32; 2],
+    color: impl Into<Color>,
+  ) {
+    let font = match self.font.as_ref() {
+      Some(font) => font,
+      None => return,
+    };
+    let mesh = TextRenderer::new(self.width, self.height)
+      .with_pixels_per_point(self.pixels_per_point)
+      .with_color(color)
+      .build_mesh(font, text, (position[0], position[1]));
+
+    let room = self.room();
+    self
+      .vertices
+      .extend(mesh.vertices().iter().take(room - room % 3));
+  }
+
+  /// The number of vertices drawn so far this frame.
+  pub fn vertex_count(&self) -> usize {
+    return self.vertices.len();
+  }
+
+  /// Commands that upload & draw every shape drawn this frame, which should
+  /// be returned from the `on_render` of the component that draws with the
+  /// renderer. The shapes are cleared for the next frame. Nothing is drawn
+  /// until the renderer has been attached.
+  pub fn finish(&mut self) -> Vec<RenderCommand> {
+    let vertices = std::mem::take(&mut self.vertices);
+    let (render_pass, pipeline) = match (self.render_pass, self.render_pipeline)
+    {
+      (Some(render_pass), Some(pipeline)) => (render_pass, pipeline),
+      _ => return vec![],
+    };
+
+    let vertex_count = vertices.len() as u32;
+    let viewport = ViewportBuilder::new().build(self.width, self.height);
+    let mut commands = vec![];
+    if vertex_count > 0 {
+      commands.push(RenderCommand::UpdateVertexBuffer {
+        pipeline,
+        buffer: 0,
+        vertices,
+      });
+    }
+    commands.extend([
+      RenderCommand::SetViewports {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetScissors {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetPipeline { pipeline },
+      RenderCommand::BeginRenderPass {
+        render_pass,
+        viewport,
+      },
+    ]);
+    if vertex_count > 0 {
+      commands.extend([
+        RenderCommand::BindVertexBuffer {
+          pipeline,
+          buffer: 0,
+        },
+        RenderCommand::Draw {
+          vertices: 0..vertex_count,
+        },
+      ]);
+    }
+    commands.push(RenderCommand::EndRenderPass);
+    return commands;
+  }
+
+  /// The number of vertices that can still be drawn this frame.
+  fn room(&self) -> usize {
+    return self.vertex_capacity.saturating_sub(self.vertices.len());
+  }
+
+  /// Adds whole triangles given in points, converting them into clip space.
+  /// Shapes that don't fit within the vertex capacity aren't drawn.
+  fn push_triangles(&mut self, points: &[[f32; 2]], color: [f32; 3]) {
+    if points.len() > self.room() {
+      return;
+    }
+
+    let scale = [
+      self.pixels_per_point / self.width as f32 * 2.0,
+      self.pixels_per_point / self.height as f32 * 2.0,
+    ];
+    self.vertices.extend(points.iter().map(|point| {
+      return Vertex {
+        position: [point[0] * scale[0] - 1.0, point[1] * scale[1] - 1.0, 0.0],
+        normal: [0.0, 0.0, 1.0],
+        color,
+        tex_coords: [0.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+      };
+    }));
+  }
+}
+
+impl Default for Render2D {
+  fn default() -> Self {
+    return Self::new();
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::Render2D;
+
+  fn assert_close(actual: [f32; 3], expected: [f32; 3]) {
+    for (actual, expected) in actual.iter().zip(expected.iter()) {
+      assert!((actual - expected).abs() < 1e-5, "{:?}", actual);
+    }
+  }
+
+  #[test]
+  fn shapes_are_converted_from_points_to_clip_space() {
+    let mut render2d = Render2D::new();
+    render2d.set_screen_size(200, 100);
+    render2d.set_pixels_per_point(2.0);
+    assert_eq!(render2d.size(), [100.0, 50.0]);
+
+    render2d.draw_rect([0.0, 0.0], [50.0, 25.0], [1.0, 0.0, 0.0]);
+    assert_eq!(render2d.vertex_count(), 6);
+    assert_close(render2d.vertices[0].position, [-1.0, -1.0, 0.0]);
+    assert_close(render2d.vertices[5].position, [0.0, 0.0, 0.0]);
+    assert_eq!(render2d.vertices[5].color, [1.0, 0.0, 0.0]);
+
+    render2d.draw_line([0.0, 10.0], [50.0, 10.0], 4.0, [1.0; 3]);
+    assert_close(render2d.vertices[6].position, [-1.0, -0.52, 0.0]);
+    assert_close(render2d.vertices[11].position, [0.0, -0.68, 0.0]);
+
+    // Degenerate lines & text without a font aren't drawn.
+    render2d.draw_line([5.0, 5.0], [5.0, 5.0], 4.0, [1.0; 3]);
+    render2d.draw_text("hello", [0.0, 0.0], [1.0; 3]);
+    assert_eq!(render2d.vertex_count(), 12);
+  }
+
+  #[test]
+  fn shapes_are_cleared_and_limited_to_the_capacity() {
+    let mut render2d = Render2D::new().with_vertex_capacity(30);
+    render2d.set_screen_size(100, 100);
+
+    // Small circles use the fewest segments, with a triangle per segment.
+    render2d.draw_circle([50.0, 50.0], 1.0, [1.0; 3]);
+    assert_eq!(render2d.vertex_count(), 24);
+    render2d.draw_rect([0.0, 0.0], [10.0, 10.0], [1.0; 3]);
+    assert_eq!(render2d.vertex_count(), 30);
+    render2d.draw_rect([0.0, 0.0], [10.0, 10.0], [1.0; 3]);
+    assert_eq!(render2d.vertex_count(), 30);
+
+    // Nothing is drawn before attaching, but the frame is still cleared.
+    assert!(render2d.finish().is_empty());
+    assert_eq!(render2d.vertex_count(), 0);
+  }
+}
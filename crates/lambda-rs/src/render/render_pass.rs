@@ -3,14 +3,52 @@ use std::rc::Rc;
 
 use lambda_platform::gfx::render_pass;
 
-use super::RenderContext;
+use super::{
+  color::Color,
+  ColorFormat,
+  RenderContext,
+};
 
 #[derive(Debug)]
 pub struct RenderPass {
   render_pass: Rc<render_pass::RenderPass<super::internal::RenderBackend>>,
+  color_attachments: Vec<ColorAttachment>,
 }
 
 impl RenderPass {
+  /// The color attachments that the render pass renders into after the
+  /// surface.
+  pub fn color_attachments(&self) -> &[ColorAttachment] {
+    return &self.color_attachments;
+  }
+
+  /// The fragment shader output location of the named color attachment. The
+  /// surface is at location 0 and the color attachments follow it in the
+  /// order they were added.
+  pub fn color_attachment_location(&self, name: &str) -> Option<u32> {
+    return self
+      .color_attachments
+      .iter()
+      .position(|attachment| attachment.name == name)
+      .map(|index| index as u32 + 1);
+  }
+
+  /// The formats of the color attachments after the surface, which pipelines
+  /// built with the render pass can only be used in render passes with.
+  pub fn color_formats(&self) -> Vec<ColorFormat> {
+    return self
+      .color_attachments
+      .iter()
+      .map(|attachment| attachment.format)
+      .collect();
+  }
+
+  /// Whether or not the render pass has a stencil, which pipelines that test
+  /// or write the stencil must be built with.
+  pub fn has_stencil(&self) -> bool {
+    return self.render_pass.has_depth_stencil();
+  }
+
   /// Destroy the render pass with the render context that created it.
   pub fn destroy(self, render_context: &RenderContext) {
     Rc::try_unwrap(self.render_pass)
@@ -37,21 +75,405 @@ impl RenderPass {
   }
 }
 
-pub struct RenderPassBuilder {}
+/// What a render pass does with the contents of its color attachment when it
+/// begins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadOp {
+  /// Keep what earlier render passes drew this frame.
+  Load,
+  /// Clear the attachment to the clear color.
+  Clear,
+  /// Start with undefined contents, for passes that draw over every pixel.
+  DontCare,
+}
+
+/// What a render pass does with the contents of its color attachment when it
+/// ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreOp {
+  /// Keep what the render pass drew for later passes & presentation.
+  Store,
+  /// Discard what the render pass drew.
+  DontCare,
+}
+
+/// A color attachment that a render pass renders into alongside the surface,
+/// such as one of the images of a G-buffer. The render context allocates an
+/// image the size of the surface for every name, which is shared by every
+/// render pass with an attachment of that name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorAttachment {
+  name: String,
+  format: ColorFormat,
+  clear_color: [f32; 4],
+  load_op: LoadOp,
+  store_op: StoreOp,
+}
+
+impl ColorAttachment {
+  pub fn name(&self) -> &str {
+    return &self.name;
+  }
+
+  pub fn format(&self) -> ColorFormat {
+    return self.format;
+  }
+}
+
+pub struct ColorAttachmentBuilder {
+  name: String,
+  format: ColorFormat,
+  clear_color: [f32; 4],
+  load_op: LoadOp,
+  store_op: StoreOp,
+}
+
+impl ColorAttachmentBuilder {
+  /// Creates a builder for a color attachment of the format, which clears
+  /// to transparent black & stores what it draws by default.
+  pub fn new(name: &str, format: ColorFormat) -> Self {
+    return Self {
+      name: name.to_string(),
+      format,
+      clear_color: [0.0, 0.0, 0.0, 0.0],
+      load_op: LoadOp::Clear,
+      store_op: StoreOp::Store,
+    };
+  }
+
+  /// The value that the attachment is cleared to when it's loaded with
+  /// `LoadOp::Clear`. Unlike the clear color of the surface it isn't
+  /// converted to linear, since attachments often store data such as
+  /// normals.
+  pub fn with_clear_value(mut self, clear_value: [f32; 4]) -> Self {
+    self.clear_color = clear_value;
+    return self;
+  }
+
+  /// What the render pass does with the attachment when it begins. Loading
+  /// the attachment keeps what earlier render passes drew into it this frame.
+  pub fn with_load_op(mut self, load_op: LoadOp) -> Self {
+    self.load_op = load_op;
+    return self;
+  }
+
+  /// What the render pass does with the attachment when it ends.
+  pub fn with_store_op(mut self, store_op: StoreOp) -> Self {
+    self.store_op = store_op;
+    return self;
+  }
+
+  pub fn build(self) -> ColorAttachment {
+    return ColorAttachment {
+      name: self.name,
+      format: self.format,
+      clear_color: self.clear_color,
+      load_op: self.load_op,
+      store_op: self.store_op,
+    };
+  }
+}
+
+pub struct RenderPassBuilder {
+  clear_color: [f32; 4],
+  color_attachments: Vec<ColorAttachment>,
+  depth_clear_value: f32,
+  load_op: LoadOp,
+  store_op: StoreOp,
+  stencil: bool,
+  stencil_clear_value: u32,
+  stencil_load_op: LoadOp,
+  stencil_store_op: StoreOp,
+}
 
 impl RenderPassBuilder {
-  /// Creates a new render pass builder.
+  /// Creates a new render pass builder, which clears to opaque black & stores
+  /// what it draws by default.
   pub fn new() -> Self {
-    return Self {};
+    return Self {
+      clear_color: [0.0, 0.0, 0.0, 1.0],
+      color_attachments: vec![],
+      depth_clear_value: 1.0,
+      load_op: LoadOp::Clear,
+      store_op: StoreOp::Store,
+      stencil: false,
+      stencil_clear_value: 0,
+      stencil_load_op: LoadOp::Clear,
+      stencil_store_op: StoreOp::DontCare,
+    };
+  }
+
+  /// The RGBA color that the color attachment is cleared to when it's loaded
+  /// with `LoadOp::Clear`.
+  pub fn with_clear_color(mut self, clear_color: impl Into<Color>) -> Self {
+    self.clear_color = clear_color.into().to_linear();
+    return self;
+  }
+
+  /// What the render pass does with the color attachment when it begins.
+  pub fn with_color_load_op(mut self, load_op: LoadOp) -> Self {
+    self.load_op = load_op;
+    return self;
+  }
+
+  /// What the render pass does with the color attachment when it ends.
+  pub fn with_color_store_op(mut self, store_op: StoreOp) -> Self {
+    self.store_op = store_op;
+    return self;
+  }
+
+  /// Renders into the color attachment after the surface & the color
+  /// attachments added before it, which fragment shaders write to at the
+  /// location returned by `RenderPass::color_attachment_location`.
+  pub fn with_color_attachment(mut self, attachment: ColorAttachment) -> Self {
+    self.color_attachments.push(attachment);
+    return self;
+  }
+
+  /// The depth that depth attachments are cleared to. Defaults to 1.0, the
+  /// farthest depth.
+  pub fn with_depth_clear_value(mut self, depth_clear_value: f32) -> Self {
+    self.depth_clear_value = depth_clear_value;
+    return self;
+  }
+
+  /// Render into a depth & stencil image the size of the surface alongside
+  /// it, so that pipelines built with `RenderPipelineBuilder::with_stencil`
+  /// can mask what they draw. The render context allocates the image when
+  /// the first render pass with a stencil begins.
+  pub fn with_stencil(mut self) -> Self {
+    self.stencil = true;
+    return self;
+  }
+
+  /// The value that the stencil is cleared to when it's loaded with
+  /// `LoadOp::Clear`. Defaults to 0.
+  pub fn with_stencil_clear_value(mut self, stencil_clear_value: u32) -> Self {
+    self.stencil_clear_value = stencil_clear_value;
+    return self;
+  }
+
+  /// What the render pass does with the stencil when it begins. Loading the
+  /// stencil keeps the masks written by earlier render passes this frame.
+  pub fn with_stencil_load_op(mut self, load_op: LoadOp) -> Self {
+    self.stencil_load_op = load_op;
+    return self;
+  }
+
+  /// What the render pass does with the stencil when it ends. The stencil is
+  /// discarded by default, so later render passes that load it must store it.
+  pub fn with_stencil_store_op(mut self, store_op: StoreOp) -> Self {
+    self.stencil_store_op = store_op;
+    return self;
   }
 
-  /// Builds a render pass that can be used for defining
+  /// Keep what earlier render passes drew this frame instead of clearing it,
+  /// so that the render pass can draw on top of them. Equivalent to
+  /// `with_color_load_op(LoadOp::Load)`.
+  pub fn with_preserved_contents(self) -> Self {
+    return self.with_color_load_op(LoadOp::Load);
+  }
+
+  /// Builds a render pass that can be used for defining. The render pass
+  /// renders in the surface format of the render context, so pipelines built
+  /// with it are compatible with the surface. Panics if the color attachments
+  /// aren't supported, see `try_build`.
   pub fn build(self, render_context: &RenderContext) -> RenderPass {
-    let render_pass =
+    return self
+      .try_build(render_context)
+      .expect("Failed to build the render pass.");
+  }
+
+  /// Builds a render pass, returning an error if two color attachments share
+  /// a name or the GPU can't render into the color attachments.
+  pub fn try_build(
+    self,
+    render_context: &RenderContext,
+  ) -> Result<RenderPass, String> {
+    validate_color_attachments(
+      &self.color_attachments,
+      render_context.limits().max_color_attachments,
+    )?;
+    for attachment in self.color_attachments.iter() {
+      if !render_context.supports_color_attachment_format(attachment.format) {
+        return Err(format!(
+          "The GPU can't render into the {} color attachment's {:?} format.",
+          attachment.name, attachment.format
+        ));
+      }
+    }
+
+    let attachment = render_pass::AttachmentBuilder::new()
+      .with_samples(1)
+      .on_load(self.load_op.to_operation())
+      .on_store(self.store_op.to_operation())
+      .with_color_format(render_context.surface_format());
+    let attachment = match self.load_op {
+      // Earlier passes leave the surface image ready to be presented.
+      LoadOp::Load => attachment.with_layouts(
+        render_pass::ImageLayoutHint::Present
+          ..render_pass::ImageLayoutHint::Present,
+      ),
+      _ => attachment,
+    };
+
+    let mut builder =
       lambda_platform::gfx::render_pass::RenderPassBuilder::new()
-        .build(render_context.internal_gpu());
-    return RenderPass {
+        .add_attachment(attachment.build());
+    let mut subpass = render_pass::SubpassBuilder::new().with_color_attachment(
+      0,
+      render_pass::ImageLayoutHint::ColorAttachmentOptimal,
+    );
+    for (index, color_attachment) in self.color_attachments.iter().enumerate() {
+      builder = builder.add_attachment(color_attachment.to_attachment());
+      subpass = subpass.with_color_attachment(
+        index + 1,
+        render_pass::ImageLayoutHint::ColorAttachmentOptimal,
+      );
+    }
+    if self.stencil {
+      builder = builder.add_attachment(self.stencil_attachment(render_context));
+      subpass = subpass.with_depth_stencil_attachment();
+    }
+
+    let render_pass = builder
+      .add_subpass(subpass.build())
+      .with_clear_color(self.clear_color)
+      .with_depth_clear_value(self.depth_clear_value)
+      .with_stencil_clear_value(self.stencil_clear_value)
+      .build(render_context.internal_gpu());
+    return Ok(RenderPass {
       render_pass: Rc::new(render_pass),
+      color_attachments: self.color_attachments,
+    });
+  }
+
+  /// The depth & stencil attachment, whose depth is loaded & stored like its
+  /// stencil since pipelines don't test depth.
+  fn stencil_attachment(
+    &self,
+    render_context: &RenderContext,
+  ) -> render_pass::Attachment {
+    let format = render_context
+      .depth_stencil_format()
+      .expect("The GPU doesn't support rendering with a stencil.");
+    let load = self.stencil_load_op.to_operation();
+    let store = self.stencil_store_op.to_operation();
+    // Loaded stencils were left ready to render into by earlier passes.
+    let initial_layout = match self.stencil_load_op {
+      LoadOp::Load => {
+        render_pass::ImageLayoutHint::DepthStencilAttachmentOptimal
+      }
+      _ => render_pass::ImageLayoutHint::Undefined,
     };
+
+    return render_pass::AttachmentBuilder::new()
+      .with_samples(1)
+      .with_color_format(format)
+      .on_load(load)
+      .on_store(store)
+      .on_stencil_load(load)
+      .on_stencil_store(store)
+      .with_layouts(
+        initial_layout
+          ..render_pass::ImageLayoutHint::DepthStencilAttachmentOptimal,
+      )
+      .build();
+  }
+}
+
+impl LoadOp {
+  fn to_operation(self) -> render_pass::Operations {
+    return match self {
+      LoadOp::Load => render_pass::Operations::Load,
+      LoadOp::Clear => render_pass::Operations::Clear,
+      LoadOp::DontCare => render_pass::Operations::DontCare,
+    };
+  }
+}
+
+impl StoreOp {
+  fn to_operation(self) -> render_pass::Operations {
+    return match self {
+      StoreOp::Store => render_pass::Operations::Store,
+      StoreOp::DontCare => render_pass::Operations::DontCare,
+    };
+  }
+}
+
+impl ColorAttachment {
+  fn to_attachment(&self) -> render_pass::Attachment {
+    // Loaded attachments were left ready to render into by earlier passes.
+    let initial_layout = match self.load_op {
+      LoadOp::Load => render_pass::ImageLayoutHint::ColorAttachmentOptimal,
+      _ => render_pass::ImageLayoutHint::Undefined,
+    };
+    return render_pass::AttachmentBuilder::new()
+      .with_samples(1)
+      .with_color_format(self.format)
+      .with_clear_color(self.clear_color)
+      .on_load(self.load_op.to_operation())
+      .on_store(self.store_op.to_operation())
+      .with_layouts(
+        initial_layout..render_pass::ImageLayoutHint::ColorAttachmentOptimal,
+      )
+      .build();
+  }
+}
+
+/// Checks that the names of the color attachments are unique and that the
+/// surface & the attachments fit within the GPU's limit.
+fn validate_color_attachments(
+  color_attachments: &[ColorAttachment],
+  max_color_attachments: usize,
+) -> Result<(), String> {
+  if color_attachments.len() + 1 > max_color_attachments {
+    return Err(format!(
+      "The render pass renders into the surface & {} color attachments, but \
+       the GPU only supports {} color attachments.",
+      color_attachments.len(),
+      max_color_attachments
+    ));
+  }
+  for (index, attachment) in color_attachments.iter().enumerate() {
+    if color_attachments[..index]
+      .iter()
+      .any(|earlier| earlier.name == attachment.name)
+    {
+      return Err(format!(
+        "The render pass has more than one {} color attachment.",
+        attachment.name
+      ));
+    }
+  }
+  return Ok(());
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    validate_color_attachments,
+    ColorAttachmentBuilder,
+  };
+  use crate::render::ColorFormat;
+
+  #[test]
+  fn color_attachments_are_validated() {
+    let albedo =
+      ColorAttachmentBuilder::new("albedo", ColorFormat::Rgba8Unorm).build();
+    let normal =
+      ColorAttachmentBuilder::new("normal", ColorFormat::Rgba16Sfloat).build();
+
+    let attachments = vec![albedo.clone(), normal];
+    assert!(validate_color_attachments(&attachments, 4).is_ok());
+
+    let error = validate_color_attachments(&attachments, 2).unwrap_err();
+    assert!(error.contains("only supports 2"));
+
+    let error =
+      validate_color_attachments(&[albedo.clone(), albedo], 4).unwrap_err();
+    assert!(error.contains("more than one albedo"));
   }
 }
@@ -0,0 +1,167 @@
+//! Generational handles to the resources attached to a render context.
+
+use std::fmt;
+
+/// A handle to a resource that was attached to a render context. Slots of
+/// destroyed resources are reused with a new generation, so the handle of a
+/// destroyed resource is an error to use instead of referring to whichever
+/// resource took its place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceId {
+  index: u32,
+  generation: u32,
+}
+
+impl ResourceId {
+  pub(crate) fn new(index: u32, generation: u32) -> Self {
+    return Self { index, generation };
+  }
+}
+
+impl fmt::Display for ResourceId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    return write!(f, "{}v{}", self.index, self.generation);
+  }
+}
+
+/// A resource along with the component it was attached by.
+#[derive(Debug)]
+struct Slot<T> {
+  generation: u32,
+  entry: Option<(T, Option<String>)>,
+}
+
+/// Resources of a single type, stored in slots that are reused once their
+/// resources are removed.
+#[derive(Debug)]
+pub(super) struct ResourcePool<T> {
+  slots: Vec<Slot<T>>,
+  free: Vec<u32>,
+}
+
+impl<T> ResourcePool<T> {
+  pub(super) fn new() -> Self {
+    return Self {
+      slots: vec![],
+      free: vec![],
+    };
+  }
+
+  /// Stores the resource, recording the owner that attached it.
+  pub(super) fn insert(
+    &mut self,
+    resource: T,
+    owner: Option<String>,
+  ) -> ResourceId {
+    let index = match self.free.pop() {
+      Some(index) => index,
+      None => {
+        self.slots.push(Slot {
+          generation: 0,
+          entry: None,
+        });
+        (self.slots.len() - 1) as u32
+      }
+    };
+
+    let slot = &mut self.slots[index as usize];
+    slot.entry = Some((resource, owner));
+    return ResourceId::new(index, slot.generation);
+  }
+
+  fn slot(&self, id: ResourceId) -> Option<&Slot<T>> {
+    return self
+      .slots
+      .get(id.index as usize)
+      .filter(|slot| slot.generation == id.generation);
+  }
+
+  /// The resource of the handle, or None if the handle is stale.
+  pub(super) fn get(&self, id: ResourceId) -> Option<&T> {
+    return self
+      .slot(id)
+      .and_then(|slot| slot.entry.as_ref())
+      .map(|(resource, _)| resource);
+  }
+
+  /// Whether or not the handle refers to a stored resource.
+  pub(super) fn contains(&self, id: ResourceId) -> bool {
+    return self.get(id).is_some();
+  }
+
+  /// Removes the resource & invalidates every handle to it.
+  pub(super) fn remove(&mut self, id: ResourceId) -> Option<T> {
+    self.slot(id)?.entry.as_ref()?;
+
+    let slot = &mut self.slots[id.index as usize];
+    slot.generation = slot.generation.wrapping_add(1);
+    self.free.push(id.index);
+    return slot.entry.take().map(|(resource, _)| resource);
+  }
+
+  /// The handles of the resources that were attached by the owner.
+  pub(super) fn owned_by(&self, owner: &str) -> Vec<ResourceId> {
+    return self
+      .slots
+      .iter()
+      .enumerate()
+      .filter_map(|(index, slot)| match &slot.entry {
+        Some((_, Some(slot_owner))) if slot_owner == owner => {
+          Some(ResourceId::new(index as u32, slot.generation))
+        }
+        _ => None,
+      })
+      .collect();
+  }
+
+  /// Removes every resource.
+  pub(super) fn drain(&mut self) -> Vec<T> {
+    self.free.clear();
+    return std::mem::take(&mut self.slots)
+      .into_iter()
+      .filter_map(|slot| slot.entry.map(|(resource, _)| resource))
+      .collect();
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::ResourcePool;
+
+  #[test]
+  fn stale_handles_dont_alias_reused_slots() {
+    let mut pool = ResourcePool::new();
+    let first = pool.insert("first", None);
+    let second = pool.insert("second", None);
+    assert_eq!(pool.get(first), Some(&"first"));
+
+    assert_eq!(pool.remove(first), Some("first"));
+    assert_eq!(pool.remove(first), None);
+    assert_eq!(pool.get(first), None);
+
+    let third = pool.insert("third", None);
+    assert_ne!(third, first);
+    assert_eq!(pool.get(first), None);
+    assert_eq!(pool.get(third), Some(&"third"));
+    assert_eq!(pool.get(second), Some(&"second"));
+  }
+
+  #[test]
+  fn resources_are_found_by_owner() {
+    let mut pool = ResourcePool::new();
+    let owned = pool.insert(1, Some("console".to_string()));
+    pool.insert(2, Some("scene".to_string()));
+    pool.insert(3, None);
+
+    assert_eq!(pool.owned_by("console"), vec![owned]);
+    pool.remove(owned);
+    assert!(pool.owned_by("console").is_empty());
+
+    let mut remaining = pool.drain();
+    remaining.sort();
+    assert_eq!(remaining, vec![2, 3]);
+    assert!(!pool.contains(owned));
+  }
+}
@@ -1,5 +1,6 @@
 //! A module for compiling shaders into SPIR-V binary.
 
+use lambda_platform::shaderc::compiler_version;
 // Expose some lower level shader
 pub use lambda_platform::shaderc::{
   ShaderCompiler,
@@ -8,23 +9,67 @@ pub use lambda_platform::shaderc::{
   VirtualShader,
 };
 
+use super::cache::{
+  content_hash,
+  DiskCache,
+};
+
 pub struct ShaderBuilder {
   compiler: ShaderCompiler,
+  cache: Option<DiskCache>,
 }
 
 impl ShaderBuilder {
   /// Creates a new shader builder that can be reused for compiling shaders.
   pub fn new() -> Self {
     let compiler = ShaderCompilerBuilder::new().build();
-    return Self { compiler };
+    return Self {
+      compiler,
+      cache: None,
+    };
+  }
+
+  /// Caches compiled shaders within the cache, keyed by the hash of their
+  /// source, kind, and entry point. Shaders are recompiled once the compiler
+  /// changes.
+  pub fn with_cache(mut self, cache: DiskCache) -> Self {
+    self.cache = Some(cache);
+    return self;
   }
 
   /// Compiles the virtual shader into a real shader with SPIR-V binary
   /// representation.
   pub fn build(&mut self, virtual_shader: VirtualShader) -> Shader {
+    let cache_key = self.cache.as_ref().and_then(|_| {
+      return cache_key(&virtual_shader);
+    });
+    let producer = content_hash(compiler_version().as_bytes());
+
+    if let (Some(cache), Some(key)) = (self.cache.as_ref(), cache_key.as_ref())
+    {
+      if let Some(binary) = cache.load(key, producer).and_then(from_bytes) {
+        logging::trace!("Loaded the cached shader: {:?}", virtual_shader);
+        return Shader {
+          binary,
+          virtual_shader,
+        };
+      }
+    }
+
     logging::trace!("Compiling shader: {:?}", virtual_shader);
     let binary = self.compiler.compile_into_binary(&virtual_shader);
 
+    if let (Some(cache), Some(key)) = (self.cache.as_ref(), cache_key.as_ref())
+    {
+      let bytes = binary
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .collect::<Vec<_>>();
+      if let Err(error) = cache.store(key, producer, &bytes) {
+        logging::warn!("{}", error);
+      }
+    }
+
     return Shader {
       binary,
       virtual_shader,
@@ -32,6 +77,41 @@ impl ShaderBuilder {
   }
 }
 
+/// The name of the cache entry of the shader, or None if the source of the
+/// shader couldn't be read.
+fn cache_key(virtual_shader: &VirtualShader) -> Option<String> {
+  let (source, kind, entry_point) = match virtual_shader {
+    VirtualShader::File {
+      path,
+      kind,
+      entry_point,
+      ..
+    } => (std::fs::read_to_string(path).ok()?, kind, entry_point),
+    VirtualShader::Source {
+      source,
+      kind,
+      entry_point,
+      ..
+    } => (source.clone(), kind, entry_point),
+  };
+  let inputs = format!("{:?}\0{}\0{}", kind, entry_point, source);
+  return Some(format!("shader-{:016x}", content_hash(inputs.as_bytes())));
+}
+
+/// SPIR-V words from little endian bytes, or None if the bytes aren't whole
+/// words.
+fn from_bytes(bytes: Vec<u8>) -> Option<Vec<u32>> {
+  let words = bytes.chunks_exact(4);
+  if bytes.is_empty() || !words.remainder().is_empty() {
+    return None;
+  }
+  return Some(
+    words
+      .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+      .collect(),
+  );
+}
+
 /// A shader that has been compiled into SPIR-V binary. Contains the binary
 /// representation of the shader as well as the virtual shader that was used
 /// to compile it.
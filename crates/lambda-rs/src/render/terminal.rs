@@ -0,0 +1,356 @@
+//! Experimental terminal render target. Triangles are rasterized on the CPU
+//! into a small pixel buffer which is then presented to a terminal as colored
+//! unicode half blocks, allowing simple demos to run on headless machines and
+//! over SSH sessions.
+
+use std::io::Write;
+
+use super::{
+  mesh::Mesh,
+  vertex::Vertex,
+};
+
+/// The character used to present two vertically stacked pixels within a
+/// single terminal cell. The foreground color is used for the top pixel and
+/// the background color is used for the bottom pixel.
+const HALF_BLOCK: char = '\u{2580}';
+
+/// Pixel colors stored by the terminal canvas as 8 bit RGB.
+pub type TerminalColor = [u8; 3];
+
+/// Convert a color with channels in the range [0, 1] into a terminal color.
+fn to_terminal_color(color: [f32; 3]) -> TerminalColor {
+  return [
+    (color[0].clamp(0.0, 1.0) * 255.0).round() as u8,
+    (color[1].clamp(0.0, 1.0) * 255.0).round() as u8,
+    (color[2].clamp(0.0, 1.0) * 255.0).round() as u8,
+  ];
+}
+
+/// Transforms a position using a column major 4x4 matrix (The same layout
+/// used by the push constant matrices uploaded to shaders) and performs the
+/// perspective divide.
+fn transform_position(
+  transform: &[[f32; 4]; 4],
+  position: [f32; 3],
+) -> [f32; 3] {
+  let [x, y, z] = position;
+  let mut result = [0.0; 4];
+  for (row, value) in result.iter_mut().enumerate() {
+    *value = transform[0][row] * x
+      + transform[1][row] * y
+      + transform[2][row] * z
+      + transform[3][row];
+  }
+
+  let w = match result[3] {
+    w if w.abs() > f32::EPSILON => w,
+    _ => 1.0,
+  };
+
+  return [result[0] / w, result[1] / w, result[2] / w];
+}
+
+/// Signed area of the parallelogram formed by the edge (a, b) and the point c.
+fn edge_function(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+  return (c[0] - a[0]) * (b[1] - a[1]) - (c[1] - a[1]) * (b[0] - a[0]);
+}
+
+// ----------------------------- TERMINAL CANVAS -------------------------------
+
+/// Builder for terminal canvases.
+pub struct TerminalCanvasBuilder {
+  dimensions: (u32, u32),
+  clear_color: [f32; 3],
+}
+
+impl TerminalCanvasBuilder {
+  /// A new terminal canvas builder will be 80x48 pixels (80x24 terminal
+  /// cells) by default and clear to black.
+  pub fn new() -> Self {
+    return Self {
+      dimensions: (80, 48),
+      clear_color: [0.0, 0.0, 0.0],
+    };
+  }
+
+  /// Specify the dimensions of the canvas in pixels. Every terminal cell
+  /// displays two vertically stacked pixels.
+  pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
+    self.dimensions = (width, height);
+    return self;
+  }
+
+  /// The color used when clearing the canvas.
+  pub fn with_clear_color(mut self, color: [f32; 3]) -> Self {
+    self.clear_color = color;
+    return self;
+  }
+
+  /// Builds a cleared terminal canvas.
+  pub fn build(self) -> TerminalCanvas {
+    let (width, height) = self.dimensions;
+    let clear_color = to_terminal_color(self.clear_color);
+    return TerminalCanvas {
+      width,
+      height,
+      clear_color,
+      pixels: vec![clear_color; (width * height) as usize],
+    };
+  }
+}
+
+/// A CPU side pixel buffer that triangles can be rasterized into and then
+/// presented to a terminal. Triangles are drawn in the order they're
+/// submitted and are expected to be in normalized device coordinates once
+/// transformed, where (-1, -1) is the bottom left of the canvas.
+pub struct TerminalCanvas {
+  width: u32,
+  height: u32,
+  clear_color: TerminalColor,
+  pixels: Vec<TerminalColor>,
+}
+
+impl TerminalCanvas {
+  /// The (width, height) of the canvas in pixels.
+  pub fn dimensions(&self) -> (u32, u32) {
+    return (self.width, self.height);
+  }
+
+  /// Resets every pixel within the canvas to the clear color.
+  pub fn clear(&mut self) {
+    let clear_color = self.clear_color;
+    self
+      .pixels
+      .iter_mut()
+      .for_each(|pixel| *pixel = clear_color);
+  }
+
+  /// Get the color of the pixel at the given coordinates, where (0, 0) is the
+  /// top left of the canvas.
+  pub fn pixel(&self, x: u32, y: u32) -> Option<TerminalColor> {
+    if x >= self.width || y >= self.height {
+      return None;
+    }
+    return Some(self.pixels[(y * self.width + x) as usize]);
+  }
+
+  /// Rasterizes a single triangle, interpolating the vertex colors across
+  /// its surface.
+  pub fn draw_triangle(
+    &mut self,
+    vertices: &[Vertex; 3],
+    transform: &[[f32; 4]; 4],
+  ) {
+    let (width, height) = (self.width as f32, self.height as f32);
+
+    // Map normalized device coordinates into pixel space with y pointing down.
+    let points = vertices.map(|vertex| {
+      let [x, y, _] = transform_position(transform, vertex.position);
+      return [(x + 1.0) * 0.5 * width, (1.0 - y) * 0.5 * height];
+    });
+
+    let area = edge_function(points[0], points[1], points[2]);
+    if area.abs() <= f32::EPSILON {
+      return;
+    }
+
+    let min_x = points.iter().map(|p| p[0]).fold(f32::MAX, f32::min);
+    let max_x = points.iter().map(|p| p[0]).fold(f32::MIN, f32::max);
+    let min_y = points.iter().map(|p| p[1]).fold(f32::MAX, f32::min);
+    let max_y = points.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
+
+    let start_x = min_x.floor().max(0.0) as u32;
+    let end_x = max_x.ceil().min(width) as u32;
+    let start_y = min_y.floor().max(0.0) as u32;
+    let end_y = max_y.ceil().min(height) as u32;
+
+    for y in start_y..end_y {
+      for x in start_x..end_x {
+        let sample = [x as f32 + 0.5, y as f32 + 0.5];
+        let w0 = edge_function(points[1], points[2], sample) / area;
+        let w1 = edge_function(points[2], points[0], sample) / area;
+        let w2 = edge_function(points[0], points[1], sample) / area;
+
+        // The weights all share the sign of the area when the sample is
+        // inside of the triangle, which makes this winding independent.
+        if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+          continue;
+        }
+
+        let mut color = [0.0; 3];
+        for (channel, value) in color.iter_mut().enumerate() {
+          *value = vertices[0].color[channel] * w0
+            + vertices[1].color[channel] * w1
+            + vertices[2].color[channel] * w2;
+        }
+
+        self.pixels[(y * self.width + x) as usize] = to_terminal_color(color);
+      }
+    }
+  }
+
+  /// Rasterizes every triangle within the mesh. The vertices (Or indices, for
+  /// indexed meshes) of the mesh are expected to be laid out as a triangle
+  /// list.
+  pub fn draw_mesh(&mut self, mesh: &Mesh, transform: &[[f32; 4]; 4]) {
+    let vertices = mesh.vertices();
+    match mesh.indices() {
+      Some(indices) => {
+        for triangle in indices.to_vec().chunks_exact(3) {
+          let vertex = |corner: usize| vertices[triangle[corner] as usize];
+          self.draw_triangle(&[vertex(0), vertex(1), vertex(2)], transform);
+        }
+      }
+      None => {
+        for triangle in vertices.chunks_exact(3) {
+          self
+            .draw_triangle(&[triangle[0], triangle[1], triangle[2]], transform);
+        }
+      }
+    }
+  }
+
+  /// Encodes the canvas as a frame of 24 bit ANSI colored half blocks. The
+  /// frame begins by moving the cursor to the top left of the terminal so
+  /// that consecutive frames overwrite each other.
+  pub fn to_ansi_frame(&self) -> String {
+    let mut frame = String::from("\x1B[H");
+
+    for row in (0..self.height).step_by(2) {
+      let mut last_colors: Option<(TerminalColor, TerminalColor)> = None;
+
+      for x in 0..self.width {
+        let top = self.pixels[(row * self.width + x) as usize];
+        let bottom = self.pixel(x, row + 1).unwrap_or(self.clear_color);
+
+        // Only emit escape codes when the colors of the cell change.
+        if last_colors != Some((top, bottom)) {
+          frame.push_str(&format!(
+            "\x1B[38;2;{};{};{}m\x1B[48;2;{};{};{}m",
+            top[0], top[1], top[2], bottom[0], bottom[1], bottom[2]
+          ));
+          last_colors = Some((top, bottom));
+        }
+        frame.push(HALF_BLOCK);
+      }
+
+      frame.push_str("\x1B[0m\n");
+    }
+
+    return frame;
+  }
+
+  /// Writes the current frame to the given output and flushes it.
+  pub fn present<Output: Write>(
+    &self,
+    output: &mut Output,
+  ) -> std::io::Result<()> {
+    output.write_all(self.to_ansi_frame().as_bytes())?;
+    return output.flush();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    TerminalCanvasBuilder,
+    HALF_BLOCK,
+  };
+  use crate::render::vertex::VertexBuilder;
+
+  const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+  ];
+
+  #[test]
+  fn terminal_canvas_builder_default_state() {
+    let canvas = TerminalCanvasBuilder::new().build();
+    assert_eq!(canvas.dimensions(), (80, 48));
+    assert_eq!(canvas.pixel(0, 0), Some([0, 0, 0]));
+    assert_eq!(canvas.pixel(80, 0), None);
+  }
+
+  #[test]
+  fn terminal_canvas_rasterizes_triangles() {
+    let mut canvas = TerminalCanvasBuilder::new()
+      .with_dimensions(10, 10)
+      .with_clear_color([0.0, 0.0, 1.0])
+      .build();
+
+    let vertex = |position| {
+      return VertexBuilder::new()
+        .with_position(position)
+        .with_color([1.0, 0.0, 0.0])
+        .build();
+    };
+
+    // Counter clockwise and clockwise triangles covering the left half of
+    // the canvas.
+    canvas.draw_triangle(
+      &[
+        vertex([-1.0, -1.0, 0.0]),
+        vertex([0.0, -1.0, 0.0]),
+        vertex([-1.0, 1.0, 0.0]),
+      ],
+      &IDENTITY,
+    );
+    canvas.draw_triangle(
+      &[
+        vertex([0.0, -1.0, 0.0]),
+        vertex([-1.0, 1.0, 0.0]),
+        vertex([0.0, 1.0, 0.0]),
+      ],
+      &IDENTITY,
+    );
+
+    assert_eq!(canvas.pixel(2, 5), Some([255, 0, 0]));
+    assert_eq!(canvas.pixel(7, 5), Some([0, 0, 255]));
+
+    canvas.clear();
+    assert_eq!(canvas.pixel(2, 5), Some([0, 0, 255]));
+  }
+
+  #[test]
+  fn terminal_canvas_applies_transforms() {
+    let mut canvas =
+      TerminalCanvasBuilder::new().with_dimensions(10, 10).build();
+    let vertex = |position| {
+      return VertexBuilder::new()
+        .with_position(position)
+        .with_color([0.0, 1.0, 0.0])
+        .build();
+    };
+
+    // Translate a small triangle from the bottom left into the top right.
+    let mut translation = IDENTITY;
+    translation[3] = [1.5, 1.5, 0.0, 1.0];
+
+    canvas.draw_triangle(
+      &[
+        vertex([-1.0, -1.0, 0.0]),
+        vertex([-0.5, -1.0, 0.0]),
+        vertex([-1.0, -0.5, 0.0]),
+      ],
+      &translation,
+    );
+
+    assert_eq!(canvas.pixel(8, 1), Some([0, 255, 0]));
+    assert_eq!(canvas.pixel(0, 9), Some([0, 0, 0]));
+  }
+
+  #[test]
+  fn terminal_canvas_encodes_half_blocks() {
+    let canvas = TerminalCanvasBuilder::new().with_dimensions(4, 3).build();
+    let frame = canvas.to_ansi_frame();
+
+    assert!(frame.starts_with("\x1B[H"));
+    assert_eq!(frame.matches(HALF_BLOCK).count(), 8);
+    assert_eq!(frame.matches('\n').count(), 2);
+    // The canvas is a single color, so each row only changes colors once.
+    assert_eq!(frame.matches("\x1B[38;2;0;0;0m").count(), 2);
+  }
+}
@@ -0,0 +1,718 @@
+//! Text rendering built on top of font atlases. Glyphs are rasterized from a
+//! TTF or OTF font into a single channel atlas, laid out into lines, and then
+//! converted into colored geometry that can be drawn with any pipeline that
+//! accepts the standard lambda vertex layout.
+
+use std::collections::HashMap;
+
+use lambda_platform::fontdue::{
+  Font,
+  RasterizedGlyph,
+};
+
+use super::{
+  buffer::{
+    Buffer,
+    BufferBuilder,
+  },
+  color::Color,
+  command::RenderCommand,
+  mesh::{
+    Mesh,
+    MeshBuilder,
+  },
+  vertex::{
+    Vertex,
+    VertexAttribute,
+    VertexElement,
+  },
+  ColorFormat,
+  RenderContext,
+  ResourceId,
+};
+
+/// The characters rasterized into an atlas by default (Printable ASCII).
+const DEFAULT_CHARACTERS: &str = " !\"#$%&'()*+,-./0123456789:;<=>?@\
+  ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
+/// The character drawn in place of characters missing from the atlas.
+const REPLACEMENT_CHARACTER: char = '?';
+
+/// Empty pixels placed between glyphs within the atlas.
+const ATLAS_PADDING: u32 = 1;
+
+/// Coverage at or above this value is considered part of the glyph when
+/// generating geometry.
+const COVERAGE_THRESHOLD: u8 = 128;
+
+// -------------------------------- FONT ATLAS ---------------------------------
+
+/// The location and metrics of a glyph within a font atlas. All metrics are
+/// in pixels at the size the atlas was built with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glyph {
+  /// The top left corner of the glyph within the atlas.
+  pub atlas_position: (u32, u32),
+  /// The (width, height) of the glyph within the atlas.
+  pub size: (u32, u32),
+  /// Offset from the pen position on the baseline to the top left corner of
+  /// the glyph, with y pointing down.
+  pub offset: (f32, f32),
+  /// How far the pen moves horizontally after the glyph.
+  pub advance: f32,
+}
+
+/// Builder for font atlases.
+pub struct FontAtlasBuilder {
+  font_size: f32,
+  characters: String,
+  atlas_width: u32,
+}
+
+impl FontAtlasBuilder {
+  /// A new font atlas builder will rasterize the printable ASCII characters
+  /// at 32 pixels into an atlas that is 512 pixels wide.
+  pub fn new() -> Self {
+    return Self {
+      font_size: 32.0,
+      characters: DEFAULT_CHARACTERS.to_string(),
+      atlas_width: 512,
+    };
+  }
+
+  /// The size in pixels that glyphs are rasterized at.
+  pub fn with_font_size(mut self, font_size: f32) -> Self {
+    self.font_size = font_size;
+    return self;
+  }
+
+  /// The characters to rasterize into the atlas.
+  pub fn with_characters(mut self, characters: &str) -> Self {
+    self.characters = characters.to_string();
+    return self;
+  }
+
+  /// The width of the atlas in pixels. The height of the atlas grows to fit
+  /// all of the glyphs.
+  pub fn with_atlas_width(mut self, atlas_width: u32) -> Self {
+    self.atlas_width = atlas_width;
+    return self;
+  }
+
+  /// Builds a font atlas from the TTF or OTF file at the given path.
+  pub fn build_from_file(self, path: &str) -> Result<FontAtlas, String> {
+    let font = Font::from_file(path)?;
+    return self.build_from_font(&font);
+  }
+
+  /// Builds a font atlas from the raw bytes of a TTF or OTF file.
+  pub fn build_from_bytes(self, bytes: &[u8]) -> Result<FontAtlas, String> {
+    let font = Font::from_bytes(bytes)?;
+    return self.build_from_font(&font);
+  }
+
+  fn build_from_font(self, font: &Font) -> Result<FontAtlas, String> {
+    let (ascent, line_height) = match font.line_metrics(self.font_size) {
+      Some(metrics) => (metrics.ascent, metrics.line_height),
+      None => (self.font_size, self.font_size),
+    };
+
+    let mut glyphs: Vec<(char, RasterizedGlyph)> = vec![];
+    for character in self.characters.chars() {
+      if glyphs.iter().all(|(existing, _)| *existing != character) {
+        glyphs.push((character, font.rasterize(character, self.font_size)));
+      }
+    }
+
+    return self.build_from_glyphs(glyphs, ascent, line_height);
+  }
+
+  /// Packs rasterized glyphs into rows of the atlas, tallest first.
+  fn build_from_glyphs(
+    self,
+    mut rasterized_glyphs: Vec<(char, RasterizedGlyph)>,
+    ascent: f32,
+    line_height: f32,
+  ) -> Result<FontAtlas, String> {
+    rasterized_glyphs.sort_by(|(a_char, a), (b_char, b)| {
+      return b.height.cmp(&a.height).then(a_char.cmp(b_char));
+    });
+
+    let mut placements = vec![];
+    let (mut x, mut y, mut row_height) = (ATLAS_PADDING, ATLAS_PADDING, 0);
+
+    for (character, glyph) in rasterized_glyphs.iter() {
+      let (width, height) = (glyph.width as u32, glyph.height as u32);
+      if width + ATLAS_PADDING * 2 > self.atlas_width {
+        return Err(format!(
+          "The glyph for {:?} is {} pixels wide and can't fit in an atlas {} \
+           pixels wide.",
+          character, width, self.atlas_width
+        ));
+      }
+
+      if x + width + ATLAS_PADDING > self.atlas_width {
+        x = ATLAS_PADDING;
+        y += row_height + ATLAS_PADDING;
+        row_height = 0;
+      }
+
+      placements.push((x, y));
+      x += width + ATLAS_PADDING;
+      row_height = row_height.max(height);
+    }
+
+    let dimensions = (self.atlas_width, y + row_height + ATLAS_PADDING);
+    let mut pixels = vec![0; (dimensions.0 * dimensions.1) as usize];
+    let mut glyphs = HashMap::new();
+
+    for ((character, glyph), (x, y)) in
+      rasterized_glyphs.into_iter().zip(placements)
+    {
+      for row in 0..glyph.height {
+        let source = row * glyph.width;
+        let destination =
+          ((y as usize + row) * dimensions.0 as usize) + x as usize;
+        pixels[destination..destination + glyph.width]
+          .copy_from_slice(&glyph.coverage[source..source + glyph.width]);
+      }
+
+      glyphs.insert(
+        character,
+        Glyph {
+          atlas_position: (x, y),
+          size: (glyph.width as u32, glyph.height as u32),
+          offset: (
+            glyph.offset_x as f32,
+            -(glyph.offset_y as f32 + glyph.height as f32),
+          ),
+          advance: glyph.advance,
+        },
+      );
+    }
+
+    return Ok(FontAtlas {
+      font_size: self.font_size,
+      dimensions,
+      pixels,
+      glyphs,
+      ascent,
+      line_height,
+    });
+  }
+}
+
+/// Glyphs rasterized into a single channel coverage atlas along with the
+/// metrics needed to lay them out.
+#[derive(Clone, Debug)]
+pub struct FontAtlas {
+  font_size: f32,
+  dimensions: (u32, u32),
+  pixels: Vec<u8>,
+  glyphs: HashMap<char, Glyph>,
+  ascent: f32,
+  line_height: f32,
+}
+
+impl FontAtlas {
+  /// The size in pixels that the glyphs were rasterized at.
+  pub fn font_size(&self) -> f32 {
+    return self.font_size;
+  }
+
+  /// The (width, height) of the atlas in pixels.
+  pub fn dimensions(&self) -> (u32, u32) {
+    return self.dimensions;
+  }
+
+  /// Row major coverage values of the atlas, where 0 is empty and 255 is
+  /// fully covered.
+  pub fn pixels(&self) -> &[u8] {
+    return &self.pixels;
+  }
+
+  /// The coverage of the atlas at the given pixel.
+  pub fn coverage(&self, x: u32, y: u32) -> u8 {
+    if x >= self.dimensions.0 || y >= self.dimensions.1 {
+      return 0;
+    }
+    return self.pixels[(y * self.dimensions.0 + x) as usize];
+  }
+
+  /// Get the glyph for a character if it was rasterized into the atlas.
+  pub fn glyph(&self, character: char) -> Option<&Glyph> {
+    return self.glyphs.get(&character);
+  }
+
+  /// The distance from the top of a line to its baseline.
+  pub fn ascent(&self) -> f32 {
+    return self.ascent;
+  }
+
+  /// The distance between the baselines of consecutive lines.
+  pub fn line_height(&self) -> f32 {
+    return self.line_height;
+  }
+}
+
+// ------------------------------- TEXT RENDERER -------------------------------
+
+/// Horizontal alignment of each line of text relative to the position the
+/// text is drawn at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextAlignment {
+  /// Lines start at the position.
+  Left,
+  /// Lines are centered on the position.
+  Center,
+}
+
+/// A glyph that has been placed by the text layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PositionedGlyph {
+  pub character: char,
+  /// The top left corner of the glyph in pixels.
+  pub position: (f32, f32),
+  pub glyph: Glyph,
+}
+
+/// Lays out strings with a font atlas and turns them into geometry and
+/// render commands. Positions are in points from the top left of the screen
+/// and are converted into the renderer's clip space, where y points down. A
+/// point is one pixel unless the renderer is given the scale factor of the
+/// window as its pixels per point.
+#[derive(Clone, Copy, Debug)]
+pub struct TextRenderer {
+  screen_size: (u32, u32),
+  scale: f32,
+  pixels_per_point: f32,
+  color: [f32; 3],
+  alignment: TextAlignment,
+  wrap_width: Option<f32>,
+}
+
+impl TextRenderer {
+  /// Creates a text renderer for a screen of the given size in pixels. Text
+  /// is white, left aligned, unscaled, and doesn't wrap by default.
+  pub fn new(width: u32, height: u32) -> Self {
+    return Self {
+      screen_size: (width, height),
+      scale: 1.0,
+      pixels_per_point: 1.0,
+      color: [1.0, 1.0, 1.0],
+      alignment: TextAlignment::Left,
+      wrap_width: None,
+    };
+  }
+
+  /// Update the size of the screen that text is rendered to.
+  pub fn with_screen_size(mut self, width: u32, height: u32) -> Self {
+    self.screen_size = (width, height);
+    return self;
+  }
+
+  /// Scale applied to the size the atlas was rasterized at.
+  pub fn with_scale(mut self, scale: f32) -> Self {
+    self.scale = scale;
+    return self;
+  }
+
+  /// The number of pixels in a point, which is usually the scale factor of
+  /// the window so that text keeps its size on high DPI displays.
+  pub fn with_pixels_per_point(mut self, pixels_per_point: f32) -> Self {
+    self.pixels_per_point = pixels_per_point;
+    return self;
+  }
+
+  /// The color of the text.
+  pub fn with_color(mut self, color: impl Into<Color>) -> Self {
+    self.color = color.into().to_rgb();
+    return self;
+  }
+
+  /// The alignment of each line of text.
+  pub fn with_alignment(mut self, alignment: TextAlignment) -> Self {
+    self.alignment = alignment;
+    return self;
+  }
+
+  /// Wrap words onto a new line once a line is wider than the given width in
+  /// points. Words wider than the width are placed on their own line.
+  pub fn with_wrap_width(mut self, wrap_width: f32) -> Self {
+    self.wrap_width = Some(wrap_width);
+    return self;
+  }
+
+  /// Lays out the text with the top of the first line at the position.
+  /// Glyphs are positioned in pixels.
+  pub fn layout(
+    &self,
+    atlas: &FontAtlas,
+    text: &str,
+    position: (f32, f32),
+  ) -> Vec<PositionedGlyph> {
+    let mut positioned = vec![];
+    let scale = self.pixel_scale();
+    let position = (
+      position.0 * self.pixels_per_point,
+      position.1 * self.pixels_per_point,
+    );
+
+    for (line_index, line) in self.wrap_lines(atlas, text).iter().enumerate() {
+      let width = self.measure(atlas, line);
+      let mut pen_x = match self.alignment {
+        TextAlignment::Left => position.0,
+        TextAlignment::Center => position.0 - width / 2.0,
+      };
+      let baseline = position.1
+        + (atlas.ascent() + atlas.line_height() * line_index as f32) * scale;
+
+      for character in line.chars() {
+        let glyph = match self.glyph_for(atlas, character) {
+          Some(glyph) => *glyph,
+          None => continue,
+        };
+
+        positioned.push(PositionedGlyph {
+          character,
+          position: (
+            pen_x + glyph.offset.0 * scale,
+            baseline + glyph.offset.1 * scale,
+          ),
+          glyph,
+        });
+        pen_x += glyph.advance * scale;
+      }
+    }
+
+    return positioned;
+  }
+
+  /// Builds a mesh containing colored quads for every covered pixel run of
+  /// the glyphs in the text. The mesh uses the same vertex attributes as
+  /// meshes loaded from OBJ files.
+  pub fn build_mesh(
+    &self,
+    atlas: &FontAtlas,
+    text: &str,
+    position: (f32, f32),
+  ) -> Mesh {
+    let mut mesh_builder = MeshBuilder::new();
+    let scale = self.pixel_scale();
+
+    for positioned in self.layout(atlas, text, position) {
+      let (atlas_x, atlas_y) = positioned.glyph.atlas_position;
+      let (width, height) = positioned.glyph.size;
+
+      for row in 0..height {
+        let mut column = 0;
+        while column < width {
+          if atlas.coverage(atlas_x + column, atlas_y + row)
+            < COVERAGE_THRESHOLD
+          {
+            column += 1;
+            continue;
+          }
+
+          // Merge horizontally adjacent covered pixels into a single quad.
+          let start = column;
+          while column < width
+            && atlas.coverage(atlas_x + column, atlas_y + row)
+              >= COVERAGE_THRESHOLD
+          {
+            column += 1;
+          }
+
+          let left = positioned.position.0 + start as f32 * scale;
+          let right = positioned.position.0 + column as f32 * scale;
+          let top = positioned.position.1 + row as f32 * scale;
+          let bottom = top + scale;
+          self.push_quad(&mut mesh_builder, left, top, right, bottom);
+        }
+      }
+    }
+
+    mesh_builder.with_attributes(vec![
+      VertexAttribute {
+        location: 0,
+        offset: 0,
+        element: VertexElement {
+          format: ColorFormat::Rgb32Sfloat,
+          offset: 0,
+        },
+      },
+      VertexAttribute {
+        location: 1,
+        offset: 0,
+        element: VertexElement {
+          format: ColorFormat::Rgb32Sfloat,
+          offset: 12,
+        },
+      },
+      VertexAttribute {
+        location: 2,
+        offset: 0,
+        element: VertexElement {
+          format: ColorFormat::Rgb32Sfloat,
+          offset: 24,
+        },
+      },
+    ]);
+
+    return mesh_builder.build();
+  }
+
+  /// Builds the text into a vertex buffer on the GPU. The buffer can be
+  /// attached to a pipeline and drawn with the commands from draw_commands.
+  pub fn build_buffer(
+    &self,
+    render_context: &mut RenderContext,
+    atlas: &FontAtlas,
+    text: &str,
+    position: (f32, f32),
+  ) -> Result<(Buffer, Mesh), &'static str> {
+    let mesh = self.build_mesh(atlas, text, position);
+    let buffer = BufferBuilder::build_from_mesh(&mesh, render_context)?;
+    return Ok((buffer, mesh));
+  }
+
+  /// Commands for drawing a text mesh whose vertex buffer has been attached
+  /// to the pipeline at the given buffer index.
+  pub fn draw_commands(
+    &self,
+    pipeline: ResourceId,
+    buffer: u32,
+    mesh: &Mesh,
+  ) -> Vec<RenderCommand> {
+    return vec![
+      RenderCommand::BindVertexBuffer { pipeline, buffer },
+      RenderCommand::Draw {
+        vertices: 0..mesh.vertices().len() as u32,
+      },
+    ];
+  }
+
+  /// The glyph to draw for a character, falling back to the replacement
+  /// character when the atlas doesn't contain it.
+  fn glyph_for<'atlas>(
+    &self,
+    atlas: &'atlas FontAtlas,
+    character: char,
+  ) -> Option<&'atlas Glyph> {
+    return atlas
+      .glyph(character)
+      .or_else(|| atlas.glyph(REPLACEMENT_CHARACTER));
+  }
+
+  /// The number of pixels that a pixel of the atlas covers on screen.
+  fn pixel_scale(&self) -> f32 {
+    return self.scale * self.pixels_per_point;
+  }
+
+  /// The width of a single line of text in pixels.
+  fn measure(&self, atlas: &FontAtlas, line: &str) -> f32 {
+    return line
+      .chars()
+      .filter_map(|character| self.glyph_for(atlas, character))
+      .map(|glyph| glyph.advance * self.pixel_scale())
+      .sum();
+  }
+
+  /// Splits the text into the lines that will be drawn.
+  fn wrap_lines(&self, atlas: &FontAtlas, text: &str) -> Vec<String> {
+    let mut lines = vec![];
+
+    for paragraph in text.split('\n') {
+      let wrap_width = match self.wrap_width {
+        Some(wrap_width) => wrap_width * self.pixels_per_point,
+        None => {
+          lines.push(paragraph.to_string());
+          continue;
+        }
+      };
+
+      let mut line = String::new();
+      for word in paragraph.split(' ') {
+        let candidate = match line.is_empty() {
+          true => word.to_string(),
+          false => format!("{} {}", line, word),
+        };
+
+        if !line.is_empty() && self.measure(atlas, &candidate) > wrap_width {
+          lines.push(line);
+          line = word.to_string();
+        } else {
+          line = candidate;
+        }
+      }
+      lines.push(line);
+    }
+
+    return lines;
+  }
+
+  /// Pushes two triangles covering the rectangle given in pixels.
+  fn push_quad(
+    &self,
+    mesh_builder: &mut MeshBuilder,
+    left: f32,
+    top: f32,
+    right: f32,
+    bottom: f32,
+  ) {
+    let (width, height) =
+      (self.screen_size.0 as f32, self.screen_size.1 as f32);
+    let vertex = |x: f32, y: f32| {
+      return Vertex {
+        position: [x / width * 2.0 - 1.0, y / height * 2.0 - 1.0, 0.0],
+        normal: [0.0, 0.0, 1.0],
+        color: self.color,
+        tex_coords: [0.0, 0.0],
+        tangent: [0.0, 0.0, 0.0, 0.0],
+      };
+    };
+
+    mesh_builder
+      .with_vertex(vertex(left, top))
+      .with_vertex(vertex(left, bottom))
+      .with_vertex(vertex(right, top))
+      .with_vertex(vertex(right, top))
+      .with_vertex(vertex(left, bottom))
+      .with_vertex(vertex(right, bottom));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use lambda_platform::fontdue::RasterizedGlyph;
+
+  use super::{
+    FontAtlas,
+    FontAtlasBuilder,
+    TextAlignment,
+    TextRenderer,
+  };
+  use crate::render::ResourceId;
+
+  /// Builds an atlas of solid 4x8 glyphs that sit on the baseline and advance
+  /// the pen by 5 pixels.
+  fn make_atlas(characters: &str) -> FontAtlas {
+    let glyphs = characters
+      .chars()
+      .map(|character| {
+        return (
+          character,
+          RasterizedGlyph {
+            width: 4,
+            height: 8,
+            offset_x: 0,
+            offset_y: 0,
+            advance: 5.0,
+            coverage: vec![255; 32],
+          },
+        );
+      })
+      .collect();
+
+    return FontAtlasBuilder::new()
+      .with_atlas_width(12)
+      .build_from_glyphs(glyphs, 8.0, 10.0)
+      .unwrap();
+  }
+
+  #[test]
+  fn font_atlas_packs_glyphs_into_rows() {
+    let atlas = make_atlas("abc");
+
+    // Two glyphs fit per row with padding.
+    assert_eq!(atlas.dimensions(), (12, 19));
+    assert_eq!(atlas.glyph('a').unwrap().atlas_position, (1, 1));
+    assert_eq!(atlas.glyph('b').unwrap().atlas_position, (6, 1));
+    assert_eq!(atlas.glyph('c').unwrap().atlas_position, (1, 10));
+    assert_eq!(atlas.glyph('a').unwrap().offset, (0.0, -8.0));
+    assert_eq!(atlas.coverage(1, 1), 255);
+    assert_eq!(atlas.coverage(0, 0), 0);
+    assert!(atlas.glyph('d').is_none());
+
+    let too_narrow = FontAtlasBuilder::new()
+      .with_atlas_width(4)
+      .build_from_glyphs(
+        vec![(
+          'a',
+          RasterizedGlyph {
+            width: 4,
+            height: 1,
+            offset_x: 0,
+            offset_y: 0,
+            advance: 4.0,
+            coverage: vec![255; 4],
+          },
+        )],
+        1.0,
+        1.0,
+      );
+    assert!(too_narrow.is_err());
+  }
+
+  #[test]
+  fn text_layout_aligns_scales_and_wraps() {
+    let atlas = make_atlas("ab ?");
+
+    let renderer = TextRenderer::new(100, 100);
+    let glyphs = renderer.layout(&atlas, "ab\nb", (10.0, 0.0));
+    let positions: Vec<(f32, f32)> =
+      glyphs.iter().map(|glyph| glyph.position).collect();
+    assert_eq!(positions, vec![(10.0, 0.0), (15.0, 0.0), (10.0, 10.0)]);
+
+    let centered = renderer
+      .with_alignment(TextAlignment::Center)
+      .with_scale(2.0)
+      .layout(&atlas, "ab", (50.0, 0.0));
+    assert_eq!(centered[0].position, (40.0, 0.0));
+    assert_eq!(centered[1].position, (50.0, 0.0));
+
+    // "ab ab" is 25 pixels wide, so it wraps onto two lines.
+    let wrapped =
+      renderer
+        .with_wrap_width(20.0)
+        .layout(&atlas, "ab ab", (0.0, 0.0));
+    assert_eq!(wrapped.len(), 4);
+    assert_eq!(wrapped[2].position, (0.0, 10.0));
+
+    // Points are scaled into pixels, including the wrap width.
+    let high_dpi = renderer
+      .with_pixels_per_point(2.0)
+      .with_wrap_width(20.0)
+      .layout(&atlas, "ab ab", (5.0, 0.0));
+    assert_eq!(high_dpi[1].position, (20.0, 0.0));
+    assert_eq!(high_dpi[2].position, (10.0, 20.0));
+
+    // Missing characters use the replacement character.
+    let replaced = renderer.layout(&atlas, "z", (0.0, 0.0));
+    assert_eq!(replaced[0].character, 'z');
+    assert_eq!(replaced[0].glyph, *atlas.glyph('?').unwrap());
+  }
+
+  #[test]
+  fn text_mesh_merges_covered_pixels_into_quads() {
+    let atlas = make_atlas("a");
+    let mesh = TextRenderer::new(100, 100)
+      .with_color([1.0, 0.0, 0.0])
+      .build_mesh(&atlas, "aa", (0.0, 0.0));
+
+    // One quad per row of each solid glyph.
+    assert_eq!(mesh.vertices().len(), 2 * 8 * 6);
+    assert_eq!(mesh.attributes().len(), 3);
+
+    let first = mesh.vertices()[0];
+    assert_eq!(first.position, [-1.0, -1.0, 0.0]);
+    assert_eq!(first.color, [1.0, 0.0, 0.0]);
+
+    let commands = TextRenderer::new(100, 100).draw_commands(
+      ResourceId::new(0, 0),
+      0,
+      &mesh,
+    );
+    assert_eq!(commands.len(), 2);
+  }
+}
@@ -0,0 +1,463 @@
+//! Staging & readback of buffer memory. Writes are staged in a ring of CPU
+//! visible memory and copied into their buffers at the start of the next
+//! frame, while reads are copied into CPU visible memory at the end of the
+//! next frame and handed back once the GPU has finished the frame.
+
+use std::rc::Rc;
+
+use lambda_platform::gfx::{
+  buffer::{
+    Buffer,
+    BufferBuilder,
+    Properties,
+    Usage,
+  },
+  command::BufferCopy,
+};
+
+use super::{
+  internal,
+  memory::{
+    AllocationId,
+    MemoryCategory,
+    MemoryTracker,
+  },
+  PlatformRenderCommand,
+};
+
+type InternalBuffer = Buffer<internal::RenderBackend>;
+type InternalGpu = internal::Gpu<internal::RenderBackend>;
+
+/// Receives the bytes of a buffer read, or why the read failed.
+pub type ReadCallback = Box<dyn FnOnce(Result<Vec<u8>, String>)>;
+
+/// The number of frames that can be submitted before the GPU finishes them.
+/// The render context waits on the previous frame before submitting the next.
+const FRAMES_IN_FLIGHT: usize = 2;
+
+/// Staged writes are aligned so that copies start on a word.
+const ALIGNMENT: usize = 4;
+
+/// Checks that a range of bytes lies within a buffer.
+pub(super) fn validate_range(
+  offset: usize,
+  length: usize,
+  buffer_length: usize,
+) -> Result<(), String> {
+  return match offset.checked_add(length) {
+    Some(end) if end <= buffer_length => Ok(()),
+    _ => Err(format!(
+      "{} bytes at offset {} is outside of the buffer of {} bytes.",
+      length, offset, buffer_length
+    )),
+  };
+}
+
+// ------------------------------- STAGING RING --------------------------------
+
+/// Hands out ranges of the ring. The ring is split into a segment per frame
+/// in flight, so that the segment being staged into is never one that the
+/// GPU is still copying from.
+#[derive(Debug)]
+struct RingAllocator {
+  segment_length: usize,
+  segment: usize,
+  cursor: usize,
+}
+
+impl RingAllocator {
+  fn new(length: usize) -> Self {
+    return Self {
+      segment_length: length / FRAMES_IN_FLIGHT,
+      segment: 0,
+      cursor: 0,
+    };
+  }
+
+  /// The offset of the range within the ring, or None if the current
+  /// segment doesn't have room for it.
+  fn allocate(&mut self, length: usize) -> Option<usize> {
+    let start = self.cursor.next_multiple_of(ALIGNMENT);
+    if start + length > self.segment_length {
+      return None;
+    }
+    self.cursor = start + length;
+    return Some(self.segment * self.segment_length + start);
+  }
+
+  /// The offset of the current segment within the ring.
+  fn segment_start(&self) -> usize {
+    return self.segment * self.segment_length;
+  }
+
+  /// Moves on to the segment of the next frame.
+  fn next_frame(&mut self) {
+    self.segment = (self.segment + 1) % FRAMES_IN_FLIGHT;
+    self.cursor = 0;
+  }
+}
+
+/// Groups the copies into one batch of regions per destination, in the order
+/// they were staged. Regions that continue the previous region of the batch
+/// in both buffers are merged, & a copy that overlaps a region already in
+/// the batch starts a new batch so that later writes land last.
+fn coalesce<Destination>(
+  copies: Vec<(Rc<Destination>, BufferCopy)>,
+) -> Vec<(Rc<Destination>, Vec<BufferCopy>)> {
+  let mut batches: Vec<(Rc<Destination>, Vec<BufferCopy>)> = vec![];
+  for (destination, copy) in copies {
+    let batch = batches
+      .iter_mut()
+      .rev()
+      .find(|(batched, _)| Rc::ptr_eq(batched, &destination))
+      .filter(|(_, regions)| {
+        return regions.iter().all(|region| {
+          return copy.dst + copy.size <= region.dst
+            || region.dst + region.size <= copy.dst;
+        });
+      });
+
+    match batch {
+      Some((_, regions)) => {
+        let last = regions.last_mut().expect("Batches are never empty.");
+        if last.src + last.size == copy.src && last.dst + last.size == copy.dst
+        {
+          last.size += copy.size;
+        } else {
+          regions.push(copy);
+        }
+      }
+      None => batches.push((destination, vec![copy])),
+    }
+  }
+  return batches;
+}
+
+/// CPU visible memory that writes to buffers are staged in. Writes are
+/// gathered on the CPU & flushed to the ring once per frame, and the copies
+/// out of it are batched into a copy per destination buffer.
+pub(super) struct StagingRing {
+  buffer: Rc<InternalBuffer>,
+  allocator: RingAllocator,
+  staged_bytes: Vec<u8>,
+  copies: Vec<(Rc<InternalBuffer>, BufferCopy)>,
+}
+
+impl StagingRing {
+  /// Allocates a ring of the length in bytes, of which half can be staged
+  /// into every frame.
+  pub(super) fn new(
+    gpu: &mut InternalGpu,
+    length: usize,
+  ) -> Result<Self, String> {
+    let buffer = BufferBuilder::new()
+      .with_length(length)
+      .with_usage(Usage::TRANSFER_SRC)
+      .with_properties(Properties::CPU_VISIBLE)
+      .build(gpu, vec![0u8; length])?;
+
+    return Ok(Self {
+      buffer: Rc::new(buffer),
+      allocator: RingAllocator::new(length),
+      staged_bytes: vec![],
+      copies: vec![],
+    });
+  }
+
+  /// Stages the bytes to be copied into the destination at the offset when
+  /// the next frame starts.
+  pub(super) fn stage(
+    &mut self,
+    destination: Rc<InternalBuffer>,
+    offset: usize,
+    bytes: &[u8],
+  ) -> Result<(), String> {
+    let staged_at = self.allocator.allocate(bytes.len()).ok_or_else(|| {
+      return format!(
+        "The staging buffer only has room for {} bytes per frame. Increase \
+         it with RenderContextBuilder::with_staging_buffer_size.",
+        self.allocator.segment_length
+      );
+    })?;
+
+    let start = staged_at - self.allocator.segment_start();
+    self.staged_bytes.resize(start, 0);
+    self.staged_bytes.extend_from_slice(bytes);
+    self.copies.push((
+      destination,
+      BufferCopy {
+        src: staged_at as u64,
+        dst: offset as u64,
+        size: bytes.len() as u64,
+      },
+    ));
+    return Ok(());
+  }
+
+  /// Flushes the bytes staged for the next frame to the ring & takes their
+  /// copies, then starts staging into the segment of the frame after it.
+  pub(super) fn take_copies(
+    &mut self,
+    gpu: &InternalGpu,
+  ) -> Vec<PlatformRenderCommand> {
+    let copies = std::mem::take(&mut self.copies);
+    let flushed = match self.staged_bytes.is_empty() {
+      true => Ok(()),
+      false => self.buffer.write_bytes(
+        gpu,
+        self.allocator.segment_start(),
+        &self.staged_bytes,
+      ),
+    };
+    self.staged_bytes.clear();
+    self.allocator.next_frame();
+
+    if let Err(error) = flushed {
+      logging::error!(
+        "Failed to flush {} staged writes: {}",
+        copies.len(),
+        error
+      );
+      return vec![];
+    }
+
+    return coalesce(copies)
+      .into_iter()
+      .map(|(destination, regions)| {
+        return PlatformRenderCommand::CopyBuffer {
+          source: self.buffer.clone(),
+          destination,
+          regions,
+        };
+      })
+      .collect();
+  }
+
+  /// Destroys the ring with the GPU that allocated it.
+  pub(super) fn destroy(mut self, gpu: &InternalGpu) {
+    self.copies.clear();
+    Rc::try_unwrap(self.buffer)
+      .expect("Failed to destroy the staging buffer.")
+      .destroy(gpu);
+  }
+}
+
+// --------------------------------- READBACK ----------------------------------
+
+/// A read whose bytes are copied into CPU visible memory.
+struct PendingRead {
+  buffer: Rc<InternalBuffer>,
+  allocation: AllocationId,
+  callback: ReadCallback,
+}
+
+impl PendingRead {
+  /// Hands the bytes to the callback & frees the CPU visible memory.
+  fn complete(self, gpu: &InternalGpu, memory: &mut MemoryTracker) {
+    let bytes = self
+      .buffer
+      .read_bytes(gpu, 0, self.buffer.length())
+      .map_err(|error| error.to_string());
+    let callback = self.free(gpu, memory);
+    callback(bytes);
+  }
+
+  /// Frees the CPU visible memory & returns the callback of the read.
+  fn free(self, gpu: &InternalGpu, memory: &mut MemoryTracker) -> ReadCallback {
+    memory.untrack(self.allocation);
+    Rc::try_unwrap(self.buffer)
+      .expect("Failed to destroy the readback buffer.")
+      .destroy(gpu);
+    return self.callback;
+  }
+}
+
+/// Reads of buffers that are waiting to be copied or on the GPU to finish.
+#[derive(Default)]
+pub(super) struct Readback {
+  queued: Vec<(PlatformRenderCommand, PendingRead)>,
+  in_flight: Vec<PendingRead>,
+}
+
+impl Readback {
+  /// Queues a read of the source buffer that's copied at the end of the next
+  /// frame.
+  pub(super) fn queue(
+    &mut self,
+    gpu: &mut InternalGpu,
+    memory: &mut MemoryTracker,
+    source: Rc<InternalBuffer>,
+    offset: usize,
+    length: usize,
+    callback: ReadCallback,
+  ) -> Result<(), String> {
+    if length == 0 {
+      return Err("Cannot read 0 bytes from a buffer.".to_string());
+    }
+
+    let buffer = Rc::new(
+      BufferBuilder::new()
+        .with_length(length)
+        .with_usage(Usage::TRANSFER_DST)
+        .with_properties(Properties::CPU_VISIBLE)
+        .build(gpu, vec![0u8; length])?,
+    );
+    let allocation =
+      memory.track(MemoryCategory::ReadbackBuffer, "readback", length as u64);
+    let copy = PlatformRenderCommand::CopyBuffer {
+      source,
+      destination: buffer.clone(),
+      regions: vec![BufferCopy {
+        src: offset as u64,
+        dst: 0,
+        size: length as u64,
+      }],
+    };
+    self.queued.push((
+      copy,
+      PendingRead {
+        buffer,
+        allocation,
+        callback,
+      },
+    ));
+    return Ok(());
+  }
+
+  /// Takes the copies of the reads queued for the frame being submitted.
+  pub(super) fn take_copies(&mut self) -> Vec<PlatformRenderCommand> {
+    let (copies, reads): (Vec<_>, Vec<_>) = self.queued.drain(..).unzip();
+    self.in_flight.extend(reads);
+    return copies;
+  }
+
+  /// Completes the reads of the previous frame. The GPU must have finished
+  /// the frame.
+  pub(super) fn complete(
+    &mut self,
+    gpu: &InternalGpu,
+    memory: &mut MemoryTracker,
+  ) {
+    for read in self.in_flight.drain(..) {
+      read.complete(gpu, memory);
+    }
+  }
+
+  /// Completes the reads in flight & fails the reads that were never copied.
+  /// The GPU must have finished every frame.
+  pub(super) fn destroy(
+    mut self,
+    gpu: &InternalGpu,
+    memory: &mut MemoryTracker,
+  ) {
+    self.complete(gpu, memory);
+    for (copy, read) in self.queued.drain(..) {
+      drop(copy);
+      let callback = read.free(gpu, memory);
+      callback(Err(
+        "The render context was destroyed before the buffer was read."
+          .to_string(),
+      ));
+    }
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::rc::Rc;
+
+  use lambda_platform::gfx::command::BufferCopy;
+
+  use super::{
+    coalesce,
+    validate_range,
+    RingAllocator,
+  };
+
+  fn copy(src: u64, dst: u64, size: u64) -> BufferCopy {
+    return BufferCopy { src, dst, size };
+  }
+
+  /// The regions of the batches as (src, dst, size).
+  fn regions<Destination>(
+    batches: &[(Rc<Destination>, Vec<BufferCopy>)],
+  ) -> Vec<Vec<(u64, u64, u64)>> {
+    return batches
+      .iter()
+      .map(|(_, regions)| {
+        return regions
+          .iter()
+          .map(|region| (region.src, region.dst, region.size))
+          .collect();
+      })
+      .collect();
+  }
+
+  #[test]
+  fn ring_segments_alternate_between_frames() {
+    let mut ring = RingAllocator::new(64);
+    assert_eq!(ring.allocate(6), Some(0));
+    assert_eq!(ring.allocate(8), Some(8));
+    assert_eq!(ring.allocate(32), None);
+
+    ring.next_frame();
+    assert_eq!(ring.allocate(32), Some(32));
+    assert_eq!(ring.allocate(1), None);
+
+    ring.next_frame();
+    assert_eq!(ring.allocate(4), Some(0));
+  }
+
+  #[test]
+  fn copies_are_batched_per_destination() {
+    let (sprites, text) = (Rc::new("sprites"), Rc::new("text"));
+    let batches = coalesce(vec![
+      (sprites.clone(), copy(0, 0, 8)),
+      (text.clone(), copy(8, 64, 4)),
+      (sprites.clone(), copy(12, 8, 4)),
+      (sprites.clone(), copy(16, 32, 4)),
+    ]);
+
+    assert_eq!(batches.len(), 2);
+    assert!(Rc::ptr_eq(&batches[0].0, &sprites));
+    assert_eq!(
+      regions(&batches),
+      vec![vec![(0, 0, 8), (12, 8, 4), (16, 32, 4)], vec![(8, 64, 4)]]
+    );
+  }
+
+  #[test]
+  fn contiguous_copies_are_merged() {
+    let sprites = Rc::new("sprites");
+    let batches = coalesce(vec![
+      (sprites.clone(), copy(0, 16, 8)),
+      (sprites.clone(), copy(8, 24, 4)),
+      (sprites.clone(), copy(12, 28, 4)),
+    ]);
+    assert_eq!(regions(&batches), vec![vec![(0, 16, 16)]]);
+  }
+
+  #[test]
+  fn overlapping_copies_land_in_order() {
+    let sprites = Rc::new("sprites");
+    let batches = coalesce(vec![
+      (sprites.clone(), copy(0, 0, 8)),
+      (sprites.clone(), copy(8, 4, 8)),
+      (sprites.clone(), copy(16, 32, 4)),
+    ]);
+    assert_eq!(
+      regions(&batches),
+      vec![vec![(0, 0, 8)], vec![(8, 4, 8), (16, 32, 4)]]
+    );
+  }
+
+  #[test]
+  fn ranges_are_validated_against_the_buffer() {
+    assert!(validate_range(0, 16, 16).is_ok());
+    assert!(validate_range(8, 8, 16).is_ok());
+    assert!(validate_range(8, 9, 16).is_err());
+    assert!(validate_range(usize::MAX, 1, 16).is_err());
+  }
+}
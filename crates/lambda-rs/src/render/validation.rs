@@ -0,0 +1,613 @@
+//! Validation of render commands before they're recorded. Validation walks
+//! the commands of a frame the way the GPU would, reporting every command that
+//! would record invalid GPU commands along with its index.
+
+use std::{
+  fmt,
+  ops::Range,
+};
+
+use super::{
+  command::RenderCommand,
+  pipeline::PipelineStage,
+  ColorFormat,
+  ResourceId,
+};
+
+/// A render command that failed validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandError {
+  /// The index of the command within the commands that were validated.
+  pub index: usize,
+  pub message: String,
+}
+
+impl fmt::Display for CommandError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    return write!(f, "Command {}: {}", self.index, self.message);
+  }
+}
+
+/// What validation needs to know about a render pipeline.
+#[derive(Debug, Clone, Default)]
+pub(super) struct PipelineLayout {
+  pub push_constants: Vec<(PipelineStage, Range<u32>)>,
+  /// The number of vertices every vertex buffer has room for.
+  pub vertex_counts: Vec<u32>,
+  /// The number of indices of the index buffer built alongside every vertex
+  /// buffer.
+  pub index_counts: Vec<Option<u32>>,
+  /// Whether or not the pipeline was built for a render pass with a stencil.
+  pub stencil_pass: bool,
+  /// The formats of the color attachments after the surface of the render
+  /// pass the pipeline was built for.
+  pub color_formats: Vec<ColorFormat>,
+}
+
+/// The resources that commands are validated against.
+pub(super) trait CommandResources {
+  fn has_render_pass(&self, id: ResourceId) -> bool;
+  fn render_pass_has_stencil(&self, id: ResourceId) -> bool;
+  /// The formats of the render pass's color attachments after the surface.
+  fn render_pass_color_formats(&self, id: ResourceId) -> Vec<ColorFormat>;
+  fn pipeline_layout(&self, id: ResourceId) -> Option<PipelineLayout>;
+}
+
+/// The state that commands have set while walking through them.
+struct Validator<'resources, Resources: CommandResources> {
+  resources: &'resources Resources,
+  errors: Vec<CommandError>,
+  render_pass_begun_at: Option<usize>,
+  render_pass_stencil: bool,
+  render_pass_color_formats: Vec<ColorFormat>,
+  pipeline: Option<(ResourceId, PipelineLayout)>,
+  vertex_count: Option<u32>,
+  index_count: Option<u32>,
+}
+
+impl<'resources, Resources: CommandResources> Validator<'resources, Resources> {
+  fn error(&mut self, index: usize, message: String) {
+    self.errors.push(CommandError { index, message });
+  }
+
+  fn pipeline_layout(
+    &mut self,
+    index: usize,
+    pipeline: ResourceId,
+  ) -> Option<PipelineLayout> {
+    let layout = self.resources.pipeline_layout(pipeline);
+    if layout.is_none() {
+      self.error(
+        index,
+        format!("Render pipeline {} isn't attached.", pipeline),
+      );
+    }
+    return layout;
+  }
+
+  /// Checks that a vertex buffer of the pipeline exists.
+  fn vertex_buffer(
+    &mut self,
+    index: usize,
+    pipeline: ResourceId,
+    buffer: u32,
+  ) -> Option<(PipelineLayout, usize)> {
+    let layout = self.pipeline_layout(index, pipeline)?;
+    if buffer as usize >= layout.vertex_counts.len() {
+      self.error(
+        index,
+        format!(
+          "Render pipeline {} has {} vertex buffers, so it doesn't have \
+           vertex buffer {}.",
+          pipeline,
+          layout.vertex_counts.len(),
+          buffer
+        ),
+      );
+      return None;
+    }
+    return Some((layout, buffer as usize));
+  }
+
+  /// Checks that a draw happens within a render pass with a pipeline set.
+  fn draw(&mut self, index: usize) -> bool {
+    if self.render_pass_begun_at.is_none() {
+      self.error(index, "Draws outside of a render pass.".to_string());
+      return false;
+    }
+    if self.pipeline.is_none() {
+      self.error(index, "Draws before a pipeline was set.".to_string());
+      return false;
+    }
+    return true;
+  }
+
+  fn validate(&mut self, index: usize, command: &RenderCommand) {
+    match command {
+      RenderCommand::SetViewports { .. }
+      | RenderCommand::SetScissors { .. } => {}
+      RenderCommand::BeginRenderPass { render_pass, .. } => {
+        if !self.resources.has_render_pass(*render_pass) {
+          self.error(
+            index,
+            format!("Render pass {} isn't attached.", render_pass),
+          );
+        }
+        if let Some(begun_at) = self.render_pass_begun_at {
+          self.error(
+            index,
+            format!(
+              "Begins a render pass while the render pass begun by command \
+               {} is still active.",
+              begun_at
+            ),
+          );
+        }
+        self.render_pass_begun_at = Some(index);
+        self.render_pass_stencil =
+          self.resources.render_pass_has_stencil(*render_pass);
+        self.render_pass_color_formats =
+          self.resources.render_pass_color_formats(*render_pass);
+      }
+      RenderCommand::EndRenderPass => {
+        if self.render_pass_begun_at.take().is_none() {
+          self.error(index, "Ends a render pass that never began.".to_string());
+        }
+      }
+      RenderCommand::SetPipeline { pipeline } => {
+        self.pipeline = self
+          .pipeline_layout(index, *pipeline)
+          .map(|layout| (*pipeline, layout));
+        let stencil_pass = self
+          .pipeline
+          .as_ref()
+          .map(|(_, layout)| layout.stencil_pass);
+        if self.render_pass_begun_at.is_some()
+          && stencil_pass.is_some_and(|stencil_pass| {
+            return stencil_pass != self.render_pass_stencil;
+          })
+        {
+          let (with, without) = match self.render_pass_stencil {
+            true => ("with", "without"),
+            false => ("without", "with"),
+          };
+          self.error(
+            index,
+            format!(
+              "Sets render pipeline {} within a render pass {} a stencil, \
+               but the pipeline was built for a render pass {} a stencil.",
+              pipeline, with, without
+            ),
+          );
+        }
+        let color_formats = self
+          .pipeline
+          .as_ref()
+          .map(|(_, layout)| layout.color_formats.clone());
+        if let (Some(_), Some(color_formats)) =
+          (self.render_pass_begun_at, color_formats)
+        {
+          if color_formats != self.render_pass_color_formats {
+            self.error(
+              index,
+              format!(
+                "Sets render pipeline {} within a render pass whose color \
+                 attachments after the surface are {:?}, but the pipeline \
+                 was built for a render pass with the color attachments \
+                 {:?}.",
+                pipeline, self.render_pass_color_formats, color_formats
+              ),
+            );
+          }
+        }
+      }
+      RenderCommand::SetStencilReference { .. } => {
+        if self.render_pass_begun_at.is_none() {
+          self.error(
+            index,
+            "Sets the stencil reference outside of a render pass.".to_string(),
+          );
+        } else if !self.render_pass_stencil {
+          self.error(
+            index,
+            "Sets the stencil reference within a render pass without a \
+             stencil."
+              .to_string(),
+          );
+        }
+      }
+      RenderCommand::PushConstants {
+        pipeline,
+        stage,
+        offset,
+        bytes,
+      } => {
+        let Some(layout) = self.pipeline_layout(index, *pipeline) else {
+          return;
+        };
+        let end = offset + (bytes.len() * std::mem::size_of::<u32>()) as u32;
+        let fits = layout.push_constants.iter().any(|(stages, range)| {
+          return stages.contains(*stage)
+            && range.start <= *offset
+            && end <= range.end;
+        });
+        if !fits {
+          self.error(
+            index,
+            format!(
+              "Pushes bytes {}..{} for the {:?} stage, but render pipeline \
+               {} only declares the push constants {:?}.",
+              offset, end, stage, pipeline, layout.push_constants
+            ),
+          );
+        }
+      }
+      RenderCommand::BindVertexBuffer { pipeline, buffer } => {
+        if let Some((current, _)) = &self.pipeline {
+          if current != pipeline {
+            self.error(
+              index,
+              format!(
+                "Binds a vertex buffer of render pipeline {} while render \
+                 pipeline {} is set, which may expect a different vertex \
+                 layout.",
+                pipeline, current
+              ),
+            );
+          }
+        }
+        self.vertex_count = self
+          .vertex_buffer(index, *pipeline, *buffer)
+          .map(|(layout, buffer)| layout.vertex_counts[buffer]);
+      }
+      RenderCommand::BindIndexBuffer { pipeline, buffer } => {
+        let Some((layout, buffer)) =
+          self.vertex_buffer(index, *pipeline, *buffer)
+        else {
+          self.index_count = None;
+          return;
+        };
+        self.index_count = layout.index_counts[buffer];
+        if self.index_count.is_none() {
+          self.error(
+            index,
+            format!(
+              "Vertex buffer {} of render pipeline {} was built without an \
+               index buffer.",
+              buffer, pipeline
+            ),
+          );
+        }
+      }
+      RenderCommand::Draw { vertices } => {
+        if !self.draw(index) {
+          return;
+        }
+        let has_buffers = self
+          .pipeline
+          .as_ref()
+          .is_some_and(|(_, layout)| !layout.vertex_counts.is_empty());
+        match self.vertex_count {
+          None if has_buffers => {
+            self.error(
+              index,
+              "Draws before a vertex buffer was bound.".to_string(),
+            );
+          }
+          Some(count) if vertices.end > count => {
+            self.error(
+              index,
+              format!(
+                "Draws vertices {:?}, but the bound vertex buffer only has \
+                 room for {} vertices.",
+                vertices, count
+              ),
+            );
+          }
+          _ => {}
+        }
+      }
+      RenderCommand::DrawIndexed { indices, .. } => {
+        if !self.draw(index) {
+          return;
+        }
+        match self.index_count {
+          None => {
+            self.error(
+              index,
+              "Draws indices before an index buffer was bound.".to_string(),
+            );
+          }
+          Some(count) if indices.end > count => {
+            self.error(
+              index,
+              format!(
+                "Draws indices {:?}, but the bound index buffer only has {} \
+                 indices.",
+                indices, count
+              ),
+            );
+          }
+          _ => {}
+        }
+      }
+      RenderCommand::UpdateVertexBuffer {
+        pipeline,
+        buffer,
+        vertices,
+      } => {
+        let Some((layout, buffer)) =
+          self.vertex_buffer(index, *pipeline, *buffer)
+        else {
+          return;
+        };
+        let count = layout.vertex_counts[buffer];
+        if vertices.len() > count as usize {
+          self.error(
+            index,
+            format!(
+              "Writes {} vertices into a vertex buffer with room for {} \
+               vertices.",
+              vertices.len(),
+              count
+            ),
+          );
+        }
+      }
+    }
+  }
+}
+
+/// Validates the commands in order, returning an error for every invalid
+/// command.
+pub(super) fn validate_commands(
+  commands: &[RenderCommand],
+  resources: &impl CommandResources,
+) -> Vec<CommandError> {
+  let mut validator = Validator {
+    resources,
+    errors: vec![],
+    render_pass_begun_at: None,
+    render_pass_stencil: false,
+    render_pass_color_formats: vec![],
+    pipeline: None,
+    vertex_count: None,
+    index_count: None,
+  };
+
+  for (index, command) in commands.iter().enumerate() {
+    validator.validate(index, command);
+  }
+
+  if let Some(begun_at) = validator.render_pass_begun_at {
+    validator.error(begun_at, "Begins a render pass that never ends.".into());
+  }
+  return validator.errors;
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::{
+    validate_commands,
+    CommandResources,
+    PipelineLayout,
+  };
+  use crate::render::{
+    command::RenderCommand,
+    pipeline::PipelineStage,
+    viewport::ViewportBuilder,
+    ColorFormat,
+    ResourceId,
+  };
+
+  struct Resources {
+    render_pass: ResourceId,
+    stencil_render_pass: ResourceId,
+    g_buffer_render_pass: ResourceId,
+    pipelines: HashMap<ResourceId, PipelineLayout>,
+  }
+
+  impl CommandResources for Resources {
+    fn has_render_pass(&self, id: ResourceId) -> bool {
+      return id == self.render_pass
+        || id == self.stencil_render_pass
+        || id == self.g_buffer_render_pass;
+    }
+
+    fn render_pass_has_stencil(&self, id: ResourceId) -> bool {
+      return id == self.stencil_render_pass;
+    }
+
+    fn render_pass_color_formats(&self, id: ResourceId) -> Vec<ColorFormat> {
+      return match id == self.g_buffer_render_pass {
+        true => g_buffer_formats(),
+        false => vec![],
+      };
+    }
+
+    fn pipeline_layout(&self, id: ResourceId) -> Option<PipelineLayout> {
+      return self.pipelines.get(&id).cloned();
+    }
+  }
+
+  fn resources() -> Resources {
+    let mut pipelines = HashMap::new();
+    pipelines.insert(
+      ResourceId::new(0, 0),
+      PipelineLayout {
+        push_constants: vec![(PipelineStage::VERTEX, 0..64)],
+        vertex_counts: vec![6],
+        index_counts: vec![Some(12)],
+        ..Default::default()
+      },
+    );
+    pipelines.insert(ResourceId::new(1, 0), PipelineLayout::default());
+    pipelines.insert(
+      ResourceId::new(3, 0),
+      PipelineLayout {
+        stencil_pass: true,
+        ..Default::default()
+      },
+    );
+    pipelines.insert(
+      ResourceId::new(4, 0),
+      PipelineLayout {
+        color_formats: g_buffer_formats(),
+        ..Default::default()
+      },
+    );
+    return Resources {
+      render_pass: ResourceId::new(0, 0),
+      stencil_render_pass: ResourceId::new(1, 0),
+      g_buffer_render_pass: ResourceId::new(2, 0),
+      pipelines,
+    };
+  }
+
+  fn g_buffer_formats() -> Vec<ColorFormat> {
+    return vec![ColorFormat::Rgba8Unorm, ColorFormat::Rgba16Sfloat];
+  }
+
+  fn begin() -> RenderCommand {
+    return begin_render_pass(ResourceId::new(0, 0));
+  }
+
+  fn begin_render_pass(render_pass: ResourceId) -> RenderCommand {
+    return RenderCommand::BeginRenderPass {
+      render_pass,
+      viewport: ViewportBuilder::new().build(800, 600),
+    };
+  }
+
+  #[test]
+  fn valid_commands_pass() {
+    let pipeline = ResourceId::new(0, 0);
+    let commands = vec![
+      begin(),
+      RenderCommand::SetPipeline { pipeline },
+      RenderCommand::PushConstants {
+        pipeline,
+        stage: PipelineStage::VERTEX,
+        offset: 0,
+        bytes: vec![0; 16],
+      },
+      RenderCommand::BindVertexBuffer {
+        pipeline,
+        buffer: 0,
+      },
+      RenderCommand::Draw { vertices: 0..6 },
+      RenderCommand::BindIndexBuffer {
+        pipeline,
+        buffer: 0,
+      },
+      RenderCommand::DrawIndexed {
+        indices: 0..12,
+        base_vertex: 0,
+      },
+      RenderCommand::SetPipeline {
+        pipeline: ResourceId::new(1, 0),
+      },
+      RenderCommand::Draw { vertices: 0..3 },
+      RenderCommand::EndRenderPass,
+    ];
+    assert_eq!(validate_commands(&commands, &resources()), vec![]);
+  }
+
+  #[test]
+  fn invalid_commands_are_reported_by_index() {
+    let pipeline = ResourceId::new(0, 0);
+    let commands = vec![
+      RenderCommand::Draw { vertices: 0..3 },
+      begin(),
+      RenderCommand::SetPipeline { pipeline },
+      RenderCommand::PushConstants {
+        pipeline,
+        stage: PipelineStage::VERTEX,
+        offset: 32,
+        bytes: vec![0; 16],
+      },
+      RenderCommand::Draw { vertices: 0..3 },
+      RenderCommand::BindVertexBuffer {
+        pipeline,
+        buffer: 0,
+      },
+      RenderCommand::Draw { vertices: 0..7 },
+      RenderCommand::SetPipeline {
+        pipeline: ResourceId::new(2, 0),
+      },
+      begin(),
+    ];
+    let errors = validate_commands(&commands, &resources())
+      .into_iter()
+      .map(|error| error.index)
+      .collect::<Vec<_>>();
+    assert_eq!(errors, vec![0, 3, 4, 6, 7, 8, 8]);
+
+    let errors =
+      validate_commands(&[RenderCommand::EndRenderPass], &resources());
+    assert_eq!(
+      errors[0].to_string(),
+      "Command 0: Ends a render pass that never began."
+    );
+  }
+
+  #[test]
+  fn stencil_commands_match_their_render_pass() {
+    let stencil_pipeline = ResourceId::new(3, 0);
+    let commands = vec![
+      RenderCommand::SetStencilReference { reference: 1 },
+      begin_render_pass(ResourceId::new(1, 0)),
+      RenderCommand::SetPipeline {
+        pipeline: stencil_pipeline,
+      },
+      RenderCommand::SetStencilReference { reference: 1 },
+      RenderCommand::Draw { vertices: 0..3 },
+      RenderCommand::SetPipeline {
+        pipeline: ResourceId::new(1, 0),
+      },
+      RenderCommand::EndRenderPass,
+      begin(),
+      RenderCommand::SetPipeline {
+        pipeline: stencil_pipeline,
+      },
+      RenderCommand::SetStencilReference { reference: 1 },
+      RenderCommand::EndRenderPass,
+    ];
+    let errors = validate_commands(&commands, &resources())
+      .into_iter()
+      .map(|error| error.index)
+      .collect::<Vec<_>>();
+    assert_eq!(errors, vec![0, 5, 8, 9]);
+  }
+
+  #[test]
+  fn pipelines_match_the_color_attachments_of_their_render_pass() {
+    let g_buffer_pipeline = ResourceId::new(4, 0);
+    let commands = vec![
+      begin_render_pass(ResourceId::new(2, 0)),
+      RenderCommand::SetPipeline {
+        pipeline: g_buffer_pipeline,
+      },
+      RenderCommand::Draw { vertices: 0..3 },
+      RenderCommand::SetPipeline {
+        pipeline: ResourceId::new(1, 0),
+      },
+      RenderCommand::EndRenderPass,
+      begin(),
+      RenderCommand::SetPipeline {
+        pipeline: g_buffer_pipeline,
+      },
+      RenderCommand::EndRenderPass,
+    ];
+    let errors = validate_commands(&commands, &resources());
+    assert_eq!(
+      errors.iter().map(|error| error.index).collect::<Vec<_>>(),
+      vec![3, 6]
+    );
+    assert!(errors[1].message.contains("[Rgba8Unorm, Rgba16Sfloat]"));
+  }
+}
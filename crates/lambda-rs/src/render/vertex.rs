@@ -5,13 +5,38 @@ pub use lambda_platform::gfx::assembler::{
   VertexElement,
 };
 
-/// Vertex data structure with position, normal, and color.
+/// Vertex data structure with position, normal, color, texture coordinates,
+/// and a tangent. The w component of the tangent is the handedness of the
+/// bitangent, which is `cross(normal, tangent.xyz) * tangent.w`.
 #[repr(C)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Vertex {
   pub position: [f32; 3],
   pub normal: [f32; 3],
   pub color: [f32; 3],
+  pub tex_coords: [f32; 2],
+  pub tangent: [f32; 4],
+}
+
+impl Vertex {
+  /// The bytes of the vertices as they're laid out in a vertex buffer.
+  pub fn to_bytes(vertices: &[Vertex]) -> Vec<u8> {
+    return vertices
+      .iter()
+      .flat_map(|vertex| {
+        return vertex
+          .position
+          .iter()
+          .chain(&vertex.normal)
+          .chain(&vertex.color)
+          .chain(&vertex.tex_coords)
+          .chain(&vertex.tangent)
+          .copied()
+          .collect::<Vec<f32>>();
+      })
+      .flat_map(f32::to_ne_bytes)
+      .collect();
+  }
 }
 
 /// Construction for
@@ -20,6 +45,8 @@ pub struct VertexBuilder {
   pub position: [f32; 3],
   pub normal: [f32; 3],
   pub color: [f32; 3],
+  pub tex_coords: [f32; 2],
+  pub tangent: [f32; 4],
 }
 
 impl VertexBuilder {
@@ -29,6 +56,8 @@ impl VertexBuilder {
       position: [0.0, 0.0, 0.0],
       normal: [0.0, 0.0, 0.0],
       color: [0.0, 0.0, 0.0],
+      tex_coords: [0.0, 0.0],
+      tangent: [0.0, 0.0, 0.0, 0.0],
     };
   }
 
@@ -50,12 +79,26 @@ impl VertexBuilder {
     return self;
   }
 
+  /// Set the texture coordinates of the vertex.
+  pub fn with_tex_coords(&mut self, tex_coords: [f32; 2]) -> &mut Self {
+    self.tex_coords = tex_coords;
+    return self;
+  }
+
+  /// Set the tangent of the vertex.
+  pub fn with_tangent(&mut self, tangent: [f32; 4]) -> &mut Self {
+    self.tangent = tangent;
+    return self;
+  }
+
   /// Build the vertex.
   pub fn build(&self) -> Vertex {
     return Vertex {
       position: self.position,
       normal: self.normal,
       color: self.color,
+      tex_coords: self.tex_coords,
+      tangent: self.tangent,
     };
   }
 }
@@ -69,15 +112,34 @@ mod test {
     assert_eq!(vertex.position, [0.0, 0.0, 0.0]);
     assert_eq!(vertex.normal, [0.0, 0.0, 0.0]);
     assert_eq!(vertex.color, [0.0, 0.0, 0.0]);
+    assert_eq!(vertex.tex_coords, [0.0, 0.0]);
+    assert_eq!(vertex.tangent, [0.0, 0.0, 0.0, 0.0]);
 
     let vertex = vertex
       .with_position([1.0, 2.0, 3.0])
       .with_normal([4.0, 5.0, 6.0])
       .with_color([7.0, 8.0, 9.0])
+      .with_tex_coords([0.5, 1.0])
+      .with_tangent([1.0, 0.0, 0.0, -1.0])
       .build();
 
     assert_eq!(vertex.position, [1.0, 2.0, 3.0]);
     assert_eq!(vertex.normal, [4.0, 5.0, 6.0]);
     assert_eq!(vertex.color, [7.0, 8.0, 9.0]);
+    assert_eq!(vertex.tex_coords, [0.5, 1.0]);
+    assert_eq!(vertex.tangent, [1.0, 0.0, 0.0, -1.0]);
+  }
+
+  #[test]
+  fn vertex_bytes_match_the_vertex_layout() {
+    let vertex = super::VertexBuilder::new()
+      .with_position([1.0, 2.0, 3.0])
+      .with_tangent([0.0, 0.0, 0.0, -1.0])
+      .build();
+    let bytes = super::Vertex::to_bytes(&[vertex, vertex]);
+
+    assert_eq!(bytes.len(), 2 * std::mem::size_of::<super::Vertex>());
+    assert_eq!(bytes[4..8], 2.0f32.to_ne_bytes());
+    assert_eq!(bytes[56..60], (-1.0f32).to_ne_bytes());
   }
 }
@@ -5,6 +5,7 @@ use lambda_platform::gfx;
 #[derive(Debug, Clone, PartialEq)]
 pub struct Viewport {
   viewport: gfx::viewport::ViewPort,
+  fraction: Option<[f32; 4]>,
 }
 
 impl Viewport {
@@ -13,6 +14,54 @@ impl Viewport {
   pub(crate) fn clone_gfx_viewport(&self) -> gfx::viewport::ViewPort {
     return self.viewport.clone();
   }
+
+  /// The position of the top left corner of the viewport in pixels.
+  /// Fractional viewports are at the origin until they're resolved.
+  pub fn coordinates(&self) -> (i16, i16) {
+    return self.viewport.coordinates();
+  }
+
+  /// The width & height of the viewport in pixels. Fractional viewports have
+  /// no size until they're resolved.
+  pub fn dimensions(&self) -> (u32, u32) {
+    return self.viewport.dimensions();
+  }
+
+  /// The position & size of a fractional viewport as fractions of the
+  /// surface, or `None` for viewports built from pixels.
+  pub fn fraction(&self) -> Option<(f32, f32, f32, f32)> {
+    return self
+      .fraction
+      .map(|[x, y, width, height]| (x, y, width, height));
+  }
+
+  /// Resolves a fractional viewport against the size of the surface into a
+  /// viewport in pixels. Viewports built from pixels are returned as is.
+  /// Edges are rounded to the nearest pixel, so that viewports which share
+  /// an edge as fractions also share it in pixels.
+  pub fn resolve(&self, surface_width: u32, surface_height: u32) -> Viewport {
+    let [x, y, width, height] = match self.fraction {
+      Some(fraction) => fraction,
+      None => return self.clone(),
+    };
+    let (left, right) = fraction_to_pixels(x, width, surface_width);
+    let (top, bottom) = fraction_to_pixels(y, height, surface_height);
+    return ViewportBuilder::new()
+      .with_coordinates(left as i16, top as i16)
+      .build((right - left) as u32, (bottom - top) as u32);
+  }
+}
+
+/// The pixels that the start & end of a span of a surface's length fall on.
+fn fraction_to_pixels(
+  start: f32,
+  length: f32,
+  surface_length: u32,
+) -> (i64, i64) {
+  let surface_length = surface_length as f32;
+  let start_pixel = (start * surface_length).round() as i64;
+  let end_pixel = ((start + length) * surface_length).round() as i64;
+  return (start_pixel, end_pixel.max(start_pixel));
 }
 
 /// Builder for viewports that are used to render a frame within the RenderContext.
@@ -27,12 +76,142 @@ impl ViewportBuilder {
     return Self { x: 0, y: 0 };
   }
 
+  /// The position of the top left corner of the viewport in pixels.
+  pub fn with_coordinates(mut self, x: i16, y: i16) -> Self {
+    self.x = x;
+    self.y = y;
+    return self;
+  }
+
   /// Builds a viewport that can be used for defining
   pub fn build(self, width: u32, height: u32) -> Viewport {
     let viewport = gfx::viewport::ViewPortBuilder::new()
       .with_coordinates(self.x, self.y)
       .build(width, height);
 
-    return Viewport { viewport };
+    return Viewport {
+      viewport,
+      fraction: None,
+    };
+  }
+
+  /// Builds a viewport whose position & size are fractions of the surface it
+  /// renders to, such as `fractional(0.5, 0.0, 0.5, 1.0)` for the right half.
+  /// The viewport is resolved into pixels against the size of the surface
+  /// when the frame is rendered, so it follows the surface as it's resized.
+  pub fn fractional(x: f32, y: f32, width: f32, height: f32) -> Viewport {
+    return Viewport {
+      viewport: Self::new().build(0, 0).viewport,
+      fraction: Some([x, y, width, height]),
+    };
+  }
+
+  /// Fractional viewports of the left & right halves of the surface.
+  pub fn vertical_halves() -> [Viewport; 2] {
+    return [
+      Self::fractional(0.0, 0.0, 0.5, 1.0),
+      Self::fractional(0.5, 0.0, 0.5, 1.0),
+    ];
+  }
+
+  /// Fractional viewports of the top & bottom halves of the surface.
+  pub fn horizontal_halves() -> [Viewport; 2] {
+    return [
+      Self::fractional(0.0, 0.0, 1.0, 0.5),
+      Self::fractional(0.0, 0.5, 1.0, 0.5),
+    ];
+  }
+
+  /// Fractional viewports of the quadrants of the surface, in the order top
+  /// left, top right, bottom left, & bottom right.
+  pub fn quadrants() -> [Viewport; 4] {
+    return [
+      Self::fractional(0.0, 0.0, 0.5, 0.5),
+      Self::fractional(0.5, 0.0, 0.5, 0.5),
+      Self::fractional(0.0, 0.5, 0.5, 0.5),
+      Self::fractional(0.5, 0.5, 0.5, 0.5),
+    ];
+  }
+
+  /// Builds a viewport at the origin from a size in logical pixels, which is
+  /// converted into physical pixels with the scale factor of the window.
+  pub fn from_logical_size(
+    width: f64,
+    height: f64,
+    scale_factor: f64,
+  ) -> Viewport {
+    let (width, height) = physical_size(width, height, scale_factor);
+    return Self::new().build(width, height);
+  }
+}
+
+/// Converts a size in logical pixels into the nearest size in physical
+/// pixels.
+pub fn physical_size(width: f64, height: f64, scale_factor: f64) -> (u32, u32) {
+  return (
+    (width * scale_factor).round() as u32,
+    (height * scale_factor).round() as u32,
+  );
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    physical_size,
+    ViewportBuilder,
+  };
+
+  #[test]
+  fn logical_sizes_are_scaled_into_physical_pixels() {
+    assert_eq!(physical_size(400.0, 300.0, 1.0), (400, 300));
+    assert_eq!(physical_size(400.0, 300.0, 2.0), (800, 600));
+    assert_eq!(physical_size(333.0, 100.0, 1.5), (500, 150));
+    assert_eq!(
+      ViewportBuilder::from_logical_size(640.0, 360.0, 2.0),
+      ViewportBuilder::new().build(1280, 720)
+    );
+  }
+
+  #[test]
+  fn fractional_viewports_resolve_against_the_surface() {
+    let picture_in_picture = ViewportBuilder::fractional(0.75, 0.0, 0.25, 0.25);
+    assert_eq!(picture_in_picture.dimensions(), (0, 0));
+    assert_eq!(
+      picture_in_picture.resolve(1280, 720),
+      ViewportBuilder::new()
+        .with_coordinates(960, 0)
+        .build(320, 180)
+    );
+
+    let pixels = ViewportBuilder::new().build(640, 480);
+    assert_eq!(pixels.fraction(), None);
+    assert_eq!(pixels.resolve(1280, 720), pixels);
+  }
+
+  #[test]
+  fn splits_tile_the_surface() {
+    let [left, right] = ViewportBuilder::vertical_halves();
+    let (left, right) = (left.resolve(801, 600), right.resolve(801, 600));
+    assert_eq!(left.dimensions().0 + right.dimensions().0, 801);
+    assert_eq!(right.coordinates().0 as u32, left.dimensions().0);
+
+    let [top, bottom] = ViewportBuilder::horizontal_halves();
+    assert_eq!(top.resolve(800, 600).dimensions(), (800, 300));
+    assert_eq!(bottom.resolve(800, 600).coordinates(), (0, 300));
+
+    let quadrants = ViewportBuilder::quadrants()
+      .map(|quadrant| quadrant.resolve(800, 600))
+      .map(|quadrant| (quadrant.coordinates(), quadrant.dimensions()));
+    assert_eq!(
+      quadrants,
+      [
+        ((0, 0), (400, 300)),
+        ((400, 0), (400, 300)),
+        ((0, 300), (400, 300)),
+        ((400, 300), (400, 300)),
+      ]
+    );
   }
 }
@@ -1,6 +1,11 @@
 //! Window implementation for rendering applications.
 
 use lambda_platform::winit::{
+  winit_exports::{
+    LogicalPosition,
+    MonitorHandle,
+    PhysicalPosition,
+  },
   Loop,
   WindowHandle,
   WindowHandleBuilder,
@@ -9,12 +14,125 @@ use lambda_platform::winit::{
 
 use crate::events::Events;
 
+/// A monitor connected to the system that windows can be placed on.
+#[derive(Clone, Debug)]
+pub struct Monitor {
+  monitor_handle: MonitorHandle,
+}
+
+impl Monitor {
+  fn new(monitor_handle: MonitorHandle) -> Self {
+    return Self { monitor_handle };
+  }
+
+  /// The name of the monitor, if the system provides one.
+  pub fn name(&self) -> Option<String> {
+    return self.monitor_handle.name();
+  }
+
+  /// The resolution of the monitor in physical pixels. (width, height)
+  pub fn resolution(&self) -> (u32, u32) {
+    let size = self.monitor_handle.size();
+    return (size.width, size.height);
+  }
+
+  /// The top left corner of the monitor on the desktop in physical pixels.
+  pub fn position(&self) -> (i32, i32) {
+    let position = self.monitor_handle.position();
+    return (position.x, position.y);
+  }
+
+  /// The refresh rate of the monitor in hertz, if the system provides one.
+  pub fn refresh_rate(&self) -> Option<f64> {
+    return self
+      .monitor_handle
+      .refresh_rate_millihertz()
+      .map(|millihertz| millihertz as f64 / 1000.0);
+  }
+
+  /// The number of physical pixels per logical pixel of the monitor.
+  pub fn scale_factor(&self) -> f64 {
+    return self.monitor_handle.scale_factor();
+  }
+
+  /// The dots per inch of the monitor, where a scale factor of 1 is 96 DPI.
+  pub fn dpi(&self) -> f64 {
+    return self.scale_factor() * BASE_DPI;
+  }
+
+  /// The position that centers a window of the given size in physical pixels
+  /// on the monitor.
+  pub fn centered_position(&self, size: (u32, u32)) -> (i32, i32) {
+    return centered_position(self.position(), self.resolution(), size);
+  }
+}
+
+/// The DPI of a monitor with a scale factor of 1.
+const BASE_DPI: f64 = 96.0;
+
+/// The position that centers an area of the given size within a monitor.
+fn centered_position(
+  monitor_position: (i32, i32),
+  monitor_size: (u32, u32),
+  size: (u32, u32),
+) -> (i32, i32) {
+  let offset = |monitor: u32, window: u32| {
+    return (monitor as i64 - window as i64) / 2;
+  };
+  return (
+    monitor_position.0 + offset(monitor_size.0, size.0) as i32,
+    monitor_position.1 + offset(monitor_size.1, size.1) as i32,
+  );
+}
+
+/// Lists the monitors connected to the system, starting with the primary
+/// monitor when it's known.
+pub fn monitors(event_loop: &Loop<Events>) -> Vec<Monitor> {
+  let primary = event_loop.get_primary_monitor();
+  let mut monitors: Vec<Monitor> =
+    primary.iter().cloned().map(Monitor::new).collect();
+  monitors.extend(
+    event_loop
+      .get_all_monitors()
+      .filter(|monitor_handle| Some(monitor_handle) != primary.as_ref())
+      .map(Monitor::new),
+  );
+  return monitors;
+}
+
+/// The monitor that a window opens on.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MonitorSelection {
+  /// The primary monitor of the system, or the first monitor when the system
+  /// doesn't have one.
+  Primary,
+  /// The monitor at the index within the list returned by `monitors`.
+  Index(usize),
+  /// The first monitor with the name.
+  Named(String),
+}
+
+/// Where a window is placed on its monitor when it opens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Placement {
+  /// The windowing system places the window.
+  Default,
+  /// The top left corner of the window is offset from the top left corner of
+  /// the monitor in physical pixels.
+  Offset(i32, i32),
+  Centered,
+}
+
 /// Builder for windows that are used to render a frame within the
 /// RenderContext.
 pub struct WindowBuilder {
   name: String,
   dimensions: (u32, u32),
   vsync: bool,
+  ime: bool,
+  monitor: MonitorSelection,
+  placement: Placement,
+  fullscreen: bool,
 }
 
 impl WindowBuilder {
@@ -27,6 +145,10 @@ impl WindowBuilder {
       name: String::from("Window"),
       dimensions: (480, 360),
       vsync: false,
+      ime: false,
+      monitor: MonitorSelection::Primary,
+      placement: Placement::Default,
+      fullscreen: false,
     };
   }
 
@@ -46,12 +168,104 @@ impl WindowBuilder {
     return self;
   }
 
+  /// Allows input methods to compose text within the window, which emits
+  /// `Key::ImePreedit` while composing. Input methods are disabled by
+  /// default, since they can intercept key presses meant for shortcuts.
+  pub fn with_ime(mut self, ime: bool) -> Self {
+    self.ime = ime;
+    return self;
+  }
+
+  /// The monitor that the window opens on (Defaults to the primary
+  /// monitor). Windows open on the primary monitor when the monitor isn't
+  /// connected.
+  pub fn with_monitor(mut self, monitor: MonitorSelection) -> Self {
+    self.monitor = monitor;
+    return self;
+  }
+
+  /// Opens the window with its top left corner offset from the top left
+  /// corner of its monitor in physical pixels.
+  pub fn with_position(mut self, x: i32, y: i32) -> Self {
+    self.placement = Placement::Offset(x, y);
+    return self;
+  }
+
+  /// Opens the window in the center of its monitor.
+  pub fn with_centered(mut self) -> Self {
+    self.placement = Placement::Centered;
+    return self;
+  }
+
+  /// Whether or not the window covers its monitor without borders, in which
+  /// case it's resized to the resolution of the monitor once it opens.
+  pub fn with_fullscreen(mut self, fullscreen: bool) -> Self {
+    self.fullscreen = fullscreen;
+    return self;
+  }
+
+  /// The dimensions that the window opens with in logical pixels.
+  pub fn dimensions(&self) -> (u32, u32) {
+    return self.dimensions;
+  }
+
   // TODO(vmarcella): Remove new call for window and construct the window directly.
   pub fn build(self, event_loop: &mut Loop<Events>) -> Window {
-    return Window::new(self.name.as_str(), self.dimensions, event_loop);
+    let monitor = select_monitor(&self.monitor, event_loop);
+    let scale_factor = monitor.scale_factor();
+    let size = (
+      (self.dimensions.0 as f64 * scale_factor).round() as u32,
+      (self.dimensions.1 as f64 * scale_factor).round() as u32,
+    );
+    let position = match self.placement {
+      Placement::Default => None,
+      Placement::Offset(x, y) => {
+        let (left, top) = monitor.position();
+        Some((left + x, top + y))
+      }
+      Placement::Centered => Some(monitor.centered_position(size)),
+    };
+
+    let window = Window::new(
+      self.name.as_str(),
+      self.dimensions,
+      monitor,
+      position,
+      self.fullscreen,
+      event_loop,
+    );
+    window.set_ime_allowed(self.ime);
+    return window;
   }
 }
 
+/// The monitor that matches the selection, falling back to the primary
+/// monitor when none of them do.
+fn select_monitor(
+  selection: &MonitorSelection,
+  event_loop: &Loop<Events>,
+) -> Monitor {
+  let mut available = monitors(event_loop);
+  let index = match selection {
+    MonitorSelection::Primary => Some(0),
+    MonitorSelection::Index(index) => Some(*index),
+    MonitorSelection::Named(name) => available
+      .iter()
+      .position(|monitor| monitor.name().as_deref() == Some(name.as_str())),
+  };
+
+  return match index.filter(|index| *index < available.len()) {
+    Some(index) => available.swap_remove(index),
+    None => {
+      logging::warn!(
+        "No monitor matches {:?}, so the primary monitor is used instead.",
+        selection
+      );
+      available.into_iter().next().expect("No monitors available")
+    }
+  };
+}
+
 /// Window implementation for rendering applications.
 pub struct Window {
   window_handle: WindowHandle,
@@ -61,20 +275,17 @@ impl Window {
   fn new(
     name: &str,
     dimensions: (u32, u32),
+    monitor: Monitor,
+    position: Option<(i32, i32)>,
+    fullscreen: bool,
     event_loop: &mut Loop<Events>,
   ) -> Self {
-    // Attempt to get the primary monitor first and then falls back to the first
-    // available monitor if that isn't found.
-    let monitor_handle = event_loop.get_primary_monitor().unwrap_or(
-      event_loop
-        .get_any_available_monitors()
-        .expect("No monitors available"),
-    );
-
     let window_properties = WindowProperties {
       name: name.to_string(),
       dimensions,
-      monitor_handle,
+      monitor_handle: monitor.monitor_handle,
+      position,
+      fullscreen,
     };
 
     let window_handle = WindowHandleBuilder::new()
@@ -95,11 +306,110 @@ impl Window {
     return &self.window_handle;
   }
 
-  /// Returns the dimensions of the current window. (width, height)
+  /// Returns the dimensions of the current window in physical pixels.
+  /// (width, height)
   pub fn dimensions(&self) -> (u32, u32) {
-    return (
-      self.window_handle.size.width,
-      self.window_handle.size.height,
+    let size = self.window_handle.window_handle.inner_size();
+    return (size.width, size.height);
+  }
+
+  /// Returns the dimensions of the current window in logical pixels, which
+  /// are the physical dimensions divided by the scale factor.
+  /// (width, height)
+  pub fn logical_dimensions(&self) -> (f64, f64) {
+    let (width, height) = self.dimensions();
+    let scale_factor = self.scale_factor();
+    return (width as f64 / scale_factor, height as f64 / scale_factor);
+  }
+
+  /// Allows or disallows input methods from composing text within the
+  /// window.
+  pub fn set_ime_allowed(&self, allowed: bool) {
+    self.window_handle.window_handle.set_ime_allowed(allowed);
+  }
+
+  /// Places the input method's candidate window at the position, in logical
+  /// pixels from the top left of the window, such as below a text cursor.
+  pub fn set_ime_position(&self, x: f64, y: f64) {
+    self
+      .window_handle
+      .window_handle
+      .set_ime_position(LogicalPosition::new(x, y));
+  }
+
+  /// Returns the number of physical pixels per logical pixel of the monitor
+  /// the window is currently on.
+  pub fn scale_factor(&self) -> f64 {
+    return self.window_handle.window_handle.scale_factor();
+  }
+
+  /// Returns the top left corner of the window on the desktop in physical
+  /// pixels, if the system supports it.
+  pub fn position(&self) -> Option<(i32, i32)> {
+    return self
+      .window_handle
+      .window_handle
+      .outer_position()
+      .ok()
+      .map(|position| (position.x, position.y));
+  }
+
+  /// Moves the top left corner of the window to the position on the desktop
+  /// in physical pixels.
+  pub fn set_position(&self, x: i32, y: i32) {
+    self
+      .window_handle
+      .window_handle
+      .set_outer_position(PhysicalPosition::new(x, y));
+  }
+
+  /// Moves the window into the center of the monitor.
+  pub fn center_on_monitor(&self, monitor: &Monitor) {
+    let size = self.window_handle.window_handle.outer_size();
+    let (x, y) = monitor.centered_position((size.width, size.height));
+    self.set_position(x, y);
+  }
+
+  /// Returns the monitor that the window is currently on.
+  pub fn current_monitor(&self) -> Option<Monitor> {
+    return self
+      .window_handle
+      .window_handle
+      .current_monitor()
+      .map(Monitor::new);
+  }
+
+  /// Lists the monitors connected to the system.
+  pub fn available_monitors(&self) -> Vec<Monitor> {
+    return self
+      .window_handle
+      .window_handle
+      .available_monitors()
+      .map(Monitor::new)
+      .collect();
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::centered_position;
+
+  #[test]
+  fn windows_are_centered_within_their_monitor() {
+    assert_eq!(
+      centered_position((0, 0), (1920, 1080), (800, 600)),
+      (560, 240)
+    );
+    assert_eq!(
+      centered_position((1920, -200), (2560, 1440), (1280, 720)),
+      (2560, 160)
+    );
+    // Windows larger than the monitor overhang it evenly.
+    assert_eq!(
+      centered_position((0, 0), (800, 600), (1000, 800)),
+      (-100, -100)
     );
   }
 }
@@ -1,9 +1,18 @@
 //! Runtime definition & functions for executing lambda applications.
 
-use std::fmt::Debug;
+use std::{
+  cell::Cell,
+  fmt::{
+    Debug,
+    Display,
+  },
+  rc::Rc,
+};
 
 use logging;
 
+use crate::render::RenderContextInfo;
+
 /// A runtime is an important but simple type in lambda that is responsible for
 /// executing the application. The event loop for the application is started
 /// within the runtime and should live for the duration of the application.
@@ -18,17 +27,186 @@ where
   fn run(self) -> Result<RuntimeResult, RuntimeError>;
 }
 
-/// Simple function for starting any prebuilt Runnable.
-pub fn start_runtime<R: Sized + Debug, E: Sized + Debug, T: Runtime<R, E>>(
-  runtime: T,
-) {
+/// Simple function for starting any prebuilt Runnable. Returns the exit code
+/// that the runtime finished with, which is 1 if the runtime failed, so that
+/// it can be passed to `std::process::exit`.
+pub fn start_runtime<E: Sized + Debug, T: Runtime<i32, E>>(runtime: T) -> i32 {
   let runtime_result = runtime.run();
   match runtime_result {
-    Ok(_) => {
-      logging::info!("Runtime finished successfully.");
+    Ok(exit_code) => {
+      logging::info!("Runtime finished with the exit code {}.", exit_code);
+      return exit_code;
     }
     Err(e) => {
       logging::fatal!("Runtime panicked because: {:?}", e);
+      return 1;
+    }
+  }
+}
+
+/// A handle for controlling a runtime while it runs, such as from within a
+/// component. Clones of a handle control the same runtime.
+#[derive(Clone, Debug, Default)]
+pub struct RuntimeHandle {
+  shutdown_request: Rc<Cell<Option<i32>>>,
+}
+
+impl RuntimeHandle {
+  pub fn new() -> Self {
+    return Self::default();
+  }
+
+  /// Asks the runtime to shut down before its next frame and finish with the
+  /// exit code. Later requests replace the exit code of earlier ones.
+  pub fn request_shutdown(&self, exit_code: i32) {
+    self.shutdown_request.set(Some(exit_code));
+  }
+
+  /// The exit code of the shutdown that was requested, if any.
+  pub fn shutdown_requested(&self) -> Option<i32> {
+    return self.shutdown_request.get();
+  }
+}
+
+// -------------------------- INITIALIZATION REPORT ----------------------------
+
+/// A summary of the environment and subsystems that a runtime was started
+/// with. Runtimes emit the report as a single log entry on startup so that
+/// bug reports always contain the same information.
+#[derive(Clone, Debug)]
+pub struct InitializationReport {
+  pub runtime_name: String,
+  pub lambda_version: &'static str,
+  pub operating_system: &'static str,
+  pub architecture: &'static str,
+  pub render_context: Option<RenderContextInfo>,
+  pub window_dimensions: Option<(u32, u32)>,
+  pub scale_factor: Option<f64>,
+  pub worker_threads: usize,
+  pub asset_mounts: Vec<String>,
+  pub component_count: usize,
+}
+
+impl InitializationReport {
+  /// Creates a report for the runtime containing only information about the
+  /// platform lambda was compiled for. Runtimes fill in the rest of the
+  /// report as their subsystems are initialized.
+  pub fn new(runtime_name: &str) -> Self {
+    return Self {
+      runtime_name: runtime_name.to_string(),
+      lambda_version: env!("CARGO_PKG_VERSION"),
+      operating_system: std::env::consts::OS,
+      architecture: std::env::consts::ARCH,
+      render_context: None,
+      window_dimensions: None,
+      scale_factor: None,
+      worker_threads: 0,
+      asset_mounts: vec![],
+      component_count: 0,
+    };
+  }
+}
+
+impl Display for InitializationReport {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    writeln!(f, "Initialized the runtime: {}", self.runtime_name)?;
+    writeln!(f, "  lambda version: {}", self.lambda_version)?;
+    writeln!(
+      f,
+      "  platform: {} ({})",
+      self.operating_system, self.architecture
+    )?;
+
+    match &self.render_context {
+      Some(info) => {
+        writeln!(f, "  rendering api: {}", info.rendering_api)?;
+        writeln!(
+          f,
+          "  gpu: {} ({:?}, vendor: {:#06x}, device: {:#06x})",
+          info.gpu.name, info.gpu.device_type, info.gpu.vendor, info.gpu.device
+        )?;
+        writeln!(f, "  gpu features: {:?}", info.features)?;
+        writeln!(f, "  surface format: {:?}", info.surface_format)?;
+        writeln!(f, "  present mode: {:?}", info.present_mode)?;
+      }
+      None => {
+        writeln!(f, "  rendering api: none")?;
+      }
     }
+
+    match (self.window_dimensions, self.scale_factor) {
+      (Some((width, height)), Some(scale_factor)) => {
+        writeln!(f, "  window: {}x{} @ {}x", width, height, scale_factor)?;
+      }
+      (Some((width, height)), None) => {
+        writeln!(f, "  window: {}x{}", width, height)?;
+      }
+      _ => {
+        writeln!(f, "  window: none")?;
+      }
+    }
+
+    writeln!(f, "  worker threads: {}", self.worker_threads)?;
+    match self.asset_mounts.is_empty() {
+      true => writeln!(f, "  asset mounts: none")?,
+      false => writeln!(f, "  asset mounts: {}", self.asset_mounts.join(", "))?,
+    }
+
+    return write!(f, "  components: {}", self.component_count);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    InitializationReport,
+    RuntimeHandle,
+  };
+
+  #[test]
+  fn initialization_report_without_subsystems() {
+    let mut report = InitializationReport::new("TestRuntime");
+    report.component_count = 2;
+
+    assert_eq!(report.lambda_version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(report.operating_system, std::env::consts::OS);
+
+    let formatted = report.to_string();
+    assert!(formatted.starts_with("Initialized the runtime: TestRuntime\n"));
+    assert!(formatted.contains("  rendering api: none\n"));
+    assert!(formatted.contains("  window: none\n"));
+    assert!(formatted.contains("  asset mounts: none\n"));
+    assert!(formatted.ends_with("  components: 2"));
+  }
+
+  #[test]
+  fn initialization_report_with_window() {
+    let mut report = InitializationReport::new("TestRuntime");
+    report.window_dimensions = Some((800, 600));
+    report.scale_factor = Some(2.0);
+
+    assert!(report.to_string().contains("  window: 800x600 @ 2x\n"));
+  }
+
+  #[test]
+  fn initialization_report_with_workers_and_assets() {
+    let mut report = InitializationReport::new("TestRuntime");
+    report.worker_threads = 7;
+    report.asset_mounts =
+      vec!["/ (.)".to_string(), "/ui (ui.pack)".to_string()];
+
+    let formatted = report.to_string();
+    assert!(formatted.contains("  worker threads: 7\n"));
+    assert!(formatted.contains("  asset mounts: / (.), /ui (ui.pack)\n"));
+  }
+
+  #[test]
+  fn runtime_handles_share_shutdown_requests() {
+    let handle = RuntimeHandle::new();
+    let component_handle = handle.clone();
+    assert_eq!(handle.shutdown_requested(), None);
+
+    component_handle.request_shutdown(3);
+    assert_eq!(handle.shutdown_requested(), Some(3));
   }
 }
@@ -2,22 +2,44 @@
 //! provides a window and a render context which can be used to render
 //! both 2D and 3D graphics to the screen.
 
-use std::time::Instant;
+use std::{
+  cell::RefCell,
+  path::PathBuf,
+  rc::Rc,
+  time::{
+    Duration,
+    Instant,
+  },
+};
 
 use lambda_platform::winit::{
+  input::key_from_virtual_key_code,
   winit_exports::{
+    ControlFlow,
     ElementState,
     Event as WinitEvent,
+    Ime,
     MouseButton,
+    MouseScrollDelta,
     WindowEvent as WinitWindowEvent,
   },
   Loop,
   LoopBuilder,
+  LoopPublisher,
+};
+use logging::{
+  self,
+  chrome_trace,
 };
-use logging;
 
 use crate::{
-  component::Component,
+  assets::vfs::Vfs,
+  component::{
+    catch_component_panic,
+    Component,
+    ComponentRegistry,
+  },
+  crash::CrashReporter,
   events::{
     Button,
     ComponentEvent,
@@ -27,17 +49,56 @@ use crate::{
     RuntimeEvent,
     WindowEvent,
   },
+  input,
+  jobs::JobPool,
+  persistence::{
+    SaveBundle,
+    Saveable,
+  },
   render::{
     window::{
       Window,
       WindowBuilder,
     },
+    PresentModePreference,
     RenderContext,
     RenderContextBuilder,
   },
-  runtime::Runtime,
+  runtime::{
+    InitializationReport,
+    Runtime,
+    RuntimeHandle,
+  },
+  runtimes::{
+    cli::StandardArgs,
+    pacing::{
+      sleep_until,
+      FramePacer,
+      FrameStatistics,
+      FrameWait,
+    },
+  },
+  time::GameClock,
+  tweakables::Tweakables,
 };
 
+/// The number of recent frames that frame statistics are computed over.
+const FRAME_STATISTICS_CAPACITY: usize = 240;
+
+/// The entry of the save bundle that the logical size of the window is saved
+/// under.
+const WINDOW_ENTRY: &str = "lambda.window";
+
+/// The entry of the save bundle that tweakables are saved under.
+const TWEAKABLES_ENTRY: &str = "lambda.tweakables";
+
+/// How many pixels that touchpads scroll by count as a line of scrolling.
+const SCROLL_PIXELS_PER_LINE: f64 = 20.0;
+
+/// A callback that runs once the runtime has shut down, given the exit code
+/// that the runtime is finishing with.
+type ShutdownHook = Box<dyn FnOnce(i32)>;
+
 #[derive(Clone, Debug)]
 pub enum ComponentResult {
   Success,
@@ -48,7 +109,20 @@ pub struct ApplicationRuntimeBuilder {
   app_name: String,
   render_context_builder: RenderContextBuilder,
   window_builder: WindowBuilder,
-  components: Vec<Box<dyn Component<ComponentResult, String>>>,
+  components: ComponentRegistry<ComponentResult, String>,
+  frame_pacer: FramePacer,
+  frame_statistics: Rc<RefCell<FrameStatistics>>,
+  game_clock: Rc<RefCell<GameClock>>,
+  job_pool: Rc<JobPool>,
+  asset_mounts: Vec<String>,
+  tweakables: Rc<RefCell<Tweakables>>,
+  save_bundle: Rc<RefCell<SaveBundle>>,
+  crash_reporter: Option<CrashReporter>,
+  trace_file: Option<PathBuf>,
+  capture_key: Option<input::Key>,
+  capture_frame: Option<u64>,
+  handle: RuntimeHandle,
+  shutdown_hooks: Vec<ShutdownHook>,
 }
 
 impl ApplicationRuntimeBuilder {
@@ -57,7 +131,22 @@ impl ApplicationRuntimeBuilder {
       app_name: app_name.to_string(),
       render_context_builder: RenderContextBuilder::new(app_name),
       window_builder: WindowBuilder::new(),
-      components: Vec::new(),
+      components: ComponentRegistry::new(),
+      frame_pacer: FramePacer::new(),
+      frame_statistics: Rc::new(RefCell::new(FrameStatistics::new(
+        FRAME_STATISTICS_CAPACITY,
+      ))),
+      game_clock: Rc::new(RefCell::new(GameClock::new())),
+      job_pool: Rc::new(JobPool::default()),
+      asset_mounts: vec![],
+      tweakables: Rc::new(RefCell::new(Tweakables::new())),
+      save_bundle: Rc::new(RefCell::new(SaveBundle::new())),
+      crash_reporter: None,
+      trace_file: None,
+      capture_key: Some(input::Key::F12),
+      capture_frame: None,
+      handle: RuntimeHandle::new(),
+      shutdown_hooks: vec![],
     };
   }
 
@@ -90,36 +179,326 @@ impl ApplicationRuntimeBuilder {
     return self;
   }
 
-  /// Attach a component to the current runnable.
+  /// Limit the runtime to the target frames per second by sleeping in between
+  /// frames. The frame rate is unlimited by default.
+  pub fn with_target_fps(mut self, target_fps: u32) -> Self {
+    self.frame_pacer = self.frame_pacer.with_target_fps(target_fps);
+    return self;
+  }
+
+  /// Wait for events in between frames instead of sleeping to reduce CPU
+  /// usage. Limits the frame rate to 30 fps unless a target fps is set.
+  pub fn with_power_saving_mode(mut self) -> Self {
+    self.frame_pacer = self.frame_pacer.with_power_saving_mode();
+    return self;
+  }
+
+  /// The key that captures the next frame with a graphics debugger such as
+  /// RenderDoc, or None to disable the hotkey. Defaults to F12.
+  pub fn with_capture_key(mut self, capture_key: Option<input::Key>) -> Self {
+    self.capture_key = capture_key;
+    return self;
+  }
+
+  /// Captures the given frame with a graphics debugger, counting from 1.
+  pub fn with_capture_frame(mut self, frame: u64) -> Self {
+    self.capture_frame = Some(frame);
+    return self;
+  }
+
+  /// Configures the runtime with the standard command line flags passed to
+  /// the executable, which are `--width`, `--height`, `--fullscreen`,
+  /// `--vsync`, `--log-level`, `--backend`, & `--capture-frame`. Flags
+  /// override the configuration made before this is called. When the flags
+  /// can't be parsed, including flags that aren't standard, the error &
+  /// usage are printed and the process exits with a code of 2.
+  pub fn with_standard_cli(self) -> Self {
+    let args = std::env::args().collect::<Vec<_>>();
+    return match StandardArgs::parse(&args) {
+      Ok(standard_args) => self.with_standard_args(standard_args),
+      Err(error) => {
+        eprintln!("{}\n\n{}", error, StandardArgs::usage(&args));
+        std::process::exit(2);
+      }
+    };
+  }
+
+  /// Configures the runtime with standard flags that have already been
+  /// parsed, for applications that parse flags of their own alongside them.
+  pub fn with_standard_args(mut self, standard_args: StandardArgs) -> Self {
+    let StandardArgs {
+      width,
+      height,
+      fullscreen,
+      vsync,
+      log_level,
+      backend,
+      capture_frame,
+    } = standard_args;
+
+    if width.is_some() || height.is_some() {
+      let (default_width, default_height) = self.window_builder.dimensions();
+      self.window_builder = self.window_builder.with_dimensions(
+        width.unwrap_or(default_width),
+        height.unwrap_or(default_height),
+      );
+    }
+    if let Some(fullscreen) = fullscreen {
+      self.window_builder = self.window_builder.with_fullscreen(fullscreen);
+    }
+    if let Some(vsync) = vsync {
+      let present_mode = match vsync {
+        true => PresentModePreference::Fifo,
+        false => PresentModePreference::AutoNoVsync,
+      };
+      self.render_context_builder =
+        self.render_context_builder.with_present_mode(present_mode);
+    }
+    if let Some(backend) = backend {
+      self.render_context_builder =
+        self.render_context_builder.with_backend(backend);
+    }
+    if let Some(log_level) = log_level {
+      match logging::filter::FilterSpec::parse(&log_level) {
+        Ok(filter) => logging::Logger::global().set_filter(filter),
+        Err(error) => logging::warn!("Invalid log level: {}", error),
+      }
+    }
+    if capture_frame.is_some() {
+      self.capture_frame = capture_frame;
+    }
+    return self;
+  }
+
+  /// A handle to the frame statistics that the runtime will record, which
+  /// components can hold onto to read frame times from.
+  pub fn frame_statistics(&self) -> Rc<RefCell<FrameStatistics>> {
+    return self.frame_statistics.clone();
+  }
+
+  /// A handle to the game clock that the runtime ticks before updating
+  /// components every frame. Components can hold onto it to read scaled game
+  /// time, or to pause & slow down time for every component at once.
+  pub fn game_clock(&self) -> Rc<RefCell<GameClock>> {
+    return self.game_clock.clone();
+  }
+
+  /// The pool that components can run background work on. The runtime runs
+  /// the callbacks of finished jobs on the main thread before updating
+  /// components every frame.
+  pub fn job_pool(&self) -> Rc<JobPool> {
+    return self.job_pool.clone();
+  }
+
+  /// Lists the mounts of the filesystem that components load assets from in
+  /// the initialization report.
+  pub fn with_asset_mounts(mut self, files: &Vfs) -> Self {
+    self.asset_mounts = files.mount_points();
+    return self;
+  }
+
+  /// The tweakables that components can register values to tune with, which
+  /// the runtime reloads from their file every frame once it changes.
+  pub fn tweakables(&self) -> Rc<RefCell<Tweakables>> {
+    return self.tweakables.clone();
+  }
+
+  /// Back the tweakables with the RON file at the path, loading the values
+  /// within it now & reloading them whenever the file changes. Values that
+  /// changed while running are saved to the file when the runtime shuts
+  /// down.
+  pub fn with_tweakables_file(self, path: impl Into<PathBuf>) -> Self {
+    if let Err(error) = self.tweakables.borrow_mut().watch_file(path) {
+      logging::warn!("Failed to load the tweakables: {}", error);
+    }
+    return self;
+  }
+
+  /// The bundle that components can register state with to save between
+  /// runs, once a save file has been set.
+  pub fn save_bundle(&self) -> Rc<RefCell<SaveBundle>> {
+    return self.save_bundle.clone();
+  }
+
+  /// Save state between runs to the file at the path. Registered state and
+  /// the size of the window are restored from the file when the runtime is
+  /// built, and saved to it when the runtime shuts down. Tweakables are
+  /// saved too, unless they're backed by a file of their own.
+  pub fn with_save_file(self, path: impl Into<PathBuf>) -> Self {
+    if let Err(error) = self.save_bundle.borrow_mut().load_file(path) {
+      logging::warn!("Failed to load the save file: {}", error);
+    }
+    return self;
+  }
+
+  /// Write a crash report to the directory whenever the application panics,
+  /// which includes the initialization report of the runtime once it has
+  /// been built. The panic hook is installed immediately, so panics while
+  /// building the runtime are reported too.
+  pub fn with_crash_reports(mut self, directory: impl Into<PathBuf>) -> Self {
+    self.crash_reporter = Some(CrashReporter::install(directory));
+    return self;
+  }
+
+  /// Record the phases of every frame and any other spans as a Chrome trace
+  /// that is written to the file at the path when the runtime shuts down.
+  /// Setting the `LAMBDA_TRACE_FILE` environment variable records a trace
+  /// to the file it names instead, without calling this.
+  pub fn with_trace_file(mut self, path: impl Into<PathBuf>) -> Self {
+    self.trace_file = Some(path.into());
+    return self;
+  }
+
+  /// A handle for shutting down the runtime, which components can hold onto
+  /// to exit the application with an exit code.
+  pub fn handle(&self) -> RuntimeHandle {
+    return self.handle.clone();
+  }
+
+  /// Adds a hook that runs once the runtime has shut down. Components are
+  /// detached first, then the render context is destroyed, and then hooks
+  /// run in the order they were added, before queued log records are
+  /// flushed. Hooks are given the exit code of the runtime.
+  pub fn with_shutdown_hook(
+    mut self,
+    hook: impl FnOnce(i32) + 'static,
+  ) -> Self {
+    self.shutdown_hooks.push(Box::new(hook));
+    return self;
+  }
+
+  /// Attach a component to the current runnable. The component is registered
+  /// under the name of its type and has no dependencies.
   pub fn with_component<
     T: Default + Component<ComponentResult, String> + 'static,
   >(
     self,
     configure_component: impl FnOnce(Self, T) -> (Self, T),
+  ) -> Self {
+    let name = self.components.unique_name(std::any::type_name::<T>());
+    return self.with_named_component(&name, &[], configure_component);
+  }
+
+  /// Attach a component to the current runnable under the given name. The
+  /// component will be attached, updated, and rendered after the components
+  /// named in dependencies and detached before them.
+  pub fn with_named_component<
+    T: Default + Component<ComponentResult, String> + 'static,
+  >(
+    self,
+    name: &str,
+    dependencies: &[&str],
+    configure_component: impl FnOnce(Self, T) -> (Self, T),
   ) -> Self {
     let (mut kernel_builder, component) =
       configure_component(self, T::default());
-    kernel_builder.components.push(Box::new(component));
+    kernel_builder
+      .components
+      .register(name, dependencies, component)
+      .expect("Failed to register the component.");
     return kernel_builder;
   }
 
+  /// Attach a component under the given name like `with_named_component`,
+  /// and register it with the save bundle under the same name so that its
+  /// state is saved between runs.
+  pub fn with_saved_component<
+    T: Default + Component<ComponentResult, String> + Saveable + 'static,
+  >(
+    self,
+    name: &str,
+    dependencies: &[&str],
+    configure_component: impl FnOnce(Self, T) -> (Self, T),
+  ) -> Self {
+    let kernel_builder =
+      self.with_named_component(name, dependencies, configure_component);
+    let component = kernel_builder
+      .components
+      .get::<T>(name)
+      .expect("The component was just registered.");
+    kernel_builder
+      .save_bundle
+      .borrow_mut()
+      .register(name, component)
+      .expect("Failed to register the component's saved state.");
+    return kernel_builder;
+  }
+
+  /// Query a component that has already been attached by name and type,
+  /// allowing components to hold onto handles of the components they need
+  /// to read from.
+  pub fn component<T: 'static>(&self, name: &str) -> Option<Rc<RefCell<T>>> {
+    return self.components.get(name);
+  }
+
   /// Builds an `ApplicationRuntime` equipped with windowing, an event loop, and a
-  /// component stack that allows components to be dynamically pushed into the
-  /// Kernel to receive events & render access.
+  /// component registry that allows components to be dynamically pushed into
+  /// the Kernel to receive events & render access. Panics if the dependencies
+  /// of the components cannot be resolved.
   pub fn build(self) -> ApplicationRuntime {
     let name = self.app_name;
+    let mut window_builder = self.window_builder;
+    if self.save_bundle.borrow().file().is_some() {
+      let mut save_bundle = self.save_bundle.borrow_mut();
+      if self.tweakables.borrow().file().is_none() {
+        save_bundle
+          .register(TWEAKABLES_ENTRY, self.tweakables.clone())
+          .expect("Failed to register the tweakables' saved state.");
+      }
+      if let Err(error) = save_bundle.restore() {
+        logging::warn!("{}", error);
+      }
+      if let Some((width, height)) =
+        save_bundle.entry(WINDOW_ENTRY).and_then(decode_window_size)
+      {
+        window_builder = window_builder.with_dimensions(width, height);
+      }
+    }
+
+    let trace_file = match chrome_trace::start_from_env() {
+      Some(path) => Some(path),
+      None => self.trace_file.inspect(|_| chrome_trace::start()),
+    };
+
     let mut event_loop = LoopBuilder::new().build();
-    let window = self.window_builder.build(&mut event_loop);
+    let window = window_builder.build(&mut event_loop);
 
-    let component_stack = self.components;
+    let mut component_registry = self.components;
+    component_registry
+      .resolve_order()
+      .expect("Failed to resolve the order of the components.");
     let render_context = self.render_context_builder.build(&window);
 
+    let mut initialization_report = InitializationReport::new(&name);
+    initialization_report.render_context = Some(render_context.info());
+    initialization_report.window_dimensions = Some(window.dimensions());
+    initialization_report.scale_factor = Some(window.scale_factor());
+    initialization_report.worker_threads = self.job_pool.worker_count();
+    initialization_report.asset_mounts = self.asset_mounts;
+    initialization_report.component_count = component_registry.len();
+    logging::info!("{}", initialization_report);
+    if let Some(crash_reporter) = self.crash_reporter.as_ref() {
+      crash_reporter.set_environment(initialization_report.to_string());
+    }
+
     return ApplicationRuntime {
       name,
       event_loop,
       window,
       render_context,
-      component_stack,
+      component_registry,
+      initialization_report,
+      frame_pacer: self.frame_pacer,
+      frame_statistics: self.frame_statistics,
+      game_clock: self.game_clock,
+      job_pool: self.job_pool,
+      tweakables: self.tweakables,
+      save_bundle: self.save_bundle,
+      trace_file,
+      capture_key: self.capture_key,
+      capture_frame: self.capture_frame,
+      handle: self.handle,
+      shutdown_hooks: self.shutdown_hooks,
     };
   }
 }
@@ -130,26 +509,87 @@ pub struct ApplicationRuntime {
   name: String,
   event_loop: Loop<Events>,
   window: Window,
-  component_stack: Vec<Box<dyn Component<ComponentResult, String>>>,
+  component_registry: ComponentRegistry<ComponentResult, String>,
   render_context: RenderContext,
+  initialization_report: InitializationReport,
+  frame_pacer: FramePacer,
+  frame_statistics: Rc<RefCell<FrameStatistics>>,
+  game_clock: Rc<RefCell<GameClock>>,
+  job_pool: Rc<JobPool>,
+  tweakables: Rc<RefCell<Tweakables>>,
+  save_bundle: Rc<RefCell<SaveBundle>>,
+  trace_file: Option<PathBuf>,
+  capture_key: Option<input::Key>,
+  capture_frame: Option<u64>,
+  handle: RuntimeHandle,
+  shutdown_hooks: Vec<ShutdownHook>,
 }
 
-impl ApplicationRuntime {}
+impl ApplicationRuntime {
+  /// The report describing the subsystems that the runtime was initialized
+  /// with. This is also logged when the runtime is built.
+  pub fn initialization_report(&self) -> &InitializationReport {
+    return &self.initialization_report;
+  }
+
+  /// A handle to the frame statistics recorded by the runtime.
+  pub fn frame_statistics(&self) -> Rc<RefCell<FrameStatistics>> {
+    return self.frame_statistics.clone();
+  }
+
+  /// A handle to the game clock ticked by the runtime.
+  pub fn game_clock(&self) -> Rc<RefCell<GameClock>> {
+    return self.game_clock.clone();
+  }
+
+  /// The pool that runs background work for components.
+  pub fn job_pool(&self) -> Rc<JobPool> {
+    return self.job_pool.clone();
+  }
+
+  /// The tweakables that components tune values with.
+  pub fn tweakables(&self) -> Rc<RefCell<Tweakables>> {
+    return self.tweakables.clone();
+  }
+
+  /// The bundle that state is saved between runs with.
+  pub fn save_bundle(&self) -> Rc<RefCell<SaveBundle>> {
+    return self.save_bundle.clone();
+  }
+
+  /// A handle for shutting down the runtime.
+  pub fn handle(&self) -> RuntimeHandle {
+    return self.handle.clone();
+  }
+}
 
-impl Runtime<(), String> for ApplicationRuntime {
+impl Runtime<i32, String> for ApplicationRuntime {
   type Component = Box<dyn Component<ComponentResult, String>>;
   /// Runs the event loop for the Application Runtime which takes ownership
   /// of all components, the windowing the render context, and anything
-  /// else relevant to the runtime.
-  fn run(self) -> Result<(), String> {
+  /// else relevant to the runtime. Returns the exit code that the runtime
+  /// was shut down with, or the error of a component that failed.
+  fn run(self) -> Result<i32, String> {
     // Decompose Runtime components to transfer ownership from the runtime to
     // the event loop closure which will run until the app is closed.
     let ApplicationRuntime {
       window,
       mut event_loop,
-      mut component_stack,
+      mut component_registry,
       name,
       render_context,
+      frame_pacer,
+      frame_statistics,
+      game_clock,
+      job_pool,
+      tweakables,
+      save_bundle,
+      trace_file,
+      capture_key,
+      capture_frame,
+      handle,
+      shutdown_hooks,
+      ..
     } = self;
 
     let mut active_render_context = Some(render_context);
@@ -159,11 +599,18 @@ impl Runtime<(), String> for ApplicationRuntime {
       event: RuntimeEvent::Initialized,
       issued_at: Instant::now(),
     });
+    publisher.publish_event(Events::Window {
+      event: WindowEvent::ScaleFactorChanged {
+        scale_factor: window.scale_factor(),
+      },
+      issued_at: Instant::now(),
+    });
 
     let mut current_frame = Instant::now();
-    let mut runtime_result: Box<Result<(), String>> = Box::new(Ok(()));
+    let mut runtime_result: Result<(), String> = Ok(());
+    let mut cursor_position: Option<(f64, f64)> = None;
 
-    event_loop.run_forever(move |event, _, control_flow| {
+    let exit_code = event_loop.run_until_exit(|event, _, control_flow| {
       let mapped_event: Option<Events> = match event {
         WinitEvent::WindowEvent { event, .. } => match event {
           WinitWindowEvent::CloseRequested => {
@@ -188,42 +635,84 @@ impl Runtime<(), String> for ApplicationRuntime {
               issued_at: Instant::now(),
             })
           }
-          WinitWindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+          WinitWindowEvent::ScaleFactorChanged {
+            scale_factor,
+            new_inner_size,
+          } => {
             active_render_context
               .as_mut()
               .unwrap()
               .resize(new_inner_size.width, new_inner_size.height);
 
-            Some(Events::Window {
-              event: WindowEvent::Resize {
-                width: new_inner_size.width,
-                height: new_inner_size.height,
+            let (changed, resized) = scale_factor_changed_events(
+              scale_factor,
+              new_inner_size.width,
+              new_inner_size.height,
+            );
+            publisher.publish_event(resized);
+            Some(changed)
+          }
+          WinitWindowEvent::Moved(_) => None,
+          WinitWindowEvent::Destroyed => None,
+          WinitWindowEvent::DroppedFile(path) => Some(Events::Window {
+            event: WindowEvent::FileDropped { path },
+            issued_at: Instant::now(),
+          }),
+          WinitWindowEvent::HoveredFile(path) => Some(Events::Window {
+            event: WindowEvent::FileHovered { path },
+            issued_at: Instant::now(),
+          }),
+          WinitWindowEvent::HoveredFileCancelled => Some(Events::Window {
+            event: WindowEvent::HoverCancelled,
+            issued_at: Instant::now(),
+          }),
+          // Control characters such as backspace are handled as key presses.
+          WinitWindowEvent::ReceivedCharacter(character)
+            if !character.is_control() =>
+          {
+            Some(Events::Keyboard {
+              event: Key::TextInput {
+                text: character.to_string(),
               },
               issued_at: Instant::now(),
             })
           }
-          WinitWindowEvent::Moved(_) => None,
-          WinitWindowEvent::Destroyed => None,
-          WinitWindowEvent::DroppedFile(_) => None,
-          WinitWindowEvent::HoveredFile(_) => None,
-          WinitWindowEvent::HoveredFileCancelled => None,
           WinitWindowEvent::ReceivedCharacter(_) => None,
+          WinitWindowEvent::Ime(Ime::Preedit(text, cursor)) => {
+            Some(Events::Keyboard {
+              event: Key::ImePreedit { text, cursor },
+              issued_at: Instant::now(),
+            })
+          }
+          WinitWindowEvent::Ime(Ime::Commit(text)) => Some(Events::Keyboard {
+            event: Key::TextInput { text },
+            issued_at: Instant::now(),
+          }),
+          WinitWindowEvent::Ime(_) => None,
           WinitWindowEvent::Focused(_) => None,
           WinitWindowEvent::KeyboardInput {
             device_id: _,
             input,
             is_synthetic,
           } => match (input.state, is_synthetic) {
-            (ElementState::Pressed, false) => Some(Events::Keyboard {
-              event: Key::Pressed {
-                scan_code: input.scancode,
-                virtual_key: input.virtual_keycode,
-              },
-              issued_at: Instant::now(),
-            }),
+            (ElementState::Pressed, false) => {
+              let key = key_from_virtual_key_code(input.virtual_keycode);
+              if capture_key == Some(key) {
+                active_render_context.as_mut().unwrap().trigger_capture();
+              }
+              Some(Events::Keyboard {
+                event: Key::Pressed {
+                  scan_code: input.scancode,
+                  key,
+                  virtual_key: input.virtual_keycode,
+                },
+                issued_at: Instant::now(),
+              })
+            }
             (ElementState::Released, false) => Some(Events::Keyboard {
               event: Key::Released {
                 scan_code: input.scancode,
+                key: key_from_virtual_key_code(input.virtual_keycode),
                 virtual_key: input.virtual_keycode,
               },
               issued_at: Instant::now(),
@@ -238,35 +727,59 @@ impl Runtime<(), String> for ApplicationRuntime {
             device_id,
             position,
             modifiers,
-          } => Some(Events::Mouse {
-            event: Mouse::Moved {
-              x: position.x,
-              y: position.y,
-              dx: 0.0,
-              dy: 0.0,
-              device_id: 0,
-            },
-            issued_at: Instant::now(),
-          }),
+          } => {
+            let position =
+              position.to_logical::<f64>(window.scale_factor());
+            // The cursor hasn't moved since it entered the window.
+            let (last_x, last_y) =
+              cursor_position.unwrap_or((position.x, position.y));
+            cursor_position = Some((position.x, position.y));
+            Some(Events::Mouse {
+              event: Mouse::Moved {
+                x: position.x,
+                y: position.y,
+                dx: position.x - last_x,
+                dy: position.y - last_y,
+                device_id: 0,
+              },
+              issued_at: Instant::now(),
+            })
+          }
           WinitWindowEvent::CursorEntered { device_id } => {
             Some(Events::Mouse {
               event: Mouse::EnteredWindow { device_id: 0 },
               issued_at: Instant::now(),
             })
           }
-          WinitWindowEvent::CursorLeft { device_id } => Some(Events::Mouse {
-            event: Mouse::LeftWindow { device_id: 0 },
-            issued_at: Instant::now(),
-          }),
+          WinitWindowEvent::CursorLeft { device_id } => {
+            cursor_position = None;
+            Some(Events::Mouse {
+              event: Mouse::LeftWindow { device_id: 0 },
+              issued_at: Instant::now(),
+            })
+          }
           WinitWindowEvent::MouseWheel {
             device_id,
             delta,
             phase,
             modifiers,
-          } => Some(Events::Mouse {
-            event: Mouse::Scrolled { device_id: 0 },
-            issued_at: Instant::now(),
-          }),
+          } => {
+            let (dx, dy) = match delta {
+              MouseScrollDelta::LineDelta(dx, dy) => (dx as f64, dy as f64),
+              MouseScrollDelta::PixelDelta(position) => (
+                position.x / SCROLL_PIXELS_PER_LINE,
+                position.y / SCROLL_PIXELS_PER_LINE,
+              ),
+            };
+            Some(Events::Mouse {
+              event: Mouse::Scrolled {
+                dx,
+                dy,
+                device_id: 0,
+              },
+              issued_at: Instant::now(),
+            })
+          }
           WinitWindowEvent::MouseInput {
             device_id,
             state,
@@ -281,17 +794,19 @@ impl Runtime<(), String> for ApplicationRuntime {
               MouseButton::Other(other) => Button::Other(other),
             };
 
+            // Buttons are pressed where the cursor last moved to.
+            let (x, y) = cursor_position.unwrap_or((0.0, 0.0));
             let event = match state {
               ElementState::Pressed => Mouse::Pressed {
                 button,
-                x: 0.0,
-                y: 0.0,
+                x,
+                y,
                 device_id: 0,
               },
               ElementState::Released => Mouse::Released {
                 button,
-                x: 0.0,
-                y: 0.0,
+                x,
+                y,
                 device_id: 0,
               },
             };
@@ -315,33 +830,133 @@ impl Runtime<(), String> for ApplicationRuntime {
           WinitWindowEvent::ThemeChanged(_) => None,
           _ => None,
         },
+        // Shutdowns requested through the handle are handled like closing the
+        // window, at the start of the next frame.
+        WinitEvent::MainEventsCleared
+          if handle.shutdown_requested().is_some()
+            && !matches!(*control_flow, ControlFlow::ExitWithCode(_)) =>
+        {
+          let exit_code = handle.shutdown_requested().unwrap_or_default();
+          control_flow.set_exit_with_code(exit_code);
+          Some(Events::Runtime {
+            event: RuntimeEvent::Shutdown,
+            issued_at: Instant::now(),
+          })
+        }
+        // Components are paused while suspended, and the time spent suspended
+        // isn't counted towards the next frame.
+        WinitEvent::MainEventsCleared
+          if active_render_context
+            .as_ref()
+            .is_some_and(|render_context| render_context.is_suspended()) =>
+        {
+          current_frame = Instant::now();
+          if !matches!(*control_flow, ControlFlow::ExitWithCode(_)) {
+            control_flow.set_wait();
+          }
+          None
+        }
+        // Events wake the event loop before the next frame is due in power
+        // saving mode, which waits for the deadline again instead of
+        // rendering above the target frame rate.
+        WinitEvent::MainEventsCleared
+          if !frame_pacer.is_frame_due(current_frame, Instant::now()) =>
+        {
+          let next_frame = frame_pacer.next_frame(current_frame);
+          match (*control_flow, next_frame) {
+            (ControlFlow::ExitWithCode(_), _) => {}
+            (_, FrameWait::WaitUntil(deadline)) => {
+              control_flow.set_wait_until(deadline);
+            }
+            (_, _) => {}
+          }
+          None
+        }
         WinitEvent::MainEventsCleared => {
+          let _frame = logging::span!("frame");
           let last_frame = current_frame.clone();
           current_frame = Instant::now();
           let duration = &current_frame.duration_since(last_frame);
+          frame_statistics.borrow_mut().record(*duration);
+          game_clock.borrow_mut().tick(*duration);
+          job_pool.run_callbacks();
+          if let Err(error) = tweakables.borrow_mut().poll() {
+            logging::warn!("Failed to reload the tweakables: {}", error);
+          }
 
           let active_render_context = active_render_context
             .as_mut()
             .expect("Couldn't get the active render context. ");
-          for component in &mut component_stack {
-            component.on_update(duration);
-            let commands = component.on_render(active_render_context);
-            active_render_context.render(commands);
+          if capture_frame == Some(frame_statistics.borrow().total_frames()) {
+            active_render_context.trigger_capture();
           }
+          active_render_context.begin_frame();
+          let mut failed_components = vec![];
+          for (component_name, component) in
+            component_registry.named_components()
+          {
+            let mut component = component.borrow_mut();
+            active_render_context.set_resource_owner(Some(component_name));
+            let result = catch_component_panic(|| {
+              {
+                let _update = logging::span!("update");
+                component.on_update(duration);
+              }
+              let _render = logging::span!("render");
+              return component.on_render(active_render_context);
+            });
+            active_render_context.set_resource_owner(None);
 
-          // Warn if frames dropped below 32 ms (30 fps).
-          match duration.as_millis() > 32 {
-            true => {
-              logging::warn!(
-                "Frame took too long to render: {:?} ms",
-                duration.as_millis()
-              );
-            }
-            false => {
-              // Disable until frametimes can be determined via monitor
-              // std::thread::sleep(std::time::Duration::from_millis(16 - duration.as_millis() as u64));
+            match result {
+              Ok(commands) => {
+                let _encode = logging::span!("encode");
+                active_render_context.render(commands);
+              }
+              Err(message) => {
+                failed_components.push((component_name.to_string(), message))
+              }
             }
           }
+          active_render_context.end_frame();
+
+          for (component_name, message) in failed_components {
+            detach_failed_component(
+              &mut component_registry,
+              active_render_context,
+              &publisher,
+              component_name,
+              message,
+            );
+          }
+
+          // Warn if frames dropped below 32 ms (30 fps) or took more than
+          // twice as long as the target frame time.
+          let frame_budget = frame_pacer
+            .target_frame_time()
+            .map(|frame_time| frame_time * 2)
+            .unwrap_or(Duration::ZERO)
+            .max(Duration::from_millis(32));
+          if *duration > frame_budget {
+            logging::warn!(
+              "Frame took too long to render: {:?} ms",
+              duration.as_millis()
+            );
+          }
+
+          // Don't override the control flow once the runtime is exiting.
+          match *control_flow {
+            ControlFlow::ExitWithCode(_) => {}
+            _ => match frame_pacer.next_frame(current_frame) {
+              FrameWait::Poll => control_flow.set_poll(),
+              FrameWait::SleepUntil(deadline) => {
+                sleep_until(deadline);
+                control_flow.set_poll();
+              }
+              FrameWait::WaitUntil(deadline) => {
+                control_flow.set_wait_until(deadline);
+              }
+            },
+          }
 
           None
         }
@@ -355,33 +970,85 @@ impl Runtime<(), String> for ApplicationRuntime {
                 "Initializing all of the components for the runtime: {}",
                 name
               );
-              for component in &mut component_stack {
-                component.on_attach(active_render_context.as_mut().unwrap());
+              let render_context = active_render_context.as_mut().unwrap();
+              let mut failed_components = vec![];
+              for (component_name, component) in
+                component_registry.named_components()
+              {
+                render_context.set_resource_owner(Some(component_name));
+                let result = catch_component_panic(|| {
+                  return component.borrow_mut().on_attach(render_context);
+                });
+                render_context.set_resource_owner(None);
+
+                if let Err(message) = result {
+                  failed_components.push((component_name.to_string(), message));
+                }
               }
-              None
-            }
-            RuntimeEvent::Shutdown => {
-              for component in &mut component_stack {
-                component.on_detach(active_render_context.as_mut().unwrap());
+
+              for (component_name, message) in failed_components {
+                detach_failed_component(
+                  &mut component_registry,
+                  render_context,
+                  &publisher,
+                  component_name,
+                  message,
+                );
               }
-              *runtime_result = Ok(());
               None
             }
+            // Components are detached once the event loop is destroyed.
+            RuntimeEvent::Shutdown => None,
             RuntimeEvent::ComponentPanic { message } => {
-              *runtime_result = Err(message);
+              runtime_result = Err(message);
               None
             }
+            // Suspending & resuming are driven by the windowing system.
+            RuntimeEvent::Suspended | RuntimeEvent::Resumed => None,
           },
-          _ => None,
+          lambda_event => forwarded_event(lambda_event),
         },
-        WinitEvent::Suspended => None,
-        WinitEvent::Resumed => None,
+        WinitEvent::Suspended => {
+          let render_context = active_render_context.as_mut().unwrap();
+          match render_context.is_suspended() {
+            true => None,
+            false => {
+              render_context.suspend();
+              Some(Events::Runtime {
+                event: RuntimeEvent::Suspended,
+                issued_at: Instant::now(),
+              })
+            }
+          }
+        }
+        // Resumed is also emitted when the event loop starts on some
+        // platforms, which is ignored since nothing was suspended.
+        WinitEvent::Resumed => {
+          let render_context = active_render_context.as_mut().unwrap();
+          match render_context.is_suspended() {
+            true => {
+              render_context.resume(&window);
+              Some(Events::Runtime {
+                event: RuntimeEvent::Resumed,
+                issued_at: Instant::now(),
+              })
+            }
+            false => None,
+          }
+        }
         WinitEvent::RedrawEventsCleared => None,
         WinitEvent::LoopDestroyed => {
-          active_render_context
+          let mut render_context = active_render_context
             .take()
-            .expect("[ERROR] The render API has been already taken.")
-            .destroy();
+            .expect("[ERROR] The render API has been already taken.");
+
+          // Detach in reverse so dependencies outlive their dependents.
+          for (component_name, component) in
+            component_registry.named_components().rev()
+          {
+            detach_component(component, &mut render_context, component_name);
+          }
+          render_context.destroy();
 
           logging::info!("All resources were successfully deleted.");
           None
@@ -391,12 +1058,19 @@ impl Runtime<(), String> for ApplicationRuntime {
       match mapped_event {
         Some(event) => {
           logging::trace!("Sending event: {:?} to all components", event);
+          let _events = logging::span!("events");
+
+          let mut failed_components = vec![];
+          for (component_name, component) in
+            component_registry.named_components()
+          {
+            let event_result = catch_component_panic(|| {
+              return component.borrow_mut().on_event(event.clone());
+            });
 
-          for component in &mut component_stack {
-            let event_result = component.on_event(event.clone());
             match event_result {
-              Ok(_) => {}
-              Err(e) => {
+              Ok(Ok(_)) => {}
+              Ok(Err(e)) => {
                 let error = format!(
                   "A component has panicked while handling an event. {:?}",
                   e
@@ -410,13 +1084,63 @@ impl Runtime<(), String> for ApplicationRuntime {
                   issued_at: Instant::now(),
                 });
               }
+              Err(message) => {
+                failed_components.push((component_name.to_string(), message))
+              }
+            }
+          }
+
+          if let Some(render_context) = active_render_context.as_mut() {
+            for (component_name, message) in failed_components {
+              detach_failed_component(
+                &mut component_registry,
+                render_context,
+                &publisher,
+                component_name,
+                message,
+              );
             }
           }
         }
         None => {}
       }
     });
-    return Ok(());
+
+    if tweakables.borrow().has_unsaved_changes() {
+      if let Err(error) = tweakables.borrow_mut().save() {
+        logging::warn!("Failed to save the tweakables: {}", error);
+      }
+    }
+    if save_bundle.borrow().file().is_some() {
+      let mut save_bundle = save_bundle.borrow_mut();
+      let (width, height) = window.logical_dimensions();
+      save_bundle.set_entry(
+        WINDOW_ENTRY,
+        encode_window_size(width.round() as u32, height.round() as u32),
+      );
+      if let Err(error) = save_bundle.save() {
+        logging::warn!("Failed to save the save file: {}", error);
+      }
+    }
+    if let Some(path) = trace_file {
+      let trace = chrome_trace::stop();
+      match trace.write_to_file(&path) {
+        Ok(()) => logging::info!(
+          "Wrote {} trace events to {}.",
+          trace.events.len(),
+          path.display()
+        ),
+        Err(error) => logging::warn!("{}", error),
+      }
+    }
+
+    for hook in shutdown_hooks {
+      hook(exit_code);
+    }
+    logging::info!("Stopped the runtime: {}", name);
+    logging::Logger::global().flush();
+
+    return runtime_result.map(|_| exit_code);
   }
 
   /// When an application runtime starts, it will attach all of the components that
@@ -429,3 +1153,159 @@ impl Runtime<(), String> for ApplicationRuntime {
     logging::info!("Stopping the runtime: {}", self.name);
   }
 }
+
+/// Detaches the component & destroys the resources it attached to the render
+/// context that it didn't destroy itself.
+fn detach_component(
+  component: &Rc<RefCell<dyn Component<ComponentResult, String>>>,
+  render_context: &mut RenderContext,
+  name: &str,
+) {
+  render_context.set_resource_owner(Some(name));
+  let result = catch_component_panic(|| {
+    return component.borrow_mut().on_detach(render_context);
+  });
+  render_context.set_resource_owner(None);
+
+  if let Err(message) = result {
+    logging::error!(
+      "The component {} panicked while detaching: {}",
+      name,
+      message
+    );
+  }
+
+  let destroyed = render_context.destroy_resources_owned_by(name);
+  if destroyed > 0 {
+    logging::debug!(
+      "Destroyed {} resources left attached by the component {}.",
+      destroyed,
+      name
+    );
+  }
+}
+
+/// Detaches and removes a component that panicked from the registry so that
+/// the rest of the application can keep running. Publishes an errored event
+/// followed by a detached event for the component.
+fn detach_failed_component(
+  component_registry: &mut ComponentRegistry<ComponentResult, String>,
+  render_context: &mut RenderContext,
+  publisher: &LoopPublisher<Events>,
+  name: String,
+  message: String,
+) {
+  logging::error!(
+    "The component {} panicked and will be detached: {}",
+    name,
+    message
+  );
+
+  publisher.publish_event(Events::Component {
+    event: ComponentEvent::Errored {
+      name: name.clone(),
+      message,
+    },
+    issued_at: Instant::now(),
+  });
+
+  if let Some(component) = component_registry.remove(&name) {
+    detach_component(&component, render_context, &name);
+  }
+
+  publisher.publish_event(Events::Component {
+    event: ComponentEvent::Detached { name },
+    issued_at: Instant::now(),
+  });
+}
+
+/// The events that components receive once the window moves to a monitor
+/// with a different scale factor, which are the change followed by the
+/// resize of the window to its new size in physical pixels.
+fn scale_factor_changed_events(
+  scale_factor: f64,
+  width: u32,
+  height: u32,
+) -> (Events, Events) {
+  return (
+    Events::Window {
+      event: WindowEvent::ScaleFactorChanged { scale_factor },
+      issued_at: Instant::now(),
+    },
+    Events::Window {
+      event: WindowEvent::Resize { width, height },
+      issued_at: Instant::now(),
+    },
+  );
+}
+
+/// The events published to the event loop that are forwarded to components.
+/// Runtime events are handled by the runtime, while input events are only
+/// received from the windowing system.
+fn forwarded_event(event: Events) -> Option<Events> {
+  return match event {
+    Events::Component { .. } | Events::Window { .. } => Some(event),
+    Events::Runtime { .. } | Events::Keyboard { .. } | Events::Mouse { .. } => {
+      None
+    }
+  };
+}
+
+/// Encodes the logical size of the window for the save bundle.
+fn encode_window_size(width: u32, height: u32) -> Vec<u8> {
+  return [width.to_le_bytes(), height.to_le_bytes()].concat();
+}
+
+/// Decodes the logical size of the window from the save bundle, ignoring
+/// sizes that can't be a window.
+fn decode_window_size(bytes: &[u8]) -> Option<(u32, u32)> {
+  let bytes: [u8; 8] = bytes.try_into().ok()?;
+  let width = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+  let height = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+  return match width > 0 && height > 0 {
+    true => Some((width, height)),
+    false => None,
+  };
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    forwarded_event,
+    scale_factor_changed_events,
+  };
+  use crate::events::{
+    Events,
+    WindowEvent,
+  };
+
+  #[test]
+  fn scale_factor_changes_reach_components() {
+    // The change is sent to components directly, while the resize is
+    // published to the event loop & forwarded once it's received.
+    let (changed, resized) = scale_factor_changed_events(2.0, 1600, 1200);
+    let resized = forwarded_event(resized);
+
+    assert!(matches!(
+      changed,
+      Events::Window {
+        event: WindowEvent::ScaleFactorChanged { scale_factor },
+        ..
+      } if scale_factor == 2.0
+    ));
+    assert!(matches!(
+      resized,
+      Some(Events::Window {
+        event: WindowEvent::Resize {
+          width: 1600,
+          height: 1200,
+        },
+        ..
+      })
+    ));
+    // The scale factor is published once the runtime starts.
+    assert!(forwarded_event(changed).is_some());
+  }
+}
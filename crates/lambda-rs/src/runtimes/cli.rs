@@ -0,0 +1,255 @@
+//! Standard command line flags that application runtimes can be configured
+//! through, so that every application built with lambda accepts the same
+//! flags for its window, renderer, & logging.
+
+use args::{
+  Argument,
+  ArgumentParser,
+  ArgumentType,
+  ArgumentValue,
+  ParsedArgument,
+};
+use logging::filter::FilterSpec;
+
+use crate::render::Backend;
+
+/// The values of the standard flags that were passed. Flags that weren't
+/// passed are None, leaving the runtime's configuration as it is.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StandardArgs {
+  /// `--width`, the width of the window in logical pixels.
+  pub width: Option<u32>,
+  /// `--height`, the height of the window in logical pixels.
+  pub height: Option<u32>,
+  /// `--fullscreen`, whether or not the window covers its monitor.
+  pub fullscreen: Option<bool>,
+  /// `--vsync`, whether or not presenting waits for vertical blank.
+  pub vsync: Option<bool>,
+  /// `--log-level`, a filter in the format of the `LAMBDA_LOG` environment
+  /// variable such as `info` or `warn,lambda::render=debug`.
+  pub log_level: Option<String>,
+  /// `--backend`, the rendering backend to render with.
+  pub backend: Option<Backend>,
+  /// `--capture-frame`, the frame to capture with a graphics debugger,
+  /// counting from 1.
+  pub capture_frame: Option<u64>,
+}
+
+impl StandardArgs {
+  /// Registers the standard flags with the parser, for applications that
+  /// parse flags of their own alongside them.
+  pub fn register(parser: ArgumentParser) -> ArgumentParser {
+    let flag = |name: &str, arg_type: ArgumentType, description: &str| {
+      return Argument::new(name)
+        .with_type(arg_type)
+        .with_description(description);
+    };
+
+    return parser
+      .with_argument(flag(
+        "--width",
+        ArgumentType::Integer,
+        "The width of the window in logical pixels.",
+      ))
+      .with_argument(flag(
+        "--height",
+        ArgumentType::Integer,
+        "The height of the window in logical pixels.",
+      ))
+      .with_argument(flag(
+        "--fullscreen",
+        ArgumentType::Boolean,
+        "Whether or not the window covers its monitor.",
+      ))
+      .with_argument(flag(
+        "--vsync",
+        ArgumentType::Boolean,
+        "Whether or not presenting waits for vertical blank.",
+      ))
+      .with_argument(flag(
+        "--log-level",
+        ArgumentType::String,
+        "The level to log at, such as info or warn,lambda::render=debug.",
+      ))
+      .with_argument(flag(
+        "--backend",
+        ArgumentType::String,
+        "The rendering backend: vulkan, metal, dx12, dx11, or gl.",
+      ))
+      .with_argument(flag(
+        "--capture-frame",
+        ArgumentType::Integer,
+        "The frame to capture with a graphics debugger, counting from 1.",
+      ));
+  }
+
+  /// Parses the standard flags out of the arguments, where the first
+  /// argument is the name of the executable. Only the standard flags are
+  /// accepted, so any other flag is an error.
+  pub fn parse(args: &[String]) -> Result<Self, String> {
+    let parsed = Self::register(ArgumentParser::new(executable_name(args)))
+      .try_compile(args)?;
+    return Self::from_parsed(&parsed);
+  }
+
+  /// The usage of the standard flags, for reporting flags that couldn't be
+  /// parsed.
+  pub fn usage(args: &[String]) -> String {
+    return Self::register(ArgumentParser::new(executable_name(args))).usage();
+  }
+
+  /// The standard flags within arguments that were parsed by a parser they
+  /// were registered with. Other arguments are ignored.
+  pub fn from_parsed(parsed: &[ParsedArgument]) -> Result<Self, String> {
+    let mut standard = Self::default();
+    for arg in parsed {
+      match (arg.name().as_str(), arg.value()) {
+        ("--width", ArgumentValue::Integer(width)) => {
+          standard.width = Some(positive(&arg.name(), width)?);
+        }
+        ("--height", ArgumentValue::Integer(height)) => {
+          standard.height = Some(positive(&arg.name(), height)?);
+        }
+        ("--fullscreen", ArgumentValue::Boolean(fullscreen)) => {
+          standard.fullscreen = Some(fullscreen);
+        }
+        ("--vsync", ArgumentValue::Boolean(vsync)) => {
+          standard.vsync = Some(vsync);
+        }
+        ("--log-level", ArgumentValue::String(log_level)) => {
+          FilterSpec::parse(&log_level)
+            .map_err(|error| format!("Invalid --log-level: {}", error))?;
+          standard.log_level = Some(log_level);
+        }
+        ("--backend", ArgumentValue::String(backend)) => {
+          standard.backend = Some(parse_backend(&backend)?);
+        }
+        ("--capture-frame", ArgumentValue::Integer(frame)) => {
+          standard.capture_frame = Some(positive(&arg.name(), frame)? as u64);
+        }
+        (_, _) => {}
+      }
+    }
+    return Ok(standard);
+  }
+}
+
+fn executable_name(args: &[String]) -> &str {
+  return args.first().map_or("lambda", |name| name.as_str());
+}
+
+fn positive(name: &str, value: i64) -> Result<u32, String> {
+  return match u32::try_from(value) {
+    Ok(value) if value > 0 => Ok(value),
+    _ => Err(format!(
+      "{} must be a positive integer, not {}.",
+      name, value
+    )),
+  };
+}
+
+fn parse_backend(name: &str) -> Result<Backend, String> {
+  return match name.trim().to_ascii_lowercase().as_str() {
+    "vulkan" => Ok(Backend::Vulkan),
+    "metal" => Ok(Backend::Metal),
+    "dx12" => Ok(Backend::Dx12),
+    "dx11" => Ok(Backend::Dx11),
+    "gl" | "opengl" => Ok(Backend::Gl),
+    _ => Err(format!(
+      "Unknown --backend {}, which must be one of vulkan, metal, dx12, dx11, \
+       or gl.",
+      name
+    )),
+  };
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use args::{
+    Argument,
+    ArgumentParser,
+    ArgumentType,
+  };
+
+  use super::StandardArgs;
+  use crate::render::Backend;
+
+  fn args(args: &[&str]) -> Vec<String> {
+    return args.iter().map(|arg| arg.to_string()).collect();
+  }
+
+  #[test]
+  fn only_passed_flags_are_set() {
+    assert_eq!(
+      StandardArgs::parse(&args(&["demo"])),
+      Ok(StandardArgs::default())
+    );
+
+    let standard = StandardArgs::parse(&args(&[
+      "demo",
+      "--width",
+      "1280",
+      "--vsync",
+      "false",
+      "--backend",
+      "Vulkan",
+      "--log-level",
+      "warn,lambda::render=debug",
+      "--capture-frame",
+      "3",
+    ]))
+    .unwrap();
+    assert_eq!(
+      standard,
+      StandardArgs {
+        width: Some(1280),
+        vsync: Some(false),
+        backend: Some(Backend::Vulkan),
+        log_level: Some("warn,lambda::render=debug".to_string()),
+        capture_frame: Some(3),
+        ..StandardArgs::default()
+      }
+    );
+  }
+
+  #[test]
+  fn invalid_values_are_rejected() {
+    assert!(StandardArgs::parse(&args(&["demo", "--height", "0"])).is_err());
+    assert!(StandardArgs::parse(&args(&["demo", "--backend", "d3d9"])).is_err());
+    assert!(StandardArgs::parse(&args(&[
+      "demo",
+      "--log-level",
+      "lambda=loud"
+    ]))
+    .is_err());
+  }
+
+  #[test]
+  fn unknown_flags_are_errors() {
+    assert_eq!(
+      StandardArgs::parse(&args(&["demo", "--widht", "1280"])),
+      Err("Argument: --widht is not a valid argument".to_string())
+    );
+    assert!(StandardArgs::parse(&args(&["demo", "--vsync", "maybe"])).is_err());
+    assert!(StandardArgs::usage(&args(&["demo"])).starts_with("Usage: demo"));
+  }
+
+  #[test]
+  fn standard_flags_are_parsed_alongside_other_flags() {
+    let parser = StandardArgs::register(ArgumentParser::new("demo"))
+      .with_argument(Argument::new("--speed").with_type(ArgumentType::Float));
+    let parsed = parser.compile(&args(&[
+      "demo",
+      "--speed",
+      "2.5",
+      "--fullscreen",
+      "true",
+    ]));
+
+    let standard = StandardArgs::from_parsed(&parsed).unwrap();
+    assert_eq!(standard.fullscreen, Some(true));
+    assert_eq!(standard.width, None);
+  }
+}
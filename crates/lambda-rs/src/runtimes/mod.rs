@@ -1,5 +1,10 @@
 pub mod application;
+pub mod cli;
+pub mod pacing;
+pub mod testing;
 pub use application::{
   ApplicationRuntime,
   ApplicationRuntimeBuilder,
 };
+pub use cli::StandardArgs;
+pub use testing::TestRuntime;
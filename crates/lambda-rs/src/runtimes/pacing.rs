@@ -0,0 +1,289 @@
+//! Frame pacing & frame time statistics for runtimes.
+
+use std::{
+  collections::VecDeque,
+  time::{
+    Duration,
+    Instant,
+  },
+};
+
+/// The frame time used by power saving mode when no target frame rate has
+/// been specified (30 fps).
+const POWER_SAVING_FRAME_TIME: Duration = Duration::from_nanos(33_333_333);
+
+/// The amount of time before a frame deadline that the pacer will stop
+/// sleeping and start spinning. OS sleeps routinely overshoot by around a
+/// millisecond, which is enough to miss a frame at higher frame rates.
+const SPIN_THRESHOLD: Duration = Duration::from_millis(1);
+
+// -------------------------------- FRAME PACER --------------------------------
+
+/// How a runtime should wait between frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrameWait {
+  /// Render the next frame immediately.
+  Poll,
+  /// Sleep precisely until the deadline and then render the next frame.
+  SleepUntil(Instant),
+  /// Yield to the event loop until the deadline or until an event arrives.
+  WaitUntil(Instant),
+}
+
+/// Computes how long a runtime should wait in between frames in order to
+/// hit a target frame rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FramePacer {
+  target_frame_time: Option<Duration>,
+  power_saving: bool,
+}
+
+impl FramePacer {
+  /// A new frame pacer doesn't limit the frame rate.
+  pub fn new() -> Self {
+    return Self {
+      target_frame_time: None,
+      power_saving: false,
+    };
+  }
+
+  /// Limit the frame rate to the target frames per second. A target of 0
+  /// removes the limit.
+  pub fn with_target_fps(mut self, target_fps: u32) -> Self {
+    self.target_frame_time = match target_fps {
+      0 => None,
+      fps => Some(Duration::from_secs_f64(1.0 / fps as f64)),
+    };
+    return self;
+  }
+
+  /// Wait on the event loop in between frames instead of sleeping, which
+  /// trades frame time precision for lower CPU usage. Limits the frame rate
+  /// to 30 fps unless a target frame rate has been specified.
+  pub fn with_power_saving_mode(mut self) -> Self {
+    self.power_saving = true;
+    return self;
+  }
+
+  /// The amount of time each frame is allotted, if the frame rate is limited.
+  pub fn target_frame_time(&self) -> Option<Duration> {
+    return match (self.target_frame_time, self.power_saving) {
+      (Some(frame_time), _) => Some(frame_time),
+      (None, true) => Some(POWER_SAVING_FRAME_TIME),
+      (None, false) => None,
+    };
+  }
+
+  /// Determine how to wait for the next frame given when the current frame
+  /// started.
+  pub fn next_frame(&self, frame_started_at: Instant) -> FrameWait {
+    return match (self.target_frame_time(), self.power_saving) {
+      (Some(frame_time), true) => {
+        FrameWait::WaitUntil(frame_started_at + frame_time)
+      }
+      (Some(frame_time), false) => {
+        FrameWait::SleepUntil(frame_started_at + frame_time)
+      }
+      (None, _) => FrameWait::Poll,
+    };
+  }
+
+  /// Whether or not the next frame is due given when the current frame
+  /// started. In power saving mode, events wake the runtime before the
+  /// deadline it waits until, so frames aren't due until it has passed.
+  pub fn is_frame_due(&self, frame_started_at: Instant, now: Instant) -> bool {
+    return match self.next_frame(frame_started_at) {
+      FrameWait::WaitUntil(deadline) => now >= deadline,
+      FrameWait::Poll | FrameWait::SleepUntil(_) => true,
+    };
+  }
+}
+
+/// Sleeps until the deadline, spinning for the final stretch to avoid
+/// oversleeping.
+pub fn sleep_until(deadline: Instant) {
+  let now = Instant::now();
+  if deadline <= now {
+    return;
+  }
+
+  let remaining = deadline - now;
+  if remaining > SPIN_THRESHOLD {
+    std::thread::sleep(remaining - SPIN_THRESHOLD);
+  }
+
+  while Instant::now() < deadline {
+    std::hint::spin_loop();
+  }
+}
+
+// ----------------------------- FRAME STATISTICS ------------------------------
+
+/// Frame time statistics computed over the most recent frames rendered by a
+/// runtime.
+#[derive(Clone, Debug)]
+pub struct FrameStatistics {
+  capacity: usize,
+  frame_times: VecDeque<Duration>,
+  total_frames: u64,
+}
+
+impl FrameStatistics {
+  /// Create frame statistics that are computed over the last `capacity`
+  /// frames.
+  pub fn new(capacity: usize) -> Self {
+    return Self {
+      capacity: capacity.max(1),
+      frame_times: VecDeque::with_capacity(capacity.max(1)),
+      total_frames: 0,
+    };
+  }
+
+  /// Record the time that a frame took.
+  pub fn record(&mut self, frame_time: Duration) {
+    if self.frame_times.len() == self.capacity {
+      self.frame_times.pop_front();
+    }
+    self.frame_times.push_back(frame_time);
+    self.total_frames += 1;
+  }
+
+  /// The total number of frames recorded since the runtime started.
+  pub fn total_frames(&self) -> u64 {
+    return self.total_frames;
+  }
+
+  /// The frame times of the recent frames, from oldest to newest.
+  pub fn frame_times(&self) -> impl Iterator<Item = Duration> + '_ {
+    return self.frame_times.iter().copied();
+  }
+
+  /// The frame time of the most recent frame.
+  pub fn last_frame_time(&self) -> Option<Duration> {
+    return self.frame_times.back().copied();
+  }
+
+  /// The average frame time across the recent frames.
+  pub fn average_frame_time(&self) -> Duration {
+    if self.frame_times.is_empty() {
+      return Duration::ZERO;
+    }
+    let total: Duration = self.frame_times.iter().sum();
+    return total / self.frame_times.len() as u32;
+  }
+
+  /// The frame time that the given percentage (0 to 100) of recent frames
+  /// were at or below. I.E. the 99th percentile is the frame time that only
+  /// 1% of frames took longer than.
+  pub fn percentile_frame_time(&self, percentile: f32) -> Duration {
+    if self.frame_times.is_empty() {
+      return Duration::ZERO;
+    }
+
+    let mut frame_times: Vec<Duration> =
+      self.frame_times.iter().copied().collect();
+    frame_times.sort();
+
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0 * frame_times.len() as f32)
+      .ceil() as usize;
+    return frame_times[rank.saturating_sub(1)];
+  }
+
+  /// The average frames per second across the recent frames.
+  pub fn frames_per_second(&self) -> f32 {
+    let average = self.average_frame_time().as_secs_f32();
+    if average == 0.0 {
+      return 0.0;
+    }
+    return 1.0 / average;
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::time::{
+    Duration,
+    Instant,
+  };
+
+  use super::{
+    FramePacer,
+    FrameStatistics,
+    FrameWait,
+    POWER_SAVING_FRAME_TIME,
+  };
+
+  #[test]
+  fn frame_pacer_waits() {
+    let now = Instant::now();
+    assert_eq!(FramePacer::new().next_frame(now), FrameWait::Poll);
+
+    let pacer = FramePacer::new().with_target_fps(50);
+    assert_eq!(
+      pacer.next_frame(now),
+      FrameWait::SleepUntil(now + Duration::from_millis(20))
+    );
+
+    let pacer = FramePacer::new()
+      .with_target_fps(50)
+      .with_power_saving_mode();
+    assert_eq!(
+      pacer.next_frame(now),
+      FrameWait::WaitUntil(now + Duration::from_millis(20))
+    );
+
+    let pacer = FramePacer::new().with_power_saving_mode();
+    assert_eq!(pacer.target_frame_time(), Some(POWER_SAVING_FRAME_TIME));
+    assert_eq!(
+      FramePacer::new().with_target_fps(0).target_frame_time(),
+      None
+    );
+  }
+
+  #[test]
+  fn power_saving_frames_are_due_at_the_deadline() {
+    let now = Instant::now();
+    let pacer = FramePacer::new()
+      .with_target_fps(50)
+      .with_power_saving_mode();
+    assert!(!pacer.is_frame_due(now, now));
+    assert!(!pacer.is_frame_due(now, now + Duration::from_millis(19)));
+    assert!(pacer.is_frame_due(now, now + Duration::from_millis(20)));
+
+    // Frames that sleep until the deadline are always due once they wake.
+    assert!(FramePacer::new().with_target_fps(50).is_frame_due(now, now));
+    assert!(FramePacer::new().is_frame_due(now, now));
+  }
+
+  #[test]
+  fn frame_statistics_over_recent_frames() {
+    let mut statistics = FrameStatistics::new(4);
+    assert_eq!(statistics.average_frame_time(), Duration::ZERO);
+    assert_eq!(statistics.frames_per_second(), 0.0);
+
+    for millis in [100, 10, 20, 30, 40] {
+      statistics.record(Duration::from_millis(millis));
+    }
+
+    // The first frame has fallen out of the window.
+    assert_eq!(statistics.total_frames(), 5);
+    assert_eq!(
+      statistics.last_frame_time(),
+      Some(Duration::from_millis(40))
+    );
+    assert_eq!(statistics.average_frame_time(), Duration::from_millis(25));
+    assert_eq!(statistics.frames_per_second(), 40.0);
+    assert_eq!(
+      statistics.percentile_frame_time(50.0),
+      Duration::from_millis(20)
+    );
+    assert_eq!(
+      statistics.percentile_frame_time(99.0),
+      Duration::from_millis(40)
+    );
+    assert_eq!(
+      statistics.percentile_frame_time(0.0),
+      Duration::from_millis(10)
+    );
+  }
+}
@@ -0,0 +1,356 @@
+//! A runtime for testing components without a window. Tests add components,
+//! send them synthetic events, and update them by a fixed frame time, so
+//! that component logic behaves the same on every run and in CI.
+
+use std::{
+  cell::RefCell,
+  rc::Rc,
+  time::{
+    Duration,
+    Instant,
+  },
+};
+
+use crate::{
+  component::{
+    Component,
+    ComponentRegistry,
+  },
+  events::{
+    Events,
+    Key,
+    Mouse,
+    WindowEvent,
+  },
+  input,
+  render::{
+    command::RenderCommand,
+    RenderContext,
+  },
+  runtimes::application::ComponentResult,
+  time::GameClock,
+};
+
+/// Drives the components of a test from test code. Unlike the application
+/// runtime, panics aren't caught, so that they fail the test that caused
+/// them, and errors returned by components are returned to the test.
+pub struct TestRuntime {
+  components: ComponentRegistry<ComponentResult, String>,
+  ordered: bool,
+  frame_time: Duration,
+  game_clock: Rc<RefCell<GameClock>>,
+}
+
+impl TestRuntime {
+  /// Creates a runtime without components that updates at 60 fps.
+  pub fn new() -> Self {
+    return Self {
+      components: ComponentRegistry::new(),
+      ordered: false,
+      frame_time: Duration::from_secs(1) / 60,
+      game_clock: Rc::new(RefCell::new(GameClock::new())),
+    };
+  }
+
+  /// The time that passes during every update.
+  pub fn with_frame_time(mut self, frame_time: Duration) -> Self {
+    self.frame_time = frame_time;
+    return self;
+  }
+
+  /// Adds a component with the names of the components it depends on,
+  /// returning a handle that tests can inspect the component through.
+  pub fn add_component<T: Component<ComponentResult, String> + 'static>(
+    &mut self,
+    name: &str,
+    dependencies: &[&str],
+    component: T,
+  ) -> Result<Rc<RefCell<T>>, String> {
+    self.ordered = false;
+    return self.components.register(name, dependencies, component);
+  }
+
+  /// A handle to the game clock that is ticked before every update.
+  pub fn game_clock(&self) -> Rc<RefCell<GameClock>> {
+    return self.game_clock.clone();
+  }
+
+  /// The number of updates that have run.
+  pub fn frame_count(&self) -> u64 {
+    return self.game_clock.borrow().frame_count();
+  }
+
+  /// Sends the event to every component in order.
+  pub fn send_event(&mut self, event: Events) -> Result<(), String> {
+    self.order()?;
+    for (name, component) in self.components.named_components() {
+      component
+        .borrow_mut()
+        .on_event(event.clone())
+        .map_err(|error| {
+          format!(
+            "The component {} failed to handle an event: {}",
+            name, error
+          )
+        })?;
+    }
+    return Ok(());
+  }
+
+  /// Sends a press of the key.
+  pub fn press_key(&mut self, key: input::Key) -> Result<(), String> {
+    return self.send_event(Events::Keyboard {
+      event: Key::Pressed {
+        scan_code: 0,
+        key,
+        virtual_key: None,
+      },
+      issued_at: Instant::now(),
+    });
+  }
+
+  /// Sends a release of the key.
+  pub fn release_key(&mut self, key: input::Key) -> Result<(), String> {
+    return self.send_event(Events::Keyboard {
+      event: Key::Released {
+        scan_code: 0,
+        key,
+        virtual_key: None,
+      },
+      issued_at: Instant::now(),
+    });
+  }
+
+  /// Sends the text as if it was typed.
+  pub fn type_text(&mut self, text: &str) -> Result<(), String> {
+    return self.send_event(Events::Keyboard {
+      event: Key::TextInput {
+        text: text.to_string(),
+      },
+      issued_at: Instant::now(),
+    });
+  }
+
+  /// Sends a resize of the window to the size in physical pixels.
+  pub fn resize(&mut self, width: u32, height: u32) -> Result<(), String> {
+    return self.send_event(Events::Window {
+      event: WindowEvent::Resize { width, height },
+      issued_at: Instant::now(),
+    });
+  }
+
+  /// Sends a move of the mouse cursor to the position in logical pixels.
+  pub fn move_mouse(&mut self, x: f64, y: f64) -> Result<(), String> {
+    return self.send_event(Events::Mouse {
+      event: Mouse::Moved {
+        x,
+        y,
+        dx: 0.0,
+        dy: 0.0,
+        device_id: 0,
+      },
+      issued_at: Instant::now(),
+    });
+  }
+
+  /// Ticks the game clock by the frame time and updates every component.
+  pub fn update(&mut self) -> Result<(), String> {
+    self.order()?;
+    self.game_clock.borrow_mut().tick(self.frame_time);
+    for (name, component) in self.components.named_components() {
+      component
+        .borrow_mut()
+        .on_update(&self.frame_time)
+        .map_err(|error| {
+          format!("The component {} failed to update: {}", name, error)
+        })?;
+    }
+    return Ok(());
+  }
+
+  /// Runs the number of updates.
+  pub fn update_frames(&mut self, frames: u32) -> Result<(), String> {
+    for _ in 0..frames {
+      self.update()?;
+    }
+    return Ok(());
+  }
+
+  /// Attaches every component to the render context, for tests that run
+  /// where a GPU is available.
+  pub fn attach(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<(), String> {
+    self.order()?;
+    for (name, component) in self.components.named_components() {
+      component
+        .borrow_mut()
+        .on_attach(render_context)
+        .map_err(|error| {
+          format!("The component {} failed to attach: {}", name, error)
+        })?;
+    }
+    return Ok(());
+  }
+
+  /// The commands that every component renders, in order, without
+  /// executing them.
+  pub fn render(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<Vec<RenderCommand>, String> {
+    self.order()?;
+    let mut commands = vec![];
+    for component in self.components.components() {
+      commands.extend(component.borrow_mut().on_render(render_context));
+    }
+    return Ok(commands);
+  }
+
+  /// Detaches every component from the render context, in reverse order.
+  pub fn detach(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<(), String> {
+    self.order()?;
+    for (name, component) in self.components.named_components().rev() {
+      component
+        .borrow_mut()
+        .on_detach(render_context)
+        .map_err(|error| {
+          format!("The component {} failed to detach: {}", name, error)
+        })?;
+    }
+    return Ok(());
+  }
+
+  /// Orders the components by their dependencies once they've changed.
+  fn order(&mut self) -> Result<(), String> {
+    if !self.ordered {
+      self.components.resolve_order()?;
+      self.ordered = true;
+    }
+    return Ok(());
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::TestRuntime;
+  use crate::{
+    component::Component,
+    events::{
+      Events,
+      Key,
+    },
+    input,
+    perf_hud::PerfHudComponent,
+    render::{
+      command::RenderCommand,
+      RenderContext,
+    },
+    runtimes::application::ComponentResult,
+  };
+
+  /// Counts what it receives and fails to update once it has seen enough
+  /// frames.
+  #[derive(Default)]
+  struct CountingComponent {
+    keys: Vec<input::Key>,
+    updated_for: Duration,
+    updates: u32,
+  }
+
+  impl Component<ComponentResult, String> for CountingComponent {
+    fn on_attach(
+      &mut self,
+      _render_context: &mut RenderContext,
+    ) -> Result<ComponentResult, String> {
+      return Ok(ComponentResult::Success);
+    }
+
+    fn on_detach(
+      &mut self,
+      _render_context: &mut RenderContext,
+    ) -> Result<ComponentResult, String> {
+      return Ok(ComponentResult::Success);
+    }
+
+    fn on_event(&mut self, event: Events) -> Result<ComponentResult, String> {
+      if let Events::Keyboard {
+        event: Key::Pressed { key, .. },
+        ..
+      } = event
+      {
+        self.keys.push(key);
+      }
+      return Ok(ComponentResult::Success);
+    }
+
+    fn on_update(
+      &mut self,
+      last_frame: &Duration,
+    ) -> Result<ComponentResult, String> {
+      self.updates += 1;
+      self.updated_for += *last_frame;
+      if self.updates > 3 {
+        return Err("Too many updates".to_string());
+      }
+      return Ok(ComponentResult::Success);
+    }
+
+    fn on_render(
+      &mut self,
+      _render_context: &mut RenderContext,
+    ) -> Vec<RenderCommand> {
+      return vec![];
+    }
+  }
+
+  #[test]
+  fn components_receive_events_and_fixed_updates() {
+    let mut runtime =
+      TestRuntime::new().with_frame_time(Duration::from_millis(10));
+    let counter = runtime
+      .add_component("counter", &[], CountingComponent::default())
+      .unwrap();
+
+    runtime.press_key(input::Key::W).unwrap();
+    runtime.release_key(input::Key::W).unwrap();
+    runtime.press_key(input::Key::Space).unwrap();
+    runtime.update_frames(3).unwrap();
+
+    assert_eq!(counter.borrow().keys, [input::Key::W, input::Key::Space]);
+    assert_eq!(counter.borrow().updated_for, Duration::from_millis(30));
+    assert_eq!(runtime.frame_count(), 3);
+    assert_eq!(
+      runtime.game_clock().borrow().elapsed(),
+      Duration::from_millis(30)
+    );
+    assert_eq!(
+      runtime.update(),
+      Err("The component counter failed to update: Too many updates".into())
+    );
+  }
+
+  #[test]
+  fn components_are_ordered_by_their_dependencies() {
+    let mut runtime = TestRuntime::new();
+    runtime
+      .add_component("dependent", &["missing"], CountingComponent::default())
+      .unwrap();
+    assert!(runtime.update().is_err());
+
+    let hud = runtime
+      .add_component("missing", &[], PerfHudComponent::new())
+      .unwrap();
+    assert!(hud.borrow().is_visible());
+    runtime.press_key(input::Key::F3).unwrap();
+    assert!(!hud.borrow().is_visible());
+  }
+}
@@ -0,0 +1,665 @@
+//! Scene graph for composing transforms. Nodes are stored in a scene and
+//! referenced through handles, with each node's world transform being the
+//! product of its own transform and the world transform of its parent. Nodes
+//! may carry a renderable which is drawn with its world transform when the
+//! scene is traversed.
+
+use std::ops::Range;
+
+use crate::{
+  math::{
+    matrix::Matrix,
+    quaternion::Quaternion,
+  },
+  render::{
+    command::RenderCommand,
+    pipeline::PipelineStage,
+    ResourceId,
+  },
+};
+
+/// A column major 4x4 matrix, the layout expected by shaders.
+pub type TransformMatrix = [[f32; 4]; 4];
+
+const IDENTITY: TransformMatrix = [
+  [1.0, 0.0, 0.0, 0.0],
+  [0.0, 1.0, 0.0, 0.0],
+  [0.0, 0.0, 1.0, 0.0],
+  [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Multiplies two column major matrices (a * b). The math module treats
+/// arrays as rows, so column major arrays are multiplied in reverse order.
+fn multiply(a: &TransformMatrix, b: &TransformMatrix) -> TransformMatrix {
+  return b.multiply(a);
+}
+
+// -------------------------------- TRANSFORM ----------------------------------
+
+/// A translation, rotation, and scale. Rotations are euler angles in radians
+/// that are applied around the x, then y, and then z axes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+  pub translation: [f32; 3],
+  pub rotation: [f32; 3],
+  pub scale: [f32; 3],
+}
+
+impl Transform {
+  /// Creates the identity transform.
+  pub fn new() -> Self {
+    return Self {
+      translation: [0.0, 0.0, 0.0],
+      rotation: [0.0, 0.0, 0.0],
+      scale: [1.0, 1.0, 1.0],
+    };
+  }
+
+  /// Set the translation of the transform.
+  pub fn with_translation(mut self, translation: [f32; 3]) -> Self {
+    self.translation = translation;
+    return self;
+  }
+
+  /// Set the rotation of the transform in radians around each axis.
+  pub fn with_rotation(mut self, rotation: [f32; 3]) -> Self {
+    self.rotation = rotation;
+    return self;
+  }
+
+  /// Set the rotation of the transform from a quaternion, which is stored as
+  /// euler angles.
+  pub fn with_quaternion(mut self, rotation: Quaternion) -> Self {
+    self.rotation = rotation.to_euler();
+    return self;
+  }
+
+  /// The rotation of the transform as a quaternion.
+  pub fn quaternion(&self) -> Quaternion {
+    return Quaternion::from_euler(self.rotation);
+  }
+
+  /// Set the scale of the transform along each axis.
+  pub fn with_scale(mut self, scale: [f32; 3]) -> Self {
+    self.scale = scale;
+    return self;
+  }
+
+  /// The column major matrix that scales, rotates, and then translates.
+  pub fn matrix(&self) -> TransformMatrix {
+    let (sin_x, cos_x) = self.rotation[0].sin_cos();
+    let (sin_y, cos_y) = self.rotation[1].sin_cos();
+    let (sin_z, cos_z) = self.rotation[2].sin_cos();
+    let [scale_x, scale_y, scale_z] = self.scale;
+    let [x, y, z] = self.translation;
+
+    // Columns of Rz * Ry * Rx, each scaled by the scale along its axis.
+    return [
+      [
+        cos_z * cos_y * scale_x,
+        sin_z * cos_y * scale_x,
+        -sin_y * scale_x,
+        0.0,
+      ],
+      [
+        (cos_z * sin_y * sin_x - sin_z * cos_x) * scale_y,
+        (sin_z * sin_y * sin_x + cos_z * cos_x) * scale_y,
+        cos_y * sin_x * scale_y,
+        0.0,
+      ],
+      [
+        (cos_z * sin_y * cos_x + sin_z * sin_x) * scale_z,
+        (sin_z * sin_y * cos_x - cos_z * sin_x) * scale_z,
+        cos_y * cos_x * scale_z,
+        0.0,
+      ],
+      [x, y, z, 1.0],
+    ];
+  }
+}
+
+// -------------------------------- RENDERABLE ---------------------------------
+
+/// A reference to geometry that has been attached to a render pipeline. The
+/// world transform of the node is uploaded as a vertex push constant before
+/// the vertices are drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Renderable {
+  pub pipeline: ResourceId,
+  pub buffer: u32,
+  pub vertices: Range<u32>,
+  pub transform_offset: u32,
+}
+
+impl Renderable {
+  /// Creates a renderable that draws the vertices from the buffer at the
+  /// given index of the pipeline, with the transform at push constant
+  /// offset 0.
+  pub fn new(pipeline: ResourceId, buffer: u32, vertices: Range<u32>) -> Self {
+    return Self {
+      pipeline,
+      buffer,
+      vertices,
+      transform_offset: 0,
+    };
+  }
+
+  /// The offset in bytes of the transform within the push constants.
+  pub fn with_transform_offset(mut self, transform_offset: u32) -> Self {
+    self.transform_offset = transform_offset;
+    return self;
+  }
+}
+
+// ----------------------------------- SCENE -----------------------------------
+
+/// A handle to a node within a scene. Handles to removed nodes are never
+/// reused, even once the slot of the node is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeHandle {
+  index: usize,
+  generation: u32,
+}
+
+/// A node within the scene graph.
+#[derive(Clone, Debug)]
+pub struct Node {
+  name: String,
+  transform: Transform,
+  world_transform: TransformMatrix,
+  parent: Option<NodeHandle>,
+  children: Vec<NodeHandle>,
+  renderable: Option<Renderable>,
+}
+
+impl Node {
+  /// The name of the node.
+  pub fn name(&self) -> &str {
+    return &self.name;
+  }
+
+  /// The transform of the node relative to its parent.
+  pub fn transform(&self) -> &Transform {
+    return &self.transform;
+  }
+
+  /// The parent of the node, if it has one.
+  pub fn parent(&self) -> Option<NodeHandle> {
+    return self.parent;
+  }
+
+  /// The children of the node in the order that they were added.
+  pub fn children(&self) -> &[NodeHandle] {
+    return &self.children;
+  }
+
+  /// The renderable drawn at the node, if it has one.
+  pub fn renderable(&self) -> Option<&Renderable> {
+    return self.renderable.as_ref();
+  }
+}
+
+struct Slot {
+  generation: u32,
+  node: Option<Node>,
+}
+
+/// A hierarchy of nodes with transforms.
+pub struct Scene {
+  slots: Vec<Slot>,
+  roots: Vec<NodeHandle>,
+  world_transforms_dirty: bool,
+}
+
+impl Scene {
+  /// Creates an empty scene.
+  pub fn new() -> Self {
+    return Self {
+      slots: vec![],
+      roots: vec![],
+      world_transforms_dirty: false,
+    };
+  }
+
+  /// Adds a node to the root of the scene.
+  pub fn add_node(&mut self, name: &str, transform: Transform) -> NodeHandle {
+    let handle = self.allocate(Node {
+      name: name.to_string(),
+      transform,
+      world_transform: IDENTITY,
+      parent: None,
+      children: vec![],
+      renderable: None,
+    });
+    self.roots.push(handle);
+    return handle;
+  }
+
+  /// Adds a node as the last child of the parent.
+  pub fn add_child(
+    &mut self,
+    parent: NodeHandle,
+    name: &str,
+    transform: Transform,
+  ) -> Result<NodeHandle, String> {
+    if self.node(parent).is_none() {
+      return Err(format!("The parent of {} is not in the scene.", name));
+    }
+
+    let child = self.add_node(name, transform);
+    self.set_parent(child, Some(parent))?;
+    return Ok(child);
+  }
+
+  /// Moves the node under a new parent, or to the root of the scene when the
+  /// parent is None. Fails if either node isn't in the scene or if the new
+  /// parent is the node itself or one of its descendants.
+  pub fn set_parent(
+    &mut self,
+    node: NodeHandle,
+    parent: Option<NodeHandle>,
+  ) -> Result<(), String> {
+    if self.node(node).is_none() {
+      return Err("The node is not in the scene.".to_string());
+    }
+
+    if let Some(parent) = parent {
+      if self.node(parent).is_none() {
+        return Err("The parent is not in the scene.".to_string());
+      }
+
+      let mut ancestor = Some(parent);
+      while let Some(current) = ancestor {
+        if current == node {
+          return Err(
+            "A node can't be parented to itself or its descendants."
+              .to_string(),
+          );
+        }
+        ancestor = self.node(current).and_then(|current| current.parent);
+      }
+    }
+
+    self.detach_from_parent(node);
+    match parent {
+      Some(parent) => self.node_mut(parent).unwrap().children.push(node),
+      None => self.roots.push(node),
+    }
+    self.node_mut(node).unwrap().parent = parent;
+    self.world_transforms_dirty = true;
+    return Ok(());
+  }
+
+  /// Removes the node and all of its descendants from the scene. Returns
+  /// false if the node wasn't in the scene.
+  pub fn remove_node(&mut self, node: NodeHandle) -> bool {
+    if self.node(node).is_none() {
+      return false;
+    }
+
+    self.detach_from_parent(node);
+    let mut to_remove = vec![node];
+    while let Some(handle) = to_remove.pop() {
+      let slot = &mut self.slots[handle.index];
+      if let Some(removed) = slot.node.take() {
+        slot.generation += 1;
+        to_remove.extend(removed.children);
+      }
+    }
+    return true;
+  }
+
+  /// Get the node if it's still in the scene.
+  pub fn node(&self, node: NodeHandle) -> Option<&Node> {
+    let slot = self.slots.get(node.index)?;
+    if slot.generation != node.generation {
+      return None;
+    }
+    return slot.node.as_ref();
+  }
+
+  /// Find the first node with the given name, searching from the roots in
+  /// the order that nodes are drawn.
+  pub fn find(&self, name: &str) -> Option<NodeHandle> {
+    return self
+      .traversal_order()
+      .into_iter()
+      .find(|handle| self.node(*handle).unwrap().name == name);
+  }
+
+  /// The nodes at the root of the scene.
+  pub fn roots(&self) -> &[NodeHandle] {
+    return &self.roots;
+  }
+
+  /// The number of nodes in the scene.
+  pub fn len(&self) -> usize {
+    return self.slots.iter().filter(|slot| slot.node.is_some()).count();
+  }
+
+  /// Whether or not the scene has any nodes.
+  pub fn is_empty(&self) -> bool {
+    return self.len() == 0;
+  }
+
+  /// Replace the transform of a node relative to its parent.
+  pub fn set_transform(&mut self, node: NodeHandle, transform: Transform) {
+    if let Some(node) = self.node_mut(node) {
+      node.transform = transform;
+      self.world_transforms_dirty = true;
+    }
+  }
+
+  /// Attach a renderable to the node or remove it when None.
+  pub fn set_renderable(
+    &mut self,
+    node: NodeHandle,
+    renderable: Option<Renderable>,
+  ) {
+    if let Some(node) = self.node_mut(node) {
+      node.renderable = renderable;
+    }
+  }
+
+  /// The transform of the node relative to the root of the scene.
+  pub fn world_transform(
+    &mut self,
+    node: NodeHandle,
+  ) -> Option<TransformMatrix> {
+    self.update_world_transforms();
+    return self.node(node).map(|node| node.world_transform);
+  }
+
+  /// Recomputes the world transforms of every node if any transforms or
+  /// parents have changed since they were last computed.
+  pub fn update_world_transforms(&mut self) {
+    if !self.world_transforms_dirty {
+      return;
+    }
+
+    let mut stack: Vec<(NodeHandle, TransformMatrix)> = self
+      .roots
+      .iter()
+      .rev()
+      .map(|root| (*root, IDENTITY))
+      .collect();
+
+    while let Some((handle, parent_transform)) = stack.pop() {
+      let node = self.node_mut(handle).unwrap();
+      node.world_transform =
+        multiply(&parent_transform, &node.transform.matrix());
+
+      let world_transform = node.world_transform;
+      for child in node.children.iter().rev() {
+        stack.push((*child, world_transform));
+      }
+    }
+
+    self.world_transforms_dirty = false;
+  }
+
+  /// Traverses the scene depth first and produces the commands to draw every
+  /// renderable node. The world transform of each node is multiplied by the
+  /// view projection matrix before being pushed to the vertex stage.
+  pub fn render_commands(
+    &mut self,
+    view_projection: &TransformMatrix,
+  ) -> Vec<RenderCommand> {
+    self.update_world_transforms();
+
+    let mut commands = vec![];
+    let mut active_pipeline = None;
+
+    for handle in self.traversal_order() {
+      let node = self.node(handle).unwrap();
+      let renderable = match &node.renderable {
+        Some(renderable) => renderable,
+        None => continue,
+      };
+
+      if active_pipeline != Some(renderable.pipeline) {
+        commands.push(RenderCommand::SetPipeline {
+          pipeline: renderable.pipeline,
+        });
+        active_pipeline = Some(renderable.pipeline);
+      }
+
+      let transform = multiply(view_projection, &node.world_transform);
+      commands.push(RenderCommand::BindVertexBuffer {
+        pipeline: renderable.pipeline,
+        buffer: renderable.buffer,
+      });
+      commands.push(RenderCommand::PushConstants {
+        pipeline: renderable.pipeline,
+        stage: PipelineStage::VERTEX,
+        offset: renderable.transform_offset,
+        bytes: transform
+          .iter()
+          .flatten()
+          .map(|value| value.to_bits())
+          .collect(),
+      });
+      commands.push(RenderCommand::Draw {
+        vertices: renderable.vertices.clone(),
+      });
+    }
+
+    return commands;
+  }
+
+  /// Every node in depth first order, starting with the roots.
+  fn traversal_order(&self) -> Vec<NodeHandle> {
+    let mut order = vec![];
+    let mut stack: Vec<NodeHandle> = self.roots.iter().rev().copied().collect();
+    while let Some(handle) = stack.pop() {
+      order.push(handle);
+      stack.extend(self.node(handle).unwrap().children.iter().rev());
+    }
+    return order;
+  }
+
+  fn node_mut(&mut self, node: NodeHandle) -> Option<&mut Node> {
+    let slot = self.slots.get_mut(node.index)?;
+    if slot.generation != node.generation {
+      return None;
+    }
+    return slot.node.as_mut();
+  }
+
+  fn allocate(&mut self, node: Node) -> NodeHandle {
+    self.world_transforms_dirty = true;
+    match self.slots.iter().position(|slot| slot.node.is_none()) {
+      Some(index) => {
+        self.slots[index].node = Some(node);
+        return NodeHandle {
+          index,
+          generation: self.slots[index].generation,
+        };
+      }
+      None => {
+        self.slots.push(Slot {
+          generation: 0,
+          node: Some(node),
+        });
+        return NodeHandle {
+          index: self.slots.len() - 1,
+          generation: 0,
+        };
+      }
+    }
+  }
+
+  /// Removes the node from the children of its parent or the roots.
+  fn detach_from_parent(&mut self, node: NodeHandle) {
+    let parent = self.node(node).and_then(|node| node.parent);
+    let siblings = match parent {
+      Some(parent) => &mut self.node_mut(parent).unwrap().children,
+      None => &mut self.roots,
+    };
+    siblings.retain(|sibling| *sibling != node);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    Renderable,
+    Scene,
+    Transform,
+    IDENTITY,
+  };
+  use crate::{
+    assert_approximately_equal,
+    math::quaternion::Quaternion,
+    render::{
+      command::RenderCommand,
+      ResourceId,
+    },
+  };
+
+  #[test]
+  fn transform_matrix_is_column_major() {
+    let matrix = Transform::new()
+      .with_translation([1.0, 2.0, 3.0])
+      .with_scale([2.0, 2.0, 2.0])
+      .matrix();
+    assert_eq!(matrix[3], [1.0, 2.0, 3.0, 1.0]);
+    assert_eq!(matrix[0], [2.0, 0.0, 0.0, 0.0]);
+
+    // A quarter turn around z maps the x axis onto the y axis.
+    let rotated = Transform::new()
+      .with_rotation([0.0, 0.0, std::f32::consts::FRAC_PI_2])
+      .matrix();
+    assert_approximately_equal!(rotated[0][0], 0.0, 1e-6);
+    assert_approximately_equal!(rotated[0][1], 1.0, 1e-6);
+
+    assert_eq!(Transform::new().matrix(), IDENTITY);
+  }
+
+  #[test]
+  fn transforms_match_quaternion_rotations() {
+    let transform = Transform::new().with_rotation([0.4, -1.1, 2.3]);
+    let expected = transform.quaternion().to_matrix4();
+    let matrix = transform.matrix();
+    for column in 0..3 {
+      for row in 0..3 {
+        assert_approximately_equal!(
+          matrix[column][row],
+          expected[column][row],
+          1e-5
+        );
+      }
+    }
+
+    let rotation = Quaternion::from_axis_angle([1.0, 1.0, 0.0], 0.8);
+    let transform = Transform::new().with_quaternion(rotation);
+    assert_approximately_equal!(
+      transform.quaternion().dot(&rotation),
+      1.0,
+      1e-5
+    );
+  }
+
+  #[test]
+  fn world_transforms_propagate_through_the_hierarchy() {
+    let mut scene = Scene::new();
+    let parent = scene.add_node(
+      "parent",
+      Transform::new()
+        .with_translation([1.0, 0.0, 0.0])
+        .with_scale([2.0, 2.0, 2.0]),
+    );
+    let child = scene
+      .add_child(
+        parent,
+        "child",
+        Transform::new().with_translation([1.0, 1.0, 0.0]),
+      )
+      .unwrap();
+
+    // The parent scales the translation of the child before translating it.
+    assert_eq!(
+      scene.world_transform(child).unwrap()[3],
+      [3.0, 2.0, 0.0, 1.0]
+    );
+
+    scene.set_transform(parent, Transform::new());
+    assert_eq!(
+      scene.world_transform(child).unwrap()[3],
+      [1.0, 1.0, 0.0, 1.0]
+    );
+
+    scene.set_parent(child, None).unwrap();
+    assert_eq!(scene.roots(), &[parent, child]);
+    assert!(scene.node(parent).unwrap().children().is_empty());
+    assert_eq!(scene.find("child"), Some(child));
+  }
+
+  #[test]
+  fn scene_rejects_cycles_and_stale_handles() {
+    let mut scene = Scene::new();
+    let root = scene.add_node("root", Transform::new());
+    let child = scene.add_child(root, "child", Transform::new()).unwrap();
+    let grandchild = scene
+      .add_child(child, "grandchild", Transform::new())
+      .unwrap();
+
+    assert!(scene.set_parent(root, Some(grandchild)).is_err());
+    assert!(scene.set_parent(root, Some(root)).is_err());
+
+    assert!(scene.remove_node(child));
+    assert_eq!(scene.len(), 1);
+    assert!(scene.node(grandchild).is_none());
+    assert!(!scene.remove_node(child));
+
+    // Slots are reused without reviving old handles.
+    let replacement = scene.add_node("replacement", Transform::new());
+    assert!(scene.node(child).is_none());
+    assert_eq!(scene.node(replacement).unwrap().name(), "replacement");
+  }
+
+  #[test]
+  fn render_commands_traverse_depth_first() {
+    let mut scene = Scene::new();
+    let body = scene
+      .add_node("body", Transform::new().with_translation([0.0, 1.0, 0.0]));
+    let arm = scene.add_child(body, "arm", Transform::new()).unwrap();
+    scene.add_node("empty", Transform::new());
+    let other = scene.add_node("other", Transform::new());
+
+    scene.set_renderable(
+      body,
+      Some(Renderable::new(ResourceId::new(0, 0), 0, 0..3)),
+    );
+    scene.set_renderable(
+      arm,
+      Some(
+        Renderable::new(ResourceId::new(0, 0), 1, 0..6)
+          .with_transform_offset(16),
+      ),
+    );
+    scene.set_renderable(
+      other,
+      Some(Renderable::new(ResourceId::new(1, 0), 0, 0..3)),
+    );
+
+    let commands = scene.render_commands(&IDENTITY);
+    // One pipeline switch per change in pipeline and three commands per node.
+    assert_eq!(commands.len(), 2 + 3 * 3);
+
+    match &commands[5] {
+      RenderCommand::PushConstants { offset, bytes, .. } => {
+        assert_eq!(*offset, 16);
+        assert_eq!(f32::from_bits(bytes[13]), 1.0);
+      }
+      _ => panic!("Expected the arm to push its transform."),
+    }
+
+    match &commands[7] {
+      RenderCommand::SetPipeline { pipeline } => {
+        assert_eq!(*pipeline, ResourceId::new(1, 0))
+      }
+      _ => panic!("Expected the pipeline to switch for the last node."),
+    }
+  }
+}
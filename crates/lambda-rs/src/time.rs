@@ -0,0 +1,344 @@
+//! Game time that can be paused & scaled independently of wall time, along
+//! with timers, stopwatches, and fixed timesteps that advance with it.
+
+use std::time::Duration;
+
+// --------------------------------- GAME CLOCK --------------------------------
+
+/// A clock that advances by the wall time of every frame multiplied by its
+/// time scale. Paused clocks don't advance, but still count frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameClock {
+  time_scale: f64,
+  paused: bool,
+  delta: Duration,
+  elapsed: Duration,
+  frame_count: u64,
+}
+
+impl GameClock {
+  /// A new clock is running at normal speed and hasn't ticked yet.
+  pub fn new() -> Self {
+    return Self {
+      time_scale: 1.0,
+      paused: false,
+      delta: Duration::ZERO,
+      elapsed: Duration::ZERO,
+      frame_count: 0,
+    };
+  }
+
+  /// Advances the clock by the wall time that the last frame took and
+  /// returns how much game time passed.
+  pub fn tick(&mut self, frame_time: Duration) -> Duration {
+    self.delta = match self.paused {
+      true => Duration::ZERO,
+      false => frame_time.mul_f64(self.time_scale),
+    };
+    self.elapsed += self.delta;
+    self.frame_count += 1;
+    return self.delta;
+  }
+
+  /// The game time that passed during the last frame.
+  pub fn delta(&self) -> Duration {
+    return self.delta;
+  }
+
+  /// The game time that passed during the last frame in seconds.
+  pub fn delta_seconds(&self) -> f32 {
+    return self.delta.as_secs_f32();
+  }
+
+  /// The game time that passed since the clock was created.
+  pub fn elapsed(&self) -> Duration {
+    return self.elapsed;
+  }
+
+  /// The number of frames the clock has ticked, including paused frames.
+  pub fn frame_count(&self) -> u64 {
+    return self.frame_count;
+  }
+
+  /// How fast game time passes relative to wall time, where 0.5 is half
+  /// speed. Negative scales are treated as 0.
+  pub fn set_time_scale(&mut self, time_scale: f64) {
+    self.time_scale = time_scale.max(0.0);
+  }
+
+  pub fn time_scale(&self) -> f64 {
+    return self.time_scale;
+  }
+
+  pub fn set_paused(&mut self, paused: bool) {
+    self.paused = paused;
+  }
+
+  pub fn is_paused(&self) -> bool {
+    return self.paused;
+  }
+}
+
+impl Default for GameClock {
+  fn default() -> Self {
+    return Self::new();
+  }
+}
+
+// ----------------------------------- TIMER -----------------------------------
+
+/// Counts down a duration of game time, optionally restarting every time it
+/// finishes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Timer {
+  duration: Duration,
+  elapsed: Duration,
+  repeating: bool,
+}
+
+impl Timer {
+  /// Creates a timer that finishes once the duration has passed.
+  pub fn new(duration: Duration) -> Self {
+    return Self {
+      duration,
+      elapsed: Duration::ZERO,
+      repeating: false,
+    };
+  }
+
+  /// Restart the timer every time it finishes.
+  pub fn with_repeating(mut self) -> Self {
+    self.repeating = true;
+    return self;
+  }
+
+  /// Advances the timer and returns the number of times it finished, which
+  /// can be more than once for repeating timers.
+  pub fn advance(&mut self, delta: Duration) -> u32 {
+    if self.is_finished() {
+      return 0;
+    }
+    self.elapsed += delta;
+    if self.elapsed < self.duration {
+      return 0;
+    }
+
+    if !self.repeating || self.duration.is_zero() {
+      self.elapsed = self.duration;
+      return 1;
+    }
+
+    let finished = self.elapsed.as_nanos() / self.duration.as_nanos();
+    self.elapsed = Duration::from_nanos(
+      (self.elapsed.as_nanos() % self.duration.as_nanos()) as u64,
+    );
+    return finished as u32;
+  }
+
+  /// Whether a timer that doesn't repeat has finished.
+  pub fn is_finished(&self) -> bool {
+    return !self.repeating && self.elapsed >= self.duration;
+  }
+
+  /// The game time left until the timer finishes.
+  pub fn remaining(&self) -> Duration {
+    return self.duration.saturating_sub(self.elapsed);
+  }
+
+  /// How far the timer is towards finishing, from 0 to 1.
+  pub fn progress(&self) -> f32 {
+    if self.duration.is_zero() {
+      return 1.0;
+    }
+    return (self.elapsed.as_secs_f64() / self.duration.as_secs_f64()) as f32;
+  }
+
+  /// Restarts the timer from the beginning.
+  pub fn reset(&mut self) {
+    self.elapsed = Duration::ZERO;
+  }
+}
+
+// --------------------------------- STOPWATCH ---------------------------------
+
+/// Measures the game time that passes while it's running.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stopwatch {
+  elapsed: Duration,
+  paused: bool,
+}
+
+impl Stopwatch {
+  /// A new stopwatch is running from zero.
+  pub fn new() -> Self {
+    return Self::default();
+  }
+
+  /// Advances the stopwatch unless it's paused.
+  pub fn advance(&mut self, delta: Duration) {
+    if !self.paused {
+      self.elapsed += delta;
+    }
+  }
+
+  pub fn elapsed(&self) -> Duration {
+    return self.elapsed;
+  }
+
+  pub fn set_paused(&mut self, paused: bool) {
+    self.paused = paused;
+  }
+
+  pub fn is_paused(&self) -> bool {
+    return self.paused;
+  }
+
+  /// Sets the stopwatch back to zero without pausing or resuming it.
+  pub fn reset(&mut self) {
+    self.elapsed = Duration::ZERO;
+  }
+}
+
+// ------------------------------- FIXED TIMESTEP ------------------------------
+
+/// The most steps a fixed timestep runs in a frame by default, which keeps a
+/// slow frame from causing even slower frames afterwards.
+const DEFAULT_MAX_STEPS: u32 = 8;
+
+/// Splits game time into steps of a fixed size, such as for physics, and
+/// carries the time left over into the next frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedTimestep {
+  step: Duration,
+  max_steps: u32,
+  accumulated: Duration,
+}
+
+impl FixedTimestep {
+  /// Creates a fixed timestep that runs steps of the given size.
+  pub fn new(step: Duration) -> Self {
+    return Self {
+      step: step.max(Duration::from_nanos(1)),
+      max_steps: DEFAULT_MAX_STEPS,
+      accumulated: Duration::ZERO,
+    };
+  }
+
+  /// Creates a fixed timestep that runs the given number of steps per second
+  /// of game time.
+  pub fn from_rate(steps_per_second: u32) -> Self {
+    return Self::new(Duration::from_secs_f64(
+      1.0 / steps_per_second.max(1) as f64,
+    ));
+  }
+
+  /// The most steps that run in a single frame. Time beyond that is dropped.
+  pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+    self.max_steps = max_steps.max(1);
+    return self;
+  }
+
+  pub fn step(&self) -> Duration {
+    return self.step;
+  }
+
+  /// Accumulates the game time of a frame and returns the number of steps to
+  /// run. Paused clocks pass no time, so they run no steps.
+  pub fn advance(&mut self, delta: Duration) -> u32 {
+    self.accumulated += delta;
+    let steps = (self.accumulated.as_nanos() / self.step.as_nanos())
+      .min(self.max_steps as u128) as u32;
+    self.accumulated = self.accumulated.saturating_sub(self.step * steps);
+
+    if steps == self.max_steps {
+      self.accumulated = self.accumulated.min(self.step);
+    }
+    return steps;
+  }
+
+  /// How far the leftover time is into the next step, from 0 to 1, for
+  /// interpolating between the last two steps when rendering.
+  pub fn alpha(&self) -> f32 {
+    return (self.accumulated.as_secs_f64() / self.step.as_secs_f64()) as f32;
+  }
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::{
+    FixedTimestep,
+    GameClock,
+    Stopwatch,
+    Timer,
+  };
+
+  fn millis(millis: u64) -> Duration {
+    return Duration::from_millis(millis);
+  }
+
+  #[test]
+  fn game_clocks_scale_and_pause() {
+    let mut clock = GameClock::new();
+    assert_eq!(clock.tick(millis(10)), millis(10));
+
+    clock.set_time_scale(0.5);
+    assert_eq!(clock.tick(millis(10)), millis(5));
+
+    clock.set_paused(true);
+    assert_eq!(clock.tick(millis(10)), Duration::ZERO);
+    assert_eq!(clock.elapsed(), millis(15));
+    assert_eq!(clock.frame_count(), 3);
+
+    clock.set_time_scale(-1.0);
+    assert_eq!(clock.time_scale(), 0.0);
+  }
+
+  #[test]
+  fn timers_finish_once_or_repeat() {
+    let mut timer = Timer::new(millis(100));
+    assert_eq!(timer.advance(millis(60)), 0);
+    assert_eq!(timer.remaining(), millis(40));
+    assert_eq!(timer.advance(millis(60)), 1);
+    assert!(timer.is_finished());
+    assert_eq!(timer.advance(millis(60)), 0);
+    assert_eq!(timer.progress(), 1.0);
+
+    let mut repeating = Timer::new(millis(100)).with_repeating();
+    assert_eq!(repeating.advance(millis(250)), 2);
+    assert!(!repeating.is_finished());
+    assert_eq!(repeating.remaining(), millis(50));
+
+    timer.reset();
+    assert!(!timer.is_finished());
+  }
+
+  #[test]
+  fn stopwatches_only_run_while_unpaused() {
+    let mut stopwatch = Stopwatch::new();
+    stopwatch.advance(millis(10));
+    stopwatch.set_paused(true);
+    stopwatch.advance(millis(10));
+    assert_eq!(stopwatch.elapsed(), millis(10));
+
+    stopwatch.reset();
+    assert_eq!(stopwatch.elapsed(), Duration::ZERO);
+  }
+
+  #[test]
+  fn fixed_timesteps_carry_leftover_time() {
+    let mut timestep = FixedTimestep::new(millis(10));
+    assert_eq!(timestep.advance(millis(25)), 2);
+    assert_eq!(timestep.alpha(), 0.5);
+    assert_eq!(timestep.advance(millis(5)), 1);
+    assert_eq!(timestep.advance(Duration::ZERO), 0);
+
+    // Slow frames are capped instead of running every missed step.
+    let mut capped = FixedTimestep::new(millis(10)).with_max_steps(3);
+    assert_eq!(capped.advance(millis(1000)), 3);
+    assert!(capped.alpha() <= 1.0);
+  }
+}
@@ -0,0 +1,562 @@
+//! Named values that can be tuned while the application is running, such as
+//! physics constants & camera parameters. Components register tweakables
+//! with a default value and read them back every frame. Tweakables can be
+//! persisted to a RON file that is reloaded whenever it changes, so values
+//! can be tuned by editing the file without recompiling.
+
+use std::{
+  collections::BTreeMap,
+  fmt::Write,
+  iter::Peekable,
+  path::{
+    Path,
+    PathBuf,
+  },
+  str::Chars,
+  time::{
+    Duration,
+    Instant,
+    SystemTime,
+  },
+};
+
+use crate::persistence::Saveable;
+
+/// How often the tweakables file is checked for changes by default.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// -------------------------------- TWEAK VALUE --------------------------------
+
+/// The value of a tweakable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TweakValue {
+  Float(f32),
+  Integer(i32),
+  Boolean(bool),
+  /// An RGB color with channels between 0 and 1.
+  Color([f32; 3]),
+}
+
+/// A type that can be stored as a tweakable.
+pub trait Tweak: Copy {
+  fn into_value(self) -> TweakValue;
+
+  /// Converts the value back, or None if it's a different type of value.
+  fn from_value(value: TweakValue) -> Option<Self>;
+}
+
+impl Tweak for f32 {
+  fn into_value(self) -> TweakValue {
+    return TweakValue::Float(self);
+  }
+
+  /// Integers are accepted as floats, since files may omit the decimal point.
+  fn from_value(value: TweakValue) -> Option<Self> {
+    return match value {
+      TweakValue::Float(value) => Some(value),
+      TweakValue::Integer(value) => Some(value as f32),
+      _ => None,
+    };
+  }
+}
+
+impl Tweak for i32 {
+  fn into_value(self) -> TweakValue {
+    return TweakValue::Integer(self);
+  }
+
+  fn from_value(value: TweakValue) -> Option<Self> {
+    return match value {
+      TweakValue::Integer(value) => Some(value),
+      _ => None,
+    };
+  }
+}
+
+impl Tweak for bool {
+  fn into_value(self) -> TweakValue {
+    return TweakValue::Boolean(self);
+  }
+
+  fn from_value(value: TweakValue) -> Option<Self> {
+    return match value {
+      TweakValue::Boolean(value) => Some(value),
+      _ => None,
+    };
+  }
+}
+
+impl Tweak for [f32; 3] {
+  fn into_value(self) -> TweakValue {
+    return TweakValue::Color(self);
+  }
+
+  fn from_value(value: TweakValue) -> Option<Self> {
+    return match value {
+      TweakValue::Color(value) => Some(value),
+      _ => None,
+    };
+  }
+}
+
+// -------------------------------- TWEAKABLES ---------------------------------
+
+/// A set of named tweakables, optionally backed by a RON file.
+pub struct Tweakables {
+  values: BTreeMap<String, TweakValue>,
+  file: Option<PathBuf>,
+  /// When the file was last modified as of the last load or save.
+  modified: Option<SystemTime>,
+  poll_interval: Duration,
+  last_poll: Option<Instant>,
+  /// Whether values have changed since the file was last loaded or saved.
+  changed: bool,
+  version: u64,
+}
+
+impl Tweakables {
+  /// Creates an empty set of tweakables that isn't backed by a file.
+  pub fn new() -> Self {
+    return Self {
+      values: BTreeMap::new(),
+      file: None,
+      modified: None,
+      poll_interval: DEFAULT_POLL_INTERVAL,
+      last_poll: None,
+      changed: false,
+      version: 0,
+    };
+  }
+
+  /// How often `poll` checks the file for changes. Defaults to 500ms.
+  pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+    self.poll_interval = poll_interval;
+    return self;
+  }
+
+  /// Backs the tweakables with the RON file at the path, loading its values
+  /// if it exists. The file is created once the tweakables are saved.
+  pub fn watch_file(&mut self, path: impl Into<PathBuf>) -> Result<(), String> {
+    self.file = Some(path.into());
+    self.modified = None;
+    return self.reload().map(|_| ());
+  }
+
+  /// The RON file backing the tweakables.
+  pub fn file(&self) -> Option<&Path> {
+    return self.file.as_deref();
+  }
+
+  /// Registers a tweakable with a default value and returns its current
+  /// value, which is the value from the file if it has one of the same type.
+  pub fn register<T: Tweak>(&mut self, name: &str, default: T) -> T {
+    if let Some(value) = self.values.get(name).copied().and_then(T::from_value)
+    {
+      // Store the registered type, such as a float written as an integer.
+      self.values.insert(name.to_string(), value.into_value());
+      return value;
+    }
+    if let Some(existing) = self.values.get(name) {
+      logging::warn!(
+        "The tweakable {} is {:?}, but was registered as {:?}.",
+        name,
+        existing,
+        default.into_value()
+      );
+    }
+    self.values.insert(name.to_string(), default.into_value());
+    self.changed = true;
+    return default;
+  }
+
+  /// The value of the tweakable, or None if it hasn't been registered or is
+  /// a different type of value.
+  pub fn get<T: Tweak>(&self, name: &str) -> Option<T> {
+    return self.values.get(name).copied().and_then(T::from_value);
+  }
+
+  /// Sets the value of the tweakable, registering it if it doesn't exist.
+  pub fn set<T: Tweak>(&mut self, name: &str, value: T) {
+    self.values.insert(name.to_string(), value.into_value());
+    self.changed = true;
+  }
+
+  /// Every tweakable & its value, sorted by name, such as for editing them
+  /// in a debug panel.
+  pub fn values(&self) -> impl Iterator<Item = (&str, TweakValue)> {
+    return self
+      .values
+      .iter()
+      .map(|(name, value)| (name.as_str(), *value));
+  }
+
+  /// Incremented every time values are reloaded from the file, so that
+  /// components can tell when to re-read the tweakables they cache.
+  pub fn version(&self) -> u64 {
+    return self.version;
+  }
+
+  /// Whether values have changed since the file was last loaded or saved.
+  pub fn has_unsaved_changes(&self) -> bool {
+    return self.changed;
+  }
+
+  /// Reloads the file if the poll interval has passed and the file has been
+  /// modified since it was last loaded or saved. Returns whether the values
+  /// were reloaded. The application runtime polls its tweakables every
+  /// frame.
+  pub fn poll(&mut self) -> Result<bool, String> {
+    let now = Instant::now();
+    if self
+      .last_poll
+      .is_some_and(|last_poll| now - last_poll < self.poll_interval)
+    {
+      return Ok(false);
+    }
+    self.last_poll = Some(now);
+    return self.reload();
+  }
+
+  /// Reloads the file if it has been modified since it was last loaded or
+  /// saved, keeping the values of tweakables that aren't in the file.
+  fn reload(&mut self) -> Result<bool, String> {
+    let path = match self.file.as_ref() {
+      Some(path) => path,
+      None => return Ok(false),
+    };
+    let modified = std::fs::metadata(path)
+      .and_then(|metadata| metadata.modified())
+      .ok();
+    if modified.is_none() || modified == self.modified {
+      return Ok(false);
+    }
+    self.modified = modified;
+
+    let text = std::fs::read_to_string(path).map_err(|error| {
+      format!("Failed to read {}: {}", path.display(), error)
+    })?;
+    let values =
+      parse(&text).map_err(|error| format!("{}: {}", path.display(), error))?;
+    let source = path.display().to_string();
+    self.merge(values, &source);
+    self.changed = false;
+    self.version += 1;
+    return Ok(true);
+  }
+
+  /// Stores the values, keeping the type of tweakables that already exist.
+  fn merge(&mut self, values: Vec<(String, TweakValue)>, source: &str) {
+    for (name, value) in values {
+      let existing = self.values.get(&name).copied();
+      let value = match (existing, value) {
+        (Some(TweakValue::Float(_)), TweakValue::Integer(value)) => {
+          TweakValue::Float(value as f32)
+        }
+        (Some(existing), value)
+          if std::mem::discriminant(&existing)
+            != std::mem::discriminant(&value) =>
+        {
+          logging::warn!(
+            "Ignoring {} from {}, since it's {:?} instead of {:?}.",
+            name,
+            source,
+            value,
+            existing
+          );
+          continue;
+        }
+        (_, value) => value,
+      };
+      self.values.insert(name, value);
+    }
+  }
+
+  /// Writes every tweakable to the file, if the tweakables are backed by one.
+  pub fn save(&mut self) -> Result<(), String> {
+    let path = match self.file.as_ref() {
+      Some(path) => path,
+      None => return Ok(()),
+    };
+    std::fs::write(path, self.to_ron()).map_err(|error| {
+      format!("Failed to write {}: {}", path.display(), error)
+    })?;
+    self.modified = std::fs::metadata(path)
+      .and_then(|metadata| metadata.modified())
+      .ok();
+    self.changed = false;
+    return Ok(());
+  }
+
+  /// The tweakables as a RON map from their names to their values.
+  pub fn to_ron(&self) -> String {
+    let mut ron = String::from("{\n");
+    for (name, value) in self.values.iter() {
+      let name = name.replace('\\', "\\\\").replace('"', "\\\"");
+      let value = match value {
+        TweakValue::Float(value) => format!("{:?}", value),
+        TweakValue::Integer(value) => value.to_string(),
+        TweakValue::Boolean(value) => value.to_string(),
+        TweakValue::Color([r, g, b]) => format!("({:?}, {:?}, {:?})", r, g, b),
+      };
+      writeln!(ron, "  \"{}\": {},", name, value)
+        .expect("Writing to a string can't fail.");
+    }
+    ron.push_str("}\n");
+    return ron;
+  }
+}
+
+impl Default for Tweakables {
+  fn default() -> Self {
+    return Self::new();
+  }
+}
+
+/// Tweakables are saved as a RON map, for applications that remember their
+/// tweakables in a save bundle instead of a file of their own.
+impl Saveable for Tweakables {
+  fn save_state(&self) -> Vec<u8> {
+    return self.to_ron().into_bytes();
+  }
+
+  fn restore_state(&mut self, bytes: &[u8]) -> Result<(), String> {
+    let text = std::str::from_utf8(bytes)
+      .map_err(|_| "The saved tweakables aren't UTF-8.".to_string())?;
+    self.merge(parse(text)?, "the save bundle");
+    self.version += 1;
+    return Ok(());
+  }
+}
+
+// ------------------------------------ RON ------------------------------------
+
+/// Parses a RON map from names to tweakable values. Numbers with a decimal
+/// point or exponent are floats, and tuples of three numbers are colors.
+fn parse(text: &str) -> Result<Vec<(String, TweakValue)>, String> {
+  let mut characters = text.chars().peekable();
+  let mut values = vec![];
+  skip_whitespace(&mut characters);
+  expect(&mut characters, '{')?;
+  loop {
+    skip_whitespace(&mut characters);
+    if characters.next_if_eq(&'}').is_some() {
+      break;
+    }
+    let name = parse_string(&mut characters)?;
+    skip_whitespace(&mut characters);
+    expect(&mut characters, ':')?;
+    skip_whitespace(&mut characters);
+    values.push((name, parse_value(&mut characters)?));
+    skip_whitespace(&mut characters);
+    match characters.next() {
+      Some(',') => continue,
+      Some('}') => break,
+      _ => return Err("expected , or } after a value".to_string()),
+    }
+  }
+
+  skip_whitespace(&mut characters);
+  return match characters.next() {
+    Some(character) => Err(format!("unexpected {:?} after the map", character)),
+    None => Ok(values),
+  };
+}
+
+/// Skips whitespace & line comments.
+fn skip_whitespace(characters: &mut Peekable<Chars>) {
+  loop {
+    while characters
+      .next_if(|character| character.is_whitespace())
+      .is_some()
+    {}
+    if characters.peek() != Some(&'/') {
+      return;
+    }
+    while characters.next_if(|character| *character != '\n').is_some() {}
+  }
+}
+
+fn expect(
+  characters: &mut Peekable<Chars>,
+  expected: char,
+) -> Result<(), String> {
+  return match characters.next() {
+    Some(character) if character == expected => Ok(()),
+    Some(character) => {
+      Err(format!("expected {:?}, found {:?}", expected, character))
+    }
+    None => Err(format!(
+      "expected {:?} before the end of the file",
+      expected
+    )),
+  };
+}
+
+fn parse_string(characters: &mut Peekable<Chars>) -> Result<String, String> {
+  expect(characters, '"')?;
+  let mut string = String::new();
+  loop {
+    match characters.next() {
+      Some('"') => return Ok(string),
+      Some('\\') => match characters.next() {
+        Some(character) => string.push(character),
+        None => return Err("unterminated string".to_string()),
+      },
+      Some(character) => string.push(character),
+      None => return Err("unterminated string".to_string()),
+    }
+  }
+}
+
+fn parse_number(
+  characters: &mut Peekable<Chars>,
+) -> Result<TweakValue, String> {
+  let mut number = String::new();
+  while let Some(character) = characters.next_if(|character| {
+    return character.is_ascii_digit() || "+-.eE".contains(*character);
+  }) {
+    number.push(character);
+  }
+
+  let invalid = || format!("{:?} isn't a number", number);
+  if number.contains(['.', 'e', 'E']) {
+    return number.parse().map(TweakValue::Float).map_err(|_| invalid());
+  }
+  return number
+    .parse()
+    .map(TweakValue::Integer)
+    .map_err(|_| invalid());
+}
+
+fn parse_value(characters: &mut Peekable<Chars>) -> Result<TweakValue, String> {
+  let word: String = {
+    let mut word = String::new();
+    while let Some(character) =
+      characters.next_if(|character| character.is_ascii_alphabetic())
+    {
+      word.push(character);
+    }
+    word
+  };
+  match word.as_str() {
+    "true" => return Ok(TweakValue::Boolean(true)),
+    "false" => return Ok(TweakValue::Boolean(false)),
+    "" => {}
+    word => return Err(format!("{:?} isn't a value", word)),
+  }
+
+  if characters.next_if_eq(&'(').is_none() {
+    return parse_number(characters);
+  }
+  let mut channels = vec![];
+  loop {
+    skip_whitespace(characters);
+    if characters.next_if_eq(&')').is_some() {
+      break;
+    }
+    match parse_number(characters)? {
+      TweakValue::Float(channel) => channels.push(channel),
+      TweakValue::Integer(channel) => channels.push(channel as f32),
+      _ => unreachable!("Numbers are either floats or integers."),
+    }
+    skip_whitespace(characters);
+    match characters.next() {
+      Some(',') => continue,
+      Some(')') => break,
+      _ => return Err("expected , or ) within a color".to_string()),
+    }
+  }
+  return match channels.as_slice() {
+    [r, g, b] => Ok(TweakValue::Color([*r, *g, *b])),
+    _ => Err(format!("colors have 3 channels, not {}", channels.len())),
+  };
+}
+
+// ---------------------------------- TESTS ------------------------------------
+
+#[cfg(test)]
+mod tests {
+  use std::time::Duration;
+
+  use super::{
+    parse,
+    TweakValue,
+    Tweakables,
+  };
+
+  #[test]
+  fn registered_values_keep_their_types() {
+    let mut tweakables = Tweakables::new();
+    assert_eq!(tweakables.register("physics.gravity", -9.81_f32), -9.81);
+    assert_eq!(tweakables.register("physics.gravity", 0.0_f32), -9.81);
+    assert!(!tweakables.register("debug.wireframe", false));
+
+    // Registering with a different type replaces the value.
+    assert_eq!(tweakables.register("physics.gravity", 3), 3);
+    assert_eq!(tweakables.get::<bool>("physics.gravity"), None);
+
+    tweakables.set("camera.tint", [1.0, 0.5, 0.0]);
+    assert_eq!(tweakables.get("camera.tint"), Some([1.0, 0.5, 0.0]));
+    assert!(tweakables.has_unsaved_changes());
+  }
+
+  #[test]
+  fn ron_maps_round_trip() {
+    let mut tweakables = Tweakables::new();
+    tweakables.register("camera.fov", 60.0_f32);
+    tweakables.register("player.lives", 3);
+    tweakables.register("debug.\"quoted\"", true);
+    tweakables.register("ui.tint", [0.25, 1.0, 0.5]);
+
+    let parsed = parse(&tweakables.to_ron()).unwrap();
+    assert_eq!(
+      parsed,
+      tweakables
+        .values()
+        .map(|(name, value)| {
+          return (name.to_string(), value);
+        })
+        .collect::<Vec<_>>()
+    );
+
+    let parsed =
+      parse("// Tuned by hand.\n{ \"speed\": 2, \"tint\": (1, 0, 0.5), }")
+        .unwrap();
+    assert_eq!(parsed[0].1, TweakValue::Integer(2));
+    assert_eq!(parsed[1].1, TweakValue::Color([1.0, 0.0, 0.5]));
+    assert!(parse("{ \"speed\": fast }").is_err());
+    assert!(parse("{ \"tint\": (1, 0) }").is_err());
+  }
+
+  #[test]
+  fn files_are_reloaded_when_they_change() {
+    let path = std::env::temp_dir()
+      .join(format!("lambda-tweakables-{}.ron", std::process::id()));
+    std::fs::remove_file(&path).ok();
+
+    let mut tweakables = Tweakables::new().with_poll_interval(Duration::ZERO);
+    tweakables.watch_file(&path).unwrap();
+    assert_eq!(tweakables.register("physics.gravity", -9.81_f32), -9.81);
+    tweakables.register("debug.wireframe", false);
+    tweakables.save().unwrap();
+    assert!(!tweakables.poll().unwrap());
+
+    // Files written within the timestamp resolution of the last save would
+    // look unchanged, so the recorded modification time is cleared.
+    std::fs::write(
+      &path,
+      "{ \"physics.gravity\": -1, \"debug.wireframe\": 2 }",
+    )
+    .unwrap();
+    tweakables.modified = None;
+    assert!(tweakables.poll().unwrap());
+    assert_eq!(tweakables.get("physics.gravity"), Some(-1.0_f32));
+    assert_eq!(tweakables.get("debug.wireframe"), Some(false));
+    assert_eq!(tweakables.version(), 1);
+
+    let mut reloaded = Tweakables::new();
+    reloaded.watch_file(&path).unwrap();
+    assert_eq!(reloaded.register("physics.gravity", 0.0_f32), -1.0);
+    std::fs::remove_file(&path).ok();
+  }
+}
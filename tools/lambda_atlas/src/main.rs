@@ -0,0 +1,167 @@
+//! Packs a directory of PNG images into a texture atlas & a JSON manifest of
+//! where every image was placed, which can be loaded with `AtlasLoader`.
+
+use std::{
+  env,
+  path::Path,
+};
+
+use args::{
+  Argument,
+  ArgumentParser,
+  ArgumentType,
+  ArgumentValue,
+  ParsedArgument,
+  ValueHint,
+};
+use lambda::render::atlas::{
+  AtlasBuilder,
+  AtlasLoader,
+};
+
+struct Args {
+  input: String,
+  output: String,
+  manifest: Option<String>,
+  width: i64,
+  padding: i64,
+  trim: bool,
+}
+
+impl From<Vec<ParsedArgument>> for Args {
+  fn from(parsed: Vec<ParsedArgument>) -> Self {
+    let mut args = Args {
+      input: String::new(),
+      output: String::new(),
+      manifest: None,
+      width: 1024,
+      padding: 1,
+      trim: true,
+    };
+
+    for arg in parsed {
+      match (arg.name().as_str(), arg.value()) {
+        ("--input", ArgumentValue::String(path)) => args.input = path,
+        ("--output", ArgumentValue::String(path)) => args.output = path,
+        ("--manifest", ArgumentValue::String(path)) => {
+          args.manifest = Some(path)
+        }
+        ("--width", ArgumentValue::Integer(width)) => args.width = width,
+        ("--padding", ArgumentValue::Integer(padding)) => {
+          args.padding = padding
+        }
+        ("--trim", ArgumentValue::Boolean(trim)) => args.trim = trim,
+        (_, _) => {}
+      }
+    }
+
+    return args;
+  }
+}
+
+fn parse_arguments() -> Args {
+  let parser = ArgumentParser::new("lambda-atlas");
+
+  let input = Argument::new("--input")
+    .is_required(true)
+    .with_type(ArgumentType::String)
+    .with_value_hint(ValueHint::DirPath)
+    .with_description("The directory of PNG images to pack.");
+
+  let output = Argument::new("--output")
+    .is_required(true)
+    .with_type(ArgumentType::String)
+    .with_value_hint(ValueHint::FilePath)
+    .with_description("The path to write the atlas PNG to.");
+
+  let manifest = Argument::new("--manifest")
+    .with_type(ArgumentType::String)
+    .with_value_hint(ValueHint::FilePath)
+    .with_description(
+      "The path to write the JSON manifest to. Defaults to the output path \
+       with a json extension.",
+    );
+
+  let width = Argument::new("--width")
+    .with_type(ArgumentType::Integer)
+    .with_default_value(ArgumentValue::Integer(1024))
+    .with_description("The width of the atlas in pixels.");
+
+  let padding = Argument::new("--padding")
+    .with_type(ArgumentType::Integer)
+    .with_default_value(ArgumentValue::Integer(1))
+    .with_description("The empty pixels placed between images.");
+
+  let trim = Argument::new("--trim")
+    .with_type(ArgumentType::Boolean)
+    .with_description(
+      "Whether to trim the transparent borders of images. Defaults to true.",
+    );
+
+  let args = parser
+    .with_argument(input)
+    .with_argument(output)
+    .with_argument(manifest)
+    .with_argument(width)
+    .with_argument(padding)
+    .with_argument(trim)
+    .compile(&env::args().collect::<Vec<_>>());
+
+  return args.into();
+}
+
+fn main() -> Result<(), String> {
+  let args = parse_arguments();
+  let output = Path::new(&args.output);
+  let manifest_path = match args.manifest.as_ref() {
+    Some(path) => Path::new(path).to_path_buf(),
+    None => output.with_extension("json"),
+  };
+
+  let atlas = AtlasBuilder::new()
+    .with_width(args.width.clamp(1, u32::MAX as i64) as u32)
+    .with_padding(args.padding.clamp(0, u32::MAX as i64) as u32)
+    .with_trimming(args.trim)
+    .with_directory(Path::new(&args.input))?
+    .build()?;
+
+  // The manifest refers to the image relative to itself when they share a
+  // directory, which is the common case.
+  let image_path = match output.parent() == manifest_path.parent() {
+    true => output.file_name().map_or(args.output.clone(), |name| {
+      name.to_string_lossy().to_string()
+    }),
+    false => output.display().to_string(),
+  };
+
+  let write = |path: &Path, bytes: &[u8]| {
+    return std::fs::write(path, bytes).map_err(|error| {
+      format!("Failed to write {}: {}", path.display(), error)
+    });
+  };
+  write(output, &atlas.encode_image()?)?;
+  write(&manifest_path, atlas.manifest(&image_path).as_bytes())?;
+
+  // Reload the atlas to verify that the manifest can be loaded.
+  let loaded = AtlasLoader::new().load_from_file(&manifest_path)?;
+  for region in loaded.regions() {
+    println!(
+      "{} at ({}, {}) {}x{}",
+      region.name,
+      region.position.0,
+      region.position.1,
+      region.size.0,
+      region.size.1
+    );
+  }
+  let (width, height) = loaded.dimensions();
+  println!(
+    "Packed {} images from {} into a {}x{} atlas at {}.",
+    loaded.regions().len(),
+    args.input,
+    width,
+    height,
+    args.output
+  );
+  return Ok(());
+}
@@ -0,0 +1,103 @@
+//! Packs a directory of assets into a single compressed file that can be
+//! mounted into the virtual filesystem of an asset server.
+
+use std::{
+  env,
+  path::Path,
+};
+
+use args::{
+  Argument,
+  ArgumentParser,
+  ArgumentType,
+  ArgumentValue,
+  ParsedArgument,
+  ValueHint,
+};
+use lambda::assets::pack::{
+  Pack,
+  PackBuilder,
+};
+
+struct Args {
+  input: String,
+  output: String,
+  level: i64,
+}
+
+impl From<Vec<ParsedArgument>> for Args {
+  fn from(parsed: Vec<ParsedArgument>) -> Self {
+    let mut args = Args {
+      input: String::new(),
+      output: String::new(),
+      level: 6,
+    };
+
+    for arg in parsed {
+      match (arg.name().as_str(), arg.value()) {
+        ("--input", ArgumentValue::String(path)) => args.input = path,
+        ("--output", ArgumentValue::String(path)) => args.output = path,
+        ("--level", ArgumentValue::Integer(level)) => args.level = level,
+        (_, _) => {}
+      }
+    }
+
+    return args;
+  }
+}
+
+fn parse_arguments() -> Args {
+  let parser = ArgumentParser::new("lambda-pack");
+
+  let input = Argument::new("--input")
+    .is_required(true)
+    .with_type(ArgumentType::String)
+    .with_value_hint(ValueHint::DirPath)
+    .with_description("The directory of assets to pack.");
+
+  let output = Argument::new("--output")
+    .is_required(true)
+    .with_type(ArgumentType::String)
+    .with_value_hint(ValueHint::FilePath)
+    .with_description("The path to write the pack to.");
+
+  let level = Argument::new("--level")
+    .with_type(ArgumentType::Integer)
+    .with_default_value(ArgumentValue::Integer(6))
+    .with_description("The compression level between 0 and 10.");
+
+  let args = parser
+    .with_argument(input)
+    .with_argument(output)
+    .with_argument(level)
+    .compile(&env::args().collect::<Vec<_>>());
+
+  return args.into();
+}
+
+fn main() -> Result<(), String> {
+  let args = parse_arguments();
+  let output = Path::new(&args.output);
+
+  let builder = PackBuilder::new()
+    .with_compression_level(args.level.clamp(0, 10) as u8)
+    .with_directory(Path::new(&args.input))?;
+  builder.write(output)?;
+
+  // Reopen the pack to verify that it can be mounted.
+  let pack = Pack::open(output)?;
+  let size = std::fs::metadata(output)
+    .map_err(|error| format!("Failed to read {}: {}", output.display(), error))?
+    .len();
+  for path in pack.paths() {
+    println!("{}", path);
+  }
+  println!(
+    "Packed {} files from {} into {} ({} bytes).",
+    pack.paths().len(),
+    args.input,
+    args.output,
+    size
+  );
+  return Ok(());
+}
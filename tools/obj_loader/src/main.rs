@@ -1,4 +1,10 @@
-use std::env;
+use std::{
+  env,
+  f32::consts::FRAC_PI_2,
+  ops::Range,
+  path::Path,
+  sync::Arc,
+};
 
 use args::{
   Argument,
@@ -6,21 +12,34 @@ use args::{
   ArgumentType,
   ArgumentValue,
   ParsedArgument,
+  ValueHint,
 };
 use lambda::{
+  assets::{
+    vfs::Vfs,
+    AssetServer,
+    AssetServerBuilder,
+    Handle,
+    LoadState,
+  },
   component::Component,
   events::{
+    Button,
     ComponentEvent,
     Events,
+    Key,
+    Mouse,
     WindowEvent,
   },
+  input,
   logging,
-  math::matrix::{
-    self,
-    Matrix,
+  math::{
+    matrix::Matrix,
+    vector::Vector,
   },
   render::{
     buffer::BufferBuilder,
+    color::Color,
     command::RenderCommand,
     mesh::{
       Mesh,
@@ -43,6 +62,7 @@ use lambda::{
       VertexElement,
     },
     viewport,
+    Features,
     ResourceId,
   },
   runtime::start_runtime,
@@ -63,6 +83,7 @@ layout (location = 2) in vec3 vertex_color;
 
 layout (location = 0) out vec3 frag_color;
 layout (location = 1) out vec3 frag_normal;
+layout (location = 2) flat out vec4 frag_shading;
 
 layout ( push_constant ) uniform PushConstant {
   vec4 data;
@@ -73,6 +94,7 @@ void main() {
   gl_Position = push_constants.render_matrix * vec4(vertex_position, 1.0);
   frag_color = vertex_color;
   frag_normal = vertex_normal;
+  frag_shading = push_constants.data;
 }
 
 "#;
@@ -82,12 +104,24 @@ const FRAGMENT_SHADER_SOURCE: &str = r#"
 
 layout (location = 0) in vec3 frag_color;
 layout (location = 1) in vec3 frag_normal;
+layout (location = 2) flat in vec4 frag_shading;
 
 layout (location = 0) out vec4 fragment_color;
 
+#define SHADING_UNLIT 1.0
+#define SHADING_NORMALS 2.0
+
+// The direction towards the light is in xyz & the shading mode is in w.
 void main() {
-  float diffuse = dot(frag_normal, vec3(0.0, 0.0, 1.0));
-  fragment_color = vec4(frag_color, 1.0) * vec4(diffuse);
+  vec3 normal = normalize(frag_normal);
+  if (frag_shading.w == SHADING_NORMALS) {
+    fragment_color = vec4(normal * 0.5 + 0.5, 1.0);
+  } else if (frag_shading.w == SHADING_UNLIT) {
+    fragment_color = vec4(frag_color, 1.0);
+  } else {
+    float diffuse = max(dot(normal, frag_shading.xyz), 0.0);
+    fragment_color = vec4(frag_color * diffuse, 1.0);
+  }
 }
 
 "#;
@@ -112,37 +146,136 @@ pub fn push_constants_to_bytes(push_constants: &PushConstant) -> &[u32] {
   return bytes;
 }
 
-fn make_transform(
-  translate: [f32; 3],
-  angle: f32,
-  scale: f32,
-) -> [[f32; 4]; 4] {
-  let c = angle.cos() * scale;
-  let s = angle.sin() * scale;
-
-  let [x, y, z] = translate;
-
-  return [
-    [c, 0.0, s, 0.0],
-    [0.0, scale, 0.0, 0.0],
-    [-s, 0.0, c, 0.0],
-    [x, y, z, 1.0],
-  ];
+// ------------------------------- ORBIT CAMERA --------------------------------
+
+/// The vertical field of view of the camera in radians.
+const FIELD_OF_VIEW: f32 = 0.8;
+
+/// How far the camera orbits for every pixel that the cursor is dragged.
+const ORBIT_RADIANS_PER_PIXEL: f32 = 0.01;
+
+/// How much closer the camera gets for every line that the wheel scrolls.
+const ZOOM_PER_LINE: f32 = 0.9;
+
+/// Stops just short of looking straight up or down, where the camera's up
+/// direction would be undefined.
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+/// A camera that orbits around & looks at a target.
+#[derive(Debug, Clone, Copy)]
+struct OrbitCamera {
+  target: [f32; 3],
+  yaw: f32,
+  pitch: f32,
+  distance: f32,
+}
+
+impl OrbitCamera {
+  /// Creates a camera that looks at the origin from the distance along +z.
+  fn new(distance: f32) -> Self {
+    return Self {
+      target: [0.0, 0.0, 0.0],
+      yaw: 0.0,
+      pitch: 0.0,
+      distance,
+    };
+  }
+
+  fn eye(&self) -> [f32; 3] {
+    let offset = [
+      self.pitch.cos() * self.yaw.sin(),
+      self.pitch.sin(),
+      self.pitch.cos() * self.yaw.cos(),
+    ];
+    return self.target.add(&offset.scale(self.distance));
+  }
+
+  /// The forward, right, & up directions of the camera.
+  fn axes(&self) -> ([f32; 3], [f32; 3], [f32; 3]) {
+    let forward = self.target.subtract(&self.eye()).normalize();
+    let right = forward.cross(&[0.0, 1.0, 0.0]).normalize();
+    let up = right.cross(&forward);
+    return (forward, right, up);
+  }
+
+  /// Orbits around the target by how far the cursor was dragged in pixels.
+  fn orbit(&mut self, dx: f32, dy: f32) {
+    self.yaw -= dx * ORBIT_RADIANS_PER_PIXEL;
+    self.pitch =
+      (self.pitch + dy * ORBIT_RADIANS_PER_PIXEL).clamp(-MAX_PITCH, MAX_PITCH);
+  }
+
+  /// Moves the target by how far the cursor was dragged in pixels, so that
+  /// the model follows the cursor within a window of the height.
+  fn pan(&mut self, dx: f32, dy: f32, height: u32) {
+    let units_per_pixel =
+      2.0 * self.distance * (FIELD_OF_VIEW / 2.0).tan() / height.max(1) as f32;
+    let (_, right, up) = self.axes();
+    self.target = self
+      .target
+      .subtract(&right.scale(dx * units_per_pixel))
+      .add(&up.scale(dy * units_per_pixel));
+  }
+
+  /// Moves towards the target by the lines that the wheel was scrolled.
+  fn zoom(&mut self, lines: f32) {
+    self.distance = (self.distance * ZOOM_PER_LINE.powf(lines)).max(0.01);
+  }
+
+  /// The matrix that transforms models into clip space, in the column major
+  /// order that shaders expect.
+  fn view_projection(&self, aspect_ratio: f32) -> [[f32; 4]; 4] {
+    let eye = self.eye();
+    let (forward, right, up) = self.axes();
+    let view = [
+      [right[0], up[0], -forward[0], 0.0],
+      [right[1], up[1], -forward[1], 0.0],
+      [right[2], up[2], -forward[2], 0.0],
+      [-right.dot(&eye), -up.dot(&eye), forward.dot(&eye), 1.0],
+    ];
+
+    // Depth is mapped from 0 to 1 & y points down in clip space.
+    let near = self.distance * 0.01;
+    let far = self.distance * 100.0;
+    let focal_length = 1.0 / (FIELD_OF_VIEW / 2.0).tan();
+    let projection = [
+      [focal_length / aspect_ratio, 0.0, 0.0, 0.0],
+      [0.0, -focal_length, 0.0, 0.0],
+      [0.0, 0.0, far / (near - far), -1.0],
+      [0.0, 0.0, near * far / (near - far), 0.0],
+    ];
+
+    // Column major matrices are multiplied in the reverse order.
+    return view.multiply(&projection);
+  }
 }
 
 struct Args {
   obj_path: String,
+  pack_path: String,
+  camera_distance: f32,
+  background: String,
 }
 
 impl Into<Args> for Vec<ParsedArgument> {
   fn into(self) -> Args {
     let mut args = Args {
       obj_path: String::new(),
+      pack_path: String::new(),
+      camera_distance: 2.0,
+      background: String::from("#000000"),
     };
 
     for arg in self {
       match (arg.name().as_str(), arg.value()) {
         ("--obj-path", ArgumentValue::String(path)) => args.obj_path = path,
+        ("--pack-path", ArgumentValue::String(path)) => args.pack_path = path,
+        ("--camera-distance", ArgumentValue::Float(distance)) => {
+          args.camera_distance = distance
+        }
+        ("--background", ArgumentValue::String(color)) => {
+          args.background = color
+        }
         (_, _) => {}
       }
     }
@@ -156,51 +289,212 @@ fn parse_arguments() -> Args {
 
   let obj_file = Argument::new("--obj-path")
     .is_required(true)
-    .with_type(ArgumentType::String);
+    .with_type(ArgumentType::String)
+    .with_value_hint(ValueHint::FilePath);
+
+  let pack_file = Argument::new("--pack-path")
+    .with_type(ArgumentType::String)
+    .with_value_hint(ValueHint::FilePath)
+    .with_default_value(ArgumentValue::String(String::new()))
+    .with_description("A pack built by lambda-pack to load --obj-path from.");
+
+  let camera_distance = Argument::new("--camera-distance")
+    .with_type(ArgumentType::Float)
+    .with_default_value(ArgumentValue::Float(2.0))
+    .with_description("How far the camera starts from the model.");
+
+  let background = Argument::new("--background")
+    .with_type(ArgumentType::String)
+    .with_default_value(ArgumentValue::String(String::from("#000000")))
+    .with_description("The sRGB hex color to clear the background to.");
 
   let args = parser
     .with_argument(obj_file)
+    .with_argument(pack_file)
+    .with_argument(camera_distance)
+    .with_argument(background)
     .compile(&env::args().collect::<Vec<_>>());
 
   return args.into();
 }
 
+/// Creates the asset server that the model is loaded from, along with the
+/// path of the model within it. Models are loaded from the pack when one is
+/// given and from the directory containing the model otherwise.
+fn make_asset_server(obj_path: &str, pack_path: &str) -> (AssetServer, String) {
+  if !pack_path.is_empty() {
+    let files = Vfs::new()
+      .with_pack("", pack_path)
+      .expect("Failed to open the pack.");
+    let server = AssetServerBuilder::new().with_files(files).build();
+    return (server, obj_path.to_string());
+  }
+
+  let path = Path::new(obj_path);
+  let directory = path.parent().unwrap_or(Path::new(""));
+  let file_name = path
+    .file_name()
+    .map_or(String::new(), |name| name.to_string_lossy().to_string());
+  let server = AssetServerBuilder::new().with_root(directory).build();
+  return (server, file_name);
+}
+
 struct ObjLoader {
   obj_path: String,
+  asset_path: String,
   vertex_shader: Shader,
   fragment_shader: Shader,
   render_pipeline: Option<ResourceId>,
+  wireframe_pipeline: Option<ResourceId>,
   render_pass: Option<ResourceId>,
-  mesh: Option<Mesh>,
+  assets: AssetServer,
+  mesh: Option<Handle<Mesh>>,
+  loaded_mesh: Option<Arc<Mesh>>,
+  mesh_version: u64,
+  reloading: bool,
+  camera: OrbitCamera,
+  dragging: Option<Button>,
+  background: Color,
+  supports_wireframe: bool,
+  wireframe: bool,
+  lighting: bool,
+  show_normals: bool,
   frame_number: u32,
   width: u32,
   height: u32,
 }
 
-impl Component<ComponentResult, String> for ObjLoader {
-  fn on_event(&mut self, event: Events) -> Result<ComponentResult, String> {
+impl ObjLoader {
+  /// Starts loading the model in the background, which is rendered once it's
+  /// ready. Models without normals are smoothed across edges sharper than 60
+  /// degrees so that they can still be lit.
+  fn load_mesh(&mut self) {
+    self.mesh = Some(self.assets.load_with(&self.asset_path, |path, files| {
+      return MeshBuilder::new()
+        .with_generated_normals(std::f32::consts::FRAC_PI_3)
+        .with_indexing()
+        .build_from_obj_bytes_with_materials(
+          &files.read(path)?,
+          path.parent().unwrap_or(Path::new("")),
+          |path| files.read(path),
+        );
+    }));
+  }
+
+  /// Replaces the model with one that was dropped onto the window, which is
+  /// loaded from the directory containing it.
+  fn load_dropped_model(&mut self, path: &Path) {
+    let is_obj = path
+      .extension()
+      .is_some_and(|extension| extension.eq_ignore_ascii_case("obj"));
+    if !is_obj {
+      logging::warn!("{} isn't an .obj model.", path.display());
+      return;
+    }
+
+    let obj_path = path.to_string_lossy().to_string();
+    let (assets, asset_path) = make_asset_server(&obj_path, "");
+    logging::info!("Loading {}", obj_path);
+    self.obj_path = obj_path;
+    self.asset_path = asset_path;
+    self.assets = assets;
+    self.loaded_mesh = None;
+    self.mesh_version = 0;
+    self.load_mesh();
+  }
+
+  /// Reloads the model from disk. The current model is rendered until the
+  /// reload finishes, and is kept if the reload fails.
+  fn reload_mesh(&mut self) {
+    if let Some(mesh) = self.mesh.as_ref() {
+      logging::info!("Reloading {}", self.obj_path);
+      self.assets.reload(mesh);
+      self.reloading = true;
+    }
+  }
+
+  /// Reloads the model with R, and toggles wireframes with W, lighting with
+  /// L, and the visualization of normals with N.
+  fn handle_key(&mut self, key: input::Key) {
+    match key {
+      input::Key::R => self.reload_mesh(),
+      input::Key::W if !self.supports_wireframe => {
+        logging::warn!("The GPU doesn't support drawing wireframes.");
+      }
+      input::Key::W => self.wireframe = !self.wireframe,
+      input::Key::L => self.lighting = !self.lighting,
+      input::Key::N => self.show_normals = !self.show_normals,
+      _ => {}
+    }
+  }
+
+  /// Orbits the camera while the left button is dragged, pans it while the
+  /// right or middle button is dragged, and zooms it when the wheel scrolls.
+  fn handle_mouse(&mut self, event: Mouse) {
     match event {
-      lambda::events::Events::Window { event, issued_at } => match event {
-        WindowEvent::Resize { width, height } => {
-          self.width = width;
-          self.height = height;
-          logging::info!("Window resized to {}x{}", width, height);
+      Mouse::Pressed { button, .. } if self.dragging.is_none() => {
+        self.dragging = Some(button);
+      }
+      Mouse::Released { button, .. } if self.dragging == Some(button) => {
+        self.dragging = None;
+      }
+      Mouse::Moved { dx, dy, .. } => match self.dragging {
+        Some(Button::Left) => self.camera.orbit(dx as f32, dy as f32),
+        Some(Button::Right) | Some(Button::Middle) => {
+          self.camera.pan(dx as f32, dy as f32, self.height)
         }
         _ => {}
       },
+      Mouse::Scrolled { dy, .. } => self.camera.zoom(dy as f32),
       _ => {}
+    }
+  }
+
+  /// The shading mode that the fragment shader uses.
+  fn shading(&self) -> f32 {
+    return match (self.show_normals, self.lighting) {
+      (true, _) => 2.0,
+      (false, false) => 1.0,
+      (false, true) => 0.0,
     };
-    return Ok(ComponentResult::Success);
   }
 
-  fn on_attach(
+  /// Creates the render pass & pipeline for the mesh once it has loaded.
+  fn attach_mesh(
     &mut self,
+    mesh: Arc<Mesh>,
     render_context: &mut lambda::render::RenderContext,
-  ) -> Result<ComponentResult, String> {
-    let render_pass = RenderPassBuilder::new().build(render_context);
+  ) {
+    // Models that were replaced or reloaded are destroyed once the new model
+    // has loaded.
+    let pipelines =
+      [self.render_pipeline.take(), self.wireframe_pipeline.take()];
+    for pipeline in pipelines.into_iter().flatten() {
+      render_context
+        .destroy_render_pipeline(pipeline)
+        .expect("Failed to destroy the previous model's pipeline.");
+    }
+    if let Some(render_pass) = self.render_pass.take() {
+      render_context
+        .destroy_render_pass(render_pass)
+        .expect("Failed to destroy the previous model's render pass.");
+    }
+
+    let render_pass = RenderPassBuilder::new()
+      .with_clear_color(self.background)
+      .build(render_context);
     let push_constant_size = std::mem::size_of::<PushConstant>() as u32;
 
-    let mesh = MeshBuilder::new().build_from_obj(&self.obj_path);
+    for material in mesh.materials() {
+      if let Some(texture) = material.diffuse_texture() {
+        logging::warn!(
+          "Material {} uses the diffuse texture {}, which isn't rendered yet. \
+           Its diffuse color will be used instead.",
+          material.name(),
+          texture.display()
+        );
+      }
+    }
 
     logging::trace!(
       "[DEBUG] Mesh data from {} Mesh:\n {:#?}",
@@ -208,23 +502,62 @@ impl Component<ComponentResult, String> for ObjLoader {
       mesh
     );
 
-    let pipeline = RenderPipelineBuilder::new()
-      .with_push_constant(PipelineStage::VERTEX, push_constant_size)
-      .with_buffer(
-        BufferBuilder::build_from_mesh(&mesh, render_context)
-          .expect("Failed to create buffer"),
-        mesh.attributes().to_vec(),
-      )
-      .build(
-        render_context,
-        &render_pass,
-        &self.vertex_shader,
-        Some(&self.fragment_shader),
-      );
+    let mut build_pipeline = |wireframe: bool| {
+      let pipeline = RenderPipelineBuilder::new()
+        .with_push_constant(PipelineStage::VERTEX, push_constant_size)
+        .with_wireframe(wireframe)
+        .with_buffer(
+          BufferBuilder::build_from_mesh(&mesh, render_context)
+            .expect("Failed to create buffer"),
+          mesh.attributes().to_vec(),
+        )
+        .build(
+          render_context,
+          &render_pass,
+          &self.vertex_shader,
+          Some(&self.fragment_shader),
+        );
+      return render_context.attach_pipeline(pipeline);
+    };
 
+    self.render_pipeline = Some(build_pipeline(false));
+    if self.supports_wireframe {
+      self.wireframe_pipeline = Some(build_pipeline(true));
+    }
     self.render_pass = Some(render_context.attach_render_pass(render_pass));
-    self.render_pipeline = Some(render_context.attach_pipeline(pipeline));
-    self.mesh = Some(mesh);
+    self.loaded_mesh = Some(mesh);
+  }
+}
+
+impl Component<ComponentResult, String> for ObjLoader {
+  fn on_event(&mut self, event: Events) -> Result<ComponentResult, String> {
+    match event {
+      lambda::events::Events::Window { event, issued_at } => match event {
+        WindowEvent::Resize { width, height } => {
+          self.width = width;
+          self.height = height;
+          logging::info!("Window resized to {}x{}", width, height);
+        }
+        WindowEvent::FileDropped { path } => self.load_dropped_model(&path),
+        _ => {}
+      },
+      Events::Keyboard {
+        event: Key::Pressed { key, .. },
+        ..
+      } => self.handle_key(key),
+      Events::Mouse { event, .. } => self.handle_mouse(event),
+      _ => {}
+    };
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_attach(
+    &mut self,
+    render_context: &mut lambda::render::RenderContext,
+  ) -> Result<ComponentResult, String> {
+    self.supports_wireframe =
+      render_context.supports(Features::NON_FILL_POLYGON_MODE);
+    self.load_mesh();
     return Ok(ComponentResult::Success);
   }
 
@@ -240,6 +573,19 @@ impl Component<ComponentResult, String> for ObjLoader {
     last_frame: &std::time::Duration,
   ) -> Result<ComponentResult, String> {
     self.frame_number += 1;
+    if let Some(mesh) = self.mesh.as_ref() {
+      match self.assets.state(mesh) {
+        LoadState::Failed(error) if self.loaded_mesh.is_none() => {
+          return Err(format!("Failed to load {}: {}", self.obj_path, error));
+        }
+        LoadState::Failed(error) if self.reloading => {
+          logging::warn!("Failed to reload {}: {}", self.obj_path, error);
+          self.reloading = false;
+        }
+        LoadState::Loaded => self.reloading = false,
+        _ => {}
+      }
+    }
     return Ok(ComponentResult::Success);
   }
 
@@ -247,34 +593,39 @@ impl Component<ComponentResult, String> for ObjLoader {
     &mut self,
     render_context: &mut lambda::render::RenderContext,
   ) -> Vec<lambda::render::command::RenderCommand> {
-    let camera = [0.0, 0.0, -2.0];
-    let view: [[f32; 4]; 4] = matrix::translation_matrix(camera);
-
-    // Create a projection matrix.
-    let projection: [[f32; 4]; 4] =
-      matrix::perspective_matrix(0.12, (4 / 3) as f32, 0.1, 200.0);
-
-    // Rotate model.
-    let model: [[f32; 4]; 4] = matrix::rotate_matrix(
-      matrix::identity_matrix(4, 4),
-      [0.0, 1.0, 0.0],
-      0.001 * self.frame_number as f32,
-    );
+    // The model is attached once it has loaded & again whenever it reloads.
+    if let Some(mesh) = self.mesh.as_ref() {
+      let version = self.assets.version(mesh);
+      if version != self.mesh_version {
+        if let Some(mesh) = self.assets.get(mesh) {
+          self.attach_mesh(mesh, render_context);
+          self.mesh_version = version;
+        }
+      }
+    }
+    if self.loaded_mesh.is_none() {
+      return vec![];
+    }
 
-    // Create render matrix.
-    let mesh_matrix = projection.multiply(&view).multiply(&model);
-    let mesh_matrix =
-      make_transform([0.0, 0.0, 0.5], self.frame_number as f32 * 0.01, 0.5);
+    let mesh_matrix = self
+      .camera
+      .view_projection(self.width as f32 / self.height.max(1) as f32);
+
+    // Light the model from the camera so that every side can be seen.
+    let (forward, _, _) = self.camera.axes();
+    let data = [-forward[0], -forward[1], -forward[2], self.shading()];
 
     // Create viewport.
     let viewport =
       viewport::ViewportBuilder::new().build(self.width, self.height);
 
-    let render_pipeline = self
-      .render_pipeline
-      .expect("No render pipeline actively set for rendering.");
+    let render_pipeline = match self.wireframe {
+      true => self.wireframe_pipeline,
+      false => self.render_pipeline,
+    }
+    .expect("No render pipeline actively set for rendering.");
 
-    return vec![
+    let mut commands = vec![
       RenderCommand::SetViewports {
         start_at: 0,
         viewports: vec![viewport.clone()],
@@ -302,15 +653,50 @@ impl Component<ComponentResult, String> for ObjLoader {
         stage: PipelineStage::VERTEX,
         offset: 0,
         bytes: Vec::from(push_constants_to_bytes(&PushConstant {
-          data: [0.0, 0.0, 0.0, 0.0],
+          data,
           render_matrix: mesh_matrix,
         })),
       },
-      RenderCommand::Draw {
-        vertices: 0..self.mesh.as_ref().unwrap().vertices().len() as u32,
-      },
-      RenderCommand::EndRenderPass,
     ];
+
+    // Draw every submesh of the model, or the whole model when it wasn't
+    // split into submeshes.
+    let mesh = self.loaded_mesh.clone().unwrap();
+    let element_count = match mesh.indices() {
+      Some(indices) => indices.len(),
+      None => mesh.vertices().len(),
+    };
+    let ranges = match mesh.submeshes().is_empty() {
+      true => vec![Range {
+        start: 0,
+        end: element_count as u32,
+      }],
+      false => mesh
+        .submeshes()
+        .iter()
+        .map(|submesh| submesh.vertices())
+        .collect(),
+    };
+
+    if mesh.indices().is_some() {
+      commands.push(RenderCommand::BindIndexBuffer {
+        pipeline: render_pipeline,
+        buffer: 0,
+      });
+    }
+
+    commands.extend(ranges.into_iter().map(|range| {
+      return match mesh.indices() {
+        Some(_) => RenderCommand::DrawIndexed {
+          indices: range,
+          base_vertex: 0,
+        },
+        None => RenderCommand::Draw { vertices: range },
+      };
+    }));
+
+    commands.push(RenderCommand::EndRenderPass);
+    return commands;
   }
 }
 
@@ -335,11 +721,24 @@ impl Default for ObjLoader {
 
     return Self {
       obj_path: String::new(),
+      asset_path: String::new(),
       vertex_shader: vs,
       fragment_shader: fs,
       render_pipeline: None,
+      wireframe_pipeline: None,
       render_pass: None,
+      assets: AssetServerBuilder::new().build(),
       mesh: None,
+      loaded_mesh: None,
+      mesh_version: 0,
+      reloading: false,
+      camera: OrbitCamera::new(2.0),
+      dragging: None,
+      background: Color::BLACK,
+      supports_wireframe: false,
+      wireframe: false,
+      lighting: true,
+      show_normals: false,
       width: 800,
       height: 600,
       frame_number: 0,
@@ -349,6 +748,14 @@ impl Default for ObjLoader {
 
 fn main() {
   let args = parse_arguments();
+  let background = match Color::from_hex(&args.background) {
+    Ok(background) => background,
+    Err(error) => {
+      logging::error!("Invalid --background {}: {}", args.background, error);
+      std::process::exit(1);
+    }
+  };
+
   let runtime = ApplicationRuntimeBuilder::new(
     std::format!("obj-loader: {}", &args.obj_path).as_str(),
   )
@@ -359,10 +766,19 @@ fn main() {
       .with_vsync(true);
   })
   .with_renderer_configured_as(|renderer_builder| {
-    return renderer_builder.with_render_timeout(1_000_000_000);
+    return renderer_builder
+      .with_render_timeout(1_000_000_000)
+      .with_optional_features(Features::NON_FILL_POLYGON_MODE);
   })
   .with_component(move |runtime, mut obj_loader: ObjLoader| {
-    obj_loader.obj_path = parse_arguments().obj_path.clone();
+    let args = parse_arguments();
+    let (assets, asset_path) =
+      make_asset_server(&args.obj_path, &args.pack_path);
+    obj_loader.obj_path = args.obj_path.clone();
+    obj_loader.asset_path = asset_path;
+    obj_loader.assets = assets;
+    obj_loader.camera = OrbitCamera::new(args.camera_distance);
+    obj_loader.background = background;
     return (runtime, obj_loader);
   })
   .build();
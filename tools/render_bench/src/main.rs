@@ -0,0 +1,742 @@
+//! Stress tests the renderer by drawing thousands of sprites with one of
+//! several draw call batching strategies. Once a warmup period has passed,
+//! frame times are measured for a number of frames and printed as JSON so
+//! that regressions in the encoder & pipeline paths can be tracked.
+
+use std::{
+  env,
+  time::Duration,
+};
+
+use args::{
+  Argument,
+  ArgumentParser,
+  ArgumentType,
+  ArgumentValue,
+  ParsedArgument,
+  ValueHint,
+};
+use lambda::{
+  component::Component,
+  events::{
+    Events,
+    WindowEvent,
+  },
+  logging,
+  render::{
+    buffer::{
+      BufferBuilder,
+      BufferType,
+      Properties,
+      Usage,
+    },
+    color::Color,
+    command::RenderCommand,
+    mesh::MeshBuilder,
+    pipeline::{
+      PipelineStage,
+      RenderPipelineBuilder,
+    },
+    render2d::Render2D,
+    render_pass::RenderPassBuilder,
+    shader::{
+      ShaderBuilder,
+      ShaderKind,
+      VirtualShader,
+    },
+    vertex::{
+      Vertex,
+      VertexAttribute,
+      VertexElement,
+    },
+    viewport::ViewportBuilder,
+    ColorFormat,
+    PresentModePreference,
+    RenderContext,
+    ResourceId,
+  },
+  runtime::{
+    start_runtime,
+    RuntimeHandle,
+  },
+  runtimes::{
+    application::ComponentResult,
+    pacing::FrameStatistics,
+    ApplicationRuntimeBuilder,
+    StandardArgs,
+  },
+};
+
+// ------------------------------ SHADER SOURCE --------------------------------
+
+const VERTEX_SHADER_SOURCE: &str = r#"
+#version 450
+
+layout (location = 0) in vec3 vertex_position;
+layout (location = 1) in vec3 vertex_normal;
+layout (location = 2) in vec3 vertex_color;
+
+layout (location = 0) out vec3 frag_color;
+
+layout ( push_constant ) uniform PushConstant {
+  vec4 offset;
+} push_constants;
+
+void main() {
+  gl_Position = vec4(vertex_position + push_constants.offset.xyz, 1.0);
+  frag_color = vertex_color;
+}
+
+"#;
+
+const FRAGMENT_SHADER_SOURCE: &str = r#"
+#version 450
+
+layout (location = 0) in vec3 frag_color;
+
+layout (location = 0) out vec4 fragment_color;
+
+void main() {
+  fragment_color = vec4(frag_color, 1.0);
+}
+
+"#;
+
+/// The number of vertices in the two triangles of a sprite.
+const VERTICES_PER_SPRITE: usize = 6;
+
+// -------------------------------- STRATEGIES ---------------------------------
+
+/// How sprites are split into draw calls.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Strategy {
+  /// One draw per sprite of a shared quad, which is moved by push constants.
+  PerDraw,
+  /// Every sprite is written into one vertex buffer once and drawn in
+  /// batches. Sprites don't move.
+  Static,
+  /// Every sprite is rewritten into one vertex buffer every frame and drawn
+  /// in batches.
+  Dynamic,
+  /// Every sprite is rewritten every frame like `Dynamic`, but the writes are
+  /// staged & copied into the vertex buffer by the GPU.
+  Staged,
+  /// Every sprite is drawn as a rectangle through `Render2D`, which batches
+  /// them into a single draw.
+  Render2D,
+}
+
+impl Strategy {
+  const ALL: [Strategy; 5] = [
+    Strategy::PerDraw,
+    Strategy::Static,
+    Strategy::Dynamic,
+    Strategy::Staged,
+    Strategy::Render2D,
+  ];
+
+  fn name(&self) -> &'static str {
+    return match self {
+      Strategy::PerDraw => "per-draw",
+      Strategy::Static => "static",
+      Strategy::Dynamic => "dynamic",
+      Strategy::Staged => "staged",
+      Strategy::Render2D => "render2d",
+    };
+  }
+
+  fn parse(name: &str) -> Result<Self, String> {
+    return Self::ALL
+      .into_iter()
+      .find(|strategy| strategy.name() == name)
+      .ok_or_else(|| {
+        let names = Self::ALL.map(|strategy| strategy.name()).join(", ");
+        return format!(
+          "Unknown strategy {}, which must be one of {}.",
+          name, names
+        );
+      });
+  }
+}
+
+// -------------------------------- ARGUMENTS ----------------------------------
+
+struct Args {
+  sprites: i64,
+  strategy: String,
+  batch_size: i64,
+  warmup_frames: i64,
+  frames: i64,
+  output: String,
+}
+
+impl From<Vec<ParsedArgument>> for Args {
+  fn from(parsed: Vec<ParsedArgument>) -> Self {
+    let mut args = Args {
+      sprites: 10_000,
+      strategy: String::from("static"),
+      batch_size: 0,
+      warmup_frames: 120,
+      frames: 600,
+      output: String::new(),
+    };
+
+    for arg in parsed {
+      match (arg.name().as_str(), arg.value()) {
+        ("--sprites", ArgumentValue::Integer(sprites)) => {
+          args.sprites = sprites
+        }
+        ("--strategy", ArgumentValue::String(name)) => args.strategy = name,
+        ("--batch-size", ArgumentValue::Integer(size)) => {
+          args.batch_size = size
+        }
+        ("--warmup-frames", ArgumentValue::Integer(frames)) => {
+          args.warmup_frames = frames
+        }
+        ("--frames", ArgumentValue::Integer(frames)) => args.frames = frames,
+        ("--output", ArgumentValue::String(path)) => args.output = path,
+        (_, _) => {}
+      }
+    }
+
+    return args;
+  }
+}
+
+/// Parses the arguments of the tool alongside the standard flags of the
+/// runtime, such as `--backend` & `--vsync`.
+fn parse_arguments() -> Result<(Args, StandardArgs), String> {
+  let parser = StandardArgs::register(ArgumentParser::new("render-bench"));
+
+  let sprites = Argument::new("--sprites")
+    .with_type(ArgumentType::Integer)
+    .with_default_value(ArgumentValue::Integer(10_000))
+    .with_description("The number of sprites to draw every frame.");
+
+  let strategy = Argument::new("--strategy")
+    .with_type(ArgumentType::String)
+    .with_default_value(ArgumentValue::String(String::from("static")))
+    .with_description(
+      "How sprites are drawn: per-draw, static, dynamic, staged, or \
+       render2d.",
+    );
+
+  let batch_size = Argument::new("--batch-size")
+    .with_type(ArgumentType::Integer)
+    .with_default_value(ArgumentValue::Integer(0))
+    .with_description(
+      "The most sprites in a draw of the static & dynamic strategies, or 0 \
+       to draw every sprite at once.",
+    );
+
+  let warmup_frames = Argument::new("--warmup-frames")
+    .with_type(ArgumentType::Integer)
+    .with_default_value(ArgumentValue::Integer(120))
+    .with_description("The frames to render before measuring frame times.");
+
+  let frames = Argument::new("--frames")
+    .with_type(ArgumentType::Integer)
+    .with_default_value(ArgumentValue::Integer(600))
+    .with_description("The frames to measure frame times over.");
+
+  let output = Argument::new("--output")
+    .with_type(ArgumentType::String)
+    .with_value_hint(ValueHint::FilePath)
+    .with_default_value(ArgumentValue::String(String::new()))
+    .with_description(
+      "The path to write the results to, instead of standard output.",
+    );
+
+  let parsed = parser
+    .with_argument(sprites)
+    .with_argument(strategy)
+    .with_argument(batch_size)
+    .with_argument(warmup_frames)
+    .with_argument(frames)
+    .with_argument(output)
+    .try_compile(&env::args().collect::<Vec<_>>())?;
+
+  let standard_args = StandardArgs::from_parsed(&parsed)?;
+  return Ok((parsed.into(), standard_args));
+}
+
+// --------------------------------- SPRITES -----------------------------------
+
+/// The center & size of the sprite, which are placed in a grid that fills
+/// the window. Positions are in the unit square with y pointing down.
+fn sprite_layout(index: usize, sprites: usize) -> ([f32; 2], f32) {
+  let columns = (sprites as f32).sqrt().ceil().max(1.0) as usize;
+  let cell = 1.0 / columns as f32;
+  let (row, column) = (index / columns, index % columns);
+  let center = [(column as f32 + 0.5) * cell, (row as f32 + 0.5) * cell];
+  return (center, cell * 0.8);
+}
+
+/// How far the sprite has moved from its place in the grid at the time in
+/// seconds, so that every sprite moves differently.
+fn sprite_offset(index: usize, size: f32, time: f32) -> [f32; 2] {
+  let phase = time * 2.0 + index as f32 * 0.37;
+  return [phase.sin() * size * 0.1, phase.cos() * size * 0.1];
+}
+
+fn sprite_color(index: usize) -> [f32; 3] {
+  return Color::from_hsv(index as f32 * 7.0, 0.7, 0.9, 1.0).to_rgb();
+}
+
+/// The vertices of a quad with the center & size in the unit square,
+/// converted into clip space.
+fn quad_vertices(center: [f32; 2], size: f32, color: [f32; 3]) -> [Vertex; 6] {
+  let half = size / 2.0;
+  let [x, y] = [center[0] * 2.0 - 1.0, center[1] * 2.0 - 1.0];
+  let corner = |dx: f32, dy: f32| Vertex {
+    position: [x + dx * half * 2.0, y + dy * half * 2.0, 0.0],
+    normal: [0.0, 0.0, 1.0],
+    color,
+    tex_coords: [0.0, 0.0],
+    tangent: [0.0, 0.0, 0.0, 0.0],
+  };
+  return [
+    corner(-1.0, -1.0),
+    corner(-1.0, 1.0),
+    corner(1.0, 1.0),
+    corner(-1.0, -1.0),
+    corner(1.0, 1.0),
+    corner(1.0, -1.0),
+  ];
+}
+
+// -------------------------------- COMPONENT ----------------------------------
+
+struct RenderBench {
+  strategy: Strategy,
+  sprites: usize,
+  batch_size: usize,
+  warmup_frames: u64,
+  frames: usize,
+  output: String,
+  handle: RuntimeHandle,
+  rendering_api: String,
+  gpu: String,
+  render2d: Render2D,
+  render_pass: Option<ResourceId>,
+  render_pipeline: Option<ResourceId>,
+  width: u32,
+  height: u32,
+  time: Duration,
+  frames_updated: u64,
+  statistics: FrameStatistics,
+}
+
+impl RenderBench {
+  /// The most sprites in a draw of the strategy.
+  fn batch_size(&self) -> usize {
+    return match self.strategy {
+      Strategy::PerDraw => 1,
+      Strategy::Static | Strategy::Dynamic | Strategy::Staged
+        if self.batch_size > 0 =>
+      {
+        self.batch_size.min(self.sprites)
+      }
+      Strategy::Static
+      | Strategy::Dynamic
+      | Strategy::Staged
+      | Strategy::Render2D => self.sprites,
+    };
+  }
+
+  fn draws_per_frame(&self) -> usize {
+    return self.sprites.div_ceil(self.batch_size().max(1));
+  }
+
+  /// The vertices of every sprite at the time in seconds.
+  fn sprite_vertices(&self, time: f32) -> Vec<Vertex> {
+    return (0..self.sprites)
+      .flat_map(|index| {
+        let (center, size) = sprite_layout(index, self.sprites);
+        let offset = sprite_offset(index, size, time);
+        let center = [center[0] + offset[0], center[1] + offset[1]];
+        return quad_vertices(center, size, sprite_color(index));
+      })
+      .collect();
+  }
+
+  /// Creates the pipeline that draws the vertices, where the vertex buffer
+  /// holds a single quad for per-draw sprites and every sprite otherwise.
+  fn attach_pipeline(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<(), String> {
+    let vertices = match self.strategy {
+      Strategy::PerDraw => {
+        let (_, size) = sprite_layout(0, self.sprites);
+        quad_vertices([0.5, 0.5], size, [1.0, 1.0, 1.0]).to_vec()
+      }
+      _ => self.sprite_vertices(0.0),
+    };
+
+    let mut mesh_builder = MeshBuilder::new();
+    for vertex in vertices {
+      mesh_builder.with_vertex(vertex);
+    }
+    mesh_builder.with_attributes(
+      [0, 12, 24]
+        .iter()
+        .enumerate()
+        .map(|(location, offset)| {
+          return VertexAttribute {
+            location: location as u32,
+            offset: 0,
+            element: VertexElement {
+              format: ColorFormat::Rgb32Sfloat,
+              offset: *offset,
+            },
+          };
+        })
+        .collect(),
+    );
+    let mesh = mesh_builder.build();
+
+    let mut builder = ShaderBuilder::new();
+    let vertex_shader = builder.build(VirtualShader::Source {
+      source: VERTEX_SHADER_SOURCE.to_string(),
+      kind: ShaderKind::Vertex,
+      entry_point: "main".to_string(),
+      name: "render-bench".to_string(),
+    });
+    let fragment_shader = builder.build(VirtualShader::Source {
+      source: FRAGMENT_SHADER_SOURCE.to_string(),
+      kind: ShaderKind::Fragment,
+      entry_point: "main".to_string(),
+      name: "render-bench".to_string(),
+    });
+
+    let render_pass = RenderPassBuilder::new()
+      .with_clear_color(Color::BLACK)
+      .build(render_context);
+    // Staged sprites are copied into the vertex buffer, so it has to be a
+    // transfer destination.
+    let vertex_buffer = match self.strategy {
+      Strategy::Staged => BufferBuilder::new()
+        .with_length(std::mem::size_of_val(mesh.vertices()))
+        .with_buffer_type(BufferType::Vertex)
+        .with_usage(Usage::VERTEX | Usage::TRANSFER_DST)
+        .with_properties(Properties::CPU_VISIBLE)
+        .with_label("render-bench sprites")
+        .build(render_context, mesh.vertices().to_vec()),
+      _ => BufferBuilder::build_from_mesh(&mesh, render_context),
+    }
+    .map_err(|error| error.to_string())?;
+
+    let pipeline = RenderPipelineBuilder::new()
+      .with_push_constant(PipelineStage::VERTEX, 16)
+      .with_buffer(vertex_buffer, mesh.attributes().to_vec())
+      .try_build(
+        render_context,
+        &render_pass,
+        &vertex_shader,
+        Some(&fragment_shader),
+      )?;
+
+    self.render_pass = Some(render_context.attach_render_pass(render_pass));
+    self.render_pipeline = Some(render_context.attach_pipeline(pipeline));
+    return Ok(());
+  }
+
+  /// The results of the measured frames as a single line JSON object, where
+  /// frame times are in milliseconds.
+  fn results_json(&self) -> String {
+    let milliseconds = |duration: Duration| duration.as_secs_f64() * 1000.0;
+    let max_frame_time =
+      self.statistics.frame_times().max().unwrap_or_default();
+    return format!(
+      "{{\"strategy\":\"{}\",\"rendering_api\":\"{}\",\"gpu\":\"{}\",\
+       \"sprites\":{},\"batch_size\":{},\"draws_per_frame\":{},\
+       \"warmup_frames\":{},\"frames\":{},\"average_ms\":{:.4},\
+       \"median_ms\":{:.4},\"p95_ms\":{:.4},\"p99_ms\":{:.4},\
+       \"max_ms\":{:.4},\"fps\":{:.2}}}",
+      self.strategy.name(),
+      logging::handler::escape_json(&self.rendering_api),
+      logging::handler::escape_json(&self.gpu),
+      self.sprites,
+      self.batch_size(),
+      self.draws_per_frame(),
+      self.warmup_frames,
+      self.statistics.total_frames(),
+      milliseconds(self.statistics.average_frame_time()),
+      milliseconds(self.statistics.percentile_frame_time(50.0)),
+      milliseconds(self.statistics.percentile_frame_time(95.0)),
+      milliseconds(self.statistics.percentile_frame_time(99.0)),
+      milliseconds(max_frame_time),
+      self.statistics.frames_per_second(),
+    );
+  }
+
+  /// Writes the results & shuts the runtime down once every frame has been
+  /// measured.
+  fn finish(&self) -> Result<(), String> {
+    let results = self.results_json();
+    logging::info!(
+      "Drew {} sprites with the {} strategy at {:.2} fps on average.",
+      self.sprites,
+      self.strategy.name(),
+      self.statistics.frames_per_second()
+    );
+
+    match self.output.is_empty() {
+      true => println!("{}", results),
+      false => {
+        std::fs::write(&self.output, results + "\n").map_err(|error| {
+          return format!("Failed to write {}: {}", self.output, error);
+        })?
+      }
+    }
+    self.handle.request_shutdown(0);
+    return Ok(());
+  }
+
+  fn render_sprites(&mut self) -> Vec<RenderCommand> {
+    let (render_pass, pipeline) = match (self.render_pass, self.render_pipeline)
+    {
+      (Some(render_pass), Some(pipeline)) => (render_pass, pipeline),
+      _ => return vec![],
+    };
+    let time = self.time.as_secs_f32();
+    let viewport = ViewportBuilder::new().build(self.width, self.height);
+
+    let mut commands = vec![];
+    if matches!(self.strategy, Strategy::Dynamic | Strategy::Staged) {
+      commands.push(RenderCommand::UpdateVertexBuffer {
+        pipeline,
+        buffer: 0,
+        vertices: self.sprite_vertices(time),
+      });
+    }
+    commands.extend([
+      RenderCommand::SetViewports {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetScissors {
+        start_at: 0,
+        viewports: vec![viewport.clone()],
+      },
+      RenderCommand::SetPipeline { pipeline },
+      RenderCommand::BeginRenderPass {
+        render_pass,
+        viewport,
+      },
+      RenderCommand::BindVertexBuffer {
+        pipeline,
+        buffer: 0,
+      },
+    ]);
+
+    let push_offset = |offset: [f32; 2]| RenderCommand::PushConstants {
+      pipeline,
+      stage: PipelineStage::VERTEX,
+      offset: 0,
+      bytes: [offset[0], offset[1], 0.0, 0.0]
+        .iter()
+        .map(|value| value.to_bits())
+        .collect(),
+    };
+
+    match self.strategy {
+      Strategy::PerDraw => {
+        // The shared quad is centered in the window, so every sprite is
+        // offset from the center to its place in the grid.
+        for index in 0..self.sprites {
+          let (center, size) = sprite_layout(index, self.sprites);
+          let offset = sprite_offset(index, size, time);
+          commands.push(push_offset([
+            (center[0] + offset[0] - 0.5) * 2.0,
+            (center[1] + offset[1] - 0.5) * 2.0,
+          ]));
+          commands.push(RenderCommand::Draw {
+            vertices: 0..VERTICES_PER_SPRITE as u32,
+          });
+        }
+      }
+      _ => {
+        commands.push(push_offset([0.0, 0.0]));
+        let batch_size = self.batch_size();
+        for start in (0..self.sprites).step_by(batch_size.max(1)) {
+          let end = (start + batch_size).min(self.sprites);
+          commands.push(RenderCommand::Draw {
+            vertices: (start * VERTICES_PER_SPRITE) as u32
+              ..(end * VERTICES_PER_SPRITE) as u32,
+          });
+        }
+      }
+    }
+
+    commands.push(RenderCommand::EndRenderPass);
+    return commands;
+  }
+
+  fn render_rectangles(&mut self) -> Vec<RenderCommand> {
+    let time = self.time.as_secs_f32();
+    let [width, height] = self.render2d.size();
+    for index in 0..self.sprites {
+      let (center, size) = sprite_layout(index, self.sprites);
+      let offset = sprite_offset(index, size, time);
+      let half = size / 2.0;
+      self.render2d.draw_rect(
+        [
+          (center[0] + offset[0] - half) * width,
+          (center[1] + offset[1] - half) * height,
+        ],
+        [size * width, size * height],
+        sprite_color(index),
+      );
+    }
+    return self.render2d.finish();
+  }
+}
+
+impl Component<ComponentResult, String> for RenderBench {
+  fn on_attach(
+    &mut self,
+    render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    let info = render_context.info();
+    self.rendering_api = info.rendering_api.to_string();
+    self.gpu = info.gpu.name;
+    match self.strategy {
+      Strategy::Render2D => self.render2d.attach(render_context)?,
+      _ => self.attach_pipeline(render_context)?,
+    }
+    logging::info!(
+      "Drawing {} sprites with the {} strategy in {} draws per frame.",
+      self.sprites,
+      self.strategy.name(),
+      self.draws_per_frame()
+    );
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_detach(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Result<ComponentResult, String> {
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_event(&mut self, event: Events) -> Result<ComponentResult, String> {
+    self.render2d.on_event(&event);
+    if let Events::Window {
+      event: WindowEvent::Resize { width, height },
+      ..
+    } = event
+    {
+      self.width = width;
+      self.height = height;
+    }
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_update(
+    &mut self,
+    last_frame: &Duration,
+  ) -> Result<ComponentResult, String> {
+    self.time += *last_frame;
+    self.frames_updated += 1;
+    if self.frames_updated <= self.warmup_frames
+      || self.statistics.total_frames() >= self.frames as u64
+    {
+      return Ok(ComponentResult::Success);
+    }
+
+    self.statistics.record(*last_frame);
+    if self.statistics.total_frames() == self.frames as u64 {
+      self.finish()?;
+    }
+    return Ok(ComponentResult::Success);
+  }
+
+  fn on_render(
+    &mut self,
+    _render_context: &mut RenderContext,
+  ) -> Vec<RenderCommand> {
+    return match self.strategy {
+      Strategy::Render2D => self.render_rectangles(),
+      _ => self.render_sprites(),
+    };
+  }
+}
+
+impl Default for RenderBench {
+  fn default() -> Self {
+    return Self {
+      strategy: Strategy::Static,
+      sprites: 10_000,
+      batch_size: 0,
+      warmup_frames: 120,
+      frames: 600,
+      output: String::new(),
+      handle: RuntimeHandle::new(),
+      rendering_api: String::new(),
+      gpu: String::new(),
+      render2d: Render2D::new(),
+      render_pass: None,
+      render_pipeline: None,
+      width: 800,
+      height: 600,
+      time: Duration::ZERO,
+      frames_updated: 0,
+      statistics: FrameStatistics::new(1),
+    };
+  }
+}
+
+fn main() {
+  let (args, standard_args) = match parse_arguments() {
+    Ok(args) => args,
+    Err(error) => {
+      logging::error!("{}", error);
+      std::process::exit(1);
+    }
+  };
+  let strategy = match Strategy::parse(&args.strategy) {
+    Ok(strategy) => strategy,
+    Err(error) => {
+      logging::error!("{}", error);
+      std::process::exit(1);
+    }
+  };
+  let sprites = args.sprites.max(1) as usize;
+  let frames = args.frames.max(1) as usize;
+
+  let runtime = ApplicationRuntimeBuilder::new("render-bench")
+    .with_window_configured_as(|window_builder| {
+      return window_builder
+        .with_dimensions(800, 600)
+        .with_name("render-bench");
+    })
+    .with_renderer_configured_as(|render_context_builder| {
+      // Every sprite is staged each frame by the staged strategy.
+      let sprite_bytes =
+        sprites * VERTICES_PER_SPRITE * std::mem::size_of::<Vertex>();
+      return render_context_builder
+        .with_present_mode(PresentModePreference::AutoNoVsync)
+        .with_staging_buffer_size((2 * sprite_bytes).max(8 * 1024 * 1024));
+    })
+    .with_standard_args(standard_args)
+    .with_component(move |runtime, mut bench: RenderBench| {
+      bench.strategy = strategy;
+      bench.sprites = sprites;
+      bench.batch_size = args.batch_size.max(0) as usize;
+      bench.warmup_frames = args.warmup_frames.max(0) as u64;
+      bench.frames = frames;
+      bench.output = args.output;
+      bench.handle = runtime.handle();
+      bench.statistics = FrameStatistics::new(frames);
+      bench.render2d = Render2D::new()
+        .with_clear_color(Color::BLACK)
+        .with_vertex_capacity(sprites * VERTICES_PER_SPRITE);
+      return (runtime, bench);
+    })
+    .build();
+
+  std::process::exit(start_runtime(runtime));
+}